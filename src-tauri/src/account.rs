@@ -1,28 +1,164 @@
 use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::crypto::SecretKey;
+use crate::database::dao::accounts;
+use crate::database::dao::providers::{ProviderRecord, TokenQuota};
+use crate::database::Database;
+use crate::utils;
 
 /// Persistent account state managed by Tauri
 pub struct AccountState {
     pub inner: Mutex<AccountStateInner>,
+    /// Where sessions are saved across restarts; `None` keeps them in memory only.
+    store: Option<SessionStore>,
+}
+
+struct SessionStore {
+    db: Arc<Database>,
+    key: SecretKey,
+}
+
+/// One logged-in platform account.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub base_url: String,
+    pub session_cookie: String,
+    pub user_id: i64,
+    pub username: String,
 }
 
 pub struct AccountStateInner {
-    pub session_cookie: Option<String>,
-    pub user_id: Option<i64>,
-    pub username: Option<String>,
-    pub base_url: Option<String>,
+    /// Logged-in accounts by id (the `accounts` row id when persisted)
+    pub sessions: BTreeMap<i64, Session>,
+    /// Account used when a command doesn't name one
+    pub active: Option<i64>,
 }
 
 impl AccountState {
     pub fn new() -> Self {
         Self {
             inner: Mutex::new(AccountStateInner {
-                session_cookie: None,
-                user_id: None,
-                username: None,
-                base_url: None,
+                sessions: BTreeMap::new(),
+                active: None,
             }),
+            store: None,
+        }
+    }
+
+    /// State backed by the `accounts` table, which is the source of truth for
+    /// sessions. Starts from the saved accounts; the UI validates the active
+    /// one with `account_check_session` when it first needs it.
+    pub fn persistent(db: Arc<Database>, key: SecretKey) -> Self {
+        let mut state = Self::new();
+        match accounts::load_all(&db, &key) {
+            Ok(saved) => {
+                let inner = state.inner.get_mut().unwrap_or_else(|p| p.into_inner());
+                for account in saved {
+                    if account.is_active {
+                        inner.active = Some(account.id);
+                    }
+                    inner.sessions.insert(
+                        account.id,
+                        Session {
+                            base_url: account.base_url,
+                            session_cookie: account.session_cookie,
+                            user_id: account.user_id,
+                            username: account.username,
+                        },
+                    );
+                }
+                if !inner.sessions.is_empty() {
+                    tracing::info!(
+                        "[account] Restored {} saved account(s)",
+                        inner.sessions.len()
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("[account] Failed to load saved accounts: {}", e),
+        }
+        state.store = Some(SessionStore { db, key });
+        state
+    }
+
+    /// Add or refresh the session for its (base_url, username) and make it
+    /// the active account. Returns the account id.
+    fn insert(&self, session: Session) -> Result<i64, String> {
+        let saved_id = self.store.as_ref().and_then(|store| {
+            accounts::upsert(
+                &store.db,
+                &store.key,
+                &session.base_url,
+                &session.username,
+                session.user_id,
+                &session.session_cookie,
+            )
+            .map_err(|e| tracing::warn!("[account] Failed to save session: {}", e))
+            .ok()
+        });
+        let mut inner = lock_account(self)?;
+        let id = saved_id
+            .or_else(|| {
+                inner
+                    .sessions
+                    .iter()
+                    .find(|(_, s)| s.base_url == session.base_url && s.username == session.username)
+                    .map(|(id, _)| *id)
+            })
+            .unwrap_or_else(|| inner.sessions.keys().next_back().map_or(1, |id| id + 1));
+        inner.sessions.insert(id, session);
+        inner.active = Some(id);
+        Ok(id)
+    }
+
+    /// Account `id`, or the active account when `None`.
+    fn session(&self, id: Option<i64>) -> Result<(i64, Session), String> {
+        let inner = lock_account(self)?;
+        let id = id.or(inner.active).ok_or("NOT_LOGGED_IN")?;
+        let session = inner.sessions.get(&id).cloned().ok_or("NOT_LOGGED_IN")?;
+        Ok((id, session))
+    }
+
+    /// Make `id` the active account.
+    fn set_active(&self, id: i64) -> Result<(), String> {
+        let mut inner = lock_account(self)?;
+        if !inner.sessions.contains_key(&id) {
+            return Err("ACCOUNT_NOT_FOUND".to_string());
+        }
+        inner.active = Some(id);
+        drop(inner);
+        if let Some(store) = &self.store {
+            accounts::set_active(&store.db, id)?;
+        }
+        Ok(())
+    }
+
+    /// Drop account `id` from memory and from the database. If it was active,
+    /// the most recently added remaining account takes over.
+    fn forget(&self, id: i64) {
+        let next = match lock_account(self) {
+            Ok(mut inner) => {
+                inner.sessions.remove(&id);
+                if inner.active == Some(id) {
+                    inner.active = inner.sessions.keys().next_back().copied();
+                    inner.active
+                } else {
+                    None
+                }
+            }
+            Err(_) => None,
+        };
+        if let Some(store) = &self.store {
+            if let Err(e) = accounts::delete(&store.db, id) {
+                tracing::warn!("[account] Failed to clear saved session: {}", e);
+            }
+            if let Some(next) = next {
+                if let Err(e) = accounts::set_active(&store.db, next) {
+                    tracing::warn!("[account] Failed to activate account #{}: {}", next, e);
+                }
+            }
         }
     }
 }
@@ -53,7 +189,6 @@ struct PageData<T> {
     page: Option<i64>,
     #[allow(dead_code)]
     page_size: Option<i64>,
-    #[allow(dead_code)]
     total: Option<i64>,
     items: Option<Vec<T>>,
 }
@@ -72,6 +207,32 @@ struct RawToken {
     model_limits: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct RawUserSelf {
+    quota: Option<i64>,
+    used_quota: Option<i64>,
+    request_count: Option<i64>,
+    group: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLog {
+    created_at: Option<i64>,
+    model_name: Option<String>,
+    token_name: Option<String>,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+    quota: Option<i64>,
+}
+
+/// `/api/log/self` is paginated on current new-api, a bare list on older builds
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LogData {
+    Page(PageData<RawLog>),
+    List(Vec<RawLog>),
+}
+
 // ── Types returned to frontend ──
 
 #[derive(Debug, Serialize, Clone)]
@@ -79,10 +240,14 @@ pub struct PlatformInfo {
     pub system_name: String,
     pub version: String,
     pub register_enabled: bool,
+    /// Registration needs an emailed verification code
+    pub email_verification: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
 pub struct AccountInfo {
+    /// Local id of the saved account (see `account_list`)
+    pub account_id: i64,
     pub user_id: i64,
     pub username: String,
     pub display_name: String,
@@ -90,6 +255,15 @@ pub struct AccountInfo {
     pub session_cookie: Option<String>,
 }
 
+/// A saved account as listed for the account switcher
+#[derive(Debug, Serialize, Clone)]
+pub struct AccountSummary {
+    pub id: i64,
+    pub base_url: String,
+    pub username: String,
+    pub active: bool,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct ApiTokenInfo {
     pub id: i64,
@@ -104,19 +278,163 @@ pub struct ApiTokenInfo {
     pub model_limits: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct UserQuota {
+    /// Remaining balance, in new-api quota units
+    pub quota: i64,
+    pub used_quota: i64,
+    pub request_count: i64,
+    pub group: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UsageLogEntry {
+    pub model: String,
+    pub token_name: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub quota: i64,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UsageLogPage {
+    pub items: Vec<UsageLogEntry>,
+    pub total: i64,
+}
+
+impl From<RawLog> for UsageLogEntry {
+    fn from(l: RawLog) -> Self {
+        Self {
+            model: l.model_name.unwrap_or_default(),
+            token_name: l.token_name.unwrap_or_default(),
+            prompt_tokens: l.prompt_tokens.unwrap_or(0),
+            completion_tokens: l.completion_tokens.unwrap_or(0),
+            quota: l.quota.unwrap_or(0),
+            created_at: l.created_at.unwrap_or(0),
+        }
+    }
+}
+
+impl From<RawToken> for ApiTokenInfo {
+    fn from(t: RawToken) -> Self {
+        let model_limits = t
+            .model_limits
+            .as_deref()
+            .map(parse_model_limits)
+            .unwrap_or_default();
+
+        let key_raw = t.key.unwrap_or_default();
+        // new-api stores key without "sk-" prefix, but returns it; ensure consistency
+        let key = if key_raw.starts_with("sk-") {
+            key_raw
+        } else {
+            format!("sk-{key_raw}")
+        };
+
+        ApiTokenInfo {
+            id: t.id.unwrap_or(0),
+            name: t.name.unwrap_or_default(),
+            key,
+            status: t.status.unwrap_or(0),
+            used_quota: t.used_quota.unwrap_or(0),
+            remain_quota: t.remain_quota.unwrap_or(0),
+            unlimited_quota: t.unlimited_quota.unwrap_or(false),
+            // new-api uses -1 for "never expires", but some versions return 0.
+            // Treat both as never-expires to avoid false "expired" display.
+            expired_time: match t.expired_time.unwrap_or(-1) {
+                0 | -1 => -1,
+                ts => ts,
+            },
+            model_limits_enabled: t.model_limits_enabled.unwrap_or(false),
+            model_limits,
+        }
+    }
+}
+
+/// new-api stores model limits comma-separated; older builds used a JSON array
+fn parse_model_limits(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_else(|_| {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+impl ApiTokenInfo {
+    /// Used/remaining quota as cached on a linked provider.
+    pub fn quota(&self) -> TokenQuota {
+        TokenQuota {
+            used_quota: self.used_quota,
+            remain_quota: self.remain_quota,
+            unlimited_quota: self.unlimited_quota,
+            fetched_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Provider pointing at the account's platform with this token's key.
+    /// A token limited to exactly one model makes that the default model.
+    pub fn into_record(
+        self,
+        account_id: i64,
+        base_url: &str,
+        name: Option<&str>,
+    ) -> ProviderRecord {
+        let name = name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .or_else(|| Some(self.name.trim().to_string()).filter(|n| !n.is_empty()))
+            .unwrap_or_else(|| format!("Token #{}", self.id));
+        let default_model = match self.model_limits.as_slice() {
+            [only] if self.model_limits_enabled => only.trim().to_string(),
+            _ => String::new(),
+        };
+        let quota = self.quota();
+        ProviderRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            url: normalize_base(base_url),
+            api_key: self.key,
+            default_model,
+            per_cli_models: "{}".to_string(),
+            is_current: false,
+            sort_index: None,
+            notes: Some(format!("Account token #{}", self.id)),
+            created_at: chrono::Utc::now().timestamp(),
+            opencode_adapter: String::new(),
+            account_id: Some(account_id),
+            model_aliases: "{}".to_string(),
+            account_base_url: Some(normalize_base(base_url)),
+            token_id: Some(self.id),
+            quota: Some(quota),
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+}
+
 // ── Helper ──
 
 fn build_client() -> Result<reqwest::Client, String> {
-    reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+    utils::api_client(std::time::Duration::from_secs(15))
 }
 
 fn normalize_base(base_url: &str) -> String {
     base_url.trim_end_matches('/').to_string()
 }
 
+fn connect_error(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        "CONNECT_TIMEOUT".to_string()
+    } else {
+        "CONNECT_FAILED".to_string()
+    }
+}
+
 /// Extract `session=xxx` from Set-Cookie headers
 fn extract_session_cookie(response: &reqwest::Response) -> Option<String> {
     for val in response.headers().get_all("set-cookie") {
@@ -170,13 +488,7 @@ pub async fn check_platform(base_url: String) -> Result<PlatformInfo, String> {
         .get(format!("{base}/api/status"))
         .send()
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "CONNECT_TIMEOUT".to_string()
-            } else {
-                "CONNECT_FAILED".to_string()
-            }
-        })?;
+        .map_err(connect_error)?;
 
     if !response.status().is_success() {
         return Err(format!("Server returned {}", response.status()));
@@ -206,20 +518,44 @@ pub async fn check_platform(base_url: String) -> Result<PlatformInfo, String> {
             // fallback: check old field name
             .or_else(|| data.get("register_enabled").and_then(|v| v.as_bool()))
             .unwrap_or(false),
+        email_verification: data
+            .get("email_verification")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     })
 }
 
-/// Login with username/password, store session in state
+/// Login with username/password, store session in state.
+/// Accounts with 2FA enabled fail with REQUIRE_2FA until `totp_code` is given.
 #[tauri::command]
 pub async fn account_login(
     base_url: String,
     username: String,
     password: String,
+    totp_code: Option<String>,
     state: tauri::State<'_, AccountState>,
 ) -> Result<AccountInfo, String> {
     let base = normalize_base(&base_url);
     let client = build_client()?;
+    login(
+        &client,
+        base,
+        &username,
+        &password,
+        totp_code.as_deref(),
+        &state,
+    )
+    .await
+}
 
+async fn login(
+    client: &reqwest::Client,
+    base: String,
+    username: &str,
+    password: &str,
+    totp_code: Option<&str>,
+    state: &AccountState,
+) -> Result<AccountInfo, String> {
     let response = client
         .post(format!("{base}/api/user/login"))
         .json(&serde_json::json!({
@@ -228,19 +564,13 @@ pub async fn account_login(
         }))
         .send()
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "CONNECT_TIMEOUT".to_string()
-            } else {
-                "CONNECT_FAILED".to_string()
-            }
-        })?;
+        .map_err(connect_error)?;
 
     // Extract session cookie before consuming response body
-    let session_cookie = extract_session_cookie(&response);
+    let mut session_cookie = extract_session_cookie(&response);
 
-    let status_code = response.status();
-    let body: serde_json::Value = response
+    let mut status_code = response.status();
+    let mut body: serde_json::Value = response
         .json()
         .await
         .map_err(|_| "INVALID_RESPONSE".to_string())?;
@@ -251,7 +581,37 @@ pub async fn account_login(
         .and_then(|d| d.get("require_2fa"))
         .and_then(|v| v.as_bool())
     {
-        return Err("REQUIRE_2FA".to_string());
+        let code = totp_code
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .ok_or("REQUIRE_2FA")?;
+        // The first response's cookie holds the pending login; the server
+        // replaces it with the real session once the code checks out
+        let pending = session_cookie.take().ok_or("NO_SESSION_COOKIE")?;
+        let response = client
+            .post(format!("{base}/api/user/login/2fa"))
+            .header(
+                COOKIE,
+                HeaderValue::from_str(&pending)
+                    .map_err(|e| format!("Invalid cookie value: {e}"))?,
+            )
+            .json(&serde_json::json!({ "code": code }))
+            .send()
+            .await
+            .map_err(connect_error)?;
+        session_cookie = extract_session_cookie(&response).or(Some(pending));
+        status_code = response.status();
+        body = response
+            .json()
+            .await
+            .map_err(|_| "INVALID_RESPONSE".to_string())?;
+        if !body
+            .get("success")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            return Err("WRONG_TOTP".to_string());
+        }
     }
 
     let success = body
@@ -300,16 +660,16 @@ pub async fn account_login(
 
     let session = session_cookie.ok_or("NO_SESSION_COOKIE")?;
 
-    // Store in state
-    {
-        let mut inner = lock_account(&state)?;
-        inner.session_cookie = Some(session.clone());
-        inner.user_id = Some(id);
-        inner.username = Some(uname.clone());
-        inner.base_url = Some(base);
-    }
+    // Store in state; a second login to the same account replaces its session
+    let account_id = state.insert(Session {
+        base_url: base,
+        session_cookie: session.clone(),
+        user_id: id,
+        username: uname.clone(),
+    })?;
 
     Ok(AccountInfo {
+        account_id,
         user_id: id,
         username: uname,
         display_name: display,
@@ -317,24 +677,107 @@ pub async fn account_login(
     })
 }
 
-/// Get all API tokens for the logged-in user
+/// Email a registration verification code (platforms with email verification on)
+#[tauri::command]
+pub async fn account_send_email_code(base_url: String, email: String) -> Result<(), String> {
+    let base = normalize_base(&base_url);
+    let client = build_client()?;
+
+    let response = client
+        .get(format!("{base}/api/verification"))
+        .query(&[("email", email.trim()), ("turnstile", "")])
+        .send()
+        .await
+        .map_err(connect_error)?;
+
+    let body: ApiResponse<serde_json::Value> = response
+        .json()
+        .await
+        .map_err(|_| "INVALID_RESPONSE".to_string())?;
+    if !body.success {
+        let msg = body.message.unwrap_or_default();
+        tracing::warn!("[account] Verification email rejected: {}", msg);
+        return Err(register_error_code(&msg).to_string());
+    }
+    Ok(())
+}
+
+/// Create an account, then log straight in so `AccountState` is populated
+#[tauri::command]
+pub async fn account_register(
+    base_url: String,
+    username: String,
+    password: String,
+    email: String,
+    verification_code: Option<String>,
+    state: tauri::State<'_, AccountState>,
+) -> Result<AccountInfo, String> {
+    let base = normalize_base(&base_url);
+    let client = build_client()?;
+
+    let response = client
+        .post(format!("{base}/api/user/register"))
+        .query(&[("turnstile", "")])
+        .json(&serde_json::json!({
+            "username": username.trim(),
+            "password": password,
+            "email": email.trim(),
+            "verification_code": verification_code.as_deref().map(str::trim).unwrap_or(""),
+        }))
+        .send()
+        .await
+        .map_err(connect_error)?;
+
+    let body: ApiResponse<serde_json::Value> = response
+        .json()
+        .await
+        .map_err(|_| "INVALID_RESPONSE".to_string())?;
+    if !body.success {
+        let msg = body.message.unwrap_or_default();
+        tracing::warn!("[account] Registration rejected: {}", msg);
+        return Err(register_error_code(&msg).to_string());
+    }
+
+    tracing::info!("[account] Registered {}, logging in", username.trim());
+    login(&client, base, username.trim(), &password, None, &state).await
+}
+
+/// Map new-api registration / verification messages (Chinese or English) to error codes
+fn register_error_code(msg: &str) -> &'static str {
+    let lower = msg.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+    if has(&["邮箱验证", "email verification"]) {
+        "EMAIL_CODE_REQUIRED"
+    } else if has(&["验证码", "verification code"]) {
+        "WRONG_EMAIL_CODE"
+    } else if has(&["邮箱地址已被占用", "email is already", "email already"]) {
+        "EMAIL_TAKEN"
+    } else if has(&["白名单", "whitelist", "not allowed"]) {
+        "EMAIL_NOT_ALLOWED"
+    } else if has(&["已存在", "已被占用", "already exists", "taken"]) {
+        "USERNAME_TAKEN"
+    } else if has(&["关闭", "disabled", "not enabled"]) {
+        "REGISTER_DISABLED"
+    } else if has(&["不合法", "invalid"]) {
+        "INVALID_INPUT"
+    } else {
+        "REGISTER_FAILED"
+    }
+}
+
+/// Get all API tokens of `account_id` (default: the active account)
 #[tauri::command]
 pub async fn account_get_tokens(
+    account_id: Option<i64>,
     state: tauri::State<'_, AccountState>,
 ) -> Result<Vec<ApiTokenInfo>, String> {
-    let (base, session, user_id) = {
-        let inner = lock_account(&state)?;
-        let base = inner.base_url.clone().ok_or("Not logged in")?;
-        let session = inner.session_cookie.clone().ok_or("Not logged in")?;
-        let user_id = inner.user_id.ok_or("Not logged in")?;
-        (base, session, user_id)
-    };
+    let (_, session) = state.session(account_id)?;
 
     let client = build_client()?;
-    let headers = auth_headers(&session, user_id)?;
+    let headers = auth_headers(&session.session_cookie, session.user_id)?;
 
     let response = client
-        .get(format!("{base}/api/token/?p=1&page_size=100"))
+        .get(format!("{}/api/token/?p=1&page_size=100", session.base_url))
         .headers(headers)
         .send()
         .await
@@ -366,64 +809,184 @@ pub async fn account_get_tokens(
     let page_data = body.data.ok_or("No data in response")?;
     let items = page_data.items.unwrap_or_default();
 
-    let tokens: Vec<ApiTokenInfo> = items
-        .into_iter()
-        .map(|t| {
-            let model_limits: Vec<String> = t
-                .model_limits
-                .as_deref()
-                .and_then(|s| serde_json::from_str(s).ok())
-                .unwrap_or_default();
-
-            let key_raw = t.key.unwrap_or_default();
-            // new-api stores key without "sk-" prefix, but returns it; ensure consistency
-            let key = if key_raw.starts_with("sk-") {
-                key_raw
-            } else {
-                format!("sk-{key_raw}")
-            };
+    Ok(items.into_iter().map(ApiTokenInfo::from).collect())
+}
 
-            ApiTokenInfo {
-                id: t.id.unwrap_or(0),
-                name: t.name.unwrap_or_default(),
-                key,
-                status: t.status.unwrap_or(0),
-                used_quota: t.used_quota.unwrap_or(0),
-                remain_quota: t.remain_quota.unwrap_or(0),
-                unlimited_quota: t.unlimited_quota.unwrap_or(false),
-                // new-api uses -1 for "never expires", but some versions return 0.
-                // Treat both as never-expires to avoid false "expired" display.
-                expired_time: match t.expired_time.unwrap_or(-1) {
-                    0 | -1 => -1,
-                    ts => ts,
-                },
-                model_limits_enabled: t.model_limits_enabled.unwrap_or(false),
-                model_limits,
-            }
+/// Look up one of the logged-in user's tokens by id
+pub async fn fetch_token(
+    state: &AccountState,
+    account_id: Option<i64>,
+    token_id: i64,
+) -> Result<ApiTokenInfo, String> {
+    let raw: Option<RawToken> = get_authed(state, account_id, &format!("/api/token/{token_id}"))
+        .await
+        .map_err(|e| match e.as_str() {
+            "SESSION_EXPIRED" | "NOT_LOGGED_IN" | "CONNECT_TIMEOUT" | "CONNECT_FAILED" => e,
+            _ => "TOKEN_NOT_FOUND".to_string(),
+        })?;
+    raw.map(ApiTokenInfo::from)
+        .ok_or_else(|| "TOKEN_NOT_FOUND".to_string())
+}
+
+/// Account balance from GET /api/user/self (default: the active account)
+#[tauri::command]
+pub async fn account_get_self_quota(
+    account_id: Option<i64>,
+    state: tauri::State<'_, AccountState>,
+) -> Result<UserQuota, String> {
+    let data: Option<RawUserSelf> = get_authed(&state, account_id, "/api/user/self").await?;
+    let data = data.ok_or("INVALID_RESPONSE")?;
+    Ok(UserQuota {
+        quota: data.quota.unwrap_or(0),
+        used_quota: data.used_quota.unwrap_or(0),
+        request_count: data.request_count.unwrap_or(0),
+        group: data.group.unwrap_or_default(),
+    })
+}
+
+/// One page of the user's own request log, newest first
+#[tauri::command]
+pub async fn account_get_usage_logs(
+    page: u32,
+    page_size: u32,
+    account_id: Option<i64>,
+    state: tauri::State<'_, AccountState>,
+) -> Result<UsageLogPage, String> {
+    let page = page.max(1);
+    let page_size = page_size.clamp(1, 100);
+    let data: Option<LogData> = get_authed(
+        &state,
+        account_id,
+        &format!("/api/log/self?p={page}&page_size={page_size}"),
+    )
+    .await?;
+    let (raw, total) = match data {
+        Some(LogData::Page(p)) => {
+            let items = p.items.unwrap_or_default();
+            let total = p.total.unwrap_or(items.len() as i64);
+            (items, total)
+        }
+        Some(LogData::List(items)) => {
+            let total = items.len() as i64;
+            (items, total)
+        }
+        None => (Vec::new(), 0),
+    };
+    Ok(UsageLogPage {
+        items: raw.into_iter().map(UsageLogEntry::from).collect(),
+        total,
+    })
+}
+
+/// GET `path` with `account_id`'s session (default: active) and unwrap new-api's `{success, data}`
+/// envelope. 401/403 become SESSION_EXPIRED.
+async fn get_authed<T: serde::de::DeserializeOwned>(
+    state: &AccountState,
+    account_id: Option<i64>,
+    path: &str,
+) -> Result<Option<T>, String> {
+    let (_, session) = state.session(account_id)?;
+
+    let client = build_client()?;
+    let response = client
+        .get(format!("{}{path}", session.base_url))
+        .headers(auth_headers(&session.session_cookie, session.user_id)?)
+        .send()
+        .await
+        .map_err(connect_error)?;
+
+    let status_code = response.status();
+    if status_code.as_u16() == 401 || status_code.as_u16() == 403 {
+        return Err("SESSION_EXPIRED".to_string());
+    }
+    if !status_code.is_success() {
+        return Err(format!("Server returned {status_code}"));
+    }
+
+    let body: ApiResponse<T> = response
+        .json()
+        .await
+        .map_err(|_| "INVALID_RESPONSE".to_string())?;
+    if !body.success {
+        return Err(body
+            .message
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| "REQUEST_FAILED".to_string()));
+    }
+    Ok(body.data)
+}
+
+/// Id and base URL of `account_id`, or of the active account
+pub fn session_base_url(
+    state: &AccountState,
+    account_id: Option<i64>,
+) -> Result<(i64, String), String> {
+    state
+        .session(account_id)
+        .map(|(id, session)| (id, session.base_url))
+}
+
+/// Logged-in account that can see a token on `base_url`: `account_id` when
+/// it is still on that platform, else any session there.
+pub fn account_for_base(
+    state: &AccountState,
+    account_id: Option<i64>,
+    base_url: &str,
+) -> Result<i64, String> {
+    let base = normalize_base(base_url);
+    let inner = lock_account(state)?;
+    account_id
+        .filter(|id| inner.sessions.get(id).is_some_and(|s| s.base_url == base))
+        .or_else(|| {
+            inner
+                .sessions
+                .iter()
+                .find(|(_, s)| s.base_url == base)
+                .map(|(id, _)| *id)
         })
-        .collect();
+        .ok_or_else(|| "NOT_LOGGED_IN".to_string())
+}
 
-    Ok(tokens)
+/// Saved accounts, oldest first
+#[tauri::command]
+pub async fn account_list(
+    state: tauri::State<'_, AccountState>,
+) -> Result<Vec<AccountSummary>, String> {
+    let inner = lock_account(&state)?;
+    Ok(inner
+        .sessions
+        .iter()
+        .map(|(id, session)| AccountSummary {
+            id: *id,
+            base_url: session.base_url.clone(),
+            username: session.username.clone(),
+            active: inner.active == Some(*id),
+        })
+        .collect())
 }
 
-/// Check if session is still valid by calling GET /api/user/self
+/// Make `id` the account commands use by default
+#[tauri::command]
+pub async fn account_switch_active(
+    id: i64,
+    state: tauri::State<'_, AccountState>,
+) -> Result<(), String> {
+    state.set_active(id)
+}
+
+/// Check if `account_id`'s session (default: active) is still valid via GET /api/user/self
 #[tauri::command]
 pub async fn account_check_session(
+    account_id: Option<i64>,
     state: tauri::State<'_, AccountState>,
 ) -> Result<AccountInfo, String> {
-    let (base, session, user_id) = {
-        let inner = lock_account(&state)?;
-        let base = inner.base_url.clone().ok_or("NOT_LOGGED_IN")?;
-        let session = inner.session_cookie.clone().ok_or("NOT_LOGGED_IN")?;
-        let user_id = inner.user_id.ok_or("NOT_LOGGED_IN")?;
-        (base, session, user_id)
-    };
+    let (account_id, session) = state.session(account_id)?;
 
     let client = build_client()?;
-    let headers = auth_headers(&session, user_id)?;
+    let headers = auth_headers(&session.session_cookie, session.user_id)?;
 
     let response = client
-        .get(format!("{base}/api/user/self"))
+        .get(format!("{}/api/user/self", session.base_url))
         .headers(headers)
         .send()
         .await
@@ -432,13 +995,8 @@ pub async fn account_check_session(
     let status_code = response.status();
 
     if status_code.as_u16() == 401 || status_code.as_u16() == 403 {
-        // Clear entire expired session
-        if let Ok(mut inner) = lock_account(&state) {
-            inner.session_cookie = None;
-            inner.user_id = None;
-            inner.username = None;
-            inner.base_url = None;
-        }
+        // Clear the expired session
+        state.forget(account_id);
         return Err("SESSION_EXPIRED".to_string());
     }
 
@@ -458,6 +1016,7 @@ pub async fn account_check_session(
     let data = body.data.ok_or("SESSION_EXPIRED")?;
 
     Ok(AccountInfo {
+        account_id,
         user_id: data.id,
         username: data.username.clone(),
         display_name: data.display_name.unwrap_or(data.username),
@@ -465,7 +1024,9 @@ pub async fn account_check_session(
     })
 }
 
-/// Restore session from frontend-persisted data (called on app startup)
+/// Restore session from frontend-persisted data (called on app startup).
+/// Kept for older frontends: saved accounts in the database are the source
+/// of truth, so this only applies when nothing was restored from them.
 #[tauri::command]
 pub async fn account_restore_session(
     base_url: String,
@@ -474,47 +1035,355 @@ pub async fn account_restore_session(
     username: String,
     state: tauri::State<'_, AccountState>,
 ) -> Result<(), String> {
-    {
-        let mut inner = lock_account(&state)?;
-        inner.base_url = Some(normalize_base(&base_url));
-        inner.session_cookie = Some(session_cookie);
-        inner.user_id = Some(user_id);
-        inner.username = Some(username);
+    if !lock_account(&state)?.sessions.is_empty() {
+        return Ok(());
     }
+    state.insert(Session {
+        base_url: normalize_base(&base_url),
+        session_cookie,
+        user_id,
+        username,
+    })?;
     Ok(())
 }
 
-/// Logout — clear state
+/// Log `account_id` (default: the active account) out and forget it
 #[tauri::command]
-pub async fn account_logout(state: tauri::State<'_, AccountState>) -> Result<(), String> {
-    // Optionally call server logout
-    let (base, session, user_id) = {
-        let inner = lock_account(&state)?;
-        (
-            inner.base_url.clone(),
-            inner.session_cookie.clone(),
-            inner.user_id,
-        )
+pub async fn account_logout(
+    account_id: Option<i64>,
+    state: tauri::State<'_, AccountState>,
+) -> Result<(), String> {
+    let Ok((account_id, session)) = state.session(account_id) else {
+        return Ok(());
     };
 
-    if let (Some(base), Some(session), Some(uid)) = (base, session, user_id) {
-        let client = build_client()?;
-        if let Ok(headers) = auth_headers(&session, uid) {
-            // Fire and forget — don't fail if server logout fails
-            let _ = client
-                .get(format!("{base}/api/user/logout"))
-                .headers(headers)
-                .send()
+    // Optionally call server logout
+    let client = build_client()?;
+    if let Ok(headers) = auth_headers(&session.session_cookie, session.user_id) {
+        // Fire and forget — don't fail if server logout fails
+        let _ = client
+            .get(format!("{}/api/user/logout", session.base_url))
+            .headers(headers)
+            .send()
+            .await;
+    }
+
+    // Clear local and saved state
+    state.forget(account_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_error_codes() {
+        let cases = [
+            (
+                "管理员开启了邮箱验证，请输入邮箱地址和验证码",
+                "EMAIL_CODE_REQUIRED",
+            ),
+            ("验证码错误或已过期", "WRONG_EMAIL_CODE"),
+            ("用户名已存在，或已注销", "USERNAME_TAKEN"),
+            ("Username already exists", "USERNAME_TAKEN"),
+            ("邮箱地址已被占用", "EMAIL_TAKEN"),
+            ("管理员关闭了通过密码进行注册", "REGISTER_DISABLED"),
+            ("输入不合法 Key: 'User.Password'", "INVALID_INPUT"),
+            ("something else", "REGISTER_FAILED"),
+        ];
+        for (msg, code) in cases {
+            assert_eq!(register_error_code(msg), code, "{msg}");
+        }
+    }
+
+    #[test]
+    fn test_token_into_record() {
+        let raw: RawToken = serde_json::from_value(serde_json::json!({
+            "id": 12,
+            "name": "",
+            "key": "abc",
+            "model_limits_enabled": true,
+            "model_limits": "claude-sonnet-4-5",
+        }))
+        .unwrap();
+        let record = ApiTokenInfo::from(raw).into_record(3, "https://api.example.com/", None);
+        assert_eq!(record.name, "Token #12");
+        assert_eq!(record.url, "https://api.example.com");
+        assert_eq!(record.api_key, "sk-abc");
+        assert_eq!(record.default_model, "claude-sonnet-4-5");
+        assert_eq!(record.account_id, Some(3));
+        assert_eq!(
+            record.account_base_url.as_deref(),
+            Some("https://api.example.com")
+        );
+        assert_eq!(record.token_id, Some(12));
+
+        assert_eq!(parse_model_limits(r#"["a","b"]"#), ["a", "b"]);
+        assert_eq!(parse_model_limits("a, b,"), ["a", "b"]);
+        let token = ApiTokenInfo::from(RawToken {
+            model_limits: Some("a,b".into()),
+            model_limits_enabled: Some(true),
+            ..serde_json::from_value(serde_json::json!({})).unwrap()
+        });
+        let record = token.into_record(3, "https://api.example.com", Some(" Work "));
+        assert_eq!(record.name, "Work");
+        assert_eq!(record.default_model, "");
+    }
+
+    fn session(base_url: &str, username: &str, cookie: &str) -> Session {
+        Session {
+            base_url: base_url.into(),
+            session_cookie: cookie.into(),
+            user_id: 7,
+            username: username.into(),
+        }
+    }
+
+    #[test]
+    fn test_accounts_survive_restart_and_switch() {
+        let db = Arc::new(Database::memory().unwrap());
+        let tmp = tempfile::tempdir().unwrap();
+        let key = || SecretKey::load_or_create(&tmp.path().join("secret.key")).unwrap();
+
+        let state = AccountState::persistent(db.clone(), key());
+        let work = state
+            .insert(session("https://work.example.com", "alice", "session=a"))
+            .unwrap();
+        let home = state
+            .insert(session("https://home.example.com", "alice", "session=b"))
+            .unwrap();
+        assert_eq!(state.session(None).unwrap().0, home);
+        state.set_active(work).unwrap();
+        assert_eq!(
+            state.set_active(99).unwrap_err(),
+            "ACCOUNT_NOT_FOUND".to_string()
+        );
+
+        let restarted = AccountState::persistent(db.clone(), key());
+        let (id, active) = restarted.session(None).unwrap();
+        assert_eq!(id, work);
+        assert_eq!(active.session_cookie, "session=a");
+        assert_eq!(
+            restarted.session(Some(home)).unwrap().1.base_url,
+            "https://home.example.com"
+        );
+
+        // Logging out the active account hands over to the remaining one
+        restarted.forget(work);
+        let after_logout = AccountState::persistent(db, key());
+        assert_eq!(after_logout.session(None).unwrap().0, home);
+        assert_eq!(
+            after_logout.session(Some(work)).unwrap_err(),
+            "NOT_LOGGED_IN"
+        );
+    }
+
+    #[test]
+    fn test_in_memory_ids_are_per_account() {
+        let state = AccountState::new();
+        let a = state
+            .insert(session("https://a.example.com", "x", "session=1"))
+            .unwrap();
+        let b = state
+            .insert(session("https://b.example.com", "x", "session=2"))
+            .unwrap();
+        let again = state
+            .insert(session("https://a.example.com", "x", "session=3"))
+            .unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a, again);
+        assert_eq!(
+            state.session(Some(a)).unwrap().1.session_cookie,
+            "session=3"
+        );
+
+        // A provider's stored account falls back to any session on its platform
+        assert_eq!(
+            account_for_base(&state, Some(a), "https://a.example.com/"),
+            Ok(a)
+        );
+        assert_eq!(
+            account_for_base(&state, Some(b), "https://a.example.com"),
+            Ok(a)
+        );
+        assert!(account_for_base(&state, None, "https://c.example.com").is_err());
+    }
+
+    mod two_factor {
+        use super::*;
+        use wiremock::matchers::{body_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const USER: &str = r#"{"success":true,"message":"","data":{"id":7,"username":"alice","display_name":"Alice","status":1}}"#;
+
+        async fn server() -> MockServer {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/api/user/login"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("set-cookie", "session=pending; Path=/; HttpOnly")
+                        .set_body_raw(
+                            r#"{"success":true,"message":"请输入两步验证码","data":{"require_2fa":true}}"#,
+                            "application/json",
+                        ),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/api/user/login/2fa"))
+                .and(header("cookie", "session=pending"))
+                .and(body_json(serde_json::json!({ "code": "123456" })))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("set-cookie", "session=full; Path=/; HttpOnly")
+                        .set_body_raw(USER, "application/json"),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/api/user/login/2fa"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    r#"{"success":false,"message":"验证码或备用码错误，请重试"}"#,
+                    "application/json",
+                ))
+                .mount(&server)
                 .await;
+            server
+        }
+
+        async fn try_login(server: &MockServer, code: Option<&str>) -> Result<AccountInfo, String> {
+            let client = build_client().unwrap();
+            let state = AccountState::new();
+            login(&client, server.uri(), "alice", "secret", code, &state).await
+        }
+
+        #[tokio::test]
+        async fn test_login_without_code_requires_2fa() {
+            let server = server().await;
+            assert_eq!(try_login(&server, None).await.unwrap_err(), "REQUIRE_2FA");
+            assert_eq!(
+                try_login(&server, Some("  ")).await.unwrap_err(),
+                "REQUIRE_2FA"
+            );
+        }
+
+        #[tokio::test]
+        async fn test_login_with_code_keeps_final_session() {
+            let server = server().await;
+            let client = build_client().unwrap();
+            let state = AccountState::new();
+            let info = login(
+                &client,
+                server.uri(),
+                "alice",
+                "secret",
+                Some(" 123456 "),
+                &state,
+            )
+            .await
+            .unwrap();
+            assert_eq!(info.user_id, 7);
+            assert_eq!(info.display_name, "Alice");
+            assert_eq!(info.session_cookie.as_deref(), Some("session=full"));
+            let (id, stored) = state.session(None).unwrap();
+            assert_eq!(id, info.account_id);
+            assert_eq!(stored.session_cookie, "session=full");
+            assert_eq!(stored.user_id, 7);
+        }
+
+        #[tokio::test]
+        async fn test_wrong_code_is_not_wrong_credentials() {
+            let server = server().await;
+            assert_eq!(
+                try_login(&server, Some("000000")).await.unwrap_err(),
+                "WRONG_TOTP"
+            );
         }
     }
 
-    // Clear local state
-    let mut inner = lock_account(&state)?;
-    inner.session_cookie = None;
-    inner.user_id = None;
-    inner.username = None;
-    inner.base_url = None;
+    mod usage {
+        use super::*;
+        use wiremock::matchers::{header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn logged_in(server: &MockServer) -> AccountState {
+            let state = AccountState::new();
+            state
+                .insert(Session {
+                    base_url: server.uri(),
+                    session_cookie: "session=abc".into(),
+                    user_id: 7,
+                    username: "alice".into(),
+                })
+                .unwrap();
+            state
+        }
 
-    Ok(())
+        #[tokio::test]
+        async fn test_self_quota_and_logs() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/user/self"))
+                .and(header("cookie", "session=abc"))
+                .and(header("new-api-user", "7"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "id": 7, "quota": 500000, "used_quota": 1200, "request_count": 3, "group": "vip" }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/log/self"))
+                .and(query_param("p", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "items": [{
+                        "created_at": 1700000000, "model_name": "gpt-4o", "token_name": "cli",
+                        "prompt_tokens": 10, "completion_tokens": 5, "quota": 42, "content": "ignored"
+                    }], "total": 21, "page": 2, "page_size": 20 }
+                })))
+                .mount(&server)
+                .await;
+            let state = logged_in(&server);
+
+            let data: Option<RawUserSelf> =
+                get_authed(&state, None, "/api/user/self").await.unwrap();
+            let data = data.unwrap();
+            assert_eq!(data.quota, Some(500000));
+            assert_eq!(data.group.as_deref(), Some("vip"));
+
+            let logs: Option<LogData> = get_authed(&state, None, "/api/log/self?p=2&page_size=20")
+                .await
+                .unwrap();
+            let Some(LogData::Page(page)) = logs else {
+                panic!("expected a paginated log response");
+            };
+            assert_eq!(page.total, Some(21));
+            let entry = UsageLogEntry::from(page.items.unwrap().remove(0));
+            assert_eq!(entry.model, "gpt-4o");
+            assert_eq!(entry.prompt_tokens + entry.completion_tokens, 15);
+            assert_eq!(entry.quota, 42);
+        }
+
+        #[tokio::test]
+        async fn test_unauthorized_maps_to_session_expired() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&server)
+                .await;
+            let state = logged_in(&server);
+            let err = get_authed::<RawUserSelf>(&state, None, "/api/user/self")
+                .await
+                .unwrap_err();
+            assert_eq!(err, "SESSION_EXPIRED");
+            let err = get_authed::<RawUserSelf>(&AccountState::new(), None, "/api/user/self")
+                .await
+                .unwrap_err();
+            assert_eq!(err, "NOT_LOGGED_IN");
+        }
+    }
 }
@@ -0,0 +1,264 @@
+//! Tells the user when a newer release of this app is out.
+//!
+//! Only the tag of the latest GitHub release is fetched; installing is left
+//! to the user. The answer is cached in the settings table for a day, and the
+//! check can be turned off entirely for offline or locked-down machines.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::database::{dao::settings, Database};
+use crate::error::CommandError;
+use crate::store::AppState;
+use crate::utils;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/zhaozhongke/hajimi-cli-sync/releases/latest";
+const CACHE_KEY: &str = "app_update_cache";
+const ENABLED_KEY: &str = "app_update_check_enabled";
+const CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AppUpdate {
+    pub current: String,
+    /// `None` when the check is turned off.
+    pub latest: Option<String>,
+    pub update_available: bool,
+    pub release_notes_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct LatestRelease {
+    version: String,
+    url: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRelease {
+    fetched_at: i64, // Unix seconds
+    release: LatestRelease,
+}
+
+pub fn check_enabled(db: &Database) -> bool {
+    settings::get_bool(db, ENABLED_KEY, true)
+}
+
+pub fn set_check_enabled(db: &Database, enabled: bool) -> Result<(), String> {
+    settings::set_bool(db, ENABLED_KEY, enabled)
+}
+
+/// "v1.4.0" → "1.4.0"
+fn normalize_version(tag: &str) -> String {
+    let tag = tag.trim();
+    tag.strip_prefix(['v', 'V']).unwrap_or(tag).to_string()
+}
+
+fn parse_release(body: &Value) -> Result<LatestRelease, String> {
+    let tag = body
+        .get("tag_name")
+        .and_then(Value::as_str)
+        .filter(|t| !t.trim().is_empty())
+        .ok_or("Release has no tag")?;
+    Ok(LatestRelease {
+        version: normalize_version(tag),
+        url: body
+            .get("html_url")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+async fn fetch_latest_release(url: &str) -> Result<LatestRelease, String> {
+    let client = utils::http_client(std::time::Duration::from_secs(10))?;
+
+    // GitHub rejects requests without a User-Agent
+    let response = client
+        .get(url)
+        .header("Accept", "application/vnd.github+json")
+        .header(
+            "User-Agent",
+            concat!("hajimi-cli-sync/", env!("CARGO_PKG_VERSION")),
+        )
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "Request timed out (10s)".to_string()
+            } else if e.is_connect() {
+                format!("Connection failed: {e}")
+            } else {
+                format!("Request failed: {e}")
+            }
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let summary = body.chars().take(200).collect::<String>();
+        return Err(format!("API returned {status}: {summary}"));
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    parse_release(&body)
+}
+
+fn read_cache(db: &Database, now: i64) -> Option<LatestRelease> {
+    let raw = settings::get(db, CACHE_KEY).ok().flatten()?;
+    let entry: CachedRelease = serde_json::from_str(&raw).ok()?;
+    // A clock that went backwards counts as stale
+    (0..CACHE_TTL_SECS)
+        .contains(&(now - entry.fetched_at))
+        .then_some(entry.release)
+}
+
+fn write_cache(db: &Database, release: &LatestRelease, now: i64) -> Result<(), String> {
+    let entry = CachedRelease {
+        fetched_at: now,
+        release: release.clone(),
+    };
+    let raw = serde_json::to_string(&entry).map_err(|e| format!("serialize update cache: {e}"))?;
+    settings::set(db, CACHE_KEY, &raw)
+}
+
+fn compare(current: &str, release: Option<LatestRelease>) -> AppUpdate {
+    let update_available = release.as_ref().is_some_and(|r| {
+        utils::compare_versions(&r.version, current) == std::cmp::Ordering::Greater
+    });
+    AppUpdate {
+        current: current.to_string(),
+        update_available,
+        release_notes_url: release
+            .as_ref()
+            .map(|r| r.url.clone())
+            .filter(|u| !u.is_empty()),
+        latest: release.map(|r| r.version),
+    }
+}
+
+async fn check(db: &Database, url: &str, current: &str) -> Result<AppUpdate, String> {
+    if !check_enabled(db) {
+        return Ok(compare(current, None));
+    }
+    let now = chrono::Utc::now().timestamp();
+    if let Some(release) = read_cache(db, now) {
+        return Ok(compare(current, Some(release)));
+    }
+    let release = fetch_latest_release(url).await?;
+    if let Err(e) = write_cache(db, &release, now) {
+        tracing::warn!("[app_update] Failed to cache the latest release: {e}");
+    }
+    Ok(compare(current, Some(release)))
+}
+
+/// Tauri command: compare this build with the latest GitHub release.
+#[tauri::command]
+pub async fn check_app_update(state: State<'_, AppState>) -> Result<AppUpdate, CommandError> {
+    Ok(check(&state.db, LATEST_RELEASE_URL, env!("CARGO_PKG_VERSION")).await?)
+}
+
+#[tauri::command]
+pub async fn get_update_check_enabled(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(check_enabled(&state.db))
+}
+
+#[tauri::command]
+pub async fn set_update_check_enabled(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    set_check_enabled(&state.db, enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn release(version: &str) -> LatestRelease {
+        LatestRelease {
+            version: version.to_string(),
+            url: format!("https://github.com/zhaozhongke/hajimi-cli-sync/releases/tag/v{version}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let body = json!({
+            "tag_name": "v1.10.0",
+            "html_url": "https://github.com/zhaozhongke/hajimi-cli-sync/releases/tag/v1.10.0"
+        });
+        assert_eq!(parse_release(&body).unwrap(), release("1.10.0"));
+        assert!(parse_release(&json!({"tag_name": ""})).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let newer = compare("1.9.2", Some(release("1.10.0")));
+        assert!(newer.update_available);
+        assert_eq!(newer.latest.as_deref(), Some("1.10.0"));
+        assert!(newer.release_notes_url.is_some());
+        assert!(!compare("1.10.0", Some(release("1.10.0"))).update_available);
+        assert!(!compare("2.0.0", Some(release("1.10.0"))).update_available);
+        assert_eq!(compare("1.0.0", None).latest, None);
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let db = Database::memory().unwrap();
+        assert!(read_cache(&db, 1_000).is_none());
+        write_cache(&db, &release("1.2.0"), 1_000).unwrap();
+        assert_eq!(read_cache(&db, 1_000 + 3_600), Some(release("1.2.0")));
+        assert!(read_cache(&db, 1_000 + CACHE_TTL_SECS).is_none());
+        assert!(read_cache(&db, 900).is_none());
+    }
+
+    mod http {
+        use super::*;
+        use wiremock::matchers::{header_exists, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn test_check_fetches_once_and_respects_opt_out() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(header_exists("user-agent"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(json!({"tag_name": "v9.0.0"})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+            let db = Database::memory().unwrap();
+
+            let first = check(&db, &server.uri(), "1.0.0").await.unwrap();
+            assert!(first.update_available);
+            assert_eq!(first.release_notes_url, None);
+            // Served from the cache
+            let second = check(&db, &server.uri(), "1.0.0").await.unwrap();
+            assert_eq!(first, second);
+
+            set_check_enabled(&db, false).unwrap();
+            let off = check(&db, "http://127.0.0.1:1", "1.0.0").await.unwrap();
+            assert_eq!(off.latest, None);
+            assert!(!off.update_available);
+        }
+
+        #[tokio::test]
+        async fn test_check_reports_http_errors() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(403).set_body_string("rate limited"))
+                .mount(&server)
+                .await;
+            let db = Database::memory().unwrap();
+            let err = check(&db, &server.uri(), "1.0.0").await.unwrap_err();
+            assert!(err.contains("403"), "{err}");
+            assert!(settings::get(&db, CACHE_KEY).unwrap().is_none());
+        }
+    }
+}
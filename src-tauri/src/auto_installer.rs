@@ -1,9 +1,17 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
 use std::time::Duration;
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
 
+use crate::database::{dao::settings, Database};
 use crate::error::{Result, SyncError};
-use crate::utils;
+use crate::store::AppState;
+use crate::{notify, utils};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -32,15 +40,406 @@ pub enum InstallStatus {
     Skipped,
 }
 
+/// 安装进度事件名（payload 为 `InstallProgress`）
+pub const EVENT_INSTALL_PROGRESS: &str = "install://progress";
+
+/// 下载大小上限，防止磁盘被占满
+const MAX_DOWNLOAD_SIZE: u64 = 500 * 1024 * 1024;
+
+/// 固定版本的独立安装包，`file` 相对于下载源（官方或镜像）
+///
+/// 升级 Node.js / MinGit 只需修改下面的表：版本、文件名和对应的 SHA-256。
+/// `sha256` 为 None 的 Node.js 包改用同版本的 SHASUMS256.txt 校验。
+#[derive(Debug, PartialEq)]
+struct PinnedArchive {
+    file: &'static str,
+    sha256: Option<&'static str>,
+}
+
+const NODE_VERSION: &str = "v22.16.0";
+
+/// (OS, 架构) → 独立版 Node.js 包
+const NODE_ARCHIVES: &[(&str, &str, PinnedArchive)] = &[
+    (
+        "windows",
+        "x86_64",
+        PinnedArchive {
+            file: "node-v22.16.0-win-x64.zip",
+            sha256: None,
+        },
+    ),
+    (
+        "macos",
+        "aarch64",
+        PinnedArchive {
+            file: "node-v22.16.0-darwin-arm64.tar.gz",
+            sha256: None,
+        },
+    ),
+    (
+        "macos",
+        "x86_64",
+        PinnedArchive {
+            file: "node-v22.16.0-darwin-x64.tar.gz",
+            sha256: None,
+        },
+    ),
+    (
+        "linux",
+        "x86_64",
+        PinnedArchive {
+            file: "node-v22.16.0-linux-x64.tar.xz",
+            sha256: None,
+        },
+    ),
+];
+
+/// 便携版 MinGit（相对于 git-for-windows 的 release 下载目录）
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const MINGIT_ARCHIVE: PinnedArchive = PinnedArchive {
+    file: "v2.43.0.windows.1/MinGit-2.43.0-64-bit.zip",
+    sha256: None,
+};
+
+const DOWNLOAD_MIRROR_KEY: &str = "download_mirror";
+const NPMMIRROR_REGISTRY: &str = "https://registry.npmmirror.com";
+const NODE_DIST: &str = "https://nodejs.org/dist";
+const NPMMIRROR_NODE_DIST: &str = "https://registry.npmmirror.com/-/binary/node";
+const GIT_FOR_WINDOWS_RELEASES: &str = "https://github.com/git-for-windows/git/releases/download";
+const NPMMIRROR_GIT_FOR_WINDOWS: &str = "https://registry.npmmirror.com/-/binary/git-for-windows";
+
+/// 下载源（`download_mirror` 设置）
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum DownloadMirror {
+    /// 官方源：registry.npmjs.org、nodejs.org、GitHub
+    #[default]
+    Default,
+    /// npmmirror：npm 包、Node.js 与 MinGit 都走国内镜像
+    China,
+    /// 自定义 npm registry；Node.js 与 MinGit 仍走官方源
+    Custom(String),
+}
+
+impl DownloadMirror {
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value.trim() {
+            "" | "default" => Ok(Self::Default),
+            "china" => Ok(Self::China),
+            url => {
+                utils::validate_url(url).map_err(|e| e.to_string())?;
+                Ok(Self::Custom(url.trim_end_matches('/').to_string()))
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Default => "default",
+            Self::China => "china",
+            Self::Custom(url) => url,
+        }
+    }
+
+    pub(crate) fn npm_registry(&self) -> &str {
+        match self {
+            Self::Default => NPM_REGISTRY,
+            Self::China => NPMMIRROR_REGISTRY,
+            Self::Custom(url) => url,
+        }
+    }
+
+    /// 追加到 npm 命令的 `--registry` 参数；官方源时不覆盖用户自己的 .npmrc
+    fn registry_args(&self) -> Vec<&str> {
+        match self {
+            Self::Default => Vec::new(),
+            _ => vec!["--registry", self.npm_registry()],
+        }
+    }
+
+    fn node_dist(&self) -> &str {
+        match self {
+            Self::China => NPMMIRROR_NODE_DIST,
+            _ => NODE_DIST,
+        }
+    }
+
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn git_for_windows_releases(&self) -> &str {
+        match self {
+            Self::China => NPMMIRROR_GIT_FOR_WINDOWS,
+            _ => GIT_FOR_WINDOWS_RELEASES,
+        }
+    }
+}
+
+pub fn download_mirror(db: &Database) -> DownloadMirror {
+    settings::get(db, DOWNLOAD_MIRROR_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| DownloadMirror::parse(&v).ok())
+        .unwrap_or_default()
+}
+
+pub fn set_download_mirror(
+    db: &Database,
+    mirror: &DownloadMirror,
+) -> std::result::Result<(), String> {
+    settings::set(db, DOWNLOAD_MIRROR_KEY, mirror.as_str())
+}
+
+const PACKAGE_MANAGERS_KEY: &str = "package_managers";
+
+/// 全局安装 npm 包所用的包管理器
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    Npm,
+    Bun,
+    Pnpm,
+}
+
+impl PackageManager {
+    /// 默认顺序：npm 优先，缺失时依次尝试 bun、pnpm
+    const DEFAULT_ORDER: [Self; 3] = [Self::Npm, Self::Bun, Self::Pnpm];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "npm" => Some(Self::Npm),
+            "bun" => Some(Self::Bun),
+            "pnpm" => Some(Self::Pnpm),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Bun => "bun",
+            Self::Pnpm => "pnpm",
+        }
+    }
+
+    /// 全局安装参数（`package` 可带 `@latest`）
+    fn install_args(self, package: &str) -> Vec<&str> {
+        match self {
+            Self::Npm => vec!["install", "-g", package, "--silent", "--no-progress"],
+            Self::Bun | Self::Pnpm => vec!["add", "-g", package],
+        }
+    }
+
+    fn uninstall_args(self, package: &str) -> Vec<&str> {
+        match self {
+            Self::Npm => vec!["uninstall", "-g", package, "--silent"],
+            Self::Bun | Self::Pnpm => vec!["remove", "-g", package],
+        }
+    }
+}
+
+/// 包管理器的尝试顺序（`package_managers` 设置，逗号分隔）
+pub fn package_manager_order(db: &Database) -> Vec<PackageManager> {
+    let order: Vec<PackageManager> = settings::get(db, PACKAGE_MANAGERS_KEY)
+        .ok()
+        .flatten()
+        .map(|v| v.split(',').filter_map(PackageManager::parse).collect())
+        .unwrap_or_default();
+    if order.is_empty() {
+        PackageManager::DEFAULT_ORDER.to_vec()
+    } else {
+        order
+    }
+}
+
+pub fn set_package_manager_order(
+    db: &Database,
+    order: &[PackageManager],
+) -> std::result::Result<(), String> {
+    let value: Vec<&str> = order.iter().map(|pm| pm.as_str()).collect();
+    settings::set(db, PACKAGE_MANAGERS_KEY, &value.join(","))
+}
+
+const SILLYTAVERN_DIR_KEY: &str = "sillytavern_dir";
+
+/// 用户指定的 SillyTavern 安装目录；未设置时为 `~/SillyTavern`
+pub fn sillytavern_dir(db: &Database) -> Option<PathBuf> {
+    settings::get(db, SILLYTAVERN_DIR_KEY)
+        .ok()
+        .flatten()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// 空字符串恢复默认目录
+pub fn set_sillytavern_dir(db: &Database, dir: &str) -> std::result::Result<(), String> {
+    let dir = dir.trim();
+    if !dir.is_empty() && !Path::new(dir).is_absolute() {
+        return Err(format!("Path must be absolute: {dir}"));
+    }
+    settings::set(db, SILLYTAVERN_DIR_KEY, dir)
+}
+
+/// 来自设置的安装选项，随进度推送器传给各安装步骤
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    pub mirror: DownloadMirror,
+    pub package_managers: Vec<PackageManager>,
+    pub sillytavern_dir: Option<PathBuf>,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        Self {
+            mirror: DownloadMirror::Default,
+            package_managers: PackageManager::DEFAULT_ORDER.to_vec(),
+            sillytavern_dir: None,
+        }
+    }
+}
+
+impl InstallOptions {
+    pub fn load(db: &Database) -> Self {
+        Self {
+            mirror: download_mirror(db),
+            package_managers: package_manager_order(db),
+            sillytavern_dir: sillytavern_dir(db),
+        }
+    }
+
+    /// git 仓库类工具的安装目录
+    fn checkout_dir(&self) -> Result<PathBuf> {
+        match &self.sillytavern_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => dirs::home_dir()
+                .map(|home| home.join("SillyTavern"))
+                .ok_or_else(|| SyncError::Other("Cannot determine home directory".to_string())),
+        }
+    }
+}
+
+/// 向前端推送单个工具的安装进度
+#[derive(Clone)]
+pub struct ProgressReporter {
+    app: Option<tauri::AppHandle>,
+    tool: String,
+    options: InstallOptions,
+}
+
+impl ProgressReporter {
+    pub fn new(app: &tauri::AppHandle, tool: &str, options: InstallOptions) -> Self {
+        Self {
+            app: Some(app.clone()),
+            tool: tool.to_string(),
+            options,
+        }
+    }
+
+    /// 发送一条进度事件，并返回该进度（供命令汇总结果）
+    fn report(
+        &self,
+        status: InstallStatus,
+        progress: u8,
+        message: impl Into<String>,
+    ) -> InstallProgress {
+        let event = InstallProgress {
+            tool: self.tool.clone(),
+            status,
+            progress,
+            message: message.into(),
+        };
+        if let Some(app) = &self.app {
+            if let Err(e) = app.emit(EVENT_INSTALL_PROGRESS, event.clone()) {
+                tracing::warn!(
+                    "[auto_installer] Failed to emit progress for {}: {}",
+                    self.tool,
+                    e
+                );
+            }
+        }
+        event
+    }
+
+    /// 记录并推送一个安装步骤
+    fn step(&self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::info!("[auto_installer] {}: {}", self.tool, message);
+        self.report(InstallStatus::Installing, 0, message);
+    }
+}
+
+/// 正在进行的安装（工具 → 取消令牌），供 `cancel_install` 使用
+#[derive(Default)]
+pub struct InstallJobs {
+    running: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl InstallJobs {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CancellationToken>> {
+        self.running.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("[auto_installer] InstallJobs mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+
+    /// 登记一个安装；该工具已在安装中时返回 None
+    fn start(&self, tool: &str) -> Option<InstallJob<'_>> {
+        let mut running = self.lock();
+        if running.contains_key(tool) {
+            return None;
+        }
+        let token = CancellationToken::new();
+        running.insert(tool.to_string(), token.clone());
+        Some(InstallJob {
+            jobs: self,
+            tool: tool.to_string(),
+            token,
+        })
+    }
+
+    /// 取消该工具正在进行的安装；没有在安装时返回 false
+    pub fn cancel(&self, tool: &str) -> bool {
+        match self.lock().get(tool) {
+            Some(token) => {
+                tracing::info!("[auto_installer] Cancelling installation of {}", tool);
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 一次登记中的安装，结束（包括出错、panic）时自动注销
+struct InstallJob<'a> {
+    jobs: &'a InstallJobs,
+    tool: String,
+    token: CancellationToken,
+}
+
+impl InstallJob<'_> {
+    /// 运行安装，取消时直接丢弃该 future：子进程随之被杀，临时下载文件随之删除
+    async fn run(&self, install: impl Future<Output = Result<()>>) -> Result<()> {
+        tokio::select! {
+            result = install => result,
+            _ = self.token.cancelled() => Err(SyncError::InstallCancelled {
+                tool: self.tool.clone(),
+            }),
+        }
+    }
+}
+
+impl Drop for InstallJob<'_> {
+    fn drop(&mut self) {
+        self.jobs.lock().remove(&self.tool);
+    }
+}
+
 /// 自动安装Git（静默）
-pub async fn auto_install_git() -> Result<()> {
+pub async fn auto_install_git(reporter: &ProgressReporter) -> Result<()> {
     tracing::info!("[auto_installer] Starting automatic Git installation...");
 
     #[cfg(target_os = "windows")]
     {
         // Windows: 优先使用winget，fallback到chocolatey
         if check_command_exists("winget") {
-            tracing::info!("[auto_installer] Using winget to install Git");
+            reporter.step("Installing Git with winget...");
             run_silent_command(
                 "winget",
                 &[
@@ -54,11 +453,11 @@ pub async fn auto_install_git() -> Result<()> {
             )
             .await?;
         } else if check_command_exists("choco") {
-            tracing::info!("[auto_installer] Using chocolatey to install Git");
+            reporter.step("Installing Git with chocolatey...");
             run_silent_command("choco", &["install", "git", "-y"]).await?;
         } else {
             // 下载便携版Git（无需安装）
-            return download_portable_git().await;
+            return download_portable_git(reporter).await;
         }
     }
 
@@ -66,10 +465,10 @@ pub async fn auto_install_git() -> Result<()> {
     {
         // macOS: 使用Homebrew，如果没有则使用Xcode Command Line Tools
         if check_command_exists("brew") {
-            tracing::info!("[auto_installer] Using Homebrew to install Git");
+            reporter.step("Installing Git with Homebrew...");
             run_silent_command("brew", &["install", "git"]).await?;
         } else {
-            tracing::info!("[auto_installer] Installing Xcode Command Line Tools");
+            reporter.step("Installing Xcode Command Line Tools...");
             run_silent_command("xcode-select", &["--install"]).await?;
         }
     }
@@ -78,17 +477,17 @@ pub async fn auto_install_git() -> Result<()> {
     {
         // Linux: 自动检测包管理器
         if check_command_exists("apt-get") {
-            tracing::info!("[auto_installer] Using apt-get to install Git");
+            reporter.step("Installing Git with apt-get...");
             run_silent_command("sudo", &["apt-get", "update", "-qq"]).await?;
             run_silent_command("sudo", &["apt-get", "install", "-y", "git"]).await?;
         } else if check_command_exists("yum") {
-            tracing::info!("[auto_installer] Using yum to install Git");
+            reporter.step("Installing Git with yum...");
             run_silent_command("sudo", &["yum", "install", "-y", "git"]).await?;
         } else if check_command_exists("dnf") {
-            tracing::info!("[auto_installer] Using dnf to install Git");
+            reporter.step("Installing Git with dnf...");
             run_silent_command("sudo", &["dnf", "install", "-y", "git"]).await?;
         } else if check_command_exists("pacman") {
-            tracing::info!("[auto_installer] Using pacman to install Git");
+            reporter.step("Installing Git with pacman...");
             run_silent_command("sudo", &["pacman", "-S", "--noconfirm", "git"]).await?;
         } else {
             return Err(SyncError::Other("No package manager found".to_string()));
@@ -99,12 +498,12 @@ pub async fn auto_install_git() -> Result<()> {
 }
 
 /// 自动安装Node.js（静默）
-pub async fn auto_install_nodejs() -> Result<()> {
-    auto_install_nodejs_version("22").await
+pub async fn auto_install_nodejs(reporter: &ProgressReporter) -> Result<()> {
+    auto_install_nodejs_version("22", reporter).await
 }
 
 /// Ensure Node.js 22+ is available (required by OpenClaw)
-async fn ensure_node22() -> Result<()> {
+async fn ensure_node22(reporter: &ProgressReporter) -> Result<()> {
     if let Some(version) = get_node_major_version() {
         if version >= 22 {
             tracing::info!(
@@ -120,7 +519,7 @@ async fn ensure_node22() -> Result<()> {
     } else {
         tracing::info!("[auto_installer] Node.js not found, installing v22...");
     }
-    auto_install_nodejs_version("22").await
+    auto_install_nodejs_version("22", reporter).await
 }
 
 /// Get the major version of installed Node.js, if any
@@ -129,19 +528,11 @@ fn get_node_major_version() -> Option<u32> {
     if !output.status.success() {
         return None;
     }
-    let version_str = String::from_utf8_lossy(&output.stdout);
-    // Parse "v22.12.0" -> 22
-    version_str
-        .trim()
-        .trim_start_matches('v')
-        .split('.')
-        .next()?
-        .parse::<u32>()
-        .ok()
+    utils::major_version(&String::from_utf8_lossy(&output.stdout))
 }
 
 /// 安装指定大版本的Node.js（静默）
-async fn auto_install_nodejs_version(major: &str) -> Result<()> {
+async fn auto_install_nodejs_version(major: &str, reporter: &ProgressReporter) -> Result<()> {
     tracing::info!(
         "[auto_installer] Starting automatic Node.js {} installation...",
         major
@@ -150,7 +541,7 @@ async fn auto_install_nodejs_version(major: &str) -> Result<()> {
     #[cfg(target_os = "windows")]
     {
         if check_command_exists("winget") {
-            tracing::info!("[auto_installer] Using winget to install Node.js");
+            reporter.step("Installing Node.js with winget...");
             run_silent_command(
                 "winget",
                 &[
@@ -164,20 +555,20 @@ async fn auto_install_nodejs_version(major: &str) -> Result<()> {
             )
             .await?;
         } else if check_command_exists("choco") {
-            tracing::info!("[auto_installer] Using chocolatey to install Node.js");
+            reporter.step("Installing Node.js with chocolatey...");
             run_silent_command("choco", &["install", "nodejs", "-y"]).await?;
         } else {
-            return install_nodejs_standalone().await;
+            return install_nodejs_standalone(reporter).await;
         }
     }
 
     #[cfg(target_os = "macos")]
     {
         if check_command_exists("brew") {
-            tracing::info!("[auto_installer] Using Homebrew to install Node.js");
+            reporter.step("Installing Node.js with Homebrew...");
             run_silent_command("brew", &["install", "node"]).await?;
         } else {
-            return install_nodejs_standalone().await;
+            return install_nodejs_standalone(reporter).await;
         }
     }
 
@@ -185,107 +576,455 @@ async fn auto_install_nodejs_version(major: &str) -> Result<()> {
     {
         // 使用NodeSource官方脚本
         tracing::info!("[auto_installer] Using NodeSource to install Node.js");
-        install_nodejs_nodesource().await?;
+        install_nodejs_nodesource(reporter).await?;
     }
 
     Ok(())
 }
 
-/// 自动安装CLI工具（通过npm）
-pub async fn auto_install_cli_tool(tool: &str) -> Result<()> {
-    tracing::info!("[auto_installer] Installing CLI tool: {}", tool);
+/// npm 官方 registry
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// 可通过 npm 全局安装的 CLI 工具（工具 id → 包名）
+const NPM_PACKAGES: &[(&str, &str)] = &[
+    ("claude", "@anthropic-ai/claude-code"),
+    ("codex", "@openai/codex"),
+    ("gemini", "@google/gemini-cli"),
+    // OpenClaw requires Node.js 22.12.0+, official npm package is "openclaw"
+    ("openclaw", "openclaw"),
+];
+
+/// VS Code 扩展（工具 id → 扩展 id），通过 `code --install-extension` 安装
+const VSCODE_EXTENSIONS: &[(&str, &str)] = &[
+    ("claude-vscode", "anthropic.claude-code"),
+    ("cline", "saoudrizwan.claude-dev"),
+    ("roo-code", "rooveterinaryinc.roo-cline"),
+    ("kilo-code", "kilocode.kilo-code"),
+];
+
+/// 官方安装脚本（及 Homebrew / Windows 上的替代方式）
+#[derive(Debug, PartialEq)]
+struct ScriptInstall {
+    /// macOS/Linux：官方脚本地址及执行它的 shell
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    unix_script: (&'static str, &'static str),
+    /// 可用时优先于脚本的 Homebrew formula
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    brew_formula: Option<&'static str>,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    windows: WindowsInstall,
+}
 
-    // 确保npm可用
-    if !check_command_exists("npm") {
-        tracing::warn!("[auto_installer] npm not found, installing Node.js first");
-        auto_install_nodejs().await?;
+/// Windows 上的官方安装方式
+#[derive(Debug, PartialEq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+enum WindowsInstall {
+    Npm(&'static str),
+    PowerShell(&'static str),
+}
 
-        // 等待npm安装完成
-        for _ in 0..30 {
-            tokio::time::sleep(Duration::from_secs(2)).await;
-            if check_command_exists("npm") {
-                break;
-            }
-        }
+/// 通过官方安装脚本安装的工具（工具 id → 安装方式）
+const SCRIPT_INSTALLS: &[(&str, ScriptInstall)] = &[
+    (
+        "opencode",
+        ScriptInstall {
+            unix_script: ("https://opencode.ai/install", "bash"),
+            brew_formula: Some("opencode"),
+            windows: WindowsInstall::Npm("opencode-ai"),
+        },
+    ),
+    (
+        "droid",
+        ScriptInstall {
+            unix_script: ("https://app.factory.ai/cli", "sh"),
+            brew_formula: None,
+            windows: WindowsInstall::PowerShell("https://app.factory.ai/cli/windows"),
+        },
+    ),
+];
+
+/// 安装脚本可能要下载较大的二进制文件
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 以 git 仓库形式运行的工具（工具 id → 仓库地址）
+const GIT_REPOS: &[(&str, &str)] = &[("sillytavern", "https://github.com/SillyTavern/SillyTavern")];
+
+/// 克隆完成的标志文件：SillyTavern 的入口脚本
+const CHECKOUT_MARKER: &str = "server.js";
+
+/// 工具的安装来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ToolSource {
+    Npm(&'static str),
+    VscodeExtension(&'static str),
+    Script(&'static ScriptInstall),
+    GitRepo(&'static str),
+}
+
+/// 查找工具的安装来源；无法自动安装的工具返回说明原因的错误
+fn tool_source(tool: &str) -> Result<ToolSource> {
+    let lookup = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(id, _)| *id == tool)
+            .map(|(_, value)| *value)
+    };
+    if let Some(package) = lookup(NPM_PACKAGES) {
+        return Ok(ToolSource::Npm(package));
+    }
+    if let Some(extension) = lookup(VSCODE_EXTENSIONS) {
+        return Ok(ToolSource::VscodeExtension(extension));
+    }
+    if let Some(repo) = lookup(GIT_REPOS) {
+        return Ok(ToolSource::GitRepo(repo));
+    }
+    if let Some((_, script)) = SCRIPT_INSTALLS.iter().find(|(id, _)| *id == tool) {
+        return Ok(ToolSource::Script(script));
+    }
+
+    let reason = match tool {
+        // Desktop apps — cannot be installed via npm
+        "chatbox" | "cherry-studio" | "jan" | "cursor" | "lobechat" | "boltai" | "warp"
+        | "amazon-q" => format!(
+            "{tool} is a desktop application. Please download it from its official website."
+        ),
+        _ => format!("Unknown tool '{tool}'. Only known tools can be installed."),
+    };
+    Err(SyncError::Other(reason))
+}
 
-        if !check_command_exists("npm") {
-            return Err(SyncError::Other("Failed to install npm".to_string()));
+/// 按设置的顺序找到第一个能运行的包管理器
+fn detect_package_manager(order: &[PackageManager]) -> Option<(PackageManager, String)> {
+    order.iter().find_map(|&pm| {
+        let path = utils::resolve_executable(pm.as_str())?;
+        utils::get_cli_version(&path)?;
+        Some((pm, path.to_string_lossy().into_owned()))
+    })
+}
+
+/// 确保有可用的包管理器；npm、bun、pnpm 都没有时先安装Node.js
+async fn ensure_package_manager(
+    order: &[PackageManager],
+    reporter: &ProgressReporter,
+) -> Result<(PackageManager, String)> {
+    if let Some(found) = detect_package_manager(order) {
+        return Ok(found);
+    }
+    tracing::warn!("[auto_installer] No package manager found, installing Node.js first");
+    reporter.step("npm not found, installing Node.js first...");
+    auto_install_nodejs(reporter).await?;
+
+    // 等待npm安装完成
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        if check_command_exists("npm") {
+            return Ok((PackageManager::Npm, "npm".to_string()));
         }
     }
+    Err(SyncError::Other("Failed to install npm".to_string()))
+}
+
+/// 用可用的包管理器全局安装 `package`
+async fn install_package(
+    tool: &str,
+    package: &str,
+    verb: &str,
+    reporter: &ProgressReporter,
+) -> Result<()> {
+    let (pm, executable) =
+        ensure_package_manager(&reporter.options.package_managers, reporter).await?;
+    if tool == "openclaw" {
+        ensure_node22(reporter).await?;
+    }
 
-    let package_name = match tool {
-        "claude" => "@anthropic-ai/claude-code",
-        "codex" => "@openai/codex",
-        "gemini" => "@google/gemini-cli",
-        // OpenClaw requires Node.js 22.12.0+, official npm package is "openclaw"
-        "openclaw" => {
-            ensure_node22().await?;
-            "openclaw"
+    reporter.step(format!(
+        "{verb} {package} with {} (registry: {})...",
+        pm.as_str(),
+        reporter.options.mirror.npm_registry()
+    ));
+
+    let mut args = pm.install_args(package);
+    args.extend(reporter.options.mirror.registry_args());
+    run_silent_command(&executable, &args).await
+}
+
+/// 自动安装CLI工具（通过npm，或 bun / pnpm）
+pub async fn auto_install_cli_tool(tool: &str, reporter: &ProgressReporter) -> Result<()> {
+    tracing::info!("[auto_installer] Installing CLI tool: {}", tool);
+
+    match tool_source(tool)? {
+        ToolSource::VscodeExtension(extension_id) => {
+            install_vscode_extension(extension_id, reporter).await
         }
-        // OpenCode is installed from GitHub, not npm
-        "opencode" => {
-            return Err(SyncError::Other(
-                "OpenCode must be installed from GitHub. See: https://github.com/anomalyco/opencode".to_string()
-            ));
+        ToolSource::Npm(package_name) => {
+            install_package(tool, package_name, "Installing", reporter).await
         }
-        // Desktop apps — cannot be installed via npm
-        "chatbox" | "cherry-studio" | "jan" | "cursor" | "lobechat" | "boltai" => {
+        ToolSource::Script(script) => install_with_script(tool, script, reporter).await,
+        ToolSource::GitRepo(repo) => install_from_git(repo, reporter).await,
+    }
+}
+
+/// 克隆仓库到安装目录并安装其依赖；目录里已有克隆时只重装依赖
+async fn install_from_git(repo: &str, reporter: &ProgressReporter) -> Result<()> {
+    let dir = reporter.options.checkout_dir()?;
+    if !dir.join(CHECKOUT_MARKER).is_file() {
+        if dir
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
             return Err(SyncError::Other(format!(
-                "{tool} is a desktop application. Please download it from its official website."
+                "{} already exists and is not a SillyTavern folder; choose another install directory.",
+                dir.display()
             )));
         }
-        // VS Code extensions — install via `code --install-extension`
-        "claude-vscode" => {
-            return install_vscode_extension("anthropic.claude-code").await;
+        if !check_command_exists("git") {
+            reporter.step("Git not found, installing Git first...");
+            auto_install_git(reporter).await?;
+            if !check_command_exists("git") {
+                return Err(SyncError::Other(
+                    "Git was installed but is not on PATH yet; restart the app and try again."
+                        .to_string(),
+                ));
+            }
+        }
+
+        reporter.step(format!("Cloning {repo} into {}...", dir.display()));
+        let target = dir.to_string_lossy().to_string();
+        run_silent_command_with_timeout(
+            "git",
+            &["clone", "--depth", "1", repo, &target],
+            SCRIPT_TIMEOUT,
+        )
+        .await?;
+    }
+    install_checkout_dependencies(&dir, reporter).await
+}
+
+/// 在克隆目录中执行 `npm install`（项目自带 package-lock，固定用 npm）
+async fn install_checkout_dependencies(dir: &Path, reporter: &ProgressReporter) -> Result<()> {
+    let (_, npm) = ensure_package_manager(&[PackageManager::Npm], reporter).await?;
+    reporter.step(format!(
+        "Installing dependencies with npm (registry: {})...",
+        reporter.options.mirror.npm_registry()
+    ));
+    let prefix = dir.to_string_lossy().to_string();
+    let mut args = vec![
+        "install",
+        "--prefix",
+        &prefix,
+        "--omit=dev",
+        "--no-audit",
+        "--no-fund",
+        "--no-progress",
+    ];
+    args.extend(reporter.options.mirror.registry_args());
+    run_silent_command_with_timeout(&npm, &args, SCRIPT_TIMEOUT).await
+}
+
+/// 用官方安装方式安装，并确认可执行文件已能找到
+async fn install_with_script(
+    tool: &str,
+    script: &ScriptInstall,
+    reporter: &ProgressReporter,
+) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    match script.windows {
+        WindowsInstall::Npm(package) => {
+            install_package(tool, package, "Installing", reporter).await?
         }
-        "cline" => {
-            return install_vscode_extension("saoudrizwan.claude-dev").await;
+        WindowsInstall::PowerShell(url) => {
+            run_remote_script(tool, url, "powershell", reporter).await?
         }
-        "roo-code" => {
-            return install_vscode_extension("rooveterinaryinc.roo-cline").await;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    match script.brew_formula.filter(|_| check_command_exists("brew")) {
+        Some(formula) => {
+            reporter.step(format!("Installing {formula} with Homebrew..."));
+            run_silent_command_with_timeout("brew", &["install", formula], SCRIPT_TIMEOUT).await?;
         }
-        "kilo-code" => {
-            return install_vscode_extension("kilocode.kilo-code").await;
+        None => {
+            let (url, shell) = script.unix_script;
+            run_remote_script(tool, url, shell, reporter).await?;
         }
-        // SillyTavern is a Node.js app, not an npm global package
-        "sillytavern" => {
-            return Err(SyncError::Other(
-                "SillyTavern must be installed via git clone. See: https://docs.sillytavern.app/installation/".to_string()
+    }
+
+    if utils::resolve_executable(tool).is_none() {
+        return Err(SyncError::Other(format!(
+            "The {tool} installer finished, but `{tool}` was not found. Open a new terminal, or add its install directory to PATH."
+        )));
+    }
+    Ok(())
+}
+
+/// 下载官方安装脚本到临时文件后执行（不经管道，下载失败时不会执行半个脚本）
+async fn run_remote_script(
+    tool: &str,
+    url: &str,
+    shell: &str,
+    reporter: &ProgressReporter,
+) -> Result<()> {
+    let extension = if shell == "powershell" { "ps1" } else { "sh" };
+    let script = std::env::temp_dir().join(format!(
+        "hajimi-install-{tool}-{}.{extension}",
+        uuid::Uuid::new_v4()
+    ));
+    download_to_file(url, &script, None, Duration::from_secs(60), reporter).await?;
+
+    reporter.step(format!("Running the official {tool} installer..."));
+    let script_path = script.to_string_lossy().to_string();
+    let args: Vec<&str> = if shell == "powershell" {
+        vec![
+            "-NoProfile",
+            "-ExecutionPolicy",
+            "Bypass",
+            "-File",
+            &script_path,
+        ]
+    } else {
+        vec![&script_path]
+    };
+    let result = run_silent_command_with_timeout(shell, &args, SCRIPT_TIMEOUT).await;
+    std::fs::remove_file(&script).ok();
+    result
+}
+
+/// 升级CLI工具：Homebrew 安装的用 brew upgrade，其余按安装来源重新安装最新版
+async fn update_cli(tool: &str, reporter: &ProgressReporter) -> Result<()> {
+    if let Some(formula) = homebrew_formula(tool) {
+        reporter.step(format!("Upgrading {formula} with Homebrew..."));
+        return run_silent_command("brew", &["upgrade", &formula]).await;
+    }
+
+    match tool_source(tool)? {
+        // `npm update -g` stays within the installed major version; @latest does not
+        ToolSource::Npm(package_name) => {
+            let package = format!("{package_name}@latest");
+            install_package(tool, &package, "Updating", reporter).await
+        }
+        // --force reinstalls the latest published version
+        ToolSource::VscodeExtension(extension_id) => {
+            install_vscode_extension(extension_id, reporter).await
+        }
+        // 官方脚本总是安装最新版
+        ToolSource::Script(script) => install_with_script(tool, script, reporter).await,
+        ToolSource::GitRepo(_) => {
+            let dir = reporter.options.checkout_dir()?;
+            reporter.step(format!(
+                "Pulling the latest changes in {}...",
+                dir.display()
             ));
+            let dir_arg = dir.to_string_lossy().to_string();
+            run_silent_command_with_timeout(
+                "git",
+                &["-C", &dir_arg, "pull", "--ff-only"],
+                SCRIPT_TIMEOUT,
+            )
+            .await?;
+            install_checkout_dependencies(&dir, reporter).await
         }
-        // Droid has no public npm package
-        "droid" => {
-            return Err(SyncError::Other(
-                "Droid must be installed from https://factory.ai".to_string(),
+    }
+}
+
+/// 卸载CLI工具
+async fn uninstall_cli(tool: &str, reporter: &ProgressReporter) -> Result<()> {
+    if let Some(formula) = homebrew_formula(tool) {
+        reporter.step(format!("Uninstalling {formula} with Homebrew..."));
+        return run_silent_command("brew", &["uninstall", &formula]).await;
+    }
+
+    match tool_source(tool)? {
+        ToolSource::Npm(package_name) => {
+            let (pm, executable) = detect_package_manager(&reporter.options.package_managers)
+                .ok_or_else(|| {
+                    SyncError::Other(
+                        "No npm, bun or pnpm found; cannot uninstall the package without one."
+                            .to_string(),
+                    )
+                })?;
+            reporter.step(format!(
+                "Uninstalling {package_name} with {}...",
+                pm.as_str()
             ));
+            run_silent_command(&executable, &pm.uninstall_args(package_name)).await
         }
-        _ => {
-            return Err(SyncError::Other(format!(
-                "Unknown tool '{tool}'. Only known tools can be installed."
-            )));
+        ToolSource::VscodeExtension(extension_id) => {
+            require_vscode_cli()?;
+            reporter.step(format!("Uninstalling VS Code extension {extension_id}..."));
+            run_silent_command("code", &["--uninstall-extension", extension_id]).await
         }
-    };
-
-    tracing::info!("[auto_installer] Installing npm package: {}", package_name);
+        ToolSource::Script(_) => Err(SyncError::Other(format!(
+            "{tool} was installed by its own installer; remove it following its documentation."
+        ))),
+        // 目录里有用户的聊天记录和角色卡，不自动删除
+        ToolSource::GitRepo(_) => Err(SyncError::Other(format!(
+            "{tool} keeps your chats in its install folder; delete {} yourself after backing it up.",
+            reporter.options.checkout_dir()?.display()
+        ))),
+    }
+}
 
-    let args = vec!["install", "-g", package_name, "--silent", "--no-progress"];
-    run_silent_command("npm", &args).await?;
+/// 工具若由 Homebrew 安装，返回其 formula/cask 名
+fn homebrew_formula(tool: &str) -> Option<String> {
+    let executable = utils::resolve_executable(tool)?.canonicalize().ok()?;
+    homebrew_formula_from_path(&executable).filter(|_| check_command_exists("brew"))
+}
 
-    Ok(())
+/// `.../Cellar/<formula>/<version>/...` 或 `.../Caskroom/<cask>/<version>/...`
+fn homebrew_formula_from_path(path: &Path) -> Option<String> {
+    let mut components = path.components().map(|c| c.as_os_str().to_string_lossy());
+    components.find(|c| c == "Cellar" || c == "Caskroom")?;
+    components.next().map(|name| name.into_owned())
 }
 
 /// Install a VS Code extension via the `code` CLI
-async fn install_vscode_extension(extension_id: &str) -> Result<()> {
+async fn install_vscode_extension(extension_id: &str, reporter: &ProgressReporter) -> Result<()> {
+    require_vscode_cli()?;
+    reporter.step(format!("Installing VS Code extension {extension_id}..."));
+    run_silent_command("code", &["--install-extension", extension_id, "--force"]).await
+}
+
+fn require_vscode_cli() -> Result<()> {
     if !check_command_exists("code") {
         return Err(SyncError::Other(
             "VS Code CLI ('code') not found. Please install VS Code first.".to_string(),
         ));
     }
-    tracing::info!(
-        "[auto_installer] Installing VS Code extension: {}",
-        extension_id
-    );
-    run_silent_command("code", &["--install-extension", extension_id, "--force"]).await
+    Ok(())
+}
+
+/// 有新版本可用的CLI工具
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CliUpdate {
+    pub tool: String,
+    pub package: String,
+    pub installed: String,
+    pub latest: String,
+}
+
+/// 从 npm registry 查询包的最新发布版本
+async fn fetch_latest_version(
+    client: &reqwest::Client,
+    registry: &str,
+    package: &str,
+) -> Result<String> {
+    #[derive(Deserialize)]
+    struct Latest {
+        version: String,
+    }
+
+    let url = format!("{}/{package}/latest", registry.trim_end_matches('/'));
+    let latest: Latest = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| SyncError::Other(format!("Failed to query {package}: {e}")))?
+        .json()
+        .await
+        .map_err(|e| SyncError::Other(format!("Invalid registry response for {package}: {e}")))?;
+    Ok(latest.version)
 }
 
 /// 检查命令是否存在
@@ -336,23 +1075,17 @@ async fn run_silent_command_with_timeout(
         timeout
     );
 
-    let cmd_str = cmd.to_string();
-    let args_vec: Vec<String> = args.iter().map(|s| s.to_string()).collect();
-
-    let task = tokio::task::spawn_blocking(move || {
-        let mut command = Command::new(&cmd_str);
-        command.args(&args_vec);
+    let mut command = tokio::process::Command::new(cmd);
+    command.args(args).kill_on_drop(true);
 
-        #[cfg(target_os = "windows")]
-        command.creation_flags(CREATE_NO_WINDOW);
-
-        command.output()
-    });
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
 
     let cmd_display = format!("{cmd} {args:?}");
 
-    let result = match tokio::time::timeout(timeout, task).await {
-        Ok(join_result) => join_result.map_err(|e| SyncError::CommandExecutionFailed {
+    // kill_on_drop: 超时或安装被取消而丢弃 future 时，子进程会被杀掉
+    let output = match tokio::time::timeout(timeout, command.output()).await {
+        Ok(result) => result.map_err(|e| SyncError::CommandExecutionFailed {
             command: cmd_display.clone(),
             reason: e.to_string(),
         })?,
@@ -369,11 +1102,6 @@ async fn run_silent_command_with_timeout(
         }
     };
 
-    let output = result.map_err(|e| SyncError::CommandExecutionFailed {
-        command: cmd_display.clone(),
-        reason: e.to_string(),
-    })?;
-
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         tracing::error!("[auto_installer] Command failed: {}", stderr);
@@ -388,7 +1116,7 @@ async fn run_silent_command_with_timeout(
 
 /// Windows: 下载便携版Git（无需安装权限）
 #[cfg(target_os = "windows")]
-async fn download_portable_git() -> Result<()> {
+async fn download_portable_git(reporter: &ProgressReporter) -> Result<()> {
     use std::fs;
 
     tracing::info!("[auto_installer] Downloading portable Git...");
@@ -403,62 +1131,33 @@ async fn download_portable_git() -> Result<()> {
     })?;
 
     // 下载MinGit（最小化Git）
-    let url = "https://github.com/git-for-windows/git/releases/download/v2.43.0.windows.1/MinGit-2.43.0-64-bit.zip";
-
-    tracing::info!("[auto_installer] Downloading from {}", url);
-
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(300))
-        .build()
-        .map_err(|e| SyncError::Other(e.to_string()))?;
-
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|e| SyncError::Other(format!("Download failed: {}", e)))?;
-
-    // SECURITY: Enforce a 500 MB size limit
-    const MAX_DOWNLOAD_SIZE: u64 = 500 * 1024 * 1024;
-    if let Some(len) = response.content_length() {
-        if len > MAX_DOWNLOAD_SIZE {
-            return Err(SyncError::Other(format!(
-                "Download too large: {} bytes (max {})",
-                len, MAX_DOWNLOAD_SIZE
-            )));
-        }
-    }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| SyncError::Other(format!("Download failed: {}", e)))?;
-
-    if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
-        return Err(SyncError::Other(format!(
-            "Download too large: {} bytes (max {})",
-            bytes.len(),
-            MAX_DOWNLOAD_SIZE
-        )));
+    let url = format!(
+        "{}/{}",
+        reporter.options.mirror.git_for_windows_releases(),
+        MINGIT_ARCHIVE.file
+    );
+    if MINGIT_ARCHIVE.sha256.is_none() {
+        tracing::warn!("[auto_installer] No pinned SHA-256 for MinGit, skipping verification");
     }
 
     let zip_path = git_dir.join("mingit.zip");
-    fs::write(&zip_path, &bytes).map_err(|e| SyncError::FileWriteFailed {
-        path: zip_path.to_string_lossy().to_string(),
-        reason: e.to_string(),
-    })?;
+    download_to_file(
+        &url,
+        &zip_path,
+        MINGIT_ARCHIVE.sha256,
+        Duration::from_secs(300),
+        reporter,
+    )
+    .await?;
 
     // 解压
-    tracing::info!("[auto_installer] Extracting Git...");
-    extract_zip(&zip_path, &git_dir)?;
+    reporter.step("Extracting Git...");
+    let extracted = extract_zip(&zip_path, &git_dir);
+    fs::remove_file(&zip_path).ok();
+    extracted?;
 
-    // 添加到PATH（仅本进程）
-    let git_bin = git_dir.join("cmd");
-    if let Ok(mut path) = std::env::var("PATH") {
-        path.push(';');
-        path.push_str(&git_bin.to_string_lossy());
-        std::env::set_var("PATH", path);
-    }
+    // 添加到PATH（本进程及用户PATH）
+    add_to_path(&git_dir.join("cmd"))?;
 
     tracing::info!("[auto_installer] Portable Git installed successfully");
     Ok(())
@@ -466,7 +1165,7 @@ async fn download_portable_git() -> Result<()> {
 
 #[cfg(not(target_os = "windows"))]
 #[allow(dead_code)]
-async fn download_portable_git() -> Result<()> {
+async fn download_portable_git(_reporter: &ProgressReporter) -> Result<()> {
     Err(SyncError::Other(
         "Portable Git only available on Windows".to_string(),
     ))
@@ -545,74 +1244,199 @@ fn extract_zip(zip_path: &std::path::Path, dest: &std::path::Path) -> Result<()>
 }
 
 /// 安装独立版Node.js（无需包管理器）
-async fn install_nodejs_standalone() -> Result<()> {
+async fn install_nodejs_standalone(reporter: &ProgressReporter) -> Result<()> {
     tracing::info!("[auto_installer] Installing standalone Node.js...");
 
     let home = dirs::home_dir().ok_or(SyncError::HomeDirectoryNotFound)?;
     let node_dir = home.join(".hajimi").join("nodejs");
 
-    #[cfg(target_os = "windows")]
-    let url = "https://nodejs.org/dist/v22.16.0/node-v22.16.0-win-x64.zip";
+    let archive = node_archive(std::env::consts::OS, std::env::consts::ARCH)?;
+    let release = format!("{}/{NODE_VERSION}", reporter.options.mirror.node_dist());
+    let sha256 = match archive.sha256 {
+        Some(sha256) => sha256.to_string(),
+        None => fetch_node_sha256(&release, archive.file).await?,
+    };
 
-    #[cfg(target_os = "macos")]
-    let url = if cfg!(target_arch = "aarch64") {
-        "https://nodejs.org/dist/v22.16.0/node-v22.16.0-darwin-arm64.tar.gz"
+    let url = format!("{release}/{}", archive.file);
+    download_and_extract(&url, &node_dir, Some(&sha256), reporter).await?;
+
+    // 添加到PATH（Windows 的 zip 包没有 bin 目录，且保留了顶层文件夹）
+    let bin_dir = if cfg!(windows) {
+        node_dir.join(archive.file.trim_end_matches(".zip"))
     } else {
-        "https://nodejs.org/dist/v22.16.0/node-v22.16.0-darwin-x64.tar.gz"
+        node_dir.join("bin")
     };
+    add_to_path(&bin_dir)?;
 
-    #[cfg(target_os = "linux")]
-    let url = "https://nodejs.org/dist/v22.16.0/node-v22.16.0-linux-x64.tar.xz";
+    Ok(())
+}
 
-    download_and_extract(url, &node_dir).await?;
+/// 当前平台对应的 Node.js 包
+fn node_archive(os: &str, arch: &str) -> Result<&'static PinnedArchive> {
+    NODE_ARCHIVES
+        .iter()
+        .find(|(o, a, _)| *o == os && *a == arch)
+        .map(|(_, _, archive)| archive)
+        .ok_or_else(|| {
+            SyncError::Other(format!(
+                "No standalone Node.js {NODE_VERSION} build for {os}-{arch}. Please install Node.js manually."
+            ))
+        })
+}
 
-    // 添加到PATH
-    let bin_dir = node_dir.join("bin");
-    add_to_path(&bin_dir)?;
+/// 从该版本的 SHASUMS256.txt 中取出 `file` 的 SHA-256
+async fn fetch_node_sha256(release: &str, file: &str) -> Result<String> {
+    let url = format!("{release}/SHASUMS256.txt");
+    let sums = utils::http_client(Duration::from_secs(30))
+        .map_err(SyncError::Other)?
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| SyncError::Other(format!("Failed to fetch {url}: {e}")))?
+        .text()
+        .await
+        .map_err(|e| SyncError::Other(format!("Failed to fetch {url}: {e}")))?;
+    find_sha256(&sums, file)
+        .ok_or_else(|| SyncError::Other(format!("{file} is not listed in {url}")))
+}
 
-    Ok(())
+/// SHASUMS 格式：每行 `<sha256>  <文件名>`
+fn find_sha256(sums: &str, file: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        (name.trim() == file).then(|| hash.to_string())
+    })
 }
 
 /// Linux: 使用NodeSource安装Node.js
 #[cfg(target_os = "linux")]
-async fn install_nodejs_nodesource() -> Result<()> {
+async fn install_nodejs_nodesource(reporter: &ProgressReporter) -> Result<()> {
     // SECURITY: Instead of piping a remote script to sudo bash, use the standalone
     // binary approach which doesn't require root and doesn't execute remote scripts.
     tracing::info!("[auto_installer] Installing Node.js via standalone binary (no sudo required)");
-    install_nodejs_standalone().await
+    install_nodejs_standalone(reporter).await
 }
 
 #[cfg(not(target_os = "linux"))]
 #[allow(dead_code)]
-async fn install_nodejs_nodesource() -> Result<()> {
+async fn install_nodejs_nodesource(_reporter: &ProgressReporter) -> Result<()> {
     Ok(())
 }
 
 /// 下载并解压文件
-async fn download_and_extract(url: &str, dest: &std::path::Path) -> Result<()> {
+async fn download_and_extract(
+    url: &str,
+    dest: &Path,
+    sha256: Option<&str>,
+    reporter: &ProgressReporter,
+) -> Result<()> {
     use std::fs;
 
-    tracing::info!("[auto_installer] Downloading from {}", url);
-
     fs::create_dir_all(dest).map_err(|e| SyncError::DirectoryCreationFailed {
         path: dest.to_string_lossy().to_string(),
         reason: e.to_string(),
     })?;
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(600))
-        .build()
-        .map_err(|e| SyncError::Other(e.to_string()))?;
+    let temp_file = dest.join("download.tmp");
+    download_to_file(url, &temp_file, sha256, Duration::from_secs(600), reporter).await?;
+
+    reporter.step("Extracting...");
+    let extracted = extract_archive(url, &temp_file, dest);
+    fs::remove_file(&temp_file).ok();
+    extracted
+}
+
+/// 根据文件扩展名解压
+fn extract_archive(url: &str, archive: &Path, dest: &Path) -> Result<()> {
+    if url.ends_with(".zip") {
+        #[cfg(target_os = "windows")]
+        extract_zip(archive, dest)?;
+    } else if url.ends_with(".tar.gz") || url.ends_with(".tar.xz") {
+        extract_tar(archive, dest)?;
+    }
+    Ok(())
+}
+
+/// 流式下载到文件，按 Content-Length 推送下载百分比；给出 `sha256` 时校验，
+/// 失败或不匹配时删除半成品
+async fn download_to_file(
+    url: &str,
+    path: &Path,
+    sha256: Option<&str>,
+    timeout: Duration,
+    reporter: &ProgressReporter,
+) -> Result<()> {
+    tracing::info!("[auto_installer] Downloading from {}", url);
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string());
+    reporter.report(
+        InstallStatus::Downloading,
+        0,
+        format!(
+            "Downloading from {host} (mirror: {})...",
+            reporter.options.mirror.as_str()
+        ),
+    );
+
+    let mut partial = PartialDownload {
+        path: path.to_path_buf(),
+        complete: false,
+    };
+    let actual = stream_to_file(url, path, timeout, reporter).await?;
+    if let Some(expected) = sha256 {
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(SyncError::ChecksumMismatch {
+                file: url.rsplit('/').next().unwrap_or(url).to_string(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+        tracing::info!("[auto_installer] SHA-256 verified for {}", url);
+    }
+    partial.complete = true;
+    Ok(())
+}
+
+/// 未完成的下载文件：下载出错或安装被取消（future 被丢弃）时删除
+struct PartialDownload {
+    path: PathBuf,
+    complete: bool,
+}
+
+impl Drop for PartialDownload {
+    fn drop(&mut self) {
+        if !self.complete {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+/// 返回已写入内容的 SHA-256（小写十六进制）
+async fn stream_to_file(
+    url: &str,
+    path: &Path,
+    timeout: Duration,
+    reporter: &ProgressReporter,
+) -> Result<String> {
+    use futures_util::StreamExt;
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncWriteExt;
+
+    let client = utils::http_client(timeout).map_err(SyncError::Other)?;
 
     let response = client
         .get(url)
         .send()
         .await
+        .and_then(|r| r.error_for_status())
         .map_err(|e| SyncError::Other(format!("Download failed: {e}")))?;
 
     // SECURITY: Enforce a 500 MB size limit to prevent disk exhaustion
-    const MAX_DOWNLOAD_SIZE: u64 = 500 * 1024 * 1024;
-    if let Some(len) = response.content_length() {
+    let total = response.content_length();
+    if let Some(len) = total {
         if len > MAX_DOWNLOAD_SIZE {
             return Err(SyncError::Other(format!(
                 "Download too large: {len} bytes (max {MAX_DOWNLOAD_SIZE})"
@@ -620,36 +1444,42 @@ async fn download_and_extract(url: &str, dest: &std::path::Path) -> Result<()> {
         }
     }
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| SyncError::Other(format!("Download failed: {e}")))?;
-
-    if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
-        return Err(SyncError::Other(format!(
-            "Download too large: {} bytes (max {})",
-            bytes.len(),
-            MAX_DOWNLOAD_SIZE
-        )));
-    }
-
-    let temp_file = dest.join("download.tmp");
-    fs::write(&temp_file, &bytes).map_err(|e| SyncError::FileWriteFailed {
-        path: temp_file.to_string_lossy().to_string(),
+    let write_err = |e: std::io::Error| SyncError::FileWriteFailed {
+        path: path.to_string_lossy().to_string(),
         reason: e.to_string(),
-    })?;
-
-    // 根据文件扩展名解压
-    if url.ends_with(".zip") {
-        #[cfg(target_os = "windows")]
-        extract_zip(&temp_file, dest)?;
-    } else if url.ends_with(".tar.gz") || url.ends_with(".tar.xz") {
-        extract_tar(&temp_file, dest)?;
+    };
+    let mut file = tokio::fs::File::create(path).await.map_err(write_err)?;
+    let mut stream = response.bytes_stream();
+    let mut received: u64 = 0;
+    let mut last_percent = 0;
+    let mut hasher = Sha256::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| SyncError::Other(format!("Download failed: {e}")))?;
+        received += chunk.len() as u64;
+        if received > MAX_DOWNLOAD_SIZE {
+            return Err(SyncError::Other(format!(
+                "Download too large: more than {MAX_DOWNLOAD_SIZE} bytes"
+            )));
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(write_err)?;
+
+        if let Some(total) = total.filter(|&t| t > 0) {
+            let percent = (received.min(total) * 100 / total) as u8;
+            if percent > last_percent {
+                last_percent = percent;
+                reporter.report(
+                    InstallStatus::Downloading,
+                    percent,
+                    format!("Downloading... {percent}%"),
+                );
+            }
+        }
     }
 
-    fs::remove_file(&temp_file).ok();
-
-    Ok(())
+    file.flush().await.map_err(write_err)?;
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 /// 解压tar文件
@@ -692,125 +1522,613 @@ fn extract_tar(_archive_path: &std::path::Path, _dest: &std::path::Path) -> Resu
     Ok(())
 }
 
-/// 添加目录到PATH（仅本进程）
+/// 添加目录到PATH：本进程立即生效，并写入用户PATH供新终端和已配置的CLI使用
 fn add_to_path(dir: &std::path::Path) -> Result<()> {
-    if let Ok(mut path) = std::env::var("PATH") {
-        let separator = if cfg!(windows) { ";" } else { ":" };
-        path.push_str(separator);
-        path.push_str(&dir.to_string_lossy());
-        std::env::set_var("PATH", path);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut entries: Vec<PathBuf> = std::env::split_paths(&path).collect();
+    if !entries.iter().any(|entry| entry == dir) {
+        entries.push(dir.to_path_buf());
+        let joined = std::env::join_paths(entries).map_err(|e| SyncError::Other(e.to_string()))?;
+        std::env::set_var("PATH", joined);
         tracing::info!("[auto_installer] Added to PATH: {:?}", dir);
     }
+
+    // 持久化失败不影响本次安装，本应用仍能通过 resolve_executable 找到工具
+    if let Err(e) = crate::user_path::persist(dir) {
+        tracing::warn!(
+            "[auto_installer] Failed to persist PATH entry {:?}: {}",
+            dir,
+            e
+        );
+    }
     Ok(())
 }
 
 /// Tauri command: 自动安装所有缺失依赖
+///
+/// 各阶段进度通过 `EVENT_INSTALL_PROGRESS` 实时推送，返回值为每个依赖的最终状态。
 #[tauri::command]
-pub async fn auto_install_dependencies() -> std::result::Result<Vec<InstallProgress>, String> {
-    let mut results = Vec::new();
-
-    // 检测并安装Git
-    if !check_command_exists("git") {
-        results.push(InstallProgress {
-            tool: "git".to_string(),
-            status: InstallStatus::Installing,
-            progress: 0,
-            message: "Installing Git...".to_string(),
-        });
+pub async fn auto_install_dependencies(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    jobs: tauri::State<'_, InstallJobs>,
+) -> std::result::Result<Vec<InstallProgress>, String> {
+    let options = InstallOptions::load(&state.db);
+    let results = vec![
+        install_dependency(&app, &jobs, &options, "git", "Git").await?,
+        install_dependency(&app, &jobs, &options, "nodejs", "Node.js").await?,
+    ];
+
+    notify_install_result(&app, &results, ToolAction::Install);
+    Ok(results)
+}
 
-        match auto_install_git().await {
-            Ok(_) => {
-                results.push(InstallProgress {
-                    tool: "git".to_string(),
-                    status: InstallStatus::Completed,
-                    progress: 100,
-                    message: "Git installed successfully".to_string(),
-                });
-            }
-            Err(e) => {
-                tracing::error!("[auto_install] Git installation failed: {}", e);
-                results.push(InstallProgress {
-                    tool: "git".to_string(),
-                    status: InstallStatus::Failed,
-                    progress: 0,
-                    message: format!("Failed: {e}"),
-                });
-            }
+/// 检测单个依赖，缺失时安装
+async fn install_dependency(
+    app: &tauri::AppHandle,
+    jobs: &InstallJobs,
+    options: &InstallOptions,
+    tool: &str,
+    label: &str,
+) -> std::result::Result<InstallProgress, String> {
+    let job = jobs
+        .start(tool)
+        .ok_or_else(|| format!("{label} is already being installed"))?;
+    let reporter = ProgressReporter::new(app, tool, options.clone());
+    reporter.report(
+        InstallStatus::Checking,
+        0,
+        format!("Checking for {label}..."),
+    );
+
+    let command = if tool == "nodejs" { "node" } else { tool };
+    if check_command_exists(command) {
+        return Ok(reporter.report(InstallStatus::Skipped, 100, "Already installed"));
+    }
+
+    reporter.report(
+        InstallStatus::Installing,
+        0,
+        format!("Installing {label}..."),
+    );
+    let result = if tool == "git" {
+        job.run(auto_install_git(&reporter)).await
+    } else {
+        job.run(auto_install_nodejs(&reporter)).await
+    };
+    Ok(finish_install(
+        &reporter,
+        label,
+        ToolAction::Install,
+        result,
+    ))
+}
+
+/// 对单个工具执行的操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ToolAction {
+    Install,
+    Update,
+    Uninstall,
+}
+
+impl ToolAction {
+    fn in_progress(self) -> &'static str {
+        match self {
+            Self::Install => "Installing",
+            Self::Update => "Updating",
+            Self::Uninstall => "Uninstalling",
         }
+    }
+
+    fn done(self) -> &'static str {
+        match self {
+            Self::Install => "Installed",
+            Self::Update => "Updated",
+            Self::Uninstall => "Uninstalled",
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            Self::Install => "Installation",
+            Self::Update => "Update",
+            Self::Uninstall => "Uninstall",
+        }
+    }
+}
+
+/// 推送并返回操作的最终状态
+fn finish_install(
+    reporter: &ProgressReporter,
+    label: &str,
+    action: ToolAction,
+    result: Result<()>,
+) -> InstallProgress {
+    match result {
+        Ok(()) => reporter.report(
+            InstallStatus::Completed,
+            100,
+            format!("{label} {} successfully", action.done().to_lowercase()),
+        ),
+        Err(e) => {
+            tracing::error!("[auto_install] {} {} failed: {}", label, action.noun(), e);
+            let message = match action {
+                ToolAction::Uninstall => format!("Failed: {e}"),
+                _ => format!(
+                    "Failed: {e} (download mirror: {})",
+                    reporter.options.mirror.as_str()
+                ),
+            };
+            reporter.report(InstallStatus::Failed, 0, message)
+        }
+    }
+}
+
+/// 操作结束后发送桌面通知（全部跳过时不打扰）
+fn notify_install_result(app: &tauri::AppHandle, results: &[InstallProgress], action: ToolAction) {
+    let names = |status: InstallStatus| -> Vec<&str> {
+        results
+            .iter()
+            .filter(|r| r.status == status)
+            .map(|r| r.tool.as_str())
+            .collect()
+    };
+    let installed = names(InstallStatus::Completed);
+    let failed = names(InstallStatus::Failed);
+    if installed.is_empty() && failed.is_empty() {
+        return;
+    }
+
+    let mut lines = Vec::new();
+    if !installed.is_empty() {
+        lines.push(format!("{}: {}", action.done(), installed.join(", ")));
+    }
+    if !failed.is_empty() {
+        lines.push(format!("Failed: {}", failed.join(", ")));
+    }
+    let title = if failed.is_empty() {
+        format!("{} finished", action.noun())
     } else {
-        results.push(InstallProgress {
-            tool: "git".to_string(),
-            status: InstallStatus::Skipped,
-            progress: 100,
-            message: "Already installed".to_string(),
-        });
+        format!("{} finished with errors", action.noun())
+    };
+    notify::notify(app, &title, &lines.join("\n"));
+}
+
+/// Tauri command: 安装特定CLI工具
+#[tauri::command]
+pub async fn install_cli_tool(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    jobs: tauri::State<'_, InstallJobs>,
+    tool: String,
+) -> std::result::Result<InstallProgress, String> {
+    let options = InstallOptions::load(&state.db);
+    run_tool_action(&app, &jobs, options, &tool, ToolAction::Install).await
+}
+
+/// Tauri command: 升级CLI工具到最新版
+#[tauri::command]
+pub async fn update_cli_tool(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    jobs: tauri::State<'_, InstallJobs>,
+    tool: String,
+) -> std::result::Result<InstallProgress, String> {
+    let options = InstallOptions::load(&state.db);
+    run_tool_action(&app, &jobs, options, &tool, ToolAction::Update).await
+}
+
+/// Tauri command: 卸载CLI工具
+#[tauri::command]
+pub async fn uninstall_cli_tool(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    jobs: tauri::State<'_, InstallJobs>,
+    tool: String,
+) -> std::result::Result<InstallProgress, String> {
+    let options = InstallOptions::load(&state.db);
+    run_tool_action(&app, &jobs, options, &tool, ToolAction::Uninstall).await
+}
+
+/// 以可取消的任务对工具执行一次操作，推送各阶段进度并返回最终状态
+async fn run_tool_action(
+    app: &tauri::AppHandle,
+    jobs: &InstallJobs,
+    options: InstallOptions,
+    tool: &str,
+    action: ToolAction,
+) -> std::result::Result<InstallProgress, String> {
+    let job = jobs
+        .start(tool)
+        .ok_or_else(|| format!("{tool} is already being installed or updated"))?;
+    let reporter = ProgressReporter::new(app, tool, options);
+
+    if action == ToolAction::Install {
+        reporter.report(
+            InstallStatus::Checking,
+            0,
+            format!("Checking for {tool}..."),
+        );
+        // Use enhanced detection (same as get_all_cli_status) to avoid false negatives
+        if is_installed(tool, &reporter.options) {
+            return Ok(reporter.report(InstallStatus::Skipped, 100, "Already installed"));
+        }
     }
 
-    // 检测并安装Node.js
-    if !check_command_exists("node") {
-        results.push(InstallProgress {
-            tool: "nodejs".to_string(),
-            status: InstallStatus::Installing,
-            progress: 0,
-            message: "Installing Node.js...".to_string(),
-        });
+    reporter.report(
+        InstallStatus::Installing,
+        0,
+        format!("{} {tool}...", action.in_progress()),
+    );
+    let result = match action {
+        ToolAction::Install => job.run(auto_install_cli_tool(tool, &reporter)).await,
+        ToolAction::Update => job.run(update_cli(tool, &reporter)).await,
+        ToolAction::Uninstall => job.run(uninstall_cli(tool, &reporter)).await,
+    };
+    let progress = finish_install(&reporter, tool, action, result);
+    notify_install_result(app, std::slice::from_ref(&progress), action);
+    Ok(progress)
+}
 
-        match auto_install_nodejs().await {
-            Ok(_) => {
-                results.push(InstallProgress {
-                    tool: "nodejs".to_string(),
-                    status: InstallStatus::Completed,
-                    progress: 100,
-                    message: "Node.js installed successfully".to_string(),
-                });
-            }
-            Err(e) => {
-                tracing::error!("[auto_install] Node.js installation failed: {}", e);
-                results.push(InstallProgress {
-                    tool: "nodejs".to_string(),
-                    status: InstallStatus::Failed,
-                    progress: 0,
-                    message: format!("Failed: {e}"),
+/// 工具是否已安装：可执行文件可找到，或 git 仓库类工具已克隆
+fn is_installed(tool: &str, options: &InstallOptions) -> bool {
+    if let Ok(ToolSource::GitRepo(_)) = tool_source(tool) {
+        return options
+            .checkout_dir()
+            .is_ok_and(|dir| dir.join(CHECKOUT_MARKER).is_file());
+    }
+    utils::resolve_executable(tool).is_some() || check_command_exists(tool)
+}
+
+/// Tauri command: 检查已安装的 npm CLI 工具是否有新版本，只返回过时的工具
+#[tauri::command]
+pub async fn check_cli_updates(
+    state: tauri::State<'_, AppState>,
+) -> std::result::Result<Vec<CliUpdate>, String> {
+    let mirror = download_mirror(&state.db);
+    let client = utils::http_client(Duration::from_secs(15))?;
+
+    let mut outdated = Vec::new();
+    for (tool, package) in NPM_PACKAGES {
+        let Some(installed) =
+            utils::resolve_executable(tool).and_then(|path| utils::get_cli_version(&path))
+        else {
+            continue;
+        };
+        if installed == "unknown" {
+            continue;
+        }
+        match fetch_latest_version(&client, mirror.npm_registry(), package).await {
+            Ok(latest) if utils::compare_versions(&latest, &installed).is_gt() => {
+                outdated.push(CliUpdate {
+                    tool: tool.to_string(),
+                    package: package.to_string(),
+                    installed,
+                    latest,
                 });
             }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("[auto_installer] Update check for {} failed: {}", tool, e),
         }
-    } else {
-        results.push(InstallProgress {
-            tool: "nodejs".to_string(),
-            status: InstallStatus::Skipped,
-            progress: 100,
-            message: "Already installed".to_string(),
-        });
     }
-
-    Ok(results)
+    Ok(outdated)
 }
 
-/// Tauri command: 安装特定CLI工具
+/// Tauri command: 取消正在进行的安装，返回是否确有安装被取消
 #[tauri::command]
-pub async fn install_cli_tool(tool: String) -> std::result::Result<InstallProgress, String> {
-    // Use enhanced detection (same as get_all_cli_status) to avoid false negatives
-    if utils::resolve_executable(&tool).is_some() || check_command_exists(&tool) {
-        return Ok(InstallProgress {
-            tool: tool.clone(),
-            status: InstallStatus::Skipped,
-            progress: 100,
-            message: "Already installed".to_string(),
+pub fn cancel_install(
+    jobs: tauri::State<'_, InstallJobs>,
+    tool: String,
+) -> std::result::Result<bool, String> {
+    Ok(jobs.cancel(&tool))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn silent_reporter() -> ProgressReporter {
+        ProgressReporter {
+            app: None,
+            tool: "nodejs".to_string(),
+            options: InstallOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_sillytavern_dir_setting_and_detection() {
+        let db = Database::memory().unwrap();
+        assert_eq!(sillytavern_dir(&db), None);
+        assert!(set_sillytavern_dir(&db, "relative/ST").is_err());
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("ST");
+        set_sillytavern_dir(&db, dir.to_str().unwrap()).unwrap();
+        let options = InstallOptions::load(&db);
+        assert_eq!(options.checkout_dir().unwrap(), dir);
+        assert!(!is_installed("sillytavern", &options));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CHECKOUT_MARKER), "").unwrap();
+        assert!(is_installed("sillytavern", &options));
+
+        set_sillytavern_dir(&db, "").unwrap();
+        assert_eq!(sillytavern_dir(&db), None);
+    }
+
+    #[tokio::test]
+    async fn test_install_from_git_refuses_unrelated_folder() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("notes.txt"), "mine").unwrap();
+        let mut reporter = silent_reporter();
+        reporter.options.sillytavern_dir = Some(tmp.path().to_path_buf());
+
+        let err = install_from_git("https://example.invalid/repo", &reporter)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a SillyTavern folder"));
+        assert!(tmp.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_package_manager_order_setting() {
+        let db = Database::memory().unwrap();
+        assert_eq!(
+            package_manager_order(&db),
+            PackageManager::DEFAULT_ORDER.to_vec()
+        );
+
+        set_package_manager_order(&db, &[PackageManager::Pnpm, PackageManager::Npm]).unwrap();
+        assert_eq!(
+            package_manager_order(&db),
+            vec![PackageManager::Pnpm, PackageManager::Npm]
+        );
+
+        // 无法识别的值回退到默认顺序
+        settings::set(&db, PACKAGE_MANAGERS_KEY, "yarn").unwrap();
+        assert_eq!(
+            package_manager_order(&db),
+            PackageManager::DEFAULT_ORDER.to_vec()
+        );
+
+        assert_eq!(
+            PackageManager::Bun.install_args("openclaw@latest"),
+            vec!["add", "-g", "openclaw@latest"]
+        );
+        assert_eq!(
+            PackageManager::Npm.uninstall_args("openclaw"),
+            vec!["uninstall", "-g", "openclaw", "--silent"]
+        );
+        assert!(detect_package_manager(&[]).is_none());
+    }
+
+    #[test]
+    fn test_download_mirror_setting() {
+        let db = Database::memory().unwrap();
+        assert_eq!(download_mirror(&db), DownloadMirror::Default);
+
+        let china = DownloadMirror::parse("china").unwrap();
+        set_download_mirror(&db, &china).unwrap();
+        assert_eq!(download_mirror(&db), china);
+        assert_eq!(china.npm_registry(), NPMMIRROR_REGISTRY);
+        assert_eq!(china.node_dist(), NPMMIRROR_NODE_DIST);
+
+        let custom = DownloadMirror::parse(" https://npm.corp.example/ ").unwrap();
+        assert_eq!(custom.as_str(), "https://npm.corp.example");
+        assert_eq!(
+            custom.registry_args(),
+            vec!["--registry", "https://npm.corp.example"]
+        );
+        // 自定义 registry 只影响 npm
+        assert_eq!(custom.node_dist(), NODE_DIST);
+        assert!(DownloadMirror::Default.registry_args().is_empty());
+        assert!(DownloadMirror::parse("ftp://mirror").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_streams_to_disk() {
+        let server = MockServer::start().await;
+        let body = vec![7u8; 64 * 1024];
+        Mock::given(method("GET"))
+            .and(path("/node.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+            .mount(&server)
+            .await;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("download.tmp");
+        let url = format!("{}/node.tar.gz", server.uri());
+        let sha256 = {
+            use sha2::{Digest, Sha256};
+            format!("{:x}", Sha256::digest(&body))
+        };
+        download_to_file(
+            &url,
+            &dest,
+            Some(&sha256.to_uppercase()),
+            Duration::from_secs(10),
+            &silent_reporter(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        // 校验和不符时报错并删除文件
+        let err = download_to_file(
+            &url,
+            &dest,
+            Some(&"0".repeat(64)),
+            Duration::from_secs(10),
+            &silent_reporter(),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, SyncError::ChecksumMismatch { ref file, .. } if file == "node.tar.gz")
+        );
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_pinned_node_archives() {
+        let linux = node_archive("linux", "x86_64").unwrap();
+        assert!(linux.file.starts_with(&format!("node-{NODE_VERSION}-")));
+        assert!(node_archive("linux", "riscv64").is_err());
+        for (_, _, archive) in NODE_ARCHIVES {
+            assert!(archive.file.contains(NODE_VERSION));
+            if let Some(sha256) = archive.sha256 {
+                assert_eq!(sha256.len(), 64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_sha256() {
+        let sums =
+            "aaa111  node-v22.16.0-darwin-arm64.tar.gz\nbbb222  node-v22.16.0-linux-x64.tar.xz\n";
+        assert_eq!(
+            find_sha256(sums, "node-v22.16.0-linux-x64.tar.xz").as_deref(),
+            Some("bbb222")
+        );
+        assert_eq!(find_sha256(sums, "node-v22.16.0-linux-x64.tar"), None);
+    }
+
+    #[test]
+    fn test_tool_source() {
+        assert_eq!(
+            tool_source("claude").unwrap(),
+            ToolSource::Npm("@anthropic-ai/claude-code")
+        );
+        assert_eq!(
+            tool_source("cline").unwrap(),
+            ToolSource::VscodeExtension("saoudrizwan.claude-dev")
+        );
+        assert!(tool_source("cursor")
+            .unwrap_err()
+            .to_string()
+            .contains("desktop application"));
+        assert!(matches!(
+            tool_source("opencode").unwrap(),
+            ToolSource::Script(ScriptInstall {
+                brew_formula: Some("opencode"),
+                ..
+            })
+        ));
+        assert!(matches!(
+            tool_source("droid").unwrap(),
+            ToolSource::Script(_)
+        ));
+        assert_eq!(
+            tool_source("sillytavern").unwrap(),
+            ToolSource::GitRepo("https://github.com/SillyTavern/SillyTavern")
+        );
+        assert!(tool_source("rm -rf").is_err());
+    }
+
+    #[test]
+    fn test_homebrew_formula_from_path() {
+        assert_eq!(
+            homebrew_formula_from_path(Path::new(
+                "/opt/homebrew/Cellar/gemini-cli/0.1.9/libexec/bin/gemini"
+            )),
+            Some("gemini-cli".to_string())
+        );
+        assert_eq!(
+            homebrew_formula_from_path(Path::new(
+                "/opt/homebrew/Caskroom/claude-code/1.0.0/claude"
+            )),
+            Some("claude-code".to_string())
+        );
+        assert_eq!(
+            homebrew_formula_from_path(Path::new("/usr/local/lib/node_modules/.bin/codex")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_latest_version() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/@openai/codex/latest"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({"name": "@openai/codex", "version": "0.92.0"}),
+                ),
+            )
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let latest = fetch_latest_version(&client, &server.uri(), "@openai/codex")
+            .await
+            .unwrap();
+        assert_eq!(latest, "0.92.0");
+        assert!(fetch_latest_version(&client, &server.uri(), "missing")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_install_stops_job_and_unregisters() {
+        let jobs = InstallJobs::default();
+        assert!(!jobs.cancel("claude"));
+
+        let job = jobs.start("claude").unwrap();
+        // 同一工具不能并发安装
+        assert!(jobs.start("claude").is_none());
+
+        let install = job.run(async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
         });
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert!(jobs.cancel("claude"));
+        };
+        let (result, ()) = tokio::join!(install, cancel);
+        assert!(matches!(result, Err(SyncError::InstallCancelled { .. })));
+
+        drop(job);
+        assert!(!jobs.cancel("claude"));
+        assert!(jobs.start("claude").is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_dropping_command_kills_child() {
+        let start = std::time::Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_millis(100),
+            run_silent_command("sleep", &["30"]),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
     }
 
-    match auto_install_cli_tool(&tool).await {
-        Ok(_) => Ok(InstallProgress {
-            tool: tool.clone(),
-            status: InstallStatus::Completed,
-            progress: 100,
-            message: format!("{tool} installed successfully"),
-        }),
-        Err(e) => Ok(InstallProgress {
-            tool: tool.clone(),
-            status: InstallStatus::Failed,
-            progress: 0,
-            message: format!("Failed: {e}"),
-        }),
+    #[tokio::test]
+    async fn test_download_to_file_removes_partial_file_on_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("download.tmp");
+        let url = format!("{}/missing.zip", server.uri());
+        let result = download_to_file(
+            &url,
+            &dest,
+            None,
+            Duration::from_secs(10),
+            &silent_reporter(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(!dest.exists());
     }
 }
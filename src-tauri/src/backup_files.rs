@@ -0,0 +1,230 @@
+//! Browse the `.hajimi.bak` files sync leaves next to each config file:
+//! list them across apps, preview one, and prune old timestamped copies.
+//!
+//! Naming follows `utils::create_rotated_backup`: `<file><suffix>` is the
+//! restore point and `<file>.<YYYYmmdd_HHMMSS><suffix>` the rotated copies.
+//! Pruning only touches rotated copies so Restore keeps working.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::CommandError;
+use crate::utils::{BACKUP_SUFFIX, LEGACY_BACKUP_SUFFIX};
+
+/// Largest backup `preview_backup` returns.
+const MAX_PREVIEW_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BackupFile {
+    pub app: String,
+    /// Config file the backup belongs to.
+    pub file: String,
+    pub path: String,
+    /// Unix seconds: from the name for rotated copies, else the file's mtime.
+    pub timestamp: i64,
+    pub size_bytes: u64,
+    /// `false` for the restore point, which pruning never removes.
+    pub rotated: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    pub removed: usize,
+    pub bytes_freed: u64,
+    pub errors: Vec<String>,
+}
+
+/// Unix seconds of a rotated backup's `YYYYmmdd_HHMMSS` stamp (local time).
+fn parse_stamp(stamp: &str) -> Option<i64> {
+    let naive = chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d_%H%M%S").ok()?;
+    naive
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+fn mtime(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Backups of `config` (either suffix) in its directory.
+fn backups_of(app: &str, config: &Path) -> Vec<BackupFile> {
+    let (Some(dir), Some(name)) = (config.parent(), config.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy().to_string();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(stem) = file_name
+            .strip_suffix(BACKUP_SUFFIX)
+            .or_else(|| file_name.strip_suffix(LEGACY_BACKUP_SUFFIX))
+        else {
+            continue;
+        };
+        let stamp = match stem.strip_prefix(name.as_str()) {
+            Some("") => None,
+            Some(rest) => match rest.strip_prefix('.').and_then(parse_stamp) {
+                Some(ts) => Some(ts),
+                // Another file sharing the prefix (`settings.json.local...`)
+                None => continue,
+            },
+            None => continue,
+        };
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        found.push(BackupFile {
+            app: app.to_string(),
+            file: name.clone(),
+            path: entry.path().to_string_lossy().to_string(),
+            timestamp: stamp.unwrap_or_else(|| mtime(&meta)),
+            size_bytes: meta.len(),
+            rotated: stamp.is_some(),
+        });
+    }
+    found
+}
+
+/// Newest timestamped backup of `config`, for when there is no restore point.
+pub(crate) fn latest_rotated(config: &Path) -> Option<PathBuf> {
+    backups_of("", config)
+        .into_iter()
+        .filter(|b| b.rotated)
+        .max_by_key(|b| b.timestamp)
+        .map(|b| PathBuf::from(b.path))
+}
+
+/// Every backup next to the config files of `apps`, newest first.
+fn scan(apps: &[(String, PathBuf)]) -> Vec<BackupFile> {
+    let mut seen = BTreeSet::new();
+    let mut all: Vec<BackupFile> = apps
+        .iter()
+        .flat_map(|(app, config)| backups_of(app, config))
+        // Path overrides can point two apps at the same file
+        .filter(|b| seen.insert(b.path.clone()))
+        .collect();
+    all.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.path.cmp(&b.path)));
+    all
+}
+
+fn managed_configs() -> Vec<(String, PathBuf)> {
+    crate::status_apps()
+        .into_iter()
+        .flat_map(|app| {
+            crate::drift::managed_files(app)
+                .into_iter()
+                .map(move |path| (app.to_string(), path))
+        })
+        .collect()
+}
+
+/// Delete rotated backups stamped before `cutoff` (unix seconds).
+fn prune(backups: &[BackupFile], cutoff: i64) -> PruneReport {
+    let mut report = PruneReport::default();
+    for backup in backups.iter().filter(|b| b.rotated && b.timestamp < cutoff) {
+        match fs::remove_file(&backup.path) {
+            Ok(()) => {
+                report.removed += 1;
+                report.bytes_freed += backup.size_bytes;
+            }
+            Err(e) => report.errors.push(format!("{}: {e}", backup.path)),
+        }
+    }
+    report
+}
+
+#[tauri::command]
+pub async fn list_all_backups() -> Result<Vec<BackupFile>, CommandError> {
+    Ok(scan(&managed_configs()))
+}
+
+/// Content of one backup. Only paths `list_all_backups` reports are
+/// accepted, so this can't be used to read arbitrary files.
+#[tauri::command]
+pub async fn preview_backup(path: String) -> Result<String, CommandError> {
+    let requested = fs::canonicalize(&path)
+        .map_err(|_| CommandError::invalid_input(format!("Backup not found: {path}")))?;
+    let backup = scan(&managed_configs())
+        .into_iter()
+        .find(|b| fs::canonicalize(&b.path).is_ok_and(|p| p == requested))
+        .ok_or_else(|| CommandError::invalid_input(format!("Not a hajimi backup: {path}")))?;
+    if backup.size_bytes > MAX_PREVIEW_BYTES {
+        return Err(CommandError::invalid_input(format!(
+            "Backup is too large to preview ({} bytes)",
+            backup.size_bytes
+        )));
+    }
+    let bytes = fs::read(&backup.path).map_err(|e| {
+        CommandError::new(
+            "READ_FAILED",
+            format!("Failed to read {}: {e}", backup.path),
+        )
+    })?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Remove rotated backups older than `older_than_days`; restore points stay.
+#[tauri::command]
+pub async fn prune_backups(older_than_days: u32) -> Result<PruneReport, CommandError> {
+    let cutoff = chrono::Utc::now().timestamp() - i64::from(older_than_days) * 86_400;
+    let report = prune(&scan(&managed_configs()), cutoff);
+    tracing::info!(
+        "[backup_files] Pruned {} backup(s), {} bytes freed",
+        report.removed,
+        report.bytes_freed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_and_prune() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("settings.json");
+        fs::write(&config, "{}").unwrap();
+        for name in [
+            "settings.json.hajimi.bak",
+            "settings.json.20200101_000000.hajimi.bak",
+            "settings.json.20990101_000000.hajimi.bak",
+            "settings.json.20200102_000000.antigravity.bak",
+            "settings.json.local.hajimi.bak",
+            "other.json.hajimi.bak",
+        ] {
+            fs::write(tmp.path().join(name), "12345").unwrap();
+        }
+
+        let apps = vec![
+            ("claude".to_string(), config.clone()),
+            ("claude".to_string(), config),
+        ];
+        let backups = scan(&apps);
+        assert_eq!(backups.len(), 4);
+        assert!(backups[0].path.ends_with("20990101_000000.hajimi.bak"));
+        assert_eq!(backups.iter().filter(|b| !b.rotated).count(), 1);
+        assert!(backups.iter().all(|b| b.size_bytes == 5));
+
+        let report = prune(&backups, parse_stamp("20210101_000000").unwrap());
+        assert_eq!((report.removed, report.bytes_freed), (2, 10));
+        let left = scan(&apps);
+        assert_eq!(left.len(), 2);
+        assert!(left.iter().any(|b| !b.rotated));
+    }
+}
@@ -0,0 +1,5 @@
+//! Headless companion to the GUI for scripts and CI; see `headless.rs`.
+
+fn main() {
+    std::process::exit(hajimi_cli_sync_lib::headless::run(std::env::args().skip(1)))
+}
@@ -0,0 +1,618 @@
+//! Export/import everything needed to set up a new machine as one zip.
+//!
+//! A bundle holds `manifest.json` (app versions, what's inside), the provider
+//! list as `providers.json`, and the config files of installed apps under
+//! `configs/<app>/<file>`. Config files always contain the synced API key, so
+//! they are only included together with provider keys. Bundles with keys are
+//! marked in the manifest and, given a passphrase, have everything except the
+//! manifest sealed into `payload.enc`.
+//!
+//! On import, entry names are only ever matched against our own list of
+//! config files; they are never joined onto a path.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::crypto;
+use crate::database::dao::providers::{self, ProviderRecord};
+use crate::database::Database;
+use crate::droid_sync;
+use crate::error::CommandError;
+use crate::extra_clients::{self, ExtraClient};
+use crate::openclaw_sync;
+use crate::opencode_sync;
+use crate::store::AppState;
+use crate::utils;
+
+const FORMAT: &str = "hajimi-bundle";
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const PROVIDERS: &str = "providers.json";
+const PAYLOAD: &str = "payload.enc";
+const KEYS_NOTICE: &str = "CONTAINS_API_KEYS.txt";
+const CONFIGS_DIR: &str = "configs/";
+/// Unpacked size limits; real bundles are a few hundred KB at most.
+const MAX_ENTRY_BYTES: u64 = 16 * 1024 * 1024;
+const MAX_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: String,
+    /// Version of this app that wrote the bundle.
+    pub app_version: String,
+    /// Provider API keys and config snapshots are included.
+    pub contains_keys: bool,
+    /// Everything but the manifest is in `payload.enc`, sealed with a passphrase.
+    pub encrypted: bool,
+    pub apps: Vec<BundleApp>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleApp {
+    pub app: String,
+    pub version: Option<String>,
+    /// Config files snapshotted under `configs/<app>/`.
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BundleImportResult {
+    pub providers_imported: usize,
+    /// Imported archived because they have no key (the bundle had none and no
+    /// local copy exists).
+    pub providers_missing_keys: usize,
+    /// `app/file` entries written back.
+    pub configs_restored: Vec<String>,
+    /// Providers and config files that were skipped, one line each.
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProvidersFile {
+    version: u32,
+    providers: Vec<ProviderRecord>,
+}
+
+/// Config files of `app` that its module can write back, as (file name, path).
+fn config_files(app: &str) -> Vec<(String, PathBuf)> {
+    let single = |name: &str, path: Option<PathBuf>| {
+        path.map(|p| (name.to_string(), p)).into_iter().collect()
+    };
+    match app {
+        "claude" | "codex" | "gemini" => crate::get_cli_app(app)
+            .map(|cli| {
+                cli.config_files()
+                    .into_iter()
+                    .map(|f| (f.name, f.path))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "opencode" => single("opencode.json", opencode_sync::get_config_path()),
+        "openclaw" => single("openclaw.json", openclaw_sync::get_config_path()),
+        "droid" => single("settings.json", droid_sync::get_config_path()),
+        other => match ExtraClient::from_str(other) {
+            // Same file as the Claude CLI
+            Some(ExtraClient::ClaudeVSCode) | None => Vec::new(),
+            Some(client) => extra_clients::config_path_for(&client)
+                .and_then(|p| {
+                    let name = p.file_name()?.to_string_lossy().to_string();
+                    Some((name, p))
+                })
+                .into_iter()
+                .collect(),
+        },
+    }
+}
+
+fn providers_json(db: &Database, include_keys: bool) -> Result<Vec<u8>, String> {
+    let mut list = providers::get_all(db, true)?;
+    for p in &mut list {
+        if !include_keys {
+            p.api_key.clear();
+        }
+        // Saved accounts don't travel with the bundle
+        p.account_id = None;
+        p.account_base_url = None;
+        p.token_id = None;
+    }
+    serde_json::to_vec_pretty(&ProvidersFile {
+        version: 1,
+        providers: list,
+    })
+    .map_err(|e| format!("Failed to serialize providers: {e}"))
+}
+
+fn zip_entries(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, bytes) in entries {
+        zip.start_file(name.as_str(), options)
+            .and_then(|()| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {name} to bundle: {e}"))?;
+    }
+    zip.finish()
+        .map(Cursor::into_inner)
+        .map_err(|e| format!("Failed to finish bundle: {e}"))
+}
+
+fn unzip_entries(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, CommandError> {
+    let invalid = |e: zip::result::ZipError| {
+        CommandError::new("BUNDLE_INVALID", format!("Not a valid bundle: {e}"))
+    };
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+    let mut entries = BTreeMap::new();
+    let mut total = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(invalid)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let limit = MAX_ENTRY_BYTES.min(MAX_TOTAL_BYTES - total);
+        let too_large = || {
+            CommandError::new(
+                "BUNDLE_INVALID",
+                format!("Not a valid bundle: {name} is too large"),
+            )
+        };
+        // The header size can lie, so cap the read as well
+        if file.size() > limit {
+            return Err(too_large());
+        }
+        let mut buf = Vec::new();
+        file.take(limit + 1)
+            .read_to_end(&mut buf)
+            .map_err(|e| CommandError::new("BUNDLE_INVALID", format!("Not a valid bundle: {e}")))?;
+        if buf.len() as u64 > limit {
+            return Err(too_large());
+        }
+        total += buf.len() as u64;
+        entries.insert(name, buf);
+    }
+    Ok(entries)
+}
+
+/// Assemble the zip. `payload` is everything but the manifest.
+fn build_bundle(
+    manifest: &BundleManifest,
+    mut payload: Vec<(String, Vec<u8>)>,
+    passphrase: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let manifest_json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {e}"))?;
+    let mut entries = vec![(MANIFEST.to_string(), manifest_json)];
+    match passphrase {
+        Some(passphrase) => {
+            let sealed = crypto::seal_with_passphrase(passphrase, &zip_entries(&payload)?)?;
+            entries.push((PAYLOAD.to_string(), sealed));
+        }
+        None => {
+            if manifest.contains_keys {
+                payload.push((
+                    KEYS_NOTICE.to_string(),
+                    b"This bundle contains API keys in plain text. Keep it private.\n".to_vec(),
+                ));
+            }
+            entries.extend(payload);
+        }
+    }
+    zip_entries(&entries)
+}
+
+/// Manifest plus the (decrypted) payload entries.
+fn read_bundle(
+    bytes: &[u8],
+    passphrase: Option<&str>,
+) -> Result<(BundleManifest, BTreeMap<String, Vec<u8>>), CommandError> {
+    let mut entries = unzip_entries(bytes)?;
+    let manifest: BundleManifest = entries
+        .remove(MANIFEST)
+        .and_then(|m| serde_json::from_slice(&m).ok())
+        .filter(|m: &BundleManifest| m.format == FORMAT)
+        .ok_or_else(|| CommandError::new("BUNDLE_INVALID", "Not a Hajimi configuration bundle"))?;
+    if manifest.version > FORMAT_VERSION {
+        return Err(CommandError::new(
+            "BUNDLE_INVALID",
+            format!(
+                "This bundle was made by a newer version ({}); update the app first",
+                manifest.app_version
+            ),
+        ));
+    }
+    if !manifest.encrypted {
+        return Ok((manifest, entries));
+    }
+
+    let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or_else(|| {
+        CommandError::new(
+            "PASSPHRASE_REQUIRED",
+            "This bundle is encrypted; enter its passphrase",
+        )
+    })?;
+    let sealed = entries
+        .get(PAYLOAD)
+        .ok_or_else(|| CommandError::new("BUNDLE_INVALID", "Encrypted bundle has no payload"))?;
+    let inner = crypto::open_with_passphrase(passphrase, sealed)
+        .map_err(|_| CommandError::new("WRONG_PASSPHRASE", "Wrong passphrase for this bundle"))?;
+    Ok((manifest, unzip_entries(&inner)?))
+}
+
+/// Upsert the bundle's providers. A provider without a key keeps the local key
+/// if one exists, or is imported archived otherwise; invalid ones are skipped
+/// and listed in `errors`. The bundle's current provider becomes current only
+/// when nothing is current here yet.
+fn restore_providers(
+    db: &Database,
+    bytes: &[u8],
+    result: &mut BundleImportResult,
+) -> Result<(), String> {
+    let file: ProvidersFile =
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid providers.json: {e}"))?;
+    let existing = providers::get_all(db, true)?;
+    let mut bundle_current = None;
+    for mut provider in file.providers {
+        let local = existing.iter().find(|p| p.id == provider.id);
+        let mut missing_key = false;
+        if provider.api_key.is_empty() {
+            match local {
+                Some(local) => provider.api_key = local.api_key.clone(),
+                None => {
+                    // Can't be switched to until the user adds a key
+                    provider.archived = true;
+                    missing_key = true;
+                }
+            }
+        }
+        // Saved accounts don't travel with the bundle; keep the local links
+        provider.account_id = local.and_then(|l| l.account_id);
+        provider.account_base_url = local.and_then(|l| l.account_base_url.clone());
+        provider.token_id = local.and_then(|l| l.token_id);
+        // Switched below, once we know nothing here is current
+        let was_current = std::mem::take(&mut provider.is_current);
+        // Saving keeps an existing row's archive state
+        let archived = provider.archived || local.is_some_and(|l| l.archived);
+
+        let outcome = if missing_key {
+            crate::validate_keyless_provider(&provider)
+        } else {
+            crate::validate_provider(&provider)
+        }
+        .map_err(|e| e.message)
+        .and_then(|()| providers::save(db, &provider));
+        if let Err(e) = outcome {
+            tracing::warn!("[bundle] Skipped provider {}: {}", provider.id, e);
+            result.errors.push(format!("{}: {e}", provider.name));
+            continue;
+        }
+        if was_current && !archived {
+            bundle_current = Some(provider.id.clone());
+        }
+        if missing_key {
+            result.providers_missing_keys += 1;
+        }
+        result.providers_imported += 1;
+    }
+    if let Some(id) = bundle_current {
+        if providers::get_current(db)?.is_none() {
+            providers::set_current(db, &id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Back up and rewrite each config snapshot through its app's writer.
+fn restore_configs(entries: &BTreeMap<String, Vec<u8>>, result: &mut BundleImportResult) {
+    for app in crate::status_apps() {
+        for (name, path) in config_files(app) {
+            let Some(bytes) = entries.get(&format!("{CONFIGS_DIR}{app}/{name}")) else {
+                continue;
+            };
+            let label = format!("{app}/{name}");
+            let outcome = String::from_utf8(bytes.clone())
+                .map_err(|_| "not UTF-8 text".to_string())
+                .and_then(|content| {
+                    utils::create_rotated_backup(&path, utils::BACKUP_SUFFIX)
+                        .map_err(|e| e.to_string())?;
+                    crate::write_app_config(app, &name, &content)
+                });
+            match outcome {
+                Ok(()) => result.configs_restored.push(label),
+                Err(e) => {
+                    tracing::warn!("[bundle] Failed to restore {}: {}", label, e);
+                    result.errors.push(format!("{label}: {e}"));
+                }
+            }
+        }
+    }
+}
+
+/// Write a bundle to `dest_path`. Config snapshots are only included with
+/// `include_keys`; a non-empty `passphrase` encrypts the bundle.
+#[tauri::command]
+pub async fn export_bundle(
+    state: State<'_, AppState>,
+    dest_path: String,
+    include_keys: bool,
+    passphrase: Option<String>,
+) -> Result<BundleManifest, CommandError> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    if passphrase.is_some() && !include_keys {
+        return Err(CommandError::invalid_input(
+            "A passphrase is only used for bundles that include API keys",
+        ));
+    }
+
+    let mut payload = vec![(
+        PROVIDERS.to_string(),
+        providers_json(&state.db, include_keys)?,
+    )];
+    let mut apps = Vec::new();
+    for app in crate::status_apps() {
+        let Some(status) = crate::app_status(&state.db, None, app, None, None) else {
+            continue;
+        };
+        if !status.installed {
+            continue;
+        }
+        let mut files = Vec::new();
+        if include_keys {
+            for (name, path) in config_files(app) {
+                match std::fs::read(&path) {
+                    Ok(bytes) => {
+                        payload.push((format!("{CONFIGS_DIR}{app}/{name}"), bytes));
+                        files.push(name);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(format!("Failed to read {}: {e}", path.display()).into()),
+                }
+            }
+        }
+        apps.push(BundleApp {
+            app: app.to_string(),
+            version: status.version,
+            files,
+        });
+    }
+
+    let manifest = BundleManifest {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        contains_keys: include_keys,
+        encrypted: passphrase.is_some(),
+        apps,
+    };
+    let bytes = build_bundle(&manifest, payload, passphrase.as_deref())?;
+    crypto::write_private(std::path::Path::new(&dest_path), &bytes)
+        .map_err(|e| format!("Failed to write {dest_path}: {e}"))?;
+    tracing::info!(
+        "[bundle] Exported {} apps (keys: {}, encrypted: {})",
+        manifest.apps.len(),
+        manifest.contains_keys,
+        manifest.encrypted
+    );
+    Ok(manifest)
+}
+
+/// Restore providers from a bundle and, with `apply_configs`, write its config
+/// snapshots back (each file is backed up first).
+#[tauri::command]
+pub async fn import_bundle(
+    state: State<'_, AppState>,
+    src_path: String,
+    apply_configs: bool,
+    passphrase: Option<String>,
+) -> Result<BundleImportResult, CommandError> {
+    let size = std::fs::metadata(&src_path)
+        .map_err(|e| format!("Failed to read {src_path}: {e}"))?
+        .len();
+    if size > MAX_TOTAL_BYTES {
+        return Err(CommandError::new(
+            "BUNDLE_INVALID",
+            "Not a valid bundle: file is too large",
+        ));
+    }
+    let bytes = std::fs::read(&src_path).map_err(|e| format!("Failed to read {src_path}: {e}"))?;
+    let (manifest, entries) = read_bundle(&bytes, passphrase.as_deref())?;
+
+    let mut result = BundleImportResult::default();
+    let providers = entries
+        .get(PROVIDERS)
+        .ok_or_else(|| CommandError::new("BUNDLE_INVALID", "Bundle has no providers.json"))?;
+    restore_providers(&state.db, providers, &mut result)?;
+
+    if apply_configs {
+        let _lock = state.sync_lock.acquire().await?;
+        restore_configs(&entries, &mut result);
+    }
+    tracing::info!(
+        "[bundle] Imported {} providers and {} config files from a bundle made {} ({} skipped)",
+        result.providers_imported,
+        result.configs_restored.len(),
+        manifest.created_at,
+        result.errors.len()
+    );
+    // Whatever was imported stays imported; the caller reports `errors`
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(id: &str, key: &str, current: bool) -> ProviderRecord {
+        ProviderRecord {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: "https://api.example.com".to_string(),
+            api_key: key.to_string(),
+            default_model: String::new(),
+            per_cli_models: "{}".to_string(),
+            is_current: current,
+            sort_index: None,
+            notes: None,
+            created_at: 0,
+            opencode_adapter: String::new(),
+            account_id: None,
+            model_aliases: "{}".to_string(),
+            account_base_url: None,
+            token_id: None,
+            quota: None,
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+
+    fn manifest(contains_keys: bool, encrypted: bool) -> BundleManifest {
+        BundleManifest {
+            format: FORMAT.to_string(),
+            version: FORMAT_VERSION,
+            created_at: String::new(),
+            app_version: "0.0.0".to_string(),
+            contains_keys,
+            encrypted,
+            apps: Vec::new(),
+        }
+    }
+
+    fn payload() -> Vec<(String, Vec<u8>)> {
+        vec![
+            (PROVIDERS.to_string(), b"{}".to_vec()),
+            ("configs/codex/auth.json".to_string(), b"{\"k\":1}".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn test_plain_bundle_roundtrip_marks_keys() {
+        let bytes = build_bundle(&manifest(true, false), payload(), None).unwrap();
+        let (m, entries) = read_bundle(&bytes, None).unwrap();
+        assert!(m.contains_keys);
+        assert_eq!(entries["configs/codex/auth.json"], b"{\"k\":1}");
+        assert!(entries.contains_key(KEYS_NOTICE));
+    }
+
+    #[test]
+    fn test_encrypted_bundle_needs_passphrase() {
+        let bytes = build_bundle(&manifest(true, true), payload(), Some("pw")).unwrap();
+        // Only the manifest is readable without the passphrase
+        let outer = unzip_entries(&bytes).unwrap();
+        assert_eq!(outer.keys().collect::<Vec<_>>(), vec![MANIFEST, PAYLOAD]);
+
+        assert_eq!(
+            read_bundle(&bytes, None).unwrap_err().code,
+            "PASSPHRASE_REQUIRED"
+        );
+        assert_eq!(
+            read_bundle(&bytes, Some("nope")).unwrap_err().code,
+            "WRONG_PASSPHRASE"
+        );
+        let (_, entries) = read_bundle(&bytes, Some("pw")).unwrap();
+        assert_eq!(entries[PROVIDERS], b"{}");
+    }
+
+    #[test]
+    fn test_rejects_foreign_zip() {
+        let bytes = zip_entries(&[("readme.txt".to_string(), b"hi".to_vec())]).unwrap();
+        assert_eq!(
+            read_bundle(&bytes, None).unwrap_err().code,
+            "BUNDLE_INVALID"
+        );
+        assert_eq!(
+            read_bundle(b"not a zip", None).unwrap_err().code,
+            "BUNDLE_INVALID"
+        );
+    }
+
+    #[test]
+    fn test_providers_export_strips_keys_and_restores() {
+        let source = Database::memory().unwrap();
+        providers::save(&source, &provider("a", "sk-a", false)).unwrap();
+        providers::save(&source, &provider("b", "sk-b", false)).unwrap();
+        providers::set_current(&source, "b").unwrap();
+        let keyless = providers_json(&source, false).unwrap();
+        assert!(!String::from_utf8_lossy(&keyless).contains("sk-"));
+
+        // Target already knows "a", so its local key and account link are kept;
+        // "b" has no key anywhere, so it lands in the archive
+        let target = Database::memory().unwrap();
+        let mut local = provider("a", "sk-local", false);
+        local.account_id = Some(3);
+        local.token_id = Some(9);
+        providers::save(&target, &local).unwrap();
+        let mut result = BundleImportResult::default();
+        restore_providers(&target, &keyless, &mut result).unwrap();
+        assert_eq!(result.providers_imported, 2);
+        assert_eq!(result.providers_missing_keys, 1);
+        assert!(result.errors.is_empty());
+
+        let all = providers::get_all(&target, true).unwrap();
+        let a = all.iter().find(|p| p.id == "a").unwrap();
+        assert_eq!(a.api_key, "sk-local");
+        assert_eq!((a.account_id, a.token_id), (Some(3), Some(9)));
+        assert!(all.iter().find(|p| p.id == "b").unwrap().archived);
+        assert!(providers::get_current(&target).unwrap().is_none());
+
+        // With keys, the bundle's current provider becomes current here
+        let mut result = BundleImportResult::default();
+        let with_keys = providers_json(&source, true).unwrap();
+        restore_providers(&target, &with_keys, &mut result).unwrap();
+        assert_eq!(result.providers_missing_keys, 0);
+        providers::restore(&target, "b").unwrap();
+        restore_providers(&target, &with_keys, &mut result).unwrap();
+        assert_eq!(providers::get_current(&target).unwrap().unwrap().id, "b");
+    }
+
+    #[test]
+    fn test_restore_skips_invalid_providers() {
+        let mut bad_url = provider("a", "sk-a", false);
+        bad_url.url = "file:///etc/passwd".to_string();
+        let mut bad_ref = provider("b", "op://vault", false);
+        bad_ref.name = "Broken".to_string();
+        let bytes = serde_json::to_vec(&ProvidersFile {
+            version: 1,
+            providers: vec![bad_url, bad_ref, provider("c", "sk-c", false)],
+        })
+        .unwrap();
+
+        let db = Database::memory().unwrap();
+        let mut result = BundleImportResult::default();
+        restore_providers(&db, &bytes, &mut result).unwrap();
+        assert_eq!(result.providers_imported, 1);
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.errors[1].starts_with("Broken: "));
+        assert_eq!(ids(&db), ["c"]);
+    }
+
+    fn ids(db: &Database) -> Vec<String> {
+        providers::get_all(db, true)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_unzip_rejects_oversized_entries() {
+        let big = vec![0u8; MAX_ENTRY_BYTES as usize + 1];
+        let bytes = zip_entries(&[(PROVIDERS.to_string(), big)]).unwrap();
+        let err = unzip_entries(&bytes).unwrap_err();
+        assert_eq!(err.code, "BUNDLE_INVALID");
+        assert!(err.message.contains("too large"));
+
+        let fits = vec![0u8; MAX_ENTRY_BYTES as usize];
+        let bytes = zip_entries(&[(PROVIDERS.to_string(), fits)]).unwrap();
+        assert_eq!(
+            unzip_entries(&bytes).unwrap()[PROVIDERS].len() as u64,
+            MAX_ENTRY_BYTES
+        );
+    }
+}
@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::utils;
-
-static GEMINI_BASE_URL_RE: std::sync::LazyLock<regex::Regex> =
-    std::sync::LazyLock::new(|| regex::Regex::new(r#"(?m)^GOOGLE_GEMINI_BASE_URL=(.*)$"#).unwrap());
+use crate::database::{dao::settings, Database};
+use crate::file_status::{FileStatus, SyncStatus};
+use crate::provider_advanced::ProviderAdvanced;
+use crate::provider_import::DetectedProvider;
+use crate::{config_paths, paths, utils, wsl};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum CliApp {
@@ -21,7 +23,7 @@ pub struct CliConfigFile {
     pub path: PathBuf,
 }
 
-use crate::utils::BACKUP_SUFFIX;
+use crate::utils::{EnvFile, BACKUP_SUFFIX};
 
 impl CliApp {
     pub fn as_str(&self) -> &'static str {
@@ -32,49 +34,73 @@ impl CliApp {
         }
     }
 
+    /// Env var each CLI itself honours for relocating its config dir.
+    fn config_dir_env(&self) -> Option<&'static str> {
+        match self {
+            CliApp::Claude => Some("CLAUDE_CONFIG_DIR"),
+            CliApp::Codex => Some("CODEX_HOME"),
+            CliApp::Gemini => None,
+        }
+    }
+
     pub fn config_files(&self) -> Vec<CliConfigFile> {
-        let home = match dirs::home_dir() {
+        let home = match paths::cli_home_dir() {
             Some(p) => p,
             None => {
                 tracing::warn!("[cli_sync] Could not determine home directory");
                 return vec![];
             }
         };
+        let custom_dir = config_paths::custom_location(self.as_str(), self.config_dir_env());
         match self {
-            CliApp::Claude => vec![
-                CliConfigFile {
-                    name: ".claude.json".to_string(),
-                    path: home.join(".claude.json"),
-                },
-                CliConfigFile {
-                    name: "settings.json".to_string(),
-                    path: home.join(".claude").join("settings.json"),
-                },
-            ],
-            CliApp::Codex => vec![
-                CliConfigFile {
-                    name: "auth.json".to_string(),
-                    path: home.join(".codex").join("auth.json"),
-                },
-                CliConfigFile {
-                    name: "config.toml".to_string(),
-                    path: home.join(".codex").join("config.toml"),
-                },
-            ],
-            CliApp::Gemini => vec![
-                CliConfigFile {
-                    name: ".env".to_string(),
-                    path: home.join(".gemini").join(".env"),
-                },
-                CliConfigFile {
-                    name: "settings.json".to_string(),
-                    path: home.join(".gemini").join("settings.json"),
-                },
-                CliConfigFile {
-                    name: "config.json".to_string(),
-                    path: home.join(".gemini").join("config.json"),
-                },
-            ],
+            CliApp::Claude => {
+                // With a custom config dir Claude keeps .claude.json inside it
+                // instead of at the home root.
+                let (claude_json, dir) = match custom_dir {
+                    Some(dir) => (dir.join(".claude.json"), dir),
+                    None => (home.join(".claude.json"), home.join(".claude")),
+                };
+                vec![
+                    CliConfigFile {
+                        name: ".claude.json".to_string(),
+                        path: claude_json,
+                    },
+                    CliConfigFile {
+                        name: "settings.json".to_string(),
+                        path: dir.join("settings.json"),
+                    },
+                ]
+            }
+            CliApp::Codex => {
+                let dir = custom_dir.unwrap_or_else(|| home.join(".codex"));
+                vec![
+                    CliConfigFile {
+                        name: "auth.json".to_string(),
+                        path: dir.join("auth.json"),
+                    },
+                    CliConfigFile {
+                        name: "config.toml".to_string(),
+                        path: dir.join("config.toml"),
+                    },
+                ]
+            }
+            CliApp::Gemini => {
+                let dir = custom_dir.unwrap_or_else(|| home.join(".gemini"));
+                vec![
+                    CliConfigFile {
+                        name: ".env".to_string(),
+                        path: dir.join(".env"),
+                    },
+                    CliConfigFile {
+                        name: "settings.json".to_string(),
+                        path: dir.join("settings.json"),
+                    },
+                    CliConfigFile {
+                        name: "config.json".to_string(),
+                        path: dir.join("config.json"),
+                    },
+                ]
+            }
         }
     }
 
@@ -92,142 +118,492 @@ impl CliApp {
 pub fn check_cli_installed(app: &CliApp) -> (bool, Option<String>) {
     let name = app.as_str();
 
-    match utils::resolve_executable(name) {
+    match wsl::resolve_cli(name) {
         Some(path) => {
-            let version = utils::get_cli_version(&path);
+            let version = wsl::cli_version(&path);
             (true, version)
         }
         None => (false, None),
     }
 }
 
-/// Read current config and check sync status
-pub fn get_sync_status(app: &CliApp, proxy_url: &str) -> (bool, bool, Option<String>) {
-    let files = app.config_files();
-    if files.is_empty() {
-        return (false, false, None);
-    }
+const CODEX_PROFILE_MODE_KEY: &str = "codex_profile_mode";
+/// Profile and provider table written in Codex profile mode.
+pub const CODEX_PROFILE: &str = "hajimi";
 
-    let mut all_synced = true;
-    let mut has_backup = false;
-    let mut current_base_url = None;
+/// Codex "profile mode": write `[profiles.hajimi]` and
+/// `[model_providers.hajimi]` and leave the root `model` / `model_provider`
+/// alone. Cached like the path overrides so sync code needs no database.
+static CODEX_PROFILE_MODE: AtomicBool = AtomicBool::new(false);
 
-    for file in &files {
-        let backup_path = file
-            .path
-            .with_file_name(format!("{}{}", file.name, BACKUP_SUFFIX));
+/// Load the persisted profile mode (called once at startup).
+pub fn load_codex_profile_mode(db: &Database) {
+    let on = settings::get_bool(db, CODEX_PROFILE_MODE_KEY, false);
+    CODEX_PROFILE_MODE.store(on, Ordering::Relaxed);
+}
 
-        if backup_path.exists() {
-            has_backup = true;
-        }
+pub fn codex_profile_mode() -> bool {
+    CODEX_PROFILE_MODE.load(Ordering::Relaxed)
+}
 
-        if !file.path.exists() {
-            // .claude.json and Gemini's optional files are not required for synced status.
-            // Only settings.json (Claude) / config.toml (Codex) / .env (Gemini) are mandatory.
-            if app == &CliApp::Claude && file.name == ".claude.json" {
-                continue;
+pub fn set_codex_profile_mode(db: &Database, on: bool) -> Result<(), String> {
+    settings::set_bool(db, CODEX_PROFILE_MODE_KEY, on)?;
+    CODEX_PROFILE_MODE.store(on, Ordering::Relaxed);
+    Ok(())
+}
+
+const GEMINI_FORCE_AUTH_KEY: &str = "gemini_force_auth_type";
+/// The Gemini auth type that makes the CLI use `GEMINI_API_KEY` and our base URL.
+const GEMINI_API_KEY_AUTH: &str = "gemini-api-key";
+
+/// Overwrite an existing Gemini `security.auth.selectedType` (e.g. Vertex or
+/// Cloud Shell auth) with `gemini-api-key`. Off: only set it when missing.
+static GEMINI_FORCE_AUTH: AtomicBool = AtomicBool::new(false);
+
+/// Load the persisted Gemini auth override (called once at startup).
+pub fn load_gemini_force_auth(db: &Database) {
+    let on = settings::get_bool(db, GEMINI_FORCE_AUTH_KEY, false);
+    GEMINI_FORCE_AUTH.store(on, Ordering::Relaxed);
+}
+
+pub fn gemini_force_auth() -> bool {
+    GEMINI_FORCE_AUTH.load(Ordering::Relaxed)
+}
+
+pub fn set_gemini_force_auth(db: &Database, on: bool) -> Result<(), String> {
+    settings::set_bool(db, GEMINI_FORCE_AUTH_KEY, on)?;
+    GEMINI_FORCE_AUTH.store(on, Ordering::Relaxed);
+    Ok(())
+}
+
+/// What the user still has to do after a successful sync, if anything.
+fn sync_notice(app: &CliApp) -> Option<String> {
+    (app == &CliApp::Codex && codex_profile_mode()).then(|| {
+        format!("Codex profile mode: run `codex --profile {CODEX_PROFILE}` to use this provider")
+    })
+}
+
+/// Claude's shared project file.
+pub const CLAUDE_PROJECT_SETTINGS: &str = "settings.json";
+/// Claude's per-developer project file; gitignored, and wins over the shared one.
+pub const CLAUDE_PROJECT_LOCAL_SETTINGS: &str = "settings.local.json";
+
+/// Claude env 中由 provider 高级选项控制的变量：有值时写入；没有值时只撤销我们写入的
+const CLAUDE_ADVANCED_ENV: [&str; 3] = [
+    "ANTHROPIC_CUSTOM_HEADERS",
+    "ANTHROPIC_SMALL_FAST_MODEL",
+    "API_TIMEOUT_MS",
+];
+
+/// Claude settings 旁的记录文件：`{变量: {"written": 写入值, "previous": 原值或 null}}`，
+/// 与 Gemini 的 `.hajimi-auth` 一样，让 sync/restore 只撤销我们自己的改动
+fn env_note_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{name}.hajimi-env"))
+}
+
+fn read_env_note(path: &Path) -> serde_json::Map<String, Value> {
+    fs::read_to_string(env_note_path(path))
+        .ok()
+        .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+fn write_env_note(path: &Path, note: &serde_json::Map<String, Value>) -> Result<(), String> {
+    let note_path = env_note_path(path);
+    if note.is_empty() {
+        return match fs::remove_file(&note_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove {note_path:?}: {e}"))
             }
-            if app == &CliApp::Gemini
-                && (file.name == "settings.json" || file.name == "config.json")
-            {
-                continue;
+            _ => Ok(()),
+        };
+    }
+    let content = Value::Object(note.clone()).to_string();
+    utils::atomic_write(&note_path, &content).map_err(|e| e.to_string())
+}
+
+/// 撤销一个我们写入的变量：值仍是我们写的才放回原值（或删除），用户改过的保留
+fn revert_env_key(env: &mut serde_json::Map<String, Value>, key: &str, entry: &Value) {
+    if env.get(key) != entry.get("written") {
+        return;
+    }
+    match entry.get("previous").filter(|p| !p.is_null()) {
+        Some(previous) => env.insert(key.to_string(), previous.clone()),
+        None => env.remove(key),
+    };
+}
+
+fn apply_claude_advanced(
+    path: &Path,
+    env: &mut serde_json::Map<String, Value>,
+    advanced: &ProviderAdvanced,
+) -> Result<(), String> {
+    let values = [
+        advanced.custom_headers(),
+        advanced.small_fast_model.clone(),
+        advanced.api_timeout_ms.map(|ms| ms.to_string()),
+    ];
+    let mut note = read_env_note(path);
+    for (key, value) in CLAUDE_ADVANCED_ENV.into_iter().zip(values) {
+        match value {
+            Some(value) => {
+                let value = Value::String(value);
+                // 已有记录时保留最初的原值
+                let previous = match note.get(key) {
+                    Some(entry) => entry.get("previous").cloned().unwrap_or(Value::Null),
+                    None => env.get(key).cloned().unwrap_or(Value::Null),
+                };
+                note.insert(
+                    key.to_string(),
+                    serde_json::json!({ "written": value, "previous": previous }),
+                );
+                env.insert(key.to_string(), value);
+            }
+            None => {
+                if let Some(entry) = note.remove(key) {
+                    revert_env_key(env, key, &entry);
+                }
             }
-            all_synced = false;
-            continue;
         }
+    }
+    write_env_note(path, &note)
+}
 
-        let content = match fs::read_to_string(&file.path) {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!("[cli_sync] Failed to read {:?}: {}", file.path, e);
-                all_synced = false;
-                continue;
+/// restore 时撤销 `apply_claude_advanced` 写入的变量并删除记录文件
+fn revert_claude_advanced(path: &Path) -> Result<(), String> {
+    let note = read_env_note(path);
+    if note.is_empty() {
+        return Ok(());
+    }
+    if let Ok(content) = fs::read_to_string(path) {
+        let mut json: Value = serde_json::from_str(&content).unwrap_or_default();
+        if let Some(env) = json.get_mut("env").and_then(|e| e.as_object_mut()) {
+            for (key, entry) in &note {
+                revert_env_key(env, key, entry);
+            }
+            utils::atomic_write(path, &utils::to_json_like(&json, &content)?)
+                .map_err(|e| format!("Failed to restore Claude env: {e}"))?;
+        }
+    }
+    write_env_note(path, &Default::default())
+}
+
+/// Both Claude settings files in `<project_dir>/.claude`, the one that takes
+/// precedence last.
+pub fn claude_project_files(project_dir: &Path) -> Vec<CliConfigFile> {
+    let dir = project_dir.join(".claude");
+    [CLAUDE_PROJECT_SETTINGS, CLAUDE_PROJECT_LOCAL_SETTINGS]
+        .into_iter()
+        .map(|name| CliConfigFile {
+            name: name.to_string(),
+            path: dir.join(name),
+        })
+        .collect()
+}
+
+fn is_claude_settings(name: &str) -> bool {
+    name == CLAUDE_PROJECT_SETTINGS || name == CLAUDE_PROJECT_LOCAL_SETTINGS
+}
+
+/// Read current config and check sync status, file by file
+pub fn get_sync_status(app: &CliApp, proxy_url: Option<&str>) -> SyncStatus {
+    files_status(app, &app.config_files(), proxy_url)
+}
+
+/// Status of `<project_dir>/.claude`. `settings.local.json` overrides
+/// `settings.json`, so the shared file only counts when the local one sets no URL.
+pub fn get_project_sync_status(project_dir: &Path, proxy_url: &str) -> SyncStatus {
+    let mut status = files_status(
+        &CliApp::Claude,
+        &claude_project_files(project_dir),
+        Some(proxy_url),
+    );
+    // Neither file is required in a project
+    for file in status.files.iter_mut().filter(|f| !f.exists) {
+        file.synced = None;
+    }
+    if let [shared, local] = status.files.as_mut_slice() {
+        if local.detected_url.is_some() {
+            if shared.synced.is_some() {
+                shared.synced = None;
+                shared.note = Some(format!("Overridden by {CLAUDE_PROJECT_LOCAL_SETTINGS}"));
             }
+        } else if shared.detected_url.is_some() {
+            local.synced = None;
+            local.note = None;
+        }
+    }
+    status
+}
+
+fn files_status(app: &CliApp, files: &[CliConfigFile], proxy_url: Option<&str>) -> SyncStatus {
+    let has_backup = files
+        .iter()
+        .any(|file| utils::find_backup(&file.path).is_some());
+    let statuses = files
+        .iter()
+        .map(|file| file_status(app, file, proxy_url))
+        .collect();
+    SyncStatus::new(statuses, has_backup)
+}
+
+fn file_status(app: &CliApp, file: &CliConfigFile, proxy_url: Option<&str>) -> FileStatus {
+    let name = file.name.as_str();
+    if !file.path.exists() {
+        // .claude.json and Gemini's optional files are not required for synced status.
+        // Only settings.json (Claude) / auth.json + config.toml (Codex) / .env (Gemini) are mandatory.
+        let optional = matches!(
+            (app, name),
+            (CliApp::Claude, ".claude.json") | (CliApp::Gemini, "settings.json" | "config.json")
+        );
+        return if optional {
+            FileStatus::optional_missing(name)
+        } else {
+            FileStatus::missing(name)
         };
+    }
 
-        match app {
-            CliApp::Claude => {
-                if file.name == "settings.json" {
-                    let json: Value = serde_json::from_str(&content).unwrap_or_default();
-                    let url = json
-                        .get("env")
-                        .and_then(|e| e.get("ANTHROPIC_BASE_URL"))
-                        .and_then(|v| v.as_str());
-                    if let Some(u) = url {
-                        current_base_url = Some(u.to_string());
-                        if u.trim_end_matches('/') != proxy_url.trim_end_matches('/') {
-                            all_synced = false;
-                        }
-                    } else {
-                        all_synced = false;
-                    }
-                }
-                // .claude.json is optional — skip is_synced check for it
+    let content = match fs::read_to_string(&file.path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("[cli_sync] Failed to read {:?}: {}", file.path, e);
+            return FileStatus::new(name, true)
+                .with_synced(false)
+                .with_note(format!("Cannot read: {e}"));
+        }
+    };
+    let status = FileStatus::new(name, true);
+
+    match (app, name) {
+        (CliApp::Claude, name) if is_claude_settings(name) => {
+            let json: Value = serde_json::from_str(&content).unwrap_or_default();
+            let url = json
+                .get("env")
+                .and_then(|e| e.get("ANTHROPIC_BASE_URL"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+            status.with_url(url, proxy_url, "ANTHROPIC_BASE_URL", true)
+        }
+        (CliApp::Codex, "auth.json") => {
+            let json: Value = serde_json::from_str(&content).unwrap_or_default();
+            match json.get("OPENAI_BASE_URL").and_then(|v| v.as_str()) {
+                Some(url) => status.with_url(Some(url.to_string()), proxy_url, "", true),
+                // Key only; Codex reads the endpoint from config.toml
+                None => status,
             }
-            CliApp::Codex => {
-                if file.name == "config.toml" {
-                    use toml_edit::DocumentMut;
-                    let synced = content.parse::<DocumentMut>().ok().and_then(|doc| {
-                        let provider = doc
-                            .get("model_provider")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("");
-                        if provider != "custom" {
-                            return None;
-                        }
-                        doc.get("model_providers")
-                            .and_then(|mp| mp.as_table())
-                            .and_then(|t| t.get("custom"))
-                            .and_then(|c| c.as_table())
-                            .and_then(|t| t.get("base_url"))
-                            .and_then(|v| v.as_str())
-                            .map(|u| u.to_string())
-                    });
-                    match synced {
-                        Some(url) => {
-                            current_base_url = Some(url.clone());
-                            if url.trim_end_matches('/') != proxy_url.trim_end_matches('/') {
-                                all_synced = false;
-                            }
-                        }
-                        None => {
-                            all_synced = false;
-                        }
-                    }
+        }
+        (CliApp::Codex, "config.toml") => {
+            use toml_edit::DocumentMut;
+            let Ok(doc) = content.parse::<DocumentMut>() else {
+                return status.with_synced(false).with_note("Invalid TOML");
+            };
+            let root = doc
+                .get("model_provider")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            // Root keys first, then the profile written in profile mode
+            let profile = doc
+                .get("profiles")
+                .and_then(|p| p.get(CODEX_PROFILE))
+                .and_then(|p| p.get("model_provider"))
+                .and_then(|v| v.as_str());
+            let provider = match profile {
+                _ if root == "custom" => root,
+                Some(p) => p,
+                None => {
+                    return status.with_synced(false).with_note(format!(
+                        "model_provider is \"{root}\", not \"custom\", and there is no {CODEX_PROFILE} profile"
+                    ))
                 }
+            };
+            let url = doc
+                .get("model_providers")
+                .and_then(|mp| mp.as_table())
+                .and_then(|t| t.get(provider))
+                .and_then(|c| c.as_table())
+                .and_then(|t| t.get("base_url"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+            let status = status.with_url(
+                url,
+                proxy_url,
+                &format!("model_providers.{provider}.base_url"),
+                true,
+            );
+            if provider == root || status.note.is_some() {
+                status
+            } else {
+                status.with_note(format!(
+                    "Profile {CODEX_PROFILE}: codex --profile {CODEX_PROFILE}"
+                ))
             }
-            CliApp::Gemini => {
-                if file.name == ".env" {
-                    if let Some(caps) = GEMINI_BASE_URL_RE.captures(&content) {
-                        let url = caps[1].trim();
-                        current_base_url = Some(url.to_string());
-                        if url.trim_end_matches('/') != proxy_url.trim_end_matches('/') {
-                            all_synced = false;
-                        }
-                    } else {
-                        all_synced = false;
-                    }
+        }
+        (CliApp::Gemini, ".env") => {
+            let url = EnvFile::parse(&content)
+                .get("GOOGLE_GEMINI_BASE_URL")
+                .map(str::to_string);
+            status.with_url(url, proxy_url, "GOOGLE_GEMINI_BASE_URL", true)
+        }
+        (CliApp::Gemini, _) => {
+            let json: Value = serde_json::from_str(&content).unwrap_or_default();
+            let auth_type = json
+                .pointer("/security/auth/selectedType")
+                .and_then(|v| v.as_str());
+            match auth_type {
+                Some(t) if t != GEMINI_API_KEY_AUTH => {
+                    status.with_note(format!("Auth type is {t}, not {GEMINI_API_KEY_AUTH}"))
                 }
+                _ => status,
             }
         }
+        // .claude.json carries no proxy settings
+        _ => status,
+    }
+}
+
+/// Read the proxy URL, key and model the CLI currently uses, whoever wrote them.
+pub fn detect_existing_provider(app: &CliApp) -> Option<DetectedProvider> {
+    let files = app.config_files();
+    let read = |name: &str| {
+        files
+            .iter()
+            .find(|f| f.name == name)
+            .and_then(|f| fs::read_to_string(&f.path).ok())
+    };
+    match app {
+        CliApp::Claude => detect_claude(&read("settings.json")?),
+        CliApp::Codex => detect_codex(&read("config.toml")?, read("auth.json").as_deref(), |var| {
+            std::env::var(var).ok()
+        }),
+        CliApp::Gemini => detect_gemini(&read(".env")?),
     }
+}
+
+fn detect_claude(settings: &str) -> Option<DetectedProvider> {
+    let json: Value = serde_json::from_str(settings).ok()?;
+    let env = json.get("env")?;
+    let env_str = |k: &str| env.get(k).and_then(|v| v.as_str());
+    let key = env_str("ANTHROPIC_API_KEY").or_else(|| env_str("ANTHROPIC_AUTH_TOKEN"))?;
+    let model = json
+        .get("model")
+        .and_then(|v| v.as_str())
+        .or_else(|| env_str("ANTHROPIC_MODEL"));
+    DetectedProvider::new("claude", env_str("ANTHROPIC_BASE_URL")?, key, model)
+}
+
+/// Uses the active `model_provider` table, whatever it is named. The key comes
+/// from that provider's `env_key` when set, otherwise from auth.json.
+fn detect_codex(
+    config: &str,
+    auth: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<DetectedProvider> {
+    use toml_edit::DocumentMut;
+    let doc = config.parse::<DocumentMut>().ok()?;
+    let auth: Value = auth
+        .and_then(|a| serde_json::from_str(a).ok())
+        .unwrap_or_default();
+
+    let provider = doc
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .and_then(|name| {
+            doc.get("model_providers")?
+                .as_table()?
+                .get(name)?
+                .as_table()
+        });
+    let url = provider
+        .and_then(|t| t.get("base_url"))
+        .and_then(|v| v.as_str())
+        .or_else(|| doc.get("openai_base_url").and_then(|v| v.as_str()))
+        .or_else(|| auth.get("OPENAI_BASE_URL").and_then(|v| v.as_str()))?;
+    let key = provider
+        .and_then(|t| t.get("env_key"))
+        .and_then(|v| v.as_str())
+        .and_then(env)
+        .or_else(|| {
+            auth.get("OPENAI_API_KEY")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })?;
+    let model = doc.get("model").and_then(|v| v.as_str());
+    DetectedProvider::new("codex", url, &key, model)
+}
 
-    (all_synced, has_backup, current_base_url)
+fn detect_gemini(env_file: &str) -> Option<DetectedProvider> {
+    let env = EnvFile::parse(env_file);
+    let value = |name: &str| env.get(name).map(str::to_string);
+    let key = value("GEMINI_API_KEY").or_else(|| value("GOOGLE_API_KEY"))?;
+    let model = value("GOOGLE_GEMINI_MODEL").or_else(|| value("GEMINI_MODEL"));
+    DetectedProvider::new(
+        "gemini",
+        &value("GOOGLE_GEMINI_BASE_URL")?,
+        &key,
+        model.as_deref(),
+    )
 }
 
 /// Execute sync logic - writes config files for the given CLI app.
+/// `Ok(Some(_))` is a notice for the user, e.g. an auth type we left alone.
+/// `advanced` is only used for Claude.
 pub fn sync_config(
     app: &CliApp,
     proxy_url: &str,
     api_key: &str,
     model: Option<&str>,
-) -> Result<(), String> {
+    advanced: &ProviderAdvanced,
+) -> Result<Option<String>, String> {
     let files = app.config_files();
     if files.is_empty() {
         return Err("Could not determine config file paths (home directory not found)".to_string());
     }
+    let notice = sync_files(app, &files, proxy_url, api_key, model, advanced)?;
+    Ok([notice, sync_notice(app)]
+        .into_iter()
+        .flatten()
+        .reduce(|a, b| format!("{a}; {b}")))
+}
 
-    for file in &files {
+/// Sync one Claude settings file in `<project_dir>/.claude`: the local one
+/// (default) or the shared one.
+pub fn sync_project_config(
+    project_dir: &Path,
+    local: bool,
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    advanced: &ProviderAdvanced,
+) -> Result<(), String> {
+    let name = if local {
+        CLAUDE_PROJECT_LOCAL_SETTINGS
+    } else {
+        CLAUDE_PROJECT_SETTINGS
+    };
+    let files: Vec<_> = claude_project_files(project_dir)
+        .into_iter()
+        .filter(|f| f.name == name)
+        .collect();
+    sync_files(&CliApp::Claude, &files, proxy_url, api_key, model, advanced).map(drop)
+}
+
+/// Merge our settings into each of `files`, backing each up first. Returns a
+/// notice when something was deliberately left as the user had it.
+fn sync_files(
+    app: &CliApp,
+    files: &[CliConfigFile],
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    advanced: &ProviderAdvanced,
+) -> Result<Option<String>, String> {
+    let mut notice = None;
+    for file in files {
         // Gemini compatibility: prefer settings.json over config.json
         if app == &CliApp::Gemini && file.name == "config.json" && !file.path.exists() {
             let settings_path = file.path.with_file_name("settings.json");
@@ -250,11 +626,13 @@ pub fn sync_config(
             String::new()
         };
 
+        // Top-level keys of a Claude file before the merge, checked again after the write
+        let mut keys_before = Vec::new();
         match app {
             CliApp::Claude => {
                 if file.name == ".claude.json" {
-                    let mut json: Value =
-                        serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}));
+                    let mut json = utils::validate_and_repair_json(&file.path, &content)?;
+                    keys_before = top_level_keys(&json);
                     if let Some(obj) = json.as_object_mut() {
                         obj.insert("hasCompletedOnboarding".to_string(), Value::Bool(true));
                         obj.insert("autoUpdates".to_string(), Value::Bool(false));
@@ -277,15 +655,12 @@ pub fn sync_config(
                             }
                         }
                     }
-                    content = utils::to_json_pretty(&json)?;
-                } else if file.name == "settings.json" {
-                    let mut json: Value =
-                        serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}));
-                    if !json.is_object() {
-                        json = serde_json::json!({});
-                    }
+                    content = utils::to_json_like(&json, &content)?;
+                } else if is_claude_settings(&file.name) {
+                    let mut json = utils::validate_and_repair_json(&file.path, &content)?;
+                    keys_before = top_level_keys(&json);
 
-                    // Safe: we just ensured json is an object above
+                    // Safe: validate_and_repair_json only returns objects
                     let obj = json
                         .as_object_mut()
                         .ok_or_else(|| "Internal error: json is not an object".to_string())?;
@@ -310,6 +685,7 @@ pub fn sync_config(
                         } else {
                             env_obj.remove("ANTHROPIC_API_KEY");
                         }
+                        apply_claude_advanced(&file.path, env_obj, advanced)?;
                     }
 
                     if let Some(m) = model {
@@ -317,7 +693,7 @@ pub fn sync_config(
                             root.insert("model".to_string(), Value::String(m.to_string()));
                         }
                     }
-                    content = utils::to_json_pretty(&json)?;
+                    content = utils::to_json_like(&json, &content)?;
                 }
             }
             CliApp::Codex => {
@@ -334,75 +710,20 @@ pub fn sync_config(
                             Value::String(proxy_url.to_string()),
                         );
                     }
-                    content = utils::to_json_pretty(&json)?;
+                    content = utils::to_json_like(&json, &content)?;
                 } else if file.name == "config.toml" {
-                    use toml_edit::{value, DocumentMut};
-                    let mut doc = content
-                        .parse::<DocumentMut>()
-                        .unwrap_or_else(|_| DocumentMut::new());
-
-                    let providers = doc
-                        .entry("model_providers")
-                        .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-                    if let Some(p_table) = providers.as_table_mut() {
-                        let custom = p_table
-                            .entry("custom")
-                            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-                        if let Some(c_table) = custom.as_table_mut() {
-                            c_table.insert("name", value("custom"));
-                            c_table.insert("wire_api", value("responses"));
-                            c_table.insert("requires_openai_auth", value(true));
-                            c_table.insert("base_url", value(proxy_url));
-                            if let Some(m) = model {
-                                c_table.insert("model", value(m));
-                            }
-                        }
-                    }
-                    doc.insert("model_provider", value("custom"));
-                    if let Some(m) = model {
-                        doc.insert("model", value(m));
-                    }
-                    doc.remove("openai_api_key");
-                    doc.remove("openai_base_url");
-                    content = doc.to_string();
+                    content = apply_codex_toml(&content, proxy_url, model, codex_profile_mode());
                 }
             }
             CliApp::Gemini => {
                 if file.name == ".env" {
-                    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-                    let mut found_url = false;
-                    let mut found_key = false;
-                    for line in lines.iter_mut() {
-                        if line.starts_with("GOOGLE_GEMINI_BASE_URL=") {
-                            *line = format!("GOOGLE_GEMINI_BASE_URL={proxy_url}");
-                            found_url = true;
-                        } else if line.trim().starts_with("GEMINI_API_KEY=") {
-                            *line = format!("GEMINI_API_KEY={api_key}");
-                            found_key = true;
-                        }
-                    }
-                    if !found_url {
-                        lines.push(format!("GOOGLE_GEMINI_BASE_URL={proxy_url}"));
-                    }
-                    if !found_key {
-                        lines.push(format!("GEMINI_API_KEY={api_key}"));
-                    }
+                    let mut env = EnvFile::parse(&content);
+                    env.set("GOOGLE_GEMINI_BASE_URL", proxy_url);
+                    env.set("GEMINI_API_KEY", api_key);
                     if let Some(m) = model {
-                        let mut found_model = false;
-                        for line in lines.iter_mut() {
-                            if line.starts_with("GOOGLE_GEMINI_MODEL=") {
-                                *line = format!("GOOGLE_GEMINI_MODEL={m}");
-                                found_model = true;
-                            }
-                        }
-                        if !found_model {
-                            lines.push(format!("GOOGLE_GEMINI_MODEL={m}"));
-                        }
-                    }
-                    content = lines.join("\n");
-                    if !content.ends_with('\n') {
-                        content.push('\n');
+                        env.set("GOOGLE_GEMINI_MODEL", m);
                     }
+                    content = env.render();
                 } else if file.name == "settings.json" || file.name == "config.json" {
                     let mut json: Value =
                         serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}));
@@ -410,30 +731,253 @@ pub fn sync_config(
                         json = serde_json::json!({});
                     }
 
-                    // Build nested security.auth structure safely
-                    let obj = json
-                        .as_object_mut()
-                        .ok_or_else(|| "Internal error".to_string())?;
-                    let sec = obj.entry("security").or_insert(serde_json::json!({}));
-                    if let Some(sec_obj) = sec.as_object_mut() {
-                        let auth = sec_obj.entry("auth").or_insert(serde_json::json!({}));
-                        if let Some(auth_obj) = auth.as_object_mut() {
-                            auth_obj.insert(
-                                "selectedType".to_string(),
-                                Value::String("gemini-api-key".to_string()),
-                            );
+                    match set_gemini_auth_type(&mut json, gemini_force_auth()) {
+                        AuthTypeChange::Unchanged => {}
+                        AuthTypeChange::Kept(current) => {
+                            notice = Some(format!(
+                                "Gemini auth type left as {current}; the proxy key is only \
+                                 used with {GEMINI_API_KEY_AUTH}"
+                            ));
+                        }
+                        AuthTypeChange::Set { previous } => {
+                            record_auth_change(&file.path, previous.as_deref())?;
+                            if let Some(previous) = previous {
+                                notice = Some(format!(
+                                    "Gemini auth type changed from {previous} to {GEMINI_API_KEY_AUTH}"
+                                ));
+                            }
                         }
                     }
-                    content = utils::to_json_pretty(&json)?;
+                    content = utils::to_json_like(&json, &content)?;
                 }
             }
         }
 
         // Atomic write with temp file
-        utils::atomic_write(&file.path, &content)?;
+        write_file(app, file, &content)?;
+        utils::verify_written_config(
+            &file.path,
+            &injected_values(app, &file.name, proxy_url, api_key),
+            || write_file(app, file, &content),
+        )?;
+        let written = fs::read_to_string(&file.path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok());
+        let lost: Vec<_> = keys_before
+            .iter()
+            .filter(|key| written.as_ref().and_then(|w| w.get(key.as_str())).is_none())
+            .collect();
+        if !lost.is_empty() {
+            tracing::error!(
+                "[cli_sync] Sync dropped top-level keys {:?} from {}",
+                lost,
+                file.name
+            );
+        }
     }
 
-    Ok(())
+    Ok(notice)
+}
+
+fn top_level_keys(json: &Value) -> Vec<String> {
+    json.as_object()
+        .map(|obj| obj.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// What `set_gemini_auth_type` did to `security.auth.selectedType`.
+#[derive(Debug, PartialEq, Eq)]
+enum AuthTypeChange {
+    /// Already `gemini-api-key`.
+    Unchanged,
+    /// Left as the user's own auth type.
+    Kept(String),
+    /// Set to `gemini-api-key`; `previous` is what was there before, if anything.
+    Set { previous: Option<String> },
+}
+
+/// Select API-key auth unless another auth type is configured and `force` is off.
+fn set_gemini_auth_type(json: &mut Value, force: bool) -> AuthTypeChange {
+    let current = json
+        .pointer("/security/auth/selectedType")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    match current {
+        Some(t) if t == GEMINI_API_KEY_AUTH => return AuthTypeChange::Unchanged,
+        Some(t) if !force => return AuthTypeChange::Kept(t),
+        _ => {}
+    }
+    let Some(obj) = json.as_object_mut() else {
+        return AuthTypeChange::Unchanged;
+    };
+    // Build nested security.auth structure safely
+    let sec = obj.entry("security").or_insert(serde_json::json!({}));
+    let Some(auth_obj) = sec
+        .as_object_mut()
+        .map(|sec_obj| sec_obj.entry("auth").or_insert(serde_json::json!({})))
+        .and_then(|auth| auth.as_object_mut())
+    else {
+        return AuthTypeChange::Unchanged;
+    };
+    auth_obj.insert(
+        "selectedType".to_string(),
+        Value::String(GEMINI_API_KEY_AUTH.to_string()),
+    );
+    AuthTypeChange::Set { previous: current }
+}
+
+/// Sidecar next to Gemini's settings recording the auth type sync replaced,
+/// so restore reverts only a change we made.
+fn auth_note_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{name}.hajimi-auth"))
+}
+
+/// Note the auth type we replaced. An existing note already holds the user's
+/// original and is kept.
+fn record_auth_change(path: &Path, previous: Option<&str>) -> Result<(), String> {
+    let note = auth_note_path(path);
+    if note.exists() {
+        return Ok(());
+    }
+    let content = serde_json::json!({ "previous": previous }).to_string();
+    utils::atomic_write(&note, &content).map_err(|e| e.to_string())
+}
+
+/// Put back the auth type a sync replaced, if it is still ours, and drop the note.
+fn revert_gemini_auth(path: &Path) -> Result<(), String> {
+    let note_path = auth_note_path(path);
+    let Ok(note) = fs::read_to_string(&note_path) else {
+        return Ok(());
+    };
+    let previous = serde_json::from_str::<Value>(&note)
+        .ok()
+        .and_then(|n| n.get("previous")?.as_str().map(str::to_string));
+    if let Ok(content) = fs::read_to_string(path) {
+        let mut json: Value = serde_json::from_str(&content).unwrap_or_default();
+        let ours = json
+            .pointer("/security/auth/selectedType")
+            .and_then(|v| v.as_str())
+            == Some(GEMINI_API_KEY_AUTH);
+        if let Some(auth) = json
+            .pointer_mut("/security/auth")
+            .and_then(|a| a.as_object_mut())
+            .filter(|_| ours)
+        {
+            match previous {
+                Some(previous) => {
+                    auth.insert("selectedType".to_string(), Value::String(previous));
+                }
+                None => {
+                    auth.remove("selectedType");
+                }
+            }
+            utils::atomic_write(path, &utils::to_json_like(&json, &content)?)
+                .map_err(|e| format!("Failed to restore the Gemini auth type: {e}"))?;
+        }
+    }
+    fs::remove_file(&note_path).map_err(|e| format!("Failed to remove {note_path:?}: {e}"))
+}
+
+/// Point Codex's config.toml at the proxy: at the root, or only through
+/// `[profiles.hajimi]` in profile mode.
+fn apply_codex_toml(
+    content: &str,
+    proxy_url: &str,
+    model: Option<&str>,
+    profile_mode: bool,
+) -> String {
+    use toml_edit::{value, DocumentMut};
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .unwrap_or_else(|_| DocumentMut::new());
+
+    let provider_id = if profile_mode {
+        CODEX_PROFILE
+    } else {
+        "custom"
+    };
+
+    let providers = doc
+        .entry("model_providers")
+        .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+    if let Some(p_table) = providers.as_table_mut() {
+        let custom = p_table
+            .entry(provider_id)
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        if let Some(c_table) = custom.as_table_mut() {
+            c_table.insert("name", value(provider_id));
+            c_table.insert("wire_api", value("responses"));
+            c_table.insert("requires_openai_auth", value(true));
+            c_table.insert("base_url", value(proxy_url));
+            if let Some(m) = model {
+                c_table.insert("model", value(m));
+            }
+        }
+    }
+    if profile_mode {
+        // `codex --profile hajimi`; the root keys stay the user's
+        let profiles = doc
+            .entry("profiles")
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        if let Some(p_table) = profiles.as_table_mut() {
+            p_table.set_implicit(true);
+            let profile = p_table
+                .entry(CODEX_PROFILE)
+                .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+            if let Some(table) = profile.as_table_mut() {
+                table.insert("model_provider", value(provider_id));
+                if let Some(m) = model {
+                    table.insert("model", value(m));
+                }
+            }
+        }
+    } else {
+        doc.insert("model_provider", value("custom"));
+        if let Some(m) = model {
+            doc.insert("model", value(m));
+        }
+        doc.remove("openai_api_key");
+        doc.remove("openai_base_url");
+    }
+    doc.to_string()
+}
+
+/// Values `sync_config` writes into `file_name`, checked after the write.
+fn injected_values<'a>(
+    app: &CliApp,
+    file_name: &str,
+    proxy_url: &'a str,
+    api_key: &'a str,
+) -> Vec<&'a str> {
+    match (app, file_name) {
+        (CliApp::Claude, ".claude.json") => vec![api_key],
+        (CliApp::Claude, name) if is_claude_settings(name) => vec![proxy_url, api_key],
+        (CliApp::Codex, "auth.json") | (CliApp::Gemini, ".env") => vec![proxy_url, api_key],
+        (CliApp::Codex, "config.toml") => vec![proxy_url],
+        _ => Vec::new(),
+    }
+}
+
+/// 该配置文件是否保存 API 密钥（写入时需限制为仅本人可读）
+pub(crate) fn holds_api_key(app: &CliApp, file_name: &str) -> bool {
+    match app {
+        CliApp::Claude => file_name == ".claude.json" || is_claude_settings(file_name),
+        CliApp::Codex => file_name == "auth.json",
+        CliApp::Gemini => file_name == ".env",
+    }
+}
+
+/// 原子写入配置文件；保存密钥的文件使用 `atomic_write_secure`
+fn write_file(app: &CliApp, file: &CliConfigFile, content: &str) -> crate::error::Result<()> {
+    if holds_api_key(app, &file.name) {
+        utils::atomic_write_secure(&file.path, content)
+    } else {
+        utils::atomic_write(&file.path, content)
+    }
 }
 
 /// Restore from backup files
@@ -442,14 +986,74 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
     if files.is_empty() {
         return Err("Could not determine config file paths".to_string());
     }
+    if app == &CliApp::Codex && codex_profile_mode() {
+        // Leave the rest of config.toml as it is now; only auth.json goes back
+        let (config, rest): (Vec<_>, Vec<_>) =
+            files.into_iter().partition(|f| f.name == "config.toml");
+        for file in &config {
+            remove_codex_profile(&file.path)?;
+        }
+        return restore_files(app, &rest);
+    }
+    if app == &CliApp::Gemini {
+        // Files with a backup are replaced wholesale below anyway
+        for file in &files {
+            revert_gemini_auth(&file.path)?;
+        }
+    }
+    if app == &CliApp::Claude {
+        for file in files.iter().filter(|f| is_claude_settings(&f.name)) {
+            revert_claude_advanced(&file.path)?;
+        }
+    }
+    restore_files(app, &files)
+}
 
-    let mut restored_count = 0;
+/// Drop `[profiles.hajimi]` and `[model_providers.hajimi]` (and the parent
+/// tables if that leaves them empty), touching nothing else.
+fn remove_codex_profile(path: &Path) -> Result<(), String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| format!("Cannot parse {}: {e}", path.display()))?;
+    let mut changed = false;
+    for parent in ["profiles", "model_providers"] {
+        let Some(table) = doc.get_mut(parent).and_then(|t| t.as_table_mut()) else {
+            continue;
+        };
+        if table.remove(CODEX_PROFILE).is_some() {
+            changed = true;
+            if table.is_empty() {
+                doc.remove(parent);
+            }
+        }
+    }
+    if changed {
+        utils::atomic_write(path, &doc.to_string())?;
+        tracing::info!("[cli_sync] Removed the {} Codex profile", CODEX_PROFILE);
+    }
+    Ok(())
+}
 
+/// Undo `sync_project_config` for both project files.
+pub fn restore_project_config(project_dir: &Path) -> Result<(), String> {
+    let files: Vec<_> = claude_project_files(project_dir)
+        .into_iter()
+        .filter(|f| f.path.exists())
+        .collect();
     for file in &files {
-        let backup_path = file
-            .path
-            .with_file_name(format!("{}{}", file.name, BACKUP_SUFFIX));
-        if backup_path.exists() {
+        revert_claude_advanced(&file.path)?;
+    }
+    restore_files(&CliApp::Claude, &files)
+}
+
+fn restore_files(app: &CliApp, files: &[CliConfigFile]) -> Result<(), String> {
+    let mut restored_count = 0;
+
+    for file in files {
+        if let Some(backup_path) = utils::find_backup(&file.path) {
             if let Err(e) = fs::rename(&backup_path, &file.path) {
                 return Err(format!("Failed to restore backup {}: {}", file.name, e));
             }
@@ -464,7 +1068,7 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
 
     // No backup found — remove only the proxy-related keys we injected,
     // instead of writing empty/default values that would break the user's config.
-    for file in &files {
+    for file in files {
         if !file.path.exists() {
             continue;
         }
@@ -475,13 +1079,13 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
 
         let new_content = match app {
             CliApp::Claude => {
-                if file.name == "settings.json" {
+                if is_claude_settings(&file.name) {
                     let mut json: Value = serde_json::from_str(&content).unwrap_or_default();
                     if let Some(env_obj) = json.get_mut("env").and_then(|e| e.as_object_mut()) {
                         env_obj.remove("ANTHROPIC_BASE_URL");
                         env_obj.remove("ANTHROPIC_API_KEY");
                     }
-                    Some(serde_json::to_string_pretty(&json).unwrap_or(content.clone()))
+                    Some(utils::to_json_like(&json, &content).unwrap_or(content.clone()))
                 } else if file.name == ".claude.json" {
                     let mut json: Value = serde_json::from_str(&content).unwrap_or_default();
                     let mut changed = false;
@@ -496,7 +1100,7 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
                         }
                     }
                     if changed {
-                        Some(serde_json::to_string_pretty(&json).unwrap_or(content.clone()))
+                        Some(utils::to_json_like(&json, &content).unwrap_or(content.clone()))
                     } else {
                         None
                     }
@@ -524,19 +1128,16 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
             }
             CliApp::Gemini => {
                 if file.name == ".env" {
-                    let lines: Vec<&str> = content
-                        .lines()
-                        .filter(|l| {
-                            !l.starts_with("GOOGLE_GEMINI_BASE_URL=")
-                                && !l.starts_with("GEMINI_API_KEY=")
-                                && !l.starts_with("GOOGLE_GEMINI_MODEL=")
-                        })
-                        .collect();
-                    let mut result = lines.join("\n");
-                    if !result.is_empty() && !result.ends_with('\n') {
-                        result.push('\n');
-                    }
-                    Some(result)
+                    let mut env = EnvFile::parse(&content);
+                    let removed = [
+                        "GOOGLE_GEMINI_BASE_URL",
+                        "GEMINI_API_KEY",
+                        "GOOGLE_GEMINI_MODEL",
+                    ]
+                    .iter()
+                    .map(|key| env.remove(key))
+                    .sum::<usize>();
+                    (removed > 0).then(|| env.render())
                 } else {
                     None
                 }
@@ -544,7 +1145,7 @@ pub fn restore_config(app: &CliApp) -> Result<(), String> {
         };
 
         if let Some(c) = new_content {
-            utils::atomic_write(&file.path, &c)
+            write_file(app, file, &c)
                 .map_err(|e| format!("Failed to clean config {}: {}", file.name, e))?;
         }
     }
@@ -586,8 +1187,7 @@ pub fn write_config_content(app: &CliApp, file_name: &str, content: &str) -> Res
         serde_json::from_str::<Value>(content).map_err(|e| format!("Invalid JSON: {e}"))?;
     }
 
-    utils::atomic_write(&file.path, content)
-        .map_err(|e| format!("Failed to write {file_name}: {e}"))
+    write_file(app, &file, content).map_err(|e| format!("Failed to write {file_name}: {e}"))
 }
 
 #[cfg(test)]
@@ -630,41 +1230,94 @@ mod tests {
     /// 测试Claude settings.json的sync写入正确性
     #[test]
     fn test_claude_sync_writes_correct_json() {
-        let dir = TempDir::new().unwrap();
-        let settings_path = dir.path().join("settings.json");
-        let claude_json_path = dir.path().join(".claude.json");
-
-        // 写入空的初始文件
-        fs::write(&settings_path, "{}").unwrap();
-        fs::write(&claude_json_path, "{}").unwrap();
-
-        // 模拟sync逻辑（直接测试JSON merge部分）
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
         let proxy_url = "https://proxy.example.com";
         let api_key = "sk-test-key-123";
 
-        let content = fs::read_to_string(&settings_path).unwrap();
-        let mut json: Value = serde_json::from_str(&content).unwrap();
-        let obj = json.as_object_mut().unwrap();
-        let env = obj.entry("env").or_insert(serde_json::json!({}));
-        if let Some(env_obj) = env.as_object_mut() {
-            env_obj.insert(
-                "ANTHROPIC_BASE_URL".to_string(),
-                Value::String(proxy_url.to_string()),
-            );
-            env_obj.insert(
-                "ANTHROPIC_API_KEY".to_string(),
-                Value::String(api_key.to_string()),
-            );
-        }
-
-        let result = serde_json::to_string_pretty(&json).unwrap();
-        fs::write(&settings_path, &result).unwrap();
+        sync_config(
+            &CliApp::Claude,
+            proxy_url,
+            api_key,
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap();
 
-        // 验证
+        let settings_path = home.path().join(".claude/settings.json");
         let written: Value =
             serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
         assert_eq!(written["env"]["ANTHROPIC_BASE_URL"], proxy_url);
         assert_eq!(written["env"]["ANTHROPIC_API_KEY"], api_key);
+        assert!(home.path().join(".claude.json").exists());
+    }
+
+    /// 测试在临时home中对每个CLI完整执行 同步→状态→恢复
+    #[test]
+    fn test_sync_status_restore_end_to_end() {
+        let proxy = "https://proxy.e2e.test";
+        let cases = [
+            (
+                CliApp::Claude,
+                ".claude/settings.json",
+                "{\n  \"theme\": \"dark\"\n}\n",
+                vec![(".claude/settings.json", "\"ANTHROPIC_API_KEY\": \"sk-e2e\"")],
+            ),
+            (
+                CliApp::Codex,
+                ".codex/config.toml",
+                "model = \"o3\"\n",
+                vec![
+                    (
+                        ".codex/config.toml",
+                        "base_url = \"https://proxy.e2e.test\"",
+                    ),
+                    (".codex/auth.json", "\"OPENAI_API_KEY\": \"sk-e2e\""),
+                ],
+            ),
+            (
+                CliApp::Gemini,
+                ".gemini/.env",
+                "OTHER=1\n",
+                vec![
+                    (
+                        ".gemini/.env",
+                        "GOOGLE_GEMINI_BASE_URL=https://proxy.e2e.test",
+                    ),
+                    (".gemini/.env", "OTHER=1"),
+                ],
+            ),
+        ];
+
+        for (app, own_file, original, expected) in cases {
+            let home = TempDir::new().unwrap();
+            let _home = paths::override_home(home.path());
+            let own_path = home.path().join(own_file);
+            fs::create_dir_all(own_path.parent().unwrap()).unwrap();
+            fs::write(&own_path, original).unwrap();
+            assert!(!get_sync_status(&app, Some(proxy)).is_synced(), "{app:?}");
+
+            sync_config(
+                &app,
+                proxy,
+                "sk-e2e",
+                Some("e2e-model"),
+                &ProviderAdvanced::default(),
+            )
+            .unwrap();
+            for (file, needle) in expected {
+                let content = fs::read_to_string(home.path().join(file)).unwrap();
+                assert!(content.contains(needle), "{app:?} {file}: {content}");
+            }
+            assert!(get_sync_status(&app, Some(proxy)).is_synced(), "{app:?}");
+            let unchecked = get_sync_status(&app, None);
+            assert!(!unchecked.is_synced(), "{app:?}");
+            assert!(unchecked.current_base_url().is_some(), "{app:?}");
+
+            restore_config(&app).unwrap();
+            assert_eq!(fs::read_to_string(&own_path).unwrap(), original, "{app:?}");
+            assert!(!get_sync_status(&app, Some(proxy)).is_synced(), "{app:?}");
+        }
     }
 
     /// 测试Claude sync保留已有字段
@@ -925,6 +1578,80 @@ base_url = "http://localhost:8045/v1"
         assert!(!content.contains("old-url"));
     }
 
+    /// 测试已有的Vertex认证不被覆盖，恢复时也不改动
+    #[test]
+    fn test_gemini_existing_vertex_auth_untouched() {
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
+        let settings = home.path().join(".gemini/settings.json");
+        fs::create_dir_all(settings.parent().unwrap()).unwrap();
+        let original = "{\n  \"security\": {\n    \"auth\": {\n      \"selectedType\": \"vertex-ai\"\n    }\n  }\n}\n";
+        fs::write(&settings, original).unwrap();
+
+        let notice = sync_config(
+            &CliApp::Gemini,
+            "https://proxy.test",
+            "sk-1",
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap();
+        assert!(notice.unwrap().contains("left as vertex-ai"));
+        assert_eq!(fs::read_to_string(&settings).unwrap(), original);
+        assert!(!auth_note_path(&settings).exists());
+
+        // The .env still gets the key; restore leaves the auth type alone
+        fs::remove_file(utils::backup_path(&settings)).unwrap();
+        restore_config(&CliApp::Gemini).unwrap();
+        assert_eq!(fs::read_to_string(&settings).unwrap(), original);
+    }
+
+    /// 测试强制切换认证类型后，恢复只还原我们改过的字段
+    #[test]
+    fn test_gemini_forced_auth_reverted_on_restore() {
+        let dir = TempDir::new().unwrap();
+        let settings = dir.path().join("settings.json");
+        let mut json = serde_json::json!({
+            "security": { "auth": { "selectedType": "cloud-shell" } },
+            "theme": "dark"
+        });
+        assert_eq!(
+            set_gemini_auth_type(&mut json, false),
+            AuthTypeChange::Kept("cloud-shell".to_string())
+        );
+        assert_eq!(
+            set_gemini_auth_type(&mut json, true),
+            AuthTypeChange::Set {
+                previous: Some("cloud-shell".to_string())
+            }
+        );
+        assert_eq!(
+            set_gemini_auth_type(&mut json, true),
+            AuthTypeChange::Unchanged
+        );
+        record_auth_change(&settings, Some("cloud-shell")).unwrap();
+        // A later sync doesn't lose the original
+        record_auth_change(&settings, Some(GEMINI_API_KEY_AUTH)).unwrap();
+        fs::write(&settings, json.to_string()).unwrap();
+
+        revert_gemini_auth(&settings).unwrap();
+        let restored: Value =
+            serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(restored["security"]["auth"]["selectedType"], "cloud-shell");
+        assert_eq!(restored["theme"], "dark");
+        assert!(!auth_note_path(&settings).exists());
+
+        // Without a note nothing is reverted
+        let mut fresh = serde_json::json!({});
+        assert_eq!(
+            set_gemini_auth_type(&mut fresh, false),
+            AuthTypeChange::Set { previous: None }
+        );
+        fs::write(&settings, fresh.to_string()).unwrap();
+        revert_gemini_auth(&settings).unwrap();
+        assert_eq!(fs::read_to_string(&settings).unwrap(), fresh.to_string());
+    }
+
     /// 测试.env新文件写入（不存在已有字段）
     #[test]
     fn test_gemini_env_write_fresh() {
@@ -970,6 +1697,47 @@ base_url = "http://localhost:8045/v1"
         assert_eq!(url, Some("https://proxy.test"));
     }
 
+    /// 测试逐文件状态：auth.json 正确但 config.toml 指向别处
+    #[test]
+    fn test_file_status_per_codex_file() {
+        let dir = TempDir::new().unwrap();
+        let file = |name: &str| CliConfigFile {
+            name: name.to_string(),
+            path: dir.path().join(name),
+        };
+        let proxy = "https://proxy.test/v1";
+        fs::write(
+            dir.path().join("auth.json"),
+            r#"{"OPENAI_API_KEY": "sk-1", "OPENAI_BASE_URL": "https://proxy.test/v1"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("config.toml"),
+            "model_provider = \"custom\"\n[model_providers.custom]\nbase_url = \"https://other.test/v1\"\n",
+        )
+        .unwrap();
+
+        let auth = file_status(&CliApp::Codex, &file("auth.json"), Some(proxy));
+        assert_eq!(auth.synced, Some(true));
+        let config = file_status(&CliApp::Codex, &file("config.toml"), Some(proxy));
+        assert_eq!(config.synced, Some(false));
+        assert_eq!(
+            config.detected_url.as_deref(),
+            Some("https://other.test/v1")
+        );
+
+        fs::write(
+            dir.path().join("config.toml"),
+            "model_provider = \"openai\"\n",
+        )
+        .unwrap();
+        let config = file_status(&CliApp::Codex, &file("config.toml"), Some(proxy));
+        assert!(config.note.unwrap().contains("openai"));
+
+        let missing = file_status(&CliApp::Gemini, &file("settings.json"), Some(proxy));
+        assert_eq!((missing.exists, missing.synced), (false, None));
+    }
+
     /// 测试backup只创建一次
     #[test]
     fn test_backup_created_once() {
@@ -1014,18 +1782,173 @@ base_url = "http://localhost:8045/v1"
         assert!(!tmp_path.exists());
     }
 
-    /// 测试损坏JSON配置的容错
+    /// 测试损坏的Claude配置不会被当作空对象覆盖
+    #[test]
+    fn test_corrupted_claude_settings_not_overwritten() {
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
+        let settings_path = home.path().join(".claude/settings.json");
+        fs::create_dir_all(settings_path.parent().unwrap()).unwrap();
+        let corrupted = "{ \"permissions\": { this is not valid json }}}";
+        fs::write(&settings_path, corrupted).unwrap();
+
+        let err = sync_config(
+            &CliApp::Claude,
+            "https://proxy.example.com",
+            "sk-x",
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap_err();
+        assert!(err.contains("settings.json"), "{err}");
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), corrupted);
+    }
+
+    /// 测试损坏的.claude.json从备份修复后再合并，保留mcpServers
+    #[test]
+    fn test_corrupted_claude_json_repaired_from_backup() {
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
+        let claude_json = home.path().join(".claude.json");
+        fs::write(
+            home.path().join(".claude.json.20200101_000000.hajimi.bak"),
+            r#"{"mcpServers": {"fs": {"command": "npx"}}, "numStartups": 3}"#,
+        )
+        .unwrap();
+        fs::write(&claude_json, "{\"mcpServers\": {\"fs\": ").unwrap();
+
+        sync_config(
+            &CliApp::Claude,
+            "https://proxy.example.com",
+            "sk-x",
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap();
+
+        let written: Value =
+            serde_json::from_str(&fs::read_to_string(&claude_json).unwrap()).unwrap();
+        assert_eq!(written["mcpServers"]["fs"]["command"], "npx");
+        assert_eq!(written["numStartups"], 3);
+        assert_eq!(written["hasCompletedOnboarding"], true);
+    }
+
+    /// 测试 provider 高级选项写入 Claude env，切换到没有高级选项的 provider 时清除
+    #[test]
+    fn test_claude_advanced_options() {
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
+        let settings_path = home.path().join(".claude/settings.json");
+        let advanced = ProviderAdvanced::parse(
+            r#"{"headers": {"x-portkey-provider": "anthropic", "x-team": "core"},
+                "small_fast_model": "claude-haiku-4-5", "api_timeout_ms": 600000}"#,
+        )
+        .unwrap();
+        let env = || -> Value {
+            let json: Value =
+                serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+            json["env"].clone()
+        };
+
+        sync_config(
+            &CliApp::Claude,
+            "https://proxy.test",
+            "sk-1",
+            None,
+            &advanced,
+        )
+        .unwrap();
+        assert_eq!(
+            env()["ANTHROPIC_CUSTOM_HEADERS"],
+            "x-portkey-provider: anthropic\nx-team: core"
+        );
+        assert_eq!(env()["ANTHROPIC_SMALL_FAST_MODEL"], "claude-haiku-4-5");
+        assert_eq!(env()["API_TIMEOUT_MS"], "600000");
+
+        sync_config(
+            &CliApp::Claude,
+            "https://other.test",
+            "sk-2",
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap();
+        for key in CLAUDE_ADVANCED_ENV {
+            assert!(env().get(key).is_none(), "{key}");
+        }
+
+        // 没有备份时 restore 只删除我们写入的字段
+        sync_config(
+            &CliApp::Claude,
+            "https://proxy.test",
+            "sk-1",
+            None,
+            &advanced,
+        )
+        .unwrap();
+        for dir in [home.path().to_path_buf(), home.path().join(".claude")] {
+            for entry in fs::read_dir(dir).unwrap().flatten() {
+                if entry.file_name().to_string_lossy().contains(".bak") {
+                    fs::remove_file(entry.path()).unwrap();
+                }
+            }
+        }
+        restore_config(&CliApp::Claude).unwrap();
+        assert_eq!(env(), serde_json::json!({}));
+        assert!(!env_note_path(&settings_path).exists());
+    }
+
+    /// 测试用户手动设置的高级变量不会被同步或 restore 删除
     #[test]
-    fn test_corrupted_json_fallback() {
-        let corrupted = "{ this is not valid json }}}";
-        let json: Value = serde_json::from_str(corrupted).unwrap_or_default();
-        // unwrap_or_default应返回Null
-        assert!(json.is_null());
+    fn test_claude_advanced_keeps_user_values() {
+        let home = TempDir::new().unwrap();
+        let _home = paths::override_home(home.path());
+        let settings_path = home.path().join(".claude/settings.json");
+        fs::create_dir_all(settings_path.parent().unwrap()).unwrap();
+        fs::write(
+            &settings_path,
+            r#"{"env": {"API_TIMEOUT_MS": "123", "ANTHROPIC_SMALL_FAST_MODEL": "mine"}}"#,
+        )
+        .unwrap();
+        let env = || -> Value {
+            let json: Value =
+                serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+            json["env"].clone()
+        };
+        let sync = |advanced: &ProviderAdvanced| {
+            sync_config(
+                &CliApp::Claude,
+                "https://proxy.test",
+                "sk-1",
+                None,
+                advanced,
+            )
+            .unwrap();
+        };
 
-        // 用空对象fallback
-        let json: Value = serde_json::from_str(corrupted).unwrap_or_else(|_| serde_json::json!({}));
-        assert!(json.is_object());
-        assert!(json.as_object().unwrap().is_empty());
+        sync(&ProviderAdvanced::default());
+        assert_eq!(env()["API_TIMEOUT_MS"], "123");
+        assert_eq!(env()["ANTHROPIC_SMALL_FAST_MODEL"], "mine");
+
+        sync(&ProviderAdvanced::parse(r#"{"api_timeout_ms": 600000}"#).unwrap());
+        assert_eq!(env()["API_TIMEOUT_MS"], "600000");
+        sync(&ProviderAdvanced::default());
+        assert_eq!(env()["API_TIMEOUT_MS"], "123");
+
+        // A value the user changed after we wrote it is theirs
+        sync(&ProviderAdvanced::parse(r#"{"api_timeout_ms": 600000}"#).unwrap());
+        let mut json: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        json["env"]["API_TIMEOUT_MS"] = Value::from("999");
+        fs::write(&settings_path, json.to_string()).unwrap();
+        for entry in fs::read_dir(home.path().join(".claude")).unwrap().flatten() {
+            if entry.file_name().to_string_lossy().contains(".bak") {
+                fs::remove_file(entry.path()).unwrap();
+            }
+        }
+        restore_config(&CliApp::Claude).unwrap();
+        assert_eq!(env()["API_TIMEOUT_MS"], "999");
+        assert_eq!(env()["ANTHROPIC_SMALL_FAST_MODEL"], "mine");
     }
 
     /// 测试restore清理代理字段（Claude）
@@ -1219,4 +2142,91 @@ base_url = "https://proxy.test"
         assert!(!result.contains("base_url"));
         assert!(result.contains("some_user_key = \"keep\""));
     }
+
+    /// 测试profile模式只写入[profiles.hajimi]，保留根键，状态识别并可单独移除
+    #[test]
+    fn test_codex_profile_mode_sync_status_and_restore() {
+        let original = r#"model_provider = "openai"
+model = "o3"
+
+[model_providers.other]
+base_url = "https://other.test"
+"#;
+        let proxy = "https://proxy.test/v1";
+        let content = apply_codex_toml(original, proxy, Some("gpt-5"), true);
+        let doc = content.parse::<toml_edit::DocumentMut>().unwrap();
+        assert_eq!(doc["model_provider"].as_str(), Some("openai"));
+        assert_eq!(doc["model"].as_str(), Some("o3"));
+        assert_eq!(
+            doc["profiles"]["hajimi"]["model_provider"].as_str(),
+            Some("hajimi")
+        );
+        assert_eq!(doc["profiles"]["hajimi"]["model"].as_str(), Some("gpt-5"));
+        assert_eq!(
+            doc["model_providers"]["hajimi"]["base_url"].as_str(),
+            Some(proxy)
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = CliConfigFile {
+            name: "config.toml".to_string(),
+            path: dir.path().join("config.toml"),
+        };
+        fs::write(&file.path, &content).unwrap();
+        let status = file_status(&CliApp::Codex, &file, Some(proxy));
+        assert_eq!(status.synced, Some(true));
+        assert!(status.note.unwrap().contains("--profile hajimi"));
+
+        remove_codex_profile(&file.path).unwrap();
+        let restored = fs::read_to_string(&file.path).unwrap();
+        assert!(!restored.contains("hajimi"));
+        assert!(restored.contains("[model_providers.other]"));
+        assert!(restored.contains("model = \"o3\""));
+        assert_eq!(
+            file_status(&CliApp::Codex, &file, Some(proxy)).synced,
+            Some(false)
+        );
+    }
+
+    /// 测试从已有配置反向识别provider
+    #[test]
+    fn test_detect_existing_provider_from_configs() {
+        let claude = r#"{"model": "opus", "env": {"ANTHROPIC_BASE_URL": "https://p.test/", "ANTHROPIC_AUTH_TOKEN": "tok"}}"#;
+        let d = detect_claude(claude).unwrap();
+        assert_eq!(
+            (d.url.as_str(), d.api_key.as_str()),
+            ("https://p.test", "tok")
+        );
+        assert_eq!(d.model.as_deref(), Some("opus"));
+        assert!(detect_claude(r#"{"env": {"ANTHROPIC_API_KEY": "k"}}"#).is_none());
+
+        let codex = r#"
+model_provider = "myproxy"
+model = "gpt-5"
+
+[model_providers.myproxy]
+base_url = "https://p.test/v1"
+env_key = "MY_PROXY_KEY"
+"#;
+        let auth = r#"{"OPENAI_API_KEY": "from-auth"}"#;
+        let d = detect_codex(codex, Some(auth), |v| {
+            (v == "MY_PROXY_KEY").then(|| "from-env".into())
+        })
+        .unwrap();
+        assert_eq!(
+            (d.url.as_str(), d.api_key.as_str()),
+            ("https://p.test", "from-env")
+        );
+        assert_eq!(d.model.as_deref(), Some("gpt-5"));
+        let d = detect_codex(codex, Some(auth), |_| None).unwrap();
+        assert_eq!(d.api_key, "from-auth");
+
+        let env = "export GOOGLE_GEMINI_BASE_URL=\"https://p.test\"\nGEMINI_API_KEY=gk\n";
+        let d = detect_gemini(env).unwrap();
+        assert_eq!(
+            (d.url.as_str(), d.api_key.as_str()),
+            ("https://p.test", "gk")
+        );
+        assert!(detect_gemini("GEMINI_API_KEY=gk\n").is_none());
+    }
 }
@@ -0,0 +1,193 @@
+//! What Restore would change: a line diff between an app's config file and
+//! the backup Restore would put back (the restore point, else the newest
+//! timestamped copy).
+//!
+//! The diff runs from the current file to the backup, so `additions` are
+//! lines Restore brings back and `removals` lines it drops. Binary or very
+//! large files are summarized with a note instead of diffed, and long diffs
+//! are cut so the IPC payload stays small.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+use crate::error::CommandError;
+use crate::utils;
+
+/// Files above this are not diffed.
+const MAX_FILE_BYTES: usize = 1024 * 1024;
+/// Longest `unified_diff` returned.
+const MAX_DIFF_CHARS: usize = 64 * 1024;
+/// Most lines listed in each of `additions` / `removals`.
+const MAX_LINES: usize = 500;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigDiff {
+    pub backup_exists: bool,
+    pub backup_path: Option<String>,
+    pub additions: Vec<String>,
+    pub removals: Vec<String>,
+    pub unified_diff: String,
+    /// Some output was cut or skipped; `note` says why.
+    pub truncated: bool,
+    pub note: Option<String>,
+}
+
+/// Decoded text of a file for diffing; `Err` explains why it can't be.
+fn diffable_text(bytes: &[u8], label: &str) -> Result<String, String> {
+    if bytes.len() > MAX_FILE_BYTES {
+        return Err(format!(
+            "{label} is too large to diff ({} KB)",
+            bytes.len() / 1024
+        ));
+    }
+    if bytes.contains(&0) {
+        return Err(format!("{label} looks like a binary file"));
+    }
+    String::from_utf8(bytes.to_vec()).map_err(|_| format!("{label} is not UTF-8 text"))
+}
+
+fn diff_texts(current: &str, backup: &str) -> ConfigDiff {
+    let diff = TextDiff::from_lines(current, backup);
+    let mut result = ConfigDiff {
+        backup_exists: true,
+        ..ConfigDiff::default()
+    };
+    for change in diff.iter_all_changes() {
+        let list = match change.tag() {
+            ChangeTag::Insert => &mut result.additions,
+            ChangeTag::Delete => &mut result.removals,
+            ChangeTag::Equal => continue,
+        };
+        if list.len() < MAX_LINES {
+            list.push(change.value().trim_end_matches(['\r', '\n']).to_string());
+        } else {
+            result.truncated = true;
+        }
+    }
+
+    let mut unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .header("current", "backup")
+        .to_string();
+    if unified.len() > MAX_DIFF_CHARS {
+        let mut cut = MAX_DIFF_CHARS;
+        while !unified.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        unified.truncate(cut);
+        result.truncated = true;
+    }
+    result.unified_diff = unified;
+    if result.truncated {
+        result.note = Some("Diff is too long and was shortened".to_string());
+    }
+    result
+}
+
+/// Diff `config` against `backup`; a missing config diffs as empty.
+fn diff_files(config: &Path, backup: &Path) -> Result<ConfigDiff, CommandError> {
+    let read_failed = |path: &Path, e: std::io::Error| {
+        CommandError::new(
+            "READ_FAILED",
+            format!("Failed to read {}: {e}", path.display()),
+        )
+    };
+    let current = match fs::read(config) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(read_failed(config, e)),
+    };
+    let saved = fs::read(backup).map_err(|e| read_failed(backup, e))?;
+
+    let texts = diffable_text(&current, "The config file")
+        .and_then(|current| Ok((current, diffable_text(&saved, "The backup")?)));
+    let mut result = match texts {
+        Ok((current, saved)) => diff_texts(&current, &saved),
+        Err(note) => ConfigDiff {
+            backup_exists: true,
+            truncated: true,
+            note: Some(note),
+            ..ConfigDiff::default()
+        },
+    };
+    result.backup_path = Some(backup.to_string_lossy().to_string());
+    Ok(result)
+}
+
+/// The managed config file of `app` called `file_name`.
+fn managed_file(app: &str, file_name: &str) -> Result<PathBuf, CommandError> {
+    if !crate::is_known_app(app) {
+        return Err(CommandError::unknown_app(app));
+    }
+    crate::drift::managed_files(app)
+        .into_iter()
+        .find(|p| p.file_name().is_some_and(|n| n == file_name))
+        .ok_or_else(|| {
+            CommandError::invalid_input(format!("{app} has no config file named {file_name}"))
+        })
+}
+
+#[tauri::command]
+pub async fn diff_config_with_backup(
+    app: String,
+    file_name: String,
+) -> Result<ConfigDiff, CommandError> {
+    let config = managed_file(&app, &file_name)?;
+    let backup =
+        utils::find_backup(&config).or_else(|| crate::backup_files::latest_rotated(&config));
+    match backup {
+        Some(backup) => diff_files(&config, &backup),
+        None => Ok(ConfigDiff::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_runs_from_current_to_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("settings.json");
+        let backup = utils::backup_path(&config);
+        fs::write(
+            &config,
+            "{\n  \"url\": \"https://proxy\",\n  \"theme\": \"dark\"\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &backup,
+            "{\n  \"url\": \"https://own\",\n  \"theme\": \"dark\"\n}\n",
+        )
+        .unwrap();
+
+        let diff = diff_files(&config, &backup).unwrap();
+        assert!(diff.backup_exists);
+        assert_eq!(diff.additions, ["  \"url\": \"https://own\","]);
+        assert_eq!(diff.removals, ["  \"url\": \"https://proxy\","]);
+        assert!(diff.unified_diff.contains("+++ backup"));
+        assert!(!diff.truncated);
+
+        // Restore recreates a deleted config
+        fs::remove_file(&config).unwrap();
+        assert_eq!(diff_files(&config, &backup).unwrap().additions.len(), 4);
+
+        fs::write(&config, [0u8, 1, 2]).unwrap();
+        let binary = diff_files(&config, &backup).unwrap();
+        assert!(binary.truncated && binary.unified_diff.is_empty());
+        assert!(binary.note.unwrap().contains("binary"));
+    }
+
+    #[test]
+    fn test_long_diffs_are_cut() {
+        let current: String = (0..2000).map(|i| format!("line {i}\n")).collect();
+        let diff = diff_texts(&current, "");
+        assert_eq!(diff.removals.len(), MAX_LINES);
+        assert!(diff.unified_diff.len() <= MAX_DIFF_CHARS);
+        assert!(diff.truncated && diff.note.is_some());
+    }
+}
@@ -0,0 +1,192 @@
+//! User-overridable config locations.
+//!
+//! Every app resolves its config location the same way:
+//! user override (settings table) → app-specific env var → default home path.
+//! Status, sync and restore all go through these helpers so they never disagree.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use crate::database::{dao::settings, Database};
+use crate::paths;
+
+const SETTINGS_KEY: &str = "config_path_overrides";
+
+/// In-memory copy of the persisted overrides, keyed by app id.
+static OVERRIDES: LazyLock<RwLock<BTreeMap<String, PathBuf>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+fn read_overrides() -> std::sync::RwLockReadGuard<'static, BTreeMap<String, PathBuf>> {
+    OVERRIDES.read().unwrap_or_else(|p| p.into_inner())
+}
+
+fn write_overrides() -> std::sync::RwLockWriteGuard<'static, BTreeMap<String, PathBuf>> {
+    OVERRIDES.write().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Load persisted overrides from the settings table (called once at startup).
+pub fn load(db: &Database) -> Result<(), String> {
+    let map: BTreeMap<String, PathBuf> = match settings::get(db, SETTINGS_KEY)? {
+        Some(raw) => serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid {SETTINGS_KEY} setting: {e}"))?,
+        None => BTreeMap::new(),
+    };
+    if !map.is_empty() {
+        tracing::info!("[config_paths] Loaded {} path override(s)", map.len());
+    }
+    *write_overrides() = map;
+    Ok(())
+}
+
+fn persist(db: &Database, map: &BTreeMap<String, PathBuf>) -> Result<(), String> {
+    let raw = serde_json::to_string(map).map_err(|e| format!("serialize overrides: {e}"))?;
+    settings::set(db, SETTINGS_KEY, &raw)
+}
+
+/// All current overrides as display strings, keyed by app id.
+pub fn list_overrides() -> BTreeMap<String, String> {
+    read_overrides()
+        .iter()
+        .map(|(k, v)| (k.clone(), v.to_string_lossy().to_string()))
+        .collect()
+}
+
+/// The override for `app`, if one is set.
+pub fn get_override(app: &str) -> Option<PathBuf> {
+    read_overrides().get(app).cloned()
+}
+
+/// Validate and persist an override for `app`. Returns the normalised path.
+pub fn set_override(db: &Database, app: &str, path: &str) -> Result<PathBuf, String> {
+    let path = validate_path(path)?;
+    let mut map = read_overrides().clone();
+    map.insert(app.to_string(), path.clone());
+    persist(db, &map)?;
+    *write_overrides() = map;
+    tracing::info!("[config_paths] Override set for {app}: {}", path.display());
+    Ok(path)
+}
+
+pub fn clear_override(db: &Database, app: &str) -> Result<(), String> {
+    let mut map = read_overrides().clone();
+    if map.remove(app).is_none() {
+        return Ok(());
+    }
+    persist(db, &map)?;
+    *write_overrides() = map;
+    tracing::info!("[config_paths] Override cleared for {app}");
+    Ok(())
+}
+
+/// An override must be absolute and either exist already or be creatable,
+/// i.e. its nearest existing ancestor is a writable directory.
+fn validate_path(raw: &str) -> Result<PathBuf, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("Path cannot be empty".to_string());
+    }
+    let path = PathBuf::from(trimmed);
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {trimmed}"));
+    }
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let ancestor = path
+        .ancestors()
+        .skip(1)
+        .find(|a| a.exists())
+        .ok_or_else(|| format!("No existing parent directory for {trimmed}"))?;
+    let meta = std::fs::metadata(ancestor)
+        .map_err(|e| format!("Cannot inspect {}: {e}", ancestor.display()))?;
+    if !meta.is_dir() {
+        return Err(format!("{} is not a directory", ancestor.display()));
+    }
+    if meta.permissions().readonly() {
+        return Err(format!("{} is not writable", ancestor.display()));
+    }
+    Ok(path)
+}
+
+/// User-chosen location for `app`: the override if set, else `env_var` if set
+/// and non-empty. `None` means the app's default home location applies.
+pub fn custom_location(app: &str, env_var: Option<&str>) -> Option<PathBuf> {
+    if let Some(p) = read_overrides().get(app) {
+        return Some(p.clone());
+    }
+    env_var.and_then(paths::cli_env_dir)
+}
+
+/// Resolve a single-file config. A custom location may name the file itself
+/// or the directory that holds it (then the default file name is kept).
+pub fn resolve_file(app: &str, env_var: Option<&str>, default: Option<PathBuf>) -> Option<PathBuf> {
+    match custom_location(app, env_var) {
+        Some(custom) => Some(file_in(&custom, default.as_deref())),
+        None => default,
+    }
+}
+
+fn file_in(custom: &Path, default: Option<&Path>) -> PathBuf {
+    let looks_like_dir = custom.is_dir() || custom.extension().is_none();
+    match default.and_then(|d| d.file_name()) {
+        Some(name) if looks_like_dir => custom.join(name),
+        _ => custom.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_path_rejects_relative_and_empty() {
+        assert!(validate_path("").is_err());
+        assert!(validate_path("relative/dir").is_err());
+    }
+
+    #[test]
+    fn test_validate_path_accepts_creatable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nested = tmp.path().join("a/b/settings.json");
+        let p = validate_path(nested.to_str().unwrap()).unwrap();
+        assert_eq!(p, nested);
+
+        // A regular file cannot act as a parent directory
+        let file = tmp.path().join("plain.txt");
+        std::fs::write(&file, "x").unwrap();
+        assert!(validate_path(file.join("child.json").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_file_in_dir_or_file() {
+        let default = PathBuf::from("/home/u/.factory/settings.json");
+        assert_eq!(
+            file_in(Path::new("/dotfiles/factory"), Some(&default)),
+            PathBuf::from("/dotfiles/factory/settings.json")
+        );
+        assert_eq!(
+            file_in(Path::new("/dotfiles/droid.json"), Some(&default)),
+            PathBuf::from("/dotfiles/droid.json")
+        );
+    }
+
+    #[test]
+    fn test_set_and_clear_override_persists() {
+        let db = Database::memory().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        // Unique app id: the override map is process-global
+        let app = "test-override-app";
+
+        set_override(&db, app, tmp.path().to_str().unwrap()).unwrap();
+        assert_eq!(custom_location(app, None), Some(tmp.path().to_path_buf()));
+        let raw = settings::get(&db, SETTINGS_KEY).unwrap().unwrap();
+        assert!(raw.contains(app));
+
+        clear_override(&db, app).unwrap();
+        assert_eq!(custom_location(app, None), None);
+        let raw = settings::get(&db, SETTINGS_KEY).unwrap().unwrap();
+        assert!(!raw.contains(app));
+    }
+}
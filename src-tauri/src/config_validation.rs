@@ -0,0 +1,720 @@
+//! "Validate all": checks every existing config file of every app.
+//!
+//! Each file must parse in its format (JSON with comments allowed, TOML,
+//! dotenv). The parts the apps and hajimi depend on are then checked for
+//! shape: opencode.json against a bundled subset of opencode's JSON schema,
+//! OpenClaw's providers, Droid's custom models, Codex's `model_providers` and
+//! Claude's `env`. Every problem carries a JSON pointer (`/env/API_TIMEOUT_MS`;
+//! TOML keys are written the same way) and, when known, a line, so the editor
+//! can jump to it.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::CommandError;
+use crate::sync_target;
+use crate::utils::{self, ConfigFormat, EnvFile};
+
+/// Subset of https://opencode.ai/config.json; see its `$comment`.
+const OPENCODE_SCHEMA: &str = include_str!("schemas/opencode.schema.json");
+
+/// Providers Codex knows without a `model_providers` table.
+const CODEX_BUILTIN_PROVIDERS: &[&str] = &["openai", "oss", "ollama", "lmstudio"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    /// JSON pointer to the offending value; "" for the whole file.
+    pub path: String,
+    /// 1-based line, for parse errors and dotenv lines.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigValidation {
+    pub app: String,
+    /// File name, as in the editor's tabs.
+    pub file: String,
+    pub path: String,
+    pub ok: bool,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Collects problems while walking a document.
+#[derive(Default)]
+struct Errors(Vec<ValidationError>);
+
+impl Errors {
+    fn at(&mut self, path: &str, message: impl Into<String>) {
+        self.0.push(ValidationError {
+            path: path.to_string(),
+            line: None,
+            message: message.into(),
+        });
+    }
+
+    fn at_line(&mut self, line: usize, message: impl Into<String>) {
+        self.0.push(ValidationError {
+            path: String::new(),
+            line: Some(line),
+            message: message.into(),
+        });
+    }
+}
+
+/// `parent` + `/key`, escaping `~` and `/` as RFC 6901 requires.
+fn pointer(parent: &str, key: &str) -> String {
+    format!("{parent}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    let actual = type_name(value);
+    actual == name || (name == "number" && actual == "integer")
+}
+
+/// The JSON schema keywords the bundled schema uses: `type`, `enum`,
+/// `required`, `properties`, `additionalProperties` and `items`.
+fn check_schema(schema: &Value, value: &Value, path: &str, errors: &mut Errors) {
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|name| has_type(value, name)) {
+            errors.at(
+                path,
+                format!(
+                    "expected {}, found {}",
+                    names.join(" or "),
+                    type_name(value)
+                ),
+            );
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.at(path, format!("must be one of {}", allowed.join(", ")));
+        }
+    }
+    match value {
+        Value::Object(map) => {
+            for key in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !map.contains_key(key) {
+                    errors.at(path, format!("missing required \"{key}\""));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, child) in map {
+                let child_path = pointer(path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => check_schema(child_schema, child, &child_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.at(&child_path, format!("unknown key \"{key}\""))
+                        }
+                        Some(extra @ Value::Object(_)) => {
+                            check_schema(extra, child, &child_path, errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_schema(item_schema, item, &pointer(path, &i.to_string()), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `value` must be an object; returns it when it is.
+fn object<'a>(
+    value: &'a Value,
+    path: &str,
+    errors: &mut Errors,
+) -> Option<&'a serde_json::Map<String, Value>> {
+    let map = value.as_object();
+    if map.is_none() {
+        errors.at(path, format!("expected object, found {}", type_name(value)));
+    }
+    map
+}
+
+/// Optional `key` of `map` must be a string.
+fn optional_string(
+    map: &serde_json::Map<String, Value>,
+    key: &str,
+    path: &str,
+    errors: &mut Errors,
+) {
+    if let Some(v) = map.get(key).filter(|v| !v.is_string()) {
+        errors.at(
+            &pointer(path, key),
+            format!("expected string, found {}", type_name(v)),
+        );
+    }
+}
+
+/// One of `keys` must hold a non-empty string.
+fn required_string(
+    map: &serde_json::Map<String, Value>,
+    keys: &[&str],
+    path: &str,
+    errors: &mut Errors,
+) {
+    let found = keys.iter().find_map(|key| map.get(*key).map(|v| (*key, v)));
+    match found {
+        Some((_, Value::String(s))) if !s.trim().is_empty() => {}
+        Some((key, Value::String(_))) => errors.at(&pointer(path, key), "must not be empty"),
+        Some((key, v)) => errors.at(
+            &pointer(path, key),
+            format!("expected a non-empty string, found {}", type_name(v)),
+        ),
+        None => errors.at(path, format!("missing \"{}\"", keys.join("\" or \""))),
+    }
+}
+
+fn check_claude_settings(root: &Value, errors: &mut Errors) {
+    let Some(root) = object(root, "", errors) else {
+        return;
+    };
+    let Some(env) = root.get("env") else {
+        return;
+    };
+    // Claude Code passes `env` to the environment, which only holds strings
+    if let Some(env) = object(env, "/env", errors) {
+        for (key, value) in env {
+            if !value.is_string() {
+                errors.at(
+                    &pointer("/env", key),
+                    format!("expected string, found {}", type_name(value)),
+                );
+            }
+        }
+    }
+}
+
+fn check_codex_auth(root: &Value, errors: &mut Errors) {
+    if let Some(root) = object(root, "", errors) {
+        if root.get("OPENAI_API_KEY").is_some_and(|v| !v.is_null()) {
+            optional_string(root, "OPENAI_API_KEY", "", errors);
+        }
+    }
+}
+
+fn check_gemini_settings(root: &Value, errors: &mut Errors) {
+    if object(root, "", errors).is_none() {
+        return;
+    }
+    if let Some(v) = root
+        .pointer("/security/auth/selectedType")
+        .filter(|v| !v.is_string())
+    {
+        errors.at(
+            "/security/auth/selectedType",
+            format!("expected string, found {}", type_name(v)),
+        );
+    }
+}
+
+fn check_opencode_config(root: &Value, errors: &mut Errors) {
+    let schema: Value = serde_json::from_str(OPENCODE_SCHEMA).expect("bundled schema is JSON");
+    check_schema(&schema, root, "", errors);
+    // Models are referenced as provider/model
+    for key in ["model", "small_model"] {
+        if let Some(model) = root.get(key).and_then(Value::as_str) {
+            if !model.contains('/') {
+                errors.at(
+                    &pointer("", key),
+                    format!("\"{model}\" should be provider/model"),
+                );
+            }
+        }
+    }
+}
+
+fn check_opencode_auth(root: &Value, errors: &mut Errors) {
+    let Some(root) = object(root, "", errors) else {
+        return;
+    };
+    for (provider, entry) in root {
+        let path = pointer("", provider);
+        let Some(entry) = object(entry, &path, errors) else {
+            continue;
+        };
+        required_string(entry, &["type"], &path, errors);
+        if entry.get("type").and_then(Value::as_str) == Some("api") {
+            required_string(entry, &["key"], &path, errors);
+        }
+    }
+}
+
+fn check_openclaw(root: &Value, errors: &mut Errors) {
+    let Some(root) = object(root, "", errors) else {
+        return;
+    };
+    let Some(models) = root.get("models") else {
+        return;
+    };
+    let Some(models) = object(models, "/models", errors) else {
+        return;
+    };
+    let Some(providers) = models.get("providers") else {
+        return;
+    };
+    let Some(providers) = object(providers, "/models/providers", errors) else {
+        return;
+    };
+    for (id, provider) in providers {
+        let path = pointer("/models/providers", id);
+        let Some(provider) = object(provider, &path, errors) else {
+            continue;
+        };
+        required_string(provider, &["baseUrl"], &path, errors);
+        optional_string(provider, "apiKey", &path, errors);
+        optional_string(provider, "api", &path, errors);
+        let Some(list) = provider.get("models") else {
+            continue;
+        };
+        let list_path = pointer(&path, "models");
+        match list.as_array() {
+            Some(list) => {
+                for (i, model) in list.iter().enumerate() {
+                    let model_path = pointer(&list_path, &i.to_string());
+                    if let Some(model) = object(model, &model_path, errors) {
+                        required_string(model, &["id"], &model_path, errors);
+                    }
+                }
+            }
+            None => errors.at(
+                &list_path,
+                format!("expected array, found {}", type_name(list)),
+            ),
+        }
+    }
+}
+
+fn check_droid(root: &Value, errors: &mut Errors) {
+    let Some(root) = object(root, "", errors) else {
+        return;
+    };
+    // Both layouts Droid has used, with their field names
+    let layouts: [(&str, [&[&str]; 3]); 2] = [
+        (
+            "customModels",
+            [&["model", "id"], &["baseUrl"], &["apiKey"]],
+        ),
+        ("custom_models", [&["model"], &["base_url"], &["api_key"]]),
+    ];
+    for (key, fields) in layouts {
+        let Some(list) = root.get(key) else {
+            continue;
+        };
+        let path = pointer("", key);
+        let Some(list) = list.as_array() else {
+            errors.at(&path, format!("expected array, found {}", type_name(list)));
+            continue;
+        };
+        for (i, entry) in list.iter().enumerate() {
+            let entry_path = pointer(&path, &i.to_string());
+            if let Some(entry) = object(entry, &entry_path, errors) {
+                for keys in fields {
+                    required_string(entry, keys, &entry_path, errors);
+                }
+            }
+        }
+    }
+}
+
+fn check_codex_config(doc: &toml_edit::DocumentMut, errors: &mut Errors) {
+    let mut defined: Vec<String> = CODEX_BUILTIN_PROVIDERS
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    if let Some(item) = doc.get("model_providers") {
+        match item.as_table_like() {
+            Some(table) => {
+                for (id, provider) in table.iter() {
+                    let path = pointer("/model_providers", id);
+                    defined.push(id.to_string());
+                    let Some(provider) = provider.as_table_like() else {
+                        errors.at(&path, "expected a table");
+                        continue;
+                    };
+                    let string = |key: &str| provider.get(key).map(|v| v.as_str().is_some());
+                    if string("name") != Some(true) {
+                        errors.at(&pointer(&path, "name"), "missing or not a string");
+                    }
+                    for key in ["base_url", "env_key", "wire_api"] {
+                        if string(key) == Some(false) {
+                            errors.at(&pointer(&path, key), "expected string");
+                        }
+                    }
+                    if let Some(wire_api) = provider.get("wire_api").and_then(|v| v.as_str()) {
+                        if !["chat", "responses"].contains(&wire_api) {
+                            errors.at(
+                                &pointer(&path, "wire_api"),
+                                "must be \"chat\" or \"responses\"",
+                            );
+                        }
+                    }
+                }
+            }
+            None => errors.at("/model_providers", "expected a table"),
+        }
+    }
+
+    // `model_provider` at the top level and in each profile must name a provider
+    let mut references = vec![("/model_provider".to_string(), doc.get("model_provider"))];
+    if let Some(profiles) = doc.get("profiles").and_then(|p| p.as_table_like()) {
+        for (name, profile) in profiles.iter() {
+            let path = pointer("/profiles", name);
+            references.push((
+                pointer(&path, "model_provider"),
+                profile
+                    .as_table_like()
+                    .and_then(|p| p.get("model_provider")),
+            ));
+        }
+    }
+    for (path, item) in references {
+        let Some(item) = item else {
+            continue;
+        };
+        match item.as_str() {
+            Some(id) if defined.iter().any(|d| d == id) => {}
+            Some(id) => errors.at(
+                &path,
+                format!("\"{id}\" is not defined under [model_providers]"),
+            ),
+            None => errors.at(&path, "expected string"),
+        }
+    }
+}
+
+/// 1-based line of byte `offset` in `content`.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Problems with `content`, the text of `app`'s config at `path`.
+pub fn validate_content(app: &str, path: &Path, content: &str) -> Vec<ValidationError> {
+    let mut errors = Errors::default();
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match ConfigFormat::from_path(path) {
+        ConfigFormat::Env => {
+            for line in EnvFile::parse(content).malformed_lines() {
+                errors.at_line(line, "not a KEY=value line");
+            }
+        }
+        ConfigFormat::Toml => match content.parse::<toml_edit::DocumentMut>() {
+            Ok(doc) => {
+                if app == "codex" {
+                    check_codex_config(&doc, &mut errors);
+                }
+            }
+            Err(e) => {
+                let line = e.span().map(|span| line_of(content, span.start));
+                errors.0.push(ValidationError {
+                    path: String::new(),
+                    line,
+                    message: format!("invalid TOML: {}", e.message()),
+                });
+            }
+        },
+        ConfigFormat::Json => match utils::parse_json_lenient(content) {
+            Ok(root) => match (app, file_name.as_str()) {
+                ("claude", "settings.json") => check_claude_settings(&root, &mut errors),
+                ("codex", "auth.json") => check_codex_auth(&root, &mut errors),
+                ("gemini", _) => check_gemini_settings(&root, &mut errors),
+                ("opencode", "auth.json") => check_opencode_auth(&root, &mut errors),
+                ("opencode", _) => check_opencode_config(&root, &mut errors),
+                ("openclaw", _) => check_openclaw(&root, &mut errors),
+                ("droid", _) => check_droid(&root, &mut errors),
+                // Jan's legacy engine file and others have their own layouts;
+                // parsing is all that is checked
+                _ => {}
+            },
+            Err(e) => errors.0.push(ValidationError {
+                path: String::new(),
+                line: Some(e.line()).filter(|l| *l > 0),
+                message: format!("invalid JSON: {e}"),
+            }),
+        },
+    }
+    errors.0
+}
+
+/// Every app's existing config files. A file shared by two apps (the Claude
+/// extension uses Claude Code's settings.json) is listed once.
+fn config_files() -> Vec<(String, PathBuf)> {
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    for target in sync_target::all() {
+        for path in target.config_paths() {
+            if path.is_file() && seen.insert(path.clone()) {
+                files.push((target.id().to_string(), path));
+            }
+        }
+    }
+    files
+}
+
+pub fn validate_all() -> Vec<ConfigValidation> {
+    config_files()
+        .into_iter()
+        .map(|(app, path)| {
+            let errors = match fs::read_to_string(&path) {
+                Ok(content) => validate_content(&app, &path, &content),
+                Err(e) => vec![ValidationError {
+                    path: String::new(),
+                    line: None,
+                    message: format!("cannot read the file: {e}"),
+                }],
+            };
+            if !errors.is_empty() {
+                tracing::info!(
+                    "[config_validation] {} has {} problem(s)",
+                    path.display(),
+                    errors.len()
+                );
+            }
+            ConfigValidation {
+                file: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+                ok: errors.is_empty(),
+                errors,
+                app,
+            }
+        })
+        .collect()
+}
+
+/// Check every existing config file; see the module docs.
+#[tauri::command]
+pub async fn validate_configs() -> Result<Vec<ConfigValidation>, CommandError> {
+    Ok(utils::run_blocking(validate_all).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths;
+
+    fn errors(app: &str, file: &str, content: &str) -> Vec<(String, String)> {
+        validate_content(app, Path::new(file), content)
+            .into_iter()
+            .map(|e| (e.path, e.message))
+            .collect()
+    }
+
+    fn paths_of(app: &str, file: &str, content: &str) -> Vec<String> {
+        errors(app, file, content)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_errors_have_lines() {
+        let json = validate_content("claude", Path::new("settings.json"), "{\n  \"env\": {\n}}}");
+        assert_eq!(json.len(), 1);
+        assert_eq!(json[0].line, Some(3));
+        assert!(json[0].message.starts_with("invalid JSON"));
+
+        let toml = validate_content("codex", Path::new("config.toml"), "model = 1\n[broken\n");
+        assert_eq!(toml[0].line, Some(2));
+
+        let env = validate_content("gemini", Path::new(".env"), "# c\nA=1\n\nnot an entry\n");
+        assert_eq!(env.len(), 1);
+        assert_eq!(env[0].line, Some(4));
+
+        // Comments and trailing commas are fine, as the apps accept them
+        assert!(errors("claude", "settings.json", "{ // c\n \"env\": {},\n}").is_empty());
+    }
+
+    #[test]
+    fn test_claude_env_must_hold_strings() {
+        assert_eq!(
+            paths_of(
+                "claude",
+                "settings.json",
+                r#"{"env": {"ANTHROPIC_BASE_URL": "https://x", "API_TIMEOUT_MS": 600000}}"#
+            ),
+            vec!["/env/API_TIMEOUT_MS"]
+        );
+        assert_eq!(
+            paths_of("claude", "settings.json", r#"{"env": []}"#),
+            vec!["/env"]
+        );
+        assert_eq!(
+            paths_of("claude", ".claude.json", "[]"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_opencode_schema() {
+        let config = r#"{
+            "$schema": "https://opencode.ai/config.json",
+            "model": "gpt-4o",
+            "share": "sometimes",
+            "provider": {
+                "hajimi": {"npm": "@ai-sdk/openai", "options": {"baseURL": 5}},
+                "a/b": "x"
+            },
+            "mcp": {"fs": {"command": ["npx"]}},
+            "instructions": ["ok", 1],
+            "newer_key": true
+        }"#;
+        let mut found = paths_of("opencode", "opencode.json", config);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                "/instructions/1",
+                "/mcp/fs",
+                "/model",
+                "/provider/a~1b",
+                "/provider/hajimi/options/baseURL",
+                "/share",
+            ]
+        );
+        assert!(paths_of(
+            "opencode",
+            "auth.json",
+            r#"{"hajimi": {"type": "api", "key": "sk"}, "x": {"type": "api"}}"#
+        )
+        .contains(&"/x".to_string()));
+    }
+
+    #[test]
+    fn test_openclaw_and_droid_shapes() {
+        let openclaw = r#"{"models": {"providers": {
+            "hajimi": {"baseUrl": "https://x/v1", "apiKey": "sk", "models": [{"id": "m"}, {"name": "n"}]},
+            "bad": {"apiKey": 1, "models": {}}
+        }}}"#;
+        let mut found = paths_of("openclaw", "openclaw.json", openclaw);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                "/models/providers/bad",
+                "/models/providers/bad/apiKey",
+                "/models/providers/bad/models",
+                "/models/providers/hajimi/models/1",
+            ]
+        );
+
+        let droid = r#"{
+            "customModels": [{"id": "custom:a", "baseUrl": "https://x", "apiKey": "sk"}, {"model": "b", "apiKey": ""}],
+            "custom_models": [{"model": "c", "base_url": "https://x", "api_key": "sk"}, "d"]
+        }"#;
+        let found = errors("droid", "settings.json", droid);
+        let found: Vec<&str> = found.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            found,
+            vec![
+                "/customModels/1",
+                "/customModels/1/apiKey",
+                "/custom_models/1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_codex_model_providers() {
+        let config = r#"
+model_provider = "hajimi"
+
+[model_providers.hajimi]
+name = "hajimi"
+base_url = "https://x/v1"
+wire_api = "sse"
+
+[model_providers.broken]
+base_url = 1
+
+[profiles.work]
+model_provider = "missing"
+
+[profiles.local]
+model_provider = "ollama"
+"#;
+        let mut found = paths_of("codex", "config.toml", config);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                "/model_providers/broken/base_url",
+                "/model_providers/broken/name",
+                "/model_providers/hajimi/wire_api",
+                "/profiles/work/model_provider",
+            ]
+        );
+        assert_eq!(
+            paths_of("codex", "config.toml", "model_provider = \"nope\"\n"),
+            vec!["/model_provider"]
+        );
+    }
+
+    #[test]
+    fn test_validate_all_lists_existing_files() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = paths::override_home(home.path());
+        let claude = home.path().join(".claude");
+        fs::create_dir_all(&claude).unwrap();
+        fs::write(claude.join("settings.json"), r#"{"env": {"A": 1}}"#).unwrap();
+        let gemini = home.path().join(".gemini");
+        fs::create_dir_all(&gemini).unwrap();
+        fs::write(gemini.join(".env"), "GEMINI_API_KEY=sk\n").unwrap();
+
+        let results = validate_all();
+        let settings: Vec<_> = results
+            .iter()
+            .filter(|r| r.path == claude.join("settings.json").to_string_lossy())
+            .collect();
+        // Listed once although the Claude extension shares it
+        assert_eq!(settings.len(), 1);
+        assert_eq!(settings[0].app, "claude");
+        assert!(!settings[0].ok);
+        let env = results.iter().find(|r| r.file == ".env").unwrap();
+        assert!(env.ok);
+        assert!(results.iter().all(|r| Path::new(&r.path).is_file()));
+    }
+}
@@ -0,0 +1,533 @@
+//! Deep connection checks against a proxy.
+//!
+//! A 200 from `/v1/models` only proves the proxy is reachable and the key is
+//! accepted; the chat probe sends a one-token completion to confirm requests
+//! actually go through. The per-CLI check speaks each tool's own wire format,
+//! since a proxy can serve chat completions yet break on `/v1/messages`.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::model_list::{self, ModelListFlavor};
+use crate::utils;
+
+const CHAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a probe failed, so the UI can tell "slow" from "broken".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeErrorKind {
+    Timeout,
+    RateLimited,
+    Auth,
+    Connect,
+    Http,
+    NoModel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeError {
+    pub kind: ProbeErrorKind,
+    pub message: String,
+}
+
+impl ProbeError {
+    fn new(kind: ProbeErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn from_reqwest(e: &reqwest::Error, timeout: Duration) -> Self {
+        if e.is_timeout() {
+            Self::new(
+                ProbeErrorKind::Timeout,
+                format!("Request timed out ({}s)", timeout.as_secs()),
+            )
+        } else if e.is_connect() {
+            Self::new(
+                ProbeErrorKind::Connect,
+                format!("Cannot connect to server: {e}"),
+            )
+        } else {
+            Self::new(ProbeErrorKind::Http, format!("Request failed: {e}"))
+        }
+    }
+
+    /// Classify a non-success response. The body is truncated so large error
+    /// pages don't end up in the UI.
+    pub(crate) fn from_status(status: reqwest::StatusCode, body: &str) -> Self {
+        let summary: String = body.chars().take(200).collect();
+        let kind = match status.as_u16() {
+            429 => ProbeErrorKind::RateLimited,
+            401 | 403 => ProbeErrorKind::Auth,
+            _ => ProbeErrorKind::Http,
+        };
+        Self::new(kind, format!("Server returned {status}: {summary}"))
+    }
+}
+
+/// Result of `test_connection_deep`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionReport {
+    pub models_ok: bool,
+    pub chat_ok: bool,
+    pub latency_ms: Option<u64>,
+    pub model_used: Option<String>,
+    pub error: Option<ProbeError>,
+}
+
+/// First model that can plausibly serve chat completions.
+fn pick_probe_model(models: &[String]) -> Option<String> {
+    const NON_CHAT: [&str; 6] = ["embed", "image", "tts", "whisper", "moderation", "dall-e"];
+    models
+        .iter()
+        .find(|id| {
+            let lower = id.to_ascii_lowercase();
+            !NON_CHAT.iter().any(|kw| lower.contains(kw))
+        })
+        .or_else(|| models.first())
+        .cloned()
+}
+
+fn chat_probe_body(model: &str) -> Value {
+    json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "ping"}],
+        "max_tokens": 1,
+        "stream": false
+    })
+}
+
+/// Send a one-token chat completion. Returns latency and the model the
+/// server reports having used (falls back to the requested one).
+async fn probe_chat(
+    base_url: &str,
+    api_key: &str,
+    model: &str,
+) -> Result<(u64, String), ProbeError> {
+    let url = format!("{}/v1/chat/completions", base_url.trim_end_matches('/'));
+    let client =
+        utils::api_client(CHAT_TIMEOUT).map_err(|e| ProbeError::new(ProbeErrorKind::Http, e))?;
+
+    let started = Instant::now();
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {api_key}"))
+        .json(&chat_probe_body(model))
+        .send()
+        .await
+        .map_err(|e| ProbeError::from_reqwest(&e, CHAT_TIMEOUT))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    if !status.is_success() {
+        return Err(ProbeError::from_status(status, &body));
+    }
+    let echoed = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| v.get("model").and_then(|m| m.as_str()).map(String::from))
+        .unwrap_or_else(|| model.to_string());
+    Ok((latency_ms, echoed))
+}
+
+/// List models, then send a minimal chat completion to `model` (or the first
+/// chat-capable model listed). Failures are reported in the result, not as `Err`.
+pub async fn test_connection_deep(
+    base_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+) -> ConnectionReport {
+    let mut report = ConnectionReport {
+        models_ok: false,
+        chat_ok: false,
+        latency_ms: None,
+        model_used: None,
+        error: None,
+    };
+
+    let listed = model_list::fetch_model_list(base_url, api_key, ModelListFlavor::Openai)
+        .await
+        .map(|fetched| fetched.models);
+    report.models_ok = listed.is_ok();
+    let listing_error = listed.as_ref().err().cloned();
+
+    let requested = model.map(str::trim).filter(|m| !m.is_empty());
+    let probe_model = match requested {
+        Some(m) => m.to_string(),
+        None => match listed.as_deref().ok().and_then(pick_probe_model) {
+            Some(m) => m,
+            None => {
+                let message = listing_error
+                    .unwrap_or_else(|| "Proxy returned no models to probe".to_string());
+                report.error = Some(ProbeError::new(ProbeErrorKind::NoModel, message));
+                return report;
+            }
+        },
+    };
+
+    match probe_chat(base_url, api_key, &probe_model).await {
+        Ok((latency_ms, used)) => {
+            report.chat_ok = true;
+            report.latency_ms = Some(latency_ms);
+            report.model_used = Some(used);
+        }
+        Err(e) => {
+            tracing::warn!("[connection_test] Chat probe failed ({:?})", e.kind);
+            report.model_used = Some(probe_model);
+            report.error = Some(e);
+        }
+    }
+    report
+}
+
+/// Quick reachability check: `GET /v1/models` with the key. This is what the
+/// "Test" button and the background health poller run.
+pub async fn check_models_endpoint(base_url: &str, api_key: &str) -> Result<(), String> {
+    let models_url = format!("{}/v1/models", base_url.trim_end_matches('/'));
+    let client = utils::api_client(Duration::from_secs(10))?;
+
+    let response = client
+        .get(&models_url)
+        .header("Authorization", format!("Bearer {api_key}"))
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "Connection timed out (10s). Check the URL.".to_string()
+            } else if e.is_connect() {
+                format!("Cannot connect to server: {e}")
+            } else {
+                format!("Request failed: {e}")
+            }
+        })?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status.as_u16() == 401 || status.as_u16() == 403 {
+        Err("Invalid API key (401/403)".to_string())
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        // SECURITY: Truncate body to prevent leaking large error pages
+        let summary: String = body.chars().take(200).collect();
+        Err(format!("Server returned {status}: {summary}"))
+    }
+}
+
+/// Wire formats the synced CLIs actually use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireProtocol {
+    AnthropicMessages,
+    OpenaiResponses,
+    OpenaiChat,
+    GeminiGenerateContent,
+}
+
+impl WireProtocol {
+    /// Protocols exercised by `app`. Droid routes models to both Anthropic
+    /// and OpenAI endpoints, so it gets two checks.
+    pub fn for_app(app: &str) -> Vec<Self> {
+        match app {
+            "claude" | "claude-vscode" => vec![Self::AnthropicMessages],
+            "codex" => vec![Self::OpenaiResponses],
+            "gemini" => vec![Self::GeminiGenerateContent],
+            "droid" => vec![Self::AnthropicMessages, Self::OpenaiChat],
+            _ => vec![Self::OpenaiChat],
+        }
+    }
+
+    /// Model used when the caller doesn't pick one and listing fails.
+    fn fallback_model(self) -> &'static str {
+        match self {
+            Self::AnthropicMessages => "claude-sonnet-4-5",
+            Self::GeminiGenerateContent => "gemini-2.5-flash",
+            Self::OpenaiResponses | Self::OpenaiChat => "gpt-4o",
+        }
+    }
+
+    /// Prefer a listed model from the protocol's own family.
+    fn pick_model(self, listed: &[String]) -> String {
+        let family = match self {
+            Self::AnthropicMessages => Some("claude"),
+            Self::GeminiGenerateContent => Some("gemini"),
+            Self::OpenaiResponses | Self::OpenaiChat => None,
+        };
+        family
+            .and_then(|f| listed.iter().find(|id| id.contains(f)).cloned())
+            .or_else(|| pick_probe_model(listed))
+            .unwrap_or_else(|| self.fallback_model().to_string())
+    }
+
+    /// Endpoint for `proxy_url` as written into the app's config (see `get_proxy_url`).
+    fn endpoint(self, proxy_url: &str, model: &str) -> String {
+        let url = proxy_url.trim_end_matches('/');
+        let v1 = if url.ends_with("/v1") {
+            url.to_string()
+        } else {
+            format!("{url}/v1")
+        };
+        match self {
+            Self::AnthropicMessages => format!("{v1}/messages"),
+            Self::OpenaiResponses => format!("{v1}/responses"),
+            Self::OpenaiChat => format!("{v1}/chat/completions"),
+            Self::GeminiGenerateContent => {
+                let root = url.strip_suffix("/v1").unwrap_or(url);
+                format!("{root}/v1beta/models/{model}:generateContent")
+            }
+        }
+    }
+
+    fn body(self, model: &str) -> Value {
+        match self {
+            Self::AnthropicMessages => json!({
+                "model": model,
+                "max_tokens": 1,
+                "messages": [{"role": "user", "content": "ping"}]
+            }),
+            // The Responses API rejects max_output_tokens below 16.
+            Self::OpenaiResponses => json!({
+                "model": model,
+                "input": "ping",
+                "max_output_tokens": 16
+            }),
+            Self::OpenaiChat => chat_probe_body(model),
+            Self::GeminiGenerateContent => json!({
+                "contents": [{"role": "user", "parts": [{"text": "ping"}]}],
+                "generationConfig": {"maxOutputTokens": 1}
+            }),
+        }
+    }
+
+    fn authorize(self, req: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+        match self {
+            Self::AnthropicMessages => req
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01"),
+            Self::GeminiGenerateContent => req.header("x-goog-api-key", api_key),
+            Self::OpenaiResponses | Self::OpenaiChat => {
+                req.header("Authorization", format!("Bearer {api_key}"))
+            }
+        }
+    }
+}
+
+/// Outcome of one protocol check in `test_connection_for`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolCheck {
+    pub protocol: WireProtocol,
+    pub endpoint: String,
+    pub model: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    /// First 200 chars of the response body.
+    pub body: String,
+    pub error: Option<ProbeError>,
+}
+
+async fn probe_protocol(
+    client: &reqwest::Client,
+    protocol: WireProtocol,
+    proxy_url: &str,
+    api_key: &str,
+    model: String,
+) -> ProtocolCheck {
+    let endpoint = protocol.endpoint(proxy_url, &model);
+    let mut check = ProtocolCheck {
+        protocol,
+        endpoint: endpoint.clone(),
+        model,
+        ok: false,
+        status: None,
+        latency_ms: None,
+        body: String::new(),
+        error: None,
+    };
+
+    let started = Instant::now();
+    let request = protocol
+        .authorize(client.post(&endpoint), api_key)
+        .json(&protocol.body(&check.model));
+    let response = match request.send().await {
+        Ok(r) => r,
+        Err(e) => {
+            check.error = Some(ProbeError::from_reqwest(&e, CHAT_TIMEOUT));
+            return check;
+        }
+    };
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    check.latency_ms = Some(started.elapsed().as_millis() as u64);
+    check.status = Some(status.as_u16());
+    check.body = body.chars().take(200).collect();
+    if status.is_success() {
+        check.ok = true;
+    } else {
+        check.error = Some(ProbeError::from_status(status, &body));
+    }
+    check
+}
+
+/// Exercise every wire protocol `app` uses against `proxy_url`.
+/// Without an explicit model, one is picked from the proxy's listing.
+pub async fn test_connection_for(
+    app: &str,
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+) -> Result<Vec<ProtocolCheck>, String> {
+    let protocols = WireProtocol::for_app(app);
+    let requested = model.map(str::trim).filter(|m| !m.is_empty());
+    let listed = if requested.is_some() {
+        Vec::new()
+    } else {
+        let root = proxy_url.trim_end_matches('/');
+        let root = root.strip_suffix("/v1").unwrap_or(root);
+        model_list::fetch_model_list(root, api_key, ModelListFlavor::Openai)
+            .await
+            .map(|fetched| fetched.models)
+            .unwrap_or_default()
+    };
+
+    let client = utils::api_client(CHAT_TIMEOUT)?;
+
+    let mut checks = Vec::with_capacity(protocols.len());
+    for protocol in protocols {
+        let model = requested
+            .map(str::to_string)
+            .unwrap_or_else(|| protocol.pick_model(&listed));
+        let check = probe_protocol(&client, protocol, proxy_url, api_key, model).await;
+        if !check.ok {
+            tracing::warn!(
+                "[connection_test] {app} {:?} probe failed (status {:?})",
+                protocol,
+                check.status
+            );
+        }
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_probe_model_skips_non_chat() {
+        let models = vec![
+            "text-embedding-3-small".to_string(),
+            "gpt-image-1".to_string(),
+            "claude-sonnet-4-5".to_string(),
+        ];
+        assert_eq!(
+            pick_probe_model(&models).as_deref(),
+            Some("claude-sonnet-4-5")
+        );
+        assert_eq!(
+            pick_probe_model(&["text-embedding-3-small".to_string()]).as_deref(),
+            Some("text-embedding-3-small")
+        );
+        assert!(pick_probe_model(&[]).is_none());
+    }
+
+    #[test]
+    fn test_chat_probe_body_is_minimal() {
+        let body = chat_probe_body("gpt-4o");
+        assert_eq!(body["model"], "gpt-4o");
+        assert_eq!(body["max_tokens"], 1);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_status_classification() {
+        use reqwest::StatusCode;
+        let long_body = "x".repeat(1000);
+        let e = ProbeError::from_status(StatusCode::TOO_MANY_REQUESTS, &long_body);
+        assert_eq!(e.kind, ProbeErrorKind::RateLimited);
+        assert!(e.message.len() < 300);
+        assert_eq!(
+            ProbeError::from_status(StatusCode::UNAUTHORIZED, "").kind,
+            ProbeErrorKind::Auth
+        );
+        assert_eq!(
+            ProbeError::from_status(StatusCode::BAD_GATEWAY, "").kind,
+            ProbeErrorKind::Http
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deep_probe_unreachable_reports_connect() {
+        let report = test_connection_deep("http://127.0.0.1:1", "sk-test", Some("gpt-4o")).await;
+        assert!(!report.models_ok);
+        assert!(!report.chat_ok);
+        assert_eq!(report.model_used.as_deref(), Some("gpt-4o"));
+        assert_eq!(report.error.unwrap().kind, ProbeErrorKind::Connect);
+    }
+
+    #[test]
+    fn test_protocol_endpoints_follow_proxy_url() {
+        // Claude and Gemini get the bare root, Codex the /v1 root (see get_proxy_url)
+        assert_eq!(
+            WireProtocol::AnthropicMessages.endpoint("http://localhost:8045", "m"),
+            "http://localhost:8045/v1/messages"
+        );
+        assert_eq!(
+            WireProtocol::OpenaiResponses.endpoint("http://localhost:8045/v1", "m"),
+            "http://localhost:8045/v1/responses"
+        );
+        assert_eq!(
+            WireProtocol::GeminiGenerateContent
+                .endpoint("http://localhost:8045/", "gemini-2.5-pro"),
+            "http://localhost:8045/v1beta/models/gemini-2.5-pro:generateContent"
+        );
+        assert_eq!(
+            WireProtocol::OpenaiChat.endpoint("http://localhost:8045/v1", "m"),
+            "http://localhost:8045/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_protocol_for_app_and_model_pick() {
+        assert_eq!(
+            WireProtocol::for_app("codex"),
+            vec![WireProtocol::OpenaiResponses]
+        );
+        assert_eq!(WireProtocol::for_app("droid").len(), 2);
+        assert_eq!(
+            WireProtocol::for_app("chatbox"),
+            vec![WireProtocol::OpenaiChat]
+        );
+
+        let listed = vec!["gpt-4o".to_string(), "claude-opus-4-1".to_string()];
+        assert_eq!(
+            WireProtocol::AnthropicMessages.pick_model(&listed),
+            "claude-opus-4-1"
+        );
+        assert_eq!(WireProtocol::OpenaiChat.pick_model(&listed), "gpt-4o");
+        assert_eq!(
+            WireProtocol::GeminiGenerateContent.pick_model(&[]),
+            "gemini-2.5-flash"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_for_unreachable() {
+        let checks = test_connection_for("gemini", "http://127.0.0.1:1", "sk-test", Some("g"))
+            .await
+            .unwrap();
+        assert_eq!(checks.len(), 1);
+        assert!(!checks[0].ok);
+        assert!(checks[0].status.is_none());
+        assert_eq!(
+            checks[0].error.as_ref().unwrap().kind,
+            ProbeErrorKind::Connect
+        );
+    }
+}
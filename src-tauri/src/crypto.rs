@@ -0,0 +1,159 @@
+//! Local encryption for secrets we keep in SQLite (the account session cookie).
+//!
+//! The key is 32 random bytes in `secret.key` next to providers.db, readable
+//! only by the owner. That keeps secrets out of DB copies and backups; it is
+//! not meant to stop someone who can already read the user's data directory.
+//!
+//! Exported bundles use a key derived from a passphrase instead (Argon2id),
+//! since they have to open on another machine.
+
+use std::path::{Path, PathBuf};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::database::Database;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// `secret.key` next to the provider database.
+pub fn default_key_path() -> Option<PathBuf> {
+    Database::default_path().and_then(|db| db.parent().map(|dir| dir.join("secret.key")))
+}
+
+pub struct SecretKey(Key);
+
+impl SecretKey {
+    pub fn generate() -> Self {
+        Self(ChaCha20Poly1305::generate_key(&mut OsRng))
+    }
+
+    /// Read the key at `path`, creating it on first use.
+    pub fn load_or_create(path: &Path) -> Result<Self, String> {
+        match std::fs::read(path) {
+            Ok(bytes) if bytes.len() == KEY_LEN => return Ok(Self(*Key::from_slice(&bytes))),
+            Ok(_) => tracing::warn!("[crypto] {:?} has the wrong length, replacing it", path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        }
+        let key = Self::generate();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        write_private(path, key.0.as_slice())
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        Ok(key)
+    }
+
+    /// Nonce followed by ciphertext.
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = ChaCha20Poly1305::new(&self.0)
+            .encrypt(&nonce, plaintext)
+            .expect("encrypting into a Vec cannot fail");
+        let mut out = nonce.to_vec();
+        out.extend(ciphertext);
+        out
+    }
+
+    fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, String> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+            .map_err(|e| format!("Failed to derive key: {e}"))?;
+        Ok(Self(key))
+    }
+
+    /// Inverse of `seal`; fails for data sealed under another key or tampered with.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < NONCE_LEN {
+            return Err("Sealed data is truncated".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        ChaCha20Poly1305::new(&self.0)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Failed to decrypt sealed data".to_string())
+    }
+}
+
+/// Salt, then `seal` output under a key derived from `passphrase`.
+pub fn seal_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut out = salt.to_vec();
+    out.extend(SecretKey::from_passphrase(passphrase, &salt)?.seal(plaintext));
+    Ok(out)
+}
+
+/// Inverse of `seal_with_passphrase`; a wrong passphrase fails like tampering.
+pub fn open_with_passphrase(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < SALT_LEN {
+        return Err("Sealed data is truncated".to_string());
+    }
+    let (salt, rest) = sealed.split_at(SALT_LEN);
+    SecretKey::from_passphrase(passphrase, salt)?.open(rest)
+}
+
+#[cfg(unix)]
+pub(crate) fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(bytes)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_roundtrip_and_key_persistence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("secret.key");
+        let key = SecretKey::load_or_create(&path).unwrap();
+        let sealed = key.seal(b"session=abc");
+        assert_ne!(&sealed[NONCE_LEN..], b"session=abc");
+
+        let reloaded = SecretKey::load_or_create(&path).unwrap();
+        assert_eq!(reloaded.open(&sealed).unwrap(), b"session=abc");
+        assert!(SecretKey::generate().open(&sealed).is_err());
+        assert!(key.open(&sealed[..4]).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_passphrase_roundtrip() {
+        let sealed = seal_with_passphrase("correct horse", b"providers").unwrap();
+        assert_eq!(
+            open_with_passphrase("correct horse", &sealed).unwrap(),
+            b"providers"
+        );
+        assert!(open_with_passphrase("wrong", &sealed).is_err());
+        // Fresh salt each time
+        assert_ne!(
+            sealed,
+            seal_with_passphrase("correct horse", b"providers").unwrap()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::crypto::SecretKey;
+use crate::database::{lock_conn, Database};
+
+/// A logged-in platform account, kept across restarts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedAccount {
+    pub id: i64,
+    pub base_url: String,
+    pub username: String,
+    pub user_id: i64,
+    pub session_cookie: String,
+    pub is_active: bool,
+    pub saved_at: i64, // Unix seconds
+}
+
+/// Save the session for (`base_url`, `username`), replacing an older one for
+/// the same account, and make it the active account. Returns its row id.
+/// The cookie is stored sealed with `key`.
+pub fn upsert(
+    db: &Database,
+    key: &SecretKey,
+    base_url: &str,
+    username: &str,
+    user_id: i64,
+    session_cookie: &str,
+) -> Result<i64, String> {
+    let mut conn = lock_conn!(db.conn);
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("accounts upsert begin: {e}"))?;
+    let id: i64 = tx
+        .query_row(
+            "INSERT INTO accounts (base_url, username, user_id, session_cookie, saved_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(base_url, username) DO UPDATE SET
+                 user_id        = excluded.user_id,
+                 session_cookie = excluded.session_cookie,
+                 saved_at       = excluded.saved_at
+             RETURNING id",
+            rusqlite::params![
+                base_url,
+                username,
+                user_id,
+                key.seal(session_cookie.as_bytes()),
+                chrono::Utc::now().timestamp(),
+            ],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("accounts upsert: {e}"))?;
+    tx.execute("UPDATE accounts SET is_active = (id = ?1)", [id])
+        .map_err(|e| format!("accounts upsert activate: {e}"))?;
+    tx.commit()
+        .map_err(|e| format!("accounts upsert commit: {e}"))?;
+    Ok(id)
+}
+
+/// Every saved account, oldest first. Accounts whose cookie can't be
+/// decrypted with `key` (e.g. the key file was deleted) are skipped.
+pub fn load_all(db: &Database, key: &SecretKey) -> Result<Vec<SavedAccount>, String> {
+    let conn = lock_conn!(db.conn);
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, base_url, username, user_id, session_cookie, is_active, saved_at
+             FROM accounts ORDER BY id",
+        )
+        .map_err(|e| format!("prepare accounts load_all: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Vec<u8>>(4)?,
+                row.get::<_, i64>(5)? != 0,
+                row.get::<_, i64>(6)?,
+            ))
+        })
+        .map_err(|e| format!("query accounts load_all: {e}"))?;
+
+    let mut accounts = Vec::new();
+    for row in rows {
+        let (id, base_url, username, user_id, sealed, is_active, saved_at) =
+            row.map_err(|e| format!("row accounts load_all: {e}"))?;
+        match key.open(&sealed).map(String::from_utf8) {
+            Ok(Ok(session_cookie)) => accounts.push(SavedAccount {
+                id,
+                base_url,
+                username,
+                user_id,
+                session_cookie,
+                is_active,
+                saved_at,
+            }),
+            _ => tracing::warn!(
+                "[account] Saved session #{} can't be decrypted, ignoring it",
+                id
+            ),
+        }
+    }
+    Ok(accounts)
+}
+
+/// Make `id` the only active account.
+pub fn set_active(db: &Database, id: i64) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    conn.execute("UPDATE accounts SET is_active = (id = ?1)", [id])
+        .map_err(|e| format!("accounts set_active: {e}"))?;
+    Ok(())
+}
+
+pub fn delete(db: &Database, id: i64) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    conn.execute("DELETE FROM accounts WHERE id = ?1", [id])
+        .map_err(|e| format!("accounts delete: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_load_activate_delete() {
+        let db = Database::memory().unwrap();
+        let key = SecretKey::generate();
+        assert!(load_all(&db, &key).unwrap().is_empty());
+
+        let work = upsert(
+            &db,
+            &key,
+            "https://work.example.com",
+            "alice",
+            7,
+            "session=a",
+        )
+        .unwrap();
+        let home = upsert(
+            &db,
+            &key,
+            "https://home.example.com",
+            "alice",
+            3,
+            "session=b",
+        )
+        .unwrap();
+        assert_ne!(work, home);
+        // Logging in again refreshes the row instead of adding one
+        let again = upsert(
+            &db,
+            &key,
+            "https://work.example.com",
+            "alice",
+            7,
+            "session=c",
+        )
+        .unwrap();
+        assert_eq!(again, work);
+
+        let all = load_all(&db, &key).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].session_cookie, "session=c");
+        assert!(all[0].is_active && !all[1].is_active);
+
+        let stored: Vec<u8> = lock_conn!(db.conn)
+            .query_row(
+                "SELECT session_cookie FROM accounts WHERE id = ?1",
+                [work],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(!stored.windows(9).any(|w| w == b"session=c"));
+        assert!(load_all(&db, &SecretKey::generate()).unwrap().is_empty());
+
+        set_active(&db, home).unwrap();
+        let all = load_all(&db, &key).unwrap();
+        assert!(!all[0].is_active && all[1].is_active);
+
+        delete(&db, work).unwrap();
+        let all = load_all(&db, &key).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, home);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::database::{lock_conn, Database};
+
+/// Remember the hash of a file we just wrote. Keyed by path alone because some
+/// apps share a file (Claude Code and its VS Code extension, the VS Code
+/// extensions' settings.json); `app_type` records who wrote it last.
+pub fn record(db: &Database, app_type: &str, path: &str, sha256: &str) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    conn.execute(
+        "INSERT OR REPLACE INTO file_hashes (path, app_type, sha256, written_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![path, app_type, sha256, chrono::Utc::now().timestamp()],
+    )
+    .map_err(|e| format!("record file hash: {e}"))?;
+    Ok(())
+}
+
+/// Hash recorded when we last wrote `path`.
+pub fn get(db: &Database, path: &str) -> Result<Option<String>, String> {
+    let conn = lock_conn!(db.conn);
+    let mut stmt = conn
+        .prepare("SELECT sha256 FROM file_hashes WHERE path = ?1")
+        .map_err(|e| format!("prepare get file hash: {e}"))?;
+    let mut rows = stmt
+        .query_map([path], |row| row.get(0))
+        .map_err(|e| format!("query get file hash: {e}"))?;
+    match rows.next() {
+        Some(Ok(v)) => Ok(Some(v)),
+        Some(Err(e)) => Err(format!("row get file hash: {e}")),
+        None => Ok(None),
+    }
+}
+
+/// Forget `path`, e.g. after a restore hands the file back to the user.
+pub fn remove(db: &Database, path: &str) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    conn.execute("DELETE FROM file_hashes WHERE path = ?1", [path])
+        .map_err(|e| format!("remove file hash: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_replaces_and_remove() {
+        let db = Database::memory().unwrap();
+        record(&db, "claude", "/a/settings.json", "111").unwrap();
+        record(&db, "claude-vscode", "/a/settings.json", "222").unwrap();
+        record(&db, "codex", "/b/config.toml", "333").unwrap();
+
+        assert_eq!(
+            get(&db, "/a/settings.json").unwrap().as_deref(),
+            Some("222")
+        );
+        remove(&db, "/a/settings.json").unwrap();
+        assert_eq!(get(&db, "/a/settings.json").unwrap(), None);
+        assert_eq!(get(&db, "/b/config.toml").unwrap().as_deref(), Some("333"));
+    }
+}
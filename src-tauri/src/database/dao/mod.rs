@@ -1,3 +1,5 @@
+pub mod accounts;
 pub mod backup;
+pub mod file_hashes;
 pub mod providers;
 pub mod settings;
@@ -1,4 +1,7 @@
+use std::collections::HashSet;
+
 use crate::database::{lock_conn, Database};
+use crate::error::CommandError;
 use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +17,39 @@ pub struct ProviderRecord {
     pub sort_index: Option<i64>,
     pub notes: Option<String>,
     pub created_at: i64, // Unix seconds
+    /// OpenCode npm adapter id ("anthropic" | "openai-compatible" | "openai"); "" = default.
+    #[serde(default)]
+    pub opencode_adapter: String,
+    /// Saved account (`accounts.id`) this provider's key came from, if any.
+    #[serde(default)]
+    pub account_id: Option<i64>,
+    /// JSON string: canonical model id -> { app or protocol -> id to write }.
+    #[serde(default)]
+    pub model_aliases: String,
+    /// Platform the linked account token lives on (see `token_id`).
+    #[serde(default)]
+    pub account_base_url: Option<String>,
+    /// new-api token this provider's key belongs to, for quota lookups.
+    #[serde(default)]
+    pub token_id: Option<i64>,
+    /// Last quota fetched by `refresh_provider_quota`; read-only for `save`.
+    #[serde(default)]
+    pub quota: Option<TokenQuota>,
+    /// Deleted from the list but kept so the key isn't lost; see `archive`.
+    #[serde(default)]
+    pub archived: bool,
+    /// JSON string: headers, small_fast_model, api_timeout_ms (see `ProviderAdvanced`).
+    #[serde(default)]
+    pub advanced: String,
+}
+
+/// Cached used/remaining quota of a provider's linked token.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TokenQuota {
+    pub used_quota: i64,
+    pub remain_quota: i64,
+    pub unlimited_quota: bool,
+    pub fetched_at: i64, // Unix seconds
 }
 
 // ── shared row-mapper ────────────────────────────────────────────────────────
@@ -30,34 +66,141 @@ fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ProviderRecord> {
         sort_index: row.get(7)?,
         notes: row.get(8)?,
         created_at: row.get(9)?,
+        opencode_adapter: row.get(10)?,
+        account_id: row.get(11)?,
+        model_aliases: row.get(12)?,
+        account_base_url: row.get(13)?,
+        token_id: row.get(14)?,
+        quota: match row.get::<_, Option<i64>>(18)? {
+            Some(fetched_at) => Some(TokenQuota {
+                used_quota: row.get::<_, Option<i64>>(15)?.unwrap_or(0),
+                remain_quota: row.get::<_, Option<i64>>(16)?.unwrap_or(0),
+                unlimited_quota: row.get::<_, Option<i64>>(17)?.unwrap_or(0) != 0,
+                fetched_at,
+            }),
+            None => None,
+        },
+        archived: row.get::<_, i64>(19)? != 0,
+        advanced: row.get(20)?,
     })
 }
 
 // ── public API ───────────────────────────────────────────────────────────────
 
-pub fn get_all(db: &Database) -> Result<Vec<ProviderRecord>, String> {
+/// Providers in display order; archived ones only with `include_archived`.
+pub fn get_all(db: &Database, include_archived: bool) -> Result<Vec<ProviderRecord>, String> {
     let conn = lock_conn!(db.conn);
     let mut stmt = conn
         .prepare(
             "SELECT id, name, url, api_key, default_model, per_cli_models, is_current,
-                    sort_index, notes, created_at
+                    sort_index, notes, created_at, opencode_adapter, account_id,
+                    model_aliases, account_base_url, token_id, quota_used, quota_remain,
+                    quota_unlimited, quota_fetched_at, archived, advanced
              FROM providers
-             ORDER BY COALESCE(sort_index, 999999), created_at ASC",
+             WHERE archived = 0 OR ?1
+             ORDER BY COALESCE(sort_index, 999999), created_at ASC, rowid ASC",
         )
         .map_err(|e| format!("prepare get_all: {e}"))?;
     let rows = stmt
-        .query_map([], map_row)
+        .query_map([include_archived], map_row)
         .map_err(|e| format!("query get_all: {e}"))?;
     rows.collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("collect get_all: {e}"))
 }
 
+/// One window of the provider list plus how many rows match in total.
+#[derive(Debug, Serialize, Clone)]
+pub struct ProviderPage {
+    pub total: i64,
+    pub items: Vec<ProviderRecord>,
+}
+
+/// Most rows `get_page` returns at once.
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+/// Filters shared by the count and the page query, so `total` always agrees
+/// with the rows. ?1 include archived, ?2 search pattern, ?3 tag pattern.
+const PAGE_FILTER: &str = r"(archived = 0 OR ?1)
+    AND (?2 IS NULL
+         OR name LIKE ?2 ESCAPE '\'
+         OR url LIKE ?2 ESCAPE '\'
+         OR COALESCE(notes, '') LIKE ?2 ESCAPE '\')
+    AND (?3 IS NULL
+         OR ' ' || replace(replace(replace(replace(COALESCE(notes, ''),
+                char(10), ' '), char(13), ' '), char(9), ' '), ',', ' ') || ' '
+            LIKE ?3 ESCAPE '\')";
+
+/// Escape LIKE wildcards so user input only matches itself.
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Providers in `get_all` order, `limit` (capped at `MAX_PAGE_SIZE`) rows
+/// from `offset`. `query` matches name, URL or notes anywhere, ignoring ASCII
+/// case; `tag` matches a `#tag` word in the notes.
+pub fn get_page(
+    db: &Database,
+    offset: u32,
+    limit: u32,
+    query: Option<&str>,
+    tag: Option<&str>,
+    include_archived: bool,
+) -> Result<ProviderPage, String> {
+    let query = query
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(|q| format!("%{}%", escape_like(q)));
+    let tag = tag
+        .map(|t| t.trim().trim_start_matches('#'))
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("% #{} %", escape_like(t)));
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+
+    let conn = lock_conn!(db.conn);
+    let total: i64 = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FROM providers WHERE {PAGE_FILTER}"
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_row(rusqlite::params![include_archived, query, tag], |row| {
+                row.get(0)
+            })
+        })
+        .map_err(|e| format!("count get_page: {e}"))?;
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT id, name, url, api_key, default_model, per_cli_models, is_current,
+                    sort_index, notes, created_at, opencode_adapter, account_id,
+                    model_aliases, account_base_url, token_id, quota_used, quota_remain,
+                    quota_unlimited, quota_fetched_at, archived, advanced
+             FROM providers
+             WHERE {PAGE_FILTER}
+             ORDER BY COALESCE(sort_index, 999999), created_at ASC, rowid ASC
+             LIMIT ?4 OFFSET ?5"
+        ))
+        .map_err(|e| format!("prepare get_page: {e}"))?;
+    let items = stmt
+        .query_map(
+            rusqlite::params![include_archived, query, tag, limit, offset],
+            map_row,
+        )
+        .map_err(|e| format!("query get_page: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("collect get_page: {e}"))?;
+    Ok(ProviderPage { total, items })
+}
+
 pub fn get_current(db: &Database) -> Result<Option<ProviderRecord>, String> {
     let conn = lock_conn!(db.conn);
     let mut stmt = conn
         .prepare(
             "SELECT id, name, url, api_key, default_model, per_cli_models, is_current,
-                    sort_index, notes, created_at
+                    sort_index, notes, created_at, opencode_adapter, account_id,
+                    model_aliases, account_base_url, token_id, quota_used, quota_remain,
+                    quota_unlimited, quota_fetched_at, archived, advanced
              FROM providers WHERE is_current = 1 LIMIT 1",
         )
         .map_err(|e| format!("prepare get_current: {e}"))?;
@@ -93,8 +236,9 @@ pub fn save(db: &Database, provider: &ProviderRecord) -> Result<(), String> {
     conn.execute(
         "INSERT INTO providers
              (id, name, url, api_key, default_model, per_cli_models,
-              is_current, sort_index, notes, created_at)
-         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10)
+              is_current, sort_index, notes, created_at, opencode_adapter, account_id,
+              model_aliases, account_base_url, token_id, archived, advanced)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)
          ON CONFLICT(id) DO UPDATE SET
              name          = excluded.name,
              url           = excluded.url,
@@ -102,7 +246,13 @@ pub fn save(db: &Database, provider: &ProviderRecord) -> Result<(), String> {
              default_model = excluded.default_model,
              per_cli_models= excluded.per_cli_models,
              sort_index    = excluded.sort_index,
-             notes         = excluded.notes",
+             notes         = excluded.notes,
+             opencode_adapter = excluded.opencode_adapter,
+             account_id    = excluded.account_id,
+             model_aliases = excluded.model_aliases,
+             advanced      = excluded.advanced,
+             account_base_url = excluded.account_base_url,
+             token_id      = excluded.token_id",
         rusqlite::params![
             provider.id,
             provider.name,
@@ -114,12 +264,38 @@ pub fn save(db: &Database, provider: &ProviderRecord) -> Result<(), String> {
             provider.sort_index,
             provider.notes,
             provider.created_at,
+            provider.opencode_adapter,
+            provider.account_id,
+            provider.model_aliases,
+            provider.account_base_url,
+            provider.token_id,
+            provider.archived,
+            provider.advanced,
         ],
     )
     .map_err(|e| format!("save upsert: {e}"))?;
     Ok(())
 }
 
+/// Cache the quota last fetched for a provider's linked token.
+pub fn set_quota(db: &Database, id: &str, quota: &TokenQuota) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    conn.execute(
+        "UPDATE providers SET quota_used = ?1, quota_remain = ?2, quota_unlimited = ?3,
+                              quota_fetched_at = ?4
+         WHERE id = ?5",
+        rusqlite::params![
+            quota.used_quota,
+            quota.remain_quota,
+            quota.unlimited_quota as i64,
+            quota.fetched_at,
+            id,
+        ],
+    )
+    .map_err(|e| format!("set_quota: {e}"))?;
+    Ok(())
+}
+
 /// Atomically transfer `is_current` to `id` in a single transaction.
 /// Uses parameterised statements inside an explicit transaction — no format! interpolation.
 pub fn set_current(db: &Database, id: &str) -> Result<(), String> {
@@ -128,7 +304,7 @@ pub fn set_current(db: &Database, id: &str) -> Result<(), String> {
     // Verify the target exists before we mutate anything.
     let exists: i64 = conn
         .query_row(
-            "SELECT COUNT(*) FROM providers WHERE id = ?1",
+            "SELECT COUNT(*) FROM providers WHERE id = ?1 AND archived = 0",
             [id],
             |row| row.get(0),
         )
@@ -147,11 +323,8 @@ pub fn set_current(db: &Database, id: &str) -> Result<(), String> {
     tx.commit().map_err(|e| format!("set_current commit: {e}"))
 }
 
-/// Delete a provider. Refuses if it is currently active.
-/// Uses parameterised statements inside an explicit transaction — no format! interpolation.
-pub fn delete(db: &Database, id: &str) -> Result<(), String> {
-    let conn = lock_conn!(db.conn);
-
+/// Refuse to remove the active provider from the list.
+fn ensure_not_current(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
     // Pre-check: is_current guard (parameterised).
     let is_current: i64 = conn
         .query_row(
@@ -168,6 +341,39 @@ pub fn delete(db: &Database, id: &str) -> Result<(), String> {
             "Cannot delete the active provider — switch to another provider first.".to_string(),
         );
     }
+    Ok(())
+}
+
+/// Hide a provider from the list, keeping the row (and its key) for
+/// `restore`. Refuses if it is currently active.
+pub fn archive(db: &Database, id: &str) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    ensure_not_current(&conn, id)?;
+    conn.execute("UPDATE providers SET archived = 1 WHERE id = ?1", [id])
+        .map_err(|e| format!("archive: {e}"))?;
+    Ok(())
+}
+
+/// Bring an archived provider back into the list.
+pub fn restore(db: &Database, id: &str) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    let changed = conn
+        .execute(
+            "UPDATE providers SET archived = 0 WHERE id = ?1 AND archived = 1",
+            [id],
+        )
+        .map_err(|e| format!("restore: {e}"))?;
+    if changed == 0 {
+        return Err(format!("No archived provider: {id}"));
+    }
+    Ok(())
+}
+
+/// Delete a provider for good. Refuses if it is currently active.
+/// Uses parameterised statements inside an explicit transaction — no format! interpolation.
+pub fn purge(db: &Database, id: &str) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    ensure_not_current(&conn, id)?;
 
     let tx = conn
         .unchecked_transaction()
@@ -177,20 +383,92 @@ pub fn delete(db: &Database, id: &str) -> Result<(), String> {
     tx.commit().map_err(|e| format!("delete commit: {e}"))
 }
 
-/// Batch-update sort_index in a single transaction.
-pub fn reorder(db: &Database, ids: &[String]) -> Result<(), String> {
+/// Why `reorder` refused an id list.
+#[derive(Debug)]
+pub enum ReorderError {
+    /// The list doesn't match the stored providers, e.g. it came from a
+    /// window that hasn't seen a provider being deleted.
+    Conflict {
+        unknown: Vec<String>,
+        duplicates: Vec<String>,
+    },
+    Db(String),
+}
+
+impl From<String> for ReorderError {
+    fn from(message: String) -> Self {
+        Self::Db(message)
+    }
+}
+
+impl From<ReorderError> for CommandError {
+    fn from(err: ReorderError) -> Self {
+        match err {
+            ReorderError::Conflict {
+                unknown,
+                duplicates,
+            } => CommandError::new(
+                "CONFLICT",
+                "The provider list changed; reload and try again",
+            )
+            .with_details(serde_json::json!({ "unknown": unknown, "duplicates": duplicates })),
+            ReorderError::Db(message) => message.into(),
+        }
+    }
+}
+
+/// Batch-update sort_index in a single transaction. `ids` must be distinct
+/// non-archived providers; ones it leaves out (added elsewhere since the
+/// list was loaded) keep their relative order after the given ones.
+pub fn reorder(db: &Database, ids: &[String]) -> Result<(), ReorderError> {
     let conn = lock_conn!(db.conn);
     let tx = conn
         .unchecked_transaction()
         .map_err(|e| format!("reorder begin: {e}"))?;
-    for (i, id) in ids.iter().enumerate() {
+
+    let stored: Vec<String> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id FROM providers WHERE archived = 0
+                 ORDER BY COALESCE(sort_index, 999999), created_at ASC",
+            )
+            .map_err(|e| format!("reorder prepare: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("reorder query: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("reorder collect: {e}"))?
+    };
+
+    let mut seen = HashSet::new();
+    let duplicates: Vec<String> = ids
+        .iter()
+        .filter(|id| !seen.insert(id.as_str()))
+        .cloned()
+        .collect();
+    let unknown: Vec<String> = ids
+        .iter()
+        .filter(|id| !stored.contains(id))
+        .cloned()
+        .collect();
+    if !duplicates.is_empty() || !unknown.is_empty() {
+        return Err(ReorderError::Conflict {
+            unknown,
+            duplicates,
+        });
+    }
+
+    let missing = stored.iter().filter(|id| !seen.contains(id.as_str()));
+    for (i, id) in ids.iter().chain(missing).enumerate() {
         tx.execute(
             "UPDATE providers SET sort_index = ?1 WHERE id = ?2",
             rusqlite::params![i as i64, id],
         )
         .map_err(|e| format!("reorder update {id}: {e}"))?;
     }
-    tx.commit().map_err(|e| format!("reorder commit: {e}"))
+    tx.commit()
+        .map_err(|e| format!("reorder commit: {e}"))
+        .map_err(ReorderError::Db)
 }
 
 #[allow(dead_code)]
@@ -199,3 +477,219 @@ pub fn count(db: &Database) -> Result<i64, String> {
     conn.query_row("SELECT COUNT(*) FROM providers", [], |row| row.get(0))
         .map_err(|e| format!("count: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str) -> ProviderRecord {
+        ProviderRecord {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: "https://x.com".to_string(),
+            api_key: "k".to_string(),
+            default_model: String::new(),
+            per_cli_models: "{}".to_string(),
+            is_current: false,
+            sort_index: None,
+            notes: None,
+            created_at: 0,
+            opencode_adapter: String::new(),
+            account_id: None,
+            model_aliases: "{}".to_string(),
+            account_base_url: None,
+            token_id: None,
+            quota: None,
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+
+    fn ids(db: &Database, include_archived: bool) -> Vec<String> {
+        get_all(db, include_archived)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_save_can_unlink_account() {
+        let db = Database::memory().unwrap();
+        let mut p = record("a");
+        p.account_id = Some(7);
+        p.account_base_url = Some("https://platform.test".into());
+        p.token_id = Some(12);
+        save(&db, &p).unwrap();
+        let linked = &get_all(&db, true).unwrap()[0];
+        assert_eq!(linked.account_id, Some(7));
+        assert_eq!(
+            linked.account_base_url.as_deref(),
+            Some("https://platform.test")
+        );
+        assert_eq!(linked.token_id, Some(12));
+
+        p.account_id = None;
+        p.account_base_url = None;
+        p.token_id = None;
+        save(&db, &p).unwrap();
+        let unlinked = &get_all(&db, true).unwrap()[0];
+        assert_eq!(unlinked.account_id, None);
+        assert_eq!(unlinked.account_base_url, None);
+        assert_eq!(unlinked.token_id, None);
+    }
+
+    #[test]
+    fn test_archive_restore_and_purge() {
+        let db = Database::memory().unwrap();
+        save(&db, &record("a")).unwrap();
+        save(&db, &record("b")).unwrap();
+        set_current(&db, "a").unwrap();
+
+        assert!(archive(&db, "a").is_err());
+        archive(&db, "b").unwrap();
+        assert_eq!(ids(&db, false), ["a"]);
+        assert_eq!(ids(&db, true), ["a", "b"]);
+        assert!(get_all(&db, true).unwrap()[1].archived);
+        // Archived providers can't become current, and editing keeps them archived
+        assert!(set_current(&db, "b").is_err());
+        save(&db, &record("b")).unwrap();
+        assert_eq!(ids(&db, false), ["a"]);
+
+        restore(&db, "b").unwrap();
+        assert!(restore(&db, "b").is_err());
+        assert_eq!(ids(&db, false), ["a", "b"]);
+
+        archive(&db, "b").unwrap();
+        purge(&db, "b").unwrap();
+        assert_eq!(ids(&db, true), ["a"]);
+        assert!(purge(&db, "a").is_err());
+    }
+
+    fn page_ids(page: &ProviderPage) -> Vec<&str> {
+        page.items.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_page_windows_match_get_all() {
+        let db = Database::memory().unwrap();
+        for i in 0..7 {
+            save(&db, &record(&format!("p{i}"))).unwrap();
+        }
+        archive(&db, "p3").unwrap();
+        reorder(&db, &["p6", "p0", "p1"].map(String::from)).unwrap();
+        let all = ids(&db, false);
+
+        let mut paged = Vec::new();
+        for offset in (0..10).step_by(4) {
+            let page = get_page(&db, offset, 4, None, None, false).unwrap();
+            assert_eq!(page.total, 6);
+            paged.extend(page.items.into_iter().map(|p| p.id));
+        }
+        assert_eq!(paged, all);
+
+        // Past the end is empty; a zero limit still returns a row
+        let page = get_page(&db, 6, 4, None, None, false).unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.total, 6);
+        assert_eq!(
+            get_page(&db, 0, 0, None, None, false).unwrap().items.len(),
+            1
+        );
+        assert_eq!(get_page(&db, 0, 100, None, None, true).unwrap().total, 7);
+    }
+
+    #[test]
+    fn test_page_search_and_tags() {
+        let db = Database::memory().unwrap();
+        let mut a = record("a");
+        a.name = "Main Gateway".to_string();
+        a.notes = Some("#work, shared\r\n50% off".to_string());
+        let mut b = record("b");
+        b.url = "https://backup.example.com".to_string();
+        b.notes = Some("#workshop".to_string());
+        let mut c = record("c");
+        c.name = "my_proxy".to_string();
+        c.notes = Some("#home".to_string());
+        for p in [&a, &b, &c] {
+            save(&db, p).unwrap();
+        }
+        let search = |query: &str| {
+            let page = get_page(&db, 0, 50, Some(query), None, false).unwrap();
+            page_ids(&page).join(",")
+        };
+        assert_eq!(search("gateway"), "a");
+        assert_eq!(search("BACKUP"), "b");
+        assert_eq!(search("  "), "a,b,c");
+        // Wildcards in the query are literal
+        assert_eq!(search("50%"), "a");
+        assert_eq!(search("%"), "a");
+        assert_eq!(search("y_p"), "c");
+        assert_eq!(search("_"), "c");
+        assert_eq!(search("\\"), "");
+
+        let tagged = |tag: &str| {
+            let page = get_page(&db, 0, 50, None, Some(tag), false).unwrap();
+            page_ids(&page).join(",")
+        };
+        assert_eq!(tagged("work"), "a");
+        assert_eq!(tagged("#workshop"), "b");
+        assert_eq!(tagged("wor_"), "");
+        let page = get_page(&db, 0, 50, Some("example"), Some("home"), false).unwrap();
+        assert_eq!(page.total, 0);
+    }
+
+    #[test]
+    fn test_reorder_appends_missing_rows() {
+        let db = Database::memory().unwrap();
+        for id in ["a", "b", "c", "d"] {
+            save(&db, &record(id)).unwrap();
+        }
+        archive(&db, "d").unwrap();
+        reorder(&db, &["c".to_string(), "a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(ids(&db, false), ["c", "a", "b"]);
+
+        // A stale list from before "e" was added still places it, last
+        save(&db, &record("e")).unwrap();
+        reorder(&db, &["b".to_string(), "c".to_string()]).unwrap();
+        assert_eq!(ids(&db, false), ["b", "c", "a", "e"]);
+        let indexes: Vec<Option<i64>> = get_all(&db, false)
+            .unwrap()
+            .iter()
+            .map(|p| p.sort_index)
+            .collect();
+        assert_eq!(indexes, [Some(0), Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_reorder_rejects_stale_and_duplicate_ids() {
+        let db = Database::memory().unwrap();
+        for id in ["a", "b"] {
+            save(&db, &record(id)).unwrap();
+        }
+        archive(&db, "b").unwrap();
+        let before = ids(&db, true);
+
+        for bad in [vec!["a", "gone"], vec!["a", "a"], vec!["b", "a"]] {
+            let ids_in: Vec<String> = bad.iter().map(|s| s.to_string()).collect();
+            let err = CommandError::from(reorder(&db, &ids_in).unwrap_err());
+            assert_eq!(err.code, "CONFLICT", "{bad:?}");
+        }
+        match reorder(&db, &["a".to_string(), "a".to_string()]) {
+            Err(ReorderError::Conflict {
+                unknown,
+                duplicates,
+            }) => {
+                assert!(unknown.is_empty());
+                assert_eq!(duplicates, ["a"]);
+            }
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+        // Nothing was written
+        assert_eq!(ids(&db, true), before);
+        assert!(get_all(&db, true)
+            .unwrap()
+            .iter()
+            .all(|p| p.sort_index.is_none()));
+    }
+}
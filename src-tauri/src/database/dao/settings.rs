@@ -1,6 +1,5 @@
 use crate::database::{lock_conn, Database};
 
-#[allow(dead_code)]
 pub fn get(db: &Database, key: &str) -> Result<Option<String>, String> {
     let conn = lock_conn!(db.conn);
     let mut stmt = conn
@@ -16,7 +15,6 @@ pub fn get(db: &Database, key: &str) -> Result<Option<String>, String> {
     }
 }
 
-#[allow(dead_code)]
 pub fn set(db: &Database, key: &str, value: &str) -> Result<(), String> {
     let conn = lock_conn!(db.conn);
     conn.execute(
@@ -26,3 +24,38 @@ pub fn set(db: &Database, key: &str, value: &str) -> Result<(), String> {
     .map_err(|e| format!("settings set: {e}"))?;
     Ok(())
 }
+
+/// Boolean flag stored as "true"/"false"; missing or unrecognised values give `default`.
+pub fn get_bool(db: &Database, key: &str, default: bool) -> bool {
+    match get(db, key) {
+        Ok(Some(v)) if v == "true" => true,
+        Ok(Some(v)) if v == "false" => false,
+        _ => default,
+    }
+}
+
+pub fn set_bool(db: &Database, key: &str, value: bool) -> Result<(), String> {
+    set(db, key, if value { "true" } else { "false" })
+}
+
+/// Every `(key, value)` whose key starts with `prefix`.
+pub fn list_prefix(db: &Database, prefix: &str) -> Result<Vec<(String, String)>, String> {
+    let conn = lock_conn!(db.conn);
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings WHERE substr(key, 1, length(?1)) = ?1")
+        .map_err(|e| format!("prepare settings list: {e}"))?;
+    let rows = stmt
+        .query_map([prefix], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("query settings list: {e}"))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("row settings list: {e}"))
+}
+
+pub fn delete(db: &Database, keys: &[String]) -> Result<(), String> {
+    let conn = lock_conn!(db.conn);
+    for key in keys {
+        conn.execute("DELETE FROM settings WHERE key = ?1", [key])
+            .map_err(|e| format!("settings delete: {e}"))?;
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
-use rusqlite::Connection;
-use std::path::Path;
+use rusqlite::{Connection, ErrorCode};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 pub mod dao;
@@ -20,18 +22,149 @@ macro_rules! lock_conn {
 }
 pub(crate) use lock_conn;
 
+/// A corrupted database file that was moved aside and replaced at startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct DbRecovery {
+    /// SQLite's error, or what the integrity check reported.
+    pub reason: String,
+    /// Where the corrupted file now is (`providers.db.corrupt-<timestamp>`).
+    pub corrupt_path: String,
+    /// Rows copied from the old file into the new one.
+    pub salvaged_rows: usize,
+    /// Tables that couldn't be read to the end; rows past the damage were lost.
+    pub damaged_tables: Vec<String>,
+}
+
+impl DbRecovery {
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "The provider database was corrupted and has been replaced. \
+             {} rows were salvaged; the old file is at {}.",
+            self.salvaged_rows, self.corrupt_path
+        );
+        if !self.damaged_tables.is_empty() {
+            summary.push_str(&format!(
+                " Some data in {} could not be read.",
+                self.damaged_tables.join(", ")
+            ));
+        }
+        summary
+    }
+}
+
+enum OpenError {
+    /// The file is damaged or not a database; replacing it may help.
+    Corrupt(String),
+    Failed(String),
+}
+
+fn is_corruption(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// `providers.db` + `-wal` → `providers.db-wal`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
 impl Database {
-    pub fn init(path: &Path) -> Result<Self, String> {
+    /// `<data_dir>/hajimi-cli-sync/providers.db`, shared by the GUI and the
+    /// headless `hajimi-sync` binary.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir()
+            .or_else(dirs::home_dir)
+            .map(|p| p.join("hajimi-cli-sync").join("providers.db"))
+    }
+
+    /// Open (or create) the database at `path`. A corrupted file is moved
+    /// aside and replaced by a fresh one holding whatever rows could still be
+    /// read; the returned `DbRecovery` says what happened.
+    pub fn init(path: &Path) -> Result<(Self, Option<DbRecovery>), String> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .map_err(|e| format!("Failed to create DB directory: {e}"))?;
         }
-        let conn = Connection::open(path).map_err(|e| format!("Failed to open SQLite DB: {e}"))?;
-        Self::configure(&conn)?;
-        Self::apply_schema(&conn)?;
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+        let (conn, recovery) = match Self::open_file(path) {
+            Ok(conn) => (conn, None),
+            Err(OpenError::Failed(e)) => return Err(e),
+            Err(OpenError::Corrupt(reason)) => {
+                tracing::error!("[database] {} is corrupted: {}", path.display(), reason);
+                let (conn, recovery) = Self::replace_corrupt(path, reason)?;
+                (conn, Some(recovery))
+            }
+        };
+        Ok((
+            Self {
+                conn: Mutex::new(conn),
+            },
+            recovery,
+        ))
+    }
+
+    fn open_file(path: &Path) -> Result<Connection, OpenError> {
+        let failed = |e: rusqlite::Error| {
+            if is_corruption(&e) {
+                OpenError::Corrupt(e.to_string())
+            } else {
+                OpenError::Failed(format!("Failed to open SQLite DB: {e}"))
+            }
+        };
+        let conn = Connection::open(path).map_err(failed)?;
+        // Damage inside a table only shows when it is read, so look before trusting the file
+        let check: String = conn
+            .query_row("PRAGMA quick_check", [], |row| row.get(0))
+            .map_err(failed)?;
+        if check != "ok" {
+            return Err(OpenError::Corrupt(format!(
+                "integrity check failed: {check}"
+            )));
+        }
+        Self::configure(&conn).map_err(OpenError::Failed)?;
+        Self::apply_schema(&conn).map_err(OpenError::Failed)?;
+        Ok(conn)
+    }
+
+    /// Move the corrupted file (and its WAL, which must not be replayed into
+    /// a new file) to `<name>.corrupt-<timestamp>`, create a fresh database
+    /// and salvage what it can from the old one.
+    fn replace_corrupt(path: &Path, reason: String) -> Result<(Connection, DbRecovery), String> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let corrupt_path = with_suffix(path, &format!(".corrupt-{stamp}"));
+        for suffix in ["", "-wal", "-shm"] {
+            let from = with_suffix(path, suffix);
+            if from.exists() {
+                std::fs::rename(&from, with_suffix(&corrupt_path, suffix))
+                    .map_err(|e| format!("Failed to move the corrupted {}: {e}", from.display()))?;
+            }
+        }
+        tracing::warn!(
+            "[database] Moved the corrupted database to {}",
+            corrupt_path.display()
+        );
+
+        let conn = Self::open_file(path).map_err(|e| match e {
+            OpenError::Corrupt(e) | OpenError::Failed(e) => e,
+        })?;
+        let (salvaged_rows, damaged_tables) = salvage(&conn, &corrupt_path);
+        tracing::info!(
+            "[database] Salvaged {} rows; damaged tables: {:?}",
+            salvaged_rows,
+            damaged_tables
+        );
+        Ok((
+            conn,
+            DbRecovery {
+                reason,
+                corrupt_path: corrupt_path.to_string_lossy().to_string(),
+                salvaged_rows,
+                damaged_tables,
+            },
+        ))
     }
 
     pub fn memory() -> Result<Self, String> {
@@ -70,3 +203,168 @@ impl Database {
         Ok(count > 0)
     }
 }
+
+/// Copy every row still readable from `corrupt` into the fresh `conn`, in the
+/// spirit of sqlite3's `.recover`: each table is read until the first damaged
+/// page, and the rows before it are kept. Columns the old file predates keep
+/// their defaults. Returns the rows copied and the tables that hit damage.
+fn salvage(conn: &Connection, corrupt: &Path) -> (usize, Vec<String>) {
+    let tables: Vec<String> = conn
+        .prepare(
+            "SELECT name FROM main.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+        .unwrap_or_default();
+    if let Err(e) = conn.execute(
+        "ATTACH DATABASE ?1 AS old",
+        [corrupt.to_string_lossy().as_ref()],
+    ) {
+        tracing::warn!("[database] Cannot attach the corrupted database: {}", e);
+        return (0, tables);
+    }
+    let mut copied = 0;
+    let mut damaged = Vec::new();
+    for table in tables {
+        let (rows, error) = copy_rows(conn, &table);
+        copied += rows;
+        if let Some(e) = error {
+            tracing::warn!(
+                "[database] Salvage of {} stopped after {} rows: {}",
+                table,
+                rows,
+                e
+            );
+            damaged.push(table);
+        }
+    }
+    if let Err(e) = conn.execute_batch("DETACH DATABASE old") {
+        tracing::warn!("[database] Cannot detach the corrupted database: {}", e);
+    }
+    (copied, damaged)
+}
+
+/// Copy `old.<table>` into `main.<table>` row by row over the columns both
+/// have. A table the old file doesn't have copies nothing and isn't damage.
+fn copy_rows(conn: &Connection, table: &str) -> (usize, Option<rusqlite::Error>) {
+    let columns = |schema: &str| -> rusqlite::Result<Vec<String>> {
+        conn.prepare(&format!("PRAGMA {schema}.table_info(\"{table}\")"))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect()
+    };
+    let shared = match (columns("main"), columns("old")) {
+        (Ok(new), Ok(old)) => new
+            .into_iter()
+            .filter(|c| old.contains(c))
+            .map(|c| format!("\"{c}\""))
+            .collect::<Vec<_>>(),
+        (Err(e), _) | (_, Err(e)) => return (0, Some(e)),
+    };
+    if shared.is_empty() {
+        return (0, None);
+    }
+    let list = shared.join(", ");
+    let placeholders = vec!["?"; shared.len()].join(", ");
+    let mut select = match conn.prepare(&format!("SELECT {list} FROM old.\"{table}\"")) {
+        Ok(stmt) => stmt,
+        Err(e) => return (0, Some(e)),
+    };
+    let mut insert = match conn.prepare(&format!(
+        "INSERT OR IGNORE INTO main.\"{table}\" ({list}) VALUES ({placeholders})"
+    )) {
+        Ok(stmt) => stmt,
+        Err(e) => return (0, Some(e)),
+    };
+    let mut rows = match select.query([]) {
+        Ok(rows) => rows,
+        Err(e) => return (0, Some(e)),
+    };
+    let mut copied = 0;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => return (copied, None),
+            Err(e) => return (copied, Some(e)),
+        };
+        let values: rusqlite::Result<Vec<rusqlite::types::Value>> =
+            (0..shared.len()).map(|i| row.get(i)).collect();
+        match values.and_then(|v| insert.execute(rusqlite::params_from_iter(v))) {
+            Ok(n) => copied += n,
+            Err(e) => return (copied, Some(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::dao::settings;
+
+    fn corrupt_files(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.to_string_lossy().contains(".corrupt-"))
+            .collect()
+    }
+
+    #[test]
+    fn test_not_a_database_is_moved_aside() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("providers.db");
+        std::fs::write(&path, vec![0x5a; 8192]).unwrap();
+
+        let (db, recovery) = Database::init(&path).unwrap();
+        let recovery = recovery.unwrap();
+        assert_eq!(recovery.salvaged_rows, 0);
+        assert_eq!(corrupt_files(tmp.path()).len(), 1);
+        assert_eq!(
+            std::fs::read(&recovery.corrupt_path).unwrap(),
+            vec![0x5a; 8192]
+        );
+
+        // The replacement is a working database on disk
+        settings::set(&db, "k", "v").unwrap();
+        drop(db);
+        let (db, recovery) = Database::init(&path).unwrap();
+        assert!(recovery.is_none());
+        assert_eq!(settings::get(&db, "k").unwrap().as_deref(), Some("v"));
+    }
+
+    #[test]
+    fn test_damaged_table_is_salvaged_up_to_the_damage() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("providers.db");
+        let (db, _) = Database::init(&path).unwrap();
+        for i in 0..400 {
+            settings::set(&db, &format!("key{i:03}"), &"x".repeat(200)).unwrap();
+        }
+        let page_size: i64 = {
+            let conn = lock_conn!(db.conn);
+            conn.query_row("PRAGMA page_size", [], |row| row.get(0))
+                .unwrap()
+        };
+        drop(db);
+
+        // Overwrite a page near the end, well inside the settings table
+        let mut bytes = std::fs::read(&path).unwrap();
+        let pages = bytes.len() / page_size as usize;
+        let start = (pages - 3) * page_size as usize;
+        bytes[start..start + page_size as usize].fill(0xff);
+        std::fs::write(&path, bytes).unwrap();
+
+        let (db, recovery) = Database::init(&path).unwrap();
+        let recovery = recovery.unwrap();
+        assert!(recovery.reason.contains("integrity") || recovery.reason.contains("malformed"));
+        assert_eq!(recovery.damaged_tables, vec!["settings".to_string()]);
+        assert!(
+            (1..400).contains(&recovery.salvaged_rows),
+            "{}",
+            recovery.salvaged_rows
+        );
+        assert_eq!(
+            settings::get(&db, "key000").unwrap().as_deref(),
+            Some("x".repeat(200).as_str())
+        );
+        assert!(recovery.summary().contains("settings"));
+    }
+}
@@ -1,6 +1,6 @@
 use rusqlite::Connection;
 
-const SCHEMA_VERSION: u32 = 1;
+const SCHEMA_VERSION: u32 = 7;
 
 pub fn create_tables(conn: &Connection) -> Result<(), String> {
     // Wrap DDL + version stamp in one atomic transaction so a mid-crash DB is
@@ -34,6 +34,24 @@ pub fn create_tables(conn: &Connection) -> Result<(), String> {
             value TEXT
         );
 
+        CREATE TABLE IF NOT EXISTS accounts (
+            id             INTEGER PRIMARY KEY AUTOINCREMENT,
+            base_url       TEXT NOT NULL,
+            username       TEXT NOT NULL,
+            user_id        INTEGER NOT NULL,
+            session_cookie BLOB NOT NULL,
+            is_active      INTEGER NOT NULL DEFAULT 0,
+            saved_at       INTEGER NOT NULL,
+            UNIQUE (base_url, username)
+        );
+
+        CREATE TABLE IF NOT EXISTS file_hashes (
+            path       TEXT PRIMARY KEY,
+            app_type   TEXT NOT NULL,
+            sha256     TEXT NOT NULL,
+            written_at INTEGER NOT NULL
+        );
+
         COMMIT;
         ",
     )
@@ -48,9 +66,71 @@ pub fn run_migrations(conn: &Connection) -> Result<(), String> {
         .query_row("PRAGMA user_version", [], |row| row.get(0))
         .map_err(|e| format!("Failed to read user_version: {e}"))?;
 
+    if version < 2 {
+        // v1 → v2: per-provider OpenCode adapter ("" = default).
+        add_column_if_missing(
+            conn,
+            "providers",
+            "opencode_adapter",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+    }
+
+    if version < 3 {
+        // v2 → v3: several saved accounts instead of one session row, and
+        // providers remember the account they were created from.
+        let has_single_session: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'account_session'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to look up account_session: {e}"))?;
+        if has_single_session > 0 {
+            conn.execute_batch(
+                "INSERT OR IGNORE INTO accounts
+                     (base_url, username, user_id, session_cookie, is_active, saved_at)
+                 SELECT base_url, username, user_id, session_cookie, 1, saved_at
+                 FROM account_session;
+                 DROP TABLE account_session;",
+            )
+            .map_err(|e| format!("Failed to migrate account_session: {e}"))?;
+        }
+        add_column_if_missing(conn, "providers", "account_id", "INTEGER")?;
+    }
+
+    if version < 4 {
+        // v3 → v4: per-provider model id aliases (JSON map).
+        add_column_if_missing(
+            conn,
+            "providers",
+            "model_aliases",
+            "TEXT NOT NULL DEFAULT '{}'",
+        )?;
+    }
+
+    if version < 5 {
+        // v4 → v5: providers created from an account token remember the token,
+        // plus the last quota fetched for it.
+        add_column_if_missing(conn, "providers", "account_base_url", "TEXT")?;
+        add_column_if_missing(conn, "providers", "token_id", "INTEGER")?;
+        add_column_if_missing(conn, "providers", "quota_used", "INTEGER")?;
+        add_column_if_missing(conn, "providers", "quota_remain", "INTEGER")?;
+        add_column_if_missing(conn, "providers", "quota_unlimited", "INTEGER")?;
+        add_column_if_missing(conn, "providers", "quota_fetched_at", "INTEGER")?;
+    }
+
+    if version < 6 {
+        // v5 → v6: deleting a provider archives it; purge removes the row.
+        add_column_if_missing(conn, "providers", "archived", "INTEGER NOT NULL DEFAULT 0")?;
+    }
+
+    if version < 7 {
+        // v6 → v7: per-provider advanced options (JSON object).
+        add_column_if_missing(conn, "providers", "advanced", "TEXT NOT NULL DEFAULT '{}'")?;
+    }
+
     if version < SCHEMA_VERSION {
-        // v0 → v1: schema already applied by create_tables above.
-        // Future versions add new `if version < N { ... }` blocks here.
         // PRAGMA user_version does not support bound parameters in SQLite.
         // SCHEMA_VERSION is a compile-time const u32 — not user-controlled, safe to format.
         let pragma_sql = format!("PRAGMA user_version = {SCHEMA_VERSION}");
@@ -60,3 +140,87 @@ pub fn run_migrations(conn: &Connection) -> Result<(), String> {
 
     Ok(())
 }
+
+/// `ALTER TABLE ... ADD COLUMN` guarded by a table_info lookup, so re-running a
+/// migration arm after a partial upgrade is harmless.
+/// Table/column names are compile-time literals from this file — safe to format.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<(), String> {
+    let exists: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('{table}') WHERE name = ?1"),
+            [column],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("table_info {table}: {e}"))?;
+    if exists == 0 {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))
+            .map_err(|e| format!("add column {table}.{column}: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_rerunnable() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        // Simulate a crash before the version stamp landed
+        conn.execute_batch("PRAGMA user_version = 1").unwrap();
+        run_migrations(&conn).unwrap();
+
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        conn.execute(
+            "INSERT INTO providers
+                 (id, name, url, api_key, created_at, opencode_adapter, account_id, model_aliases,
+                  account_base_url, token_id, quota_used, quota_remain, quota_unlimited,
+                  quota_fetched_at, archived, advanced)
+             VALUES ('p', 'n', 'u', 'k', 0, 'openai-compatible', 1, '{}',
+                     'https://a.example.com', 3, 10, 90, 0, 5, 1, '{}')",
+            [],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_single_session_moves_to_accounts() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE account_session (
+                 id INTEGER PRIMARY KEY CHECK (id = 1), base_url TEXT NOT NULL,
+                 session_cookie BLOB NOT NULL, user_id INTEGER NOT NULL,
+                 username TEXT NOT NULL, saved_at INTEGER NOT NULL);
+             INSERT INTO account_session VALUES (1, 'https://a.example.com', x'00', 7, 'alice', 5);
+             PRAGMA user_version = 2;",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let (username, active): (String, i64) = conn
+            .query_row("SELECT username, is_active FROM accounts", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((username.as_str(), active), ("alice", 1));
+        let leftover: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'account_session'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(leftover, 0);
+    }
+}
@@ -0,0 +1,201 @@
+//! `hajimi://add-provider?name=...&url=...&key=...&model=...` links.
+//!
+//! Opening a link only parses and validates it, then queues the result in
+//! `PendingLinks` and emits `EVENT_RECEIVED` so the UI can ask for
+//! confirmation. A link that launched the app arrives before the UI listens,
+//! so the UI drains the queue on mount as well as on every event. Nothing is
+//! written until the UI calls `confirm_deep_link_provider`.
+
+use std::sync::Mutex;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::database::dao::providers::ProviderRecord;
+use crate::{logging, utils};
+
+pub const SCHEME: &str = "hajimi";
+const ACTION_ADD_PROVIDER: &str = "add-provider";
+pub const EVENT_RECEIVED: &str = "deep-link-received";
+
+/// Provider fields carried by an add-provider link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderLink {
+    pub name: String,
+    pub url: String,
+    pub api_key: String,
+    pub model: Option<String>,
+}
+
+impl ProviderLink {
+    /// Same checks as `save_provider`, plus the proxy URL must be https since
+    /// links travel through chat and email.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Provider name cannot be empty".to_string());
+        }
+        utils::validate_url(&self.url).map_err(|e| e.to_string())?;
+        if !self.url.trim().to_ascii_lowercase().starts_with("https://") {
+            return Err("Provider links must use an https:// URL".to_string());
+        }
+        if self.api_key.trim().is_empty() {
+            return Err("API key cannot be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// New provider record with a generated id.
+    pub fn into_record(self) -> ProviderRecord {
+        ProviderRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name.trim().to_string(),
+            url: self.url.trim().trim_end_matches('/').to_string(),
+            api_key: self.api_key.trim().to_string(),
+            default_model: self.model.unwrap_or_default().trim().to_string(),
+            per_cli_models: "{}".to_string(),
+            is_current: false,
+            sort_index: None,
+            notes: None,
+            created_at: chrono::Utc::now().timestamp(),
+            opencode_adapter: String::new(),
+            account_id: None,
+            model_aliases: "{}".to_string(),
+            account_base_url: None,
+            token_id: None,
+            quota: None,
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+}
+
+/// Parse and validate a `hajimi://add-provider?...` link.
+pub fn parse_link(raw: &str) -> Result<ProviderLink, String> {
+    let url = Url::parse(raw.trim()).map_err(|e| format!("Malformed link: {e}"))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported link scheme: {}", url.scheme()));
+    }
+    // `hajimi://add-provider?..` puts the action in the host, `hajimi:add-provider?..` in the path
+    let action = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| url.path().trim_matches('/'));
+    if action != ACTION_ADD_PROVIDER {
+        return Err(format!("Unsupported link action: {action}"));
+    }
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let link = ProviderLink {
+        name: param("name").unwrap_or_default(),
+        url: param("url").ok_or("Link is missing the url parameter")?,
+        api_key: param("key").ok_or("Link is missing the key parameter")?,
+        model: param("model"),
+    };
+    link.validate()?;
+    Ok(link)
+}
+
+/// A received link waiting for the UI: a provider to confirm, or why the
+/// link was rejected.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum PendingLink {
+    Provider(ProviderLink),
+    Error(String),
+}
+
+/// Links received but not yet taken by the UI.
+#[derive(Default)]
+pub struct PendingLinks(Mutex<Vec<PendingLink>>);
+
+impl PendingLinks {
+    fn push(&self, link: PendingLink) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(link);
+    }
+
+    /// Everything queued so far, oldest first; the queue is left empty.
+    pub fn take(&self) -> Vec<PendingLink> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Handle a link opened by the OS: queue the parsed provider for
+/// confirmation, or the error the UI should show, and tell the UI.
+pub fn handle_url<R: Runtime>(app: &AppHandle<R>, raw: &str) {
+    let pending = match parse_link(raw) {
+        Ok(link) => {
+            tracing::info!("[deep_link] Provider link received for {}", link.name);
+            PendingLink::Provider(link)
+        }
+        Err(e) => {
+            tracing::warn!("[deep_link] Rejected link {}: {}", logging::redact(raw), e);
+            PendingLink::Error(e)
+        }
+    };
+    app.state::<PendingLinks>().push(pending);
+    if let Err(e) = app.emit(EVENT_RECEIVED, ()) {
+        tracing::warn!("[deep_link] Failed to emit link event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_add_provider_link() {
+        let link = parse_link(
+            "hajimi://add-provider?name=Team%20Proxy&url=https%3A%2F%2Fproxy.example.com&key=sk-abc&model=claude-sonnet-4-5",
+        )
+        .unwrap();
+        assert_eq!(link.name, "Team Proxy");
+        assert_eq!(link.url, "https://proxy.example.com");
+        assert_eq!(link.api_key, "sk-abc");
+        assert_eq!(link.model.as_deref(), Some("claude-sonnet-4-5"));
+
+        let record = link.into_record();
+        assert!(!record.id.is_empty());
+        assert!(!record.is_current);
+        assert_eq!(record.default_model, "claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn test_pending_links_drain_in_order() {
+        let pending = PendingLinks::default();
+        pending.push(PendingLink::Error("bad".to_string()));
+        pending.push(PendingLink::Provider(
+            parse_link("hajimi://add-provider?name=a&url=https://x.com&key=k").unwrap(),
+        ));
+        let taken = pending.take();
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[0], PendingLink::Error("bad".to_string()));
+        assert!(pending.take().is_empty());
+
+        let json = serde_json::to_value(&taken[1]).unwrap();
+        assert_eq!(json["kind"], "provider");
+        assert_eq!(json["value"]["url"], "https://x.com");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_links() {
+        let bad = [
+            "not a url",
+            "https://add-provider?name=a&url=https://x.com&key=k",
+            "hajimi://delete-everything?name=a&url=https://x.com&key=k",
+            "hajimi://add-provider?name=a&url=http://x.com&key=k",
+            "hajimi://add-provider?name=a&url=ftp://x.com&key=k",
+            "hajimi://add-provider?name=a&url=https://x.com",
+            "hajimi://add-provider?name=a&url=https://x.com&key=%20",
+            "hajimi://add-provider?url=https://x.com&key=k",
+        ];
+        for raw in bad {
+            assert!(parse_link(raw).is_err(), "accepted: {raw}");
+        }
+    }
+}
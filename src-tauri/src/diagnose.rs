@@ -0,0 +1,418 @@
+//! One-app "doctor": why isn't this tool talking to the current provider?
+//!
+//! Runs the checks a user would otherwise piece together from the dashboard,
+//! the system check and a connection test — installation, config files, the
+//! base URL each file points at, shell variables that override it, and a live
+//! request in the app's own wire format — and returns them as one ordered list
+//! of findings.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::connection_test::{self, ProbeErrorKind, ProtocolCheck};
+use crate::database::dao::providers::{self, ProviderRecord};
+use crate::database::Database;
+use crate::error::{get_install_hint, CommandError};
+use crate::opencode_sync::OpencodeAdapter;
+use crate::store::AppState;
+use crate::sync_target::{self, SyncTarget};
+use crate::system_check::{self, EnvConflict, IssueSeverity, SystemIssue};
+use crate::utils;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnosis {
+    pub app: String,
+    /// Name of the current provider, if there is one.
+    pub provider: Option<String>,
+    /// In the order the checks ran; passing checks are `Info`.
+    pub findings: Vec<SystemIssue>,
+}
+
+fn finding(
+    severity: IssueSeverity,
+    code: &str,
+    message: impl Into<String>,
+    fix_hint: impl Into<String>,
+) -> SystemIssue {
+    SystemIssue {
+        severity,
+        code: code.to_string(),
+        message: message.into(),
+        fix_hint: fix_hint.into(),
+    }
+}
+
+/// Tauri command: run every check for `app` against the current provider.
+#[tauri::command]
+pub async fn diagnose(state: State<'_, AppState>, app: String) -> Result<Diagnosis, CommandError> {
+    if sync_target::find(&app).is_none() {
+        return Err(CommandError::unknown_app(&app));
+    }
+    let db = Arc::clone(&state.db);
+    let local_app = app.clone();
+    let (mut diagnosis, probe) =
+        utils::run_blocking(move || local_checks(&db, &local_app)).await??;
+
+    if let Some((proxy_url, api_key)) = probe {
+        let checks = match crate::secrets::resolve(&api_key).await {
+            Ok(key) => connection_test::test_connection_for(&app, &proxy_url, &key, None)
+                .await
+                .map_err(|e| finding(
+                    IssueSeverity::Error,
+                    "CONNECTION_FAILED",
+                    crate::logging::redact(&e),
+                    "",
+                )),
+            Err(e) => Err(finding(
+                IssueSeverity::Error,
+                &e.code,
+                e.message,
+                "Check the op:// or keychain:// reference in the provider, and that 1Password is unlocked.",
+            )),
+        };
+        let findings = match checks {
+            Ok(checks) => checks.iter().map(connection_finding).collect(),
+            Err(issue) => vec![issue],
+        };
+        diagnosis.findings.extend(findings);
+    }
+    Ok(diagnosis)
+}
+
+/// Everything but the network probe. Also returns the proxy URL and key to
+/// probe with, when there is a provider to probe.
+fn local_checks(
+    db: &Database,
+    app: &str,
+) -> Result<(Diagnosis, Option<(String, String)>), CommandError> {
+    let target = sync_target::find(app).ok_or_else(|| CommandError::unknown_app(app))?;
+    let provider = providers::get_current(db)?;
+    let mut findings = Vec::new();
+
+    let (installed, version) = target.detect();
+    findings.push(install_finding(
+        target.as_ref(),
+        installed,
+        version.as_deref(),
+    ));
+
+    if let Some(steps) = target.manual_steps() {
+        findings.push(finding(
+            IssueSeverity::Info,
+            "MANUAL_SETUP",
+            format!(
+                "{} is set up by hand; its settings aren't checked",
+                target.display_name()
+            ),
+            steps,
+        ));
+    } else if installed {
+        findings.extend(target.config_paths().iter().map(|p| config_file_finding(p)));
+    }
+
+    let Some(provider) = provider else {
+        findings.push(finding(
+            IssueSeverity::Warning,
+            "NO_PROVIDER",
+            "No provider is selected",
+            "Add a provider and make it the current one.",
+        ));
+        return Ok((diagnosis(app, None, findings), None));
+    };
+
+    let proxy_url = target.proxy_url(&provider.url);
+    if installed && target.manual_steps().is_none() {
+        findings.push(base_url_finding(target.as_ref(), &provider, &proxy_url));
+    }
+    findings.extend(
+        system_check::env_conflicts(app)
+            .iter()
+            .map(|c| env_finding(c, &proxy_url)),
+    );
+
+    let probe = (!provider.api_key.trim().is_empty())
+        .then(|| (proxy_url.clone(), provider.api_key.clone()));
+    if probe.is_none() {
+        findings.push(finding(
+            IssueSeverity::Warning,
+            "NO_API_KEY",
+            format!("{} has no API key", provider.name),
+            "Add the key to the provider to test the connection.",
+        ));
+    }
+    Ok((diagnosis(app, Some(provider.name), findings), probe))
+}
+
+fn diagnosis(app: &str, provider: Option<String>, findings: Vec<SystemIssue>) -> Diagnosis {
+    Diagnosis {
+        app: app.to_string(),
+        provider,
+        findings,
+    }
+}
+
+fn install_finding(target: &dyn SyncTarget, installed: bool, version: Option<&str>) -> SystemIssue {
+    let name = target.display_name();
+    if !installed {
+        return finding(
+            IssueSeverity::Error,
+            "NOT_INSTALLED",
+            format!("{name} is not installed"),
+            get_install_hint(target.id()),
+        );
+    }
+    let message = match version {
+        Some(version) => format!("{name} {version} is installed"),
+        None => format!("{name} is installed"),
+    };
+    finding(IssueSeverity::Info, "INSTALLED", message, "")
+}
+
+fn config_file_finding(path: &Path) -> SystemIssue {
+    let shown = path.to_string_lossy();
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return finding(
+                IssueSeverity::Warning,
+                "CONFIG_MISSING",
+                format!("{shown} does not exist"),
+                "Sync this app to create it.",
+            );
+        }
+        Err(e) => {
+            return finding(
+                IssueSeverity::Error,
+                "CONFIG_UNREADABLE",
+                format!("Cannot read {shown}: {e}"),
+                "Check the file's permissions.",
+            );
+        }
+    };
+    match parse_error(path, &content) {
+        Some(reason) => finding(
+            IssueSeverity::Error,
+            "CONFIG_INVALID",
+            format!("{shown} cannot be parsed: {reason}"),
+            "Fix the syntax error, or restore the file from a backup.",
+        ),
+        None => finding(
+            IssueSeverity::Info,
+            "CONFIG_OK",
+            format!("{shown} is valid"),
+            "",
+        ),
+    }
+}
+
+/// Why `content` doesn't parse as the format its extension names. Files in
+/// other formats (`.env`, plain text) are not checked.
+fn parse_error(path: &Path, content: &str) -> Option<String> {
+    if content.trim().is_empty() {
+        return None;
+    }
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json" | "jsonc") => utils::parse_json_lenient(content)
+            .err()
+            .map(|e| e.to_string()),
+        Some("toml") => content
+            .parse::<toml_edit::DocumentMut>()
+            .err()
+            .map(|e| e.message().to_string()),
+        _ => None,
+    }
+}
+
+fn base_url_finding(
+    target: &dyn SyncTarget,
+    provider: &ProviderRecord,
+    proxy_url: &str,
+) -> SystemIssue {
+    let adapter = OpencodeAdapter::parse(&provider.opencode_adapter);
+    let status = target.status(Some(proxy_url), adapter).status;
+    let name = target.display_name();
+    if status.is_synced() {
+        return finding(
+            IssueSeverity::Info,
+            "BASE_URL_MATCHES",
+            format!("{name} points at {} ({proxy_url})", provider.name),
+            "",
+        );
+    }
+    match status.current_base_url() {
+        Some(url) => finding(
+            IssueSeverity::Warning,
+            "BASE_URL_MISMATCH",
+            format!(
+                "{name} points at {}, not {} ({proxy_url})",
+                crate::logging::redact(&url),
+                provider.name
+            ),
+            format!("Sync {name} to switch it to {}.", provider.name),
+        ),
+        None => finding(
+            IssueSeverity::Warning,
+            "BASE_URL_NOT_SET",
+            format!("{name} has no base URL set, so it uses its default endpoint"),
+            format!("Sync {name} to point it at {}.", provider.name),
+        ),
+    }
+}
+
+fn env_finding(conflict: &EnvConflict, proxy_url: &str) -> SystemIssue {
+    let location = match (&conflict.source, conflict.line) {
+        (Some(file), Some(line)) => format!("{file}:{line}"),
+        (Some(file), None) => file.clone(),
+        (None, _) => "the environment".to_string(),
+    };
+    let fix_hint = match &conflict.source {
+        Some(file) => format!(
+            "Remove or comment out {} in {file}, then open a new terminal.",
+            conflict.name
+        ),
+        None => format!(
+            "Unset {} where it's defined (system environment variables, launchctl or your terminal), then restart the app and the terminal.",
+            conflict.name
+        ),
+    };
+    // A base URL that already matches the provider does no harm
+    let harmless = conflict
+        .value
+        .as_deref()
+        .is_some_and(|v| v.trim_end_matches('/') == proxy_url.trim_end_matches('/'));
+    let (severity, message) = match &conflict.value {
+        Some(value) if harmless => (
+            IssueSeverity::Info,
+            format!(
+                "{} is set to {value} in {location}, matching the provider",
+                conflict.name
+            ),
+        ),
+        Some(value) => (
+            IssueSeverity::Warning,
+            format!(
+                "{} is set to {value} in {location} and can override the synced config",
+                conflict.name
+            ),
+        ),
+        None => (
+            IssueSeverity::Warning,
+            format!(
+                "{} is set in {location} and can override the synced config",
+                conflict.name
+            ),
+        ),
+    };
+    finding(severity, "ENV_OVERRIDE", message, fix_hint)
+}
+
+fn connection_finding(check: &ProtocolCheck) -> SystemIssue {
+    let protocol = format!("{:?}", check.protocol);
+    if check.ok {
+        let latency = check
+            .latency_ms
+            .map(|ms| format!(" in {ms} ms"))
+            .unwrap_or_default();
+        return finding(
+            IssueSeverity::Info,
+            "CONNECTION_OK",
+            format!("{protocol} request with {} succeeded{latency}", check.model),
+            "",
+        );
+    }
+    let (message, kind) = match &check.error {
+        Some(e) => (crate::logging::redact(&e.message), Some(e.kind)),
+        None => ("request failed".to_string(), None),
+    };
+    let fix_hint = match kind {
+        Some(ProbeErrorKind::Auth) => "Check the provider's API key.".to_string(),
+        Some(ProbeErrorKind::Connect | ProbeErrorKind::Timeout) => {
+            "Check the provider URL and your network or proxy settings.".to_string()
+        }
+        Some(ProbeErrorKind::RateLimited) => {
+            "The provider is rate limiting; wait a moment or switch provider.".to_string()
+        }
+        _ => format!(
+            "Check that the provider serves {protocol} requests for {}.",
+            check.model
+        ),
+    };
+    finding(
+        IssueSeverity::Error,
+        "CONNECTION_FAILED",
+        format!("{protocol} request to {} failed: {message}", check.endpoint),
+        fix_hint,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_file_finding() {
+        let tmp = tempfile::tempdir().unwrap();
+        let json = tmp.path().join("settings.json");
+        assert_eq!(config_file_finding(&json).code, "CONFIG_MISSING");
+
+        std::fs::write(&json, "{\"env\": {}, // note\n}").unwrap();
+        assert_eq!(config_file_finding(&json).code, "CONFIG_OK");
+        std::fs::write(&json, "{\"env\": ").unwrap();
+        let broken = config_file_finding(&json);
+        assert_eq!(broken.code, "CONFIG_INVALID");
+        assert_eq!(broken.severity, IssueSeverity::Error);
+
+        let toml = tmp.path().join("config.toml");
+        std::fs::write(&toml, "model = \"gpt-5\"\n[model_providers").unwrap();
+        assert_eq!(config_file_finding(&toml).code, "CONFIG_INVALID");
+        let env = tmp.path().join(".env");
+        std::fs::write(&env, "not = [json").unwrap();
+        assert_eq!(config_file_finding(&env).code, "CONFIG_OK");
+    }
+
+    #[test]
+    fn test_env_finding_severity() {
+        let mut conflict = EnvConflict {
+            name: "ANTHROPIC_BASE_URL".to_string(),
+            source: Some("/home/me/.zshrc".to_string()),
+            line: Some(4),
+            value: Some("https://api.anthropic.com".to_string()),
+        };
+        let issue = env_finding(&conflict, "http://localhost:8045");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(issue.message.contains("/home/me/.zshrc:4"));
+
+        conflict.value = Some("http://localhost:8045/".to_string());
+        assert_eq!(
+            env_finding(&conflict, "http://localhost:8045").severity,
+            IssueSeverity::Info
+        );
+
+        let key = EnvConflict {
+            name: "ANTHROPIC_API_KEY".to_string(),
+            source: None,
+            line: None,
+            value: None,
+        };
+        let issue = env_finding(&key, "http://localhost:8045");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(issue.message.contains("the environment"));
+    }
+
+    #[test]
+    fn test_local_checks_without_provider() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::paths::override_home(home.path());
+        let db = Database::memory().unwrap();
+        let (diagnosis, probe) = local_checks(&db, "claude").unwrap();
+        assert!(probe.is_none());
+        assert_eq!(diagnosis.provider, None);
+        let codes: Vec<_> = diagnosis.findings.iter().map(|f| f.code.as_str()).collect();
+        assert_eq!(codes.last(), Some(&"NO_PROVIDER"));
+        assert!(local_checks(&db, "nope").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Detect manual edits to config files between syncs.
+//!
+//! After every successful sync the SHA-256 of each file the app owns is stored
+//! in `file_hashes`. Before the next sync the on-disk hash is compared; a
+//! mismatch means the file was edited since we last wrote it. Depending on the
+//! `drift_policy` setting the sync then goes ahead with a warning or refuses to
+//! touch that app.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::database::{
+    dao::{file_hashes, settings},
+    Database,
+};
+use crate::error::SyncError;
+
+const POLICY_KEY: &str = "drift_policy";
+
+/// What to do when a file changed since our last write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftPolicy {
+    /// Sync anyway and report the edited files as a warning.
+    #[default]
+    Note,
+    /// Skip the app with a `MODIFIED_EXTERNALLY` error.
+    Abort,
+}
+
+impl DriftPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "note" => Some(Self::Note),
+            "abort" => Some(Self::Abort),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Note => "note",
+            Self::Abort => "abort",
+        }
+    }
+}
+
+pub fn policy(db: &Database) -> DriftPolicy {
+    settings::get(db, POLICY_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| DriftPolicy::parse(&v))
+        .unwrap_or_default()
+}
+
+pub fn set_policy(db: &Database, policy: DriftPolicy) -> Result<(), String> {
+    settings::set(db, POLICY_KEY, policy.as_str())
+}
+
+/// Config files a sync of `app` writes.
+pub(crate) fn managed_files(app: &str) -> Vec<PathBuf> {
+    crate::sync_target::find(app)
+        .map(|target| target.config_paths())
+        .unwrap_or_default()
+}
+
+fn sha256_file(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&content)))
+}
+
+/// Files among `paths` whose content no longer matches the recorded hash.
+/// Files we never wrote, or that have since been deleted, don't count.
+fn changed_since_write(db: &Database, paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let key = path.to_string_lossy();
+            let recorded = file_hashes::get(db, &key).ok().flatten()?;
+            let current = sha256_file(path)?;
+            (current != recorded).then(|| key.into_owned())
+        })
+        .collect()
+}
+
+/// Files of `app` edited since the last sync.
+pub fn modified_files(db: &Database, app: &str) -> Vec<String> {
+    changed_since_write(db, &managed_files(app))
+}
+
+/// Pre-sync check. `Ok(Some(note))` when files changed under the `Note`
+/// policy, `Err` under `Abort`.
+pub fn check_before_sync(db: &Database, app: &str) -> Result<Option<String>, SyncError> {
+    let files = modified_files(db, app);
+    if files.is_empty() {
+        return Ok(None);
+    }
+    tracing::warn!("[drift] {} edited since last sync: {:?}", app, files);
+    match policy(db) {
+        DriftPolicy::Note => Ok(Some(format!(
+            "Overwrote manual edits in: {}",
+            files.join(", ")
+        ))),
+        DriftPolicy::Abort => Err(SyncError::ModifiedExternally {
+            app: app.to_string(),
+            files,
+        }),
+    }
+}
+
+/// Store the hashes of `app`'s files after a successful sync.
+pub fn record(db: &Database, app: &str) {
+    for path in managed_files(app) {
+        let Some(hash) = sha256_file(&path) else {
+            continue;
+        };
+        if let Err(e) = file_hashes::record(db, app, &path.to_string_lossy(), &hash) {
+            tracing::warn!("[drift] Failed to record hash for {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Drop `app`'s hashes once its files are handed back (restore).
+pub fn forget(db: &Database, app: &str) {
+    for path in managed_files(app) {
+        let _ = file_hashes::remove(db, &path.to_string_lossy());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_since_write() {
+        let db = Database::memory().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let ours = tmp.path().join("config.toml");
+        let untracked = tmp.path().join("other.toml");
+        fs::write(&ours, "model = \"a\"\n").unwrap();
+        fs::write(&untracked, "x").unwrap();
+        let hash = sha256_file(&ours).unwrap();
+        file_hashes::record(&db, "codex", &ours.to_string_lossy(), &hash).unwrap();
+
+        let paths = vec![ours.clone(), untracked];
+        assert!(changed_since_write(&db, &paths).is_empty());
+
+        fs::write(&ours, "model = \"hand-tuned\"\n").unwrap();
+        assert_eq!(
+            changed_since_write(&db, &paths),
+            vec![ours.to_string_lossy().into_owned()]
+        );
+
+        // A deleted file is simply rewritten, not reported
+        fs::remove_file(&ours).unwrap();
+        assert!(changed_since_write(&db, &paths).is_empty());
+    }
+
+    #[test]
+    fn test_policy_defaults_to_note() {
+        let db = Database::memory().unwrap();
+        assert_eq!(policy(&db), DriftPolicy::Note);
+        set_policy(&db, DriftPolicy::Abort).unwrap();
+        assert_eq!(policy(&db), DriftPolicy::Abort);
+    }
+}
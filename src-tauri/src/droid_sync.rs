@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-use crate::utils;
+use crate::file_status::{FileStatus, SyncStatus};
+use crate::model_aliases::ModelAliases;
+use crate::provider_import::DetectedProvider;
+use crate::{config_paths, model_list, paths, utils, wsl};
 
 const DROID_DIR: &str = ".factory";
 const DROID_CONFIG_FILE: &str = "settings.json";
@@ -22,89 +26,311 @@ pub struct DroidStatus {
     pub synced_count: usize,
 }
 
-fn get_config_path() -> Option<PathBuf> {
-    dirs::home_dir().map(|h| h.join(DROID_DIR).join(DROID_CONFIG_FILE))
+pub(crate) fn get_config_path() -> Option<PathBuf> {
+    config_paths::resolve_file(
+        "droid",
+        None,
+        paths::cli_home_dir().map(|h| h.join(DROID_DIR).join(DROID_CONFIG_FILE)),
+    )
 }
 
 pub fn check_droid_installed() -> (bool, Option<String>) {
-    match utils::resolve_executable("droid") {
+    match wsl::resolve_cli("droid") {
         Some(path) => {
-            let version = utils::get_cli_version(&path);
+            let version = wsl::cli_version(&path);
             (true, version.or_else(|| Some("unknown".to_string())))
         }
         None => (false, None),
     }
 }
 
+/// Display-name prefix on every entry we write; lets status recognise our
+/// entries even if Droid rewrites the `id`.
+const AG_NAME_PREFIX: &str = "[Hajimi] ";
+
+/// Count our custom models. Handles both the `customModels` (camelCase) and the
+/// newer `custom_models` (snake_case) layouts and their URL field names.
 fn count_synced_models(json: &Value) -> (usize, Option<String>) {
     let mut count = 0;
     let mut first_url = None;
 
-    if let Some(arr) = json.get("customModels").and_then(|v| v.as_array()) {
-        for m in arr {
-            let id = m.get("id").and_then(|v| v.as_str()).unwrap_or_default();
-            if !id.starts_with(AG_ID_PREFIX) {
-                continue;
-            }
-            count += 1;
-            if first_url.is_none() {
-                first_url = m
-                    .get("baseUrl")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-            }
+    let entries = ["customModels", "custom_models"]
+        .iter()
+        .filter_map(|key| json.get(*key).and_then(|v| v.as_array()))
+        .flatten();
+    for m in entries {
+        if !is_hajimi_entry(m) {
+            continue;
+        }
+        count += 1;
+        if first_url.is_none() {
+            first_url = m
+                .get("baseUrl")
+                .or_else(|| m.get("base_url"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
         }
     }
     (count, first_url)
 }
 
-pub fn get_sync_status(proxy_url: &str) -> (bool, bool, Option<String>, usize) {
-    let config_path = match get_config_path() {
-        Some(p) => p,
-        None => return (false, false, None, 0),
-    };
+fn is_hajimi_entry(m: &Value) -> bool {
+    let str_field = |k: &str| m.get(k).and_then(|v| v.as_str());
+    str_field("id").is_some_and(|id| id.starts_with(AG_ID_PREFIX))
+        || ["displayName", "model_display_name", "name"]
+            .iter()
+            .any(|k| str_field(k).is_some_and(|n| n.starts_with(AG_NAME_PREFIX)))
+}
 
-    let backup_path = config_path.with_file_name(format!("{DROID_CONFIG_FILE}{BACKUP_SUFFIX}"));
-    let has_backup = backup_path.exists();
+/// Status plus how many of our models are in the file.
+pub fn get_sync_status(proxy_url: Option<&str>) -> (SyncStatus, usize) {
+    let Some(config_path) = get_config_path() else {
+        return (SyncStatus::default(), 0);
+    };
 
-    if !config_path.exists() {
-        return (false, has_backup, None, 0);
-    }
+    let has_backup = utils::find_backup(&config_path).is_some();
 
-    let content = match fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return (false, has_backup, None, 0),
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        let missing = FileStatus::missing(DROID_CONFIG_FILE);
+        return (SyncStatus::new(vec![missing], has_backup), 0);
     };
 
     let json: Value = serde_json::from_str(&content).unwrap_or_default();
     let (synced_count, first_url) = count_synced_models(&json);
-    // is_synced: must have AG models AND their baseUrl must match current proxy
-    let is_synced = synced_count > 0
-        && first_url
-            .as_deref()
-            .is_some_and(|u| utils::urls_match(u, proxy_url));
-    (is_synced, has_backup, first_url, synced_count)
+    // Synced: must have AG models AND their baseUrl must match current proxy
+    let status = if synced_count == 0 {
+        FileStatus::new(DROID_CONFIG_FILE, true)
+            .with_synced(false)
+            .with_note("No Hajimi models")
+    } else {
+        FileStatus::new(DROID_CONFIG_FILE, true).with_url(first_url, proxy_url, "baseUrl", false)
+    };
+    (SyncStatus::new(vec![status], has_backup), synced_count)
 }
 
-fn build_droid_custom_models(proxy_url: &str, api_key: &str, model_ids: &[&str]) -> Vec<Value> {
+/// URL and key from Droid's custom models: ours first, else the first complete entry.
+/// No model is returned since Droid mirrors the whole list.
+pub fn detect_existing_provider() -> Option<DetectedProvider> {
+    detect_from_config(&fs::read_to_string(get_config_path()?).ok()?)
+}
+
+fn detect_from_config(content: &str) -> Option<DetectedProvider> {
+    let json: Value = serde_json::from_str(content).ok()?;
+    let entries: Vec<&Value> = ["customModels", "custom_models"]
+        .iter()
+        .filter_map(|key| json.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .collect();
+    let fields = |m: &Value| {
+        let url = m.get("baseUrl").or_else(|| m.get("base_url"))?.as_str()?;
+        let key = m.get("apiKey").or_else(|| m.get("api_key"))?.as_str()?;
+        Some((url.to_string(), key.to_string()))
+    };
+    let (url, key) = entries
+        .iter()
+        .filter(|m| is_hajimi_entry(m))
+        .chain(entries.iter())
+        .find_map(|m| fields(m))?;
+    DetectedProvider::new("droid", &url, &key, None)
+}
+
+/// Used when the proxy's model list can't be fetched.
+const DEFAULT_MODELS: &[&str] = &[
+    "claude-sonnet-4-5",
+    "claude-sonnet-4-5-thinking",
+    "claude-opus-4-5-thinking",
+    "gemini-3-pro-high",
+    "gemini-3-pro-low",
+    "gemini-3-flash",
+    "gemini-2.5-flash",
+    "gemini-2.5-pro",
+    "gpt-4o",
+    "o3",
+];
+
+/// Fetch chat model ids from `{proxy}/v1/models`. Returns an empty list on any
+/// failure so the caller can fall back to `DEFAULT_MODELS`.
+async fn fetch_model_ids(proxy_url: &str, api_key: &str) -> Vec<String> {
+    let ids = model_list::fetch_openai_models(
+        proxy_url,
+        api_key,
+        model_list::FETCH_RETRIES,
+        model_list::FETCH_TIMEOUT,
+    )
+    .await
+    .unwrap_or_default();
+    // Droid only talks chat to custom models
+    ids.into_iter()
+        .filter(|id| model_list::classify_model(id, None).is_chat())
+        .collect()
+}
+
+/// Wire protocol Droid uses for a BYOK custom model (`provider` field).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroidProvider {
+    Anthropic,
+    Openai,
+    GenericChat,
+}
+
+impl DroidProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Anthropic => "anthropic",
+            Self::Openai => "openai",
+            Self::GenericChat => "generic-chat-completion-api",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "anthropic" => Some(Self::Anthropic),
+            "openai" => Some(Self::Openai),
+            "generic-chat-completion-api" | "generic" => Some(Self::GenericChat),
+            _ => None,
+        }
+    }
+
+    /// Infer the protocol from the model id: Claude speaks Anthropic Messages,
+    /// GPT / o-series speak OpenAI, everything else (Gemini, open models) goes
+    /// through the generic chat-completions adapter.
+    pub fn for_model(model_id: &str) -> Self {
+        let id = model_id.to_lowercase();
+        if id.contains("claude") {
+            Self::Anthropic
+        } else if id.starts_with("gpt")
+            || id.starts_with("o1")
+            || id.starts_with("o3")
+            || id.starts_with("o4")
+            || id.contains("codex")
+        {
+            Self::Openai
+        } else {
+            Self::GenericChat
+        }
+    }
+
+    /// Anthropic's SDK appends /v1 itself; the OpenAI-style providers expect it.
+    fn base_url(&self, proxy_url: &str) -> String {
+        let root = proxy_url
+            .trim()
+            .trim_end_matches('/')
+            .trim_end_matches("/v1");
+        match self {
+            Self::Anthropic => root.to_string(),
+            Self::Openai | Self::GenericChat => format!("{root}/v1"),
+        }
+    }
+}
+
+/// Token limits written on each custom model. Newer Droid builds ignore
+/// entries without them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelLimits {
+    pub context_window: u64,
+    pub max_output_tokens: u64,
+}
+
+impl ModelLimits {
+    /// Sensible per-family defaults, inferred from the model id.
+    pub fn for_model(model_id: &str) -> Self {
+        let id = model_id.to_lowercase();
+        let (context_window, max_output_tokens) = if id.contains("claude") {
+            (200_000, 64_000)
+        } else if id.contains("gemini") {
+            (1_048_576, 65_536)
+        } else if id.starts_with("o1") || id.starts_with("o3") || id.starts_with("o4") {
+            (200_000, 100_000)
+        } else if id.starts_with("gpt") {
+            (128_000, 16_384)
+        } else {
+            (128_000, 32_768)
+        };
+        Self {
+            context_window,
+            max_output_tokens,
+        }
+    }
+}
+
+/// Per-model overrides, keyed by model id; both take precedence over inference.
+#[derive(Debug, Clone, Default)]
+pub struct DroidModelOverrides {
+    pub providers: HashMap<String, DroidProvider>,
+    pub limits: HashMap<String, ModelLimits>,
+}
+
+fn build_droid_custom_models(
+    proxy_url: &str,
+    api_key: &str,
+    model_ids: &[&str],
+    overrides: Option<&DroidModelOverrides>,
+) -> Vec<Value> {
     model_ids
         .iter()
         .map(|model_id| {
+            let provider = overrides
+                .and_then(|o| o.providers.get(*model_id).copied())
+                .unwrap_or_else(|| DroidProvider::for_model(model_id));
+            let limits = overrides
+                .and_then(|o| o.limits.get(*model_id).copied())
+                .unwrap_or_else(|| ModelLimits::for_model(model_id));
+            let display_name = format!("{AG_NAME_PREFIX}{model_id}");
+            // Old and new field names side by side: each Droid version ignores
+            // the keys it doesn't know.
             serde_json::json!({
                 "id": format!("{}{}", AG_ID_PREFIX, model_id),
-                "name": format!("[Hajimi] {}", model_id),
-                "baseUrl": proxy_url,
+                "model": model_id,
+                "name": display_name,
+                "displayName": display_name,
+                "baseUrl": provider.base_url(proxy_url),
                 "apiKey": api_key,
-                "provider": "anthropic"
+                "provider": provider.as_str(),
+                "contextWindow": limits.context_window,
+                "maxOutputTokens": limits.max_output_tokens,
+                "maxTokens": limits.max_output_tokens
             })
         })
         .collect()
 }
 
-pub fn sync_droid_config(
+pub async fn sync_droid_config(
     proxy_url: &str,
     api_key: &str,
     model: Option<&str>,
+    overrides: Option<&DroidModelOverrides>,
+    aliases: &ModelAliases,
+) -> Result<usize, String> {
+    // An explicit model is a single-model override; otherwise mirror the proxy.
+    let models_to_sync: Vec<String> = match model {
+        Some(m) => vec![m.to_string()],
+        None => {
+            let fetched = fetch_model_ids(proxy_url, api_key).await;
+            let ids = if fetched.is_empty() {
+                tracing::warn!("[droid_sync] Could not fetch models from proxy, using defaults");
+                DEFAULT_MODELS.iter().map(|s| s.to_string()).collect()
+            } else {
+                fetched
+            };
+            aliases.resolve_all(&ids, "droid")
+        }
+    };
+
+    let proxy_url = proxy_url.to_string();
+    let api_key = api_key.to_string();
+    let overrides = overrides.cloned();
+    utils::run_blocking(move || {
+        write_droid_config(&proxy_url, &api_key, &models_to_sync, overrides.as_ref())
+    })
+    .await?
+}
+
+/// Replace our `customModels` entries with `models`, keeping the user's own.
+fn write_droid_config(
+    proxy_url: &str,
+    api_key: &str,
+    models_to_sync: &[String],
+    overrides: Option<&DroidModelOverrides>,
 ) -> Result<usize, String> {
     let config_path = get_config_path()
         .ok_or_else(|| "Failed to get Droid config directory (home dir not found)".to_string())?;
@@ -131,34 +357,15 @@ pub fn sync_droid_config(
         config = serde_json::json!({});
     }
 
-    let default_models: Vec<&str> = vec![
-        "claude-sonnet-4-5",
-        "claude-sonnet-4-5-thinking",
-        "claude-opus-4-5-thinking",
-        "gemini-3-pro-high",
-        "gemini-3-pro-low",
-        "gemini-3-flash",
-        "gemini-2.5-flash",
-        "gemini-2.5-pro",
-        "gpt-4o",
-        "o3",
-    ];
-
-    let models_to_sync: Vec<&str> = if let Some(m) = model {
-        vec![m]
-    } else {
-        default_models
-    };
-
-    let new_ag_models = build_droid_custom_models(proxy_url, api_key, &models_to_sync);
+    let model_refs: Vec<&str> = models_to_sync.iter().map(|s| s.as_str()).collect();
+    let new_ag_models = build_droid_custom_models(proxy_url, api_key, &model_refs, overrides);
     let ag_count = new_ag_models.len();
 
     // Preserve user's non-AG custom models
     let mut existing_non_ag: Vec<Value> = Vec::new();
     if let Some(arr) = config.get("customModels").and_then(|v| v.as_array()) {
         for m in arr {
-            let id = m.get("id").and_then(|v| v.as_str()).unwrap_or_default();
-            if !id.starts_with(AG_ID_PREFIX) {
+            if !is_hajimi_entry(m) {
                 existing_non_ag.push(m.clone());
             }
         }
@@ -172,8 +379,10 @@ pub fn sync_droid_config(
         .ok_or_else(|| "Internal error: config is not an object".to_string())?;
     obj.insert("customModels".to_string(), Value::Array(merged));
 
-    let content = utils::to_json_pretty(&config)?;
-    utils::atomic_write(&config_path, &content)?;
+    utils::write_json_secure(&config_path, &config)?;
+    utils::verify_written_config(&config_path, &[api_key], || {
+        utils::write_json_secure(&config_path, &config)
+    })?;
 
     Ok(ag_count)
 }
@@ -182,14 +391,40 @@ pub fn restore_droid_config() -> Result<(), String> {
     let config_path =
         get_config_path().ok_or_else(|| "Failed to get Droid config directory".to_string())?;
 
-    let backup_path = config_path.with_file_name(format!("{DROID_CONFIG_FILE}{BACKUP_SUFFIX}"));
-    if backup_path.exists() {
+    if let Some(backup_path) = utils::find_backup(&config_path) {
         fs::rename(&backup_path, &config_path)
             .map_err(|e| format!("Failed to restore config: {e}"))?;
-        Ok(())
-    } else {
-        Err("No backup file found".to_string())
+        return Ok(());
+    }
+
+    // No backup found — drop only our custom models, keep the user's own.
+    if !config_path.exists() {
+        return Ok(());
+    }
+    let content =
+        fs::read_to_string(&config_path).map_err(|e| format!("Failed to read config: {e}"))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Cannot restore: settings.json is not valid JSON: {e}"))?;
+    let removed = strip_hajimi_models(&mut json);
+    if removed == 0 {
+        return Ok(());
     }
+    tracing::info!("[droid] No backup found, removed {removed} hajimi model(s) from config");
+    utils::write_json_secure(&config_path, &json).map_err(|e| e.to_string())
+}
+
+/// Remove our entries from `customModels` / `custom_models`, keeping user models.
+/// Returns how many entries were removed.
+fn strip_hajimi_models(json: &mut Value) -> usize {
+    let mut removed = 0;
+    for key in ["customModels", "custom_models"] {
+        if let Some(arr) = json.get_mut(key).and_then(|v| v.as_array_mut()) {
+            let before = arr.len();
+            arr.retain(|m| !is_hajimi_entry(m));
+            removed += before - arr.len();
+        }
+    }
+    removed
 }
 
 pub fn read_droid_config_content() -> Result<String, String> {
@@ -206,13 +441,68 @@ pub fn read_droid_config_content() -> Result<String, String> {
 pub fn write_droid_config_content(content: &str) -> Result<(), String> {
     let config_path = get_config_path().ok_or_else(|| "Config path not found".to_string())?;
     serde_json::from_str::<serde_json::Value>(content).map_err(|e| format!("Invalid JSON: {e}"))?;
-    utils::atomic_write(&config_path, content).map_err(|e| e.to_string())
+    utils::atomic_write_secure(&config_path, content).map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_sync_status_restore_end_to_end() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::paths::override_home(home.path());
+        let config_path = home.path().join(DROID_DIR).join(DROID_CONFIG_FILE);
+        let original = "{\n  \"customModels\": [\n    {\n      \"id\": \"custom:my-local\",\n      \"model\": \"llama3\"\n    }\n  ]\n}\n";
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, original).unwrap();
+
+        let proxy = "http://127.0.0.1:1";
+        let synced = sync_droid_config(
+            proxy,
+            "sk-e2e",
+            Some("claude-sonnet-4-5"),
+            None,
+            &ModelAliases::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(synced, 1);
+
+        let config: Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        let models = config["customModels"].as_array().unwrap();
+        assert_eq!(models[0]["model"], "llama3");
+        assert_eq!(models[1]["model"], "claude-sonnet-4-5");
+        assert_eq!(models[1]["apiKey"], "sk-e2e");
+        let (status, count) = get_sync_status(Some(proxy));
+        assert!(status.is_synced());
+        assert_eq!(count, 1);
+
+        restore_droid_config().unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+        assert!(!get_sync_status(Some(proxy)).0.is_synced());
+    }
+
+    #[test]
+    fn test_strip_hajimi_models_keeps_user_entries() {
+        let mut json = serde_json::json!({
+            "customModels": [
+                {"id": "custom:AG-claude-sonnet-4-5", "model": "claude-sonnet-4-5"},
+                {"id": "custom:my-local", "model": "llama3"},
+                {"id": "custom:renamed", "displayName": "[Hajimi] gpt-4o"}
+            ],
+            "custom_models": [{"model_display_name": "[Hajimi] gemini"}],
+            "theme": "dark"
+        });
+        assert_eq!(strip_hajimi_models(&mut json), 3);
+        assert_eq!(json["customModels"].as_array().unwrap().len(), 1);
+        assert_eq!(json["customModels"][0]["id"], "custom:my-local");
+        assert!(json["custom_models"].as_array().unwrap().is_empty());
+        assert_eq!(json["theme"], "dark");
+        assert_eq!(strip_hajimi_models(&mut json), 0);
+    }
+
     #[test]
     fn test_count_synced_models_empty() {
         let json = serde_json::json!({});
@@ -237,10 +527,132 @@ mod tests {
 
     #[test]
     fn test_build_droid_custom_models() {
-        let models = build_droid_custom_models("https://example.com", "sk-test", &["gpt-4o"]);
+        let models = build_droid_custom_models("https://example.com", "sk-test", &["gpt-4o"], None);
         assert_eq!(models.len(), 1);
         assert_eq!(models[0]["id"], "custom:AG-gpt-4o");
-        assert_eq!(models[0]["baseUrl"], "https://example.com");
+        assert_eq!(models[0]["baseUrl"], "https://example.com/v1");
         assert_eq!(models[0]["apiKey"], "sk-test");
+        assert_eq!(models[0]["provider"], "openai");
+    }
+
+    #[test]
+    fn test_droid_provider_for_model() {
+        let cases = [
+            ("claude-sonnet-4-5", DroidProvider::Anthropic),
+            ("claude-opus-4-5-thinking", DroidProvider::Anthropic),
+            ("gpt-4o", DroidProvider::Openai),
+            ("gpt-5-codex", DroidProvider::Openai),
+            ("o3", DroidProvider::Openai),
+            ("o4-mini", DroidProvider::Openai),
+            ("gemini-2.5-pro", DroidProvider::GenericChat),
+            ("gemini-3-flash", DroidProvider::GenericChat),
+            ("deepseek-v3", DroidProvider::GenericChat),
+        ];
+        for (id, expected) in cases {
+            assert_eq!(DroidProvider::for_model(id), expected, "{id}");
+        }
+    }
+
+    #[test]
+    fn test_droid_provider_base_url() {
+        let cases = [
+            (
+                DroidProvider::Anthropic,
+                "https://p.test/v1/",
+                "https://p.test",
+            ),
+            (DroidProvider::Anthropic, "https://p.test", "https://p.test"),
+            (DroidProvider::Openai, "https://p.test", "https://p.test/v1"),
+            (
+                DroidProvider::GenericChat,
+                "https://p.test/v1",
+                "https://p.test/v1",
+            ),
+        ];
+        for (provider, input, expected) in cases {
+            assert_eq!(provider.base_url(input), expected, "{provider:?} {input}");
+        }
+    }
+
+    #[test]
+    fn test_build_droid_custom_models_with_override() {
+        let overrides = DroidModelOverrides {
+            providers: HashMap::from([("gemini-2.5-pro".to_string(), DroidProvider::Openai)]),
+            limits: HashMap::from([(
+                "claude-sonnet-4-5".to_string(),
+                ModelLimits {
+                    context_window: 1_000_000,
+                    max_output_tokens: 32_000,
+                },
+            )]),
+        };
+        let models = build_droid_custom_models(
+            "https://p.test",
+            "k",
+            &["gemini-2.5-pro", "claude-sonnet-4-5"],
+            Some(&overrides),
+        );
+        assert_eq!(models[0]["provider"], "openai");
+        assert_eq!(models[1]["provider"], "anthropic");
+        assert_eq!(models[1]["baseUrl"], "https://p.test");
+        assert_eq!(models[1]["contextWindow"], 1_000_000);
+        assert_eq!(models[1]["maxOutputTokens"], 32_000);
+        // Inferred limits for models without an override
+        assert_eq!(models[0]["contextWindow"], 1_048_576);
+    }
+
+    #[test]
+    fn test_model_limits_for_model() {
+        let cases = [
+            ("claude-sonnet-4-5", 200_000, 64_000),
+            ("gemini-2.5-pro", 1_048_576, 65_536),
+            ("o3", 200_000, 100_000),
+            ("gpt-4o", 128_000, 16_384),
+            ("deepseek-v3", 128_000, 32_768),
+        ];
+        for (id, context, output) in cases {
+            let l = ModelLimits::for_model(id);
+            assert_eq!(
+                (l.context_window, l.max_output_tokens),
+                (context, output),
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_synced_models_new_schema() {
+        let json = serde_json::json!({
+            "custom_models": [
+                { "model_display_name": "[Hajimi] gpt-4o", "base_url": "https://p.test/v1" },
+                { "model_display_name": "Mine", "base_url": "https://other.test" }
+            ],
+            "customModels": [
+                { "id": "custom:[Hajimi] claude-0", "displayName": "[Hajimi] claude", "baseUrl": "https://p.test" }
+            ]
+        });
+        let (count, url) = count_synced_models(&json);
+        assert_eq!(count, 2);
+        assert_eq!(url.as_deref(), Some("https://p.test"));
+    }
+
+    #[test]
+    fn test_detect_from_config_prefers_our_entries() {
+        let config = r#"{"custom_models": [
+            {"model": "x", "base_url": "https://other.test", "api_key": "other"},
+            {"id": "custom:AG-y", "base_url": "https://ours.test/v1", "api_key": "ours"}
+        ]}"#;
+        let d = detect_from_config(config).unwrap();
+        assert_eq!(
+            (d.url.as_str(), d.api_key.as_str()),
+            ("https://ours.test", "ours")
+        );
+
+        let d = detect_from_config(
+            r#"{"customModels": [{"baseUrl": "https://o.test", "apiKey": "k"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(d.api_key, "k");
+        assert!(detect_from_config(r#"{"customModels": []}"#).is_none());
     }
 }
@@ -0,0 +1,314 @@
+//! Environment-variable snippets for tools that read OPENAI_* / ANTHROPIC_* /
+//! GEMINI_* instead of a config file (aider, scripts, curl, self-hosted LobeChat).
+//!
+//! Keys are included verbatim — that is the point of the snippet — so files
+//! are only written into the user's home or a folder they picked, with owner-
+//! only permissions and a rotated backup of whatever was there.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tauri::State;
+
+use crate::crypto;
+use crate::database::dao::providers::{self, ProviderRecord};
+use crate::error::CommandError;
+use crate::secrets;
+use crate::store::AppState;
+use crate::utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnvFormat {
+    Bash,
+    Fish,
+    PowerShell,
+    Dotenv,
+    Direnv,
+}
+
+impl EnvFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bash" | "sh" | "zsh" => Some(Self::Bash),
+            "fish" => Some(Self::Fish),
+            "powershell" | "pwsh" => Some(Self::PowerShell),
+            "dotenv" => Some(Self::Dotenv),
+            "direnv" => Some(Self::Direnv),
+            _ => None,
+        }
+    }
+
+    /// File written by `write_envrc`, for the formats that have one.
+    fn file_name(self) -> Option<&'static str> {
+        match self {
+            Self::Direnv => Some(".envrc"),
+            Self::Dotenv => Some(".env"),
+            _ => None,
+        }
+    }
+
+    fn line(self, name: &str, value: &str) -> String {
+        match self {
+            Self::Bash | Self::Direnv => {
+                format!("export {name}='{}'", value.replace('\'', r"'\''"))
+            }
+            Self::Fish => format!(
+                "set -gx {name} '{}'",
+                value.replace('\\', r"\\").replace('\'', r"\'")
+            ),
+            Self::PowerShell => format!("$env:{name} = '{}'", value.replace('\'', "''")),
+            Self::Dotenv => format!(
+                "{name}=\"{}\"",
+                value.replace('\\', r"\\").replace('"', "\\\"")
+            ),
+        }
+    }
+}
+
+/// Variable name → value for each protocol, with the same base-URL suffix
+/// rules the synced apps use.
+fn env_vars(provider: &ProviderRecord) -> Vec<(&'static str, String)> {
+    let openai = crate::get_proxy_url("codex", &provider.url);
+    let anthropic = crate::get_proxy_url("claude", &provider.url);
+    let gemini = crate::get_proxy_url("gemini", &provider.url);
+    let key = provider.api_key.clone();
+    vec![
+        ("OPENAI_BASE_URL", openai.clone()),
+        // Older OpenAI SDKs and aider read this name
+        ("OPENAI_API_BASE", openai),
+        ("OPENAI_API_KEY", key.clone()),
+        ("ANTHROPIC_BASE_URL", anthropic),
+        ("ANTHROPIC_API_KEY", key.clone()),
+        ("GOOGLE_GEMINI_BASE_URL", gemini),
+        ("GEMINI_API_KEY", key),
+    ]
+}
+
+fn render(format: EnvFormat, provider: &ProviderRecord) -> String {
+    let name: String = provider
+        .name
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let mut out = format!("# Hajimi AI Switch — {name}\n");
+    for (var, value) in env_vars(provider) {
+        out.push_str(&format.line(var, &value));
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_format(format: &str) -> Result<EnvFormat, CommandError> {
+    EnvFormat::parse(format.trim()).ok_or_else(|| {
+        CommandError::invalid_input(format!(
+            "Unknown format: {format} (expected bash, fish, powershell, dotenv or direnv)"
+        ))
+    })
+}
+
+/// Server variables for a self-hosted LobeChat. Unquoted, because
+/// `docker run --env-file` keeps quotes as part of the value.
+fn render_lobechat(provider: &ProviderRecord) -> String {
+    let vars = [
+        (
+            "OPENAI_PROXY_URL",
+            crate::get_proxy_url("codex", &provider.url),
+        ),
+        ("OPENAI_API_KEY", provider.api_key.clone()),
+    ];
+    vars.iter()
+        .map(|(name, value)| format!("{name}={}\n", value.trim()))
+        .collect()
+}
+
+/// `id`, or the current provider when `None`, with an op:// or keychain://
+/// key replaced by the secret it points to.
+async fn resolve_provider(
+    state: &AppState,
+    id: Option<&str>,
+) -> Result<ProviderRecord, CommandError> {
+    let mut provider = match id {
+        Some(id) => providers::get_all(&state.db, false)?
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| {
+                CommandError::new("PROVIDER_NOT_FOUND", format!("Provider not found: {id}"))
+                    .with_details(json!({ "id": id }))
+            }),
+        None => providers::get_current(&state.db)?
+            .ok_or_else(|| CommandError::new("NO_CURRENT_PROVIDER", "No provider is selected yet")),
+    }?;
+    provider.api_key = secrets::resolve(&provider.api_key).await?;
+    Ok(provider)
+}
+
+/// `dir` must be an existing directory, inside `home` unless the user picked
+/// it in the folder dialog.
+fn check_target_dir(
+    dir: &Path,
+    home: Option<&Path>,
+    user_chosen: bool,
+) -> Result<PathBuf, CommandError> {
+    if !dir.is_absolute() {
+        return Err(CommandError::invalid_input(format!(
+            "Path must be absolute: {}",
+            dir.display()
+        )));
+    }
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| CommandError::invalid_input(format!("Cannot open {}: {e}", dir.display())))?;
+    if !dir.is_dir() {
+        return Err(CommandError::invalid_input(format!(
+            "Not a directory: {}",
+            dir.display()
+        )));
+    }
+    let inside_home = home
+        .and_then(|h| h.canonicalize().ok())
+        .is_some_and(|h| dir.starts_with(h));
+    if !inside_home && !user_chosen {
+        return Err(CommandError::new(
+            "OUTSIDE_HOME",
+            format!(
+                "Refusing to write API keys to {} — choose the folder yourself to confirm",
+                dir.display()
+            ),
+        )
+        .with_details(json!({ "path": dir })));
+    }
+    Ok(dir)
+}
+
+/// Ready-to-paste snippet for the current provider (or `provider_id`).
+#[tauri::command]
+pub async fn generate_env_exports(
+    state: State<'_, AppState>,
+    format: String,
+    provider_id: Option<String>,
+) -> Result<String, CommandError> {
+    let format = parse_format(&format)?;
+    let provider = resolve_provider(&state, provider_id.as_deref()).await?;
+    Ok(render(format, &provider))
+}
+
+/// `.env` lines for a self-hosted LobeChat server (the desktop app is synced
+/// through its settings file instead).
+#[tauri::command]
+pub async fn generate_lobechat_env(
+    state: State<'_, AppState>,
+    provider_id: Option<String>,
+) -> Result<String, CommandError> {
+    let provider = resolve_provider(&state, provider_id.as_deref()).await?;
+    Ok(render_lobechat(&provider))
+}
+
+/// Write `.envrc` (direnv, the default) or `.env` (dotenv) into `project_dir`.
+/// `user_chosen` is set when the folder came from the folder picker, which
+/// allows directories outside the home folder. Returns the written path.
+#[tauri::command]
+pub async fn write_envrc(
+    state: State<'_, AppState>,
+    project_dir: String,
+    format: Option<String>,
+    provider_id: Option<String>,
+    user_chosen: Option<bool>,
+) -> Result<String, CommandError> {
+    let format = parse_format(format.as_deref().unwrap_or("direnv"))?;
+    let file_name = format.file_name().ok_or_else(|| {
+        CommandError::invalid_input("Only the direnv and dotenv formats can be written to a file")
+    })?;
+    let dir = check_target_dir(
+        Path::new(project_dir.trim()),
+        dirs::home_dir().as_deref(),
+        user_chosen.unwrap_or(false),
+    )?;
+    let provider = resolve_provider(&state, provider_id.as_deref()).await?;
+
+    let path = dir.join(file_name);
+    utils::create_rotated_backup(&path, utils::BACKUP_SUFFIX)?;
+    crypto::write_private(&path, render(format, &provider).as_bytes())
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    tracing::info!(
+        "[env_exports] Wrote {} for {}",
+        path.display(),
+        provider.name
+    );
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(url: &str, key: &str) -> ProviderRecord {
+        ProviderRecord {
+            id: "p".to_string(),
+            name: "Main\nproxy".to_string(),
+            url: url.to_string(),
+            api_key: key.to_string(),
+            default_model: String::new(),
+            per_cli_models: "{}".to_string(),
+            is_current: true,
+            sort_index: None,
+            notes: None,
+            created_at: 0,
+            opencode_adapter: String::new(),
+            account_id: None,
+            model_aliases: "{}".to_string(),
+            account_base_url: None,
+            token_id: None,
+            quota: None,
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_base_urls_follow_proxy_rules() {
+        let vars = env_vars(&provider("https://api.example.com/", "sk-1"));
+        let get = |name: &str| vars.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        assert_eq!(get("OPENAI_BASE_URL"), "https://api.example.com/v1");
+        assert_eq!(get("ANTHROPIC_BASE_URL"), "https://api.example.com");
+        assert_eq!(get("GEMINI_API_KEY"), "sk-1");
+    }
+
+    #[test]
+    fn test_quoting_per_format() {
+        let v = r#"a'b"c\d"#;
+        assert_eq!(EnvFormat::Bash.line("K", v), r#"export K='a'\''b"c\d'"#);
+        assert_eq!(EnvFormat::Fish.line("K", v), r#"set -gx K 'a\'b"c\\d'"#);
+        assert_eq!(EnvFormat::PowerShell.line("K", v), r#"$env:K = 'a''b"c\d'"#);
+        assert_eq!(EnvFormat::Dotenv.line("K", v), r#"K="a'b\"c\\d""#);
+    }
+
+    #[test]
+    fn test_render_keeps_name_on_comment_line() {
+        let out = render(EnvFormat::Direnv, &provider("https://x.io", "sk-2"));
+        assert!(out.starts_with("# Hajimi AI Switch — Main proxy\n"));
+        assert!(out.contains("export OPENAI_API_KEY='sk-2'\n"));
+        assert!(parse_format("cmd").is_err());
+    }
+
+    #[test]
+    fn test_lobechat_env_uses_v1_root() {
+        assert_eq!(
+            render_lobechat(&provider("https://x.io/", "sk-3")),
+            "OPENAI_PROXY_URL=https://x.io/v1\nOPENAI_API_KEY=sk-3\n"
+        );
+    }
+
+    #[test]
+    fn test_target_dir_must_be_in_home_unless_chosen() {
+        let home = tempfile::tempdir().unwrap();
+        let project = home.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        assert!(check_target_dir(&project, Some(home.path()), false).is_ok());
+        assert!(check_target_dir(Path::new("relative"), Some(home.path()), true).is_err());
+
+        let elsewhere = tempfile::tempdir().unwrap();
+        let err = check_target_dir(elsewhere.path(), Some(home.path()), false).unwrap_err();
+        assert_eq!(err.code, "OUTSIDE_HOME");
+        assert!(check_target_dir(elsewhere.path(), Some(home.path()), true).is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+use serde::Serialize;
+use serde_json::{json, Value};
 use thiserror::Error;
 
 /// 主错误类型，提供详细的错误信息和用户友好的修复建议
@@ -42,15 +44,37 @@ pub enum SyncError {
     #[error("File is locked by another process: {path}\n\nPlease close any applications using this file and try again.")]
     FileLocked { path: String },
 
+    #[error("Another sync is still running (waited {seconds} seconds).\n\nWait for it to finish, or check whether hajimi-sync is running in a terminal.")]
+    SyncBusy { seconds: u64 },
+
+    #[error("Refusing to write through symlink {path}: it points outside your home directory ({target}).\n\nWrite to the target directly, or replace the symlink with a regular file.")]
+    SymlinkOutsideHome { path: String, target: String },
+
+    #[error("{path} contains comments or trailing commas that rewriting it would lose.\n\nEdit the file by hand, or remove the comments and sync again.")]
+    WouldLoseComments { path: String },
+
     #[error("Operation timed out after {seconds} seconds: {operation}")]
     Timeout { operation: String, seconds: u64 },
 
+    #[error("Checksum mismatch for {file}: expected SHA-256 {expected}, got {actual}.\n\nThe download may be corrupted or tampered with and has been deleted. Try again, or switch the download mirror.")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Installation of {tool} was cancelled")]
+    InstallCancelled { tool: String },
+
     #[error("Invalid URL: {url}\n\nURL must start with http:// or https://")]
     InvalidUrl { url: String },
 
     #[error("Environment variable '{var}' is not set.\n\nThis is required on Windows. Please check your system settings.")]
     EnvVarNotSet { var: String },
 
+    #[error("{app} config was edited outside this app since the last sync:\n{}\n\nReview the changes, or allow syncing over manual edits in Settings.", .files.join("\n"))]
+    ModifiedExternally { app: String, files: Vec<String> },
+
     #[error("{0}")]
     Other(String),
 }
@@ -72,9 +96,15 @@ impl SyncError {
             Self::CommandExecutionFailed { .. } => "COMMAND_FAILED",
             Self::BackupNotFound { .. } => "BACKUP_NOT_FOUND",
             Self::FileLocked { .. } => "FILE_LOCKED",
+            Self::SyncBusy { .. } => "SYNC_BUSY",
+            Self::SymlinkOutsideHome { .. } => "SYMLINK_OUTSIDE_HOME",
+            Self::WouldLoseComments { .. } => "WOULD_LOSE_COMMENTS",
             Self::Timeout { .. } => "TIMEOUT",
+            Self::InstallCancelled { .. } => "INSTALL_CANCELLED",
+            Self::ChecksumMismatch { .. } => "CHECKSUM_MISMATCH",
             Self::InvalidUrl { .. } => "INVALID_URL",
             Self::EnvVarNotSet { .. } => "ENV_VAR_NOT_SET",
+            Self::ModifiedExternally { .. } => "MODIFIED_EXTERNALLY",
             Self::Other(_) => "UNKNOWN",
         }
     }
@@ -84,6 +114,7 @@ impl SyncError {
         matches!(
             self,
             Self::FileLocked { .. }
+                | Self::SyncBusy { .. }
                 | Self::FileWriteFailed { .. }
                 | Self::Timeout { .. }
                 | Self::ConfigCorrupted { .. }
@@ -101,6 +132,99 @@ impl From<SyncError> for String {
     }
 }
 
+/// 返回给前端的结构化错误：`code` 用于程序判断和本地化，`message` 保留原始可读文本
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: String,
+    pub message: String,
+    /// 装箱以避免 `Result<_, CommandError>` 过大
+    pub details: Option<Box<Value>>,
+    pub recoverable: bool,
+}
+
+impl CommandError {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            details: None,
+            recoverable: false,
+        }
+    }
+
+    /// 命令参数校验失败
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new("INVALID_INPUT", message)
+    }
+
+    pub fn unknown_app(app: &str) -> Self {
+        Self::new("UNKNOWN_APP", format!("Unknown app: {app}")).with_details(json!({ "app": app }))
+    }
+
+    /// A known app that can't do `operation`, e.g. restoring a client configured by hand.
+    pub fn unsupported_operation(app: &str, operation: &str) -> Self {
+        Self::new(
+            "UNSUPPORTED_OPERATION",
+            format!("{app} does not support {operation}"),
+        )
+        .with_details(json!({ "app": app, "operation": operation }))
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(Box::new(details));
+        self
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<SyncError> for CommandError {
+    fn from(err: SyncError) -> Self {
+        let details = match &err {
+            SyncError::PermissionDenied { path }
+            | SyncError::ConfigCorrupted { path, .. }
+            | SyncError::DirectoryCreationFailed { path, .. }
+            | SyncError::FileReadFailed { path, .. }
+            | SyncError::FileWriteFailed { path, .. }
+            | SyncError::JsonParseFailed { path, .. }
+            | SyncError::BackupNotFound { path }
+            | SyncError::FileLocked { path }
+            | SyncError::WouldLoseComments { path } => Some(json!({ "path": path })),
+            SyncError::SymlinkOutsideHome { path, target } => {
+                Some(json!({ "path": path, "target": target }))
+            }
+            SyncError::InvalidUrl { url } => Some(json!({ "url": url })),
+            SyncError::CliNotInstalled { name, .. } => Some(json!({ "name": name })),
+            SyncError::DependencyMissing { tool, .. } => Some(json!({ "tool": tool })),
+            SyncError::ModifiedExternally { app, files } => {
+                Some(json!({ "app": app, "files": files }))
+            }
+            SyncError::InsufficientDiskSpace {
+                required,
+                available,
+            } => Some(json!({ "required_mb": required, "available_mb": available })),
+            _ => None,
+        };
+        Self {
+            code: err.code().to_string(),
+            message: err.to_string(),
+            details: details.map(Box::new),
+            recoverable: err.is_recoverable(),
+        }
+    }
+}
+
+/// 模块内部仍返回 String 错误，统一归为 UNKNOWN
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        SyncError::Other(message).into()
+    }
+}
+
 /// 辅助函数：获取CLI安装提示
 pub fn get_install_hint(tool: &str) -> String {
     match tool {
@@ -167,6 +291,16 @@ pub fn get_install_hint(tool: &str) -> String {
         "boltai" => {
             "Download BoltAI from: https://boltai.com (macOS only)".to_string()
         }
+        "warp" => {
+            "Download Warp from: https://www.warp.dev/download".to_string()
+        }
+        "amazon-q" => {
+            if cfg!(target_os = "macos") {
+                "Run: brew install --cask amazon-q\nOr download from: https://aws.amazon.com/q/developer/".to_string()
+            } else {
+                "Install Amazon Q Developer CLI:\nhttps://docs.aws.amazon.com/amazonq/latest/qdeveloper-ug/command-line-installing.html".to_string()
+            }
+        }
         _ => format!("Search for '{tool} installation guide' for your platform"),
     }
 }
@@ -193,9 +327,31 @@ mod tests {
             path: "test".to_string()
         }
         .is_recoverable());
+        assert!(SyncError::SyncBusy { seconds: 30 }.is_recoverable());
         assert!(!SyncError::HomeDirectoryNotFound.is_recoverable());
     }
 
+    #[test]
+    fn test_command_error_from_sync_error() {
+        let err: CommandError = SyncError::FileLocked {
+            path: "/tmp/settings.json".to_string(),
+        }
+        .into();
+        assert_eq!(err.code, "FILE_LOCKED");
+        assert!(err.recoverable);
+        assert!(err.message.contains("/tmp/settings.json"));
+        assert_eq!(err.details.unwrap()["path"], "/tmp/settings.json");
+
+        let err: CommandError = "boom".to_string().into();
+        assert_eq!(err.code, "UNKNOWN");
+        assert_eq!(err.message, "boom");
+        assert!(!err.recoverable);
+
+        let json = serde_json::to_value(CommandError::unknown_app("nope")).unwrap();
+        assert_eq!(json["code"], "UNKNOWN_APP");
+        assert_eq!(json["details"]["app"], "nope");
+    }
+
     #[test]
     fn test_install_hints() {
         let hint = get_install_hint("git");
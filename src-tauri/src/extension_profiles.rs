@@ -0,0 +1,241 @@
+//! Importable provider profiles for the VS Code agent extensions.
+//!
+//! Cline, Roo Code and Kilo Code keep their API settings in VS Code's
+//! extension storage, which hajimi can't write. Each can import a settings
+//! JSON from its settings page, so this builds that file with an
+//! OpenAI-compatible provider pointed at the proxy. The file holds the API key.
+
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::crypto;
+use crate::error::CommandError;
+use crate::extra_clients::ExtraClient;
+use crate::utils;
+
+/// Name and id of the profile added to Roo Code / Kilo Code.
+const PROFILE_NAME: &str = "hajimi";
+
+fn profile_client(client: &str) -> Result<ExtraClient, CommandError> {
+    match ExtraClient::from_str(client) {
+        Some(c @ (ExtraClient::Cline | ExtraClient::RooCode | ExtraClient::KiloCode)) => Ok(c),
+        Some(_) => Err(CommandError::unsupported_operation(
+            client,
+            "export an extension profile",
+        )),
+        None => Err(CommandError::unknown_app(client)),
+    }
+}
+
+/// Roo Code's "Export settings" layout, which Kilo Code (a Roo fork) shares.
+/// Import merges `apiConfigs` into the existing profiles.
+fn roo_profile(base_url: &str, api_key: &str, model: Option<&str>) -> Value {
+    let mut config = Map::new();
+    config.insert("id".to_string(), json!(PROFILE_NAME));
+    config.insert("apiProvider".to_string(), json!("openai"));
+    config.insert("openAiBaseUrl".to_string(), json!(base_url));
+    config.insert("openAiApiKey".to_string(), json!(api_key));
+    if let Some(model) = model {
+        config.insert("openAiModelId".to_string(), json!(model));
+    }
+    json!({
+        "providerProfiles": {
+            "currentApiConfigName": PROFILE_NAME,
+            "apiConfigs": { PROFILE_NAME: config },
+            "modeApiConfigs": {}
+        },
+        "globalSettings": {}
+    })
+}
+
+/// Cline's `apiConfiguration` keys. Plan and Act mode each pick their own
+/// provider and model; the unprefixed keys are read by releases before the split.
+fn cline_profile(base_url: &str, api_key: &str, model: Option<&str>) -> Value {
+    let mut config = Map::new();
+    for key in ["apiProvider", "planModeApiProvider", "actModeApiProvider"] {
+        config.insert(key.to_string(), json!("openai"));
+    }
+    config.insert("openAiBaseUrl".to_string(), json!(base_url));
+    config.insert("openAiApiKey".to_string(), json!(api_key));
+    if let Some(model) = model {
+        for key in [
+            "openAiModelId",
+            "planModeOpenAiModelId",
+            "actModeOpenAiModelId",
+        ] {
+            config.insert(key.to_string(), json!(model));
+        }
+    }
+    json!({ "apiConfiguration": config })
+}
+
+fn build_profile(
+    client: &str,
+    url: &str,
+    api_key: &str,
+    model: Option<&str>,
+) -> Result<String, CommandError> {
+    let client = profile_client(client)?;
+    utils::validate_url(url).map_err(|e| CommandError::invalid_input(e.to_string()))?;
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(CommandError::invalid_input("API key cannot be empty"));
+    }
+    // The extensions' OpenAI-compatible provider wants the /v1 root, like Codex
+    let base_url = crate::get_proxy_url("codex", url);
+    let model = model.map(str::trim).filter(|m| !m.is_empty());
+    let profile = match client {
+        ExtraClient::Cline => cline_profile(&base_url, api_key, model),
+        _ => roo_profile(&base_url, api_key, model),
+    };
+    serde_json::to_string_pretty(&profile)
+        .map_err(|e| CommandError::from(format!("serialize profile: {e}")))
+}
+
+/// Profile JSON for `client` ("cline", "roo-code" or "kilo-code"), ready to
+/// import from the extension's settings.
+#[tauri::command]
+pub async fn generate_extension_profile(
+    client: String,
+    url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<String, CommandError> {
+    let api_key = crate::secrets::resolve(&api_key).await?;
+    build_profile(&client, &url, &api_key, model.as_deref())
+}
+
+/// Write the profile to `dest_path`, picked in the save dialog. Returns the
+/// written path.
+#[tauri::command]
+pub async fn save_extension_profile(
+    client: String,
+    dest_path: String,
+    url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<String, CommandError> {
+    let api_key = crate::secrets::resolve(&api_key).await?;
+    let profile = build_profile(&client, &url, &api_key, model.as_deref())?;
+    let path = Path::new(dest_path.trim());
+    if !path.is_absolute() {
+        return Err(CommandError::invalid_input(format!(
+            "Path must be absolute: {}",
+            path.display()
+        )));
+    }
+    crypto::write_private(path, profile.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    tracing::info!(
+        "[extension_profiles] Wrote a {} profile to {}",
+        client,
+        path.display()
+    );
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(client: &str, model: Option<&str>) -> Value {
+        let raw = build_profile(client, "https://api.example.com/", "sk-test", model).unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    #[test]
+    fn test_roo_profile_snapshot() {
+        assert_eq!(
+            profile("roo-code", Some("claude-sonnet-4-5")),
+            json!({
+                "providerProfiles": {
+                    "currentApiConfigName": "hajimi",
+                    "apiConfigs": {
+                        "hajimi": {
+                            "id": "hajimi",
+                            "apiProvider": "openai",
+                            "openAiBaseUrl": "https://api.example.com/v1",
+                            "openAiApiKey": "sk-test",
+                            "openAiModelId": "claude-sonnet-4-5"
+                        }
+                    },
+                    "modeApiConfigs": {}
+                },
+                "globalSettings": {}
+            })
+        );
+        // Kilo Code imports Roo's format
+        assert_eq!(
+            profile("kilo-code", Some("claude-sonnet-4-5")),
+            profile("roo-code", Some("claude-sonnet-4-5"))
+        );
+    }
+
+    #[test]
+    fn test_cline_profile_snapshot() {
+        assert_eq!(
+            profile("cline", Some("gpt-4o")),
+            json!({
+                "apiConfiguration": {
+                    "apiProvider": "openai",
+                    "planModeApiProvider": "openai",
+                    "actModeApiProvider": "openai",
+                    "openAiBaseUrl": "https://api.example.com/v1",
+                    "openAiApiKey": "sk-test",
+                    "openAiModelId": "gpt-4o",
+                    "planModeOpenAiModelId": "gpt-4o",
+                    "actModeOpenAiModelId": "gpt-4o"
+                }
+            })
+        );
+        let without_model = profile("cline", Some(" "));
+        assert!(without_model["apiConfiguration"]
+            .get("openAiModelId")
+            .is_none());
+    }
+
+    #[test]
+    fn test_profile_rejects_other_clients_and_bad_input() {
+        let err = |client: &str, url: &str, key: &str| {
+            build_profile(client, url, key, None).unwrap_err().code
+        };
+        assert_eq!(
+            err("chatbox", "https://x.io", "sk"),
+            "UNSUPPORTED_OPERATION"
+        );
+        assert_eq!(err("nope", "https://x.io", "sk"), "UNKNOWN_APP");
+        assert_eq!(err("cline", "x.io", "sk"), "INVALID_INPUT");
+        assert_eq!(err("cline", "https://x.io", " "), "INVALID_INPUT");
+    }
+
+    #[tokio::test]
+    async fn test_save_writes_profile() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("roo-settings.json");
+        let written = save_extension_profile(
+            "roo-code".to_string(),
+            dest.to_string_lossy().to_string(),
+            "https://api.example.com".to_string(),
+            "sk-test".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        let saved: Value =
+            serde_json::from_str(&std::fs::read_to_string(&written).unwrap()).unwrap();
+        assert_eq!(
+            saved["providerProfiles"]["apiConfigs"]["hajimi"]["openAiApiKey"],
+            "sk-test"
+        );
+        assert!(save_extension_profile(
+            "roo-code".to_string(),
+            "relative.json".to_string(),
+            "https://api.example.com".to_string(),
+            "sk-test".to_string(),
+            None,
+        )
+        .await
+        .is_err());
+    }
+}
@@ -3,6 +3,12 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::cli_sync;
+use crate::config_paths;
+use crate::file_status::{FileStatus, SyncStatus};
+use crate::paths;
+use crate::provider_advanced::ProviderAdvanced;
+use crate::provider_import::DetectedProvider;
+use crate::sync_target::SyncOutcome;
 use crate::utils;
 
 use crate::utils::BACKUP_SUFFIX;
@@ -21,6 +27,8 @@ pub enum ExtraClient {
     SillyTavern,
     LobeChat,
     BoltAI,
+    WarpTerminal,
+    AmazonQ,
 }
 
 impl ExtraClient {
@@ -37,6 +45,8 @@ impl ExtraClient {
             Self::SillyTavern => "sillytavern",
             Self::LobeChat => "lobechat",
             Self::BoltAI => "boltai",
+            Self::WarpTerminal => "warp",
+            Self::AmazonQ => "amazon-q",
         }
     }
 
@@ -53,6 +63,8 @@ impl ExtraClient {
             Self::SillyTavern => "SillyTavern",
             Self::LobeChat => "LobeChat",
             Self::BoltAI => "BoltAI",
+            Self::WarpTerminal => "Warp",
+            Self::AmazonQ => "Amazon Q Developer CLI",
         }
     }
 
@@ -69,6 +81,8 @@ impl ExtraClient {
             Self::SillyTavern,
             Self::LobeChat,
             Self::BoltAI,
+            Self::WarpTerminal,
+            Self::AmazonQ,
         ]
     }
 
@@ -85,17 +99,56 @@ impl ExtraClient {
             "sillytavern" => Some(Self::SillyTavern),
             "lobechat" => Some(Self::LobeChat),
             "boltai" => Some(Self::BoltAI),
+            "warp" => Some(Self::WarpTerminal),
+            "amazon-q" => Some(Self::AmazonQ),
             _ => None,
         }
     }
 
     /// Whether this client supports file-based config sync.
-    /// Clients using encrypted/keychain storage return false.
+    /// Clients using encrypted/keychain storage return false; BoltAI's
+    /// service file and Keychain item are only written on macOS.
     pub fn supports_file_sync(&self) -> bool {
         matches!(
             self,
-            Self::Chatbox | Self::CherryStudio | Self::Jan | Self::SillyTavern
-        )
+            Self::Chatbox | Self::CherryStudio | Self::Jan | Self::SillyTavern | Self::LobeChat
+        ) || (*self == Self::BoltAI && cfg!(target_os = "macos"))
+    }
+
+    /// How to point the client at the provider by hand, for clients whose
+    /// config hajimi can't write.
+    pub fn manual_steps(&self) -> Option<String> {
+        let steps = match self {
+            Self::Cursor => "AI configuration must be set through the app UI: \
+                 Settings > Models > OpenAI API Key / Base URL."
+                .to_string(),
+            Self::Cline | Self::RooCode | Self::KiloCode => {
+                "stores API config in its extension settings. \
+                 Open the extension sidebar > Settings icon > set API Provider to \"OpenAI Compatible\", \
+                 then enter your Base URL and API Key."
+                    .to_string()
+            }
+            Self::BoltAI if self.supports_file_sync() => return None,
+            Self::BoltAI => "stores API keys in macOS Keychain. \
+                 Configure it through the app: Settings > Models > Add OpenAI-compatible Server."
+                .to_string(),
+            Self::WarpTerminal => "AI always goes through Warp's own service and has no base URL setting. \
+                 Tools you run inside Warp still read the usual variables: add OPENAI_BASE_URL / OPENAI_API_KEY \
+                 (or ANTHROPIC_BASE_URL / ANTHROPIC_API_KEY) to your shell profile, e.g. from the env export snippet, \
+                 and open a new tab."
+                .to_string(),
+            Self::AmazonQ => "signs in with AWS Builder ID or IAM Identity Center and only talks to Amazon's service; \
+                 it has no base URL setting. Commands it runs inherit your shell, so export OPENAI_BASE_URL / \
+                 OPENAI_API_KEY (or ANTHROPIC_BASE_URL / ANTHROPIC_API_KEY) in your shell profile for the tools it calls."
+                .to_string(),
+            Self::ClaudeVSCode
+            | Self::Chatbox
+            | Self::CherryStudio
+            | Self::Jan
+            | Self::SillyTavern
+            | Self::LobeChat => return None,
+        };
+        Some(format!("{} {steps}", self.display_name()))
     }
 
     pub fn config_files_display(&self) -> Vec<String> {
@@ -103,14 +156,20 @@ impl ExtraClient {
             Self::ClaudeVSCode => vec!["settings.json".to_string()],
             Self::Chatbox => vec!["config.json".to_string()],
             Self::CherryStudio => vec!["config.json".to_string()],
-            Self::Jan => vec!["openai.json".to_string()],
+            Self::Jan => match jan_layout() {
+                JanLayout::ProviderSettings => vec!["settings.json".to_string()],
+                JanLayout::LegacyEngine => vec!["openai.json".to_string()],
+            },
             Self::Cursor => vec!["(app settings)".to_string()],
             Self::Cline | Self::RooCode | Self::KiloCode => {
                 vec!["(extension settings)".to_string()]
             }
             Self::SillyTavern => vec!["secrets.json".to_string()],
-            Self::LobeChat => vec!["(browser storage)".to_string()],
+            Self::LobeChat => vec![LOBECHAT_SETTINGS_FILE.to_string()],
+            Self::BoltAI if self.supports_file_sync() => vec![BOLTAI_SERVICES_FILE.to_string()],
             Self::BoltAI => vec!["(macOS Keychain)".to_string()],
+            Self::WarpTerminal => vec!["(app settings)".to_string()],
+            Self::AmazonQ => vec!["(AWS sign-in)".to_string()],
         }
     }
 }
@@ -120,7 +179,7 @@ impl ExtraClient {
 // ---------------------------------------------------------------------------
 
 fn home_dir() -> Option<PathBuf> {
-    dirs::home_dir()
+    paths::home_dir()
 }
 
 #[cfg(target_os = "macos")]
@@ -135,40 +194,147 @@ fn app_support_dir() -> Option<PathBuf> {
 
 #[cfg(target_os = "windows")]
 fn app_support_dir() -> Option<PathBuf> {
-    std::env::var("APPDATA")
-        .map(PathBuf::from)
-        .ok()
-        .or_else(|| home_dir().map(|h| h.join("AppData/Roaming")))
+    paths::env_dir("APPDATA").or_else(|| home_dir().map(|h| h.join("AppData/Roaming")))
+}
+
+/// Flatpak application ids and Snap names for the desktop clients we sync.
+#[cfg(target_os = "linux")]
+const CHATBOX_SANDBOX_IDS: (&str, &str) = ("xyz.chatboxapp.app", "chatbox");
+#[cfg(target_os = "linux")]
+const CHERRY_SANDBOX_IDS: (&str, &str) = ("com.cherryai.CherryStudio", "cherry-studio");
+
+/// Flatpak (`~/.var/app/<id>/config`) and Snap (`~/snap/<name>/current/.config`)
+/// keep the sandboxed app's XDG config dir outside `~/.config`.
+#[cfg(target_os = "linux")]
+fn sandboxed_config_dirs((flatpak_id, snap_name): (&str, &str)) -> Vec<PathBuf> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    vec![
+        home.join(".var/app").join(flatpak_id).join("config"),
+        home.join("snap").join(snap_name).join("current/.config"),
+    ]
+}
+
+/// Pick the config file to use from an ordered list of candidates: the first
+/// that exists, else the first whose directory exists, else the first one.
+fn pick_config_path(candidates: Vec<PathBuf>) -> Option<PathBuf> {
+    if let Some(p) = candidates.iter().find(|p| p.exists()) {
+        return Some(p.clone());
+    }
+    if let Some(p) = candidates
+        .iter()
+        .find(|p| p.parent().is_some_and(|d| d.is_dir()))
+    {
+        return Some(p.clone());
+    }
+    candidates.into_iter().next()
 }
 
 fn chatbox_config_path() -> Option<PathBuf> {
-    let app_sup = app_support_dir()?;
-    #[cfg(target_os = "macos")]
-    let dir = app_sup.join("xyz.chatboxapp.app");
+    #[allow(unused_mut)]
+    let mut roots = vec![app_support_dir()?];
     #[cfg(target_os = "linux")]
-    let dir = app_sup.join("Chatbox");
-    #[cfg(target_os = "windows")]
-    let dir = app_sup.join("Chatbox");
-    Some(dir.join("config.json"))
+    roots.extend(sandboxed_config_dirs(CHATBOX_SANDBOX_IDS));
+
+    #[cfg(target_os = "macos")]
+    let dir_name = "xyz.chatboxapp.app";
+    #[cfg(not(target_os = "macos"))]
+    let dir_name = "Chatbox";
+
+    pick_config_path(
+        roots
+            .iter()
+            .map(|r| r.join(dir_name).join("config.json"))
+            .collect(),
+    )
 }
 
 fn cherry_config_path() -> Option<PathBuf> {
-    let app_sup = app_support_dir()?;
-    // Cherry Studio may use different directory names across versions
-    for name in &["CherryStudio", "cherry-studio", "Cherry Studio"] {
-        let p = app_sup.join(name).join("config.json");
-        if p.exists() {
-            return Some(p);
+    #[allow(unused_mut)]
+    let mut roots = vec![app_support_dir()?];
+    #[cfg(target_os = "linux")]
+    roots.extend(sandboxed_config_dirs(CHERRY_SANDBOX_IDS));
+
+    // Cherry Studio may use different directory names across versions.
+    // Defaults to <app support>/CherryStudio if none found.
+    let mut candidates = Vec::new();
+    for root in &roots {
+        for name in &["CherryStudio", "cherry-studio", "Cherry Studio"] {
+            candidates.push(root.join(name).join("config.json"));
         }
     }
-    // Default to CherryStudio if none found
-    Some(app_sup.join("CherryStudio").join("config.json"))
+    pick_config_path(candidates)
+}
+
+/// Directory name of our provider under Jan's per-provider settings tree.
+const JAN_PROVIDER_DIR: &str = "hajimi";
+
+/// Which on-disk layout the installed Jan version reads remote provider config from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JanLayout {
+    /// Jan >= 0.6: ~/jan/settings/@janhq/<provider>/settings.json
+    ProviderSettings,
+    /// Older Jan: ~/jan/engines/openai.json
+    LegacyEngine,
+}
+
+fn jan_data_dir() -> Option<PathBuf> {
+    home_dir().map(|h| h.join("jan"))
+}
+
+fn jan_layout() -> JanLayout {
+    match jan_data_dir() {
+        Some(dir) if dir.join("settings").join("@janhq").is_dir() => JanLayout::ProviderSettings,
+        _ => JanLayout::LegacyEngine,
+    }
 }
 
 fn jan_config_path() -> Option<PathBuf> {
-    let home = home_dir()?;
-    // Jan stores OpenAI-compatible engine config at ~/jan/engines/openai.json
-    Some(home.join("jan").join("engines").join("openai.json"))
+    let jan = jan_data_dir()?;
+    match jan_layout() {
+        JanLayout::ProviderSettings => Some(
+            jan.join("settings")
+                .join("@janhq")
+                .join(JAN_PROVIDER_DIR)
+                .join("settings.json"),
+        ),
+        // Legacy OpenAI-compatible engine config; used when the new tree is absent
+        JanLayout::LegacyEngine => Some(jan.join("engines").join("openai.json")),
+    }
+}
+
+/// LobeHub desktop's user settings store. The app folder was renamed from
+/// LobeChat to LobeHub, and Linux packages use the lowercase package name.
+const LOBECHAT_SETTINGS_FILE: &str = "settings.json";
+
+fn lobechat_config_path() -> Option<PathBuf> {
+    let app_sup = app_support_dir()?;
+    pick_config_path(
+        ["LobeHub", "lobehub-desktop", "LobeChat"]
+            .iter()
+            .map(|name| app_sup.join(name).join(LOBECHAT_SETTINGS_FILE))
+            .collect(),
+    )
+}
+
+/// Custom services BoltAI imports; keys stay in the Keychain, referenced by
+/// service and account.
+const BOLTAI_SERVICES_FILE: &str = "custom-services.json";
+const BOLTAI_KEYCHAIN_SERVICE: &str = "co.podzim.BoltGPT";
+
+#[cfg(target_os = "macos")]
+fn boltai_config_path() -> Option<PathBuf> {
+    Some(
+        app_support_dir()?
+            .join(BOLTAI_KEYCHAIN_SERVICE)
+            .join(BOLTAI_SERVICES_FILE),
+    )
+}
+
+#[cfg(not(target_os = "macos"))]
+fn boltai_config_path() -> Option<PathBuf> {
+    None
 }
 
 fn cursor_config_path() -> Option<PathBuf> {
@@ -201,11 +367,16 @@ fn sillytavern_secrets_path() -> Option<PathBuf> {
 }
 
 /// Get the config file path for a client (the primary file we sync to).
-fn config_path_for(client: &ExtraClient) -> Option<PathBuf> {
-    match client {
+/// Honours user path overrides, so detection, sync and restore all agree.
+pub(crate) fn config_path_for(client: &ExtraClient) -> Option<PathBuf> {
+    let default = match client {
         ExtraClient::ClaudeVSCode => {
-            // Shares config with Claude Code CLI: ~/.claude/settings.json
-            home_dir().map(|h| h.join(".claude").join("settings.json"))
+            // Shares config with Claude Code CLI (settings.json, incl. its overrides)
+            return cli_sync::CliApp::Claude
+                .config_files()
+                .into_iter()
+                .find(|f| f.name == "settings.json")
+                .map(|f| f.path);
         }
         ExtraClient::Chatbox => chatbox_config_path(),
         ExtraClient::CherryStudio => cherry_config_path(),
@@ -213,41 +384,142 @@ fn config_path_for(client: &ExtraClient) -> Option<PathBuf> {
         ExtraClient::Cursor => cursor_config_path(),
         ExtraClient::Cline | ExtraClient::RooCode | ExtraClient::KiloCode => vscode_settings_path(),
         ExtraClient::SillyTavern => sillytavern_secrets_path(),
-        ExtraClient::LobeChat | ExtraClient::BoltAI => None,
-    }
+        ExtraClient::LobeChat => lobechat_config_path(),
+        ExtraClient::BoltAI => boltai_config_path(),
+        ExtraClient::WarpTerminal | ExtraClient::AmazonQ => None,
+    }?;
+    config_paths::resolve_file(client.as_str(), None, Some(default))
 }
 
 // ---------------------------------------------------------------------------
 // Detection
 // ---------------------------------------------------------------------------
 
-/// Check if the VS Code extension is installed by scanning known extensions dirs.
-fn is_vscode_extension_installed(ext_prefix: &str) -> bool {
-    let home = match home_dir() {
-        Some(h) => h,
-        None => return false,
-    };
+/// Find a VS Code extension by scanning known extensions dirs. Returns the
+/// highest installed version, or "extension" if no version can be read.
+fn find_vscode_extension(ext_prefix: &str) -> Option<String> {
+    let home = home_dir()?;
     // VS Code stores extensions in ~/.vscode/extensions on all platforms.
     // Cursor stores its own copy in ~/.cursor/extensions.
     let ext_dirs = [
         home.join(".vscode").join("extensions"),
         home.join(".cursor").join("extensions"),
     ];
+    let mut found = false;
+    let mut best: Option<String> = None;
     for ext_dir in &ext_dirs {
-        if !ext_dir.exists() {
+        let Ok(entries) = fs::read_dir(ext_dir) else {
             continue;
-        }
-        if let Ok(entries) = fs::read_dir(ext_dir) {
-            for entry in entries.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.starts_with(ext_prefix) {
-                        return true;
-                    }
+        };
+        for entry in entries.flatten() {
+            let Some(name) = entry.file_name().to_str().map(|n| n.to_string()) else {
+                continue;
+            };
+            if !name.starts_with(ext_prefix) {
+                continue;
+            }
+            found = true;
+            let version = read_extension_package_version(&entry.path())
+                .or_else(|| version_from_extension_dir(&name, ext_prefix));
+            if let Some(v) = version {
+                if best
+                    .as_deref()
+                    .is_none_or(|b| utils::compare_versions(&v, b).is_gt())
+                {
+                    best = Some(v);
                 }
             }
         }
     }
-    false
+    found.then(|| best.unwrap_or_else(|| "extension".to_string()))
+}
+
+/// Highest version across several extension ids (forks / renamed publishers).
+fn find_any_vscode_extension(ext_prefixes: &[&str]) -> Option<String> {
+    ext_prefixes
+        .iter()
+        .filter_map(|p| find_vscode_extension(p))
+        .max_by(|a, b| utils::compare_versions(a, b))
+}
+
+fn read_extension_package_version(ext_dir: &std::path::Path) -> Option<String> {
+    let content = fs::read_to_string(ext_dir.join("package.json")).ok()?;
+    let json: Value = utils::parse_json_lenient(&content).ok()?;
+    json.get("version")?.as_str().map(|s| s.to_string())
+}
+
+/// "saoudrizwan.claude-dev-3.13.1" -> "3.13.1". Platform-specific builds carry
+/// a suffix ("anthropic.claude-code-1.0.0-darwin-arm64") which is dropped.
+fn version_from_extension_dir(dir_name: &str, ext_prefix: &str) -> Option<String> {
+    let version = dir_name.strip_prefix(ext_prefix)?.split('-').next()?;
+    let valid = !version.is_empty()
+        && version.contains('.')
+        && version.split('.').all(|p| p.parse::<u64>().is_ok());
+    valid.then(|| version.to_string())
+}
+
+/// Installed Chatbox version, where the app bundle exposes it.
+#[cfg(target_os = "macos")]
+fn chatbox_app_version() -> Option<String> {
+    bundle_version("Chatbox")
+}
+
+/// `CFBundleShortVersionString` of `{app_name}.app` in either Applications folder.
+#[cfg(target_os = "macos")]
+fn bundle_version(app_name: &str) -> Option<String> {
+    let bundle = format!("{app_name}.app");
+    std::iter::once(PathBuf::from("/Applications").join(&bundle))
+        .chain(home_dir().map(|h| h.join("Applications").join(&bundle)))
+        .filter_map(|b| fs::read_to_string(b.join("Contents/Info.plist")).ok())
+        .find_map(|plist| plist_string(&plist, "CFBundleShortVersionString"))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn bundle_version(_app_name: &str) -> Option<String> {
+    None
+}
+
+/// `<key>{key}</key><string>value</string>` from an XML plist.
+#[cfg(target_os = "macos")]
+fn plist_string(plist: &str, key: &str) -> Option<String> {
+    let after = plist.split_once(&format!("<key>{key}</key>"))?.1;
+    let value = after.split_once("<string>")?.1.split_once("</string>")?.0;
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Unpacked Electron builds ship `resources/app/package.json`.
+#[cfg(target_os = "windows")]
+fn electron_app_version(app_name: &str) -> Option<String> {
+    let dir = windows_app_dir(app_name)?;
+    let content = fs::read_to_string(dir.join("resources/app/package.json")).ok()?;
+    let json: Value = utils::parse_json_lenient(&content).ok()?;
+    json.get("version")?.as_str().map(|s| s.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn chatbox_app_version() -> Option<String> {
+    electron_app_version("Chatbox")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn chatbox_app_version() -> Option<String> {
+    None
+}
+
+/// Installed LobeHub desktop version, under its current or former name.
+#[cfg(target_os = "macos")]
+fn lobechat_app_version() -> Option<String> {
+    bundle_version("LobeHub").or_else(|| bundle_version("LobeChat"))
+}
+
+#[cfg(target_os = "windows")]
+fn lobechat_app_version() -> Option<String> {
+    electron_app_version("LobeHub").or_else(|| electron_app_version("LobeChat"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn lobechat_app_version() -> Option<String> {
+    None
 }
 
 /// Check if a macOS app bundle is installed.
@@ -267,53 +539,114 @@ fn is_app_installed(app_name: &str) -> bool {
     false
 }
 
+/// Common Windows install roots (per-user first).
 #[cfg(target_os = "windows")]
-fn is_app_installed(app_name: &str) -> bool {
-    // Check common Windows install locations
-    let candidates: &[&str] = &[
+fn windows_install_roots() -> Vec<PathBuf> {
+    [
         "LOCALAPPDATA",
         "PROGRAMFILES",
         "PROGRAMFILES(X86)",
         "APPDATA",
-    ];
-    for env_var in candidates {
-        if let Ok(base) = std::env::var(env_var) {
-            let path = PathBuf::from(&base).join(app_name);
-            if path.exists() {
-                return true;
-            }
-            // Also check with .exe suffix directly
-            let exe = PathBuf::from(&base).join(format!("{}.exe", app_name));
-            if exe.exists() {
-                return true;
-            }
-        }
+    ]
+    .iter()
+    .filter_map(|var| std::env::var(var).ok())
+    .map(PathBuf::from)
+    .collect()
+}
+
+/// Install folder of a Windows app: `<root>\<name>`, or `<root>\Programs\<name>`
+/// where per-user Electron installers put it.
+#[cfg(target_os = "windows")]
+pub(crate) fn windows_app_dir(app_name: &str) -> Option<PathBuf> {
+    windows_install_roots()
+        .into_iter()
+        .flat_map(|root| [root.join(app_name), root.join("Programs").join(app_name)])
+        .find(|dir| dir.is_dir())
+}
+
+#[cfg(target_os = "windows")]
+fn is_app_installed(app_name: &str) -> bool {
+    if windows_app_dir(app_name).is_some() {
+        return true;
+    }
+    // Also check with .exe suffix directly
+    if windows_install_roots()
+        .iter()
+        .any(|root| root.join(format!("{}.exe", app_name)).exists())
+    {
+        return true;
     }
     // Check if binary is in PATH
     utils::resolve_executable(&app_name.to_lowercase()).is_some()
 }
 
+/// Lowercase and drop separators so "Cherry Studio" matches "com.cherryai.CherryStudio".
+#[cfg(any(target_os = "linux", test))]
+fn compact_app_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Entry names are app ids like xyz.chatboxapp.app or cherry-studio.desktop.
 #[cfg(target_os = "linux")]
-fn is_app_installed(app_name: &str) -> bool {
-    // Check ~/.local/share/applications and /usr/share/applications for .desktop files
-    let lower = app_name.to_lowercase();
-    let candidates = [
-        dirs::home_dir().map(|h| h.join(".local/share/applications")),
+fn entry_matches_app(entry_name: &str, app_name: &str) -> bool {
+    entry_name.to_lowercase().contains(&app_name.to_lowercase())
+        || compact_app_name(entry_name).contains(&compact_app_name(app_name))
+}
+
+/// First entry in `dirs` whose file name matches `app_name` (and ends in
+/// `.{ext}` when given).
+#[cfg(target_os = "linux")]
+fn find_matching_entry(
+    dirs: &[Option<PathBuf>],
+    app_name: &str,
+    ext: Option<&str>,
+) -> Option<PathBuf> {
+    dirs.iter()
+        .flatten()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .find(|path| {
+            let name_ok = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| entry_matches_app(name, app_name));
+            name_ok && ext.is_none_or(|ext| path.extension().is_some_and(|e| e == ext))
+        })
+}
+
+/// The app's .desktop file (native or Flatpak export), used to detect and launch it.
+#[cfg(target_os = "linux")]
+pub(crate) fn linux_desktop_entry(app_name: &str) -> Option<PathBuf> {
+    let home = dirs::home_dir();
+    let dirs = [
+        home.as_ref().map(|h| h.join(".local/share/applications")),
         Some(PathBuf::from("/usr/share/applications")),
         Some(PathBuf::from("/usr/local/share/applications")),
+        Some(PathBuf::from("/var/lib/flatpak/exports/share/applications")),
+        home.as_ref()
+            .map(|h| h.join(".local/share/flatpak/exports/share/applications")),
     ];
-    for dir in candidates.iter().flatten() {
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.to_lowercase().contains(&lower) {
-                        return true;
-                    }
-                }
-            }
-        }
+    find_matching_entry(&dirs, app_name, Some("desktop"))
+}
+
+#[cfg(target_os = "linux")]
+fn is_app_installed(app_name: &str) -> bool {
+    if linux_desktop_entry(app_name).is_some() {
+        return true;
     }
-    utils::resolve_executable(&lower).is_some()
+    // Flatpak/Snap install dirs
+    let home = dirs::home_dir();
+    let install_dirs = [
+        Some(PathBuf::from("/var/lib/flatpak/app")),
+        home.as_ref().map(|h| h.join(".local/share/flatpak/app")),
+        Some(PathBuf::from("/snap")),
+    ];
+    find_matching_entry(&install_dirs, app_name, None).is_some()
+        || utils::resolve_executable(&app_name.to_lowercase()).is_some()
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
@@ -321,27 +654,38 @@ fn is_app_installed(_app_name: &str) -> bool {
     false
 }
 
+/// SillyTavern's folder. A custom folder is recognised through the sync path
+/// override, which points at `<folder>/data/default-user/secrets.json`.
+pub(crate) fn sillytavern_install_dir() -> Option<PathBuf> {
+    let custom = config_path_for(&ExtraClient::SillyTavern).and_then(|secrets| {
+        secrets
+            .ancestors()
+            .find(|dir| dir.join("server.js").is_file())
+            .map(|dir| dir.to_path_buf())
+    });
+    custom.or_else(|| {
+        let home = home_dir()?;
+        ["SillyTavern", "sillytavern", ".sillytavern"]
+            .iter()
+            .map(|d| home.join(d))
+            .find(|dir| dir.exists())
+    })
+}
+
 /// Detect whether a client is installed. Returns (installed, version).
 pub fn check_extra_installed(client: &ExtraClient) -> (bool, Option<String>) {
     match client {
         ExtraClient::ClaudeVSCode => {
-            let installed = is_vscode_extension_installed("anthropic.claude-code-");
-            (
-                installed,
-                if installed {
-                    Some("extension".to_string())
-                } else {
-                    None
-                },
-            )
+            let version = find_vscode_extension("anthropic.claude-code-");
+            (version.is_some(), version)
         }
         ExtraClient::Chatbox => {
             let installed = is_app_installed("Chatbox")
-                || chatbox_config_path().is_some_and(|p| p.parent().is_some_and(|d| d.exists()));
+                || config_path_for(client).is_some_and(|p| p.parent().is_some_and(|d| d.exists()));
             (
                 installed,
                 if installed {
-                    Some("detected".to_string())
+                    chatbox_app_version().or_else(|| Some("detected".to_string()))
                 } else {
                     None
                 },
@@ -349,7 +693,7 @@ pub fn check_extra_installed(client: &ExtraClient) -> (bool, Option<String>) {
         }
         ExtraClient::CherryStudio => {
             let installed = is_app_installed("Cherry Studio")
-                || cherry_config_path().is_some_and(|p| p.exists());
+                || config_path_for(client).is_some_and(|p| p.exists());
             (
                 installed,
                 if installed {
@@ -378,7 +722,7 @@ pub fn check_extra_installed(client: &ExtraClient) -> (bool, Option<String>) {
                 return (true, version.or_else(|| Some("detected".to_string())));
             }
             let installed =
-                is_app_installed("Cursor") || cursor_config_path().is_some_and(|p| p.exists());
+                is_app_installed("Cursor") || config_path_for(client).is_some_and(|p| p.exists());
             (
                 installed,
                 if installed {
@@ -389,75 +733,79 @@ pub fn check_extra_installed(client: &ExtraClient) -> (bool, Option<String>) {
             )
         }
         ExtraClient::Cline => {
-            let installed = is_vscode_extension_installed("saoudrizwan.claude-dev-")
-                || is_vscode_extension_installed("hybridtalentcomputing.cline-chinese-")
-                || is_vscode_extension_installed("cline.cline-");
-            (
-                installed,
-                if installed {
-                    Some("extension".to_string())
-                } else {
-                    None
-                },
-            )
+            let version = find_any_vscode_extension(&[
+                "saoudrizwan.claude-dev-",
+                "hybridtalentcomputing.cline-chinese-",
+                "cline.cline-",
+            ]);
+            (version.is_some(), version)
         }
         ExtraClient::RooCode => {
-            let installed = is_vscode_extension_installed("rooveterinaryinc.roo-cline-");
+            let version = find_vscode_extension("rooveterinaryinc.roo-cline-");
+            (version.is_some(), version)
+        }
+        ExtraClient::KiloCode => {
+            let version = find_vscode_extension("kilocode.kilo-code-");
+            (version.is_some(), version)
+        }
+        ExtraClient::SillyTavern => {
+            let installed = sillytavern_install_dir().is_some();
             (
                 installed,
                 if installed {
-                    Some("extension".to_string())
+                    Some("detected".to_string())
                 } else {
                     None
                 },
             )
         }
-        ExtraClient::KiloCode => {
-            let installed = is_vscode_extension_installed("kilocode.kilo-code-");
+        ExtraClient::LobeChat => {
+            let installed = is_app_installed("LobeHub")
+                || is_app_installed("LobeChat")
+                || config_path_for(client).is_some_and(|p| p.parent().is_some_and(|d| d.exists()));
             (
                 installed,
                 if installed {
-                    Some("extension".to_string())
+                    lobechat_app_version().or_else(|| Some("detected".to_string()))
                 } else {
                     None
                 },
             )
         }
-        ExtraClient::SillyTavern => {
-            let home = match home_dir() {
-                Some(h) => h,
-                None => return (false, None),
-            };
-            let installed = ["SillyTavern", "sillytavern", ".sillytavern"]
-                .iter()
-                .any(|d| home.join(d).exists());
+        ExtraClient::BoltAI => {
+            let installed = is_app_installed("BoltAI");
             (
                 installed,
                 if installed {
-                    Some("detected".to_string())
+                    bundle_version("BoltAI").or_else(|| Some("detected".to_string()))
                 } else {
                     None
                 },
             )
         }
-        ExtraClient::LobeChat => {
-            let installed = is_app_installed("LobeChat")
-                || app_support_dir().is_some_and(|d| d.join("LobeChat").exists());
+        ExtraClient::WarpTerminal => {
+            // The Linux packages install `warp-terminal`
+            let installed =
+                is_app_installed("Warp") || utils::resolve_executable("warp-terminal").is_some();
             (
                 installed,
                 if installed {
-                    Some("detected".to_string())
+                    bundle_version("Warp").or_else(|| Some("detected".to_string()))
                 } else {
                     None
                 },
             )
         }
-        ExtraClient::BoltAI => {
-            let installed = is_app_installed("BoltAI");
+        ExtraClient::AmazonQ => {
+            if let Some(path) = utils::resolve_executable("q") {
+                let version = utils::get_cli_version(&path);
+                return (true, version.or_else(|| Some("detected".to_string())));
+            }
+            let installed = is_app_installed("Amazon Q");
             (
                 installed,
                 if installed {
-                    Some("detected".to_string())
+                    bundle_version("Amazon Q").or_else(|| Some("detected".to_string()))
                 } else {
                     None
                 },
@@ -472,68 +820,101 @@ pub fn check_extra_installed(client: &ExtraClient) -> (bool, Option<String>) {
 
 const HAJIMI_MARKER: &str = "hajimi";
 
-pub fn get_extra_sync_status(
-    client: &ExtraClient,
-    proxy_url: &str,
-) -> (bool, bool, Option<String>) {
-    let config_path = match config_path_for(client) {
-        Some(p) => p,
-        None => return (false, false, None),
+pub fn get_extra_sync_status(client: &ExtraClient, proxy_url: Option<&str>) -> SyncStatus {
+    let Some(config_path) = config_path_for(client) else {
+        return SyncStatus::default();
     };
 
-    let backup_path = backup_path_for(&config_path);
-    let has_backup = backup_path.exists();
-
-    if !config_path.exists() {
-        return (false, has_backup, None);
+    if matches!(client, ExtraClient::ClaudeVSCode) {
+        // Reuse Claude CLI sync status check
+        return cli_sync::get_sync_status(&cli_sync::CliApp::Claude, proxy_url);
     }
 
-    let content = match fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return (false, has_backup, None),
+    let has_backup = utils::find_backup(&config_path).is_some();
+    let name = config_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        return SyncStatus::new(vec![FileStatus::missing(&name)], has_backup);
     };
 
+    let status = match client {
+        ExtraClient::Chatbox => check_chatbox_synced(&name, &content, proxy_url),
+        ExtraClient::CherryStudio => check_cherry_synced(&name, &content, proxy_url),
+        ExtraClient::Jan => check_jan_synced(&name, &content, proxy_url),
+        ExtraClient::SillyTavern => check_sillytavern_synced(&name, &content, proxy_url),
+        ExtraClient::LobeChat => check_lobechat_synced(&name, &content, proxy_url),
+        ExtraClient::BoltAI => check_boltai_synced(&name, &content, proxy_url),
+        _ => return SyncStatus::default(),
+    };
+    SyncStatus::new(vec![status], has_backup)
+}
+
+/// Reverse sync for clients whose config we know how to read back.
+pub fn detect_existing_provider(client: &ExtraClient) -> Option<DetectedProvider> {
     match client {
-        ExtraClient::ClaudeVSCode => {
-            // Reuse Claude CLI sync status check
-            let cli_app = cli_sync::CliApp::Claude;
-            cli_sync::get_sync_status(&cli_app, proxy_url)
+        ExtraClient::ClaudeVSCode => cli_sync::detect_existing_provider(&cli_sync::CliApp::Claude)
+            .map(|d| DetectedProvider {
+                app: client.as_str().to_string(),
+                ..d
+            }),
+        ExtraClient::Chatbox => {
+            let content = fs::read_to_string(config_path_for(client)?).ok()?;
+            detect_chatbox(&content)
         }
-        ExtraClient::Chatbox => check_chatbox_synced(&content, proxy_url, has_backup),
-        ExtraClient::CherryStudio => check_cherry_synced(&content, proxy_url, has_backup),
-        ExtraClient::Jan => check_jan_synced(&content, proxy_url, has_backup),
-        ExtraClient::SillyTavern => check_sillytavern_synced(&content, proxy_url, has_backup),
-        ExtraClient::Cursor | ExtraClient::Cline | ExtraClient::RooCode | ExtraClient::KiloCode => {
-            (false, false, None)
+        ExtraClient::LobeChat => {
+            let content = fs::read_to_string(config_path_for(client)?).ok()?;
+            let json: Value = utils::parse_json_lenient(&content).ok()?;
+            let field = |k: &str| json.pointer(&format!("/keyVaults/openai/{k}"))?.as_str();
+            DetectedProvider::new(client.as_str(), field("baseURL")?, field("apiKey")?, None)
         }
-        _ => (false, false, None),
+        _ => None,
     }
 }
 
-fn check_chatbox_synced(
-    content: &str,
-    proxy_url: &str,
-    has_backup: bool,
-) -> (bool, bool, Option<String>) {
-    let json: Value = serde_json::from_str(content).unwrap_or_default();
-    let current_url = json
-        .get("openaiApiHost")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+fn detect_chatbox(content: &str) -> Option<DetectedProvider> {
+    let json: Value = utils::parse_json_lenient(content).ok()?;
+    match chatbox_schema(&json, None) {
+        ChatboxSchema::Legacy => {
+            let field = |k: &str| json.get(k).and_then(|v| v.as_str());
+            DetectedProvider::new(
+                ExtraClient::Chatbox.as_str(),
+                field("openaiApiHost")?,
+                field("openaiApiKey")?,
+                field("chatgptModel"),
+            )
+        }
+        ChatboxSchema::Providers => {
+            let entry = chatbox_hajimi_entry(&json)?;
+            let field = |k: &str| entry.get(k).and_then(|v| v.as_str());
+            DetectedProvider::new(
+                ExtraClient::Chatbox.as_str(),
+                field("apiHost")?,
+                field("apiKey")?,
+                field("defaultModel"),
+            )
+        }
+    }
+}
 
-    let is_synced = current_url
-        .as_deref()
-        .is_some_and(|u| utils::urls_match(u, proxy_url));
+fn check_chatbox_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
+    let (current_url, field) = match chatbox_schema(&json, None) {
+        ChatboxSchema::Legacy => (json.get("openaiApiHost"), "openaiApiHost"),
+        ChatboxSchema::Providers => (
+            chatbox_hajimi_entry(&json).and_then(|p| p.get("apiHost")),
+            "settings.providers[hajimi].apiHost",
+        ),
+    };
+    let current_url = current_url.and_then(|v| v.as_str()).map(|s| s.to_string());
 
-    (is_synced, has_backup, current_url)
+    FileStatus::new(name, true).with_url(current_url, proxy_url, field, false)
 }
 
-fn check_cherry_synced(
-    content: &str,
-    proxy_url: &str,
-    has_backup: bool,
-) -> (bool, bool, Option<String>) {
-    let json: Value = serde_json::from_str(content).unwrap_or_default();
+fn check_cherry_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
 
     // Cherry Studio stores providers in a "providers" array/object
     let current_url = json
@@ -552,137 +933,223 @@ fn check_cherry_synced(
             })
         });
 
-    let is_synced = current_url
-        .as_deref()
-        .is_some_and(|u| utils::urls_match(u, proxy_url));
-
-    (is_synced, has_backup, current_url)
+    FileStatus::new(name, true).with_url(current_url, proxy_url, "providers[hajimi].apiHost", false)
 }
 
-fn check_jan_synced(
-    content: &str,
-    proxy_url: &str,
-    has_backup: bool,
-) -> (bool, bool, Option<String>) {
-    let json: Value = serde_json::from_str(content).unwrap_or_default();
-
-    // Jan engine config uses "full_url" (ends with /chat/completions) and "api_key"
-    let current_url = json.get("full_url").and_then(|v| v.as_str()).map(|s| {
-        // Normalise: strip trailing /chat/completions to get base URL
-        s.trim_end_matches("/chat/completions").to_string()
-    });
+fn check_jan_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
 
-    let is_synced = current_url
-        .as_deref()
-        .is_some_and(|u| utils::urls_match(u, proxy_url));
+    // New provider settings carry "base_url"; the legacy engine config uses
+    // "full_url" (ends with /chat/completions). Accept either so status follows
+    // whichever file the installed version reads.
+    let current_url = json
+        .get("base_url")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| {
+            json.get("full_url").and_then(|v| v.as_str()).map(|s| {
+                // Normalise: strip trailing /chat/completions to get base URL
+                s.trim_end_matches("/chat/completions").to_string()
+            })
+        });
 
-    (is_synced, has_backup, current_url)
+    FileStatus::new(name, true).with_url(current_url, proxy_url, "base_url", false)
 }
 
-fn check_sillytavern_synced(
-    content: &str,
-    proxy_url: &str,
-    has_backup: bool,
-) -> (bool, bool, Option<String>) {
-    let json: Value = serde_json::from_str(content).unwrap_or_default();
+fn check_sillytavern_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
 
     let current_url = json
         .get("api_url_scale")
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    let is_synced = current_url
-        .as_deref()
-        .is_some_and(|u| utils::urls_match(u, proxy_url));
+    FileStatus::new(name, true).with_url(current_url, proxy_url, "api_url_scale", false)
+}
+
+fn check_lobechat_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
+
+    let current_url = json
+        .pointer("/keyVaults/openai/baseURL")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    FileStatus::new(name, true).with_url(current_url, proxy_url, "keyVaults.openai.baseURL", false)
+}
+
+fn check_boltai_synced(name: &str, content: &str, proxy_url: Option<&str>) -> FileStatus {
+    let json: Value = utils::parse_json_lenient(content).unwrap_or_default();
+
+    let current_url = boltai_hajimi_entry(&json)
+        .and_then(|s| s.get("baseURL"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
 
-    (is_synced, has_backup, current_url)
+    FileStatus::new(name, true).with_url(current_url, proxy_url, "services[hajimi].baseURL", false)
 }
 
 // ---------------------------------------------------------------------------
 // Sync
 // ---------------------------------------------------------------------------
 
+/// `advanced` only matters for the Claude extension, which shares Claude
+/// Code's settings.json.
 pub fn sync_extra_config(
     client: &ExtraClient,
     proxy_url: &str,
     api_key: &str,
     model: Option<&str>,
-) -> Result<(), String> {
-    match client {
+    advanced: &ProviderAdvanced,
+) -> Result<SyncOutcome, String> {
+    let synced = match client {
         ExtraClient::ClaudeVSCode => {
             // Reuse Claude CLI sync logic — writes to ~/.claude/settings.json
             let cli_app = cli_sync::CliApp::Claude;
-            cli_sync::sync_config(&cli_app, proxy_url, api_key, model)
+            cli_sync::sync_config(&cli_app, proxy_url, api_key, model, advanced).map(drop)
         }
         ExtraClient::Chatbox => sync_chatbox(proxy_url, api_key, model),
         ExtraClient::CherryStudio => sync_cherry(proxy_url, api_key, model),
         ExtraClient::Jan => sync_jan(proxy_url, api_key, model),
         ExtraClient::SillyTavern => sync_sillytavern(proxy_url, api_key),
-        ExtraClient::Cursor => {
-            Err(format!(
-                "{} AI configuration must be set through the app UI: \
-                 Settings > Models > OpenAI API Key / Base URL.",
-                client.display_name()
-            ))
-        }
-        ExtraClient::Cline | ExtraClient::RooCode | ExtraClient::KiloCode => {
-            Err(format!(
-                "{} stores API config in its extension settings. \
-                 Open the extension sidebar > Settings icon > set API Provider to \"OpenAI Compatible\", \
-                 then enter your Base URL and API Key.",
-                client.display_name()
-            ))
-        }
         ExtraClient::LobeChat => {
-            Err(format!(
-                "{} uses browser storage or environment variables. \
-                 Configure it through the app UI or set OPENAI_BASE_URL and OPENAI_API_KEY env vars.",
-                client.display_name()
-            ))
+            if let Some(steps) = lobechat_unsupported(lobechat_app_version().as_deref()) {
+                return Ok(SyncOutcome::ManualStepsRequired(steps));
+            }
+            sync_lobechat(proxy_url, api_key, model)
         }
-        ExtraClient::BoltAI => {
-            Err(format!(
-                "{} stores API keys in macOS Keychain. \
-                 Configure it through the app: Settings > Models > Add OpenAI-compatible Server.",
-                client.display_name()
-            ))
+        ExtraClient::BoltAI if client.supports_file_sync() => {
+            return sync_boltai(proxy_url, api_key, model)
         }
-    }
+        ExtraClient::Cursor
+        | ExtraClient::Cline
+        | ExtraClient::RooCode
+        | ExtraClient::KiloCode
+        | ExtraClient::BoltAI
+        | ExtraClient::WarpTerminal
+        | ExtraClient::AmazonQ => {
+            let steps = client.manual_steps().unwrap_or_default();
+            return Ok(SyncOutcome::ManualStepsRequired(steps));
+        }
+    };
+    synced.map(|()| SyncOutcome::Synced(None))
 }
 
-fn sync_chatbox(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<(), String> {
-    let config_path =
-        chatbox_config_path().ok_or("Failed to determine Chatbox config directory")?;
+/// Chatbox config layouts: flat `openaiApi*` keys before 1.9, a
+/// `settings.providers` array since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatboxSchema {
+    Legacy,
+    Providers,
+}
 
-    ensure_parent_dir(&config_path)?;
-    utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
+const CHATBOX_PROVIDERS_SINCE: &str = "1.9.0";
 
-    let mut config: Value = read_or_empty_json(&config_path);
+/// The layout the existing config already uses, else the one the installed
+/// version reads. Unknown versions get the new layout: fresh installs are new.
+fn chatbox_schema(config: &Value, app_version: Option<&str>) -> ChatboxSchema {
+    if config
+        .pointer("/settings/providers")
+        .is_some_and(Value::is_array)
+    {
+        return ChatboxSchema::Providers;
+    }
+    if config.get("openaiApiHost").is_some() || config.get("openaiApiKey").is_some() {
+        return ChatboxSchema::Legacy;
+    }
+    match app_version.filter(|v| v.split('.').all(|p| p.parse::<u64>().is_ok())) {
+        Some(v) if utils::compare_versions(v, CHATBOX_PROVIDERS_SINCE).is_lt() => {
+            ChatboxSchema::Legacy
+        }
+        _ => ChatboxSchema::Providers,
+    }
+}
 
+fn chatbox_hajimi_entry(config: &Value) -> Option<&Value> {
+    config
+        .pointer("/settings/providers")?
+        .as_array()?
+        .iter()
+        .find(|p| p.get("id").and_then(|v| v.as_str()) == Some(HAJIMI_MARKER))
+}
+
+/// Write the proxy settings into `config` in `schema`'s layout.
+fn apply_chatbox_config(
+    config: &mut Value,
+    schema: ChatboxSchema,
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+) -> Result<(), String> {
     let obj = config
         .as_object_mut()
         .ok_or("Chatbox config is not a JSON object")?;
 
-    obj.insert(
-        "openaiApiHost".to_string(),
-        Value::String(proxy_url.to_string()),
-    );
-    obj.insert(
-        "openaiApiKey".to_string(),
-        Value::String(api_key.to_string()),
-    );
+    if schema == ChatboxSchema::Legacy {
+        obj.insert(
+            "openaiApiHost".to_string(),
+            Value::String(proxy_url.to_string()),
+        );
+        obj.insert(
+            "openaiApiKey".to_string(),
+            Value::String(api_key.to_string()),
+        );
+        if let Some(m) = model {
+            obj.insert("chatgptModel".to_string(), Value::String(m.to_string()));
+        }
+        return Ok(());
+    }
 
+    let mut provider = serde_json::json!({
+        "id": HAJIMI_MARKER,
+        "name": "哈基米 AI",
+        "type": "openai",
+        "apiHost": proxy_url,
+        "apiKey": api_key,
+        "enabled": true
+    });
     if let Some(m) = model {
-        obj.insert("chatgptModel".to_string(), Value::String(m.to_string()));
+        provider["defaultModel"] = Value::String(m.to_string());
+    }
+
+    let settings = obj
+        .entry("settings")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or("Chatbox settings is not a JSON object")?;
+    let providers = settings
+        .entry("providers")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or("Chatbox settings.providers is not an array")?;
+    // Replace our entry in place so the user's ordering is kept
+    match providers
+        .iter_mut()
+        .find(|p| p.get("id").and_then(|v| v.as_str()) == Some(HAJIMI_MARKER))
+    {
+        Some(existing) => *existing = provider,
+        None => providers.push(provider),
     }
+    Ok(())
+}
+
+fn sync_chatbox(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<(), String> {
+    let config_path = config_path_for(&ExtraClient::Chatbox)
+        .ok_or("Failed to determine Chatbox config directory")?;
+
+    ensure_parent_dir(&config_path)?;
+    utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
 
-    let content = utils::to_json_pretty(&config).map_err(|e| e.to_string())?;
-    utils::atomic_write(&config_path, &content).map_err(|e| e.to_string())
+    let mut config: Value = read_or_empty_json(&config_path);
+    let schema = chatbox_schema(&config, chatbox_app_version().as_deref());
+    apply_chatbox_config(&mut config, schema, proxy_url, api_key, model)?;
+
+    write_json_verified(&config_path, &config, &[proxy_url, api_key])
 }
 
 fn sync_cherry(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<(), String> {
-    let config_path =
-        cherry_config_path().ok_or("Failed to determine Cherry Studio config directory")?;
+    let config_path = config_path_for(&ExtraClient::CherryStudio)
+        .ok_or("Failed to determine Cherry Studio config directory")?;
 
     ensure_parent_dir(&config_path)?;
     utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
@@ -722,35 +1189,308 @@ fn sync_cherry(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<()
         arr.push(provider);
     }
 
-    let content = utils::to_json_pretty(&config).map_err(|e| e.to_string())?;
-    utils::atomic_write(&config_path, &content).map_err(|e| e.to_string())
+    write_json_verified(&config_path, &config, &[proxy_url, api_key])
+}
+
+/// Normalise a proxy URL to an OpenAI-compatible base (always ends with /v1).
+fn v1_base_url(proxy_url: &str) -> String {
+    let base = proxy_url.trim().trim_end_matches('/');
+    let base = base.trim_end_matches("/v1");
+    format!("{base}/v1")
+}
+
+/// Build the per-provider settings.json used by Jan >= 0.6.
+fn build_jan_provider_settings(proxy_url: &str, api_key: &str, model: Option<&str>) -> Value {
+    let models: Vec<Value> = model
+        .map(|m| vec![serde_json::json!({ "id": m, "name": m })])
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "provider": JAN_PROVIDER_DIR,
+        "name": "哈基米 AI",
+        "active": true,
+        "base_url": v1_base_url(proxy_url),
+        "api_key": api_key,
+        "models": models,
+    })
 }
 
-fn sync_jan(proxy_url: &str, api_key: &str, _model: Option<&str>) -> Result<(), String> {
-    let config_path = jan_config_path().ok_or("Failed to determine Jan config directory")?;
+/// Build the legacy engine config (~/jan/engines/openai.json).
+fn build_jan_legacy_config(proxy_url: &str, api_key: &str) -> Value {
+    serde_json::json!({
+        "full_url": format!("{}/chat/completions", v1_base_url(proxy_url)),
+        "api_key": api_key,
+    })
+}
+
+fn sync_jan(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<(), String> {
+    let config_path =
+        config_path_for(&ExtraClient::Jan).ok_or("Failed to determine Jan config directory")?;
 
     ensure_parent_dir(&config_path)?;
     utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
 
-    // Jan engine config format (~/jan/engines/openai.json):
-    // { "full_url": "https://proxy/v1/chat/completions", "api_key": "sk-..." }
-    // Normalise to always include /v1 before /chat/completions
-    let base = proxy_url.trim().trim_end_matches('/');
-    let base = base.trim_end_matches("/v1");
-    let full_url = format!("{base}/v1/chat/completions");
+    let config = match jan_layout() {
+        JanLayout::ProviderSettings => {
+            let mut config = build_jan_provider_settings(proxy_url, api_key, model);
+            // Keep any models the user already added to our provider entry
+            if let Some(existing) = read_or_empty_json(&config_path)
+                .get("models")
+                .and_then(|v| v.as_array())
+            {
+                let list = config["models"].as_array_mut().unwrap();
+                for m in existing {
+                    let id = m.get("id").and_then(|v| v.as_str());
+                    if id.is_some()
+                        && !list
+                            .iter()
+                            .any(|x| x.get("id").and_then(|v| v.as_str()) == id)
+                    {
+                        list.push(m.clone());
+                    }
+                }
+            }
+            config
+        }
+        JanLayout::LegacyEngine => build_jan_legacy_config(proxy_url, api_key),
+    };
 
-    let config = serde_json::json!({
-        "full_url": full_url,
-        "api_key": api_key,
+    write_json_verified(&config_path, &config, &[api_key])
+}
+
+/// LobeHub desktop moved user settings from `settings.json` into its local
+/// database in 2.0; writing the file there would do nothing.
+const LOBECHAT_SETTINGS_FILE_BEFORE: &str = "2.0.0";
+
+/// Manual steps when the installed desktop version doesn't read the settings
+/// file. Unknown versions (Linux, or no app bundle found) are written to.
+fn lobechat_unsupported(app_version: Option<&str>) -> Option<String> {
+    // "2.0.0-beta.3" counts as 2.0.0
+    let version = app_version?.split('-').next()?;
+    if !version.split('.').all(|p| p.parse::<u64>().is_ok())
+        || utils::compare_versions(version, LOBECHAT_SETTINGS_FILE_BEFORE).is_lt()
+    {
+        return None;
+    }
+    Some(format!(
+        "LobeChat {} keeps provider settings in its database. \
+         Configure it through the app: Settings > Language Model > OpenAI, enable the API proxy \
+         and enter your Base URL and API Key. Self-hosted servers read OPENAI_PROXY_URL and OPENAI_API_KEY.",
+        app_version.unwrap_or_default()
+    ))
+}
+
+/// `obj[key].openai`, created where missing.
+fn lobechat_openai_section<'a>(
+    obj: &'a mut serde_json::Map<String, Value>,
+    key: &str,
+) -> Result<&'a mut serde_json::Map<String, Value>, String> {
+    obj.entry(key)
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or(format!("LobeChat {key} is not a JSON object"))?
+        .entry("openai")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or(format!("LobeChat {key}.openai is not a JSON object"))
+}
+
+/// Point LobeChat's built-in OpenAI provider at the proxy; other providers
+/// and settings are left alone.
+fn apply_lobechat_config(
+    config: &mut Value,
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+) -> Result<(), String> {
+    let obj = config
+        .as_object_mut()
+        .ok_or("LobeChat settings is not a JSON object")?;
+    let vault = lobechat_openai_section(obj, "keyVaults")?;
+    vault.insert("apiKey".to_string(), Value::String(api_key.to_string()));
+    vault.insert("baseURL".to_string(), Value::String(v1_base_url(proxy_url)));
+
+    let provider = lobechat_openai_section(obj, "languageModel")?;
+    provider.insert("enabled".to_string(), Value::Bool(true));
+    if let Some(m) = model {
+        let models = provider
+            .entry("enabledModels")
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or("LobeChat languageModel.openai.enabledModels is not an array")?;
+        if !models.iter().any(|v| v.as_str() == Some(m)) {
+            models.push(Value::String(m.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn sync_lobechat(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<(), String> {
+    let config_path = config_path_for(&ExtraClient::LobeChat)
+        .ok_or("Failed to determine LobeChat config directory")?;
+
+    ensure_parent_dir(&config_path)?;
+    utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
+
+    let mut config: Value = read_or_empty_json(&config_path);
+    apply_lobechat_config(&mut config, proxy_url, api_key, model)?;
+
+    write_json_verified(&config_path, &config, &[api_key])
+}
+
+fn boltai_hajimi_entry(config: &Value) -> Option<&Value> {
+    config
+        .get("services")?
+        .as_array()?
+        .iter()
+        .find(|s| s.get("id").and_then(|v| v.as_str()) == Some(HAJIMI_MARKER))
+}
+
+/// Upsert our OpenAI-compatible service. The key is not written here; the
+/// entry names the Keychain item BoltAI reads it from.
+fn apply_boltai_service(
+    config: &mut Value,
+    proxy_url: &str,
+    model: Option<&str>,
+) -> Result<(), String> {
+    let obj = config
+        .as_object_mut()
+        .ok_or("BoltAI services file is not a JSON object")?;
+    let mut service = serde_json::json!({
+        "id": HAJIMI_MARKER,
+        "name": "哈基米 AI",
+        "type": "openai-compatible",
+        "baseURL": v1_base_url(proxy_url),
+        "apiKeyKeychain": {
+            "service": BOLTAI_KEYCHAIN_SERVICE,
+            "account": HAJIMI_MARKER,
+        },
     });
+    if let Some(m) = model {
+        service["defaultModel"] = Value::String(m.to_string());
+    }
+
+    let services = obj
+        .entry("services")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or("BoltAI services is not an array")?;
+    match services
+        .iter_mut()
+        .find(|s| s.get("id").and_then(|v| v.as_str()) == Some(HAJIMI_MARKER))
+    {
+        Some(existing) => {
+            // Keep models the user picked for the service in BoltAI
+            if let Some(models) = existing.get("models").cloned() {
+                service["models"] = models;
+            }
+            *existing = service;
+        }
+        None => services.push(service),
+    }
+    Ok(())
+}
+
+/// Writes the service entry, then the key into the Keychain. A Keychain
+/// failure leaves the entry in place and asks for the key by hand.
+fn sync_boltai(proxy_url: &str, api_key: &str, model: Option<&str>) -> Result<SyncOutcome, String> {
+    let config_path = config_path_for(&ExtraClient::BoltAI)
+        .ok_or("Failed to determine BoltAI config directory")?;
+
+    ensure_parent_dir(&config_path)?;
+    utils::create_rotated_backup(&config_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
+
+    let mut config: Value = read_or_empty_json(&config_path);
+    apply_boltai_service(&mut config, proxy_url, model)?;
+    let base_url = v1_base_url(proxy_url);
+    write_json_verified(&config_path, &config, &[&base_url])?;
+
+    match store_boltai_key(api_key) {
+        Ok(()) => Ok(SyncOutcome::Synced(None)),
+        Err(e) => {
+            tracing::warn!("[extra_clients] BoltAI Keychain item not written: {}", e);
+            Ok(SyncOutcome::ManualStepsRequired(format!(
+                "BoltAI's 哈基米 AI service was added, but the API key could not be saved to the Keychain ({e}). \
+                 Open BoltAI: Settings > Models > 哈基米 AI and paste your API key."
+            )))
+        }
+    }
+}
 
-    let content = utils::to_json_pretty(&config).map_err(|e| e.to_string())?;
-    utils::atomic_write(&config_path, &content).map_err(|e| e.to_string())
+/// `security -i` reads the command from stdin, which keeps the key out of
+/// the process list. Its parser splits on whitespace and quotes, so keys
+/// that would need escaping are left to the user.
+#[cfg(target_os = "macos")]
+fn store_boltai_key(api_key: &str) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    if api_key.is_empty()
+        || api_key
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '\\')
+    {
+        return Err("the key contains characters the security tool can't take".to_string());
+    }
+    let mut child = Command::new("/usr/bin/security")
+        .arg("-i")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run security: {e}"))?;
+    let command = format!(
+        "add-generic-password -U -s \"{BOLTAI_KEYCHAIN_SERVICE}\" -a \"{HAJIMI_MARKER}\" \
+         -l \"哈基米 AI (BoltAI)\" -w \"{api_key}\"\nquit\n"
+    );
+    child
+        .stdin
+        .take()
+        .ok_or("security has no stdin")?
+        .write_all(command.as_bytes())
+        .map_err(|e| format!("failed to talk to security: {e}"))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("security did not finish: {e}"))?;
+    // `-i` exits 0 even when a command fails; errors only show on stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.trim().is_empty() {
+        return Err(format!("security: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn store_boltai_key(_api_key: &str) -> Result<(), String> {
+    Err("the Keychain is only available on macOS".to_string())
 }
 
+/// Drop our Keychain item after the services file is restored.
+#[cfg(target_os = "macos")]
+fn remove_boltai_key() {
+    let result = std::process::Command::new("/usr/bin/security")
+        .args([
+            "delete-generic-password",
+            "-s",
+            BOLTAI_KEYCHAIN_SERVICE,
+            "-a",
+            HAJIMI_MARKER,
+        ])
+        .output();
+    if let Err(e) = result {
+        tracing::warn!(
+            "[extra_clients] Failed to remove BoltAI Keychain item: {}",
+            e
+        );
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn remove_boltai_key() {}
+
 fn sync_sillytavern(proxy_url: &str, api_key: &str) -> Result<(), String> {
-    let secrets_path =
-        sillytavern_secrets_path().ok_or("Failed to determine SillyTavern config directory")?;
+    let secrets_path = config_path_for(&ExtraClient::SillyTavern)
+        .ok_or("Failed to determine SillyTavern config directory")?;
 
     ensure_parent_dir(&secrets_path)?;
     utils::create_rotated_backup(&secrets_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
@@ -771,8 +1511,14 @@ fn sync_sillytavern(proxy_url: &str, api_key: &str) -> Result<(), String> {
         Value::String(proxy_url.to_string()),
     );
 
-    let content = utils::to_json_pretty(&secrets).map_err(|e| e.to_string())?;
-    utils::atomic_write(&secrets_path, &content).map_err(|e| e.to_string())
+    write_json_verified(&secrets_path, &secrets, &[proxy_url, api_key])
+}
+
+/// `utils::write_json_secure`, then read the file back and check `expected` landed.
+fn write_json_verified(path: &PathBuf, value: &Value, expected: &[&str]) -> Result<(), String> {
+    utils::write_json_secure(path, value).map_err(|e| e.to_string())?;
+    utils::verify_written_config(path, expected, || utils::write_json_secure(path, value))
+        .map_err(|e| e.to_string())
 }
 
 // ---------------------------------------------------------------------------
@@ -789,18 +1535,20 @@ pub fn restore_extra_config(client: &ExtraClient) -> Result<(), String> {
     let config_path = config_path_for(client)
         .ok_or_else(|| format!("{} does not use file-based config", client.display_name()))?;
 
-    let backup = backup_path_for(&config_path);
-    if !backup.exists() {
+    let Some(backup) = utils::find_backup(&config_path) else {
         return Err(format!(
             "No backup file found for {}",
             client.display_name()
         ));
-    }
+    };
 
     if config_path.exists() {
         fs::remove_file(&config_path).map_err(|e| format!("Failed to remove config: {e}"))?;
     }
     fs::rename(&backup, &config_path).map_err(|e| format!("Failed to restore config: {e}"))?;
+    if matches!(client, ExtraClient::BoltAI) {
+        remove_boltai_key();
+    }
 
     tracing::info!(
         "[extra_clients] Restored {} config from backup",
@@ -832,14 +1580,6 @@ pub fn read_extra_config_content(client: &ExtraClient) -> Result<String, String>
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn backup_path_for(config_path: &std::path::Path) -> PathBuf {
-    let file_name = config_path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy();
-    config_path.with_file_name(format!("{file_name}{BACKUP_SUFFIX}"))
-}
-
 fn ensure_parent_dir(path: &std::path::Path) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
@@ -852,7 +1592,7 @@ fn read_or_empty_json(path: &PathBuf) -> Value {
     if path.exists() {
         fs::read_to_string(path)
             .ok()
-            .and_then(|c| serde_json::from_str(&c).ok())
+            .and_then(|c| utils::parse_json_lenient(&c).ok())
             .unwrap_or_else(|| serde_json::json!({}))
     } else {
         serde_json::json!({})
@@ -882,7 +1622,7 @@ pub fn write_extra_config_content(
         )
     })?;
 
-    utils::atomic_write(&config_path, content).map_err(|e| {
+    utils::atomic_write_secure(&config_path, content).map_err(|e| {
         format!(
             "Failed to write config for {}: {}",
             client.display_name(),
@@ -907,6 +1647,47 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_sync_status_restore_end_to_end() {
+        let proxy = "https://proxy.e2e.test/v1";
+        // BoltAI's sync also writes the login Keychain
+        for client in ExtraClient::all()
+            .iter()
+            .filter(|c| c.supports_file_sync() && **c != ExtraClient::BoltAI)
+        {
+            let home = TempDir::new().unwrap();
+            let _home = paths::override_home(home.path());
+            let config_path = config_path_for(client).unwrap();
+            assert!(config_path.starts_with(home.path()), "{client:?}");
+            let original = "{}\n";
+            fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+            fs::write(&config_path, original).unwrap();
+
+            let outcome = sync_extra_config(
+                client,
+                proxy,
+                "sk-e2e",
+                Some("gpt-4o"),
+                &ProviderAdvanced::default(),
+            )
+            .unwrap();
+            assert_eq!(outcome, SyncOutcome::Synced(None), "{client:?}");
+            let content = fs::read_to_string(&config_path).unwrap();
+            assert!(content.contains("sk-e2e"), "{client:?}: {content}");
+            assert!(
+                get_extra_sync_status(client, Some(proxy)).is_synced(),
+                "{client:?}"
+            );
+
+            restore_extra_config(client).unwrap();
+            assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+            assert!(
+                !get_extra_sync_status(client, Some(proxy)).is_synced(),
+                "{client:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_extra_client_as_str_roundtrip() {
         for client in ExtraClient::all() {
@@ -929,8 +1710,36 @@ mod tests {
         assert!(ExtraClient::CherryStudio.supports_file_sync());
         assert!(ExtraClient::Jan.supports_file_sync());
         assert!(ExtraClient::SillyTavern.supports_file_sync());
-        assert!(!ExtraClient::BoltAI.supports_file_sync());
-        assert!(!ExtraClient::LobeChat.supports_file_sync());
+        assert_eq!(
+            ExtraClient::BoltAI.supports_file_sync(),
+            cfg!(target_os = "macos")
+        );
+        assert!(ExtraClient::LobeChat.supports_file_sync());
+        assert!(!ExtraClient::WarpTerminal.supports_file_sync());
+        assert!(!ExtraClient::AmazonQ.supports_file_sync());
+    }
+
+    #[test]
+    fn test_manual_clients_return_steps_not_errors() {
+        for client in ExtraClient::all() {
+            let steps = client.manual_steps();
+            if client.supports_file_sync() || *client == ExtraClient::ClaudeVSCode {
+                assert_eq!(steps, None, "{client:?}");
+                continue;
+            }
+            let steps = steps.unwrap();
+            assert!(steps.starts_with(client.display_name()), "{steps}");
+            assert_eq!(
+                sync_extra_config(
+                    client,
+                    "http://proxy",
+                    "sk-test",
+                    None,
+                    &ProviderAdvanced::default()
+                ),
+                Ok(SyncOutcome::ManualStepsRequired(steps))
+            );
+        }
     }
 
     #[test]
@@ -1031,16 +1840,7 @@ mod tests {
     #[test]
     fn test_jan_sync_fields() {
         // Jan engine config uses full_url (with /chat/completions) and api_key
-        let proxy_url = "https://proxy.test/v1";
-        let api_key = "sk-test";
-        let base = proxy_url.trim().trim_end_matches('/');
-        let base = base.trim_end_matches("/v1");
-        let full_url = format!("{base}/v1/chat/completions");
-
-        let config = serde_json::json!({
-            "full_url": full_url,
-            "api_key": api_key,
-        });
+        let config = build_jan_legacy_config("https://proxy.test/v1", "sk-test");
 
         assert_eq!(config["full_url"], "https://proxy.test/v1/chat/completions");
         assert_eq!(config["api_key"], "sk-test");
@@ -1048,6 +1848,101 @@ mod tests {
         assert!(config.get("hajimi_proxy_url").is_none());
     }
 
+    #[test]
+    fn test_pick_config_path_prefers_existing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let native = tmp.path().join(".config/Chatbox/config.json");
+        let flatpak = tmp
+            .path()
+            .join(".var/app/xyz.chatboxapp.app/config/Chatbox/config.json");
+
+        // Nothing exists: fall back to the native default
+        let picked = pick_config_path(vec![native.clone(), flatpak.clone()]);
+        assert_eq!(picked.as_ref(), Some(&native));
+
+        // Only the Flatpak dir exists: use it
+        fs::create_dir_all(flatpak.parent().unwrap()).unwrap();
+        let picked = pick_config_path(vec![native.clone(), flatpak.clone()]);
+        assert_eq!(picked.as_ref(), Some(&flatpak));
+
+        // An existing file wins over an existing directory
+        fs::create_dir_all(native.parent().unwrap()).unwrap();
+        fs::write(&flatpak, "{}").unwrap();
+        let picked = pick_config_path(vec![native, flatpak.clone()]);
+        assert_eq!(picked, Some(flatpak));
+    }
+
+    #[test]
+    fn test_compact_app_name() {
+        assert!(compact_app_name("com.cherryai.CherryStudio")
+            .contains(&compact_app_name("Cherry Studio")));
+        assert!(compact_app_name("xyz.chatboxapp.app").contains(&compact_app_name("Chatbox")));
+    }
+
+    #[test]
+    fn test_version_from_extension_dir() {
+        assert_eq!(
+            version_from_extension_dir("saoudrizwan.claude-dev-3.13.1", "saoudrizwan.claude-dev-"),
+            Some("3.13.1".to_string())
+        );
+        assert_eq!(
+            version_from_extension_dir(
+                "anthropic.claude-code-1.0.31-darwin-arm64",
+                "anthropic.claude-code-"
+            ),
+            Some("1.0.31".to_string())
+        );
+        assert_eq!(
+            version_from_extension_dir("kilocode.kilo-code-nightly", "kilocode.kilo-code-"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_extension_package_version() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("package.json"),
+            r#"{"name":"claude-dev","version":"3.17.4"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            read_extension_package_version(tmp.path()),
+            Some("3.17.4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_jan_provider_settings_format() {
+        let config =
+            build_jan_provider_settings("https://proxy.test/", "sk-test", Some("claude-sonnet-4"));
+
+        // New layout stores the base URL without /chat/completions
+        assert_eq!(config["base_url"], "https://proxy.test/v1");
+        assert_eq!(config["api_key"], "sk-test");
+        assert_eq!(config["models"][0]["id"], "claude-sonnet-4");
+        assert!(config.get("full_url").is_none());
+    }
+
+    #[test]
+    fn test_check_jan_synced_both_layouts() {
+        let legacy = r#"{"full_url":"https://proxy.test/v1/chat/completions","api_key":"k"}"#;
+        let status = check_jan_synced("openai.json", legacy, Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(true));
+        assert_eq!(
+            status.detected_url.as_deref(),
+            Some("https://proxy.test/v1")
+        );
+
+        let provider = r#"{"base_url":"https://proxy.test/v1","api_key":"k","models":[]}"#;
+        let status = check_jan_synced("settings.json", provider, Some("https://proxy.test/"));
+        assert_eq!(status.synced, Some(true));
+        assert_eq!(status.name, "settings.json");
+
+        let status = check_jan_synced("settings.json", provider, Some("https://other.test"));
+        assert_eq!(status.synced, Some(false));
+    }
+
     #[test]
     fn test_vscode_env_sync() {
         let mut config = serde_json::json!({
@@ -1087,26 +1982,113 @@ mod tests {
         })
         .to_string();
 
-        let (synced, _, url) = check_chatbox_synced(&content, "https://proxy.test", false);
-        assert!(synced);
-        assert_eq!(url, Some("https://proxy.test".to_string()));
+        let status = check_chatbox_synced("config.json", &content, Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(true));
+        assert_eq!(status.detected_url, Some("https://proxy.test".to_string()));
 
-        let (not_synced, _, _) = check_chatbox_synced(&content, "https://other.url", false);
-        assert!(!not_synced);
+        let status = check_chatbox_synced("config.json", &content, Some("https://other.url"));
+        assert_eq!(status.synced, Some(false));
     }
 
     #[test]
     fn test_check_chatbox_not_synced_empty() {
-        let (synced, _, url) = check_chatbox_synced("{}", "https://proxy.test", false);
-        assert!(!synced);
-        assert!(url.is_none());
+        let status = check_chatbox_synced("config.json", "{}", Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(false));
+        assert!(status.detected_url.is_none());
+        // An empty config gets the 1.9+ layout
+        assert_eq!(
+            status.note.as_deref(),
+            Some("settings.providers[hajimi].apiHost not set")
+        );
+        let legacy = r#"{"openaiApiKey": "sk-test"}"#;
+        let status = check_chatbox_synced("config.json", legacy, Some("https://proxy.test"));
+        assert_eq!(status.note.as_deref(), Some("openaiApiHost not set"));
+    }
+
+    #[test]
+    fn test_chatbox_schema_and_providers_layout() {
+        let empty = serde_json::json!({});
+        assert_eq!(chatbox_schema(&empty, None), ChatboxSchema::Providers);
+        assert_eq!(chatbox_schema(&empty, Some("1.8.2")), ChatboxSchema::Legacy);
+        assert_eq!(
+            chatbox_schema(&empty, Some("1.10.0")),
+            ChatboxSchema::Providers
+        );
+        // What the config already uses beats the version
+        let legacy = serde_json::json!({ "openaiApiHost": "https://old.url" });
+        assert_eq!(
+            chatbox_schema(&legacy, Some("1.12.0")),
+            ChatboxSchema::Legacy
+        );
+
+        let mut config = serde_json::json!({
+            "settings": {
+                "theme": "dark",
+                "providers": [
+                    { "id": HAJIMI_MARKER, "apiHost": "https://old.proxy" },
+                    { "id": "openai", "apiKey": "sk-own" }
+                ]
+            }
+        });
+        let schema = chatbox_schema(&config, Some("1.8.0"));
+        assert_eq!(schema, ChatboxSchema::Providers);
+        apply_chatbox_config(
+            &mut config,
+            schema,
+            "https://proxy.test",
+            "sk-test",
+            Some("gpt-4o"),
+        )
+        .unwrap();
+        let providers = config["settings"]["providers"].as_array().unwrap();
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0]["apiHost"], "https://proxy.test");
+        assert_eq!(providers[1]["apiKey"], "sk-own");
+        assert_eq!(config["settings"]["theme"], "dark");
+        assert!(config.get("openaiApiHost").is_none());
+
+        let content = config.to_string();
+        let status = check_chatbox_synced("config.json", &content, Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(true));
+        let d = detect_chatbox(&content).unwrap();
+        assert_eq!(d.api_key, "sk-test");
+        assert_eq!(d.model.as_deref(), Some("gpt-4o"));
+
+        let mut fresh = serde_json::json!({});
+        apply_chatbox_config(
+            &mut fresh,
+            ChatboxSchema::Providers,
+            "https://proxy.test",
+            "sk-test",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            fresh["settings"]["providers"][0]["id"],
+            Value::from(HAJIMI_MARKER)
+        );
     }
 
     #[test]
-    fn test_backup_path_for() {
+    fn test_detect_chatbox() {
+        let content = serde_json::json!({
+            "openaiApiHost": "https://proxy.test",
+            "openaiApiKey": "sk-test",
+            "chatgptModel": "gpt-4o"
+        })
+        .to_string();
+        let d = detect_chatbox(&content).unwrap();
+        assert_eq!(d.app, "chatbox");
+        assert_eq!(d.url, "https://proxy.test");
+        assert_eq!(d.model.as_deref(), Some("gpt-4o"));
+        assert!(detect_chatbox(r#"{"openaiApiHost": "https://proxy.test"}"#).is_none());
+    }
+
+    #[test]
+    fn test_backup_path() {
         let p = PathBuf::from("/tmp/test/config.json");
-        let bp = backup_path_for(&p);
-        assert_eq!(bp, PathBuf::from("/tmp/test/config.json.antigravity.bak"));
+        let bp = utils::backup_path(&p);
+        assert_eq!(bp, PathBuf::from("/tmp/test/config.json.hajimi.bak"));
     }
 
     #[test]
@@ -1125,7 +2107,7 @@ mod tests {
 
     #[test]
     fn test_all_clients_count() {
-        assert_eq!(ExtraClient::all().len(), 11);
+        assert_eq!(ExtraClient::all().len(), 13);
     }
 
     #[test]
@@ -1136,7 +2118,11 @@ mod tests {
         );
         assert_eq!(
             ExtraClient::BoltAI.config_files_display(),
-            vec!["(macOS Keychain)"]
+            if cfg!(target_os = "macos") {
+                vec![BOLTAI_SERVICES_FILE]
+            } else {
+                vec!["(macOS Keychain)"]
+            }
         );
         assert_eq!(
             ExtraClient::Cline.config_files_display(),
@@ -1164,4 +2150,77 @@ mod tests {
         assert_eq!(secrets["api_key_openai"], "sk-test");
         assert_eq!(secrets["api_url_scale"], "https://proxy.test");
     }
+
+    #[test]
+    fn test_lobechat_settings_keep_other_providers() {
+        let mut config = serde_json::json!({
+            "keyVaults": { "anthropic": { "apiKey": "sk-ant" } },
+            "languageModel": { "openai": { "enabledModels": ["gpt-4o"] } },
+            "general": { "fontSize": 14 }
+        });
+        apply_lobechat_config(&mut config, "https://proxy.test/", "sk-1", Some("gpt-4o")).unwrap();
+        apply_lobechat_config(&mut config, "https://proxy.test", "sk-2", Some("o3")).unwrap();
+        assert_eq!(
+            config["keyVaults"]["openai"]["baseURL"],
+            "https://proxy.test/v1"
+        );
+        assert_eq!(config["keyVaults"]["openai"]["apiKey"], "sk-2");
+        assert_eq!(config["keyVaults"]["anthropic"]["apiKey"], "sk-ant");
+        assert_eq!(
+            config["languageModel"]["openai"]["enabledModels"],
+            serde_json::json!(["gpt-4o", "o3"])
+        );
+        assert_eq!(config["general"]["fontSize"], 14);
+
+        let status = check_lobechat_synced(
+            "settings.json",
+            &config.to_string(),
+            Some("https://proxy.test"),
+        );
+        assert_eq!(status.synced, Some(true));
+        assert!(
+            apply_lobechat_config(&mut serde_json::json!({"keyVaults": []}), "u", "k", None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_boltai_service_entry() {
+        let mut config = serde_json::json!({
+            "services": [
+                { "id": "other", "baseURL": "https://other.test/v1" },
+                { "id": "hajimi", "baseURL": "https://old.test/v1", "models": ["gpt-4o"] }
+            ]
+        });
+        apply_boltai_service(&mut config, "https://proxy.test", Some("o3")).unwrap();
+        let services = config["services"].as_array().unwrap();
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0]["id"], "other");
+        assert_eq!(services[1]["baseURL"], "https://proxy.test/v1");
+        assert_eq!(services[1]["models"], serde_json::json!(["gpt-4o"]));
+        assert_eq!(services[1]["defaultModel"], "o3");
+        assert_eq!(services[1]["apiKeyKeychain"]["account"], "hajimi");
+        assert!(!config.to_string().contains("apiKey\""));
+
+        let content = config.to_string();
+        let status =
+            check_boltai_synced("custom-services.json", &content, Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(true));
+        let status = check_boltai_synced("custom-services.json", "{}", Some("https://proxy.test"));
+        assert_eq!(status.synced, Some(false));
+        assert_eq!(
+            status.note.as_deref(),
+            Some("services[hajimi].baseURL not set")
+        );
+    }
+
+    #[test]
+    fn test_lobechat_version_gate() {
+        assert_eq!(lobechat_unsupported(None), None);
+        assert_eq!(lobechat_unsupported(Some("1.92.3")), None);
+        assert_eq!(lobechat_unsupported(Some("nightly")), None);
+        let steps = lobechat_unsupported(Some("2.0.0-beta.3")).unwrap();
+        assert!(steps.starts_with("LobeChat 2.0.0-beta.3"), "{steps}");
+        assert!(lobechat_unsupported(Some("2.1.0")).is_some());
+    }
 }
@@ -0,0 +1,156 @@
+//! Per-file sync state, so a multi-file app (Codex's auth.json + config.toml)
+//! can show which file is out of date instead of one collapsed flag.
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileStatus {
+    pub name: String,
+    pub exists: bool,
+    /// `None` for files that carry no proxy settings (or optional files that
+    /// don't exist yet), so they never count against the app.
+    pub synced: Option<bool>,
+    pub detected_url: Option<String>,
+    pub note: Option<String>,
+}
+
+impl FileStatus {
+    pub fn new(name: &str, exists: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            exists,
+            synced: None,
+            detected_url: None,
+            note: None,
+        }
+    }
+
+    /// A file sync writes that isn't there.
+    pub fn missing(name: &str) -> Self {
+        Self::new(name, false).with_synced(false)
+    }
+
+    /// An optional file that isn't there; doesn't affect the app's status.
+    pub fn optional_missing(name: &str) -> Self {
+        Self::new(name, false)
+    }
+
+    pub fn with_synced(mut self, synced: bool) -> Self {
+        self.synced = Some(synced);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Synced when `url` matches `proxy_url`; a missing URL is reported as
+    /// "`what` not set". `strict` compares with `urls_equal` instead of
+    /// `urls_match` (which tolerates a `/v1` difference). Without a
+    /// `proxy_url` only the detected URL is recorded and `synced` is left unset.
+    pub fn with_url(
+        mut self,
+        url: Option<String>,
+        proxy_url: Option<&str>,
+        what: &str,
+        strict: bool,
+    ) -> Self {
+        match url {
+            Some(u) => {
+                self.synced = proxy_url.map(|proxy_url| {
+                    if strict {
+                        utils::urls_equal(&u, proxy_url)
+                    } else {
+                        utils::urls_match(&u, proxy_url)
+                    }
+                });
+                self.detected_url = Some(u);
+            }
+            None => {
+                self.synced = proxy_url.map(|_| false);
+                self.note = Some(format!("{what} not set"));
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStatus {
+    pub files: Vec<FileStatus>,
+    pub has_backup: bool,
+}
+
+impl SyncStatus {
+    pub fn new(files: Vec<FileStatus>, has_backup: bool) -> Self {
+        Self { files, has_backup }
+    }
+
+    /// At least one file was checked and none is out of date.
+    pub fn is_synced(&self) -> bool {
+        self.files.iter().any(|f| f.synced.is_some())
+            && self.files.iter().all(|f| f.synced != Some(false))
+    }
+
+    /// URL from the last file that has one; apps list the file they actually
+    /// read the endpoint from last (Codex: config.toml after auth.json).
+    pub fn current_base_url(&self) -> Option<String> {
+        self.files.iter().rev().find_map(|f| f.detected_url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_level_flags_derive_from_files() {
+        let proxy = Some("https://proxy.test/v1");
+        let auth = FileStatus::new("auth.json", true).with_url(
+            Some("https://proxy.test/v1".into()),
+            proxy,
+            "OPENAI_BASE_URL",
+            true,
+        );
+        let config = FileStatus::new("config.toml", true).with_url(
+            Some("https://other.test/v1".into()),
+            proxy,
+            "base_url",
+            true,
+        );
+        let status = SyncStatus::new(vec![auth.clone(), config], false);
+        assert!(!status.is_synced());
+        assert_eq!(
+            status.current_base_url().as_deref(),
+            Some("https://other.test/v1")
+        );
+
+        let status = SyncStatus::new(
+            vec![auth, FileStatus::optional_missing("settings.json")],
+            false,
+        );
+        assert!(status.is_synced());
+        assert!(!SyncStatus::default().is_synced());
+        assert!(!SyncStatus::new(vec![FileStatus::missing("a.json")], true).is_synced());
+    }
+
+    #[test]
+    fn test_without_proxy_url_only_detects() {
+        let found = FileStatus::new("settings.json", true).with_url(
+            Some("https://other.test".into()),
+            None,
+            "ANTHROPIC_BASE_URL",
+            true,
+        );
+        assert_eq!(found.synced, None);
+        assert_eq!(found.detected_url.as_deref(), Some("https://other.test"));
+
+        let unset =
+            FileStatus::new("settings.json", true).with_url(None, None, "ANTHROPIC_BASE_URL", true);
+        assert_eq!(unset.synced, None);
+        assert_eq!(unset.note.as_deref(), Some("ANTHROPIC_BASE_URL not set"));
+    }
+}
@@ -0,0 +1,419 @@
+//! Headless entry point behind the `hajimi-sync` binary.
+//!
+//! Opens the same SQLite database as the GUI and calls the sync modules
+//! directly, so a scripted switch writes exactly what the app would. Every
+//! command prints one JSON document to stdout; failures print
+//! `{"error": CommandError}` and exit nonzero. Logs go to stderr.
+
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::database::{dao::providers, Database};
+use crate::error::CommandError;
+use crate::sync_lock::{self, SyncLock};
+use crate::sync_target::SyncOutcome;
+use crate::{cli_sync, config_paths, system_check, wsl, SyncResult};
+
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+/// Read when `sync` is called without `--key`, so keys stay out of `ps` output.
+const API_KEY_ENV: &str = "HAJIMI_API_KEY";
+
+const USAGE: &str = "\
+Usage: hajimi-sync <command> [options]
+
+Commands:
+  status [--url <url>]                     Sync status of every known app
+  sync --app <name> --url <url> [--key <key>] [--model <model>]
+                                           Sync one app (key defaults to $HAJIMI_API_KEY)
+  switch --provider <name|id>              Switch to a saved provider and sync installed apps
+  restore --app <name>                     Restore an app's config from its backup
+  providers list                           List saved providers (without API keys)
+  providers export                         Print saved providers including API keys
+
+Exit codes: 0 success, 1 operation failed, 2 usage error";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Status {
+        url: Option<String>,
+    },
+    Sync {
+        app: String,
+        url: String,
+        key: String,
+        model: Option<String>,
+    },
+    Switch {
+        provider: String,
+    },
+    Restore {
+        app: String,
+    },
+    ProvidersList,
+    ProvidersExport,
+    Help,
+}
+
+/// Provider as shown by `providers list`.
+#[derive(Debug, Serialize)]
+struct ProviderSummary {
+    id: String,
+    name: String,
+    url: String,
+    default_model: String,
+    is_current: bool,
+}
+
+/// Run the headless CLI and return the process exit code.
+pub fn run(args: impl IntoIterator<Item = String>) -> i32 {
+    let args: Vec<String> = args.into_iter().collect();
+    let command = match parse_args(&args, std::env::var(API_KEY_ENV).ok()) {
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    init_logging();
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(e) => return fail(CommandError::new("RUNTIME", e.to_string())),
+    };
+
+    match runtime.block_on(execute(command)) {
+        Ok((output, success)) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).unwrap_or_default()
+            );
+            if success {
+                0
+            } else {
+                EXIT_FAILURE
+            }
+        }
+        Err(e) => fail(e),
+    }
+}
+
+fn fail(error: CommandError) -> i32 {
+    let output = json!({ "error": error });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).unwrap_or_default()
+    );
+    EXIT_FAILURE
+}
+
+/// stdout is reserved for JSON, so logs go to stderr at `warn` unless RUST_LOG says otherwise.
+fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .try_init();
+}
+
+fn open_db() -> Result<Database, CommandError> {
+    let path = Database::default_path()
+        .ok_or_else(|| CommandError::new("DB_OPEN_FAILED", "Cannot determine data dir"))?;
+    let (db, recovery) =
+        Database::init(&path).map_err(|e| CommandError::new("DB_OPEN_FAILED", e))?;
+    if let Some(recovery) = recovery {
+        eprintln!("warning: {}", recovery.summary());
+    }
+    if let Err(e) = config_paths::load(&db) {
+        tracing::warn!("[headless] Failed to load config path overrides: {}", e);
+    }
+    cli_sync::load_codex_profile_mode(&db);
+    cli_sync::load_gemini_force_auth(&db);
+    system_check::load_http_proxy(&db);
+    system_check::load_http_tls(&db);
+    wsl::load(&db);
+    Ok(db)
+}
+
+/// Returns the JSON to print and whether the command fully succeeded.
+async fn execute(command: Command) -> Result<(serde_json::Value, bool), CommandError> {
+    let db = Arc::new(open_db()?);
+    let lock = SyncLock::new(sync_lock::default_path());
+    match command {
+        Command::Status { url } => {
+            let current = providers::get_current(&db)?;
+            // Without a provider the apps' configured URLs are still listed
+            let url = url.or_else(|| current.as_ref().map(|p| p.url.clone()));
+            let adapter = current.map(|p| p.opencode_adapter);
+            let statuses = crate::collect_cli_status(db.clone(), None, url, adapter).await?;
+            Ok((to_value(&statuses)?, true))
+        }
+        Command::Sync {
+            app,
+            url,
+            key,
+            model,
+        } => {
+            let _lock = lock.acquire().await?;
+            let outcome =
+                crate::sync_one(&db, &app, &url, &key, model.as_deref(), None, None, None).await?;
+            let result = SyncResult::from_outcome(&app, Ok(outcome));
+            Ok((to_value(&result)?, true))
+        }
+        Command::Switch { provider } => {
+            let target = find_provider(&db, &provider)?;
+            let _lock = lock.acquire().await?;
+            let result = crate::sync_provider_to_installed(&db, &target).await?;
+            providers::set_current(&db, &target.id)?;
+            crate::rotation::record_switch(&db);
+            let success = result.success;
+            Ok((to_value(&result)?, success))
+        }
+        Command::Restore { app } => {
+            let _lock = lock.acquire().await?;
+            crate::restore_app(&db, &app)?;
+            let result = SyncResult::from_outcome(&app, Ok(SyncOutcome::Synced(None)));
+            Ok((to_value(&result)?, true))
+        }
+        Command::ProvidersList => {
+            let list: Vec<ProviderSummary> = providers::get_all(&db, false)?
+                .into_iter()
+                .map(|p| ProviderSummary {
+                    id: p.id,
+                    name: p.name,
+                    url: p.url,
+                    default_model: p.default_model,
+                    is_current: p.is_current,
+                })
+                .collect();
+            Ok((to_value(&list)?, true))
+        }
+        Command::ProvidersExport => Ok((to_value(&providers::get_all(&db, true)?)?, true)),
+        Command::Help => Ok((json!(USAGE), true)),
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<serde_json::Value, CommandError> {
+    serde_json::to_value(value).map_err(|e| CommandError::new("SERIALIZE_FAILED", e.to_string()))
+}
+
+/// Look a provider up by id, then by name (case-insensitive). Ambiguous names are an error.
+fn find_provider(db: &Database, needle: &str) -> Result<providers::ProviderRecord, CommandError> {
+    let all = providers::get_all(db, false)?;
+    if let Some(p) = all.iter().find(|p| p.id == needle) {
+        return Ok(p.clone());
+    }
+    let mut matches: Vec<_> = all
+        .into_iter()
+        .filter(|p| p.name.trim().eq_ignore_ascii_case(needle.trim()))
+        .collect();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(CommandError::new(
+            "PROVIDER_NOT_FOUND",
+            format!("Provider not found: {needle}"),
+        )
+        .with_details(json!({ "provider": needle }))),
+        _ => Err(CommandError::invalid_input(format!(
+            "Several providers are named {needle}; pass the id instead"
+        ))
+        .with_details(json!({ "ids": matches.iter().map(|p| &p.id).collect::<Vec<_>>() }))),
+    }
+}
+
+fn parse_args(args: &[String], env_key: Option<String>) -> Result<Command, String> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+    match name.as_str() {
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        "status" => {
+            let mut flags = Flags::parse(rest, &["url"])?;
+            Ok(Command::Status {
+                url: flags.take("url"),
+            })
+        }
+        "sync" => {
+            let mut flags = Flags::parse(rest, &["app", "url", "key", "model"])?;
+            let key = flags
+                .take("key")
+                .or(env_key.filter(|k| !k.trim().is_empty()))
+                .ok_or(format!("sync needs --key or ${API_KEY_ENV}"))?;
+            Ok(Command::Sync {
+                app: flags.require("app")?,
+                url: flags.require("url")?,
+                key,
+                model: flags.take("model"),
+            })
+        }
+        "switch" => {
+            let mut flags = Flags::parse(rest, &["provider"])?;
+            Ok(Command::Switch {
+                provider: flags.require("provider")?,
+            })
+        }
+        "restore" => {
+            let mut flags = Flags::parse(rest, &["app"])?;
+            Ok(Command::Restore {
+                app: flags.require("app")?,
+            })
+        }
+        "providers" => match rest {
+            [sub] if sub == "list" => Ok(Command::ProvidersList),
+            [sub] if sub == "export" => Ok(Command::ProvidersExport),
+            _ => Err("providers needs `list` or `export`".to_string()),
+        },
+        other => Err(format!("Unknown command: {other}")),
+    }
+}
+
+/// `--name value` / `--name=value` options of a single subcommand.
+struct Flags(Vec<(String, String)>);
+
+impl Flags {
+    fn parse(args: &[String], allowed: &[&str]) -> Result<Self, String> {
+        let mut out: Vec<(String, String)> = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(format!("Unexpected argument: {arg}"));
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None => (
+                    flag,
+                    iter.next()
+                        .cloned()
+                        .ok_or_else(|| format!("--{flag} needs a value"))?,
+                ),
+            };
+            if !allowed.contains(&name) {
+                return Err(format!("Unknown option: --{name}"));
+            }
+            if out.iter().any(|(n, _)| n == name) {
+                return Err(format!("--{name} given twice"));
+            }
+            out.push((name.to_string(), value));
+        }
+        Ok(Self(out))
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        let idx = self.0.iter().position(|(n, _)| n == name)?;
+        Some(self.0.remove(idx).1).filter(|v| !v.trim().is_empty())
+    }
+
+    fn require(&mut self, name: &str) -> Result<String, String> {
+        self.take(name)
+            .ok_or_else(|| format!("--{name} is required"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_args(&[], None), Ok(Command::Help));
+        assert_eq!(
+            parse_args(&args("status --url=https://x.com"), None),
+            Ok(Command::Status {
+                url: Some("https://x.com".to_string())
+            })
+        );
+        assert_eq!(
+            parse_args(
+                &args("sync --app codex --url https://x.com --model gpt-5"),
+                Some("sk-env".into())
+            ),
+            Ok(Command::Sync {
+                app: "codex".to_string(),
+                url: "https://x.com".to_string(),
+                key: "sk-env".to_string(),
+                model: Some("gpt-5".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_args(&args("switch --provider Work"), None),
+            Ok(Command::Switch {
+                provider: "Work".to_string()
+            })
+        );
+        assert_eq!(
+            parse_args(&args("providers export"), None),
+            Ok(Command::ProvidersExport)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_usage() {
+        let bad = [
+            "frobnicate",
+            "sync --app codex --url https://x.com",
+            "sync --app codex --url https://x.com --key k --key k",
+            "restore",
+            "restore claude",
+            "status --verbose",
+            "switch --provider",
+            "providers",
+        ];
+        for raw in bad {
+            assert!(parse_args(&args(raw), None).is_err(), "accepted: {raw}");
+        }
+    }
+
+    #[test]
+    fn test_find_provider_by_id_or_name() {
+        let db = Database::memory().unwrap();
+        for (id, name) in [("p1", "Work"), ("p2", "Home"), ("p3", "home")] {
+            let record = providers::ProviderRecord {
+                id: id.to_string(),
+                name: name.to_string(),
+                url: "https://x.com".to_string(),
+                api_key: "k".to_string(),
+                default_model: String::new(),
+                per_cli_models: "{}".to_string(),
+                is_current: false,
+                sort_index: None,
+                notes: None,
+                created_at: 0,
+                opencode_adapter: String::new(),
+                account_id: None,
+                model_aliases: "{}".to_string(),
+                account_base_url: None,
+                token_id: None,
+                quota: None,
+                archived: false,
+                advanced: "{}".to_string(),
+            };
+            providers::save(&db, &record).unwrap();
+        }
+
+        assert_eq!(find_provider(&db, "p2").unwrap().name, "Home");
+        assert_eq!(find_provider(&db, "work").unwrap().id, "p1");
+        assert_eq!(
+            find_provider(&db, "home").unwrap_err().code,
+            "INVALID_INPUT"
+        );
+        assert_eq!(
+            find_provider(&db, "nope").unwrap_err().code,
+            "PROVIDER_NOT_FOUND"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Background health check of the current provider.
+//!
+//! Off by default. With an interval set, the current provider is checked with
+//! the same `/v1/models` request as the "Test" button, the last result is kept
+//! in `AppState`, and `EVENT_HEALTH_CHANGED` is emitted only when the status
+//! (or the provider being checked) changes. Consecutive failures back off
+//! exponentially; with no current provider the poller waits until woken.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::Notify;
+
+use crate::connection_test;
+use crate::database::dao::{providers, settings};
+use crate::database::Database;
+use crate::store::AppState;
+
+pub const EVENT_HEALTH_CHANGED: &str = "health://changed";
+const INTERVAL_KEY: &str = "health_poll_interval_secs";
+const MIN_INTERVAL_SECS: u64 = 30;
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+/// Responses slower than this count as degraded.
+const SLOW_MS: u64 = 3000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Slow,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+    pub provider_id: String,
+    pub status: HealthStatus,
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    /// Unix seconds.
+    pub checked_at: i64,
+    pub consecutive_failures: u32,
+}
+
+impl HealthSnapshot {
+    fn from_check(
+        provider_id: &str,
+        result: Result<u64, String>,
+        previous: Option<&HealthSnapshot>,
+    ) -> Self {
+        let (status, latency_ms, error) = match result {
+            Ok(ms) if ms > SLOW_MS => (HealthStatus::Slow, Some(ms), None),
+            Ok(ms) => (HealthStatus::Healthy, Some(ms), None),
+            Err(e) => (HealthStatus::Down, None, Some(e)),
+        };
+        let consecutive_failures = match previous {
+            Some(prev) if error.is_some() && prev.provider_id == provider_id => {
+                prev.consecutive_failures + 1
+            }
+            _ if error.is_some() => 1,
+            _ => 0,
+        };
+        Self {
+            provider_id: provider_id.to_string(),
+            status,
+            ok: error.is_none(),
+            latency_ms,
+            error,
+            checked_at: chrono::Utc::now().timestamp(),
+            consecutive_failures,
+        }
+    }
+}
+
+/// Latest snapshot plus a wake-up signal for the poller.
+#[derive(Default)]
+pub struct HealthMonitor {
+    last: Mutex<Option<HealthSnapshot>>,
+    wake: Notify,
+}
+
+impl HealthMonitor {
+    pub fn snapshot(&self) -> Option<HealthSnapshot> {
+        self.last.lock().ok().and_then(|last| last.clone())
+    }
+
+    /// Re-check now, e.g. after the current provider or the interval changed.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Store `next`; returns whether the status or the provider changed.
+    fn record(&self, next: Option<HealthSnapshot>) -> bool {
+        let Ok(mut last) = self.last.lock() else {
+            return false;
+        };
+        let key =
+            |s: &Option<HealthSnapshot>| s.as_ref().map(|s| (s.provider_id.clone(), s.status));
+        let changed = key(&last) != key(&next);
+        *last = next;
+        changed
+    }
+}
+
+/// Poll interval from settings; 0 means off (the default).
+pub fn interval_secs(db: &Database) -> u64 {
+    settings::get(db, INTERVAL_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn set_interval_secs(db: &Database, secs: u64) -> Result<(), String> {
+    if secs != 0 && secs < MIN_INTERVAL_SECS {
+        return Err(format!(
+            "Health check interval must be 0 (off) or at least {MIN_INTERVAL_SECS} seconds"
+        ));
+    }
+    settings::set(db, INTERVAL_KEY, &secs.to_string())
+}
+
+/// Delay before the next check: the interval, doubled per consecutive failure
+/// up to `MAX_BACKOFF` (never shorter than the interval itself).
+fn next_delay(interval: Duration, failures: u32) -> Duration {
+    let factor = 1u32 << failures.min(16);
+    interval
+        .saturating_mul(factor)
+        .min(MAX_BACKOFF.max(interval))
+}
+
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    tauri::async_runtime::spawn(run(app.clone()));
+}
+
+async fn run<R: Runtime>(app: AppHandle<R>) {
+    let state = app.state::<AppState>();
+    loop {
+        let interval = interval_secs(&state.db);
+        if interval == 0 {
+            publish(&app, &state.health, None);
+            state.health.wake.notified().await;
+            continue;
+        }
+        let interval = Duration::from_secs(interval);
+
+        let current = providers::get_current(&state.db).unwrap_or_else(|e| {
+            tracing::warn!("[health] Cannot read current provider: {}", e);
+            None
+        });
+        // A key reference is only read once a sync resolved it, so background
+        // checks never trigger a 1Password or keychain prompt
+        let current = current.and_then(|p| crate::secrets::cached(&p.api_key).map(|key| (p, key)));
+        let delay = match current {
+            // Nothing to check; a provider switch wakes us
+            None => {
+                publish(&app, &state.health, None);
+                interval
+            }
+            Some((provider, api_key)) => {
+                let started = Instant::now();
+                let result = connection_test::check_models_endpoint(&provider.url, &api_key)
+                    .await
+                    .map(|()| started.elapsed().as_millis() as u64);
+                let snapshot = HealthSnapshot::from_check(
+                    &provider.id,
+                    result,
+                    state.health.snapshot().as_ref(),
+                );
+                let delay = next_delay(interval, snapshot.consecutive_failures);
+                if let Some(e) = &snapshot.error {
+                    tracing::debug!(
+                        "[health] {} check failed ({} in a row): {}",
+                        provider.name,
+                        snapshot.consecutive_failures,
+                        crate::logging::redact(e)
+                    );
+                }
+                publish(&app, &state.health, Some(snapshot));
+                delay
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = state.health.wake.notified() => {}
+        }
+    }
+}
+
+fn publish<R: Runtime>(app: &AppHandle<R>, monitor: &HealthMonitor, next: Option<HealthSnapshot>) {
+    if !monitor.record(next.clone()) {
+        return;
+    }
+    if let Err(e) = app.emit(EVENT_HEALTH_CHANGED, next) {
+        tracing::warn!("[health] Failed to emit health change: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_setting() {
+        let db = Database::memory().unwrap();
+        assert_eq!(interval_secs(&db), 0);
+        assert!(set_interval_secs(&db, 5).is_err());
+        set_interval_secs(&db, 60).unwrap();
+        assert_eq!(interval_secs(&db), 60);
+        set_interval_secs(&db, 0).unwrap();
+        assert_eq!(interval_secs(&db), 0);
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let interval = Duration::from_secs(60);
+        assert_eq!(next_delay(interval, 0), interval);
+        assert_eq!(next_delay(interval, 1), Duration::from_secs(120));
+        assert_eq!(next_delay(interval, 3), Duration::from_secs(480));
+        assert_eq!(next_delay(interval, 40), MAX_BACKOFF);
+        // An interval above the cap is never shortened
+        let hourly = Duration::from_secs(3600);
+        assert_eq!(next_delay(hourly, 2), hourly);
+    }
+
+    #[test]
+    fn test_failures_count_per_provider() {
+        let down = HealthSnapshot::from_check("a", Err("timeout".into()), None);
+        assert_eq!(down.status, HealthStatus::Down);
+        assert_eq!(down.consecutive_failures, 1);
+        let again = HealthSnapshot::from_check("a", Err("timeout".into()), Some(&down));
+        assert_eq!(again.consecutive_failures, 2);
+        let other = HealthSnapshot::from_check("b", Err("timeout".into()), Some(&again));
+        assert_eq!(other.consecutive_failures, 1);
+        let slow = HealthSnapshot::from_check("a", Ok(SLOW_MS + 1), Some(&again));
+        assert_eq!(slow.status, HealthStatus::Slow);
+        assert_eq!(slow.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_record_reports_transitions_only() {
+        let monitor = HealthMonitor::default();
+        let ok = || Some(HealthSnapshot::from_check("a", Ok(100), None));
+        assert!(monitor.record(ok()));
+        assert!(!monitor.record(ok()));
+        assert!(monitor.record(Some(HealthSnapshot::from_check(
+            "a",
+            Err("down".into()),
+            None
+        ))));
+        assert!(monitor.record(None));
+        assert!(!monitor.record(None));
+        assert!(monitor.snapshot().is_none());
+    }
+}
@@ -0,0 +1,289 @@
+//! Just enough JSONC (JSON with comments) for the configs we touch.
+//!
+//! `strip` turns JSONC into plain JSON for parsing. `set_top_level` edits one
+//! top-level member in place so comments and layout elsewhere in the file
+//! survive; serializing the parsed value again would drop every comment.
+
+use serde_json::Value;
+
+/// `text` with `//` and `/* */` comments and trailing commas removed.
+pub fn strip(text: &str) -> String {
+    let b = blank_comments(text);
+    let mut out = Vec::with_capacity(b.len());
+    let mut in_str = false;
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if in_str {
+            out.push(c);
+            if c == b'\\' && i + 1 < b.len() {
+                out.push(b[i + 1]);
+                i += 1;
+            } else if c == b'"' {
+                in_str = false;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            b'"' => in_str = true,
+            b',' if matches!(next_significant(&b, i + 1), Some(b'}' | b']')) => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Set top-level `key` of the object in `text` to `value`: the old value's
+/// span is replaced, or the key is added as the first member. Everything else
+/// is kept byte for byte. `None` when `text` isn't an object we can follow.
+pub fn set_top_level(text: &str, key: &str, value: &Value) -> Option<String> {
+    let b = blank_comments(text);
+    let open = skip_ws(&b, 0);
+    if b.get(open) != Some(&b'{') {
+        return None;
+    }
+    let unit = crate::utils::detect_indent(text).unwrap_or("  ");
+
+    let mut first_member = None;
+    let mut i = open + 1;
+    loop {
+        i = skip_ws(&b, i);
+        match b.get(i)? {
+            b'}' => break,
+            b',' => {
+                i += 1;
+                continue;
+            }
+            b'"' => {}
+            _ => return None,
+        }
+        let key_start = i;
+        first_member.get_or_insert(key_start);
+        let key_end = end_of_string(&b, key_start)?;
+        let name: String = serde_json::from_str(&text[key_start..key_end]).ok()?;
+        i = skip_ws(&b, key_end);
+        if b.get(i) != Some(&b':') {
+            return None;
+        }
+        let value_start = skip_ws(&b, i + 1);
+        let value_end = end_of_value(&b, value_start)?;
+        if name == key {
+            let rendered = render(value, unit, line_indent(text, key_start));
+            return Some(format!(
+                "{}{}{}",
+                &text[..value_start],
+                rendered,
+                &text[value_end..]
+            ));
+        }
+        i = value_end;
+    }
+
+    let name = serde_json::to_string(key).ok()?;
+    Some(match first_member {
+        // Same line break and indent as the member that follows
+        Some(at) => {
+            let indent = line_indent(text, at);
+            let sep = if text[..at].ends_with(indent) && text[..at - indent.len()].ends_with('\n') {
+                format!("\n{indent}")
+            } else {
+                " ".to_string()
+            };
+            format!(
+                "{}{name}: {},{sep}{}",
+                &text[..at],
+                render(value, unit, indent),
+                &text[at..]
+            )
+        }
+        None => format!(
+            "{}\n{unit}{name}: {}\n{}",
+            &text[..open + 1],
+            render(value, unit, unit),
+            text[open + 1..].trim_start()
+        ),
+    })
+}
+
+/// Comment bytes replaced by spaces (line breaks kept), so offsets still
+/// line up with the original text.
+fn blank_comments(text: &str) -> Vec<u8> {
+    let mut b = text.as_bytes().to_vec();
+    let mut in_str = false;
+    let mut i = 0;
+    while i < b.len() {
+        if in_str {
+            match b[i] {
+                b'\\' => i += 1,
+                b'"' => in_str = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+        match (b[i], b.get(i + 1)) {
+            (b'"', _) => in_str = true,
+            (b'/', Some(b'/')) => {
+                while i < b.len() && b[i] != b'\n' {
+                    b[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            }
+            (b'/', Some(b'*')) => {
+                let end = b[i + 2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(b.len(), |p| i + 2 + p + 2);
+                for c in &mut b[i..end] {
+                    if *c != b'\n' {
+                        *c = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    b
+}
+
+fn skip_ws(b: &[u8], mut i: usize) -> usize {
+    while b.get(i).is_some_and(|c| c.is_ascii_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+fn next_significant(b: &[u8], i: usize) -> Option<u8> {
+    b.get(skip_ws(b, i)).copied()
+}
+
+/// Index just past the string literal starting at `start`.
+fn end_of_string(b: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < b.len() {
+        match b[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Index just past the value starting at `start`.
+fn end_of_value(b: &[u8], start: usize) -> Option<usize> {
+    match b.get(start)? {
+        b'"' => end_of_string(b, start),
+        b'{' | b'[' => {
+            let mut depth = 0usize;
+            let mut i = start;
+            while i < b.len() {
+                match b[i] {
+                    b'"' => {
+                        i = end_of_string(b, i)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            None
+        }
+        _ => {
+            let len = b[start..]
+                .iter()
+                .position(|c| matches!(c, b',' | b'}' | b']') || c.is_ascii_whitespace())
+                .unwrap_or(b.len() - start);
+            (len > 0).then_some(start + len)
+        }
+    }
+}
+
+/// Leading whitespace of the line containing `at`.
+fn line_indent(text: &str, at: usize) -> &str {
+    let line_start = text[..at].rfind('\n').map_or(0, |p| p + 1);
+    let line = &text[line_start..at];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Pretty value whose continuation lines sit under a member indented by `base`.
+fn render(value: &Value, unit: &str, base: &str) -> String {
+    use serde::Serialize;
+    if !(value.is_object() || value.is_array()) {
+        return value.to_string();
+    }
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    if value.serialize(&mut ser).is_err() {
+        return value.to_string();
+    }
+    String::from_utf8_lossy(&buf).replace('\n', &format!("\n{base}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SETTINGS: &str = r#"{
+    // Proxy for the team account
+    "openaiApiHost": "https://old.example.com", /* set by hand */
+    "theme": "dark",
+    "url": "https://not-a-comment.example.com//x",
+}
+"#;
+
+    #[test]
+    fn test_strip_comments_and_trailing_commas() {
+        let value: Value = serde_json::from_str(&strip(SETTINGS)).unwrap();
+        assert_eq!(value["openaiApiHost"], "https://old.example.com");
+        assert_eq!(value["url"], "https://not-a-comment.example.com//x");
+        let arrays: Value = serde_json::from_str(&strip("[1, 2, /* x */ ]")).unwrap();
+        assert_eq!(arrays, json!([1, 2]));
+    }
+
+    #[test]
+    fn test_set_top_level_replaces_in_place() {
+        let edited =
+            set_top_level(SETTINGS, "openaiApiHost", &json!("https://new.example.com")).unwrap();
+        assert_eq!(
+            edited,
+            SETTINGS.replace("https://old.example.com", "https://new.example.com")
+        );
+    }
+
+    #[test]
+    fn test_set_top_level_inserts_new_key() {
+        let edited = set_top_level(SETTINGS, "openaiApiKey", &json!({ "k": 1 })).unwrap();
+        assert!(edited.starts_with(
+            "{\n    // Proxy for the team account\n    \"openaiApiKey\": {\n        \"k\": 1\n    },\n    \"openaiApiHost\""
+        ));
+        let value: Value = serde_json::from_str(&strip(&edited)).unwrap();
+        assert_eq!(value["openaiApiKey"]["k"], 1);
+        assert_eq!(value["theme"], "dark");
+
+        assert_eq!(
+            set_top_level("{ /* empty */ }", "a", &json!(true)).unwrap(),
+            "{\n  \"a\": true\n/* empty */ }"
+        );
+        assert!(set_top_level("[1]", "a", &json!(1)).is_none());
+    }
+}
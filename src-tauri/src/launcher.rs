@@ -0,0 +1,370 @@
+//! Launching detected clients from the dashboard.
+//!
+//! Only apps in `LAUNCH_SPECS` can be launched, and every launch goes through
+//! fixed programs with the app's metadata as arguments — never a shell line
+//! built from user input.
+
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::json;
+
+use crate::error::CommandError;
+use crate::extra_clients::{self, ExtraClient};
+use crate::utils;
+
+/// How an app is started.
+#[derive(Debug, PartialEq)]
+enum LaunchKind {
+    /// A GUI app. `bundle_id` is for `open -b` on macOS; `name` is the macOS app
+    /// name, the Windows install folder and the Linux .desktop search term.
+    Desktop {
+        bundle_id: &'static str,
+        name: &'static str,
+        windows_exe: &'static str,
+        linux_bins: &'static [&'static str],
+    },
+    /// A CLI, run in a new terminal window.
+    Terminal { command: &'static str },
+    /// SillyTavern's start script, run in a terminal inside its folder.
+    SillyTavern,
+}
+
+const VSCODE: LaunchKind = LaunchKind::Desktop {
+    bundle_id: "com.microsoft.VSCode",
+    name: "Microsoft VS Code",
+    windows_exe: "Code.exe",
+    linux_bins: &["code"],
+};
+
+/// App id → launch metadata, covering the core CLIs and every `ExtraClient`.
+const LAUNCH_SPECS: &[(&str, LaunchKind)] = &[
+    ("claude", LaunchKind::Terminal { command: "claude" }),
+    ("codex", LaunchKind::Terminal { command: "codex" }),
+    ("gemini", LaunchKind::Terminal { command: "gemini" }),
+    (
+        "opencode",
+        LaunchKind::Terminal {
+            command: "opencode",
+        },
+    ),
+    (
+        "openclaw",
+        LaunchKind::Terminal {
+            command: "openclaw",
+        },
+    ),
+    ("droid", LaunchKind::Terminal { command: "droid" }),
+    ("claude-vscode", VSCODE),
+    ("cline", VSCODE),
+    ("roo-code", VSCODE),
+    ("kilo-code", VSCODE),
+    (
+        "chatbox",
+        LaunchKind::Desktop {
+            bundle_id: "xyz.chatboxapp.app",
+            name: "Chatbox",
+            windows_exe: "Chatbox.exe",
+            linux_bins: &["chatbox"],
+        },
+    ),
+    (
+        "cherry-studio",
+        LaunchKind::Desktop {
+            bundle_id: "com.kangfenmao.CherryStudio",
+            name: "Cherry Studio",
+            windows_exe: "Cherry Studio.exe",
+            linux_bins: &["cherry-studio", "cherrystudio"],
+        },
+    ),
+    (
+        "jan",
+        LaunchKind::Desktop {
+            bundle_id: "jan.ai.app",
+            name: "Jan",
+            windows_exe: "Jan.exe",
+            // The Linux packages install the binary lowercase
+            linux_bins: &["jan", "Jan"],
+        },
+    ),
+    (
+        "cursor",
+        LaunchKind::Desktop {
+            bundle_id: "com.todesktop.230313mzl4w4u92",
+            name: "Cursor",
+            windows_exe: "Cursor.exe",
+            linux_bins: &["cursor"],
+        },
+    ),
+    ("sillytavern", LaunchKind::SillyTavern),
+    (
+        "lobechat",
+        LaunchKind::Desktop {
+            bundle_id: "com.lobehub.lobehub-desktop",
+            name: "LobeChat",
+            windows_exe: "LobeChat.exe",
+            linux_bins: &["lobehub", "lobechat"],
+        },
+    ),
+    (
+        "boltai",
+        LaunchKind::Desktop {
+            bundle_id: "co.podzim.BoltGPT",
+            name: "BoltAI",
+            windows_exe: "BoltAI.exe",
+            linux_bins: &[],
+        },
+    ),
+    (
+        "warp",
+        LaunchKind::Desktop {
+            bundle_id: "dev.warp.Warp-Stable",
+            name: "Warp",
+            windows_exe: "Warp.exe",
+            linux_bins: &["warp-terminal"],
+        },
+    ),
+    ("amazon-q", LaunchKind::Terminal { command: "q" }),
+];
+
+fn launch_spec(app: &str) -> Option<&'static LaunchKind> {
+    LAUNCH_SPECS
+        .iter()
+        .find(|(id, _)| *id == app)
+        .map(|(_, kind)| kind)
+}
+
+/// Start `app` (an app id such as "chatbox" or "claude").
+pub fn launch(app: &str) -> Result<(), CommandError> {
+    let kind = launch_spec(app).ok_or_else(|| CommandError::unknown_app(app))?;
+    let result = match kind {
+        LaunchKind::Desktop {
+            bundle_id,
+            name,
+            windows_exe,
+            linux_bins,
+        } => launch_desktop(bundle_id, name, windows_exe, linux_bins),
+        LaunchKind::Terminal { command } => {
+            if utils::resolve_executable(command).is_none() {
+                return Err(not_installed(app));
+            }
+            open_terminal(command, None)
+        }
+        LaunchKind::SillyTavern => {
+            let dir = extra_clients::sillytavern_install_dir().ok_or_else(|| not_installed(app))?;
+            let script = if cfg!(windows) {
+                "Start.bat"
+            } else {
+                "./start.sh"
+            };
+            open_terminal(script, Some(&dir))
+        }
+    };
+    result.map_err(|reason| {
+        tracing::warn!("[launcher] Failed to launch {}: {}", app, reason);
+        CommandError::new("LAUNCH_FAILED", format!("Failed to launch {app}: {reason}"))
+            .with_details(json!({ "app": app }))
+    })
+}
+
+fn not_installed(app: &str) -> CommandError {
+    let name = ExtraClient::from_str(app)
+        .map(|c| c.display_name())
+        .unwrap_or(app);
+    CommandError::new("APP_NOT_INSTALLED", format!("{name} is not installed"))
+        .with_details(json!({ "app": app }))
+}
+
+/// Run a short-lived launcher command and require it to succeed.
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn run_ok(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{program} exited with {status}"))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn launch_desktop(
+    bundle_id: &str,
+    name: &str,
+    _windows_exe: &str,
+    _linux_bins: &[&str],
+) -> Result<(), String> {
+    // The bundle id survives renamed app bundles; the name covers builds
+    // with a different id (e.g. beta channels).
+    run_ok("open", &["-b", bundle_id]).or_else(|_| run_ok("open", &["-a", name]))
+}
+
+#[cfg(target_os = "windows")]
+fn launch_desktop(
+    _bundle_id: &str,
+    name: &str,
+    windows_exe: &str,
+    _linux_bins: &[&str],
+) -> Result<(), String> {
+    if let Some(exe) = extra_clients::windows_app_dir(name)
+        .map(|dir| dir.join(windows_exe))
+        .filter(|exe| exe.is_file())
+    {
+        return Command::new(&exe)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("{}: {e}", exe.display()));
+    }
+    // Fall back to Start Menu / App Paths resolution
+    Command::new("cmd")
+        .args(["/c", "start", "", name])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn launch_desktop(
+    _bundle_id: &str,
+    name: &str,
+    _windows_exe: &str,
+    linux_bins: &[&str],
+) -> Result<(), String> {
+    if let Some(entry) = extra_clients::linux_desktop_entry(name) {
+        let id = entry
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path = entry.to_string_lossy().to_string();
+        if run_ok("gtk-launch", &[&id])
+            .or_else(|_| run_ok("gio", &["launch", &path]))
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+    let bin = linux_bins
+        .iter()
+        .find_map(|bin| utils::resolve_executable(bin))
+        .ok_or_else(|| format!("no .desktop entry or executable found for {name}"))?;
+    Command::new(&bin)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("{}: {e}", bin.display()))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn launch_desktop(
+    _bundle_id: &str,
+    name: &str,
+    _windows_exe: &str,
+    _linux_bins: &[&str],
+) -> Result<(), String> {
+    Err(format!(
+        "launching {name} is not supported on this platform"
+    ))
+}
+
+/// Open a new terminal window running `command` (in `dir` if given).
+#[cfg(target_os = "macos")]
+fn open_terminal(command: &str, dir: Option<&Path>) -> Result<(), String> {
+    let line = match dir {
+        Some(dir) => format!("cd {} && {command}", shell_quote(&dir.to_string_lossy())),
+        None => command.to_string(),
+    };
+    let script = format!(
+        "tell application \"Terminal\"\n  activate\n  do script {}\nend tell",
+        applescript_string(&line)
+    );
+    run_ok("osascript", &["-e", &script])
+}
+
+#[cfg(target_os = "windows")]
+fn open_terminal(command: &str, dir: Option<&Path>) -> Result<(), String> {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/c", "start", ""]);
+    if let Some(dir) = dir {
+        cmd.arg("/D").arg(dir);
+    }
+    cmd.args(["cmd", "/k", command])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn open_terminal(command: &str, dir: Option<&Path>) -> Result<(), String> {
+    const TERMINALS: &[(&str, &str)] = &[
+        ("x-terminal-emulator", "-e"),
+        ("gnome-terminal", "--"),
+        ("konsole", "-e"),
+        ("xfce4-terminal", "-x"),
+        ("xterm", "-e"),
+    ];
+    let (terminal, flag) = TERMINALS
+        .iter()
+        .find(|(terminal, _)| utils::find_in_path(terminal).is_some())
+        .ok_or("no terminal emulator found")?;
+    let mut cmd = Command::new(terminal);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    cmd.args([*flag, command])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("{terminal}: {e}"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn open_terminal(command: &str, _dir: Option<&Path>) -> Result<(), String> {
+    Err(format!(
+        "opening a terminal for {command} is not supported on this platform"
+    ))
+}
+
+/// Single-quote for sh: `it's` → `'it'\''s'`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// AppleScript string literal.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_client_has_launch_spec() {
+        for client in ExtraClient::all() {
+            assert!(
+                launch_spec(client.as_str()).is_some(),
+                "{}",
+                client.as_str()
+            );
+        }
+        for app in ["claude", "codex", "gemini", "opencode", "openclaw", "droid"] {
+            assert!(launch_spec(app).is_some(), "{app}");
+        }
+    }
+
+    #[test]
+    fn test_launch_rejects_unknown_app() {
+        let err = launch("rm -rf /").unwrap_err();
+        assert_eq!(err.code, "UNKNOWN_APP");
+        // Display names are not ids
+        assert_eq!(launch("Cherry Studio").unwrap_err().code, "UNKNOWN_APP");
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(shell_quote("/Users/o'neil/ST"), r"'/Users/o'\''neil/ST'");
+        assert_eq!(applescript_string(r#"cd "a\b""#), r#""cd \"a\\b\"""#);
+    }
+}
@@ -1,24 +1,69 @@
 mod account;
+mod app_update;
 mod auto_installer;
+mod backup_files;
+mod bundle;
 mod cli_sync;
+mod config_diff;
+mod config_paths;
+mod config_validation;
+mod connection_test;
+mod crypto;
 mod database;
+mod deep_link;
+mod diagnose;
+mod drift;
 mod droid_sync;
+mod env_exports;
 mod error;
+mod extension_profiles;
 mod extra_clients;
+mod file_status;
+pub mod headless;
+mod health;
+mod jsonc;
+mod launcher;
+mod logging;
+mod model_aliases;
+mod model_list;
+mod notify;
 mod openclaw_sync;
 mod opencode_sync;
+mod paths;
+mod pinned_apps;
+mod project_sync;
+mod provider_advanced;
+mod provider_import;
+mod rotation;
+mod secrets;
+mod status_cache;
 mod store;
+mod sync_lock;
+mod sync_target;
+mod synced_providers;
 mod system_check;
+mod user_path;
 mod utils;
+mod watcher;
+mod wsl;
 
 use cli_sync::CliApp;
-use database::dao::{backup, providers};
+use database::dao::{backup, providers, settings};
+use droid_sync::{DroidModelOverrides, DroidProvider};
+use error::CommandError;
 use extra_clients::ExtraClient;
+use file_status::FileStatus;
+use model_aliases::ModelAliases;
+use model_list::{ModelInfo, ModelList, ModelListFlavor};
+use opencode_sync::OpencodeAdapter;
+use provider_advanced::ProviderAdvanced;
+use provider_import::DetectedProvider;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use status_cache::StatusCache;
 use std::sync::Arc;
 use store::AppState;
-use tauri::State;
+use sync_target::{BulkSync, SyncOutcome, SyncRequest, TargetStatus};
+use tauri::{AppHandle, State};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CliStatusResult {
@@ -30,6 +75,40 @@ pub struct CliStatusResult {
     pub current_base_url: Option<String>,
     pub files: Vec<String>,
     pub synced_count: Option<usize>,
+    /// A file we wrote was edited by hand since the last sync.
+    pub externally_modified: bool,
+    /// Left out of sync_all and provider switches.
+    pub pinned: bool,
+    /// Per-file detail behind `is_synced` / `current_base_url`.
+    pub file_status: Vec<FileStatus>,
+    /// Saved provider the config points at; see `synced_providers::resolve`.
+    pub synced_provider_id: Option<String>,
+    pub synced_provider_name: Option<String>,
+}
+
+/// Dashboard status plus a token that changes whenever the results do.
+#[derive(Debug, Serialize, Clone)]
+pub struct CliStatusSnapshot {
+    pub results: Vec<CliStatusResult>,
+    pub state_token: String,
+}
+
+impl CliStatusSnapshot {
+    fn new(results: Vec<CliStatusResult>) -> Self {
+        let state_token = status_cache::state_token(&results);
+        Self {
+            results,
+            state_token,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StatusChange {
+    /// Same results as the token the caller sent.
+    Unchanged,
+    Changed(CliStatusSnapshot),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -42,14 +121,90 @@ pub struct SyncResult {
     pub app: String,
     pub success: bool,
     pub error: Option<String>,
+    /// Non-fatal notice, e.g. models were defaulted because the proxy's list was unavailable.
+    #[serde(default)]
+    pub warning: Option<String>,
+    /// Not synced because the app is pinned; `warning` says so.
+    #[serde(default)]
+    pub skipped: bool,
+    /// Nothing to write: the app is set up by hand, and `warning` says how.
+    #[serde(default)]
+    pub manual: bool,
+}
+
+impl SyncResult {
+    fn from_outcome(app: &str, result: Result<SyncOutcome, String>) -> Self {
+        match result {
+            Ok(SyncOutcome::Synced(warning)) => Self {
+                app: app.to_string(),
+                success: true,
+                error: None,
+                warning,
+                skipped: false,
+                manual: false,
+            },
+            Ok(SyncOutcome::ManualStepsRequired(steps)) => Self {
+                app: app.to_string(),
+                success: true,
+                error: None,
+                warning: Some(steps),
+                skipped: false,
+                manual: true,
+            },
+            Err(e) => Self::failed(app, e),
+        }
+    }
+
+    fn failed(app: &str, error: String) -> Self {
+        Self {
+            app: app.to_string(),
+            success: false,
+            error: Some(error),
+            warning: None,
+            skipped: false,
+            manual: false,
+        }
+    }
+
+    fn pinned(app: &str) -> Self {
+        Self {
+            app: app.to_string(),
+            success: true,
+            error: None,
+            warning: Some("Pinned — left unchanged".to_string()),
+            skipped: true,
+            manual: false,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SwitchResult {
     pub success: bool,
     pub errors: Vec<SyncResult>,
+    /// Apps that synced with a notice, e.g. manual edits were overwritten.
+    #[serde(default)]
+    pub warnings: Vec<SyncResult>,
+    /// Installed apps left alone because they are pinned.
+    #[serde(default)]
+    pub skipped: Vec<SyncResult>,
+    /// Installed apps that have to be pointed at the provider by hand.
+    #[serde(default)]
+    pub manual: Vec<SyncResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveProviderResult {
+    pub saved: bool,
+    /// Present when `auto_sync_on_save` re-synced the current provider.
+    pub sync: Option<SwitchResult>,
 }
 
+/// Settings flag: re-sync installed apps when the current provider is edited.
+const AUTO_SYNC_ON_SAVE_KEY: &str = "auto_sync_on_save";
+/// Settings flag: `.antigravity.bak` backups were renamed to `.hajimi.bak`.
+const LEGACY_BACKUPS_MIGRATED_KEY: &str = "legacy_backups_migrated";
+
 fn get_cli_app(app: &str) -> Option<CliApp> {
     match app {
         "claude" => Some(CliApp::Claude),
@@ -61,466 +216,501 @@ fn get_cli_app(app: &str) -> Option<CliApp> {
 
 /// Get the appropriate proxy URL for each CLI tool
 fn get_proxy_url(app: &str, base_url: &str) -> String {
-    let url = base_url.trim_end_matches('/');
-    match app {
-        "codex" | "opencode" => {
-            if url.ends_with("/v1") {
-                url.to_string()
-            } else {
-                format!("{url}/v1")
-            }
-        }
-        _ => url.to_string(),
+    match sync_target::find(app) {
+        Some(target) => target.proxy_url(base_url),
+        None => base_url.trim_end_matches('/').to_string(),
     }
 }
 
-fn is_installed(app_name: &str) -> bool {
-    match app_name {
-        "claude" | "codex" | "gemini" => get_cli_app(app_name)
-            .map(|app| cli_sync::check_cli_installed(&app).0)
-            .unwrap_or(false),
-        "opencode" => opencode_sync::check_opencode_installed().0,
-        "openclaw" => openclaw_sync::check_openclaw_installed().0,
-        "droid" => droid_sync::check_droid_installed().0,
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::check_extra_installed(&client).0
-            } else {
-                false
-            }
-        }
+/// Parse an adapter id from the UI / provider record; empty means the default adapter.
+fn parse_opencode_adapter(raw: Option<&str>) -> Result<OpencodeAdapter, CommandError> {
+    match raw.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(OpencodeAdapter::default()),
+        Some(s) => OpencodeAdapter::parse(s)
+            .ok_or_else(|| CommandError::invalid_input(format!("Unknown OpenCode adapter: {s}"))),
     }
 }
 
-#[tauri::command]
-async fn get_all_cli_status(url: String) -> Result<Vec<CliStatusResult>, String> {
-    // 首先检查系统环境
-    if let Err(e) = system_check::validate_system_requirements() {
-        tracing::warn!("[get_all_cli_status] System check warning: {}", e);
-    }
-
-    if let Err(e) = utils::validate_url(&url) {
-        return Err(e.to_string());
-    }
+/// Parse a model id → Droid provider map from the UI.
+fn parse_droid_providers(
+    raw: Option<std::collections::HashMap<String, String>>,
+) -> Result<Option<DroidModelOverrides>, CommandError> {
+    let Some(map) = raw else {
+        return Ok(None);
+    };
+    let providers = map
+        .into_iter()
+        .map(|(model, provider)| {
+            DroidProvider::parse(&provider)
+                .map(|p| (model, p))
+                .ok_or_else(|| {
+                    CommandError::invalid_input(format!("Unknown Droid provider: {provider}"))
+                })
+        })
+        .collect::<Result<_, CommandError>>()?;
+    Ok(Some(DroidModelOverrides {
+        providers,
+        ..Default::default()
+    }))
+}
 
-    let mut results = Vec::new();
+/// Without a `url` nothing is compared: every app reports `is_synced: false`
+/// and whatever base URL its configs contain.
+#[tauri::command]
+async fn get_all_cli_status(
+    state: State<'_, AppState>,
+    url: Option<String>,
+    opencode_adapter: Option<String>,
+) -> Result<CliStatusSnapshot, String> {
+    let results = collect_cli_status(
+        state.db.clone(),
+        Some(state.status_cache.clone()),
+        url,
+        opencode_adapter,
+    )
+    .await?;
+    Ok(CliStatusSnapshot::new(results))
+}
 
-    for app_name in &["claude", "codex", "gemini"] {
-        if let Some(app) = get_cli_app(app_name) {
-            let proxy_url = get_proxy_url(app_name, &url);
-            let (installed, version) = cli_sync::check_cli_installed(&app);
-            let (is_synced, has_backup, current_base_url) = if installed {
-                cli_sync::get_sync_status(&app, &proxy_url)
-            } else {
-                (false, false, None)
-            };
-            results.push(CliStatusResult {
-                app: app_name.to_string(),
-                installed,
-                version,
-                is_synced,
-                has_backup,
-                current_base_url,
-                files: app.config_files().into_iter().map(|f| f.name).collect(),
-                synced_count: None,
-            });
-        }
+/// Like `get_all_cli_status`, but only `Unchanged` when the results would
+/// still hash to `token`.
+#[tauri::command]
+async fn get_status_if_changed(
+    state: State<'_, AppState>,
+    url: Option<String>,
+    opencode_adapter: Option<String>,
+    token: Option<String>,
+) -> Result<StatusChange, String> {
+    let results = collect_cli_status(
+        state.db.clone(),
+        Some(state.status_cache.clone()),
+        url,
+        opencode_adapter,
+    )
+    .await?;
+    let snapshot = CliStatusSnapshot::new(results);
+    if token.as_deref() == Some(snapshot.state_token.as_str()) {
+        Ok(StatusChange::Unchanged)
+    } else {
+        Ok(StatusChange::Changed(snapshot))
     }
+}
 
-    // OpenCode
-    {
-        let proxy_url = get_proxy_url("opencode", &url);
-        let (installed, version) = opencode_sync::check_opencode_installed();
-        let (is_synced, has_backup, current_base_url) = if installed {
-            opencode_sync::get_sync_status(&proxy_url)
-        } else {
-            (false, false, None)
-        };
-        results.push(CliStatusResult {
-            app: "opencode".to_string(),
-            installed,
-            version,
-            is_synced,
-            has_backup,
-            current_base_url,
-            files: vec!["opencode.json".to_string()],
-            synced_count: None,
-        });
+/// `cache` skips re-parsing config files that haven't changed since the last call.
+async fn collect_cli_status(
+    db: Arc<database::Database>,
+    cache: Option<Arc<StatusCache>>,
+    url: Option<String>,
+    opencode_adapter: Option<String>,
+) -> Result<Vec<CliStatusResult>, String> {
+    // 首先检查系统环境
+    if let Err(e) = system_check::validate_system_requirements() {
+        tracing::warn!("[get_all_cli_status] System check warning: {}", e);
     }
 
-    // Droid
-    {
-        let proxy_url = get_proxy_url("droid", &url);
-        let (installed, version) = droid_sync::check_droid_installed();
-        let (is_synced, has_backup, current_base_url, synced_count) = if installed {
-            droid_sync::get_sync_status(&proxy_url)
-        } else {
-            (false, false, None, 0)
-        };
-        results.push(CliStatusResult {
-            app: "droid".to_string(),
-            installed,
-            version,
-            is_synced,
-            has_backup,
-            current_base_url,
-            files: vec!["settings.json".to_string()],
-            synced_count: Some(synced_count),
-        });
+    if let Some(url) = &url {
+        utils::validate_url(url).map_err(|e| e.to_string())?;
     }
 
-    // OpenClaw
-    {
-        let proxy_url = get_proxy_url("openclaw", &url);
-        let (installed, version) = openclaw_sync::check_openclaw_installed();
-        let (is_synced, has_backup, current_base_url) = if installed {
-            openclaw_sync::get_sync_status(&proxy_url)
-        } else {
-            (false, false, None)
-        };
-        results.push(CliStatusResult {
-            app: "openclaw".to_string(),
-            installed,
-            version,
-            is_synced,
-            has_backup,
-            current_base_url,
-            files: vec!["openclaw.json".to_string()],
-            synced_count: None,
-        });
-    }
+    utils::run_blocking(move || {
+        status_apps()
+            .into_iter()
+            .filter_map(|app| {
+                app_status(
+                    &db,
+                    cache.as_deref(),
+                    app,
+                    url.as_deref(),
+                    opencode_adapter.as_deref(),
+                )
+            })
+            .collect()
+    })
+    .await
+}
 
-    // Extra clients (Chatbox, Cherry Studio, Jan, Cursor, Cline, Roo Code, Kilo Code, SillyTavern, LobeChat, BoltAI)
-    for client in ExtraClient::all() {
-        let proxy_url = get_proxy_url(client.as_str(), &url);
-        let (installed, version) = extra_clients::check_extra_installed(client);
-        let (is_synced, has_backup, current_base_url) = if installed {
-            extra_clients::get_extra_sync_status(client, &proxy_url)
-        } else {
-            (false, false, None)
-        };
-        results.push(CliStatusResult {
-            app: client.as_str().to_string(),
-            installed,
-            version,
-            is_synced,
-            has_backup,
-            current_base_url,
-            files: client.config_files_display(),
-            synced_count: None,
-        });
-    }
+/// Every app on the dashboard, in display order.
+pub(crate) fn status_apps() -> Vec<&'static str> {
+    sync_target::all().iter().map(|t| t.id()).collect()
+}
 
-    Ok(results)
+/// Status of one app against `url` (see `get_all_cli_status` when absent);
+/// `None` for unknown apps.
+pub(crate) fn app_status(
+    db: &database::Database,
+    cache: Option<&StatusCache>,
+    app_name: &str,
+    url: Option<&str>,
+    opencode_adapter: Option<&str>,
+) -> Option<CliStatusResult> {
+    let target = sync_target::find(app_name)?;
+    let (installed, version) = target.detect();
+    let status = if installed {
+        let adapter = opencode_adapter.and_then(OpencodeAdapter::parse);
+        let proxy_url = url.map(|url| target.proxy_url(url));
+        match cache {
+            Some(cache) => cache.status(target.as_ref(), proxy_url.as_deref(), adapter),
+            None => target.status(proxy_url.as_deref(), adapter),
+        }
+    } else {
+        TargetStatus::default()
+    };
+    let TargetStatus {
+        status,
+        synced_count,
+    } = status;
+
+    let current_base_url = status.current_base_url();
+    let synced_provider = synced_providers::resolve(db, app_name, current_base_url.as_deref());
+
+    Some(CliStatusResult {
+        app: app_name.to_string(),
+        installed,
+        version,
+        is_synced: url.is_some() && status.is_synced(),
+        has_backup: status.has_backup,
+        current_base_url,
+        files: target.config_files(),
+        synced_count,
+        externally_modified: installed && !drift::modified_files(db, app_name).is_empty(),
+        pinned: pinned_apps::is_pinned(db, app_name),
+        file_status: status.files,
+        synced_provider_id: synced_provider.as_ref().map(|p| p.id.clone()),
+        synced_provider_name: synced_provider.map(|p| p.name),
+    })
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn sync_cli(
+    state: State<'_, AppState>,
     app: String,
     url: String,
     api_key: String,
     model: Option<String>,
-) -> Result<(), String> {
+    small_model: Option<String>,
+    opencode_adapter: Option<String>,
+    droid_providers: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncResult, CommandError> {
+    let _lock = state.sync_lock.acquire().await?;
+    let outcome = sync_one(
+        &state.db,
+        &app,
+        &url,
+        &api_key,
+        model.as_deref(),
+        small_model.as_deref(),
+        opencode_adapter.as_deref(),
+        droid_providers,
+    )
+    .await?;
+    Ok(SyncResult::from_outcome(&app, Ok(outcome)))
+}
+
+/// Validate, check for manual edits, sync one app and record the new file hashes.
+/// A manual-edit note is merged into `SyncOutcome::Synced`'s warning.
+/// `small_model` is opencode's `small_model`; other apps ignore it.
+#[allow(clippy::too_many_arguments)]
+async fn sync_one(
+    db: &database::Database,
+    app: &str,
+    url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    small_model: Option<&str>,
+    opencode_adapter: Option<&str>,
+    droid_providers: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncOutcome, CommandError> {
     // 检查系统环境
-    system_check::validate_system_requirements().map_err(|e| e.to_string())?;
+    system_check::validate_system_requirements()?;
 
-    utils::validate_url(&url).map_err(|e| e.to_string())?;
+    utils::validate_url(url)?;
     if api_key.trim().is_empty() {
-        return Err("API key cannot be empty".to_string());
+        return Err(CommandError::invalid_input("API key cannot be empty"));
     }
 
-    let proxy_url = get_proxy_url(&app, &url);
+    let note = drift::check_before_sync(db, app)?;
+    let aliases = provider_aliases(db, url);
+    let model = model.map(|m| aliases.resolve(m, app));
+    let small_model = small_model.map(|m| aliases.resolve(m, app));
+    let provider = synced_providers::provider_for(db, url, api_key);
+    let advanced = advanced_for(provider.as_ref());
+    let api_key = secrets::resolve(api_key).await?;
+    let outcome = sync_app_files(
+        app,
+        url,
+        &api_key,
+        model.as_deref(),
+        small_model.as_deref(),
+        opencode_adapter,
+        droid_providers,
+        &aliases,
+        &advanced,
+    )
+    .await?;
+    drift::record(db, app);
+    if matches!(outcome, SyncOutcome::Synced(_)) {
+        synced_providers::record(db, app, provider.as_ref().map(|p| p.id.as_str()));
+    }
+    Ok(match outcome {
+        SyncOutcome::Synced(warning) => SyncOutcome::Synced(join_warnings(note, warning)),
+        manual => manual,
+    })
+}
 
-    match app.as_str() {
-        "claude" | "codex" | "gemini" => {
-            let cli_app = get_cli_app(&app).ok_or_else(|| format!("Invalid app: {app}"))?;
-            cli_sync::sync_config(&cli_app, &proxy_url, &api_key, model.as_deref())
-        }
-        "opencode" => opencode_sync::sync_opencode_config(&proxy_url, &api_key).await,
-        "openclaw" => {
-            openclaw_sync::sync_openclaw_config(&proxy_url, &api_key, model.as_deref()).await
-        }
-        "droid" => {
-            droid_sync::sync_droid_config(&proxy_url, &api_key, model.as_deref()).map(|_| ())
-        }
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::sync_extra_config(&client, &proxy_url, &api_key, model.as_deref())
-            } else {
-                Err(format!("Unknown app: {app}"))
-            }
-        }
+/// Aliases of the current provider when `url` is its URL; a manually entered
+/// URL has none.
+fn provider_aliases(db: &database::Database, url: &str) -> ModelAliases {
+    let url = url.trim().trim_end_matches('/');
+    providers::get_current(db)
+        .ok()
+        .flatten()
+        .filter(|p| p.url.trim().trim_end_matches('/') == url)
+        .map(|p| ModelAliases::parse_lenient(&p.model_aliases))
+        .unwrap_or_default()
+}
+
+/// Advanced options of the saved provider being synced; none for a URL that
+/// isn't saved.
+pub(crate) fn advanced_for(provider: Option<&providers::ProviderRecord>) -> ProviderAdvanced {
+    provider
+        .map(|p| ProviderAdvanced::parse_lenient(&p.advanced))
+        .unwrap_or_default()
+}
+
+fn join_warnings(a: Option<String>, b: Option<String>) -> Option<String> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(format!("{a}\n{b}")),
+        (a, b) => a.or(b),
     }
 }
 
+/// `model` / `small_model` are already aliased; `aliases` covers the catalogs
+/// opencode, OpenClaw and Droid write.
+#[allow(clippy::too_many_arguments)]
+async fn sync_app_files(
+    app: &str,
+    url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    small_model: Option<&str>,
+    opencode_adapter: Option<&str>,
+    droid_providers: Option<std::collections::HashMap<String, String>>,
+    aliases: &ModelAliases,
+    advanced: &ProviderAdvanced,
+) -> Result<SyncOutcome, CommandError> {
+    let target = sync_target::find(app).ok_or_else(|| CommandError::unknown_app(app))?;
+    let droid_overrides = parse_droid_providers(droid_providers)?;
+    let proxy_url = target.proxy_url(url);
+    let request = SyncRequest {
+        proxy_url: &proxy_url,
+        api_key,
+        model,
+        small_model,
+        opencode_adapter: parse_opencode_adapter(opencode_adapter)?,
+        droid_overrides: droid_overrides.as_ref(),
+        aliases,
+        advanced,
+    };
+    target.sync(&request).await.map_err(CommandError::from)
+}
+
 #[tauri::command]
 async fn sync_all(
+    state: State<'_, AppState>,
     url: String,
     api_key: String,
     model: Option<String>,
     per_cli_models: Option<std::collections::HashMap<String, String>>,
-) -> Result<SyncAllResult, String> {
+    opencode_adapter: Option<String>,
+    droid_providers: Option<std::collections::HashMap<String, String>>,
+) -> Result<SyncAllResult, CommandError> {
+    let _lock = state.sync_lock.acquire().await?;
     // 检查系统环境
-    system_check::validate_system_requirements().map_err(|e| e.to_string())?;
+    system_check::validate_system_requirements()?;
 
-    utils::validate_url(&url).map_err(|e| e.to_string())?;
+    utils::validate_url(&url)?;
     if api_key.trim().is_empty() {
-        return Err("API key cannot be empty".to_string());
+        return Err(CommandError::invalid_input("API key cannot be empty"));
     }
 
     let cli_models = per_cli_models.unwrap_or_default();
-    let apps = ["claude", "codex", "gemini", "opencode", "openclaw", "droid"];
-    let mut results = Vec::new();
-
-    for app_name in &apps {
-        let proxy_url = get_proxy_url(app_name, &url);
-
-        let installed = match *app_name {
-            "claude" | "codex" | "gemini" => get_cli_app(app_name)
-                .map(|app| cli_sync::check_cli_installed(&app).0)
-                .unwrap_or(false),
-            "opencode" => opencode_sync::check_opencode_installed().0,
-            "openclaw" => openclaw_sync::check_openclaw_installed().0,
-            "droid" => droid_sync::check_droid_installed().0,
-            _ => false,
-        };
-
-        if !installed {
-            continue;
-        }
-
-        // 优先使用per-cli model，fallback到全局default model
-        let effective_model = cli_models
-            .get(*app_name)
+    let adapter = parse_opencode_adapter(opencode_adapter.as_deref())?;
+    let droid_overrides = parse_droid_providers(droid_providers)?;
+    let aliases = provider_aliases(&state.db, &url);
+    let provider = synced_providers::provider_for(&state.db, &url, &api_key);
+    let advanced = advanced_for(provider.as_ref());
+    let api_key = secrets::resolve(&api_key).await?;
+
+    // 优先使用per-cli model，fallback到全局default model
+    let model_for = |app_name: &str| {
+        cli_models
+            .get(app_name)
             .filter(|m| !m.is_empty())
-            .or(model.as_ref());
-
-        let result = match *app_name {
-            "claude" | "codex" | "gemini" => match get_cli_app(app_name) {
-                Some(cli_app) => cli_sync::sync_config(
-                    &cli_app,
-                    &proxy_url,
-                    &api_key,
-                    effective_model.map(|s| s.as_str()),
-                ),
-                None => Err(format!("Invalid app: {app_name}")),
-            },
-            "opencode" => opencode_sync::sync_opencode_config(&proxy_url, &api_key).await,
-            "openclaw" => {
-                openclaw_sync::sync_openclaw_config(
-                    &proxy_url,
-                    &api_key,
-                    effective_model.map(|s| s.as_str()),
-                )
-                .await
-            }
-            "droid" => droid_sync::sync_droid_config(
-                &proxy_url,
-                &api_key,
-                effective_model.map(|s| s.as_str()),
-            )
-            .map(|_| ()),
-            _ => continue,
-        };
-
-        results.push(SyncResult {
-            app: app_name.to_string(),
-            success: result.is_ok(),
-            error: result.err(),
-        });
-    }
-
-    // Extra clients — skip tools that don't support file-based sync
-    for client in ExtraClient::all() {
-        if !client.supports_file_sync() {
-            continue;
-        }
-
-        let app_name = client.as_str();
-        let proxy_url = get_proxy_url(app_name, &url);
-        let installed = extra_clients::check_extra_installed(client).0;
+            .or(model.as_ref())
+            .map(|m| aliases.resolve(m, app_name))
+    };
+    let small_model = cli_models
+        .get(opencode_sync::SMALL_MODEL_KEY)
+        .map(|m| aliases.resolve(m, "opencode"));
+    let plan = BulkSync {
+        url: &url,
+        api_key: &api_key,
+        model_for: &model_for,
+        small_model: small_model.as_deref(),
+        opencode_adapter: adapter,
+        droid_overrides: droid_overrides.as_ref(),
+        aliases: &aliases,
+        advanced: &advanced,
+        provider_id: provider.as_ref().map(|p| p.id.as_str()),
+        snapshot: false,
+    };
+    let results = sync_target::sync_installed(&state.db, &sync_target::all(), &plan).await;
 
-        if !installed {
-            continue;
-        }
+    Ok(SyncAllResult { results })
+}
 
-        let effective_model = cli_models
-            .get(app_name)
-            .filter(|m| !m.is_empty())
-            .or(model.as_ref());
+#[tauri::command]
+async fn restore_cli(state: State<'_, AppState>, app: String) -> Result<(), CommandError> {
+    let _lock = state.sync_lock.acquire().await?;
+    restore_app(&state.db, &app)
+}
 
-        let result = extra_clients::sync_extra_config(
-            client,
-            &proxy_url,
-            &api_key,
-            effective_model.map(|s| s.as_str()),
-        );
+fn restore_app(db: &database::Database, app: &str) -> Result<(), CommandError> {
+    let target = sync_target::find(app).ok_or_else(|| CommandError::unknown_app(app))?;
+    if target.manual_steps().is_some() {
+        return Err(CommandError::unsupported_operation(app, "restore"));
+    }
+    target.restore().map_err(CommandError::from)?;
+    drift::forget(db, app);
+    synced_providers::forget(db, app);
+    Ok(())
+}
 
-        results.push(SyncResult {
-            app: app_name.to_string(),
-            success: result.is_ok(),
-            error: result.err(),
-        });
+/// Validate a model listing request and resolve its flavor.
+fn model_list_request(
+    url: &str,
+    api_key: &str,
+    flavor: Option<&str>,
+) -> Result<ModelListFlavor, CommandError> {
+    utils::validate_url(url)?;
+    if api_key.trim().is_empty() {
+        return Err(CommandError::invalid_input("API key cannot be empty"));
+    }
+    match flavor {
+        Some(raw) => ModelListFlavor::parse(raw).ok_or_else(|| {
+            CommandError::invalid_input(format!("Unknown model list flavor: {raw}"))
+        }),
+        None => Ok(ModelListFlavor::default()),
     }
+}
 
-    Ok(SyncAllResult { results })
+#[tauri::command]
+async fn fetch_models(
+    state: State<'_, AppState>,
+    url: String,
+    api_key: String,
+    flavor: Option<String>,
+    force: bool,
+    filter: Option<String>,
+) -> Result<ModelList, CommandError> {
+    let flavor = model_list_request(&url, &api_key, flavor.as_deref())?;
+    let api_key = secrets::resolve(&api_key).await?;
+    Ok(model_list::fetch_model_list_cached(
+        &state.db,
+        &url,
+        &api_key,
+        flavor,
+        force,
+        filter.as_deref(),
+    )
+    .await?)
 }
 
+/// Uncached listing with capability hints and a chat/reasoning/image/embedding
+/// classification for each model, so pickers can pre-filter per app.
 #[tauri::command]
-async fn restore_cli(app: String) -> Result<(), String> {
-    match app.as_str() {
-        "claude" | "codex" | "gemini" => {
-            let cli_app = get_cli_app(&app).ok_or_else(|| format!("Invalid app: {app}"))?;
-            cli_sync::restore_config(&cli_app)
-        }
-        "opencode" => opencode_sync::restore_opencode_config(),
-        "openclaw" => openclaw_sync::restore_openclaw_config(),
-        "droid" => droid_sync::restore_droid_config(),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::restore_extra_config(&client)
-            } else {
-                Err(format!("Unknown app: {app}"))
-            }
-        }
-    }
+async fn fetch_models_detailed(
+    url: String,
+    api_key: String,
+    flavor: Option<String>,
+) -> Result<Vec<ModelInfo>, CommandError> {
+    let flavor = model_list_request(&url, &api_key, flavor.as_deref())?;
+    let api_key = secrets::resolve(&api_key).await?;
+    let fetched = model_list::fetch_model_list(&url, &api_key, flavor).await?;
+    Ok(fetched.details)
 }
 
 #[tauri::command]
-async fn fetch_models(url: String, api_key: String) -> Result<Vec<String>, String> {
+async fn set_model_cache_ttl(state: State<'_, AppState>, secs: u64) -> Result<(), String> {
+    model_list::set_cache_ttl_secs(&state.db, secs)
+}
+
+#[tauri::command]
+async fn test_connection(url: String, api_key: String) -> Result<String, String> {
     utils::validate_url(&url).map_err(|e| e.to_string())?;
     if api_key.trim().is_empty() {
         return Err("API key cannot be empty".to_string());
     }
-
-    let models_url = format!("{}/v1/models", url.trim_end_matches('/'));
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
-
-    let response = client
-        .get(&models_url)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .send()
+    let api_key = secrets::resolve(&api_key)
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "Request timed out (10s)".to_string()
-            } else if e.is_connect() {
-                format!("Connection failed: {e}")
-            } else {
-                format!("Request failed: {e}")
-            }
-        })?;
+        .map_err(|e| e.to_string())?;
+    connection_test::check_models_endpoint(&url, &api_key).await?;
+    Ok("ok".to_string())
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        // Truncate body to avoid leaking large error pages or sensitive data.
-        let summary = body.chars().take(200).collect::<String>();
-        return Err(format!("API returned {status}: {summary}"));
+#[tauri::command]
+async fn test_connection_deep(
+    url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<connection_test::ConnectionReport, String> {
+    utils::validate_url(&url).map_err(|e| e.to_string())?;
+    if api_key.trim().is_empty() {
+        return Err("API key cannot be empty".to_string());
     }
-
-    let body: Value = response
-        .json()
+    let api_key = secrets::resolve(&api_key)
         .await
-        .map_err(|e| format!("Failed to parse response: {e}"))?;
-
-    let mut models: Vec<String> = Vec::new();
-
-    if let Some(data) = body.get("data").and_then(|v| v.as_array()) {
-        for item in data {
-            if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
-                models.push(id.to_string());
-            }
-        }
-    }
-
-    models.sort();
-    Ok(models)
+        .map_err(|e| e.to_string())?;
+    Ok(connection_test::test_connection_deep(&url, &api_key, model.as_deref()).await)
 }
 
 #[tauri::command]
-async fn test_connection(url: String, api_key: String) -> Result<String, String> {
+async fn test_connection_for(
+    app: String,
+    url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<Vec<connection_test::ProtocolCheck>, String> {
+    if !is_known_app(&app) {
+        return Err(format!("Unknown app: {app}"));
+    }
     utils::validate_url(&url).map_err(|e| e.to_string())?;
     if api_key.trim().is_empty() {
         return Err("API key cannot be empty".to_string());
     }
-
-    let models_url = format!("{}/v1/models", url.trim_end_matches('/'));
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
-
-    let response = client
-        .get(&models_url)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .send()
+    let api_key = secrets::resolve(&api_key)
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "Connection timed out (10s). Check the URL.".to_string()
-            } else if e.is_connect() {
-                format!("Cannot connect to server: {e}")
-            } else {
-                format!("Request failed: {e}")
-            }
-        })?;
-
-    let status = response.status();
-    if status.is_success() {
-        Ok("ok".to_string())
-    } else if status.as_u16() == 401 || status.as_u16() == 403 {
-        Err("Invalid API key (401/403)".to_string())
-    } else {
-        let body = response.text().await.unwrap_or_default();
-        // SECURITY: Truncate body to prevent leaking large error pages
-        let summary: String = body.chars().take(200).collect();
-        Err(format!("Server returned {status}: {summary}"))
-    }
+        .map_err(|e| e.to_string())?;
+    let proxy_url = get_proxy_url(&app, &url);
+    connection_test::test_connection_for(&app, &proxy_url, &api_key, model.as_deref()).await
 }
 
 #[tauri::command]
 async fn get_config_content(app: String, file_name: Option<String>) -> Result<String, String> {
-    match app.as_str() {
-        "claude" | "codex" | "gemini" => {
-            let cli_app = get_cli_app(&app).ok_or_else(|| format!("Invalid app: {app}"))?;
-            cli_sync::read_config_content(&cli_app, file_name.as_deref())
-        }
-        "opencode" => opencode_sync::read_opencode_config_content(),
-        "openclaw" => openclaw_sync::read_openclaw_config_content(),
-        "droid" => droid_sync::read_droid_config_content(),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::read_extra_config_content(&client)
-            } else {
-                Err(format!("Unknown app: {app}"))
-            }
-        }
-    }
+    let target = sync_target::find(&app).ok_or_else(|| format!("Unknown app: {app}"))?;
+    target.read_content(file_name.as_deref())
 }
 
 #[tauri::command]
 async fn write_config_file(app: String, file_name: String, content: String) -> Result<(), String> {
-    match app.as_str() {
-        "claude" | "codex" | "gemini" => {
-            let cli_app = get_cli_app(&app).ok_or_else(|| format!("Invalid app: {app}"))?;
-            cli_sync::write_config_content(&cli_app, &file_name, &content)
-        }
-        "opencode" => opencode_sync::write_opencode_config_content(&content),
-        "openclaw" => openclaw_sync::write_openclaw_config_content(&content),
-        "droid" => droid_sync::write_droid_config_content(&content),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::write_extra_config_content(&client, &file_name, &content)
-            } else {
-                Err(format!("Unknown app: {other}"))
-            }
-        }
-    }
+    write_app_config(&app, &file_name, &content)
+}
+
+/// Write `content` to one of `app`'s config files through its module's writer.
+pub(crate) fn write_app_config(app: &str, file_name: &str, content: &str) -> Result<(), String> {
+    let target = sync_target::find(app).ok_or_else(|| format!("Unknown app: {app}"))?;
+    target.write_content(file_name, content)
 }
 
 #[tauri::command]
@@ -540,111 +730,427 @@ async fn open_external_url(url: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn launch_app(name: String) -> Result<(), String> {
-    // SECURITY: Only allow known application names to prevent arbitrary command execution
-    const ALLOWED_APPS: &[&str] = &[
-        "Chatbox",
-        "Cherry Studio",
-        "Jan",
-        "Cursor",
-        "SillyTavern",
-        "LobeChat",
-        "BoltAI",
-        "Droid",
-        "Factory",
-    ];
-    let trimmed = name.trim();
-    if !ALLOWED_APPS.iter().any(|a| a.eq_ignore_ascii_case(trimmed)) {
-        return Err(format!("Unknown application: {trimmed}"));
-    }
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .args(["-a", &name])
-            .spawn()
-            .map_err(|e| format!("Failed to launch {name}: {e}"))?;
-    }
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(["/c", "start", "", &name])
-            .spawn()
-            .map_err(|e| format!("Failed to launch {}: {}", name, e))?;
-    }
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new(&name)
-            .spawn()
-            .map_err(|e| format!("Failed to launch {}: {}", name, e))?;
+async fn launch_app(app: String) -> Result<(), CommandError> {
+    launcher::launch(app.trim())
+}
+
+#[tauri::command]
+async fn open_config_folder(app: String) -> Result<(), String> {
+    let folder = get_config_folder_path(&app)?;
+    // The opener exits fine even for a missing path, so check first
+    if !folder.is_dir() {
+        return Err(not_created_yet(&app, &folder));
+    }
+    let folder_str = folder.to_string_lossy().to_string();
+    open_path_in_system(&folder_str)
+}
+
+/// Show `file_name` (or the app's main config file) selected in the file
+/// manager; opens the folder instead if the file doesn't exist yet.
+#[tauri::command]
+async fn reveal_config_file(app: String, file_name: Option<String>) -> Result<(), String> {
+    let file = get_config_file_path(&app, file_name.as_deref())?;
+    if file.is_file() {
+        return reveal_in_file_manager(&file);
+    }
+    match file.parent().filter(|dir| dir.is_dir()) {
+        Some(dir) => open_path_in_system(&dir.to_string_lossy()),
+        None => Err(not_created_yet(&app, &file)),
+    }
+}
+
+fn not_created_yet(app: &str, path: &std::path::Path) -> String {
+    format!(
+        "{} has not been created yet — sync {app} first",
+        path.display()
+    )
+}
+
+/// Config file for `app`, picked by name when the app has several.
+fn get_config_file_path(app: &str, file_name: Option<&str>) -> Result<std::path::PathBuf, String> {
+    let path = match app {
+        "opencode" => opencode_sync::get_config_path(),
+        "openclaw" => openclaw_sync::get_config_path(),
+        "droid" => droid_sync::get_config_path(),
+        other => {
+            if let Some(cli_app) = get_cli_app(other) {
+                let files = cli_app.config_files();
+                match file_name {
+                    Some(name) => files.into_iter().find(|f| f.name == name),
+                    None => files.into_iter().next(),
+                }
+                .map(|f| f.path)
+            } else if let Some(client) = ExtraClient::from_str(other) {
+                extra_clients::config_path_for(&client)
+            } else {
+                return Err(format!("Unknown app: {other}"));
+            }
+        }
+    };
+    path.ok_or_else(|| match file_name {
+        Some(name) => format!("{app} has no config file named {name}"),
+        None => format!("Cannot determine config file for {app}"),
+    })
+}
+
+fn get_config_folder_path(app: &str) -> Result<std::path::PathBuf, String> {
+    // Resolve through the same path helpers sync/restore use (overrides included).
+    let parent_of = |path: Option<std::path::PathBuf>| {
+        path.and_then(|p| p.parent().map(|d| d.to_path_buf()))
+            .ok_or_else(|| format!("Cannot determine config folder for {app}"))
+    };
+    match app {
+        "opencode" => parent_of(opencode_sync::get_config_path()),
+        "openclaw" => parent_of(openclaw_sync::get_config_path()),
+        "droid" => parent_of(droid_sync::get_config_path()),
+        other => {
+            if let Some(cli_app) = get_cli_app(other) {
+                // The last file always lives in the CLI's config dir
+                // (Claude's .claude.json may sit at the home root).
+                parent_of(cli_app.config_files().pop().map(|f| f.path))
+            } else if let Some(client) = ExtraClient::from_str(other) {
+                extra_clients::get_config_folder(&client)
+                    .ok_or_else(|| format!("Cannot determine config folder for {other}"))
+            } else {
+                Err(format!("Unknown app: {other}"))
+            }
+        }
+    }
+}
+
+fn open_path_in_system(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open: {e}"))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("cmd")
+            .args(["/c", "start", "", path])
+            .spawn()
+            .map_err(|e| format!("Failed to open: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Open the file manager with `file` selected.
+fn reveal_in_file_manager(file: &std::path::Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg("-R")
+            .arg(file)
+            .spawn()
+            .map_err(|e| format!("Failed to open: {e}"))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer wants `/select,"path"` as one unsplit argument
+        std::process::Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", file.display()))
+            .spawn()
+            .map_err(|e| format!("Failed to open: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // FileManager1.ShowItems is implemented by Nautilus, Dolphin, Nemo, Caja…
+        let uri = reqwest::Url::from_file_path(file)
+            .map_err(|_| format!("Invalid path: {}", file.display()))?;
+        let shown = std::process::Command::new("dbus-send")
+            .args([
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{uri}"))
+            .arg("string:")
+            .output()
+            .is_ok_and(|out| out.status.success());
+        if !shown {
+            let parent = file.parent().unwrap_or(file);
+            return open_path_in_system(&parent.to_string_lossy());
+        }
+    }
+    Ok(())
+}
+
+// ── Config path overrides ───────────────────────────────────────────────────
+
+fn is_known_app(app: &str) -> bool {
+    sync_target::find(app).is_some()
+}
+
+#[tauri::command]
+async fn get_config_path_overrides() -> Result<std::collections::BTreeMap<String, String>, String> {
+    Ok(config_paths::list_overrides())
+}
+
+#[tauri::command]
+async fn set_config_path_override(
+    handle: AppHandle,
+    state: State<'_, AppState>,
+    app: String,
+    path: String,
+) -> Result<String, String> {
+    if !is_known_app(&app) {
+        return Err(format!("Unknown app: {app}"));
+    }
+    let path = config_paths::set_override(&state.db, &app, &path)?;
+    watcher::restart(&handle);
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn clear_config_path_override(
+    handle: AppHandle,
+    state: State<'_, AppState>,
+    app: String,
+) -> Result<(), String> {
+    config_paths::clear_override(&state.db, &app)?;
+    watcher::restart(&handle);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_recent_logs(lines: u32) -> Result<Vec<String>, String> {
+    let dir = logging::log_dir().ok_or_else(|| "Cannot determine log directory".to_string())?;
+    logging::recent_lines(&dir, lines)
+}
+
+#[tauri::command]
+async fn export_logs(dest_path: String) -> Result<String, String> {
+    let dir = logging::log_dir().ok_or_else(|| "Cannot determine log directory".to_string())?;
+    let dest = std::path::PathBuf::from(dest_path.trim());
+    if !dest.is_absolute() {
+        return Err(format!("Path must be absolute: {}", dest.display()));
+    }
+    logging::export_zip(&dir, &dest).map(|p| p.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn get_auto_sync_on_save(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(settings::get_bool(&state.db, AUTO_SYNC_ON_SAVE_KEY, false))
+}
+
+#[tauri::command]
+async fn set_auto_sync_on_save(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    settings::set_bool(&state.db, AUTO_SYNC_ON_SAVE_KEY, enabled)
+}
+
+#[tauri::command]
+async fn get_drift_policy(state: State<'_, AppState>) -> Result<drift::DriftPolicy, String> {
+    Ok(drift::policy(&state.db))
+}
+
+#[tauri::command]
+async fn set_drift_policy(state: State<'_, AppState>, policy: String) -> Result<(), String> {
+    let policy = drift::DriftPolicy::parse(&policy)
+        .ok_or_else(|| format!("Unknown drift policy: {policy}"))?;
+    drift::set_policy(&state.db, policy)
+}
+
+#[tauri::command]
+async fn get_download_mirror(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(auto_installer::download_mirror(&state.db)
+        .as_str()
+        .to_string())
+}
+
+#[tauri::command]
+async fn set_download_mirror(state: State<'_, AppState>, value: String) -> Result<(), String> {
+    let mirror = auto_installer::DownloadMirror::parse(&value)?;
+    auto_installer::set_download_mirror(&state.db, &mirror)
+}
+
+#[tauri::command]
+async fn get_package_managers(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(auto_installer::package_manager_order(&state.db)
+        .into_iter()
+        .map(|pm| pm.as_str().to_string())
+        .collect())
+}
+
+#[tauri::command]
+async fn set_package_managers(
+    state: State<'_, AppState>,
+    order: Vec<String>,
+) -> Result<(), String> {
+    let order = order
+        .iter()
+        .map(|name| {
+            auto_installer::PackageManager::parse(name)
+                .ok_or_else(|| format!("Unknown package manager: {name}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    auto_installer::set_package_manager_order(&state.db, &order)
+}
+
+#[tauri::command]
+async fn get_sillytavern_dir(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    Ok(auto_installer::sillytavern_dir(&state.db).map(|dir| dir.to_string_lossy().to_string()))
+}
+
+/// Also points SillyTavern sync at the new folder so install, status and
+/// sync agree, unless the user picked their own sync path; an empty value
+/// restores `~/SillyTavern`.
+#[tauri::command]
+async fn set_sillytavern_dir(
+    handle: AppHandle,
+    state: State<'_, AppState>,
+    value: String,
+) -> Result<(), String> {
+    let secrets =
+        |dir: std::path::PathBuf| dir.join("data").join("default-user").join("secrets.json");
+    let before = auto_installer::sillytavern_dir(&state.db).map(secrets);
+    auto_installer::set_sillytavern_dir(&state.db, &value)?;
+    let current = config_paths::get_override("sillytavern");
+    if !follows_sillytavern_dir(current.as_deref(), before.as_deref()) {
+        tracing::info!("[sillytavern] Keeping the custom sync path");
+        return Ok(());
+    }
+    match auto_installer::sillytavern_dir(&state.db).map(secrets) {
+        Some(path) => {
+            config_paths::set_override(&state.db, "sillytavern", &path.to_string_lossy())?;
+        }
+        None => config_paths::clear_override(&state.db, "sillytavern")?,
+    }
+    watcher::restart(&handle);
+    Ok(())
+}
+
+/// The SillyTavern sync path moves with the install folder only while it is
+/// unset or still the path derived from the previous folder.
+fn follows_sillytavern_dir(
+    current: Option<&std::path::Path>,
+    derived: Option<&std::path::Path>,
+) -> bool {
+    current.is_none() || current == derived
+}
+
+#[tauri::command]
+async fn get_network_probe_url(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(system_check::network_probe_url(&state.db))
+}
+
+#[tauri::command]
+async fn set_network_probe_url(state: State<'_, AppState>, value: String) -> Result<(), String> {
+    system_check::set_network_probe_url(&state.db, &value)
+}
+
+#[tauri::command]
+async fn get_codex_profile_mode() -> Result<bool, String> {
+    Ok(cli_sync::codex_profile_mode())
+}
+
+#[tauri::command]
+async fn set_codex_profile_mode(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    cli_sync::set_codex_profile_mode(&state.db, enabled)
+}
+
+#[tauri::command]
+async fn get_gemini_force_auth() -> Result<bool, String> {
+    Ok(cli_sync::gemini_force_auth())
+}
+
+#[tauri::command]
+async fn set_gemini_force_auth(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    cli_sync::set_gemini_force_auth(&state.db, enabled)
+}
+
+#[tauri::command]
+async fn get_pinned_apps(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(pinned_apps::list(&state.db).into_iter().collect())
+}
+
+/// Keep `app` out of sync_all and provider switches.
+#[tauri::command]
+async fn pin_app(state: State<'_, AppState>, app: String) -> Result<(), CommandError> {
+    if !is_known_app(&app) {
+        return Err(CommandError::unknown_app(&app));
     }
-    Ok(())
+    Ok(pinned_apps::set_pinned(&state.db, &app, true)?)
 }
 
 #[tauri::command]
-async fn open_config_folder(app: String) -> Result<(), String> {
-    let folder = get_config_folder_path(&app)?;
-    let folder_str = folder.to_string_lossy().to_string();
-    open_path_in_system(&folder_str)
+async fn unpin_app(state: State<'_, AppState>, app: String) -> Result<(), CommandError> {
+    Ok(pinned_apps::set_pinned(&state.db, &app, false)?)
 }
 
-fn get_config_folder_path(app: &str) -> Result<std::path::PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    match app {
-        "claude" => Ok(home.join(".claude")),
-        "codex" => Ok(home.join(".codex")),
-        "gemini" => Ok(home.join(".gemini")),
-        "opencode" => {
-            // XDG_CONFIG_HOME or ~/.config
-            let config_dir = std::env::var("XDG_CONFIG_HOME")
-                .map(std::path::PathBuf::from)
-                .unwrap_or_else(|_| home.join(".config"));
-            Ok(config_dir.join("opencode"))
-        }
-        "openclaw" => Ok(home.join(".openclaw")),
-        "droid" => Ok(home.join(".factory")),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::get_config_folder(&client)
-                    .ok_or_else(|| format!("Cannot determine config folder for {other}"))
-            } else {
-                Err(format!("Unknown app: {other}"))
-            }
-        }
-    }
+#[tauri::command]
+async fn get_health_poll_interval(state: State<'_, AppState>) -> Result<u64, String> {
+    Ok(health::interval_secs(&state.db))
 }
 
-fn open_path_in_system(path: &str) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| format!("Failed to open: {e}"))?;
-    }
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(["/c", "start", "", path])
-            .spawn()
-            .map_err(|e| format!("Failed to open: {}", e))?;
-    }
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| format!("Failed to open: {}", e))?;
-    }
+/// Seconds between background health checks; 0 turns them off.
+#[tauri::command]
+async fn set_health_poll_interval(state: State<'_, AppState>, secs: u64) -> Result<(), String> {
+    health::set_interval_secs(&state.db, secs)?;
+    state.health.wake();
     Ok(())
 }
 
+#[tauri::command]
+async fn get_provider_health_snapshot(
+    state: State<'_, AppState>,
+) -> Result<Option<health::HealthSnapshot>, String> {
+    Ok(state.health.snapshot())
+}
+
+#[tauri::command]
+async fn get_notifications_enabled(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(notify::is_enabled(&state.db))
+}
+
+#[tauri::command]
+async fn set_notifications_enabled(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    notify::set_enabled(&state.db, enabled)
+}
+
 // ── Provider management commands ────────────────────────────────────────────
 
 #[tauri::command]
 async fn list_providers(
     state: State<'_, AppState>,
+    include_archived: Option<bool>,
 ) -> Result<Vec<providers::ProviderRecord>, String> {
-    providers::get_all(&state.db)
+    providers::get_all(&state.db, include_archived.unwrap_or(false))
+}
+
+/// A window of the provider list for large sets; see `providers::get_page`.
+#[tauri::command]
+async fn list_providers_page(
+    state: State<'_, AppState>,
+    offset: u32,
+    limit: u32,
+    query: Option<String>,
+    tag: Option<String>,
+    include_archived: Option<bool>,
+) -> Result<providers::ProviderPage, String> {
+    providers::get_page(
+        &state.db,
+        offset,
+        limit,
+        query.as_deref(),
+        tag.as_deref(),
+        include_archived.unwrap_or(false),
+    )
 }
 
 #[tauri::command]
@@ -658,44 +1164,274 @@ async fn get_current_provider(
 async fn save_provider(
     state: State<'_, AppState>,
     provider: providers::ProviderRecord,
-) -> Result<(), String> {
+) -> Result<SaveProviderResult, CommandError> {
     // Validate at the Tauri command boundary before touching the DB.
+    validate_provider(&provider)?;
+
+    let current = providers::get_current(&state.db)?.filter(|c| c.id == provider.id);
+    providers::save(&state.db, &provider)?;
+    // The reference may now point elsewhere, or the user fixed the secret
+    secrets::clear_cache();
+
+    let needs_sync = current.is_some_and(|before| sync_fields_changed(&before, &provider))
+        && settings::get_bool(&state.db, AUTO_SYNC_ON_SAVE_KEY, false);
+    let sync = if needs_sync {
+        tracing::info!("[save_provider] Current provider changed, re-syncing installed apps");
+        let _lock = state.sync_lock.acquire().await?;
+        Some(sync_provider_to_installed(&state.db, &provider).await?)
+    } else {
+        None
+    };
+
+    Ok(SaveProviderResult { saved: true, sync })
+}
+
+pub(crate) fn validate_provider(provider: &providers::ProviderRecord) -> Result<(), CommandError> {
+    check_provider(provider, true)
+}
+
+/// `validate_provider` for a record that may not have a key yet, such as one
+/// from a backup exported without keys.
+pub(crate) fn validate_keyless_provider(
+    provider: &providers::ProviderRecord,
+) -> Result<(), CommandError> {
+    check_provider(provider, false)
+}
+
+fn check_provider(
+    provider: &providers::ProviderRecord,
+    require_key: bool,
+) -> Result<(), CommandError> {
     if provider.name.trim().is_empty() {
-        return Err("Provider name cannot be empty".to_string());
+        return Err(CommandError::invalid_input("Provider name cannot be empty"));
     }
-    utils::validate_url(&provider.url).map_err(|e| e.to_string())?;
-    if provider.api_key.trim().is_empty() {
-        return Err("API key cannot be empty".to_string());
+    utils::validate_url(&provider.url)?;
+    if require_key && provider.api_key.trim().is_empty() {
+        return Err(CommandError::invalid_input("API key cannot be empty"));
     }
+    // op:// and keychain:// references are only resolved when syncing
+    secrets::validate_reference(&provider.api_key).map_err(CommandError::invalid_input)?;
     // Validate per_cli_models is valid JSON (prevents corrupted DB rows).
     serde_json::from_str::<serde_json::Value>(&provider.per_cli_models)
-        .map_err(|_| "per_cli_models must be valid JSON".to_string())?;
+        .map_err(|_| CommandError::invalid_input("per_cli_models must be valid JSON"))?;
+    parse_opencode_adapter(Some(&provider.opencode_adapter))?;
+    ModelAliases::parse(&provider.model_aliases).map_err(CommandError::invalid_input)?;
+    ProviderAdvanced::parse(&provider.advanced).map_err(CommandError::invalid_input)?;
+    Ok(())
+}
 
-    providers::save(&state.db, &provider)
+/// Save one of an account's tokens (default: the active account) as a
+/// provider that remembers the account, optionally switching to it right away.
+#[tauri::command]
+async fn create_provider_from_token(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    account_state: State<'_, account::AccountState>,
+    token_id: i64,
+    name: Option<String>,
+    switch: Option<bool>,
+    account_id: Option<i64>,
+) -> Result<providers::ProviderRecord, CommandError> {
+    let (account_id, base_url) = account::session_base_url(&account_state, account_id)?;
+    let token = account::fetch_token(&account_state, Some(account_id), token_id).await?;
+    let record = token.into_record(account_id, &base_url, name.as_deref());
+    validate_provider(&record)?;
+    providers::save(&state.db, &record)?;
+    if let Some(quota) = &record.quota {
+        providers::set_quota(&state.db, &record.id, quota)?;
+    }
+    tracing::info!(
+        "[account] Saved provider {} from token #{}",
+        record.name,
+        token_id
+    );
+
+    if switch.unwrap_or(false) {
+        switch_to_provider(&app, &state, record.clone()).await?;
+    }
+    Ok(record)
 }
 
+/// Fetch the used/remaining quota of a provider's linked account token and
+/// cache it on the provider row. `None` for providers without a linked token.
 #[tauri::command]
-async fn delete_provider(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    providers::delete(&state.db, &id)
+async fn refresh_provider_quota(
+    state: State<'_, AppState>,
+    account_state: State<'_, account::AccountState>,
+    provider_id: String,
+) -> Result<Option<providers::TokenQuota>, CommandError> {
+    let provider = providers::get_all(&state.db, true)?
+        .into_iter()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| {
+            CommandError::new(
+                "PROVIDER_NOT_FOUND",
+                format!("Provider not found: {provider_id}"),
+            )
+        })?;
+    let (Some(base_url), Some(token_id)) = (&provider.account_base_url, provider.token_id) else {
+        return Ok(None);
+    };
+
+    let account_id = account::account_for_base(&account_state, provider.account_id, base_url)?;
+    let token = account::fetch_token(&account_state, Some(account_id), token_id).await?;
+    let quota = token.quota();
+    providers::set_quota(&state.db, &provider.id, &quota)?;
+    Ok(Some(quota))
 }
 
+/// Links received so far (including the one the app was launched with) that
+/// the UI hasn't handled yet.
 #[tauri::command]
-async fn reorder_providers(state: State<'_, AppState>, ids: Vec<String>) -> Result<(), String> {
-    providers::reorder(&state.db, &ids)
+fn take_pending_deep_links(
+    pending: State<'_, deep_link::PendingLinks>,
+) -> Vec<deep_link::PendingLink> {
+    pending.take()
 }
 
+/// Save a provider from a `hajimi://add-provider` link after the user confirmed it.
 #[tauri::command]
-async fn switch_provider(state: State<'_, AppState>, id: String) -> Result<SwitchResult, String> {
-    // Load the target provider upfront so we fail fast if it doesn't exist.
-    let target = providers::get_all(&state.db)?
+async fn confirm_deep_link_provider(
+    state: State<'_, AppState>,
+    link: deep_link::ProviderLink,
+) -> Result<providers::ProviderRecord, CommandError> {
+    link.validate().map_err(CommandError::invalid_input)?;
+    let record = link.into_record();
+    providers::save(&state.db, &record)?;
+    tracing::info!("[deep_link] Saved provider {} from link", record.name);
+    Ok(record)
+}
+
+/// Proxy settings already present in an app's config, for pre-filling a provider.
+#[tauri::command]
+async fn detect_existing_provider(app: String) -> Result<Option<DetectedProvider>, CommandError> {
+    provider_import::detect(&app).map_err(|_| CommandError::unknown_app(&app))
+}
+
+/// Save the provider detected in `app` under `name`.
+#[tauri::command]
+async fn import_provider_from_app(
+    state: State<'_, AppState>,
+    app: String,
+    name: String,
+) -> Result<providers::ProviderRecord, CommandError> {
+    if name.trim().is_empty() {
+        return Err(CommandError::invalid_input("Provider name cannot be empty"));
+    }
+    let detected = provider_import::detect(&app)
+        .map_err(|_| CommandError::unknown_app(&app))?
+        .ok_or_else(|| {
+            CommandError::new(
+                "NOTHING_TO_IMPORT",
+                format!("No proxy URL and API key found in {app}'s config"),
+            )
+            .with_details(serde_json::json!({ "app": app }))
+        })?;
+    let record = detected.into_record(&name);
+    // Same checks as a hand-entered provider; a config may hold a bad reference
+    validate_provider(&record)?;
+    providers::save(&state.db, &record)?;
+    tracing::info!("[import] Saved provider {} from {}", record.name, app);
+    Ok(record)
+}
+
+/// Whether an edit touches anything that ends up in synced config files.
+fn sync_fields_changed(
+    before: &providers::ProviderRecord,
+    after: &providers::ProviderRecord,
+) -> bool {
+    before.url != after.url
+        || before.api_key != after.api_key
+        || before.default_model != after.default_model
+        || before.per_cli_models != after.per_cli_models
+        || before.opencode_adapter != after.opencode_adapter
+        || before.model_aliases != after.model_aliases
+        || before.advanced != after.advanced
+}
+
+/// The model id `app` would get from `provider_id` for `model`, so the UI
+/// can preview an alias before syncing.
+#[tauri::command]
+async fn resolve_model_alias(
+    state: State<'_, AppState>,
+    provider_id: String,
+    model: String,
+    app: String,
+) -> Result<String, CommandError> {
+    if !is_known_app(&app) {
+        return Err(CommandError::unknown_app(&app));
+    }
+    let provider = providers::get_all(&state.db, true)?
         .into_iter()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| {
+            CommandError::new(
+                "PROVIDER_NOT_FOUND",
+                format!("Provider not found: {provider_id}"),
+            )
+            .with_details(serde_json::json!({ "id": provider_id }))
+        })?;
+    let aliases = ModelAliases::parse_lenient(&provider.model_aliases);
+    Ok(aliases.resolve(model.trim(), &app))
+}
+
+/// Archive a provider: it leaves the list but keeps its key until purged.
+#[tauri::command]
+async fn delete_provider(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    providers::archive(&state.db, &id)
+}
+
+#[tauri::command]
+async fn restore_archived_provider(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    providers::restore(&state.db, &id)
+}
+
+/// Delete a provider (archived or not) for good.
+#[tauri::command]
+async fn purge_provider(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let all = providers::get_all(&state.db, true)?;
+    let url = all
+        .iter()
         .find(|p| p.id == id)
-        .ok_or_else(|| format!("Provider not found: {id}"))?;
+        .map(|p| p.url.trim_end_matches('/').to_string());
+    providers::purge(&state.db, &id)?;
+
+    // Cached model lists of a URL no other provider uses are dead weight
+    if let Some(url) = url {
+        let shared = all
+            .iter()
+            .any(|p| p.id != id && p.url.trim_end_matches('/') == url);
+        if !shared {
+            if let Err(e) = model_list::forget_cached_url(&state.db, &url) {
+                tracing::warn!("[model_list] Failed to drop cached models: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn reorder_providers(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<(), CommandError> {
+    Ok(providers::reorder(&state.db, &ids)?)
+}
 
+/// Sync `target` to every installed app, snapshotting each config into
+/// `config_backup` first so a crash mid-sync can be recovered on next launch.
+/// Fails before touching any file when the key reference can't be resolved.
+async fn sync_provider_to_installed(
+    db: &database::Database,
+    target: &providers::ProviderRecord,
+) -> Result<SwitchResult, CommandError> {
+    let api_key = secrets::resolve(&target.api_key).await?;
     let per_cli: std::collections::HashMap<String, String> =
         serde_json::from_str(&target.per_cli_models).unwrap_or_default();
 
-    let effective_model_for = |app_name: &str| -> Option<String> {
+    let aliases = ModelAliases::parse_lenient(&target.model_aliases);
+    let advanced = advanced_for(Some(target));
+    let model_for = |app_name: &str| -> Option<String> {
         per_cli
             .get(app_name)
             .filter(|m| !m.is_empty())
@@ -707,130 +1443,137 @@ async fn switch_provider(state: State<'_, AppState>, id: String) -> Result<Switc
                     Some(target.default_model.clone())
                 }
             })
+            .map(|m| aliases.resolve(&m, app_name))
     };
+    let small_model = per_cli
+        .get(opencode_sync::SMALL_MODEL_KEY)
+        .map(|m| aliases.resolve(m, "opencode"));
 
-    let all_apps = ["claude", "codex", "gemini", "opencode", "openclaw", "droid"];
-    let mut errors: Vec<SyncResult> = Vec::new();
+    // Stored ids were validated on save; fall back to the default if one slipped through.
+    let opencode_adapter =
+        parse_opencode_adapter(Some(&target.opencode_adapter)).unwrap_or_default();
 
     // ── Phase 1: read-then-backup existing config content, then sync ─────────
-    // For each installed app we:
-    //   a) Read the current config content from disk.
-    //   b) Persist it to config_backup (INSERT OR IGNORE — never clobbers).
-    //   c) Sync the new provider config.
-    //   d) On success: delete that app's backup row.
+    // For each installed app `sync_installed`:
+    //   0) Skips the app if its files were edited by hand and the policy says so.
+    //   a) Reads the current config content from disk.
+    //   b) Persists it to config_backup (INSERT OR IGNORE — never clobbers).
+    //   c) Syncs the new provider config.
+    //   d) On success: deletes that app's backup row.
     //   On crash between b and d the row stays, triggering recovery on next launch.
+    let plan = BulkSync {
+        url: &target.url,
+        api_key: &api_key,
+        model_for: &model_for,
+        small_model: small_model.as_deref(),
+        opencode_adapter,
+        droid_overrides: None,
+        aliases: &aliases,
+        advanced: &advanced,
+        provider_id: Some(&target.id),
+        snapshot: true,
+    };
+    let results = sync_target::sync_installed(db, &sync_target::all(), &plan).await;
 
-    for app_name in &all_apps {
-        if !is_installed(app_name) {
-            continue;
-        }
-
-        let proxy_url = get_proxy_url(app_name, &target.url);
-        let model = effective_model_for(app_name);
-        let model_ref = model.as_deref();
-
-        // a+b) Read current config and persist to DB before we touch the file.
-        let snapshot = read_config_snapshot(app_name);
-        if let Some(content) = snapshot {
-            if let Err(e) = backup::save_backup(&state.db, app_name, &content) {
-                tracing::warn!("[switch] backup write failed for {}: {}", app_name, e);
-            }
-        }
-
-        // c) Sync.
-        let result: Result<(), String> = match *app_name {
-            "claude" | "codex" | "gemini" => match get_cli_app(app_name) {
-                Some(cli_app) => {
-                    cli_sync::sync_config(&cli_app, &proxy_url, &target.api_key, model_ref)
-                }
-                None => Err(format!("Invalid app: {app_name}")),
-            },
-            "opencode" => opencode_sync::sync_opencode_config(&proxy_url, &target.api_key).await,
-            "openclaw" => {
-                openclaw_sync::sync_openclaw_config(&proxy_url, &target.api_key, model_ref).await
-            }
-            "droid" => {
-                droid_sync::sync_droid_config(&proxy_url, &target.api_key, model_ref).map(|_| ())
-            }
-            _ => Ok(()),
-        };
-
-        // d) Clean up backup on success; keep it on failure (crash-safe).
-        match result {
-            Ok(()) => {
-                let _ = backup::delete_backup(&state.db, app_name);
-            }
-            Err(e) => {
-                tracing::error!("[switch] sync failed for {}: {}", app_name, e);
-                errors.push(SyncResult {
-                    app: app_name.to_string(),
-                    success: false,
-                    error: Some(e),
-                });
-            }
+    let mut errors: Vec<SyncResult> = Vec::new();
+    let mut warnings: Vec<SyncResult> = Vec::new();
+    let mut skipped: Vec<SyncResult> = Vec::new();
+    let mut manual: Vec<SyncResult> = Vec::new();
+    for result in results {
+        if result.skipped {
+            skipped.push(result);
+        } else if result.manual {
+            manual.push(result);
+        } else if !result.success {
+            errors.push(result);
+        } else if result.warning.is_some() {
+            warnings.push(result);
         }
     }
 
-    // ── Extra clients (file-sync capable only) ────────────────────────────────
-    for client in ExtraClient::all() {
-        if !client.supports_file_sync() {
-            continue;
-        }
-        let app_name = client.as_str();
-        if !extra_clients::check_extra_installed(client).0 {
-            continue;
-        }
-
-        let proxy_url = get_proxy_url(app_name, &target.url);
-        let model = effective_model_for(app_name);
-        let model_ref = model.as_deref();
-
-        if let Ok(content) = extra_clients::read_extra_config_content(client) {
-            if let Err(e) = backup::save_backup(&state.db, app_name, &content) {
-                tracing::warn!("[switch] backup write failed for {}: {}", app_name, e);
-            }
-        }
+    Ok(SwitchResult {
+        success: errors.is_empty(),
+        errors,
+        warnings,
+        skipped,
+        manual,
+    })
+}
 
-        let result =
-            extra_clients::sync_extra_config(client, &proxy_url, &target.api_key, model_ref);
+#[tauri::command]
+async fn switch_provider(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<SwitchResult, CommandError> {
+    // Load the target provider upfront so we fail fast if it doesn't exist.
+    // Archived providers are never switch targets.
+    let target = providers::get_all(&state.db, false)?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| {
+            CommandError::new("PROVIDER_NOT_FOUND", format!("Provider not found: {id}"))
+                .with_details(serde_json::json!({ "id": id }))
+        })?;
+    switch_to_provider(&app, &state, target).await
+}
 
-        match result {
-            Ok(()) => {
-                let _ = backup::delete_backup(&state.db, app_name);
-            }
-            Err(e) => {
-                tracing::error!("[switch] sync failed for {}: {}", app_name, e);
-                errors.push(SyncResult {
-                    app: app_name.to_string(),
-                    success: false,
-                    error: Some(e),
-                });
-            }
-        }
-    }
+/// Sync `target` to the installed apps and make it the current provider.
+async fn switch_to_provider(
+    app: &AppHandle,
+    state: &AppState,
+    target: providers::ProviderRecord,
+) -> Result<SwitchResult, CommandError> {
+    let id = target.id.as_str();
+    let _lock = state.sync_lock.acquire().await?;
+    let result = sync_provider_to_installed(&state.db, &target).await?;
 
     // ── Phase 2: commit new current provider ──────────────────────────────────
     // This runs regardless of individual sync errors so the UI always reflects
     // which provider was targeted.  Partial failures are surfaced in `errors`.
-    providers::set_current(&state.db, &id)?;
+    providers::set_current(&state.db, id)?;
+    rotation::record_switch(&state.db);
+    state.health.wake();
+    state.rotation.wake();
+
+    if !result.errors.is_empty() {
+        let apps: Vec<&str> = result
+            .errors
+            .iter()
+            .map(|e| sync_target::find(&e.app).map_or(e.app.as_str(), |t| t.display_name()))
+            .collect();
+        notify::notify(
+            app,
+            &format!("Switched to {} with errors", target.name),
+            &format!("Sync failed for: {}", apps.join(", ")),
+        );
+    }
 
-    Ok(SwitchResult {
-        success: errors.is_empty(),
-        errors,
-    })
+    Ok(result)
 }
 
-/// Read the primary config snapshot for an app (best-effort, returns None on
-/// any error so backup failures never abort a switch).
-fn read_config_snapshot(app_name: &str) -> Option<String> {
-    match app_name {
-        "claude" | "codex" | "gemini" => {
-            get_cli_app(app_name).and_then(|a| cli_sync::read_config_content(&a, None).ok())
-        }
-        "opencode" => opencode_sync::read_opencode_config_content().ok(),
-        "openclaw" => openclaw_sync::read_openclaw_config_content().ok(),
-        "droid" => droid_sync::read_droid_config_content().ok(),
-        _ => None,
+/// One-time rename of backups written under the old suffix, next to every
+/// config file an app's sync manages. Runs before crash recovery so its
+/// on-disk fallback finds them under the new name.
+fn migrate_legacy_backups(db: &database::Database) {
+    if settings::get_bool(db, LEGACY_BACKUPS_MIGRATED_KEY, false) {
+        return;
+    }
+    let renamed: usize = status_apps()
+        .into_iter()
+        .flat_map(drift::managed_files)
+        .map(|path| utils::migrate_legacy_backups(&path))
+        .sum();
+    if renamed > 0 {
+        tracing::info!(
+            "[backup] Renamed {} {} backup(s) to {}",
+            renamed,
+            utils::LEGACY_BACKUP_SUFFIX,
+            utils::BACKUP_SUFFIX
+        );
+    }
+    if let Err(e) = settings::set_bool(db, LEGACY_BACKUPS_MIGRATED_KEY, true) {
+        tracing::warn!("[backup] Failed to record backup migration: {}", e);
     }
 }
 
@@ -842,12 +1585,13 @@ fn read_config_snapshot(app_name: &str) -> Option<String> {
 ///      restore logic (which uses the on-disk `.bak` file).
 ///   3. Only delete the backup row when restore succeeds.
 ///   4. Failed rows are left intact so the next launch can retry.
-fn recover_from_crash(db: &database::Database) {
+fn recover_from_crash(db: &database::Database) -> RecoveryReport {
+    let mut report = RecoveryReport::default();
     let app_types = match backup::list_app_types(db) {
         Ok(v) => v,
         Err(e) => {
             tracing::error!("recover_from_crash: list_app_types failed: {}", e);
-            return;
+            return report;
         }
     };
 
@@ -867,9 +1611,12 @@ fn recover_from_crash(db: &database::Database) {
         match result {
             Ok(()) => {
                 tracing::info!("Crash recovery succeeded for {}", app_type);
+                drift::forget(db, app_type);
+                synced_providers::forget(db, app_type);
                 if let Err(e) = backup::delete_backup(db, app_type) {
                     tracing::error!("delete_backup after recovery for {}: {}", app_type, e);
                 }
+                report.restored.push(app_type.clone());
             }
             Err(e) => {
                 // Leave the row — next launch will retry.
@@ -878,123 +1625,361 @@ fn recover_from_crash(db: &database::Database) {
                     app_type,
                     e
                 );
+                report.failed.push(app_type.clone());
             }
         }
     }
+    report
 }
 
-/// Write a raw snapshot string back to the appropriate config location.
-fn restore_from_snapshot(app_type: &str, content: &str) -> Result<(), String> {
-    match app_type {
-        "claude" | "codex" | "gemini" => {
-            let cli_app =
-                get_cli_app(app_type).ok_or_else(|| format!("Unknown cli app: {app_type}"))?;
-            // Use the first config file for this app.
-            let files = cli_app.config_files();
-            let file_name = files.first().ok_or("No config files defined")?.name.clone();
-            cli_sync::write_config_content(&cli_app, &file_name, content)
+/// Apps touched by crash recovery, reported to the user once the app is up.
+#[derive(Debug, Default)]
+struct RecoveryReport {
+    restored: Vec<String>,
+    failed: Vec<String>,
+}
+
+impl RecoveryReport {
+    fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if !self.restored.is_empty() {
+            parts.push(format!("Restored: {}", self.restored.join(", ")));
         }
-        "opencode" => opencode_sync::write_opencode_config_content(content),
-        "openclaw" => openclaw_sync::write_openclaw_config_content(content),
-        "droid" => droid_sync::write_droid_config_content(content),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                let files = client.config_files_display();
-                let file_name = files.into_iter().next().unwrap_or_default();
-                extra_clients::write_extra_config_content(&client, &file_name, content)
-            } else {
-                Err(format!("Unknown app type in crash recovery: {other}"))
-            }
+        if !self.failed.is_empty() {
+            parts.push(format!("Will retry: {}", self.failed.join(", ")));
         }
+        (!parts.is_empty()).then(|| parts.join("\n"))
     }
 }
 
+/// Problems opening the database at startup, kept for `get_startup_diagnostics`.
+#[derive(Debug, Clone, Default, Serialize)]
+struct StartupDiagnostics {
+    db_path: String,
+    /// Set when a corrupted database was moved aside and replaced.
+    db_recovery: Option<database::DbRecovery>,
+    /// Set when no database file could be opened; everything then lives in
+    /// memory and is lost on exit.
+    db_error: Option<String>,
+}
+
+#[tauri::command]
+async fn get_startup_diagnostics(
+    diagnostics: State<'_, StartupDiagnostics>,
+) -> Result<StartupDiagnostics, String> {
+    Ok(diagnostics.inner().clone())
+}
+
+/// Write a raw snapshot string back to the app's main config file.
+fn restore_from_snapshot(app_type: &str, content: &str) -> Result<(), String> {
+    let target = sync_target::find(app_type)
+        .ok_or_else(|| format!("Unknown app type in crash recovery: {app_type}"))?;
+    let file_name = target
+        .config_files()
+        .into_iter()
+        .next()
+        .ok_or("No config files defined")?;
+    target.write_content(&file_name, content)
+}
+
 /// Fallback restore via each module's own restore function (uses on-disk .bak).
 fn restore_via_module(app_type: &str) -> Result<(), String> {
-    match app_type {
-        "claude" | "codex" | "gemini" => {
-            if let Some(cli_app) = get_cli_app(app_type) {
-                cli_sync::restore_config(&cli_app)
-            } else {
-                Ok(())
-            }
-        }
-        "opencode" => opencode_sync::restore_opencode_config(),
-        "openclaw" => openclaw_sync::restore_openclaw_config(),
-        "droid" => droid_sync::restore_droid_config(),
-        other => {
-            if let Some(client) = ExtraClient::from_str(other) {
-                extra_clients::restore_extra_config(&client)
-            } else {
-                Ok(())
-            }
-        }
+    match sync_target::find(app_type) {
+        Some(target) => target.restore(),
+        None => Ok(()),
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
+    // Held for the whole run so buffered log lines are flushed on exit.
+    let _log_guard = logging::init();
 
     // Initialise SQLite database
-    let db_path = dirs::data_local_dir()
-        .or_else(dirs::home_dir)
-        .map(|p| p.join("hajimi-cli-sync").join("providers.db"))
-        .expect("Cannot determine data dir");
+    let db_path = database::Database::default_path().expect("Cannot determine data dir");
+
+    let mut diagnostics = StartupDiagnostics {
+        db_path: db_path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let db = match database::Database::init(&db_path) {
+        Ok((db, recovery)) => {
+            diagnostics.db_recovery = recovery;
+            db
+        }
+        Err(e) => {
+            tracing::error!("DB init failed ({}), falling back to in-memory DB", e);
+            diagnostics.db_error = Some(e);
+            database::Database::memory().expect("In-memory DB init failed")
+        }
+    };
 
-    let db = database::Database::init(&db_path).unwrap_or_else(|e| {
-        tracing::error!("DB init failed ({}), falling back to in-memory DB", e);
-        database::Database::memory().expect("In-memory DB init failed")
-    });
+    if let Err(e) = config_paths::load(&db) {
+        tracing::warn!("Failed to load config path overrides: {}", e);
+    }
+    cli_sync::load_codex_profile_mode(&db);
+    cli_sync::load_gemini_force_auth(&db);
+    system_check::load_http_proxy(&db);
+    system_check::load_http_tls(&db);
+    wsl::load(&db);
+    migrate_legacy_backups(&db);
 
     // Crash recovery
-    if db.has_any_backup().unwrap_or(false) {
+    let recovery = if db.has_any_backup().unwrap_or(false) {
         tracing::info!("Crash backup detected — running recovery");
-        recover_from_crash(&db);
-    }
+        recover_from_crash(&db)
+    } else {
+        RecoveryReport::default()
+    };
 
-    let app_state = AppState { db: Arc::new(db) };
+    let db = Arc::new(db);
+    let account_state =
+        match crypto::default_key_path().map(|p| crypto::SecretKey::load_or_create(&p)) {
+            Some(Ok(key)) => account::AccountState::persistent(db.clone(), key),
+            Some(Err(e)) => {
+                tracing::warn!("[account] {} — the session won't survive a restart", e);
+                account::AccountState::new()
+            }
+            None => account::AccountState::new(),
+        };
+    let app_state = AppState::new(db);
+    let db_recovery = diagnostics.db_recovery.clone();
+
+    let mut builder = tauri::Builder::default();
+    // Must come first. A second launch (e.g. clicking a hajimi:// link while
+    // the app runs on Windows or Linux) exits and hands its argv to this
+    // instance; the deep-link feature forwards the URLs in it to
+    // `on_open_url` below, i.e. to `deep_link::handle_url`.
+    #[cfg(desktop)]
+    {
+        use tauri::Manager;
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }));
+    }
 
-    tauri::Builder::default()
+    builder
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
-        .manage(account::AccountState::new())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .manage(account_state)
         .manage(app_state)
+        .manage(auto_installer::InstallJobs::default())
+        .manage(diagnostics)
+        .manage(deep_link::PendingLinks::default())
+        .setup(move |app| {
+            if let Some(body) = recovery.summary() {
+                notify::notify(app.handle(), "Recovered from an interrupted switch", &body);
+            }
+            if let Some(db_recovery) = &db_recovery {
+                notify::notify(
+                    app.handle(),
+                    "Provider database was repaired",
+                    &db_recovery.summary(),
+                );
+            }
+
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                // Linux and Windows dev builds need the scheme registered at runtime.
+                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    tracing::warn!("[deep_link] Failed to register URI scheme: {}", e);
+                }
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    for url in event.urls() {
+                        deep_link::handle_url(&handle, url.as_str());
+                    }
+                });
+                // The link the app was launched with, if any
+                match app.deep_link().get_current() {
+                    Ok(urls) => {
+                        for url in urls.unwrap_or_default() {
+                            deep_link::handle_url(app.handle(), url.as_str());
+                        }
+                    }
+                    Err(e) => tracing::warn!("[deep_link] Failed to read launch URL: {}", e),
+                }
+            }
+
+            watcher::restart(app.handle());
+            health::start(app.handle());
+            rotation::start(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            get_startup_diagnostics,
             get_all_cli_status,
+            get_status_if_changed,
             sync_cli,
             sync_all,
             restore_cli,
             get_config_content,
             write_config_file,
             fetch_models,
+            fetch_models_detailed,
+            set_model_cache_ttl,
             test_connection,
+            test_connection_deep,
+            test_connection_for,
             system_check::get_system_status,
+            system_check::get_proxy_settings,
+            system_check::set_proxy_settings,
+            system_check::test_proxy_settings,
+            system_check::get_tls_settings,
+            system_check::set_tls_settings,
+            wsl::list_wsl_distros,
+            wsl::get_wsl_target,
+            wsl::set_wsl_target,
+            diagnose::diagnose,
+            app_update::check_app_update,
+            app_update::get_update_check_enabled,
+            app_update::set_update_check_enabled,
             auto_installer::auto_install_dependencies,
             auto_installer::install_cli_tool,
+            auto_installer::update_cli_tool,
+            auto_installer::uninstall_cli_tool,
+            auto_installer::check_cli_updates,
+            auto_installer::cancel_install,
             open_external_url,
             open_config_folder,
+            reveal_config_file,
             launch_app,
             account::check_platform,
             account::account_login,
+            create_provider_from_token,
+            refresh_provider_quota,
+            account::account_register,
+            account::account_send_email_code,
             account::account_get_tokens,
+            account::account_get_self_quota,
+            account::account_get_usage_logs,
             account::account_check_session,
             account::account_restore_session,
             account::account_logout,
+            account::account_list,
+            account::account_switch_active,
             // Provider management
             list_providers,
+            list_providers_page,
             get_current_provider,
             save_provider,
             delete_provider,
+            restore_archived_provider,
+            purge_provider,
             switch_provider,
+            rotation::get_rotation_status,
+            rotation::set_rotation_config,
+            resolve_model_alias,
+            backup_files::list_all_backups,
+            backup_files::preview_backup,
+            backup_files::prune_backups,
+            config_diff::diff_config_with_backup,
+            config_validation::validate_configs,
             reorder_providers,
+            // Config path overrides
+            get_config_path_overrides,
+            set_config_path_override,
+            clear_config_path_override,
+            get_recent_logs,
+            export_logs,
+            bundle::export_bundle,
+            bundle::import_bundle,
+            env_exports::generate_env_exports,
+            env_exports::generate_lobechat_env,
+            env_exports::write_envrc,
+            extension_profiles::generate_extension_profile,
+            extension_profiles::save_extension_profile,
+            project_sync::sync_project_config,
+            project_sync::get_project_sync_status,
+            project_sync::restore_project_config,
+            project_sync::get_recent_project_dirs,
+            get_drift_policy,
+            set_drift_policy,
+            get_download_mirror,
+            set_download_mirror,
+            get_package_managers,
+            set_package_managers,
+            get_sillytavern_dir,
+            set_sillytavern_dir,
+            get_pinned_apps,
+            pin_app,
+            unpin_app,
+            get_network_probe_url,
+            set_network_probe_url,
+            get_codex_profile_mode,
+            set_codex_profile_mode,
+            get_gemini_force_auth,
+            set_gemini_force_auth,
+            get_health_poll_interval,
+            set_health_poll_interval,
+            get_provider_health_snapshot,
+            get_notifications_enabled,
+            set_notifications_enabled,
+            get_auto_sync_on_save,
+            set_auto_sync_on_save,
+            take_pending_deep_links,
+            confirm_deep_link_provider,
+            detect_existing_provider,
+            import_provider_from_app,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                watcher::stop(handle);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sillytavern_dir_keeps_user_sync_path() {
+        let derived = std::path::Path::new("/opt/ST/data/default-user/secrets.json");
+        assert!(follows_sillytavern_dir(None, None));
+        assert!(follows_sillytavern_dir(None, Some(derived)));
+        assert!(follows_sillytavern_dir(Some(derived), Some(derived)));
+        let mine = std::path::Path::new("/srv/tavern/secrets.json");
+        assert!(!follows_sillytavern_dir(Some(mine), None));
+        assert!(!follows_sillytavern_dir(Some(mine), Some(derived)));
+    }
+
+    #[test]
+    fn test_imported_provider_is_validated() {
+        let record = |key: &str| {
+            provider_import::DetectedProvider::new("codex", "https://proxy.test", key, None)
+                .unwrap()
+                .into_record("Imported")
+        };
+        assert!(validate_provider(&record("sk-1")).is_ok());
+        assert!(validate_provider(&record("op://vault")).is_err());
+    }
+
+    #[test]
+    fn test_sync_fields_changed_covers_advanced() {
+        let before = deep_link::ProviderLink {
+            name: "p".to_string(),
+            url: "https://x.com".to_string(),
+            api_key: "k".to_string(),
+            model: None,
+        }
+        .into_record();
+        let mut after = before.clone();
+        after.notes = Some("just a note".to_string());
+        assert!(!sync_fields_changed(&before, &after));
+
+        after.advanced = r#"{"api_timeout_ms": 600000}"#.to_string();
+        assert!(sync_fields_changed(&before, &after));
+    }
 }
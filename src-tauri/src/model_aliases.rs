@@ -0,0 +1,140 @@
+//! Per-provider model id aliases: gateways spell the same model differently
+//! (`anthropic/claude-sonnet-4.5` vs Claude Code's `claude-sonnet-4-5`), so a
+//! provider can map a canonical id to the id each app or protocol should get.
+//!
+//! Stored as JSON on the provider row:
+//! `{"claude-sonnet-4-5": {"openai": "anthropic/claude-sonnet-4.5"}}`.
+//! An app id key (`"codex"`) wins over a protocol key (`"openai"`).
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+/// Wire protocols apps speak to the proxy; the alias keys besides app ids.
+pub const PROTOCOLS: [&str; 3] = ["anthropic", "openai", "gemini"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelAliases(BTreeMap<String, BTreeMap<String, String>>);
+
+impl ModelAliases {
+    /// Parse and validate a provider's `model_aliases` column; `""` is empty.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| format!("model_aliases must be valid JSON: {e}"))?;
+        let Value::Object(entries) = value else {
+            return Err("model_aliases must be a JSON object".to_string());
+        };
+
+        let mut aliases = BTreeMap::new();
+        for (model, targets) in entries {
+            if model.trim().is_empty() {
+                return Err("model_aliases has an empty model id".to_string());
+            }
+            let Value::Object(targets) = targets else {
+                return Err(format!(
+                    "model_aliases.{model} must map app or protocol names to model ids"
+                ));
+            };
+            let mut map = BTreeMap::new();
+            for (target, alias) in targets {
+                if !PROTOCOLS.contains(&target.as_str()) && !crate::is_known_app(&target) {
+                    return Err(format!(
+                        "model_aliases.{model}: unknown app or protocol \"{target}\""
+                    ));
+                }
+                match alias.as_str().map(str::trim) {
+                    Some(alias) if !alias.is_empty() && !alias.contains(char::is_whitespace) => {
+                        map.insert(target, alias.to_string());
+                    }
+                    _ => return Err(format!("model_aliases.{model}.{target} must be a model id")),
+                }
+            }
+            aliases.insert(model, map);
+        }
+        Ok(Self(aliases))
+    }
+
+    /// Like `parse`, for stored rows: a bad value is logged and ignored.
+    pub fn parse_lenient(json: &str) -> Self {
+        Self::parse(json).unwrap_or_else(|e| {
+            tracing::warn!("[model_aliases] Ignoring stored aliases: {}", e);
+            Self::default()
+        })
+    }
+
+    /// The id to write for `app`: its own alias, else its protocol's, else `model`.
+    pub fn resolve(&self, model: &str, app: &str) -> String {
+        self.0
+            .get(model)
+            .and_then(|targets| targets.get(app).or_else(|| targets.get(protocol_for(app))))
+            .cloned()
+            .unwrap_or_else(|| model.to_string())
+    }
+
+    /// `resolve` over a catalog, keeping order and dropping duplicates that
+    /// two canonical ids aliased to the same id would create.
+    pub fn resolve_all(&self, ids: &[String], app: &str) -> Vec<String> {
+        let mut out: Vec<String> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let resolved = self.resolve(id, app);
+            if !out.contains(&resolved) {
+                out.push(resolved);
+            }
+        }
+        out
+    }
+}
+
+/// Protocol `app` talks to the proxy with.
+pub fn protocol_for(app: &str) -> &'static str {
+    match app {
+        "claude" => "anthropic",
+        "gemini" => "gemini",
+        _ => "openai",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_prefers_app_over_protocol() {
+        let aliases = ModelAliases::parse(
+            r#"{"claude-sonnet-4-5": {"openai": "anthropic/claude-sonnet-4.5", "droid": "sonnet"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            aliases.resolve("claude-sonnet-4-5", "codex"),
+            "anthropic/claude-sonnet-4.5"
+        );
+        assert_eq!(aliases.resolve("claude-sonnet-4-5", "droid"), "sonnet");
+        assert_eq!(
+            aliases.resolve("claude-sonnet-4-5", "claude"),
+            "claude-sonnet-4-5"
+        );
+        assert_eq!(aliases.resolve("gpt-5", "codex"), "gpt-5");
+
+        let ids = vec!["claude-sonnet-4-5".to_string(), "sonnet".to_string()];
+        assert_eq!(aliases.resolve_all(&ids, "droid"), vec!["sonnet"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_shapes() {
+        assert_eq!(ModelAliases::parse("").unwrap(), ModelAliases::default());
+        assert_eq!(ModelAliases::parse("{}").unwrap(), ModelAliases::default());
+        assert!(ModelAliases::parse("[]").is_err());
+        assert!(ModelAliases::parse(r#"{"m": "x"}"#).is_err());
+        assert!(ModelAliases::parse(r#"{"m": {"vim": "x"}}"#).is_err());
+        assert!(ModelAliases::parse(r#"{"m": {"openai": ""}}"#).is_err());
+        assert!(ModelAliases::parse(r#"{"m": {"openai": 3}}"#).is_err());
+        assert!(ModelAliases::parse(r#"{"m": {"codex": "a b"}}"#).is_err());
+        assert_eq!(
+            ModelAliases::parse_lenient("not json"),
+            ModelAliases::default()
+        );
+    }
+}
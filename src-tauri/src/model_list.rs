@@ -0,0 +1,977 @@
+//! Model listing for the proxy model picker.
+//!
+//! Proxies don't agree on a `/models` envelope: OpenAI-compatible ones return
+//! `{data:[{id}]}`, Anthropic-native ones `{data:[{type:"model", id}]}` and
+//! Gemini-style ones `{models:[{name:"models/<id>"}]}`. We try the requested
+//! flavor first and fall back to the other shapes.
+//!
+//! Aggregating gateways page large listings, so `fetch_model_list` follows
+//! `nextPageToken`, `has_more`/`last_id` cursors and `?page=N` paging up to
+//! `MAX_PAGES` pages.
+//!
+//! Results are cached in the settings table per (url, key fingerprint, flavor)
+//! so reopening the model dropdown doesn't re-hit slow proxies. Expired rows
+//! are swept whenever the cache is written, and a purged provider's URL is
+//! dropped right away.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::{dao::settings, Database};
+use crate::utils;
+
+const CACHE_KEY_PREFIX: &str = "model_cache:";
+const CACHE_TTL_KEY: &str = "model_cache_ttl_secs";
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 600;
+/// Most listing pages followed; anything past this is reported as truncated.
+const MAX_PAGES: u64 = 20;
+/// Per-request timeout of model listings.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Extra attempts after a 5xx or a failed connection, so one flaky response
+/// during a provider switch doesn't leave a client without models.
+pub const FETCH_RETRIES: u32 = 2;
+
+/// Which listing API to talk to. Steers the endpoint, auth headers and the
+/// order in which response shapes are tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModelListFlavor {
+    #[default]
+    Openai,
+    Anthropic,
+    Gemini,
+}
+
+impl ModelListFlavor {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "openai" | "" => Some(Self::Openai),
+            "anthropic" | "claude" => Some(Self::Anthropic),
+            "gemini" | "google" => Some(Self::Gemini),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Openai => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Gemini => "gemini",
+        }
+    }
+
+    fn models_url(self, base_url: &str) -> String {
+        let root = base_url.trim_end_matches('/');
+        match self {
+            Self::Gemini => format!("{root}/v1beta/models"),
+            Self::Openai | Self::Anthropic => format!("{root}/v1/models"),
+        }
+    }
+
+    /// Parsers in the order they should be tried for this flavor.
+    fn parse_order(self) -> [fn(&Value) -> Vec<String>; 3] {
+        match self {
+            Self::Openai => [parse_openai, parse_anthropic, parse_gemini],
+            Self::Anthropic => [parse_anthropic, parse_openai, parse_gemini],
+            Self::Gemini => [parse_gemini, parse_openai, parse_anthropic],
+        }
+    }
+}
+
+/// OpenAI shape: `data[].id`.
+fn parse_openai(body: &Value) -> Vec<String> {
+    body.get("data")
+        .and_then(|v| v.as_array())
+        .map(|data| {
+            data.iter()
+                .filter_map(|item| item.get("id").and_then(|v| v.as_str()))
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Anthropic shape: `data[]` entries with `type: "model"`.
+fn parse_anthropic(body: &Value) -> Vec<String> {
+    body.get("data")
+        .and_then(|v| v.as_array())
+        .map(|data| {
+            data.iter()
+                .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("model"))
+                .filter_map(|item| item.get("id").and_then(|v| v.as_str()))
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Gemini shape: `models[].name`, with the `models/` prefix stripped.
+fn parse_gemini(body: &Value) -> Vec<String> {
+    body.get("models")
+        .and_then(|v| v.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|item| item.get("name").and_then(|v| v.as_str()))
+                .map(|name| name.strip_prefix("models/").unwrap_or(name).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extract model ids from a listing response. The first shape that yields
+/// any ids wins; the result is sorted and de-duplicated.
+pub fn parse_model_ids(body: &Value, flavor: ModelListFlavor) -> Vec<String> {
+    let mut models = flavor
+        .parse_order()
+        .iter()
+        .map(|parse| parse(body))
+        .find(|ids| !ids.is_empty())
+        .unwrap_or_default();
+    models.sort();
+    models.dedup();
+    models
+}
+
+/// What a model is for. Listings rarely say, so this is mostly inferred
+/// from the id; the same rules steer the OpenClaw and Droid syncs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Chat,
+    Reasoning,
+    Image,
+    Embedding,
+    /// Audio, moderation and other endpoints a CLI can't chat with.
+    Other,
+}
+
+impl ModelKind {
+    /// Whether a coding CLI can use the model for chat.
+    pub fn is_chat(self) -> bool {
+        matches!(self, Self::Chat | Self::Reasoning)
+    }
+}
+
+/// Classify a model from its id and the `type` some gateways declare.
+pub fn classify_model(id: &str, declared_type: Option<&str>) -> ModelKind {
+    match declared_type.map(str::to_ascii_lowercase).as_deref() {
+        Some("embedding" | "embeddings") => return ModelKind::Embedding,
+        Some("image" | "images" | "image_generation") => return ModelKind::Image,
+        Some("audio" | "tts" | "stt" | "moderation") => return ModelKind::Other,
+        _ => {}
+    }
+    let id = id.to_lowercase();
+    let has = |keys: &[&str]| keys.iter().any(|k| id.contains(k));
+    if has(&["embed"]) {
+        ModelKind::Embedding
+    } else if has(&["image", "dall-e"]) {
+        ModelKind::Image
+    } else if has(&["tts", "whisper", "moderation"]) {
+        ModelKind::Other
+    } else if has(&["thinking", "pro", "reason"])
+        || ["o1", "o3", "o4"].iter().any(|p| id.starts_with(p))
+    {
+        ModelKind::Reasoning
+    } else {
+        ModelKind::Chat
+    }
+}
+
+/// A listed model with whatever capability hints the endpoint provided.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub kind: ModelKind,
+    pub owned_by: Option<String>,
+    pub context_length: Option<u64>,
+    /// The entry's own `type`, when it is more than Anthropic's `"model"`.
+    pub declared_type: Option<String>,
+}
+
+impl ModelInfo {
+    fn from_entry(id: String, entry: Option<&Value>) -> Self {
+        let str_field = |key: &str| {
+            entry
+                .and_then(|e| e.get(key))
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let context_length = [
+            "context_length",
+            "context_window",
+            "max_context_length",
+            "inputTokenLimit",
+        ]
+        .iter()
+        .find_map(|k| entry.and_then(|e| e.get(*k)).and_then(Value::as_u64));
+        let mut declared_type = str_field("type").filter(|t| t != "model");
+        // Gemini lists embedding models with only `embedContent`
+        let methods = entry
+            .and_then(|e| e.get("supportedGenerationMethods"))
+            .and_then(Value::as_array);
+        if declared_type.is_none()
+            && methods.is_some_and(|m| {
+                m.iter().any(|v| v == "embedContent") && !m.iter().any(|v| v == "generateContent")
+            })
+        {
+            declared_type = Some("embedding".to_string());
+        }
+        Self {
+            kind: classify_model(&id, declared_type.as_deref()),
+            owned_by: str_field("owned_by"),
+            context_length,
+            declared_type,
+            id,
+        }
+    }
+}
+
+/// Like `parse_model_ids`, keeping each model's listing entry as hints.
+fn parse_model_infos(body: &Value, flavor: ModelListFlavor) -> Vec<ModelInfo> {
+    let entries: Vec<&Value> = ["data", "models"]
+        .iter()
+        .filter_map(|k| body.get(*k).and_then(Value::as_array))
+        .flatten()
+        .collect();
+    let entry_for = |id: &str| {
+        entries.iter().copied().find(|e| {
+            e.get("id").and_then(Value::as_str) == Some(id)
+                || e.get("name")
+                    .and_then(Value::as_str)
+                    .is_some_and(|n| n.strip_prefix("models/").unwrap_or(n) == id)
+        })
+    };
+    parse_model_ids(body, flavor)
+        .into_iter()
+        .map(|id| {
+            let entry = entry_for(&id);
+            ModelInfo::from_entry(id, entry)
+        })
+        .collect()
+}
+
+/// Query parameter asking for the page after `body`, if it says there is one:
+/// Gemini's `nextPageToken`, a `has_more` + `last_id` cursor, or plain page
+/// numbers (`has_more` without a cursor, `total_pages`, `last_page`).
+fn next_page(body: &Value, flavor: ModelListFlavor, page: u64) -> Option<(&'static str, String)> {
+    if let Some(token) = body
+        .get("nextPageToken")
+        .and_then(Value::as_str)
+        .filter(|t| !t.is_empty())
+    {
+        return Some(("pageToken", token.to_string()));
+    }
+    let has_more = body.get("has_more").and_then(Value::as_bool) == Some(true)
+        || ["total_pages", "last_page"]
+            .iter()
+            .filter_map(|k| body.get(*k).and_then(Value::as_u64))
+            .any(|last| last > page);
+    if !has_more {
+        return None;
+    }
+    match body
+        .get("last_id")
+        .and_then(Value::as_str)
+        .filter(|id| !id.is_empty())
+    {
+        Some(id) if flavor == ModelListFlavor::Anthropic => Some(("after_id", id.to_string())),
+        Some(id) => Some(("after", id.to_string())),
+        None => Some(("page", (page + 1).to_string())),
+    }
+}
+
+/// Model ids gathered from every page of a listing, sorted and de-duplicated.
+#[derive(Debug, Clone, Default)]
+pub struct FetchedModels {
+    pub models: Vec<String>,
+    /// Hints and classification for each of `models`, in the same order.
+    pub details: Vec<ModelInfo>,
+    /// The proxy had more pages than were fetched.
+    pub truncated: bool,
+}
+
+async fn fetch_page(
+    client: &reqwest::Client,
+    models_url: &str,
+    query: Option<&(&'static str, String)>,
+    api_key: &str,
+    flavor: ModelListFlavor,
+) -> Result<Value, String> {
+    let mut request = client
+        .get(models_url)
+        .header("Authorization", format!("Bearer {api_key}"));
+    if let Some(query) = query {
+        request = request.query(&[query]);
+    }
+    request = match flavor {
+        ModelListFlavor::Anthropic => request
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01"),
+        ModelListFlavor::Gemini => request.header("x-goog-api-key", api_key),
+        ModelListFlavor::Openai => request,
+    };
+
+    let response = utils::send_with_retry(request, FETCH_RETRIES)
+        .await
+        .map_err(|e| send_error(e, FETCH_TIMEOUT))?;
+    read_json(response).await
+}
+
+fn send_error(e: reqwest::Error, timeout: Duration) -> String {
+    if e.is_timeout() {
+        format!("Request timed out ({}s)", timeout.as_secs())
+    } else if e.is_connect() {
+        format!("Connection failed: {e}")
+    } else {
+        format!("Request failed: {e}")
+    }
+}
+
+async fn read_json(response: reqwest::Response) -> Result<Value, String> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        // Truncate body to avoid leaking large error pages or sensitive data.
+        let summary = body.chars().take(200).collect::<String>();
+        return Err(format!("API returned {status}: {summary}"));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {e}"))
+}
+
+/// Fetch the model list from `base_url`, following pagination. Callers
+/// validate the URL and key. A failing later page keeps what was already
+/// listed and marks the result truncated.
+pub async fn fetch_model_list(
+    base_url: &str,
+    api_key: &str,
+    flavor: ModelListFlavor,
+) -> Result<FetchedModels, String> {
+    let models_url = flavor.models_url(base_url);
+
+    let client = utils::api_client(FETCH_TIMEOUT)?;
+
+    let mut models = BTreeMap::new();
+    let mut query = None;
+    for page in 1..=MAX_PAGES {
+        let body = match fetch_page(&client, &models_url, query.as_ref(), api_key, flavor).await {
+            Ok(body) => body,
+            Err(e) if page > 1 => {
+                tracing::warn!("[model_list] Stopped at page {page}: {e}");
+                return Ok(FetchedModels::from_map(models, true));
+            }
+            Err(e) => return Err(e),
+        };
+        let before = models.len();
+        for info in parse_model_infos(&body, flavor) {
+            models.entry(info.id.clone()).or_insert(info);
+        }
+        // A proxy that ignores the page parameter keeps returning page one
+        if page > 1 && models.len() == before {
+            query = None;
+            break;
+        }
+        query = next_page(&body, flavor, page);
+        if query.is_none() {
+            break;
+        }
+    }
+
+    Ok(FetchedModels::from_map(models, query.is_some()))
+}
+
+/// Ids from an OpenAI-style `/v1/models`, for the sync modules that build a
+/// model catalog. `base_url` may or may not end in `/v1`. Only the first page
+/// is read.
+pub async fn fetch_openai_models(
+    base_url: &str,
+    api_key: &str,
+    retries: u32,
+    timeout: Duration,
+) -> Result<Vec<String>, String> {
+    let root = base_url
+        .trim()
+        .trim_end_matches('/')
+        .trim_end_matches("/v1");
+    let client = utils::api_client(timeout)?;
+    let request = client
+        .get(format!("{root}/v1/models"))
+        .header("Authorization", format!("Bearer {api_key}"));
+    let response = utils::send_with_retry(request, retries)
+        .await
+        .map_err(|e| send_error(e, timeout))?;
+    Ok(parse_openai(&read_json(response).await?))
+}
+
+impl FetchedModels {
+    fn from_map(models: BTreeMap<String, ModelInfo>, truncated: bool) -> Self {
+        Self {
+            models: models.keys().cloned().collect(),
+            details: models.into_values().collect(),
+            truncated,
+        }
+    }
+}
+
+/// Model list returned to the UI, with how old it is when served from cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelList {
+    pub models: Vec<String>,
+    pub cached: bool,
+    pub age_secs: u64,
+    /// The proxy listed more than `MAX_PAGES` pages.
+    pub truncated: bool,
+    /// Models listed before `filter` was applied.
+    pub total: usize,
+}
+
+impl ModelList {
+    /// Keep the models whose id contains `filter`, ignoring case.
+    fn filtered(mut self, filter: Option<&str>) -> Self {
+        let needle = filter.map(str::trim).unwrap_or_default().to_lowercase();
+        if !needle.is_empty() {
+            self.models.retain(|m| m.to_lowercase().contains(&needle));
+        }
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedModels {
+    fetched_at: i64,
+    models: Vec<String>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+/// Stable FNV-1a fingerprint so the raw API key never lands in the settings table.
+fn key_fingerprint(api_key: &str) -> String {
+    let hash = api_key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+fn cache_key(base_url: &str, api_key: &str, flavor: ModelListFlavor) -> String {
+    format!(
+        "{CACHE_KEY_PREFIX}{}:{}:{}",
+        flavor.as_str(),
+        key_fingerprint(api_key),
+        base_url.trim_end_matches('/')
+    )
+}
+
+/// Cache TTL from settings, falling back to the default on missing/invalid values.
+pub fn cache_ttl_secs(db: &Database) -> u64 {
+    settings::get(db, CACHE_TTL_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CACHE_TTL_SECS)
+}
+
+pub fn set_cache_ttl_secs(db: &Database, secs: u64) -> Result<(), String> {
+    settings::set(db, CACHE_TTL_KEY, &secs.to_string())
+}
+
+fn read_cache(db: &Database, key: &str, now: i64, ttl_secs: u64) -> Option<ModelList> {
+    let raw = settings::get(db, key).ok().flatten()?;
+    let entry: CachedModels = serde_json::from_str(&raw).ok()?;
+    let age_secs = u64::try_from(now - entry.fetched_at).ok()?;
+    if age_secs >= ttl_secs || entry.models.is_empty() {
+        return None;
+    }
+    Some(ModelList {
+        total: entry.models.len(),
+        models: entry.models,
+        cached: true,
+        age_secs,
+        truncated: entry.truncated,
+    })
+}
+
+fn write_cache(db: &Database, key: &str, fetched: &FetchedModels, now: i64) -> Result<(), String> {
+    let entry = CachedModels {
+        fetched_at: now,
+        models: fetched.models.clone(),
+        truncated: fetched.truncated,
+    };
+    let raw = serde_json::to_string(&entry).map_err(|e| format!("serialize model cache: {e}"))?;
+    settings::set(db, key, &raw)
+}
+
+/// URL part of a cache key, for `model_cache:<flavor>:<fingerprint>:<url>`.
+fn cache_key_url(key: &str) -> Option<&str> {
+    key.strip_prefix(CACHE_KEY_PREFIX)?.splitn(3, ':').nth(2)
+}
+
+/// Delete cache rows that are expired at `now` or no longer parse.
+fn sweep_cache(db: &Database, now: i64, ttl_secs: u64) -> Result<usize, String> {
+    let stale: Vec<String> = settings::list_prefix(db, CACHE_KEY_PREFIX)?
+        .into_iter()
+        .filter(|(_, raw)| {
+            serde_json::from_str::<CachedModels>(raw)
+                .ok()
+                .and_then(|entry| u64::try_from(now - entry.fetched_at).ok())
+                .is_none_or(|age| age >= ttl_secs)
+        })
+        .map(|(key, _)| key)
+        .collect();
+    settings::delete(db, &stale)?;
+    Ok(stale.len())
+}
+
+/// Drop every cached listing of `base_url`, whatever the key or flavor.
+pub fn forget_cached_url(db: &Database, base_url: &str) -> Result<(), String> {
+    let base_url = base_url.trim_end_matches('/');
+    let keys: Vec<String> = settings::list_prefix(db, CACHE_KEY_PREFIX)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| cache_key_url(key) == Some(base_url))
+        .collect();
+    settings::delete(db, &keys)
+}
+
+/// Serve the model list from cache when fresh, otherwise fetch and store it.
+/// `force` always refetches. Empty results are never cached. The full list is
+/// cached; `filter` only narrows what is returned.
+pub async fn fetch_model_list_cached(
+    db: &Database,
+    base_url: &str,
+    api_key: &str,
+    flavor: ModelListFlavor,
+    force: bool,
+    filter: Option<&str>,
+) -> Result<ModelList, String> {
+    let key = cache_key(base_url, api_key, flavor);
+    if !force {
+        let now = chrono::Utc::now().timestamp();
+        if let Some(hit) = read_cache(db, &key, now, cache_ttl_secs(db)) {
+            return Ok(hit.filtered(filter));
+        }
+    }
+
+    let fetched = fetch_model_list(base_url, api_key, flavor).await?;
+    if !fetched.models.is_empty() {
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = write_cache(db, &key, &fetched, now) {
+            tracing::warn!("[model_list] Failed to cache model list: {e}");
+        }
+        if let Err(e) = sweep_cache(db, now, cache_ttl_secs(db)) {
+            tracing::warn!("[model_list] Failed to sweep the model cache: {e}");
+        }
+    }
+    let list = ModelList {
+        total: fetched.models.len(),
+        models: fetched.models,
+        cached: false,
+        age_secs: 0,
+        truncated: fetched.truncated,
+    };
+    Ok(list.filtered(filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn openai_body() -> Value {
+        json!({
+            "object": "list",
+            "data": [
+                {"id": "gpt-4o", "object": "model", "owned_by": "openai"},
+                {"id": "claude-sonnet-4-5", "object": "model", "owned_by": "proxy"}
+            ]
+        })
+    }
+
+    fn anthropic_body() -> Value {
+        json!({
+            "data": [
+                {"type": "model", "id": "claude-opus-4-1", "display_name": "Claude Opus 4.1"},
+                {"type": "model", "id": "claude-sonnet-4-5", "display_name": "Claude Sonnet 4.5"}
+            ],
+            "has_more": false,
+            "first_id": "claude-opus-4-1"
+        })
+    }
+
+    fn gemini_body() -> Value {
+        json!({
+            "models": [
+                {"name": "models/gemini-2.5-pro", "displayName": "Gemini 2.5 Pro"},
+                {"name": "models/gemini-2.5-flash"}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_parse_openai_shape() {
+        let ids = parse_model_ids(&openai_body(), ModelListFlavor::Openai);
+        assert_eq!(ids, vec!["claude-sonnet-4-5", "gpt-4o"]);
+    }
+
+    #[test]
+    fn test_parse_anthropic_shape() {
+        let ids = parse_model_ids(&anthropic_body(), ModelListFlavor::Anthropic);
+        assert_eq!(ids, vec!["claude-opus-4-1", "claude-sonnet-4-5"]);
+        // Without a flavor hint the same body still parses
+        let ids = parse_model_ids(&anthropic_body(), ModelListFlavor::Openai);
+        assert_eq!(ids.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_gemini_shape_falls_back() {
+        let expected = vec!["gemini-2.5-flash", "gemini-2.5-pro"];
+        assert_eq!(
+            parse_model_ids(&gemini_body(), ModelListFlavor::Gemini),
+            expected
+        );
+        assert_eq!(
+            parse_model_ids(&gemini_body(), ModelListFlavor::Openai),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_shape_is_empty() {
+        assert!(parse_model_ids(&json!({"error": "nope"}), ModelListFlavor::Openai).is_empty());
+    }
+
+    #[test]
+    fn test_flavor_parse_and_url() {
+        assert_eq!(
+            ModelListFlavor::parse("Gemini"),
+            Some(ModelListFlavor::Gemini)
+        );
+        assert_eq!(
+            ModelListFlavor::parse("claude"),
+            Some(ModelListFlavor::Anthropic)
+        );
+        assert_eq!(ModelListFlavor::parse("bogus"), None);
+        assert_eq!(
+            ModelListFlavor::Gemini.models_url("http://localhost:8045/"),
+            "http://localhost:8045/v1beta/models"
+        );
+        assert_eq!(
+            ModelListFlavor::Openai.models_url("http://localhost:8045"),
+            "http://localhost:8045/v1/models"
+        );
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_expiry() {
+        let db = Database::memory().unwrap();
+        let key = cache_key(
+            "http://localhost:8045/",
+            "sk-secret",
+            ModelListFlavor::Openai,
+        );
+        assert!(!key.contains("sk-secret"));
+        assert_eq!(
+            key,
+            cache_key(
+                "http://localhost:8045",
+                "sk-secret",
+                ModelListFlavor::Openai
+            )
+        );
+        assert_ne!(
+            key,
+            cache_key("http://localhost:8045", "sk-other", ModelListFlavor::Openai)
+        );
+
+        let models = vec!["a".to_string(), "b".to_string()];
+        let fetched = FetchedModels {
+            models: models.clone(),
+            details: Vec::new(),
+            truncated: true,
+        };
+        write_cache(&db, &key, &fetched, 1_000).unwrap();
+
+        let hit = read_cache(&db, &key, 1_180, 600).unwrap();
+        assert_eq!(hit.models, models);
+        assert!(hit.cached && hit.truncated);
+        assert_eq!(hit.age_secs, 180);
+        assert_eq!(hit.total, 2);
+
+        assert!(read_cache(&db, &key, 1_600, 600).is_none());
+        // Clock went backwards: treat as stale
+        assert!(read_cache(&db, &key, 900, 600).is_none());
+    }
+
+    #[test]
+    fn test_cache_sweep_and_forget_url() {
+        let db = Database::memory().unwrap();
+        let fetched = FetchedModels {
+            models: vec!["a".to_string()],
+            details: Vec::new(),
+            truncated: false,
+        };
+        let old = cache_key("http://old.example", "k", ModelListFlavor::Openai);
+        let fresh = cache_key("http://a.example/v1", "k", ModelListFlavor::Openai);
+        let other_key = cache_key("http://a.example/v1/", "k2", ModelListFlavor::Anthropic);
+        write_cache(&db, &old, &fetched, 100).unwrap();
+        write_cache(&db, &fresh, &fetched, 1_000).unwrap();
+        write_cache(&db, &other_key, &fetched, 1_000).unwrap();
+        settings::set(&db, &format!("{CACHE_KEY_PREFIX}openai:x:http://junk"), "{").unwrap();
+        set_cache_ttl_secs(&db, 600).unwrap();
+
+        assert_eq!(sweep_cache(&db, 1_000, 600).unwrap(), 2);
+        assert!(settings::get(&db, &old).unwrap().is_none());
+        assert!(settings::get(&db, &fresh).unwrap().is_some());
+        // Not a cache row
+        assert_eq!(cache_ttl_secs(&db), 600);
+
+        assert_eq!(cache_key_url(&fresh), Some("http://a.example/v1"));
+        forget_cached_url(&db, "http://a.example/v1/").unwrap();
+        assert!(settings::list_prefix(&db, CACHE_KEY_PREFIX)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cache_ttl_setting() {
+        let db = Database::memory().unwrap();
+        assert_eq!(cache_ttl_secs(&db), DEFAULT_CACHE_TTL_SECS);
+        set_cache_ttl_secs(&db, 60).unwrap();
+        assert_eq!(cache_ttl_secs(&db), 60);
+        settings::set(&db, CACHE_TTL_KEY, "soon").unwrap();
+        assert_eq!(cache_ttl_secs(&db), DEFAULT_CACHE_TTL_SECS);
+    }
+
+    #[test]
+    fn test_classify_model() {
+        for (id, kind) in [
+            ("claude-sonnet-4-5", ModelKind::Chat),
+            ("gpt-4o", ModelKind::Chat),
+            ("claude-opus-4-5-thinking", ModelKind::Reasoning),
+            ("gemini-2.5-pro", ModelKind::Reasoning),
+            ("o3-mini", ModelKind::Reasoning),
+            ("text-embedding-3-small", ModelKind::Embedding),
+            ("gemini-3-pro-image", ModelKind::Image),
+            ("dall-e-3", ModelKind::Image),
+            ("whisper-1", ModelKind::Other),
+        ] {
+            assert_eq!(classify_model(id, None), kind, "{id}");
+        }
+        assert_eq!(
+            classify_model("bge-m3", Some("embedding")),
+            ModelKind::Embedding
+        );
+        assert!(!ModelKind::Image.is_chat() && ModelKind::Reasoning.is_chat());
+    }
+
+    #[test]
+    fn test_parse_model_infos_keeps_hints() {
+        let body = json!({
+            "data": [
+                {"id": "gpt-4o", "owned_by": "openai", "context_length": 128000},
+                {"id": "bge-m3", "type": "embedding"}
+            ]
+        });
+        let infos = parse_model_infos(&body, ModelListFlavor::Openai);
+        assert_eq!(infos[0].id, "bge-m3");
+        assert_eq!(infos[0].kind, ModelKind::Embedding);
+        assert_eq!(infos[1].owned_by.as_deref(), Some("openai"));
+        assert_eq!(infos[1].context_length, Some(128_000));
+
+        let gemini = json!({
+            "models": [{
+                "name": "models/text-multilingual-004",
+                "inputTokenLimit": 2048,
+                "supportedGenerationMethods": ["embedContent"]
+            }]
+        });
+        let infos = parse_model_infos(&gemini, ModelListFlavor::Gemini);
+        assert_eq!(infos[0].kind, ModelKind::Embedding);
+        assert_eq!(infos[0].context_length, Some(2048));
+        // Anthropic's `type: "model"` says nothing about capability
+        let infos = parse_model_infos(&anthropic_body(), ModelListFlavor::Anthropic);
+        assert!(infos.iter().all(|i| i.declared_type.is_none()));
+    }
+
+    #[test]
+    fn test_next_page_styles() {
+        let openai = ModelListFlavor::Openai;
+        assert_eq!(
+            next_page(&json!({"has_more": true, "last_id": "m9"}), openai, 1),
+            Some(("after", "m9".to_string()))
+        );
+        assert_eq!(
+            next_page(
+                &json!({"has_more": true, "last_id": "m9"}),
+                ModelListFlavor::Anthropic,
+                1
+            ),
+            Some(("after_id", "m9".to_string()))
+        );
+        assert_eq!(
+            next_page(&json!({"has_more": true}), openai, 2),
+            Some(("page", "3".to_string()))
+        );
+        assert_eq!(
+            next_page(&json!({"total_pages": 3}), openai, 2),
+            Some(("page", "3".to_string()))
+        );
+        assert_eq!(next_page(&json!({"total_pages": 3}), openai, 3), None);
+        assert_eq!(
+            next_page(&json!({"nextPageToken": "abc"}), ModelListFlavor::Gemini, 1),
+            Some(("pageToken", "abc".to_string()))
+        );
+        assert_eq!(next_page(&anthropic_body(), openai, 1), None);
+    }
+
+    #[test]
+    fn test_filter_keeps_total() {
+        let list = ModelList {
+            models: vec!["GPT-4o".into(), "claude-sonnet-4-5".into()],
+            cached: false,
+            age_secs: 0,
+            truncated: false,
+            total: 2,
+        };
+        let hit = list.clone().filtered(Some(" gpt "));
+        assert_eq!(hit.models, ["GPT-4o"]);
+        assert_eq!(hit.total, 2);
+        assert_eq!(list.filtered(Some("")).models.len(), 2);
+    }
+
+    mod http {
+        use super::*;
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn page(ids: &[&str], extra: Value) -> ResponseTemplate {
+            let mut body = json!({
+                "data": ids.iter().map(|id| json!({"id": id})).collect::<Vec<_>>()
+            });
+            body.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            ResponseTemplate::new(200).set_body_json(body)
+        }
+
+        #[tokio::test]
+        async fn test_follows_cursor_and_dedupes() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/v1/models"))
+                .and(query_param_is_missing("after"))
+                .respond_with(page(&["b", "a"], json!({"has_more": true, "last_id": "b"})))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/v1/models"))
+                .and(query_param("after", "b"))
+                .respond_with(page(&["a", "c"], json!({"has_more": false})))
+                .mount(&server)
+                .await;
+
+            let fetched = fetch_model_list(&server.uri(), "sk-test", ModelListFlavor::Openai)
+                .await
+                .unwrap();
+            assert_eq!(fetched.models, ["a", "b", "c"]);
+            assert!(!fetched.truncated);
+        }
+
+        #[tokio::test]
+        async fn test_page_numbers_stop_when_ignored_or_capped() {
+            // Proxy claims more pages but ignores `page`: stop once nothing new arrives
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/v1/models"))
+                .respond_with(page(&["a"], json!({"has_more": true})))
+                .expect(2)
+                .mount(&server)
+                .await;
+            let fetched = fetch_model_list(&server.uri(), "sk-test", ModelListFlavor::Openai)
+                .await
+                .unwrap();
+            assert_eq!(fetched.models, ["a"]);
+            assert!(!fetched.truncated);
+
+            // Endless distinct pages are cut at MAX_PAGES
+            let server = MockServer::start().await;
+            for n in 1..=MAX_PAGES + 1 {
+                let id = format!("m{n:02}");
+                let mock = Mock::given(method("GET")).and(path("/v1/models"));
+                let mock = if n == 1 {
+                    mock.and(query_param_is_missing("page"))
+                } else {
+                    mock.and(query_param("page", n.to_string()))
+                };
+                mock.respond_with(page(&[&id], json!({"has_more": true})))
+                    .mount(&server)
+                    .await;
+            }
+            let fetched = fetch_model_list(&server.uri(), "sk-test", ModelListFlavor::Openai)
+                .await
+                .unwrap();
+            assert_eq!(fetched.models.len() as u64, MAX_PAGES);
+            assert!(fetched.truncated);
+        }
+
+        #[tokio::test]
+        async fn test_retries_server_errors_then_succeeds() {
+            let server = MockServer::start().await;
+            Mock::given(path("/v1/models"))
+                .respond_with(ResponseTemplate::new(502))
+                .up_to_n_times(2)
+                .expect(2)
+                .mount(&server)
+                .await;
+            Mock::given(path("/v1/models"))
+                .respond_with(page(&["gpt-4o"], json!({})))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let base = format!("{}/v1", server.uri());
+            let ids = fetch_openai_models(&base, "sk", 2, FETCH_TIMEOUT)
+                .await
+                .unwrap();
+            assert_eq!(ids, vec!["gpt-4o"]);
+        }
+
+        #[tokio::test]
+        async fn test_retries_are_bounded_and_skip_client_errors() {
+            let server = MockServer::start().await;
+            Mock::given(path("/v1/models"))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(2)
+                .mount(&server)
+                .await;
+            let err = fetch_openai_models(&server.uri(), "sk", 1, FETCH_TIMEOUT)
+                .await
+                .unwrap_err();
+            assert!(err.contains("503"), "{err}");
+
+            let server = MockServer::start().await;
+            Mock::given(path("/v1/models"))
+                .respond_with(ResponseTemplate::new(401))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let err = fetch_openai_models(&server.uri(), "sk", 3, FETCH_TIMEOUT)
+                .await
+                .unwrap_err();
+            assert!(err.contains("401"), "{err}");
+        }
+
+        #[tokio::test]
+        async fn test_retries_connection_errors() {
+            let err = fetch_openai_models("http://127.0.0.1:9", "sk", 1, FETCH_TIMEOUT)
+                .await
+                .unwrap_err();
+            assert!(err.starts_with("Connection failed"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Desktop notifications for events the user may miss with the window hidden.
+//!
+//! Users can opt out via the `notifications_enabled` setting (on by default).
+
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::database::{dao::settings, Database};
+use crate::store::AppState;
+
+const SETTINGS_KEY: &str = "notifications_enabled";
+
+pub fn is_enabled(db: &Database) -> bool {
+    settings::get_bool(db, SETTINGS_KEY, true)
+}
+
+pub fn set_enabled(db: &Database, enabled: bool) -> Result<(), String> {
+    settings::set_bool(db, SETTINGS_KEY, enabled)
+}
+
+/// Show a notification unless the user opted out. Failures are only logged.
+pub fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    if let Some(state) = app.try_state::<AppState>() {
+        if !is_enabled(&state.db) {
+            return;
+        }
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("[notify] Failed to show notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled_flag_defaults_on() {
+        let db = Database::memory().unwrap();
+        assert!(is_enabled(&db));
+        set_enabled(&db, false).unwrap();
+        assert!(!is_enabled(&db));
+        set_enabled(&db, true).unwrap();
+        assert!(is_enabled(&db));
+    }
+}
@@ -2,29 +2,39 @@ use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
-use crate::utils;
+use crate::file_status::{FileStatus, SyncStatus};
+use crate::model_aliases::ModelAliases;
+use crate::model_list::{self, ModelKind};
+use crate::opencode_sync::FALLBACK_MODEL_IDS;
+use crate::provider_import::DetectedProvider;
+use crate::{config_paths, paths, utils, wsl};
 
 const CONFIG_FILE: &str = "openclaw.json";
 use crate::utils::BACKUP_SUFFIX;
 const PROVIDER_ID: &str = "hajimi";
 
 fn get_config_dir() -> Option<PathBuf> {
-    dirs::home_dir().map(|h| h.join(".openclaw"))
+    paths::cli_home_dir().map(|h| h.join(".openclaw"))
 }
 
-fn get_config_path() -> Option<PathBuf> {
-    get_config_dir().map(|dir| dir.join(CONFIG_FILE))
+pub(crate) fn get_config_path() -> Option<PathBuf> {
+    config_paths::resolve_file(
+        "openclaw",
+        None,
+        get_config_dir().map(|dir| dir.join(CONFIG_FILE)),
+    )
 }
 
 pub fn check_openclaw_installed() -> (bool, Option<String>) {
-    match utils::resolve_executable("openclaw") {
+    match wsl::resolve_cli("openclaw") {
         Some(path) => {
-            let version = utils::get_cli_version(&path);
+            let version = wsl::cli_version(&path);
             (true, version.or_else(|| Some("detected".to_string())))
         }
         None => {
             // Also check if config dir exists (installed but not in PATH)
-            let has_config = get_config_dir().is_some_and(|d| d.exists());
+            let has_config =
+                get_config_path().is_some_and(|p| p.parent().is_some_and(|d| d.exists()));
             if has_config {
                 (true, Some("detected".to_string()))
             } else {
@@ -34,26 +44,19 @@ pub fn check_openclaw_installed() -> (bool, Option<String>) {
     }
 }
 
-pub fn get_sync_status(proxy_url: &str) -> (bool, bool, Option<String>) {
-    let config_path = match get_config_path() {
-        Some(p) => p,
-        None => return (false, false, None),
+pub fn get_sync_status(proxy_url: Option<&str>) -> SyncStatus {
+    let Some(config_path) = get_config_path() else {
+        return SyncStatus::default();
     };
 
-    let backup_path = config_path.with_file_name(format!("{CONFIG_FILE}{BACKUP_SUFFIX}"));
-    let has_backup = backup_path.exists();
+    let has_backup = utils::find_backup(&config_path).is_some();
 
-    if !config_path.exists() {
-        return (false, has_backup, None);
-    }
-
-    let content = match fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return (false, has_backup, None),
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        return SyncStatus::new(vec![FileStatus::missing(CONFIG_FILE)], has_backup);
     };
 
-    // OpenClaw uses JSON5 but serde_json can parse standard JSON subset
-    let json: Value = serde_json::from_str(&content).unwrap_or_default();
+    // OpenClaw uses JSON5; comments and trailing commas are the parts seen in practice
+    let json: Value = utils::parse_json_lenient(&content).unwrap_or_default();
 
     let current_url = json
         .get("models")
@@ -63,14 +66,39 @@ pub fn get_sync_status(proxy_url: &str) -> (bool, bool, Option<String>) {
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    let is_synced = current_url
-        .as_deref()
-        .is_some_and(|u| utils::urls_match(u, proxy_url));
+    let status = FileStatus::new(CONFIG_FILE, true).with_url(
+        current_url,
+        proxy_url,
+        &format!("models.providers.{PROVIDER_ID}.baseUrl"),
+        false,
+    );
+    SyncStatus::new(vec![status], has_backup)
+}
 
-    (is_synced, has_backup, current_url)
+/// Provider OpenClaw is configured with: ours if present, else the first with a URL and key.
+pub fn detect_existing_provider() -> Option<DetectedProvider> {
+    detect_from_config(&fs::read_to_string(get_config_path()?).ok()?)
 }
 
-// urls_match: use crate::utils::urls_match
+fn detect_from_config(content: &str) -> Option<DetectedProvider> {
+    let json: Value = utils::parse_json_lenient(content).ok()?;
+    let providers = json.pointer("/models/providers")?.as_object()?;
+    let complete = |p: &Value| p.get("baseUrl").is_some() && p.get("apiKey").is_some();
+    let (id, provider) = providers
+        .get_key_value(PROVIDER_ID)
+        .filter(|(_, p)| complete(p))
+        .or_else(|| providers.iter().find(|(_, p)| complete(p)))?;
+    let model = json
+        .pointer("/agents/defaults/model/primary")
+        .and_then(|v| v.as_str())
+        .and_then(|m| m.strip_prefix(&format!("{id}/")));
+    DetectedProvider::new(
+        "openclaw",
+        provider.get("baseUrl")?.as_str()?,
+        provider.get("apiKey")?.as_str()?,
+        model,
+    )
+}
 
 fn normalize_base_url(input: &str) -> String {
     let trimmed = input.trim().trim_end_matches('/');
@@ -81,79 +109,130 @@ fn normalize_base_url(input: &str) -> String {
     }
 }
 
-/// Fetch models from proxy and build OpenClaw models array format.
-async fn fetch_models_for_openclaw(base_url: &str, api_key: &str) -> Vec<Value> {
-    let models_url = format!("{}/models", base_url.trim_end_matches('/'));
-    let client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-    {
-        Ok(c) => c,
-        Err(_) => return vec![],
-    };
+/// Outcome of an OpenClaw sync that the UI may want to surface.
+#[derive(Debug, Clone, Default)]
+pub struct OpenclawSyncReport {
+    pub model_count: usize,
+    /// True when the bundled catalog was written because the proxy's model
+    /// list could not be fetched.
+    pub models_defaulted: bool,
+    pub warning: Option<String>,
+}
 
-    let resp = match client
-        .get(&models_url)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .send()
-        .await
-    {
-        Ok(r) if r.status().is_success() => r,
-        _ => return vec![],
-    };
+/// Fetch model ids with the shared retry policy. An empty list counts as a
+/// failure since OpenClaw treats a provider without models as unusable.
+async fn fetch_model_ids(base_url: &str, api_key: &str) -> Result<Vec<String>, String> {
+    let ids = model_list::fetch_openai_models(
+        base_url,
+        api_key,
+        model_list::FETCH_RETRIES,
+        model_list::FETCH_TIMEOUT,
+    )
+    .await?;
+    if ids.is_empty() {
+        return Err("proxy returned no models".to_string());
+    }
+    Ok(ids)
+}
 
-    let body: Value = match resp.json().await {
-        Ok(v) => v,
-        Err(_) => return vec![],
+/// Build OpenClaw's model entry for an id, or None for image, embedding and
+/// other non-chat models, which are useless to a coding agent.
+fn openclaw_model_entry(id: &str) -> Option<Value> {
+    let kind = model_list::classify_model(id, None);
+    if !kind.is_chat() {
+        return None;
+    }
+    let is_reasoning = kind == ModelKind::Reasoning;
+    let is_claude = id.contains("claude");
+    let is_gemini = id.contains("gemini");
+
+    let context_window: u64 = if is_claude {
+        200_000
+    } else if is_gemini {
+        1_048_576
+    } else {
+        128_000
     };
+    let max_tokens: u64 = if is_claude { 64_000 } else { 65_536 };
 
-    let mut models = Vec::new();
-    if let Some(data) = body.get("data").and_then(|v| v.as_array()) {
-        for item in data {
-            if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
-                let is_reasoning = id.contains("thinking") || id.contains("pro");
-                let is_claude = id.contains("claude");
-                let is_gemini = id.contains("gemini");
-                let is_image = id.contains("image");
-
-                let context_window: u64 = if is_claude {
-                    200_000
-                } else if is_gemini {
-                    1_048_576
-                } else {
-                    128_000
-                };
-                let max_tokens: u64 = if is_claude { 64_000 } else { 65_536 };
-
-                let mut input_modalities = vec!["text"];
-                if is_claude || is_gemini {
-                    input_modalities.push("image");
-                }
-
-                let model = serde_json::json!({
-                    "id": id,
-                    "name": id,
-                    "reasoning": is_reasoning,
-                    "input": input_modalities,
-                    "cost": { "input": 0, "output": 0, "cacheRead": 0, "cacheWrite": 0 },
-                    "contextWindow": context_window,
-                    "maxTokens": max_tokens,
-                });
-
-                // Skip pure image generation models for coding agent use
-                if !is_image {
-                    models.push(model);
-                }
-            }
-        }
+    let mut input_modalities = vec!["text"];
+    if is_claude || is_gemini {
+        input_modalities.push("image");
     }
-    models
+
+    Some(serde_json::json!({
+        "id": id,
+        "name": id,
+        "reasoning": is_reasoning,
+        "input": input_modalities,
+        "cost": { "input": 0, "output": 0, "cacheRead": 0, "cacheWrite": 0 },
+        "contextWindow": context_window,
+        "maxTokens": max_tokens,
+    }))
+}
+
+/// Model entries from the proxy, or the bundled catalog (plus a warning) when
+/// the proxy can't be reached, with ids written through `aliases`.
+async fn resolve_models(
+    base_url: &str,
+    api_key: &str,
+    aliases: &ModelAliases,
+) -> (Vec<Value>, Option<String>) {
+    let (ids, warning) = match fetch_model_ids(base_url, api_key).await {
+        Ok(ids) => (ids, None),
+        Err(e) => {
+            tracing::warn!("[openclaw] Model fetch failed ({e}), using bundled catalog");
+            let ids = FALLBACK_MODEL_IDS.iter().map(|s| s.to_string()).collect();
+            let warning = format!(
+                "Could not fetch the model list from the proxy ({e}); wrote the default model catalog instead."
+            );
+            (ids, Some(warning))
+        }
+    };
+    let models = aliases
+        .resolve_all(&ids, "openclaw")
+        .iter()
+        .filter_map(|id| openclaw_model_entry(id))
+        .collect();
+    (models, warning)
 }
 
 pub async fn sync_openclaw_config(
     proxy_url: &str,
     api_key: &str,
     model: Option<&str>,
+    aliases: &ModelAliases,
+) -> Result<OpenclawSyncReport, String> {
+    let normalized_url = normalize_base_url(proxy_url);
+
+    // Fetch models from proxy (bundled catalog on failure)
+    let (models, warning) = resolve_models(&normalized_url, api_key, aliases).await;
+    let report = OpenclawSyncReport {
+        model_count: models.len(),
+        models_defaulted: warning.is_some(),
+        warning,
+    };
+
+    let api_key = api_key.to_string();
+    let model = model.map(str::to_string);
+    utils::run_blocking(move || {
+        write_openclaw_config(&normalized_url, &api_key, model.as_deref(), models)
+    })
+    .await??;
+    tracing::info!(
+        "[openclaw] Synced {} models (defaulted: {})",
+        report.model_count,
+        report.models_defaulted
+    );
+    Ok(report)
+}
+
+/// Point the hajimi provider at `normalized_url` with `models`, keeping the rest of the config.
+fn write_openclaw_config(
+    normalized_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    models: Vec<Value>,
 ) -> Result<(), String> {
     let config_path = get_config_path()
         .ok_or_else(|| "Failed to determine OpenClaw config directory".to_string())?;
@@ -168,7 +247,7 @@ pub async fn sync_openclaw_config(
     let mut config: Value = if config_path.exists() {
         fs::read_to_string(&config_path)
             .ok()
-            .and_then(|c| serde_json::from_str(&c).ok())
+            .and_then(|c| utils::parse_json_lenient(&c).ok())
             .unwrap_or_else(|| serde_json::json!({}))
     } else {
         serde_json::json!({})
@@ -178,11 +257,6 @@ pub async fn sync_openclaw_config(
         config = serde_json::json!({});
     }
 
-    let normalized_url = normalize_base_url(proxy_url);
-
-    // Fetch models from proxy
-    let fetched_models = fetch_models_for_openclaw(&normalized_url, api_key).await;
-
     // Ensure models.providers path exists
     if !config.get("models").is_some_and(|v| v.is_object()) {
         config["models"] = serde_json::json!({});
@@ -206,9 +280,7 @@ pub async fn sync_openclaw_config(
         "api": "openai-completions",
     });
 
-    if !fetched_models.is_empty() {
-        provider["models"] = Value::Array(fetched_models);
-    }
+    provider["models"] = Value::Array(models);
 
     // Insert/update hajimi provider
     if let Some(providers) = config["models"]
@@ -242,16 +314,18 @@ pub async fn sync_openclaw_config(
         config["agents"]["defaults"]["model"]["primary"] = Value::String(primary_model);
     }
 
-    let content = utils::to_json_pretty(&config).map_err(|e| e.to_string())?;
-    utils::atomic_write(&config_path, &content).map_err(|e| e.to_string())
+    utils::write_json_secure(&config_path, &config).map_err(|e| e.to_string())?;
+    utils::verify_written_config(&config_path, &[normalized_url, api_key], || {
+        utils::write_json_secure(&config_path, &config)
+    })
+    .map_err(|e| e.to_string())
 }
 
 pub fn restore_openclaw_config() -> Result<(), String> {
     let config_path =
         get_config_path().ok_or_else(|| "Failed to get OpenClaw config directory".to_string())?;
 
-    let backup_path = config_path.with_file_name(format!("{CONFIG_FILE}{BACKUP_SUFFIX}"));
-    if backup_path.exists() {
+    if let Some(backup_path) = utils::find_backup(&config_path) {
         // Atomic rename replaces the target file directly — no intermediate delete needed.
         fs::rename(&backup_path, &config_path)
             .map_err(|e| format!("Failed to restore config: {e}"))?;
@@ -274,8 +348,8 @@ pub fn read_openclaw_config_content() -> Result<String, String> {
 
 pub fn write_openclaw_config_content(content: &str) -> Result<(), String> {
     let config_path = get_config_path().ok_or_else(|| "Config path not found".to_string())?;
-    serde_json::from_str::<serde_json::Value>(content).map_err(|e| format!("Invalid JSON: {e}"))?;
-    utils::atomic_write(&config_path, content).map_err(|e| e.to_string())
+    utils::parse_json_lenient(content).map_err(|e| format!("Invalid JSON: {e}"))?;
+    utils::atomic_write_secure(&config_path, content).map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
@@ -303,4 +377,108 @@ mod tests {
         assert_eq!(normalize_base_url("https://x.com/v1"), "https://x.com/v1");
         assert_eq!(normalize_base_url("https://x.com/v1/"), "https://x.com/v1");
     }
+
+    #[test]
+    fn test_openclaw_model_entry() {
+        let claude = openclaw_model_entry("claude-sonnet-4-5").unwrap();
+        assert_eq!(claude["contextWindow"], 200_000);
+        assert_eq!(claude["maxTokens"], 64_000);
+        assert_eq!(claude["input"], serde_json::json!(["text", "image"]));
+
+        assert!(openclaw_model_entry("gemini-3-pro-image").is_none());
+        assert!(openclaw_model_entry("text-embedding-3-small").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_status_restore_end_to_end() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = paths::override_home(home.path());
+        let config_path = home.path().join(".openclaw").join(CONFIG_FILE);
+        let original = "{\n  \"gateway\": {\n    \"port\": 18789\n  }\n}\n";
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, original).unwrap();
+
+        let proxy = "http://127.0.0.1:1";
+        let report = sync_openclaw_config(
+            proxy,
+            "sk-e2e",
+            Some("claude-sonnet-4-5"),
+            &ModelAliases::default(),
+        )
+        .await
+        .unwrap();
+        assert!(report.models_defaulted);
+
+        let config: Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(config["gateway"]["port"], 18789);
+        let provider = &config["models"]["providers"][PROVIDER_ID];
+        assert_eq!(provider["baseUrl"], "http://127.0.0.1:1/v1");
+        assert_eq!(provider["apiKey"], "sk-e2e");
+        assert_eq!(
+            config["agents"]["defaults"]["model"]["primary"],
+            "hajimi/claude-sonnet-4-5"
+        );
+        assert!(get_sync_status(Some(proxy)).is_synced());
+
+        restore_openclaw_config().unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+        assert!(!get_sync_status(Some(proxy)).is_synced());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_models_falls_back_when_fetch_fails() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A rejected key is not retried, so the fallback kicks in right away
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let base_url = format!("{}/v1", server.uri());
+        let (models, warning) =
+            resolve_models(&base_url, "sk-test", &ModelAliases::default()).await;
+        assert!(warning.is_some());
+        assert_eq!(models.len(), FALLBACK_MODEL_IDS.len());
+        assert_eq!(models[0]["id"], FALLBACK_MODEL_IDS[0]);
+
+        let aliases = ModelAliases::parse(&format!(
+            r#"{{"{}": {{"openclaw": "gw/first"}}}}"#,
+            FALLBACK_MODEL_IDS[0]
+        ))
+        .unwrap();
+        let (models, _) = resolve_models(&base_url, "sk-test", &aliases).await;
+        assert_eq!(models[0]["id"], "gw/first");
+
+        // An empty list falls back too
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"data": []})))
+            .mount(&server)
+            .await;
+        let base_url = format!("{}/v1", server.uri());
+        let (models, warning) =
+            resolve_models(&base_url, "sk-test", &ModelAliases::default()).await;
+        assert!(warning.unwrap().contains("no models"));
+        assert_eq!(models.len(), FALLBACK_MODEL_IDS.len());
+    }
+
+    #[test]
+    fn test_detect_from_config() {
+        let config = r#"{
+            "models": {"providers": {"hajimi": {"baseUrl": "https://p.test/v1", "apiKey": "k"}}},
+            "agents": {"defaults": {"model": {"primary": "hajimi/claude-sonnet-4-5"}}}
+        }"#;
+        let d = detect_from_config(config).unwrap();
+        assert_eq!(
+            (d.url.as_str(), d.api_key.as_str()),
+            ("https://p.test", "k")
+        );
+        assert_eq!(d.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert!(detect_from_config(r#"{"models": {"providers": {}}}"#).is_none());
+    }
 }
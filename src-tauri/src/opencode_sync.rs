@@ -3,11 +3,18 @@ use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
-use crate::utils;
+use crate::file_status::{FileStatus, SyncStatus};
+use crate::model_aliases::ModelAliases;
+use crate::provider_import::DetectedProvider;
+use crate::{config_paths, model_list, paths, utils, wsl};
 
 const OPENCODE_CONFIG_FILE: &str = "opencode.json";
+const OPENCODE_AUTH_FILE: &str = "auth.json";
 use crate::utils::BACKUP_SUFFIX;
 const PROVIDER_ID: &str = "hajimi";
+/// `per_cli_models` key for opencode's `small_model` (title generation and
+/// other lightweight tasks); the plain `opencode` key is its `model`.
+pub const SMALL_MODEL_KEY: &str = "opencode:small";
 
 /// Normalize base URL to ensure it ends with `/v1`
 fn normalize_base_url(input: &str) -> String {
@@ -19,6 +26,35 @@ fn normalize_base_url(input: &str) -> String {
     }
 }
 
+/// AI SDK adapter opencode loads for the hajimi provider (`provider.hajimi.npm`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpencodeAdapter {
+    Anthropic,
+    OpenaiCompatible,
+    #[default]
+    Openai,
+}
+
+impl OpencodeAdapter {
+    /// Parse the adapter id used by the UI / provider settings.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "anthropic" => Some(Self::Anthropic),
+            "openai-compatible" => Some(Self::OpenaiCompatible),
+            "openai" => Some(Self::Openai),
+            _ => None,
+        }
+    }
+
+    pub fn npm_package(&self) -> &'static str {
+        match self {
+            Self::Anthropic => "@ai-sdk/anthropic",
+            Self::OpenaiCompatible => "@ai-sdk/openai-compatible",
+            Self::Openai => "@ai-sdk/openai",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[allow(dead_code)]
 pub struct OpencodeStatus {
@@ -32,137 +68,389 @@ pub struct OpencodeStatus {
 
 fn get_opencode_dir() -> Option<PathBuf> {
     // Respect XDG_CONFIG_HOME on Linux (consistent with lib.rs::get_config_folder_path).
-    let config_base = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            dirs::home_dir()
-                .map(|h| h.join(".config"))
-                .unwrap_or_else(|| PathBuf::from(".config"))
-        });
+    let config_base = paths::cli_env_dir("XDG_CONFIG_HOME").unwrap_or_else(|| {
+        paths::cli_home_dir()
+            .map(|h| h.join(".config"))
+            .unwrap_or_else(|| PathBuf::from(".config"))
+    });
     Some(config_base.join("opencode"))
 }
 
-fn get_config_path() -> Option<PathBuf> {
-    get_opencode_dir().map(|dir| dir.join(OPENCODE_CONFIG_FILE))
+/// Credentials store: `$XDG_DATA_HOME/opencode/auth.json` (default ~/.local/share,
+/// on every platform — opencode uses XDG dirs on macOS and Windows too).
+pub(crate) fn get_auth_path() -> Option<PathBuf> {
+    let data_base = paths::cli_env_dir("XDG_DATA_HOME")
+        .or_else(|| paths::cli_home_dir().map(|h| h.join(".local").join("share")))?;
+    Some(data_base.join("opencode").join(OPENCODE_AUTH_FILE))
+}
+
+/// Config file path: user override, then `$OPENCODE_CONFIG`, then the default dir.
+pub(crate) fn get_config_path() -> Option<PathBuf> {
+    config_paths::resolve_file(
+        "opencode",
+        Some("OPENCODE_CONFIG"),
+        get_opencode_dir().map(|dir| dir.join(OPENCODE_CONFIG_FILE)),
+    )
 }
 
 pub fn check_opencode_installed() -> (bool, Option<String>) {
-    match utils::resolve_executable("opencode") {
+    match wsl::resolve_cli("opencode") {
         Some(path) => {
-            let version = utils::get_cli_version(&path);
+            let version = wsl::cli_version(&path);
             (true, version.or_else(|| Some("unknown".to_string())))
         }
         None => (false, None),
     }
 }
 
-pub fn get_sync_status(proxy_url: &str) -> (bool, bool, Option<String>) {
-    let config_path = match get_config_path() {
-        Some(p) => p,
-        None => return (false, false, None),
+/// `adapter`: when given, the file must also use that adapter to count as synced.
+pub fn get_sync_status(proxy_url: Option<&str>, adapter: Option<OpencodeAdapter>) -> SyncStatus {
+    let Some(config_path) = get_config_path() else {
+        return SyncStatus::default();
     };
+    let has_backup = utils::find_backup(&config_path).is_some();
 
-    let backup_path = config_path.with_file_name(format!("{OPENCODE_CONFIG_FILE}{BACKUP_SUFFIX}"));
-    let has_backup = backup_path.exists();
+    let (config_status, ag_key) = match fs::read_to_string(&config_path) {
+        Ok(content) => config_file_status(&content, proxy_url, adapter),
+        Err(_) => (FileStatus::missing(OPENCODE_CONFIG_FILE), None),
+    };
 
-    if !config_path.exists() {
-        return (false, has_backup, None);
-    }
+    // auth.json must carry the same key, or newer opencode fails to authenticate
+    let auth = get_auth_path().and_then(|p| fs::read_to_string(p).ok());
+    let auth_status = match (auth, ag_key) {
+        (None, _) => FileStatus::missing(OPENCODE_AUTH_FILE),
+        (Some(_), None) => FileStatus::new(OPENCODE_AUTH_FILE, true),
+        (Some(content), Some(key)) => {
+            let auth_key = serde_json::from_str::<Value>(&content)
+                .ok()
+                .and_then(|auth| auth_entry_key(&auth).map(str::to_string));
+            let status = FileStatus::new(OPENCODE_AUTH_FILE, true)
+                .with_synced(auth_key.as_deref() == Some(key.as_str()));
+            if auth_key.is_none() {
+                status.with_note(format!("No {PROVIDER_ID} entry"))
+            } else if status.synced == Some(false) {
+                status.with_note(format!("Key differs from {OPENCODE_CONFIG_FILE}"))
+            } else {
+                status
+            }
+        }
+    };
 
-    let content = match fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return (false, has_backup, None),
+    SyncStatus::new(vec![config_status, auth_status], has_backup)
+}
+
+/// Status of opencode.json plus the key it holds for our provider.
+fn config_file_status(
+    content: &str,
+    proxy_url: Option<&str>,
+    adapter: Option<OpencodeAdapter>,
+) -> (FileStatus, Option<String>) {
+    let json: Value = serde_json::from_str(content).unwrap_or_default();
+    let ag = json.get("provider").and_then(|p| p.get(PROVIDER_ID));
+    let option = |k: &str| {
+        ag.and_then(|prov| prov.get("options"))
+            .and_then(|o| o.get(k))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
     };
+    let (url, key) = (option("baseURL"), option("apiKey"));
 
-    let json: Value = serde_json::from_str(&content).unwrap_or_default();
-    let normalized_proxy = normalize_base_url(proxy_url);
-
-    let ag_url = json
-        .get("provider")
-        .and_then(|p| p.get(PROVIDER_ID))
-        .and_then(|prov| prov.get("options"))
-        .and_then(|o| o.get("baseURL"))
-        .and_then(|v| v.as_str());
-    let ag_key = json
-        .get("provider")
-        .and_then(|p| p.get(PROVIDER_ID))
-        .and_then(|prov| prov.get("options"))
-        .and_then(|o| o.get("apiKey"))
-        .and_then(|v| v.as_str());
-
-    let mut is_synced = true;
-    let mut current_base_url = None;
-
-    if let (Some(url), Some(_key)) = (ag_url, ag_key) {
-        current_base_url = Some(url.to_string());
-        if normalize_base_url(url) != normalized_proxy {
-            is_synced = false;
+    let status = FileStatus::new(OPENCODE_CONFIG_FILE, true);
+    if key.is_none() {
+        return (
+            status.with_synced(false).with_note(format!(
+                "No {PROVIDER_ID} provider with a baseURL and apiKey"
+            )),
+            None,
+        );
+    }
+    let mut status = status.with_url(url, proxy_url, "baseURL", false);
+    if status.synced == Some(true) {
+        if let Some(mismatch) = model_mismatch(&json) {
+            status = status.with_synced(false).with_note(mismatch);
+        }
+    }
+    if let Some(adapter) = adapter {
+        let npm = ag.and_then(|prov| prov.get("npm")).and_then(|v| v.as_str());
+        if npm != Some(adapter.npm_package()) {
+            status = status.with_synced(false).with_note(format!(
+                "Uses {}, expected {}",
+                npm.unwrap_or("no adapter"),
+                adapter.npm_package()
+            ));
         }
-    } else {
-        is_synced = false;
     }
+    (status, key)
+}
 
-    (is_synced, has_backup, current_base_url)
+/// `model` / `small_model` / agent models that name a `hajimi/<id>` missing
+/// from our provider's catalog, which opencode refuses to start with.
+/// References to other providers are the user's choice and not reported.
+fn model_mismatch(json: &Value) -> Option<String> {
+    let catalog = json
+        .pointer(&format!("/provider/{PROVIDER_ID}/models"))
+        .and_then(|m| m.as_object());
+    let prefix = format!("{PROVIDER_ID}/");
+    let unknown: Vec<String> = model_refs(json)
+        .into_iter()
+        .filter_map(|(key, model)| {
+            let id = model.strip_prefix(&prefix)?;
+            (!catalog.is_some_and(|c| c.contains_key(id))).then(|| format!("{key} {model}"))
+        })
+        .collect();
+    (!unknown.is_empty()).then(|| {
+        format!(
+            "Not in the {PROVIDER_ID} model list: {}",
+            unknown.join(", ")
+        )
+    })
 }
 
-/// Fetch model IDs from the proxy's /v1/models endpoint.
-/// Returns a map of model_id -> { "name": model_id } for opencode's models format.
-/// On any failure, returns an empty map (sync still proceeds without models).
-async fn fetch_models_from_proxy(base_url: &str, api_key: &str) -> serde_json::Map<String, Value> {
-    let models_url = format!("{}/models", base_url.trim_end_matches('/'));
-    let client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-    {
-        Ok(c) => c,
-        Err(_) => return serde_json::Map::new(),
-    };
+/// Every model reference in the config: `model`, `small_model` and
+/// `agent.<name>.model`, paired with where it was found.
+fn model_refs(json: &Value) -> Vec<(String, &str)> {
+    let mut refs: Vec<(String, &str)> = ["model", "small_model"]
+        .into_iter()
+        .filter_map(|key| Some((key.to_string(), json.get(key)?.as_str()?)))
+        .collect();
+    if let Some(agents) = json.get("agent").and_then(|a| a.as_object()) {
+        for (name, agent) in agents {
+            if let Some(model) = agent.get("model").and_then(|m| m.as_str()) {
+                refs.push((format!("agent.{name}.model"), model));
+            }
+        }
+    }
+    refs
+}
 
-    let resp = match client
-        .get(&models_url)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .send()
-        .await
-    {
-        Ok(r) if r.status().is_success() => r,
-        _ => return serde_json::Map::new(),
-    };
+/// Provider opencode is configured with: ours if present, otherwise the first
+/// provider that has a `baseURL`. The key may live in auth.json instead of options.
+pub fn detect_existing_provider() -> Option<DetectedProvider> {
+    let config = fs::read_to_string(get_config_path()?).ok()?;
+    let auth = get_auth_path().and_then(|p| fs::read_to_string(p).ok());
+    detect_from_config(&config, auth.as_deref())
+}
 
-    let body: Value = match resp.json().await {
-        Ok(v) => v,
-        Err(_) => return serde_json::Map::new(),
+fn detect_from_config(config: &str, auth: Option<&str>) -> Option<DetectedProvider> {
+    let json: Value = serde_json::from_str(config).ok()?;
+    let auth: Value = auth
+        .and_then(|a| serde_json::from_str(a).ok())
+        .unwrap_or_default();
+    let providers = json.get("provider")?.as_object()?;
+    let has_url = |p: &Value| {
+        p.pointer("/options/baseURL")
+            .and_then(|v| v.as_str())
+            .is_some()
     };
+    let (id, provider) = providers
+        .get_key_value(PROVIDER_ID)
+        .filter(|(_, p)| has_url(p))
+        .or_else(|| providers.iter().find(|(_, p)| has_url(p)))?;
+
+    let url = provider.pointer("/options/baseURL")?.as_str()?;
+    let key = provider
+        .pointer("/options/apiKey")
+        .and_then(|v| v.as_str())
+        .or_else(|| auth.get(id)?.get("key")?.as_str())?;
+    let model = json
+        .get("model")
+        .and_then(|v| v.as_str())
+        .and_then(|m| m.strip_prefix(&format!("{id}/")));
+    DetectedProvider::new("opencode", url, key, model)
+}
+
+/// Catalog used when the proxy's /models endpoint is unreachable or empty.
+/// Shared with openclaw_sync so both tools default to the same models.
+pub(crate) const FALLBACK_MODEL_IDS: &[&str] = &[
+    "claude-sonnet-4-5",
+    "claude-sonnet-4-5-thinking",
+    "claude-opus-4-5-thinking",
+    "gemini-3-pro-high",
+    "gemini-3-flash",
+    "gemini-2.5-pro",
+    "gemini-2.5-flash",
+    "gpt-4o",
+];
+
+/// A model entry under `provider.hajimi.models`, derived from the model id.
+#[derive(Debug, Clone, PartialEq)]
+struct ModelDef {
+    id: String,
+    context: u64,
+    output: u64,
+    reasoning: bool,
+    attachment: bool,
+    tool_call: bool,
+}
+
+impl ModelDef {
+    /// Heuristics for limits and capability flags (same families as openclaw_sync).
+    fn from_id(id: &str) -> Self {
+        let lower = id.to_lowercase();
+        let is_claude = lower.contains("claude");
+        let is_gemini = lower.contains("gemini");
+        let is_image = lower.contains("image");
+        let is_reasoning = lower.contains("thinking")
+            || lower.contains("pro")
+            || lower.starts_with("o1")
+            || lower.starts_with("o3")
+            || lower.starts_with("o4");
+
+        let context = if is_claude {
+            200_000
+        } else if is_gemini {
+            1_048_576
+        } else {
+            128_000
+        };
+        let output = if is_claude { 64_000 } else { 65_536 };
 
-    let mut models = serde_json::Map::new();
-    if let Some(data) = body.get("data").and_then(|v| v.as_array()) {
-        for item in data {
-            if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
-                let is_reasoning = id.contains("thinking") || id.contains("pro");
-                let is_claude = id.contains("claude");
-                let is_gemini = id.contains("gemini");
-                let is_image = id.contains("image");
-                let supports_attachment = is_claude || is_gemini;
-
-                let mut model_obj = serde_json::json!({ "name": id });
-                if let Some(obj) = model_obj.as_object_mut() {
-                    if supports_attachment {
-                        obj.insert("attachment".to_string(), Value::Bool(true));
-                    }
-                    if is_reasoning {
-                        obj.insert("reasoning".to_string(), Value::Bool(true));
-                    }
-                    if !is_image {
-                        obj.insert("tool_call".to_string(), Value::Bool(true));
-                    }
-                }
-                models.insert(id.to_string(), model_obj);
+        Self {
+            id: id.to_string(),
+            context,
+            output,
+            reasoning: is_reasoning,
+            attachment: is_claude || is_gemini,
+            tool_call: !is_image,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut obj = serde_json::json!({
+            "name": self.id,
+            "limit": { "context": self.context, "output": self.output },
+        });
+        if let Some(map) = obj.as_object_mut() {
+            if self.attachment {
+                map.insert("attachment".to_string(), Value::Bool(true));
             }
+            if self.reasoning {
+                map.insert("reasoning".to_string(), Value::Bool(true));
+            }
+            if self.tool_call {
+                map.insert("tool_call".to_string(), Value::Bool(true));
+            }
+        }
+        obj
+    }
+}
+
+/// Build opencode's `models` map (model_id -> definition) for the given ids.
+fn build_model_catalog(ids: &[String]) -> serde_json::Map<String, Value> {
+    ids.iter()
+        .map(|id| (id.clone(), ModelDef::from_id(id).to_json()))
+        .collect()
+}
+
+/// Whether a `models` entry has the shape `ModelDef::to_json` writes: named
+/// after its id, with nothing but limits and capability flags. Anything the
+/// user renamed or extended no longer matches.
+fn is_catalog_entry(id: &str, def: &Value) -> bool {
+    let Some(obj) = def.as_object() else {
+        return false;
+    };
+    obj.get("name").and_then(|v| v.as_str()) == Some(id)
+        && obj.get("limit").is_some_and(|v| v.is_object())
+        && obj.keys().all(|k| {
+            matches!(
+                k.as_str(),
+                "name" | "limit" | "attachment" | "reasoning" | "tool_call"
+            )
+        })
+}
+
+/// Merge a freshly built catalog into the provider's existing models.
+/// Entries the user added by hand are kept; catalog entries are refreshed.
+/// When `catalog` is the proxy's own list (`fresh`), ones we wrote earlier
+/// that it no longer has are dropped; the built-in fallback prunes nothing.
+fn merge_models(
+    existing: Option<&Value>,
+    catalog: serde_json::Map<String, Value>,
+    fresh: bool,
+) -> serde_json::Map<String, Value> {
+    let mut merged = existing
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default();
+    if fresh {
+        merged.retain(|id, def| catalog.contains_key(id) || !is_catalog_entry(id, def));
+    }
+    for (id, def) in catalog {
+        merged.insert(id, def);
+    }
+    merged
+}
+
+/// Fetch model IDs from the proxy's /v1/models endpoint.
+/// On any failure, returns an empty list (caller falls back to the built-in catalog).
+async fn fetch_model_ids(base_url: &str, api_key: &str) -> Vec<String> {
+    model_list::fetch_openai_models(
+        base_url,
+        api_key,
+        model_list::FETCH_RETRIES,
+        model_list::FETCH_TIMEOUT,
+    )
+    .await
+    .unwrap_or_default()
+}
+
+/// `model` becomes opencode's default `model`; `small_model`, when given,
+/// its `small_model` (otherwise that follows `model` unless set elsewhere).
+/// Catalog ids are written through `aliases`.
+pub async fn sync_opencode_config(
+    proxy_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    small_model: Option<&str>,
+    adapter: OpencodeAdapter,
+    aliases: &ModelAliases,
+) -> Result<(), String> {
+    let model = model.filter(|m| !m.trim().is_empty());
+    let small_model = small_model.filter(|m| !m.trim().is_empty());
+    let normalized_url = normalize_base_url(proxy_url);
+
+    let mut model_ids = fetch_model_ids(&normalized_url, api_key).await;
+    let fresh = !model_ids.is_empty();
+    if !fresh {
+        tracing::warn!("[opencode] Could not fetch models from proxy, using built-in catalog");
+        model_ids = FALLBACK_MODEL_IDS.iter().map(|s| s.to_string()).collect();
+    }
+    let mut model_ids = aliases.resolve_all(&model_ids, "opencode");
+    // The models we point opencode at must be in the catalog
+    for id in model.into_iter().chain(small_model) {
+        if !model_ids.iter().any(|m| m == id) {
+            model_ids.push(id.to_string());
         }
     }
-    models
+    let catalog = build_model_catalog(&model_ids);
+
+    let api_key = api_key.to_string();
+    let model = model.map(str::to_string);
+    let small_model = small_model.map(str::to_string);
+    utils::run_blocking(move || {
+        write_opencode_config(
+            &normalized_url,
+            &api_key,
+            model.as_deref(),
+            small_model.as_deref(),
+            adapter,
+            catalog,
+            fresh,
+        )
+    })
+    .await?
 }
 
-pub async fn sync_opencode_config(proxy_url: &str, api_key: &str) -> Result<(), String> {
+/// Write the hajimi provider with `catalog` into opencode.json, then the key into auth.json.
+fn write_opencode_config(
+    normalized_url: &str,
+    api_key: &str,
+    model: Option<&str>,
+    small_model: Option<&str>,
+    adapter: OpencodeAdapter,
+    catalog: serde_json::Map<String, Value>,
+    fresh: bool,
+) -> Result<(), String> {
     let config_path = get_config_path().ok_or_else(|| {
         "Failed to get OpenCode config directory (home dir not found)".to_string()
     })?;
@@ -191,11 +479,6 @@ pub async fn sync_opencode_config(proxy_url: &str, api_key: &str) -> Result<(),
         config["$schema"] = Value::String("https://opencode.ai/config.json".to_string());
     }
 
-    let normalized_url = normalize_base_url(proxy_url);
-
-    // Fetch models from proxy before any mutable borrows
-    let fetched_models = fetch_models_from_proxy(&normalized_url, api_key).await;
-
     // Ensure provider object exists
     if !config.get("provider").is_some_and(|v| v.is_object()) {
         config["provider"] = serde_json::json!({});
@@ -209,7 +492,7 @@ pub async fn sync_opencode_config(proxy_url: &str, api_key: &str) -> Result<(),
             if let Some(obj) = ag_provider.as_object_mut() {
                 obj.insert(
                     "npm".to_string(),
-                    Value::String("@ai-sdk/openai".to_string()),
+                    Value::String(adapter.npm_package().to_string()),
                 );
                 obj.insert("name".to_string(), Value::String("Hajimi".to_string()));
             }
@@ -221,36 +504,216 @@ pub async fn sync_opencode_config(proxy_url: &str, api_key: &str) -> Result<(),
                 .get_mut("options")
                 .and_then(|o| o.as_object_mut())
             {
-                options.insert("baseURL".to_string(), Value::String(normalized_url));
+                options.insert(
+                    "baseURL".to_string(),
+                    Value::String(normalized_url.to_string()),
+                );
                 options.insert("apiKey".to_string(), Value::String(api_key.to_string()));
             }
 
-            // Always update models from proxy (reflects current proxy model list)
-            if !fetched_models.is_empty() {
-                if let Some(obj) = ag_provider.as_object_mut() {
-                    obj.insert("models".to_string(), Value::Object(fetched_models));
-                }
+            // Refresh catalog models while keeping any the user added by hand
+            let models = merge_models(ag_provider.get("models"), catalog, fresh);
+            if let Some(obj) = ag_provider.as_object_mut() {
+                obj.insert("models".to_string(), Value::Object(models));
             }
         }
     }
 
-    let content = utils::to_json_pretty(&config).map_err(|e| e.to_string())?;
-    utils::atomic_write(&config_path, &content).map_err(|e| e.to_string())
+    set_default_models(&mut config, model, small_model);
+
+    utils::write_json_secure(&config_path, &config).map_err(|e| e.to_string())?;
+    utils::verify_written_config(&config_path, &[normalized_url, api_key], || {
+        utils::write_json_secure(&config_path, &config)
+    })
+    .map_err(|e| e.to_string())?;
+
+    write_auth_entry(api_key)
+}
+
+/// Upsert `{"hajimi": {"type": "api", "key": ...}}` into opencode's auth.json.
+fn write_auth_entry(api_key: &str) -> Result<(), String> {
+    let auth_path =
+        get_auth_path().ok_or_else(|| "Failed to get OpenCode data directory".to_string())?;
+
+    if let Some(parent) = auth_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {parent:?}: {e}"))?;
+    }
+
+    utils::create_rotated_backup(&auth_path, BACKUP_SUFFIX).map_err(|e| e.to_string())?;
+
+    // Other providers' credentials live here too: a file that doesn't parse
+    // is repaired from a backup or refused, never replaced with `{}`
+    let content = match fs::read_to_string(&auth_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read auth.json: {e}")),
+    };
+    let mut auth =
+        utils::validate_and_repair_json(&auth_path, &content).map_err(|e| e.to_string())?;
+    upsert_auth_entry(&mut auth, api_key);
+
+    utils::write_json_secure(&auth_path, &auth).map_err(|e| e.to_string())?;
+    utils::verify_written_config(&auth_path, &[api_key], || {
+        utils::write_json_secure(&auth_path, &auth)
+    })
+    .map_err(|e| e.to_string())
+}
+
+fn upsert_auth_entry(auth: &mut Value, api_key: &str) {
+    if !auth.is_object() {
+        *auth = serde_json::json!({});
+    }
+    auth[PROVIDER_ID] = serde_json::json!({ "type": "api", "key": api_key });
+}
+
+fn auth_entry_key(auth: &Value) -> Option<&str> {
+    auth.get(PROVIDER_ID)?.get("key")?.as_str()
+}
+
+/// Undo our auth.json change. The file is opencode's shared credential
+/// store and other logins (and refreshed OAuth tokens) may have changed since
+/// the backup, so only the hajimi entry is touched: the backed-up one is put
+/// back, or ours is dropped when the backup had none.
+fn restore_auth_entry() -> Result<(), String> {
+    let Some(auth_path) = get_auth_path() else {
+        return Ok(());
+    };
+    let backup_path = utils::find_backup(&auth_path);
+    let backed_up = backup_path
+        .as_ref()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|c| utils::parse_json_lenient(&c).ok())
+        .and_then(|v| v.get(PROVIDER_ID).cloned());
+
+    if auth_path.exists() {
+        let content =
+            fs::read_to_string(&auth_path).map_err(|e| format!("Failed to read auth.json: {e}"))?;
+        let mut auth: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse auth.json: {e}"))?;
+        if let Some(obj) = auth.as_object_mut() {
+            let changed = match backed_up {
+                Some(entry) => obj.insert(PROVIDER_ID.to_string(), entry.clone()) != Some(entry),
+                None => obj.remove(PROVIDER_ID).is_some(),
+            };
+            if changed {
+                utils::write_json_secure(&auth_path, &auth).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if let Some(backup_path) = backup_path {
+        let _ = fs::remove_file(backup_path);
+    }
+    Ok(())
+}
+
+/// Point opencode's top-level `model` at `hajimi/<model>`. An explicit
+/// `small_model` is always written; otherwise `small_model` follows `model`
+/// only when unset or already ours, so a user's other-provider choice stays.
+fn set_default_models(config: &mut Value, model: Option<&str>, small_model: Option<&str>) {
+    let Some(obj) = config.as_object_mut() else {
+        return;
+    };
+    if let Some(model) = model {
+        obj.insert(
+            "model".to_string(),
+            Value::String(format!("{PROVIDER_ID}/{model}")),
+        );
+    }
+
+    let small = match (small_model, model) {
+        (Some(small), _) => small,
+        (None, Some(model)) => {
+            let ours_or_unset = match obj.get("small_model").and_then(|v| v.as_str()) {
+                None => true,
+                Some(current) => current.starts_with(&format!("{PROVIDER_ID}/")),
+            };
+            if !ours_or_unset {
+                return;
+            }
+            model
+        }
+        (None, None) => return,
+    };
+    obj.insert(
+        "small_model".to_string(),
+        Value::String(format!("{PROVIDER_ID}/{small}")),
+    );
 }
 
 pub fn restore_opencode_config() -> Result<(), String> {
     let config_path =
         get_config_path().ok_or_else(|| "Failed to get OpenCode config directory".to_string())?;
 
-    let backup_path = config_path.with_file_name(format!("{OPENCODE_CONFIG_FILE}{BACKUP_SUFFIX}"));
-    if backup_path.exists() {
+    restore_auth_entry()?;
+
+    if let Some(backup_path) = utils::find_backup(&config_path) {
         // Atomic rename replaces the target file directly — no intermediate delete needed.
         fs::rename(&backup_path, &config_path)
             .map_err(|e| format!("Failed to restore config: {e}"))?;
-        Ok(())
-    } else {
-        Err("No backup file found".to_string())
+        return Ok(());
+    }
+
+    // No backup found — remove only what we injected, leaving the user's other
+    // providers and settings intact.
+    if !config_path.exists() {
+        return Ok(());
+    }
+    let content =
+        fs::read_to_string(&config_path).map_err(|e| format!("Failed to read config: {e}"))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Cannot restore: opencode.json is not valid JSON: {e}"))?;
+    if !strip_hajimi_provider(&mut json) {
+        return Ok(());
+    }
+    tracing::info!("[opencode] No backup found, removed hajimi provider from config");
+    utils::write_json_secure(&config_path, &json).map_err(|e| e.to_string())
+}
+
+/// Remove `provider.hajimi`, model references to it (top-level and per
+/// agent), and the `$schema` we add
+/// when nothing else is left. Returns whether anything changed.
+fn strip_hajimi_provider(json: &mut Value) -> bool {
+    let Some(obj) = json.as_object_mut() else {
+        return false;
+    };
+    let mut changed = false;
+
+    if let Some(providers) = obj.get_mut("provider").and_then(|p| p.as_object_mut()) {
+        changed |= providers.remove(PROVIDER_ID).is_some();
+        if providers.is_empty() {
+            obj.remove("provider");
+        }
+    }
+
+    let prefix = format!("{PROVIDER_ID}/");
+    for key in ["model", "small_model"] {
+        if obj
+            .get(key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|m| m.starts_with(&prefix))
+        {
+            obj.remove(key);
+            changed = true;
+        }
+    }
+    if let Some(agents) = obj.get_mut("agent").and_then(|a| a.as_object_mut()) {
+        for agent in agents.values_mut().filter_map(|a| a.as_object_mut()) {
+            if agent
+                .get("model")
+                .and_then(|v| v.as_str())
+                .is_some_and(|m| m.starts_with(&prefix))
+            {
+                agent.remove("model");
+                changed = true;
+            }
+        }
+    }
+
+    if changed && obj.len() == 1 && obj.contains_key("$schema") {
+        obj.remove("$schema");
     }
+    changed
 }
 
 pub fn read_opencode_config_content() -> Result<String, String> {
@@ -266,8 +729,32 @@ pub fn read_opencode_config_content() -> Result<String, String> {
 
 pub fn write_opencode_config_content(content: &str) -> Result<(), String> {
     let config_path = get_config_path().ok_or_else(|| "Config path not found".to_string())?;
-    serde_json::from_str::<serde_json::Value>(content).map_err(|e| format!("Invalid JSON: {e}"))?;
-    fs::write(&config_path, content).map_err(|e| format!("Failed to write config: {e}"))
+    validate_config_content(content)?;
+
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {parent:?}: {e}"))?;
+    }
+    utils::atomic_write_secure(&config_path, content).map_err(|e| e.to_string())
+}
+
+/// Reject editor content opencode would fail to load: malformed JSON (reported
+/// with line/column), a non-object root, or a non-object `provider`.
+fn validate_config_content(content: &str) -> Result<(), String> {
+    let json: Value = serde_json::from_str(content).map_err(|e| {
+        format!(
+            "Invalid JSON at line {}, column {}: {e}",
+            e.line(),
+            e.column()
+        )
+    })?;
+    let obj = json
+        .as_object()
+        .ok_or_else(|| "opencode.json must be a JSON object".to_string())?;
+    if obj.get("provider").is_some_and(|p| !p.is_object()) {
+        return Err("\"provider\" must be a JSON object".to_string());
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -294,4 +781,323 @@ mod tests {
         );
         assert_eq!(normalize_base_url("  http://x.com  "), "http://x.com/v1");
     }
+
+    #[test]
+    fn test_model_def_heuristics() {
+        let claude = ModelDef::from_id("claude-sonnet-4-5-thinking");
+        assert_eq!(claude.context, 200_000);
+        assert_eq!(claude.output, 64_000);
+        assert!(claude.reasoning && claude.attachment && claude.tool_call);
+
+        let gemini = ModelDef::from_id("gemini-2.5-flash");
+        assert_eq!(gemini.context, 1_048_576);
+        assert!(!gemini.reasoning);
+
+        let image = ModelDef::from_id("gemini-3-pro-image");
+        assert!(!image.tool_call);
+
+        let json = ModelDef::from_id("gpt-4o").to_json();
+        assert_eq!(json["name"], "gpt-4o");
+        assert_eq!(json["limit"]["context"], 128_000);
+        assert!(json.get("attachment").is_none());
+    }
+
+    #[test]
+    fn test_merge_models_keeps_manual_entries() {
+        let existing = serde_json::json!({
+            "my-local-model": { "name": "My local model" },
+            "gpt-4o": { "name": "stale" }
+        });
+        let catalog = build_model_catalog(&["gpt-4o".to_string(), "o3".to_string()]);
+        let merged = merge_models(Some(&existing), catalog, true);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged["my-local-model"]["name"], "My local model");
+        assert_eq!(merged["gpt-4o"]["name"], "gpt-4o");
+        assert_eq!(merged["o3"]["reasoning"], true);
+    }
+
+    #[test]
+    fn test_merge_models_drops_models_gone_upstream() {
+        let mut existing = Value::Object(build_model_catalog(&[
+            "gpt-4o".to_string(),
+            "retired-model".to_string(),
+        ]));
+        existing["mine"] = serde_json::json!({
+            "name": "mine",
+            "limit": { "context": 1, "output": 1 },
+            "options": { "x": 1 }
+        });
+        existing["renamed"] =
+            serde_json::json!({ "name": "My pick", "limit": { "context": 1, "output": 1 } });
+        let catalog = build_model_catalog(&["gpt-4o".to_string()]);
+        let merged = merge_models(Some(&existing), catalog.clone(), true);
+
+        let mut ids: Vec<&str> = merged.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, ["gpt-4o", "mine", "renamed"]);
+
+        // The built-in fallback says nothing about what the proxy serves
+        let merged = merge_models(Some(&existing), catalog, false);
+        assert!(merged.contains_key("retired-model"));
+    }
+
+    #[test]
+    fn test_adapter_parse_and_package() {
+        assert_eq!(
+            OpencodeAdapter::parse("openai-compatible"),
+            Some(OpencodeAdapter::OpenaiCompatible)
+        );
+        assert_eq!(OpencodeAdapter::parse("bogus"), None);
+        assert_eq!(
+            OpencodeAdapter::Anthropic.npm_package(),
+            "@ai-sdk/anthropic"
+        );
+        assert_eq!(OpencodeAdapter::default().npm_package(), "@ai-sdk/openai");
+    }
+
+    #[test]
+    fn test_set_default_models() {
+        let mut config = serde_json::json!({});
+        set_default_models(&mut config, Some("claude-sonnet-4-5"), None);
+        assert_eq!(config["model"], "hajimi/claude-sonnet-4-5");
+        assert_eq!(config["small_model"], "hajimi/claude-sonnet-4-5");
+
+        // A small_model pointing at another provider is left alone
+        let mut config = serde_json::json!({ "small_model": "anthropic/claude-haiku" });
+        set_default_models(&mut config, Some("gpt-4o"), None);
+        assert_eq!(config["model"], "hajimi/gpt-4o");
+        assert_eq!(config["small_model"], "anthropic/claude-haiku");
+
+        // ...unless one is asked for explicitly
+        set_default_models(&mut config, None, Some("claude-haiku-4-5"));
+        assert_eq!(config["model"], "hajimi/gpt-4o");
+        assert_eq!(config["small_model"], "hajimi/claude-haiku-4-5");
+    }
+
+    #[test]
+    fn test_status_reports_models_missing_from_catalog() {
+        let proxy = "https://proxy.test/v1";
+        let mut config = serde_json::json!({
+            "model": "hajimi/gpt-4o",
+            "small_model": "anthropic/claude-haiku",
+            "provider": { "hajimi": {
+                "options": { "baseURL": proxy, "apiKey": "sk-1" },
+                "models": { "gpt-4o": {} }
+            }}
+        });
+        let (status, _) = config_file_status(&config.to_string(), Some(proxy), None);
+        assert_eq!(status.synced, Some(true));
+
+        config["agent"] = serde_json::json!({ "plan": { "model": "hajimi/o3" } });
+        config["small_model"] = Value::String("hajimi/gone".into());
+        let (status, _) = config_file_status(&config.to_string(), Some(proxy), None);
+        assert_eq!(status.synced, Some(false));
+        let note = status.note.unwrap();
+        assert!(note.contains("small_model hajimi/gone"));
+        assert!(note.contains("agent.plan.model hajimi/o3"));
+    }
+
+    #[test]
+    fn test_auth_entry_upsert() {
+        let mut auth = serde_json::json!({
+            "anthropic": { "type": "oauth", "refresh": "r" }
+        });
+        upsert_auth_entry(&mut auth, "sk-new");
+        assert_eq!(auth["hajimi"]["type"], "api");
+        assert_eq!(auth_entry_key(&auth), Some("sk-new"));
+        // Other providers' credentials are untouched
+        assert_eq!(auth["anthropic"]["type"], "oauth");
+
+        let mut broken = serde_json::json!([]);
+        upsert_auth_entry(&mut broken, "k");
+        assert_eq!(auth_entry_key(&broken), Some("k"));
+    }
+
+    #[test]
+    fn test_corrupt_auth_json_keeps_other_providers() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = paths::override_home(home.path());
+        let auth_path = home.path().join(".local/share/opencode/auth.json");
+        fs::create_dir_all(auth_path.parent().unwrap()).unwrap();
+        let corrupt = "{\"zai\": {\"type\": \"api\", \"key\": \"z\"},";
+        fs::write(&auth_path, corrupt).unwrap();
+
+        // No usable backup: refuse rather than drop the zai credentials
+        assert!(write_auth_entry("sk-new").is_err());
+        assert_eq!(fs::read_to_string(&auth_path).unwrap(), corrupt);
+
+        fs::write(
+            utils::backup_path(&auth_path),
+            r#"{"zai": {"type": "api", "key": "z"}}"#,
+        )
+        .unwrap();
+        write_auth_entry("sk-new").unwrap();
+        let auth: Value = serde_json::from_str(&fs::read_to_string(&auth_path).unwrap()).unwrap();
+        assert_eq!(auth["zai"]["key"], "z");
+        assert_eq!(auth_entry_key(&auth), Some("sk-new"));
+    }
+
+    #[test]
+    fn test_restore_auth_entry_keeps_newer_logins() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = paths::override_home(home.path());
+        let auth_path = home.path().join(".local/share/opencode/auth.json");
+        fs::create_dir_all(auth_path.parent().unwrap()).unwrap();
+        fs::write(
+            &auth_path,
+            r#"{"zai": {"type": "api", "key": "z"}, "hajimi": {"type": "api", "key": "sk-old"}}"#,
+        )
+        .unwrap();
+        write_auth_entry("sk-new").unwrap();
+
+        // Another provider logs in and an OAuth token rotates after our sync
+        let mut auth: Value =
+            serde_json::from_str(&fs::read_to_string(&auth_path).unwrap()).unwrap();
+        auth["zai"]["key"] = Value::from("z2");
+        auth["anthropic"] = serde_json::json!({ "type": "oauth", "refresh": "r" });
+        fs::write(&auth_path, auth.to_string()).unwrap();
+
+        restore_auth_entry().unwrap();
+        let auth: Value = serde_json::from_str(&fs::read_to_string(&auth_path).unwrap()).unwrap();
+        assert_eq!(auth["zai"]["key"], "z2");
+        assert_eq!(auth["anthropic"]["refresh"], "r");
+        assert_eq!(auth_entry_key(&auth), Some("sk-old"));
+        assert!(utils::find_backup(&auth_path).is_none());
+    }
+
+    #[test]
+    fn test_resync_same_key_leaves_auth_json_byte_identical() {
+        let original = "{\n\t\"zai\": {\"type\": \"api\", \"key\": \"z\"},\n\t\"hajimi\": {\"type\": \"api\", \"key\": \"sk-1\"}\n}\n";
+        let mut auth: Value = serde_json::from_str(original).unwrap();
+        upsert_auth_entry(&mut auth, "sk-1");
+        assert_eq!(utils::to_json_like(&auth, original).unwrap(), original);
+    }
+
+    #[test]
+    fn test_backup_path_keeps_file_name() {
+        let p = PathBuf::from("/tmp/custom/my-opencode.json");
+        assert_eq!(
+            utils::backup_path(&p),
+            PathBuf::from(format!("/tmp/custom/my-opencode.json{BACKUP_SUFFIX}"))
+        );
+    }
+
+    #[test]
+    fn test_strip_hajimi_provider() {
+        let mut json = serde_json::json!({
+            "$schema": "https://opencode.ai/config.json",
+            "theme": "dark",
+            "model": "hajimi/gpt-4o",
+            "small_model": "anthropic/claude-haiku",
+            "agent": {
+                "plan": { "model": "hajimi/o3", "temperature": 0.1 },
+                "build": { "model": "other/gpt-5" }
+            },
+            "provider": {
+                "hajimi": { "npm": "@ai-sdk/openai" },
+                "other": { "npm": "@ai-sdk/anthropic" }
+            }
+        });
+        assert!(strip_hajimi_provider(&mut json));
+        assert!(json["agent"]["plan"].get("model").is_none());
+        assert_eq!(json["agent"]["plan"]["temperature"], 0.1);
+        assert_eq!(json["agent"]["build"]["model"], "other/gpt-5");
+        assert!(json["provider"].get("hajimi").is_none());
+        assert!(json["provider"].get("other").is_some());
+        assert!(json.get("model").is_none());
+        assert_eq!(json["small_model"], "anthropic/claude-haiku");
+        assert!(json.get("$schema").is_some());
+
+        // A file that only held our provider ends up empty
+        let mut json = serde_json::json!({
+            "$schema": "https://opencode.ai/config.json",
+            "provider": { "hajimi": {} }
+        });
+        assert!(strip_hajimi_provider(&mut json));
+        assert_eq!(json, serde_json::json!({}));
+
+        let mut untouched = serde_json::json!({ "theme": "dark" });
+        assert!(!strip_hajimi_provider(&mut untouched));
+    }
+
+    #[test]
+    fn test_validate_config_content() {
+        let err = validate_config_content("{\n  \"theme\": \"dark\",\n  oops\n}").unwrap_err();
+        assert!(err.contains("line 3"), "{err}");
+        assert!(err.contains("column"), "{err}");
+
+        assert!(validate_config_content("[]").is_err());
+        assert!(validate_config_content(r#"{"provider": "hajimi"}"#).is_err());
+        assert!(validate_config_content(r#"{"provider": {"hajimi": {}}}"#).is_ok());
+    }
+
+    #[test]
+    fn test_fallback_catalog_size() {
+        let ids: Vec<String> = FALLBACK_MODEL_IDS.iter().map(|s| s.to_string()).collect();
+        assert_eq!(build_model_catalog(&ids).len(), FALLBACK_MODEL_IDS.len());
+    }
+
+    #[tokio::test]
+    async fn test_sync_status_restore_end_to_end() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = paths::override_home(home.path());
+        let config_path = home.path().join(".config/opencode/opencode.json");
+        let auth_path = home.path().join(".local/share/opencode/auth.json");
+        let original = "{\n  \"theme\": \"tokyonight\"\n}\n";
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, original).unwrap();
+
+        // Nothing listens on port 1, so the built-in catalog is written
+        let proxy = "http://127.0.0.1:1/v1";
+        sync_opencode_config(
+            proxy,
+            "sk-e2e",
+            Some("claude-sonnet-4-5"),
+            None,
+            OpencodeAdapter::default(),
+            &ModelAliases::default(),
+        )
+        .await
+        .unwrap();
+
+        let config: Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(config["theme"], "tokyonight");
+        assert_eq!(config["provider"][PROVIDER_ID]["options"]["baseURL"], proxy);
+        assert_eq!(config["model"], "hajimi/claude-sonnet-4-5");
+        let auth: Value = serde_json::from_str(&fs::read_to_string(&auth_path).unwrap()).unwrap();
+        assert_eq!(auth_entry_key(&auth), Some("sk-e2e"));
+        assert!(get_sync_status(Some(proxy), None).is_synced());
+
+        restore_opencode_config().unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+        let auth: Value = serde_json::from_str(&fs::read_to_string(&auth_path).unwrap()).unwrap();
+        assert_eq!(auth_entry_key(&auth), None);
+        assert!(!get_sync_status(Some(proxy), None).is_synced());
+    }
+
+    #[test]
+    fn test_detect_from_config() {
+        let config = r#"{
+            "model": "corp/gpt-5",
+            "provider": {
+                "local": {"options": {}},
+                "corp": {"options": {"baseURL": "https://corp.test/v1"}}
+            }
+        }"#;
+        let auth = r#"{"corp": {"type": "api", "key": "corp-key"}}"#;
+        let d = detect_from_config(config, Some(auth)).unwrap();
+        assert_eq!(d.url, "https://corp.test");
+        assert_eq!(d.api_key, "corp-key");
+        assert_eq!(d.model.as_deref(), Some("gpt-5"));
+        assert!(detect_from_config(config, None).is_none());
+
+        // Our own entry wins over others
+        let config = r#"{"provider": {
+            "corp": {"options": {"baseURL": "https://corp.test", "apiKey": "a"}},
+            "hajimi": {"options": {"baseURL": "https://ours.test/v1", "apiKey": "b"}}
+        }}"#;
+        assert_eq!(detect_from_config(config, None).unwrap().api_key, "b");
+    }
 }
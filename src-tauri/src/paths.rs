@@ -0,0 +1,116 @@
+//! The home directory the sync modules build their default config paths from.
+//!
+//! Normally the real home. `HAJIMI_TEST_HOME` (or, in tests, `override_home`
+//! for the current thread) swaps in another directory, which then stands in
+//! for the whole user environment: env vars such as `CODEX_HOME` or
+//! `XDG_CONFIG_HOME` are ignored so nothing escapes the scratch home.
+
+use std::path::PathBuf;
+
+/// Points every default config path at this directory instead of the home.
+pub const TEST_HOME_ENV: &str = "HAJIMI_TEST_HOME";
+
+#[cfg(test)]
+thread_local! {
+    static HOME_OVERRIDE: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+fn overridden_home() -> Option<PathBuf> {
+    #[cfg(test)]
+    if let Some(home) = HOME_OVERRIDE.with(|h| h.borrow().clone()) {
+        return Some(home);
+    }
+    std::env::var_os(TEST_HOME_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    overridden_home().or_else(dirs::home_dir)
+}
+
+/// Home the CLI apps' configs live under: the WSL distro's home when one is
+/// targeted (see `wsl`), else `home_dir`. An overridden home still wins.
+pub fn cli_home_dir() -> Option<PathBuf> {
+    if overridden_home().is_none() {
+        if let Some(target) = crate::wsl::target() {
+            return Some(target.home());
+        }
+    }
+    home_dir()
+}
+
+/// `env_var` for the CLI apps: the Windows environment says nothing about a
+/// WSL distro, so nothing is read while one is targeted.
+pub fn cli_env_var(var: &str) -> Option<String> {
+    if crate::wsl::target().is_some() {
+        return None;
+    }
+    env_var(var)
+}
+
+/// `env_dir` counterpart of `cli_env_var`.
+pub fn cli_env_dir(var: &str) -> Option<PathBuf> {
+    if crate::wsl::target().is_some() {
+        return None;
+    }
+    env_dir(var)
+}
+
+/// Env var `var`, if set and non-empty. Always `None` while the home is
+/// overridden.
+pub fn env_var(var: &str) -> Option<String> {
+    if overridden_home().is_some() {
+        return None;
+    }
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Directory named by env var `var`, if set and non-empty. Always `None` while
+/// the home is overridden.
+pub fn env_dir(var: &str) -> Option<PathBuf> {
+    env_var(var).map(PathBuf::from)
+}
+
+/// This thread's home override, for handing to another thread.
+#[cfg(test)]
+pub fn current_override() -> Option<PathBuf> {
+    HOME_OVERRIDE.with(|h| h.borrow().clone())
+}
+
+/// Restores the previous home override when dropped.
+#[cfg(test)]
+pub struct HomeOverride(Option<PathBuf>);
+
+/// Use `home` as the home directory on this thread until the guard drops.
+/// Async tests must stay on a current-thread runtime.
+#[cfg(test)]
+pub fn override_home(home: &std::path::Path) -> HomeOverride {
+    HomeOverride(HOME_OVERRIDE.with(|h| h.replace(Some(home.to_path_buf()))))
+}
+
+#[cfg(test)]
+impl Drop for HomeOverride {
+    fn drop(&mut self) {
+        HOME_OVERRIDE.with(|h| *h.borrow_mut() = self.0.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_home_is_scoped() {
+        let tmp = tempfile::tempdir().unwrap();
+        {
+            let _home = override_home(tmp.path());
+            assert_eq!(home_dir(), Some(tmp.path().to_path_buf()));
+            assert_eq!(env_dir("PATH"), None);
+        }
+        assert_eq!(home_dir(), dirs::home_dir());
+    }
+}
@@ -0,0 +1,66 @@
+//! Apps pinned to their current config (e.g. Gemini kept on Google's own
+//! endpoint). `sync_all` and provider switches skip them and report them as
+//! skipped; syncing a pinned app on its own still works.
+
+use std::collections::BTreeSet;
+
+use crate::database::dao::settings;
+use crate::database::Database;
+
+/// JSON array of app ids.
+const PINNED_APPS_KEY: &str = "pinned_apps";
+
+pub fn list(db: &Database) -> BTreeSet<String> {
+    settings::get(db, PINNED_APPS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+pub fn is_pinned(db: &Database, app: &str) -> bool {
+    list(db).contains(app)
+}
+
+pub fn set_pinned(db: &Database, app: &str, pinned: bool) -> Result<(), String> {
+    let mut apps = list(db);
+    let changed = if pinned {
+        apps.insert(app.to_string())
+    } else {
+        apps.remove(app)
+    };
+    if !changed {
+        return Ok(());
+    }
+    let json = serde_json::to_string(&apps).map_err(|e| e.to_string())?;
+    settings::set(db, PINNED_APPS_KEY, &json)?;
+    tracing::info!(
+        "[pinned_apps] {} {}",
+        if pinned { "Pinned" } else { "Unpinned" },
+        app
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let db = Database::memory().unwrap();
+        assert!(list(&db).is_empty());
+        set_pinned(&db, "gemini", true).unwrap();
+        set_pinned(&db, "gemini", true).unwrap();
+        set_pinned(&db, "codex", true).unwrap();
+        assert!(is_pinned(&db, "gemini"));
+        assert_eq!(list(&db).len(), 2);
+
+        set_pinned(&db, "gemini", false).unwrap();
+        assert!(!is_pinned(&db, "gemini"));
+        assert!(is_pinned(&db, "codex"));
+
+        settings::set(&db, PINNED_APPS_KEY, "not json").unwrap();
+        assert!(list(&db).is_empty());
+    }
+}
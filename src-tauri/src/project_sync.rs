@@ -0,0 +1,211 @@
+//! Project-level Claude settings (`<project>/.claude/settings*.json`), which
+//! override the user-level file inside that project. The merge, backup and
+//! restore logic is `cli_sync`'s, pointed at the project directory.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::cli_sync;
+use crate::database::dao::{providers, settings};
+use crate::database::Database;
+use crate::error::CommandError;
+use crate::file_status::FileStatus;
+use crate::store::AppState;
+use crate::synced_providers;
+use crate::utils;
+
+/// JSON array of project dirs, most recent first.
+const RECENT_PROJECTS_KEY: &str = "claude_recent_projects";
+const MAX_RECENT_PROJECTS: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectSyncStatus {
+    pub project_dir: String,
+    pub is_synced: bool,
+    pub has_backup: bool,
+    pub current_base_url: Option<String>,
+    pub file_status: Vec<FileStatus>,
+}
+
+pub fn recent_projects(db: &Database) -> Vec<String> {
+    settings::get(db, RECENT_PROJECTS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+fn remember_project(db: &Database, dir: &Path) {
+    let dir = dir.to_string_lossy().to_string();
+    let mut recent = recent_projects(db);
+    recent.retain(|d| *d != dir);
+    recent.insert(0, dir);
+    recent.truncate(MAX_RECENT_PROJECTS);
+    let saved = serde_json::to_string(&recent)
+        .map_err(|e| e.to_string())
+        .and_then(|json| settings::set(db, RECENT_PROJECTS_KEY, &json));
+    if let Err(e) = saved {
+        tracing::warn!("[project_sync] Failed to remember project dir: {}", e);
+    }
+}
+
+fn check_app(app: &str) -> Result<(), CommandError> {
+    if app == "claude" {
+        Ok(())
+    } else {
+        Err(CommandError::invalid_input(format!(
+            "Project settings are only supported for Claude Code, not {app}"
+        )))
+    }
+}
+
+fn project_path(project_dir: &str) -> Result<PathBuf, CommandError> {
+    let dir = PathBuf::from(project_dir.trim());
+    if !dir.is_absolute() || !dir.is_dir() {
+        return Err(CommandError::invalid_input(format!(
+            "Not a project folder: {project_dir}"
+        )));
+    }
+    Ok(dir)
+}
+
+/// Write the proxy into `<project_dir>/.claude/settings.local.json`, or the
+/// shared `settings.json` when `local` is false.
+#[tauri::command]
+pub async fn sync_project_config(
+    state: State<'_, AppState>,
+    app: String,
+    project_dir: String,
+    url: String,
+    api_key: String,
+    model: Option<String>,
+    local: Option<bool>,
+) -> Result<(), CommandError> {
+    check_app(&app)?;
+    let dir = project_path(&project_dir)?;
+    utils::validate_url(&url)?;
+    if api_key.trim().is_empty() {
+        return Err(CommandError::invalid_input("API key cannot be empty"));
+    }
+
+    let _lock = state.sync_lock.acquire().await?;
+    let proxy_url = crate::get_proxy_url(&app, &url);
+    let provider = synced_providers::provider_for(&state.db, &url, &api_key);
+    let api_key = crate::secrets::resolve(&api_key).await?;
+    cli_sync::sync_project_config(
+        &dir,
+        local.unwrap_or(true),
+        &proxy_url,
+        &api_key,
+        model.as_deref().filter(|m| !m.trim().is_empty()),
+        &crate::advanced_for(provider.as_ref()),
+    )?;
+    remember_project(&state.db, &dir);
+    tracing::info!("[project_sync] Synced Claude settings in {}", dir.display());
+    Ok(())
+}
+
+/// Status against `url`, or the current provider's URL when not given.
+#[tauri::command]
+pub async fn get_project_sync_status(
+    state: State<'_, AppState>,
+    project_dir: String,
+    url: Option<String>,
+) -> Result<ProjectSyncStatus, CommandError> {
+    let dir = project_path(&project_dir)?;
+    let url = match url {
+        Some(url) => url,
+        None => providers::get_current(&state.db)?
+            .map(|p| p.url)
+            .unwrap_or_default(),
+    };
+    let status = cli_sync::get_project_sync_status(&dir, &crate::get_proxy_url("claude", &url));
+    Ok(ProjectSyncStatus {
+        project_dir: dir.to_string_lossy().to_string(),
+        is_synced: status.is_synced(),
+        has_backup: status.has_backup,
+        current_base_url: status.current_base_url(),
+        file_status: status.files,
+    })
+}
+
+#[tauri::command]
+pub async fn restore_project_config(
+    state: State<'_, AppState>,
+    project_dir: String,
+) -> Result<(), CommandError> {
+    let dir = project_path(&project_dir)?;
+    let _lock = state.sync_lock.acquire().await?;
+    cli_sync::restore_project_config(&dir)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_recent_project_dirs(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(recent_projects(&state.db))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider_advanced::ProviderAdvanced;
+
+    #[test]
+    fn test_recent_projects_dedupe_and_cap() {
+        let db = Database::memory().unwrap();
+        for i in 0..12 {
+            remember_project(&db, Path::new(&format!("/p/{i}")));
+        }
+        remember_project(&db, Path::new("/p/5"));
+        let recent = recent_projects(&db);
+        assert_eq!(recent.len(), MAX_RECENT_PROJECTS);
+        assert_eq!(recent[0], "/p/5");
+        assert_eq!(recent.iter().filter(|d| *d == "/p/5").count(), 1);
+        assert!(!recent.contains(&"/p/0".to_string()));
+    }
+
+    #[test]
+    fn test_project_sync_writes_local_file_and_restores() {
+        let project = tempfile::tempdir().unwrap();
+        let claude_dir = project.path().join(".claude");
+        std::fs::create_dir(&claude_dir).unwrap();
+        std::fs::write(
+            claude_dir.join("settings.json"),
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://team.test"}, "permissions": {}}"#,
+        )
+        .unwrap();
+
+        let proxy = "https://proxy.test";
+        let status = cli_sync::get_project_sync_status(project.path(), proxy);
+        assert!(!status.is_synced());
+        assert_eq!(
+            status.current_base_url().as_deref(),
+            Some("https://team.test")
+        );
+
+        cli_sync::sync_project_config(
+            project.path(),
+            true,
+            proxy,
+            "sk-1",
+            None,
+            &ProviderAdvanced::default(),
+        )
+        .unwrap();
+        let status = cli_sync::get_project_sync_status(project.path(), proxy);
+        assert!(status.is_synced());
+        assert!(status.files[0]
+            .note
+            .as_deref()
+            .unwrap()
+            .contains("Overridden"));
+        // The shared file is left alone
+        let shared = std::fs::read_to_string(claude_dir.join("settings.json")).unwrap();
+        assert!(shared.contains("team.test"));
+
+        cli_sync::restore_project_config(project.path()).unwrap();
+        assert!(!cli_sync::get_project_sync_status(project.path(), proxy).is_synced());
+    }
+}
@@ -0,0 +1,132 @@
+//! Per-provider advanced options for gateways that need more than a URL and
+//! a key. Only the Claude sync uses them so far; other apps ignore the block.
+//!
+//! Stored as JSON on the provider row:
+//! `{"headers": {"x-portkey-provider": "anthropic"}, "small_fast_model": "claude-haiku-4-5",
+//!   "api_timeout_ms": 600000}`. Every field is optional.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderAdvanced {
+    /// Extra request headers, sent by Claude Code via `ANTHROPIC_CUSTOM_HEADERS`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Claude Code's `ANTHROPIC_SMALL_FAST_MODEL` (background tasks).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub small_fast_model: Option<String>,
+    /// Claude Code's `API_TIMEOUT_MS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_timeout_ms: Option<u64>,
+}
+
+/// RFC 9110 token characters, the only ones allowed in a header name.
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+impl ProviderAdvanced {
+    /// Parse and validate a provider's `advanced` column; `""` is empty.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| format!("advanced must be valid JSON: {e}"))?;
+        if !value.is_object() {
+            return Err("advanced must be a JSON object".to_string());
+        }
+        let advanced: Self = serde_json::from_value(value).map_err(|e| format!("advanced: {e}"))?;
+        for (name, value) in &advanced.headers {
+            if !is_header_name(name) {
+                return Err(format!("advanced.headers: invalid header name \"{name}\""));
+            }
+            // Claude Code splits the variable on newlines
+            if value.contains(['\r', '\n']) {
+                return Err(format!("advanced.headers.{name} must be a single line"));
+            }
+        }
+        if let Some(model) = &advanced.small_fast_model {
+            if model.trim().is_empty() || model.contains(char::is_whitespace) {
+                return Err("advanced.small_fast_model must be a model id".to_string());
+            }
+        }
+        if advanced.api_timeout_ms == Some(0) {
+            return Err("advanced.api_timeout_ms must be positive".to_string());
+        }
+        Ok(advanced)
+    }
+
+    /// Like `parse`, for stored rows: a bad value is logged and ignored.
+    pub fn parse_lenient(json: &str) -> Self {
+        Self::parse(json).unwrap_or_else(|e| {
+            tracing::warn!("[provider_advanced] Ignoring stored options: {}", e);
+            Self::default()
+        })
+    }
+
+    /// `ANTHROPIC_CUSTOM_HEADERS` value: one `Name: value` per line.
+    pub fn custom_headers(&self) -> Option<String> {
+        (!self.headers.is_empty()).then(|| {
+            self.headers
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", value.trim()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_custom_headers() {
+        let advanced = ProviderAdvanced::parse(
+            r#"{"headers": {"x-portkey-provider": "anthropic", "X-Team": " core "},
+                "small_fast_model": "claude-haiku-4-5", "api_timeout_ms": 600000}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            advanced.custom_headers().as_deref(),
+            Some("X-Team: core\nx-portkey-provider: anthropic")
+        );
+        assert_eq!(
+            advanced.small_fast_model.as_deref(),
+            Some("claude-haiku-4-5")
+        );
+        assert_eq!(advanced.api_timeout_ms, Some(600_000));
+        assert_eq!(
+            ProviderAdvanced::parse("{}").unwrap().custom_headers(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_shapes() {
+        assert_eq!(
+            ProviderAdvanced::parse("").unwrap(),
+            ProviderAdvanced::default()
+        );
+        assert!(ProviderAdvanced::parse("[]").is_err());
+        assert!(ProviderAdvanced::parse(r#"{"proxy": "x"}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"headers": {"bad name": "x"}}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"headers": {"x-a": "1\nx-b: 2"}}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"headers": {"x-a": 1}}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"small_fast_model": " "}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"api_timeout_ms": 0}"#).is_err());
+        assert!(ProviderAdvanced::parse(r#"{"api_timeout_ms": -5}"#).is_err());
+        assert_eq!(
+            ProviderAdvanced::parse_lenient("not json"),
+            ProviderAdvanced::default()
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Reverse sync: read a tool's existing proxy settings back into a provider.
+//!
+//! Each sync module knows its own file layout and exposes a
+//! `detect_existing_provider()`; this module dispatches by app name and turns
+//! the result into a `ProviderRecord`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli_sync::{self, CliApp};
+use crate::database::dao::providers::ProviderRecord;
+use crate::extra_clients::{self, ExtraClient};
+use crate::{droid_sync, openclaw_sync, opencode_sync};
+
+/// URL, key and model found in a tool's config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedProvider {
+    pub app: String,
+    pub url: String,
+    pub api_key: String,
+    pub model: Option<String>,
+}
+
+impl DetectedProvider {
+    /// `None` unless both a URL and a key were found. The URL is reduced to the
+    /// proxy root (no trailing `/v1`) since `get_proxy_url` adds it back per app.
+    pub fn new(app: &str, url: &str, api_key: &str, model: Option<&str>) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let url = url.strip_suffix("/v1").unwrap_or(url);
+        let api_key = api_key.trim();
+        if url.is_empty() || api_key.is_empty() {
+            return None;
+        }
+        Some(Self {
+            app: app.to_string(),
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            model: model
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    pub fn into_record(self, name: &str) -> ProviderRecord {
+        let per_cli_models = match &self.model {
+            Some(m) => serde_json::json!({ &self.app: m }).to_string(),
+            None => "{}".to_string(),
+        };
+        ProviderRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            url: self.url,
+            api_key: self.api_key,
+            default_model: String::new(),
+            per_cli_models,
+            is_current: false,
+            sort_index: None,
+            notes: Some(format!("Imported from {}", self.app)),
+            created_at: chrono::Utc::now().timestamp(),
+            opencode_adapter: String::new(),
+            account_id: None,
+            model_aliases: "{}".to_string(),
+            account_base_url: None,
+            token_id: None,
+            quota: None,
+            archived: false,
+            advanced: "{}".to_string(),
+        }
+    }
+}
+
+/// Detect the provider `app` is currently pointed at. `Err` only for unknown apps.
+pub fn detect(app: &str) -> Result<Option<DetectedProvider>, String> {
+    let detected = match app {
+        "claude" => cli_sync::detect_existing_provider(&CliApp::Claude),
+        "codex" => cli_sync::detect_existing_provider(&CliApp::Codex),
+        "gemini" => cli_sync::detect_existing_provider(&CliApp::Gemini),
+        "opencode" => opencode_sync::detect_existing_provider(),
+        "openclaw" => openclaw_sync::detect_existing_provider(),
+        "droid" => droid_sync::detect_existing_provider(),
+        other => match ExtraClient::from_str(other) {
+            Some(client) => extra_clients::detect_existing_provider(&client),
+            None => return Err(format!("Unknown app: {other}")),
+        },
+    };
+    Ok(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_normalizes_and_requires_url_and_key() {
+        let d = DetectedProvider::new("codex", " https://proxy.example.com/v1/ ", "sk-1", Some(""))
+            .unwrap();
+        assert_eq!(d.url, "https://proxy.example.com");
+        assert_eq!(d.model, None);
+
+        assert!(DetectedProvider::new("codex", "https://x.com", " ", None).is_none());
+        assert!(DetectedProvider::new("codex", "", "sk-1", None).is_none());
+    }
+
+    #[test]
+    fn test_into_record_keeps_model_per_cli() {
+        let record = DetectedProvider::new("claude", "https://x.com", "sk-1", Some("opus"))
+            .unwrap()
+            .into_record("Old proxy");
+        assert_eq!(record.name, "Old proxy");
+        assert_eq!(record.per_cli_models, r#"{"claude":"opus"}"#);
+        assert!(!record.is_current);
+    }
+}
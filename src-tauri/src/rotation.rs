@@ -0,0 +1,366 @@
+//! Scheduled rotation between providers, e.g. several pooled keys on one
+//! gateway.
+//!
+//! Off by default. When enabled, the provider after the current one in
+//! `provider_ids` is switched to every `interval_hours`, through the same
+//! pipeline as the Switch button (so it waits for the sync lock). Every switch,
+//! manual or scheduled, is timestamped, so a manual switch restarts the
+//! interval instead of being undone shortly after.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::database::dao::{providers, settings};
+use crate::database::Database;
+use crate::error::CommandError;
+use crate::notify;
+use crate::store::AppState;
+
+pub const EVENT_ROTATED: &str = "rotation://switched";
+const CONFIG_KEY: &str = "rotation";
+const LAST_SWITCH_KEY: &str = "last_provider_switch_at";
+/// When rotation was last (re)configured; the first rotation counts from here.
+const SINCE_KEY: &str = "rotation_since";
+const MAX_INTERVAL_HOURS: u32 = 24 * 30;
+/// Wait before trying again after a failed rotation (e.g. the sync lock was busy).
+const RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+/// Longest single sleep, so clock changes (suspend, DST) are noticed.
+const MAX_SLEEP: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationConfig {
+    pub enabled: bool,
+    /// Providers to cycle through, in order.
+    pub provider_ids: Vec<String>,
+    pub interval_hours: u32,
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider_ids: Vec::new(),
+            interval_hours: 24,
+        }
+    }
+}
+
+/// Payload of `EVENT_ROTATED`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationEvent {
+    pub from_id: Option<String>,
+    pub to_id: String,
+    pub to_name: String,
+    /// Unix seconds.
+    pub switched_at: i64,
+    /// Apps that failed to sync to the new provider.
+    pub failed_apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationStatus {
+    pub config: RotationConfig,
+    /// Unix seconds of the last provider switch, manual or scheduled.
+    pub last_switch_at: Option<i64>,
+    /// Unix seconds; `None` while rotation is off.
+    pub next_due_at: Option<i64>,
+    pub next_provider_id: Option<String>,
+    /// Last scheduled switch since the app started.
+    pub last_rotation: Option<RotationEvent>,
+}
+
+/// Wake-up signal for the scheduler plus the last rotation it did.
+#[derive(Default)]
+pub struct RotationScheduler {
+    wake: Notify,
+    last: Mutex<Option<RotationEvent>>,
+}
+
+impl RotationScheduler {
+    /// Re-read the config now, e.g. after it changed.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    fn last(&self) -> Option<RotationEvent> {
+        self.last.lock().ok().and_then(|last| last.clone())
+    }
+}
+
+pub fn load_config(db: &Database) -> RotationConfig {
+    settings::get(db, CONFIG_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| {
+            serde_json::from_str(&v)
+                .map_err(|e| tracing::warn!("[rotation] Ignoring stored config: {}", e))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Note that the current provider just changed; restarts the rotation interval.
+pub fn record_switch(db: &Database) {
+    let now = chrono::Utc::now().timestamp();
+    if let Err(e) = settings::set(db, LAST_SWITCH_KEY, &now.to_string()) {
+        tracing::warn!("[rotation] Failed to record provider switch: {}", e);
+    }
+}
+
+fn get_timestamp(db: &Database, key: &str) -> Option<i64> {
+    settings::get(db, key)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+}
+
+/// Unix seconds the next rotation is due: one interval after the later of the
+/// last switch and the time rotation was configured.
+fn due_at(config: &RotationConfig, last_switch: Option<i64>, since: Option<i64>) -> i64 {
+    let start = last_switch.max(since).unwrap_or(0);
+    start + i64::from(config.interval_hours) * 3600
+}
+
+/// The provider after `current` in `ids`, wrapping around; the first one when
+/// `current` isn't part of the rotation.
+fn next_provider<'a>(ids: &'a [String], current: Option<&str>) -> Option<&'a str> {
+    let next = match current.and_then(|c| ids.iter().position(|id| id == c)) {
+        Some(i) => (i + 1) % ids.len(),
+        None => 0,
+    };
+    ids.get(next).map(String::as_str)
+}
+
+fn validate(config: &RotationConfig, known: &[String]) -> Result<RotationConfig, CommandError> {
+    if config.interval_hours == 0 || config.interval_hours > MAX_INTERVAL_HOURS {
+        return Err(CommandError::invalid_input(format!(
+            "Rotation interval must be between 1 and {MAX_INTERVAL_HOURS} hours"
+        )));
+    }
+    let mut ids: Vec<String> = Vec::with_capacity(config.provider_ids.len());
+    for id in &config.provider_ids {
+        if !known.contains(id) {
+            return Err(CommandError::new(
+                "PROVIDER_NOT_FOUND",
+                format!("Provider not found: {id}"),
+            ));
+        }
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+    if config.enabled && ids.len() < 2 {
+        return Err(CommandError::invalid_input(
+            "Rotation needs at least two providers",
+        ));
+    }
+    Ok(RotationConfig {
+        provider_ids: ids,
+        ..config.clone()
+    })
+}
+
+fn status(db: &Database, scheduler: &RotationScheduler) -> Result<RotationStatus, String> {
+    let config = load_config(db);
+    let last_switch_at = get_timestamp(db, LAST_SWITCH_KEY);
+    let current = providers::get_current(db)?.map(|p| p.id);
+    let next_provider_id = config
+        .enabled
+        .then(|| next_provider(&config.provider_ids, current.as_deref()))
+        .flatten()
+        .map(str::to_string);
+    let next_due_at = config
+        .enabled
+        .then(|| due_at(&config, last_switch_at, get_timestamp(db, SINCE_KEY)));
+    Ok(RotationStatus {
+        config,
+        last_switch_at,
+        next_due_at,
+        next_provider_id,
+        last_rotation: scheduler.last(),
+    })
+}
+
+pub fn start(app: &AppHandle) {
+    tauri::async_runtime::spawn(run(app.clone()));
+}
+
+async fn run(app: AppHandle) {
+    let state = app.state::<AppState>();
+    loop {
+        let config = load_config(&state.db);
+        if !config.enabled {
+            state.rotation.wake.notified().await;
+            continue;
+        }
+
+        let due = due_at(
+            &config,
+            get_timestamp(&state.db, LAST_SWITCH_KEY),
+            get_timestamp(&state.db, SINCE_KEY),
+        );
+        let wait = due - chrono::Utc::now().timestamp();
+        let delay = if wait > 0 {
+            Duration::from_secs(wait as u64).min(MAX_SLEEP)
+        } else {
+            match rotate(&app, &state, &config).await {
+                Ok(()) => continue,
+                Err(e) => {
+                    tracing::warn!(
+                        "[rotation] Scheduled switch failed: {}",
+                        crate::logging::redact(&e.message)
+                    );
+                    RETRY_DELAY
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = state.rotation.wake.notified() => {}
+        }
+    }
+}
+
+/// Switch to the next provider in the rotation now.
+async fn rotate(
+    app: &AppHandle,
+    state: &AppState,
+    config: &RotationConfig,
+) -> Result<(), CommandError> {
+    let all = providers::get_all(&state.db, false)?;
+    let current = all.iter().find(|p| p.is_current).map(|p| p.id.clone());
+    // Providers deleted since the config was saved drop out of the cycle
+    let ids: Vec<String> = config
+        .provider_ids
+        .iter()
+        .filter(|id| all.iter().any(|p| &p.id == *id))
+        .cloned()
+        .collect();
+    let target = next_provider(&ids, current.as_deref())
+        .and_then(|id| all.iter().find(|p| p.id == id))
+        .filter(|p| Some(&p.id) != current.as_ref())
+        .cloned();
+    let Some(target) = target else {
+        tracing::info!("[rotation] Nothing to rotate to; waiting another interval");
+        record_switch(&state.db);
+        return Ok(());
+    };
+
+    tracing::info!("[rotation] Switching to {}", target.name);
+    let result = crate::switch_to_provider(app, state, target.clone()).await?;
+    let event = RotationEvent {
+        from_id: current,
+        to_id: target.id,
+        to_name: target.name,
+        switched_at: chrono::Utc::now().timestamp(),
+        failed_apps: result.errors.iter().map(|e| e.app.clone()).collect(),
+    };
+    if let Ok(mut last) = state.rotation.last.lock() {
+        *last = Some(event.clone());
+    }
+    // Failures already got their own notification from the switch
+    if event.failed_apps.is_empty() {
+        notify::notify(
+            app,
+            &format!("Rotated to {}", event.to_name),
+            &format!(
+                "Scheduled provider rotation; next switch in {} h",
+                config.interval_hours
+            ),
+        );
+    }
+    if let Err(e) = app.emit(EVENT_ROTATED, &event) {
+        tracing::warn!("[rotation] Failed to emit rotation event: {}", e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_rotation_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<RotationStatus, CommandError> {
+    Ok(status(&state.db, &state.rotation)?)
+}
+
+/// Save the rotation config; enabling (or changing) it starts a fresh interval.
+#[tauri::command]
+pub async fn set_rotation_config(
+    state: tauri::State<'_, AppState>,
+    config: RotationConfig,
+) -> Result<RotationStatus, CommandError> {
+    let known: Vec<String> = providers::get_all(&state.db, false)?
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+    let config = validate(&config, &known)?;
+    let json = serde_json::to_string(&config)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid rotation config: {e}")))?;
+    settings::set(&state.db, CONFIG_KEY, &json)?;
+    settings::set(
+        &state.db,
+        SINCE_KEY,
+        &chrono::Utc::now().timestamp().to_string(),
+    )?;
+    state.rotation.wake();
+    Ok(status(&state.db, &state.rotation)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_next_provider_wraps_around() {
+        let list = ids(&["a", "b", "c"]);
+        assert_eq!(next_provider(&list, Some("a")), Some("b"));
+        assert_eq!(next_provider(&list, Some("c")), Some("a"));
+        assert_eq!(next_provider(&list, Some("other")), Some("a"));
+        assert_eq!(next_provider(&list, None), Some("a"));
+        assert_eq!(next_provider(&[], Some("a")), None);
+    }
+
+    #[test]
+    fn test_due_counts_from_latest_switch_or_setup() {
+        let config = RotationConfig {
+            enabled: true,
+            provider_ids: ids(&["a", "b"]),
+            interval_hours: 2,
+        };
+        assert_eq!(due_at(&config, Some(1000), Some(500)), 1000 + 7200);
+        // A manual switch after setup pushes the rotation back
+        assert_eq!(due_at(&config, Some(500), Some(1000)), 1000 + 7200);
+        assert_eq!(due_at(&config, None, None), 7200);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let known = ids(&["a", "b"]);
+        let config = |list: &[&str], enabled, hours| RotationConfig {
+            enabled,
+            provider_ids: ids(list),
+            interval_hours: hours,
+        };
+        let ok = validate(&config(&["a", "b", "a"], true, 24), &known).unwrap();
+        assert_eq!(ok.provider_ids, ids(&["a", "b"]));
+        assert!(validate(&config(&["a"], false, 24), &known).is_ok());
+        assert!(validate(&config(&["a", "a"], true, 24), &known).is_err());
+        assert!(validate(&config(&["a", "b"], true, 0), &known).is_err());
+        assert_eq!(
+            validate(&config(&["a", "x"], false, 24), &known)
+                .unwrap_err()
+                .code,
+            "PROVIDER_NOT_FOUND"
+        );
+        let db = Database::memory().unwrap();
+        assert_eq!(load_config(&db), RotationConfig::default());
+    }
+}
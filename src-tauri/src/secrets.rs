@@ -0,0 +1,231 @@
+//! API keys stored as references instead of values.
+//!
+//! A provider's `api_key` may be `op://vault/item/field`, read with the
+//! 1Password CLI (`op read`), or `keychain://label`, read from the OS
+//! credential store (macOS Keychain, Windows Credential Manager, Secret
+//! Service) under the service [`KEYCHAIN_SERVICE`]. References are resolved
+//! just before a key is written or sent, and the result is cached in memory
+//! only, so the database never holds the secret itself.
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use serde_json::json;
+
+use crate::error::CommandError;
+use crate::utils;
+
+const ONEPASSWORD_SCHEME: &str = "op://";
+const KEYCHAIN_SCHEME: &str = "keychain://";
+
+/// Service name `keychain://label` entries are stored under; the label is the
+/// account.
+pub const KEYCHAIN_SERVICE: &str = "com.hajimi.switch";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SecretRef<'a> {
+    /// The whole `op://vault/item/field` URI, passed to `op read` as is.
+    OnePassword(&'a str),
+    /// The label after `keychain://`.
+    Keychain(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    fn parse(value: &'a str) -> Option<Self> {
+        let value = value.trim();
+        if value.starts_with(ONEPASSWORD_SCHEME) {
+            Some(Self::OnePassword(value))
+        } else {
+            value.strip_prefix(KEYCHAIN_SCHEME).map(Self::Keychain)
+        }
+    }
+}
+
+/// Whether `value` is a reference rather than a literal key.
+pub fn is_reference(value: &str) -> bool {
+    SecretRef::parse(value).is_some()
+}
+
+/// Check the shape of a reference without resolving it; literal keys pass.
+pub fn validate_reference(value: &str) -> Result<(), String> {
+    match SecretRef::parse(value) {
+        None => Ok(()),
+        Some(SecretRef::OnePassword(uri)) => {
+            let path = &uri[ONEPASSWORD_SCHEME.len()..];
+            let parts: Vec<&str> = path.split('/').collect();
+            if parts.len() < 3 || parts.iter().any(|p| p.trim().is_empty()) {
+                return Err(format!(
+                    "1Password reference must look like op://vault/item/field: {uri}"
+                ));
+            }
+            Ok(())
+        }
+        Some(SecretRef::Keychain(label)) => {
+            if label.trim().is_empty() {
+                return Err("Keychain reference must name an entry: keychain://label".to_string());
+            }
+            Ok(())
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<String, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Drop every cached secret, e.g. after a provider's reference was edited.
+pub fn clear_cache() {
+    cache().lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// `value` if it's a literal key, else its secret if already resolved this
+/// session. Never prompts.
+pub fn cached(value: &str) -> Option<String> {
+    if !is_reference(value) {
+        return Some(value.to_string());
+    }
+    cache()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(value.trim())
+        .cloned()
+}
+
+/// The key to use for `value`: literal keys as they are, references read
+/// from 1Password or the keychain (cached for the rest of the session).
+pub async fn resolve(value: &str) -> Result<String, CommandError> {
+    if let Some(secret) = cached(value) {
+        return Ok(secret);
+    }
+    let Some(reference) = SecretRef::parse(value) else {
+        return Ok(value.to_string());
+    };
+    let key = value.trim().to_string();
+
+    validate_reference(&key).map_err(|e| unresolved(&key, e))?;
+    let is_op = matches!(reference, SecretRef::OnePassword(_));
+    let target = key.clone();
+    let secret = utils::run_blocking(move || {
+        if is_op {
+            read_onepassword(&target)
+        } else {
+            read_keychain(&target[KEYCHAIN_SCHEME.len()..])
+        }
+    })
+    .await?
+    .map_err(|e| unresolved(&key, e))?;
+
+    let secret = secret.trim().to_string();
+    if secret.is_empty() {
+        return Err(unresolved(&key, "the stored secret is empty".to_string()));
+    }
+    tracing::info!("[secrets] Resolved {}", key);
+    cache()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, secret.clone());
+    Ok(secret)
+}
+
+/// The reference is named, never what it resolved to.
+fn unresolved(reference: &str, reason: String) -> CommandError {
+    tracing::warn!("[secrets] Cannot resolve {}: {}", reference, reason);
+    CommandError::new(
+        "SECRET_UNRESOLVED",
+        format!("Cannot resolve API key reference {reference}: {reason}"),
+    )
+    .with_details(json!({ "reference": reference }))
+}
+
+fn read_onepassword(uri: &str) -> Result<String, String> {
+    let op = utils::resolve_executable("op").ok_or_else(|| {
+        "the 1Password CLI (op) was not found. Install it and enable \"Integrate with 1Password CLI\" in the 1Password app".to_string()
+    })?;
+    let mut cmd = Command::new(op);
+    cmd.args(["read", "--no-newline", uri]);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(utils::CREATE_NO_WINDOW);
+    let output = cmd
+        .output()
+        .map_err(|e| format!("failed to run the 1Password CLI: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("op read exited with {}", output.status)
+        } else {
+            // e.g. "[ERROR] ... You are not currently signed in."
+            format!("op read failed: {stderr}")
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| "op read returned non-UTF-8 output".to_string())
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn read_keychain(label: &str) -> Result<String, String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, label)
+        .map_err(|e| format!("cannot open the credential store: {e}"))?;
+    entry.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => format!(
+            "no credential for service \"{KEYCHAIN_SERVICE}\" and account \"{label}\" in the system keychain"
+        ),
+        e => format!("cannot read the system keychain: {e}"),
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn read_keychain(_label: &str) -> Result<String, String> {
+    Err("keychain references are not supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_forms() {
+        assert!(is_reference("op://Private/Hajimi/credential"));
+        assert!(is_reference(" keychain://work "));
+        assert!(!is_reference("sk-abc"));
+        assert!(!is_reference("opx://a/b/c"));
+
+        assert!(validate_reference("sk-abc").is_ok());
+        assert!(validate_reference("op://Private/Hajimi/credential").is_ok());
+        assert!(validate_reference("op://Private/Hajimi/section/field").is_ok());
+        assert!(validate_reference("op://Private/Hajimi").is_err());
+        assert!(validate_reference("op://Private//credential").is_err());
+        assert!(validate_reference("keychain://work").is_ok());
+        assert!(validate_reference("keychain://").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_literal_key_passes_through() {
+        let key = resolve("sk-abc").await.unwrap();
+        assert_eq!(key, "sk-abc");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_reference_is_a_clear_error() {
+        let err = resolve("op://only-vault").await.unwrap_err();
+        assert_eq!(err.code, "SECRET_UNRESOLVED");
+        assert!(err.message.contains("op://only-vault"));
+    }
+
+    #[tokio::test]
+    async fn test_cached_secret_is_reused() {
+        cache()
+            .lock()
+            .unwrap()
+            .insert("op://Test/Cached/key".to_string(), "sk-cached".to_string());
+        let key = resolve("op://Test/Cached/key").await.unwrap();
+        assert_eq!(key, "sk-cached");
+        assert_eq!(cached("op://Test/Cached/key").as_deref(), Some("sk-cached"));
+        assert_eq!(cached("keychain://never-resolved"), None);
+        assert_eq!(cached("sk-abc").as_deref(), Some("sk-abc"));
+    }
+}
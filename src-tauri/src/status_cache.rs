@@ -0,0 +1,240 @@
+//! Parsed config status, kept between dashboard polls.
+//!
+//! An entry is reused only while the provider URL, the opencode adapter and
+//! every config file's mtime, size and backup are unchanged, so a provider
+//! switch or any write to the files always re-parses. Installation and drift
+//! are still checked on every call.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::opencode_sync::OpencodeAdapter;
+use crate::sync_target::{SyncTarget, TargetStatus};
+use crate::utils;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    path: PathBuf,
+    /// mtime and size; `None` when the file is missing.
+    meta: Option<(SystemTime, u64)>,
+    has_backup: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+    proxy_url: Option<String>,
+    opencode_adapter: Option<OpencodeAdapter>,
+    files: Vec<FileStamp>,
+}
+
+impl Fingerprint {
+    fn of(
+        target: &dyn SyncTarget,
+        proxy_url: Option<&str>,
+        adapter: Option<OpencodeAdapter>,
+    ) -> Self {
+        let files = target
+            .config_paths()
+            .into_iter()
+            .map(|path| FileStamp {
+                meta: fs::metadata(&path)
+                    .ok()
+                    .map(|m| (m.modified().unwrap_or(SystemTime::UNIX_EPOCH), m.len())),
+                has_backup: utils::find_backup(&path).is_some(),
+                path,
+            })
+            .collect();
+        Self {
+            proxy_url: proxy_url.map(str::to_string),
+            opencode_adapter: adapter,
+            files,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StatusCache {
+    entries: Mutex<HashMap<&'static str, (Fingerprint, TargetStatus)>>,
+}
+
+impl StatusCache {
+    /// `target.status(..)`, re-parsed only when its inputs changed.
+    pub fn status(
+        &self,
+        target: &dyn SyncTarget,
+        proxy_url: Option<&str>,
+        adapter: Option<OpencodeAdapter>,
+    ) -> TargetStatus {
+        let fingerprint = Fingerprint::of(target, proxy_url, adapter);
+        if let Some((cached, status)) = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(target.id())
+        {
+            if *cached == fingerprint {
+                return status.clone();
+            }
+        }
+        let status = target.status(proxy_url, adapter);
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(target.id(), (fingerprint, status.clone()));
+        status
+    }
+}
+
+/// Short hash of `value`'s JSON, for telling the UI whether anything changed.
+pub fn state_token<T: Serialize>(value: &T) -> String {
+    let json = serde_json::to_vec(value).unwrap_or_default();
+    let digest = format!("{:x}", Sha256::digest(&json));
+    digest[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_status::{FileStatus, SyncStatus};
+    use crate::sync_target::{SyncFuture, SyncOutcome, SyncRequest};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingTarget {
+        path: PathBuf,
+        parses: AtomicUsize,
+    }
+
+    impl CountingTarget {
+        fn new(path: &Path) -> Self {
+            Self {
+                path: path.to_path_buf(),
+                parses: AtomicUsize::new(0),
+            }
+        }
+
+        fn parses(&self) -> usize {
+            self.parses.load(Ordering::SeqCst)
+        }
+    }
+
+    impl SyncTarget for CountingTarget {
+        fn id(&self) -> &'static str {
+            "counting"
+        }
+        fn display_name(&self) -> &'static str {
+            "Counting"
+        }
+        fn detect(&self) -> (bool, Option<String>) {
+            (true, None)
+        }
+        fn status(&self, proxy_url: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+            self.parses.fetch_add(1, Ordering::SeqCst);
+            let content = fs::read_to_string(&self.path).unwrap_or_default();
+            TargetStatus {
+                status: SyncStatus::new(
+                    vec![FileStatus::new("config", true).with_url(
+                        Some(content.trim().to_string()),
+                        proxy_url,
+                        "URL",
+                        true,
+                    )],
+                    false,
+                ),
+                synced_count: None,
+            }
+        }
+        fn sync<'a>(&'a self, _: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+            Box::pin(async { Ok(SyncOutcome::Synced(None)) })
+        }
+        fn restore(&self) -> Result<(), String> {
+            Ok(())
+        }
+        fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+            Ok(String::new())
+        }
+        fn write_content(&self, _: &str, _: &str) -> Result<(), String> {
+            Ok(())
+        }
+        fn config_files(&self) -> Vec<String> {
+            vec!["config".to_string()]
+        }
+        fn config_paths(&self) -> Vec<PathBuf> {
+            vec![self.path.clone()]
+        }
+    }
+
+    #[test]
+    fn test_unchanged_files_are_not_reparsed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config");
+        fs::write(&path, "http://a").unwrap();
+        let target = CountingTarget::new(&path);
+        let cache = StatusCache::default();
+
+        let first = cache.status(&target, Some("http://a"), None);
+        let second = cache.status(&target, Some("http://a"), None);
+        assert_eq!(target.parses(), 1);
+        assert_eq!(first.status, second.status);
+        assert!(second.status.is_synced());
+    }
+
+    #[test]
+    fn test_file_write_invalidates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config");
+        fs::write(&path, "http://a").unwrap();
+        let target = CountingTarget::new(&path);
+        let cache = StatusCache::default();
+
+        assert!(cache
+            .status(&target, Some("http://a"), None)
+            .status
+            .is_synced());
+        // A different size, so the change shows even on coarse mtime clocks
+        fs::write(&path, "http://other").unwrap();
+        assert!(!cache
+            .status(&target, Some("http://a"), None)
+            .status
+            .is_synced());
+        assert_eq!(target.parses(), 2);
+
+        fs::remove_file(&path).unwrap();
+        cache.status(&target, Some("http://a"), None);
+        assert_eq!(target.parses(), 3);
+    }
+
+    #[test]
+    fn test_provider_switch_invalidates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config");
+        fs::write(&path, "http://a").unwrap();
+        let target = CountingTarget::new(&path);
+        let cache = StatusCache::default();
+
+        assert!(cache
+            .status(&target, Some("http://a"), None)
+            .status
+            .is_synced());
+        assert!(!cache
+            .status(&target, Some("http://b"), None)
+            .status
+            .is_synced());
+        cache.status(&target, Some("http://b"), Some(OpencodeAdapter::default()));
+        assert_eq!(target.parses(), 3);
+    }
+
+    #[test]
+    fn test_state_token_tracks_content() {
+        let a = state_token(&vec!["codex", "synced"]);
+        assert_eq!(a, state_token(&vec!["codex", "synced"]));
+        assert_ne!(a, state_token(&vec!["codex", "stale"]));
+        assert_eq!(a.len(), 16);
+    }
+}
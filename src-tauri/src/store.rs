@@ -1,6 +1,34 @@
 use crate::database::Database;
-use std::sync::Arc;
+use crate::health::HealthMonitor;
+use crate::rotation::RotationScheduler;
+use crate::status_cache::StatusCache;
+use crate::sync_lock::{self, SyncLock};
+use crate::watcher::ConfigWatcher;
+use std::sync::{Arc, Mutex};
 
 pub struct AppState {
     pub db: Arc<Database>,
+    /// Config file watcher; `None` until setup and after exit.
+    pub watcher: Mutex<Option<ConfigWatcher>>,
+    /// Held by every command that writes tool configs.
+    pub sync_lock: SyncLock,
+    /// Last background health check of the current provider.
+    pub health: HealthMonitor,
+    /// Scheduled provider rotation.
+    pub rotation: RotationScheduler,
+    /// Parsed config status, reused while the files are unchanged.
+    pub status_cache: Arc<StatusCache>,
+}
+
+impl AppState {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            watcher: Mutex::new(None),
+            sync_lock: SyncLock::new(sync_lock::default_path()),
+            health: HealthMonitor::default(),
+            rotation: RotationScheduler::default(),
+            status_cache: Arc::default(),
+        }
+    }
 }
@@ -0,0 +1,157 @@
+//! Serialize everything that writes tool configs.
+//!
+//! Two layers: an async mutex held in `AppState` for commands within the GUI
+//! process (tray, double clicks), and an advisory lock on `sync.lock` in the
+//! data dir so the GUI and the `hajimi-sync` binary exclude each other. Both
+//! give up after `WAIT` with `SyncError::SyncBusy` instead of hanging.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::database::Database;
+use crate::error::SyncError;
+
+const WAIT: Duration = Duration::from_secs(30);
+const POLL: Duration = Duration::from_millis(100);
+
+/// `sync.lock` next to the provider database.
+pub fn default_path() -> Option<PathBuf> {
+    Database::default_path().and_then(|db| db.parent().map(|dir| dir.join("sync.lock")))
+}
+
+pub struct SyncLock {
+    inner: Mutex<()>,
+    path: Option<PathBuf>,
+}
+
+/// Held for the duration of a sync; both locks are released on drop.
+pub struct SyncGuard<'a> {
+    _guard: MutexGuard<'a, ()>,
+    _file: Option<File>,
+}
+
+impl SyncLock {
+    /// `path: None` skips the cross-process lock (e.g. no data dir).
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            inner: Mutex::new(()),
+            path,
+        }
+    }
+
+    pub async fn acquire(&self) -> Result<SyncGuard<'_>, SyncError> {
+        self.acquire_within(WAIT).await
+    }
+
+    async fn acquire_within(&self, wait: Duration) -> Result<SyncGuard<'_>, SyncError> {
+        let deadline = Instant::now() + wait;
+        let busy = || SyncError::SyncBusy {
+            seconds: wait.as_secs(),
+        };
+        let guard = tokio::time::timeout(wait, self.inner.lock())
+            .await
+            .map_err(|_| busy())?;
+        let file = match &self.path {
+            Some(path) => Some(lock_file(path, deadline).await?.ok_or_else(busy)?),
+            None => None,
+        };
+        Ok(SyncGuard {
+            _guard: guard,
+            _file: file,
+        })
+    }
+}
+
+/// Poll for the advisory lock until `deadline`. `Ok(None)` on timeout.
+async fn lock_file(path: &Path, deadline: Instant) -> Result<Option<File>, SyncError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| SyncError::DirectoryCreationFailed {
+            path: parent.display().to_string(),
+            reason: e.to_string(),
+        })?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(|e| SyncError::FileWriteFailed {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(Some(file)),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                tokio::time::sleep(POLL).await;
+            }
+            Err(TryLockError::WouldBlock) => {
+                tracing::warn!("[sync_lock] {:?} still held by another process", path);
+                return Ok(None);
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(SyncError::FileLocked {
+                    path: format!("{} ({e})", path.display()),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_concurrent_syncs_serialize() {
+        let tmp = tempfile::tempdir().unwrap();
+        let lock = Arc::new(SyncLock::new(Some(tmp.path().join("sync.lock"))));
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let switch = |name: &'static str| {
+            let lock = lock.clone();
+            let log = log.clone();
+            tokio::spawn(async move {
+                let _guard = lock.acquire().await.unwrap();
+                log.lock().unwrap().push(format!("{name} start"));
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                log.lock().unwrap().push(format!("{name} end"));
+            })
+        };
+        let (a, b) = tokio::join!(switch("a"), switch("b"));
+        a.unwrap();
+        b.unwrap();
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 4);
+        // Each switch finishes before the other starts
+        assert!(log[0].ends_with("start") && log[1].ends_with("end"));
+        assert_eq!(log[0][..1], log[1][..1]);
+    }
+
+    #[tokio::test]
+    async fn test_times_out_while_another_process_holds_the_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("sync.lock");
+        let other = File::create(&path).unwrap();
+        other.lock().unwrap();
+
+        let lock = SyncLock::new(Some(path));
+        let err = lock
+            .acquire_within(Duration::from_millis(200))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "SYNC_BUSY");
+
+        other.unlock().unwrap();
+        assert!(lock
+            .acquire_within(Duration::from_millis(200))
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,711 @@
+//! Every app hajimi can sync, behind one trait.
+//!
+//! Commands look a target up by id or walk `all()` instead of matching on app
+//! names, so a new tool only needs a `SyncTarget` impl and a registry entry.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use crate::cli_sync::{self, CliApp};
+use crate::database::{dao::backup, Database};
+use crate::droid_sync::{self, DroidModelOverrides};
+use crate::extra_clients::{self, ExtraClient};
+use crate::file_status::SyncStatus;
+use crate::model_aliases::ModelAliases;
+use crate::opencode_sync::{self, OpencodeAdapter};
+use crate::provider_advanced::ProviderAdvanced;
+use crate::{drift, logging, openclaw_sync, pinned_apps, synced_providers, utils, SyncResult};
+
+/// What a sync did, short of an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// Config written; carries a non-fatal notice for the UI, if any.
+    Synced(Option<String>),
+    /// Nothing was written: the app can only be pointed at the provider by
+    /// hand, following these steps.
+    ManualStepsRequired(String),
+}
+
+/// A sync in flight.
+pub type SyncFuture<'a> = Pin<Box<dyn Future<Output = Result<SyncOutcome, String>> + Send + 'a>>;
+
+/// What to write into one app's config.
+pub struct SyncRequest<'a> {
+    /// Already adjusted with `SyncTarget::proxy_url`.
+    pub proxy_url: &'a str,
+    pub api_key: &'a str,
+    /// Already aliased for this app.
+    pub model: Option<&'a str>,
+    /// opencode's `small_model`; other apps ignore it.
+    pub small_model: Option<&'a str>,
+    pub opencode_adapter: OpencodeAdapter,
+    pub droid_overrides: Option<&'a DroidModelOverrides>,
+    /// Applied to the model catalogs opencode, OpenClaw and Droid write.
+    pub aliases: &'a ModelAliases,
+    /// The provider's advanced options; only Claude's settings use them.
+    pub advanced: &'a ProviderAdvanced,
+}
+
+/// Sync state of one app's config files.
+#[derive(Debug, Clone, Default)]
+pub struct TargetStatus {
+    pub status: SyncStatus,
+    /// Models written into the config, for apps that mirror the proxy's list.
+    pub synced_count: Option<usize>,
+}
+
+pub trait SyncTarget: Send + Sync {
+    /// Stable app id used by the UI, settings and the database.
+    fn id(&self) -> &'static str;
+    fn display_name(&self) -> &'static str;
+    /// Whether the app is installed, and its version when known.
+    fn detect(&self) -> (bool, Option<String>);
+    /// `opencode_adapter` is only checked when the caller says which one it expects.
+    /// Without a `proxy_url` nothing counts as synced; the files' URLs are still read.
+    fn status(
+        &self,
+        proxy_url: Option<&str>,
+        opencode_adapter: Option<OpencodeAdapter>,
+    ) -> TargetStatus;
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a>;
+    fn restore(&self) -> Result<(), String>;
+    /// `None` reads the main config file.
+    fn read_content(&self, file_name: Option<&str>) -> Result<String, String>;
+    fn write_content(&self, file_name: &str, content: &str) -> Result<(), String>;
+    /// Config file names shown in the UI, main file first.
+    fn config_files(&self) -> Vec<String>;
+    /// Config files a sync writes.
+    fn config_paths(&self) -> Vec<PathBuf>;
+
+    /// The provider URL as this app expects it.
+    fn proxy_url(&self, base_url: &str) -> String {
+        base_url.trim_end_matches('/').to_string()
+    }
+
+    /// Whether Sync All and provider switches include this app.
+    fn bulk_sync(&self) -> bool {
+        true
+    }
+
+    /// Setup steps for apps hajimi can't configure; their sync reports these
+    /// instead of writing anything.
+    fn manual_steps(&self) -> Option<String> {
+        None
+    }
+}
+
+/// `url` with a trailing `/v1`, for apps that want the OpenAI-style root.
+fn with_v1(base_url: &str) -> String {
+    let url = base_url.trim_end_matches('/');
+    if url.ends_with("/v1") {
+        url.to_string()
+    } else {
+        format!("{url}/v1")
+    }
+}
+
+impl SyncTarget for CliApp {
+    fn id(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn display_name(&self) -> &'static str {
+        match self {
+            CliApp::Claude => "Claude Code",
+            CliApp::Codex => "Codex",
+            CliApp::Gemini => "Gemini CLI",
+        }
+    }
+
+    fn detect(&self) -> (bool, Option<String>) {
+        cli_sync::check_cli_installed(self)
+    }
+
+    fn status(&self, proxy_url: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+        TargetStatus {
+            status: cli_sync::get_sync_status(self, proxy_url),
+            synced_count: None,
+        }
+    }
+
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+        let app = self.clone();
+        let (proxy_url, api_key) = (req.proxy_url.to_string(), req.api_key.to_string());
+        let model = req.model.map(str::to_string);
+        let advanced = req.advanced.clone();
+        Box::pin(async move {
+            utils::run_blocking(move || {
+                cli_sync::sync_config(&app, &proxy_url, &api_key, model.as_deref(), &advanced)
+                    .map(SyncOutcome::Synced)
+            })
+            .await?
+        })
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        cli_sync::restore_config(self)
+    }
+
+    fn read_content(&self, file_name: Option<&str>) -> Result<String, String> {
+        cli_sync::read_config_content(self, file_name)
+    }
+
+    fn write_content(&self, file_name: &str, content: &str) -> Result<(), String> {
+        cli_sync::write_config_content(self, file_name, content)
+    }
+
+    fn config_files(&self) -> Vec<String> {
+        CliApp::config_files(self)
+            .into_iter()
+            .map(|f| f.name)
+            .collect()
+    }
+
+    fn config_paths(&self) -> Vec<PathBuf> {
+        CliApp::config_files(self)
+            .into_iter()
+            .map(|f| f.path)
+            .collect()
+    }
+
+    fn proxy_url(&self, base_url: &str) -> String {
+        match self {
+            CliApp::Codex => with_v1(base_url),
+            _ => base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+pub struct Opencode;
+
+impl SyncTarget for Opencode {
+    fn id(&self) -> &'static str {
+        "opencode"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenCode"
+    }
+
+    fn detect(&self) -> (bool, Option<String>) {
+        opencode_sync::check_opencode_installed()
+    }
+
+    fn status(&self, proxy_url: Option<&str>, adapter: Option<OpencodeAdapter>) -> TargetStatus {
+        TargetStatus {
+            status: opencode_sync::get_sync_status(proxy_url, adapter),
+            synced_count: None,
+        }
+    }
+
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+        Box::pin(async move {
+            opencode_sync::sync_opencode_config(
+                req.proxy_url,
+                req.api_key,
+                req.model,
+                req.small_model,
+                req.opencode_adapter,
+                req.aliases,
+            )
+            .await
+            .map(|()| SyncOutcome::Synced(None))
+        })
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        opencode_sync::restore_opencode_config()
+    }
+
+    fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+        opencode_sync::read_opencode_config_content()
+    }
+
+    fn write_content(&self, _: &str, content: &str) -> Result<(), String> {
+        opencode_sync::write_opencode_config_content(content)
+    }
+
+    fn config_files(&self) -> Vec<String> {
+        vec!["opencode.json".to_string(), "auth.json".to_string()]
+    }
+
+    fn config_paths(&self) -> Vec<PathBuf> {
+        [
+            opencode_sync::get_config_path(),
+            opencode_sync::get_auth_path(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn proxy_url(&self, base_url: &str) -> String {
+        with_v1(base_url)
+    }
+}
+
+pub struct Droid;
+
+impl SyncTarget for Droid {
+    fn id(&self) -> &'static str {
+        "droid"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Droid"
+    }
+
+    fn detect(&self) -> (bool, Option<String>) {
+        droid_sync::check_droid_installed()
+    }
+
+    fn status(&self, proxy_url: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+        let (status, count) = droid_sync::get_sync_status(proxy_url);
+        TargetStatus {
+            status,
+            synced_count: Some(count),
+        }
+    }
+
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+        Box::pin(async move {
+            droid_sync::sync_droid_config(
+                req.proxy_url,
+                req.api_key,
+                req.model,
+                req.droid_overrides,
+                req.aliases,
+            )
+            .await
+            .map(|_| SyncOutcome::Synced(None))
+        })
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        droid_sync::restore_droid_config()
+    }
+
+    fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+        droid_sync::read_droid_config_content()
+    }
+
+    fn write_content(&self, _: &str, content: &str) -> Result<(), String> {
+        droid_sync::write_droid_config_content(content)
+    }
+
+    fn config_files(&self) -> Vec<String> {
+        vec!["settings.json".to_string()]
+    }
+
+    fn config_paths(&self) -> Vec<PathBuf> {
+        droid_sync::get_config_path().into_iter().collect()
+    }
+}
+
+pub struct Openclaw;
+
+impl SyncTarget for Openclaw {
+    fn id(&self) -> &'static str {
+        "openclaw"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenClaw"
+    }
+
+    fn detect(&self) -> (bool, Option<String>) {
+        openclaw_sync::check_openclaw_installed()
+    }
+
+    fn status(&self, proxy_url: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+        TargetStatus {
+            status: openclaw_sync::get_sync_status(proxy_url),
+            synced_count: None,
+        }
+    }
+
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+        Box::pin(async move {
+            openclaw_sync::sync_openclaw_config(req.proxy_url, req.api_key, req.model, req.aliases)
+                .await
+                .map(|report| SyncOutcome::Synced(report.warning))
+        })
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        openclaw_sync::restore_openclaw_config()
+    }
+
+    fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+        openclaw_sync::read_openclaw_config_content()
+    }
+
+    fn write_content(&self, _: &str, content: &str) -> Result<(), String> {
+        openclaw_sync::write_openclaw_config_content(content)
+    }
+
+    fn config_files(&self) -> Vec<String> {
+        vec!["openclaw.json".to_string()]
+    }
+
+    fn config_paths(&self) -> Vec<PathBuf> {
+        openclaw_sync::get_config_path().into_iter().collect()
+    }
+}
+
+impl SyncTarget for ExtraClient {
+    fn id(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn display_name(&self) -> &'static str {
+        ExtraClient::display_name(self)
+    }
+
+    fn detect(&self) -> (bool, Option<String>) {
+        extra_clients::check_extra_installed(self)
+    }
+
+    fn status(&self, proxy_url: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+        TargetStatus {
+            status: extra_clients::get_extra_sync_status(self, proxy_url),
+            synced_count: None,
+        }
+    }
+
+    fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+        let client = *self;
+        let (proxy_url, api_key) = (req.proxy_url.to_string(), req.api_key.to_string());
+        let model = req.model.map(str::to_string);
+        let advanced = req.advanced.clone();
+        Box::pin(async move {
+            utils::run_blocking(move || {
+                extra_clients::sync_extra_config(
+                    &client,
+                    &proxy_url,
+                    &api_key,
+                    model.as_deref(),
+                    &advanced,
+                )
+            })
+            .await?
+        })
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        extra_clients::restore_extra_config(self)
+    }
+
+    fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+        extra_clients::read_extra_config_content(self)
+    }
+
+    fn write_content(&self, file_name: &str, content: &str) -> Result<(), String> {
+        extra_clients::write_extra_config_content(self, file_name, content)
+    }
+
+    fn config_files(&self) -> Vec<String> {
+        self.config_files_display()
+    }
+
+    fn config_paths(&self) -> Vec<PathBuf> {
+        extra_clients::config_path_for(self).into_iter().collect()
+    }
+
+    /// Manual-setup clients are included so their steps show up in the
+    /// results. The Claude extension is left out: Claude Code's sync already
+    /// writes its file.
+    fn bulk_sync(&self) -> bool {
+        *self != ExtraClient::ClaudeVSCode
+    }
+
+    fn manual_steps(&self) -> Option<String> {
+        ExtraClient::manual_steps(self)
+    }
+}
+
+/// Every target, in dashboard order.
+pub fn all() -> Vec<Box<dyn SyncTarget>> {
+    let mut targets: Vec<Box<dyn SyncTarget>> = vec![
+        Box::new(CliApp::Claude),
+        Box::new(CliApp::Codex),
+        Box::new(CliApp::Gemini),
+        Box::new(Opencode),
+        Box::new(Droid),
+        Box::new(Openclaw),
+    ];
+    targets.extend(
+        ExtraClient::all()
+            .iter()
+            .map(|c| Box::new(*c) as Box<dyn SyncTarget>),
+    );
+    targets
+}
+
+pub fn find(app: &str) -> Option<Box<dyn SyncTarget>> {
+    all().into_iter().find(|t| t.id() == app)
+}
+
+/// One provider synced to every installed, unpinned target.
+pub struct BulkSync<'a> {
+    pub url: &'a str,
+    pub api_key: &'a str,
+    /// Aliased model for a target id; `None` keeps the app's own.
+    pub model_for: &'a (dyn Fn(&str) -> Option<String> + Sync),
+    pub small_model: Option<&'a str>,
+    pub opencode_adapter: OpencodeAdapter,
+    pub droid_overrides: Option<&'a DroidModelOverrides>,
+    pub aliases: &'a ModelAliases,
+    pub advanced: &'a ProviderAdvanced,
+    /// Saved provider being synced, recorded per app on success; `None` for a
+    /// URL that isn't saved.
+    pub provider_id: Option<&'a str>,
+    /// Save each main config into `config_backup` first and drop it once the
+    /// sync succeeds, so a crash mid-sync is recovered on the next launch.
+    pub snapshot: bool,
+}
+
+/// Sync `plan` to each installed target that takes part in bulk syncs.
+/// Targets that aren't installed are left out of the results.
+pub async fn sync_installed(
+    db: &Database,
+    targets: &[Box<dyn SyncTarget>],
+    plan: &BulkSync<'_>,
+) -> Vec<SyncResult> {
+    let pinned = pinned_apps::list(db);
+    let mut results = Vec::new();
+    for target in targets.iter().filter(|t| t.bulk_sync()) {
+        let app = target.id();
+        if !target.detect().0 {
+            continue;
+        }
+        if pinned.contains(app) {
+            results.push(SyncResult::pinned(app));
+            continue;
+        }
+        let note = match drift::check_before_sync(db, app) {
+            Ok(note) => note,
+            Err(e) => {
+                results.push(SyncResult::failed(app, e.to_string()));
+                continue;
+            }
+        };
+
+        if plan.snapshot {
+            if let Ok(content) = target.read_content(None) {
+                if let Err(e) = backup::save_backup(db, app, &content) {
+                    tracing::warn!("[sync] backup write failed for {}: {}", app, e);
+                }
+            }
+        }
+
+        let proxy_url = target.proxy_url(plan.url);
+        let model = (plan.model_for)(app);
+        let request = SyncRequest {
+            proxy_url: &proxy_url,
+            api_key: plan.api_key,
+            model: model.as_deref(),
+            small_model: plan.small_model,
+            opencode_adapter: plan.opencode_adapter,
+            droid_overrides: plan.droid_overrides,
+            aliases: plan.aliases,
+            advanced: plan.advanced,
+        };
+        let result = target.sync(&request).await;
+
+        match &result {
+            Ok(outcome) => {
+                if plan.snapshot {
+                    let _ = backup::delete_backup(db, app);
+                }
+                drift::record(db, app);
+                if matches!(outcome, SyncOutcome::Synced(_)) {
+                    synced_providers::record(db, app, plan.provider_id);
+                }
+            }
+            // The snapshot stays so the next launch restores it
+            Err(e) => tracing::error!("[sync] sync failed for {}: {}", app, logging::redact(e)),
+        }
+        let result = result.map(|outcome| match outcome {
+            SyncOutcome::Synced(warning) => {
+                SyncOutcome::Synced(crate::join_warnings(note, warning))
+            }
+            manual => manual,
+        });
+        results.push(SyncResult::from_outcome(app, result));
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type SyncLog = Arc<Mutex<Vec<String>>>;
+
+    struct MockTarget {
+        id: &'static str,
+        installed: bool,
+        fail: bool,
+        manual: bool,
+        log: SyncLog,
+    }
+
+    impl MockTarget {
+        fn new(id: &'static str, log: &SyncLog) -> Self {
+            Self {
+                id,
+                installed: true,
+                fail: false,
+                manual: false,
+                log: Arc::clone(log),
+            }
+        }
+    }
+
+    impl SyncTarget for MockTarget {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+        fn display_name(&self) -> &'static str {
+            self.id
+        }
+        fn detect(&self) -> (bool, Option<String>) {
+            (self.installed, None)
+        }
+        fn status(&self, _: Option<&str>, _: Option<OpencodeAdapter>) -> TargetStatus {
+            TargetStatus::default()
+        }
+        fn sync<'a>(&'a self, req: &'a SyncRequest<'a>) -> SyncFuture<'a> {
+            Box::pin(async move {
+                self.log.lock().unwrap().push(format!(
+                    "{} {} {}",
+                    self.id,
+                    req.proxy_url,
+                    req.model.unwrap_or("-")
+                ));
+                if self.fail {
+                    Err("disk full".to_string())
+                } else if self.manual {
+                    Ok(SyncOutcome::ManualStepsRequired(
+                        "Paste the key in Settings".to_string(),
+                    ))
+                } else {
+                    Ok(SyncOutcome::Synced(None))
+                }
+            })
+        }
+        fn restore(&self) -> Result<(), String> {
+            Ok(())
+        }
+        fn read_content(&self, _: Option<&str>) -> Result<String, String> {
+            Ok(format!("{{\"app\":\"{}\"}}", self.id))
+        }
+        fn write_content(&self, _: &str, _: &str) -> Result<(), String> {
+            Ok(())
+        }
+        fn config_files(&self) -> Vec<String> {
+            Vec::new()
+        }
+        fn config_paths(&self) -> Vec<PathBuf> {
+            Vec::new()
+        }
+        fn proxy_url(&self, base_url: &str) -> String {
+            with_v1(base_url)
+        }
+    }
+
+    #[test]
+    fn test_registry_ids_are_unique_and_ordered() {
+        let ids: Vec<_> = all().iter().map(|t| t.id()).collect();
+        assert_eq!(
+            &ids[..6],
+            ["claude", "codex", "gemini", "opencode", "droid", "openclaw"]
+        );
+        let unique: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len());
+        assert!(find("chatbox").is_some());
+        assert!(find("nope").is_none());
+        assert_eq!(find("codex").unwrap().proxy_url("http://x/"), "http://x/v1");
+        assert_eq!(find("claude").unwrap().proxy_url("http://x/"), "http://x");
+    }
+
+    #[tokio::test]
+    async fn test_sync_installed_orchestration() {
+        let db = Database::memory().unwrap();
+        pinned_apps::set_pinned(&db, "pinned", true).unwrap();
+
+        let log = SyncLog::default();
+        let missing = MockTarget {
+            installed: false,
+            ..MockTarget::new("missing", &log)
+        };
+        let broken = MockTarget {
+            fail: true,
+            ..MockTarget::new("broken", &log)
+        };
+        let by_hand = MockTarget {
+            manual: true,
+            ..MockTarget::new("by_hand", &log)
+        };
+        let targets: Vec<Box<dyn SyncTarget>> = vec![
+            Box::new(MockTarget::new("ok", &log)),
+            Box::new(missing),
+            Box::new(MockTarget::new("pinned", &log)),
+            Box::new(broken),
+            Box::new(by_hand),
+        ];
+        let aliases = ModelAliases::default();
+        let model_for = |app: &str| (app == "ok").then(|| "gpt-4o".to_string());
+        let plan = BulkSync {
+            url: "http://proxy/",
+            api_key: "sk-test",
+            model_for: &model_for,
+            small_model: None,
+            opencode_adapter: OpencodeAdapter::default(),
+            droid_overrides: None,
+            aliases: &aliases,
+            advanced: &ProviderAdvanced::default(),
+            provider_id: Some("work"),
+            snapshot: true,
+        };
+
+        let results = sync_installed(&db, &targets, &plan).await;
+        let summary: Vec<_> = results
+            .iter()
+            .map(|r| (r.app.as_str(), r.success, r.skipped, r.manual))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("ok", true, false, false),
+                ("pinned", true, true, false),
+                ("broken", false, false, false),
+                ("by_hand", true, false, true)
+            ]
+        );
+        assert_eq!(results[2].error.as_deref(), Some("disk full"));
+        assert_eq!(results[3].error, None);
+        assert_eq!(
+            results[3].warning.as_deref(),
+            Some("Paste the key in Settings")
+        );
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "ok http://proxy/v1 gpt-4o",
+                "broken http://proxy/v1 -",
+                "by_hand http://proxy/v1 -"
+            ]
+        );
+
+        // Snapshots are dropped on success and kept for recovery on failure
+        assert_eq!(backup::list_app_types(&db).unwrap(), ["broken"]);
+        // Only apps whose config was written name the provider
+        let synced = synced_providers::list(&db);
+        assert_eq!(synced.keys().collect::<Vec<_>>(), ["ok"]);
+        assert_eq!(synced["ok"].provider_id, "work");
+    }
+}
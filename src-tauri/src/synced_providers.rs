@@ -0,0 +1,176 @@
+//! Which saved provider each app was last synced to.
+//!
+//! Several providers can share a host with different keys, so the base URL in
+//! an app's config doesn't say which one it uses. Each successful sync of a
+//! saved provider records it here; configs synced before this existed, or by
+//! hand with a URL that isn't saved, fall back to matching the URL.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::dao::{providers, settings};
+use crate::database::Database;
+use crate::utils;
+
+/// JSON object of app id → `SyncedProvider`.
+const SYNCED_PROVIDERS_KEY: &str = "synced_providers";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedProvider {
+    pub provider_id: String,
+    pub synced_at: i64, // Unix seconds
+}
+
+pub fn list(db: &Database) -> BTreeMap<String, SyncedProvider> {
+    settings::get(db, SYNCED_PROVIDERS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+fn save(db: &Database, entries: &BTreeMap<String, SyncedProvider>) -> Result<(), String> {
+    let json = serde_json::to_string(entries).map_err(|e| e.to_string())?;
+    settings::set(db, SYNCED_PROVIDERS_KEY, &json)
+}
+
+/// Record a successful sync of `app`. `None` (a URL that isn't a saved
+/// provider) drops the old entry so it can't name the wrong provider.
+pub fn record(db: &Database, app: &str, provider_id: Option<&str>) {
+    let mut entries = list(db);
+    match provider_id {
+        Some(id) => {
+            entries.insert(
+                app.to_string(),
+                SyncedProvider {
+                    provider_id: id.to_string(),
+                    synced_at: chrono::Utc::now().timestamp(),
+                },
+            );
+        }
+        None => {
+            if entries.remove(app).is_none() {
+                return;
+            }
+        }
+    }
+    if let Err(e) = save(db, &entries) {
+        tracing::warn!("[synced_providers] Failed to record {}: {}", app, e);
+    }
+}
+
+/// Forget `app`, e.g. after its config was restored.
+pub fn forget(db: &Database, app: &str) {
+    record(db, app, None);
+}
+
+/// The saved provider whose URL and key are `url` / `api_key`, preferring the
+/// current one.
+pub fn provider_for(db: &Database, url: &str, api_key: &str) -> Option<providers::ProviderRecord> {
+    let mut matching: Vec<_> = providers::get_all(db, false)
+        .ok()?
+        .into_iter()
+        .filter(|p| utils::urls_match(&p.url, url) && p.api_key == api_key)
+        .collect();
+    let index = matching.iter().position(|p| p.is_current).unwrap_or(0);
+    (!matching.is_empty()).then(|| matching.swap_remove(index))
+}
+
+/// Provider `app` is synced to, given the base URL its config holds now.
+/// A recorded provider counts while its URL still matches the config;
+/// otherwise the one saved provider with that URL (or the current one, when
+/// several share it).
+pub fn resolve(
+    db: &Database,
+    app: &str,
+    current_base_url: Option<&str>,
+) -> Option<providers::ProviderRecord> {
+    let current_base_url = current_base_url?;
+    let all = providers::get_all(db, true).ok()?;
+    let url_matches = |p: &&providers::ProviderRecord| utils::urls_match(&p.url, current_base_url);
+
+    if let Some(entry) = list(db).get(app) {
+        if let Some(p) = all
+            .iter()
+            .filter(url_matches)
+            .find(|p| p.id == entry.provider_id)
+        {
+            return Some(p.clone());
+        }
+    }
+    let matching: Vec<_> = all
+        .iter()
+        .filter(|p| !p.archived)
+        .filter(url_matches)
+        .collect();
+    match matching.as_slice() {
+        [only] => Some((*only).clone()),
+        _ => matching.into_iter().find(|p| p.is_current).cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(db: &Database, id: &str, url: &str, key: &str) {
+        providers::save(
+            db,
+            &providers::ProviderRecord {
+                id: id.to_string(),
+                name: format!("Provider {id}"),
+                url: url.to_string(),
+                api_key: key.to_string(),
+                default_model: String::new(),
+                per_cli_models: "{}".to_string(),
+                is_current: false,
+                sort_index: None,
+                notes: None,
+                created_at: 0,
+                opencode_adapter: String::new(),
+                account_id: None,
+                model_aliases: "{}".to_string(),
+                account_base_url: None,
+                token_id: None,
+                quota: None,
+                archived: false,
+                advanced: "{}".to_string(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_record_and_resolve() {
+        let db = Database::memory().unwrap();
+        provider(&db, "work", "https://proxy.example.com", "sk-work");
+        provider(&db, "personal", "https://proxy.example.com", "sk-home");
+        provider(&db, "other", "https://other.example.com", "sk-other");
+
+        let resolved = |app: &str, url: &str| resolve(&db, app, Some(url)).map(|p| p.id);
+        // Before any record: a unique URL resolves, a shared one doesn't
+        assert_eq!(
+            resolved("codex", "https://other.example.com/v1").as_deref(),
+            Some("other")
+        );
+        assert_eq!(resolved("claude", "https://proxy.example.com"), None);
+
+        let found = provider_for(&db, "https://proxy.example.com/", "sk-home").unwrap();
+        record(&db, "claude", Some(&found.id));
+        assert_eq!(
+            resolved("claude", "https://proxy.example.com").as_deref(),
+            Some("personal")
+        );
+        // The config was pointed elsewhere since
+        assert_eq!(
+            resolved("claude", "https://other.example.com").as_deref(),
+            Some("other")
+        );
+        assert!(resolve(&db, "claude", None).is_none());
+
+        forget(&db, "claude");
+        assert!(list(&db).is_empty());
+        assert!(provider_for(&db, "https://proxy.example.com", "sk-none").is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Persist PATH additions for tools we install under `~/.hajimi`.
+//!
+//! Changing our own process's PATH is not enough: new terminals and the CLIs
+//! we configure would still not find the portable Node.js or Git. On Windows
+//! the directory goes into the user PATH (HKCU\Environment); on macOS/Linux it
+//! is listed in `~/.hajimi/env`, which a guarded block in the shell profile
+//! sources (fish gets its own `conf.d` file instead).
+
+use std::path::{Path, PathBuf};
+
+/// Directories the auto-installer puts executables in, whether or not they
+/// exist yet. `resolve_executable` checks these so the app finds freshly
+/// installed tools before any PATH change reaches it.
+pub fn managed_bin_dirs(home: &Path) -> Vec<PathBuf> {
+    let hajimi = home.join(".hajimi");
+    let mut dirs = vec![
+        hajimi.join("nodejs").join("bin"),
+        hajimi.join("portable").join("git").join("cmd"),
+    ];
+    // The Windows Node.js zip keeps its top-level `node-vX-win-x64` folder
+    if cfg!(windows) {
+        if let Ok(entries) = std::fs::read_dir(hajimi.join("nodejs")) {
+            dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+        }
+    }
+    dirs
+}
+
+/// Add `dir` to the user's persistent PATH. Already-present entries are left alone.
+pub fn persist(dir: &Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::persist(dir)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        unix::persist(&home, &shell, dir)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod unix {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const BLOCK_START: &str = "# >>> hajimi-cli-sync >>>";
+    const BLOCK_END: &str = "# <<< hajimi-cli-sync <<<";
+
+    pub fn persist(home: &Path, shell: &str, dir: &Path) -> Result<(), String> {
+        let dir = dir.to_string_lossy();
+        // Every line is one PATH entry, so a newline can't be written safely
+        if dir.contains(['\n', '\r']) {
+            return Err(format!("Refusing to add unusual path to PATH: {dir}"));
+        }
+
+        let env_file = home.join(".hajimi").join("env");
+        // Single quotes keep `$`, backticks and `\` from being expanded when sourced
+        let quoted = sh_quote(&dir);
+        let line = format!(
+            "case \":$PATH:\" in *:{quoted}:*) ;; *) export PATH={quoted}:\"$PATH\" ;; esac"
+        );
+        append_line(&env_file, "# PATH entries added by Hajimi CLI Sync", &line)?;
+
+        let shell_name = Path::new(shell)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if shell_name == "fish" {
+            let fish_file = home.join(".config/fish/conf.d/hajimi.fish");
+            return append_line(
+                &fish_file,
+                "# PATH entries added by Hajimi CLI Sync",
+                &format!("fish_add_path --global {}", fish_quote(&dir)),
+            );
+        }
+        for profile in profiles(home, shell_name) {
+            add_source_block(&profile)?;
+        }
+        Ok(())
+    }
+
+    /// `value` as a POSIX shell single-quoted word.
+    fn sh_quote(value: &str) -> String {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+
+    /// `value` as a fish single-quoted word, where only `\` and `'` are escaped.
+    fn fish_quote(value: &str) -> String {
+        format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+    }
+
+    /// Startup files to hook for the user's login shell.
+    fn profiles(home: &Path, shell_name: &str) -> Vec<PathBuf> {
+        match shell_name {
+            "zsh" => vec![home.join(".zshrc")],
+            // macOS Terminal starts login shells, which read .bash_profile, not .bashrc
+            "bash" if cfg!(target_os = "macos") => vec![home.join(".bash_profile")],
+            "bash" => vec![home.join(".bashrc")],
+            _ => vec![home.join(".profile")],
+        }
+    }
+
+    fn append_line(file: &Path, header: &str, line: &str) -> Result<(), String> {
+        let mut content = match fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => format!("{header}\n"),
+            Err(e) => return Err(format!("Failed to read {}: {e}", file.display())),
+        };
+        if content.lines().any(|l| l == line) {
+            return Ok(());
+        }
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(line);
+        content.push('\n');
+        write(file, &content)
+    }
+
+    fn add_source_block(profile: &Path) -> Result<(), String> {
+        let mut content = match fs::read_to_string(profile) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read {}: {e}", profile.display())),
+        };
+        if content.contains(BLOCK_START) {
+            return Ok(());
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!(
+            "\n{BLOCK_START}\n[ -f \"$HOME/.hajimi/env\" ] && . \"$HOME/.hajimi/env\"\n{BLOCK_END}\n"
+        ));
+        write(profile, &content)?;
+        tracing::info!("[user_path] Added PATH hook to {}", profile.display());
+        Ok(())
+    }
+
+    fn write(file: &Path, content: &str) -> Result<(), String> {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        fs::write(file, content).map_err(|e| format!("Failed to write {}: {e}", file.display()))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::path::Path;
+
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SendMessageTimeoutW, HWND_BROADCAST, SMTO_ABORTIFHUNG, WM_SETTINGCHANGE,
+    };
+    use winreg::enums::{RegType, HKEY_CURRENT_USER, KEY_READ, KEY_WRITE};
+    use winreg::{RegKey, RegValue};
+
+    pub fn persist(dir: &Path) -> Result<(), String> {
+        let env = RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags("Environment", KEY_READ | KEY_WRITE)
+            .map_err(|e| format!("Failed to open HKCU\\Environment: {e}"))?;
+        // Read unexpanded so entries like %USERPROFILE%\bin survive the rewrite
+        let current: String = env.get_value("Path").unwrap_or_default();
+        let dir = dir.to_string_lossy();
+        if current.split(';').any(|entry| {
+            entry
+                .trim_end_matches('\\')
+                .eq_ignore_ascii_case(dir.trim_end_matches('\\'))
+        }) {
+            return Ok(());
+        }
+
+        let updated = if current.trim_end_matches(';').is_empty() {
+            dir.to_string()
+        } else {
+            format!("{};{dir}", current.trim_end_matches(';'))
+        };
+        let bytes = updated
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        env.set_raw_value(
+            "Path",
+            &RegValue {
+                bytes,
+                vtype: RegType::REG_EXPAND_SZ,
+            },
+        )
+        .map_err(|e| format!("Failed to update the user PATH: {e}"))?;
+        broadcast_environment_change();
+        tracing::info!("[user_path] Added {} to the user PATH", dir);
+        Ok(())
+    }
+
+    /// Tell Explorer (and so every new terminal) to reload the environment.
+    fn broadcast_environment_change() {
+        let param: Vec<u16> = "Environment".encode_utf16().chain(Some(0)).collect();
+        let mut result = 0;
+        // SAFETY: `param` is a NUL-terminated UTF-16 string that outlives the call
+        unsafe {
+            SendMessageTimeoutW(
+                HWND_BROADCAST,
+                WM_SETTINGCHANGE,
+                0,
+                param.as_ptr() as isize,
+                SMTO_ABORTIFHUNG,
+                5000,
+                &mut result,
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persist_writes_env_file_and_hooks_profile_once() {
+        let home = tempfile::tempdir().unwrap();
+        let node = home.path().join(".hajimi/nodejs/bin");
+        let git = home.path().join(".hajimi/portable/git/cmd");
+        std::fs::write(home.path().join(".zshrc"), "alias ll='ls -l'").unwrap();
+
+        unix::persist(home.path(), "/bin/zsh", &node).unwrap();
+        unix::persist(home.path(), "/bin/zsh", &node).unwrap();
+        unix::persist(home.path(), "/bin/zsh", &git).unwrap();
+
+        let env = std::fs::read_to_string(home.path().join(".hajimi/env")).unwrap();
+        assert_eq!(env.matches("export PATH").count(), 2);
+        assert!(env.contains(&format!("PATH='{}':\"$PATH\"", node.display())));
+
+        let zshrc = std::fs::read_to_string(home.path().join(".zshrc")).unwrap();
+        assert!(zshrc.starts_with("alias ll='ls -l'\n"));
+        assert_eq!(zshrc.matches(". \"$HOME/.hajimi/env\"").count(), 1);
+        assert!(!home.path().join(".profile").exists());
+    }
+
+    #[test]
+    fn test_persist_fish_uses_conf_d() {
+        let home = tempfile::tempdir().unwrap();
+        let node = home.path().join(".hajimi/nodejs/bin");
+        unix::persist(home.path(), "/usr/bin/fish", &node).unwrap();
+
+        let fish =
+            std::fs::read_to_string(home.path().join(".config/fish/conf.d/hajimi.fish")).unwrap();
+        assert!(fish.contains(&format!("fish_add_path --global '{}'", node.display())));
+        assert!(!home.path().join(".profile").exists());
+        assert!(unix::persist(home.path(), "/bin/sh", Path::new("/tmp/a\nb")).is_err());
+
+        let odd = Path::new(r"/tmp/it's $HOME/`id`\bin");
+        unix::persist(home.path(), "/usr/bin/fish", odd).unwrap();
+        let fish =
+            std::fs::read_to_string(home.path().join(".config/fish/conf.d/hajimi.fish")).unwrap();
+        assert!(fish.contains(r"fish_add_path --global '/tmp/it\'s $HOME/`id`\\bin'"));
+    }
+
+    #[test]
+    fn test_env_file_does_not_expand_the_path() {
+        let home = tempfile::tempdir().unwrap();
+        let odd = r#"/tmp/it's "$HOME"/`id`\bin"#;
+        unix::persist(home.path(), "/bin/sh", Path::new(odd)).unwrap();
+
+        let env_file = home.path().join(".hajimi/env");
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(". \"$1\"; printf %s \"$PATH\"")
+            .arg("sh")
+            .arg(&env_file)
+            .env("PATH", "/usr/bin:/bin")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{odd}:/usr/bin:/bin")
+        );
+    }
+}
@@ -1,11 +1,14 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::RwLock;
 use std::time::Duration;
 
 use crate::error::{Result, SyncError};
+use crate::jsonc;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -47,6 +50,27 @@ pub fn extract_version(raw: &str) -> String {
     "unknown".to_string()
 }
 
+/// Compare dotted numeric versions; non-numeric parts sort lowest.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split('.')
+            .map(|p| p.parse::<u64>().unwrap_or(0))
+            .collect()
+    };
+    parse(a).cmp(&parse(b))
+}
+
+/// Leading major number of a version such as "v22.12.0" or "22.12.0".
+pub fn major_version(version: &str) -> Option<u32> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
+}
+
 fn is_version_like(s: &str) -> bool {
     s.chars().next().is_some_and(|c| c.is_ascii_digit())
         && s.contains('.')
@@ -100,6 +124,8 @@ pub fn find_in_common_paths(executable: &str) -> Option<PathBuf> {
         PathBuf::from("/usr/local/bin"),
         PathBuf::from("/usr/bin"),
     ];
+    // Portable Node.js / Git installed by the auto-installer
+    candidates.extend(crate::user_path::managed_bin_dirs(&home));
 
     // Scan nvm directories
     let nvm_base = home.join(".nvm/versions/node");
@@ -145,6 +171,17 @@ pub fn find_in_common_paths(executable: &str) -> Option<PathBuf> {
 /// Search common Windows binary locations.
 #[cfg(target_os = "windows")]
 pub fn find_in_common_paths(executable: &str) -> Option<PathBuf> {
+    // Portable Node.js / Git installed by the auto-installer
+    if let Some(home) = dirs::home_dir() {
+        for dir in crate::user_path::managed_bin_dirs(&home) {
+            for ext in &["cmd", "exe"] {
+                let path = dir.join(format!("{}.{}", executable, ext));
+                if path.exists() {
+                    return Some(path);
+                }
+            }
+        }
+    }
     if let Ok(app_data) = env::var("APPDATA") {
         for ext in &["cmd", "exe"] {
             let path = PathBuf::from(&app_data)
@@ -267,7 +304,7 @@ fn cleanup_old_backups(dir: &std::path::Path, base_name: &str, suffix: &str) ->
         .filter_map(|entry| entry.ok())
         .filter(|entry| {
             let name = entry.file_name().to_string_lossy().to_string();
-            // Match pattern: base_name.TIMESTAMP.suffix (e.g. settings.json.20260218_153045.antigravity.bak)
+            // Match pattern: base_name.TIMESTAMP.suffix (e.g. settings.json.20260218_153045.hajimi.bak)
             name.starts_with(&prefix)
                 && name.ends_with(&suffix_str)
                 && name != format!("{base_name}{suffix_str}")
@@ -299,12 +336,40 @@ fn cleanup_old_backups(dir: &std::path::Path, base_name: &str, suffix: &str) ->
 
 /// Atomically write content to a file using a temp file + rename pattern.
 /// Enhanced with retry mechanism for Windows file locking issues.
-pub fn atomic_write(target: &PathBuf, content: &str) -> Result<()> {
+///
+/// Symlinks are followed so the link itself survives (dotfiles repos), and the
+/// existing file's mode and owner are carried over to the new file.
+pub fn atomic_write(target: &Path, content: &str) -> Result<()> {
     atomic_write_with_retry(target, content, 5)
 }
 
+/// `atomic_write` for files holding an API key: on Unix the file ends up 0600
+/// whatever the umask or its previous mode, and the temp file is never
+/// readable by others. On Windows nothing changes; files under the user
+/// profile already inherit an ACL limited to the user, SYSTEM and admins.
+pub fn atomic_write_secure(target: &Path, content: &str) -> Result<()> {
+    write_with_retry(target, content, 5, dirs::home_dir().as_deref(), true)
+}
+
 /// Atomically write with configurable retry count.
-pub fn atomic_write_with_retry(target: &PathBuf, content: &str, max_retries: u32) -> Result<()> {
+pub fn atomic_write_with_retry(target: &Path, content: &str, max_retries: u32) -> Result<()> {
+    write_with_retry(
+        target,
+        content,
+        max_retries,
+        dirs::home_dir().as_deref(),
+        false,
+    )
+}
+
+fn write_with_retry(
+    target: &Path,
+    content: &str,
+    max_retries: u32,
+    home: Option<&Path>,
+    secure: bool,
+) -> Result<()> {
+    let target = &resolve_write_target(target, home)?;
     let tmp_path = target.with_extension("tmp");
 
     // Ensure parent directory exists
@@ -316,7 +381,9 @@ pub fn atomic_write_with_retry(target: &PathBuf, content: &str, max_retries: u32
     }
 
     for attempt in 0..max_retries {
-        match try_atomic_write(&tmp_path, target, content) {
+        match try_atomic_write(&tmp_path, target, content, secure, |from, to| {
+            fs::rename(from, to)
+        }) {
             Ok(_) => {
                 tracing::debug!("[atomic_write] Success on attempt {}", attempt + 1);
                 return Ok(());
@@ -345,9 +412,25 @@ pub fn atomic_write_with_retry(target: &PathBuf, content: &str, max_retries: u32
     })
 }
 
-fn try_atomic_write(tmp_path: &PathBuf, target: &PathBuf, content: &str) -> Result<()> {
-    // Write to temp file
-    fs::write(tmp_path, content).map_err(|e| {
+type RenameFn = fn(&Path, &Path) -> std::io::Result<()>;
+
+/// Writes `content` to `tmp_path` and moves it over `target` with `rename`.
+/// The temp file is gone afterwards whether or not this succeeds.
+fn try_atomic_write(
+    tmp_path: &PathBuf,
+    target: &PathBuf,
+    content: &str,
+    secure: bool,
+    rename: RenameFn,
+) -> Result<()> {
+    // Write to temp file; a secure one is created owner-only
+    let written = if secure {
+        crate::crypto::write_private(tmp_path, content.as_bytes())
+            .and_then(|()| restrict_to_owner(tmp_path))
+    } else {
+        fs::write(tmp_path, content)
+    };
+    written.map_err(|e| {
         let _ = fs::remove_file(tmp_path);
 
         // 检测具体错误类型
@@ -363,8 +446,17 @@ fn try_atomic_write(tmp_path: &PathBuf, target: &PathBuf, content: &str) -> Resu
         }
     })?;
 
-    // Rename to target
-    fs::rename(tmp_path, target).map_err(|e| {
+    copy_metadata(target, tmp_path, !secure);
+
+    // Rename to target; a target on another volume (e.g. behind a junction) gets a copy instead
+    let moved = match rename(tmp_path, target) {
+        Err(e) if is_cross_device(&e) => {
+            tracing::debug!("[atomic_write] {:?} is on another device, copying", target);
+            copy_into_place(tmp_path, target)
+        }
+        other => other,
+    };
+    moved.map_err(|e| {
         let _ = fs::remove_file(tmp_path);
 
         if e.kind() == std::io::ErrorKind::PermissionDenied {
@@ -382,28 +474,774 @@ fn try_atomic_write(tmp_path: &PathBuf, target: &PathBuf, content: &str) -> Resu
     Ok(())
 }
 
-/// Serialize a serde_json::Value to pretty JSON.
-pub fn to_json_pretty(value: &Value) -> Result<String> {
-    serde_json::to_string_pretty(value).map_err(|e| SyncError::JsonParseFailed {
+/// `ERROR_NOT_SAME_DEVICE`
+#[cfg(windows)]
+const ERROR_NOT_SAME_DEVICE: i32 = 17;
+
+fn is_cross_device(e: &std::io::Error) -> bool {
+    #[cfg(windows)]
+    if e.raw_os_error() == Some(ERROR_NOT_SAME_DEVICE) {
+        return true;
+    }
+    e.kind() == std::io::ErrorKind::CrossesDevices
+}
+
+/// The non-atomic fallback for when `tmp_path` can't be renamed onto `target`:
+/// copy it over, flush the copy to disk, then drop the temp file.
+fn copy_into_place(tmp_path: &Path, target: &Path) -> std::io::Result<()> {
+    fs::copy(tmp_path, target)?;
+    fs::OpenOptions::new()
+        .write(true)
+        .open(target)?
+        .sync_all()?;
+    fs::remove_file(tmp_path)
+}
+
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Follow `target` through any symlinks to the file that should be replaced.
+/// Dangling links resolve to where they point. Links leading outside `home`
+/// are refused so a planted symlink can't redirect a write elsewhere.
+fn resolve_write_target(target: &Path, home: Option<&Path>) -> Result<PathBuf> {
+    let mut current = target.to_path_buf();
+    let mut followed = false;
+    for _ in 0..MAX_SYMLINK_HOPS {
+        let is_link = fs::symlink_metadata(&current)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+        if !is_link {
+            if followed {
+                check_inside_home(target, &current, home)?;
+                tracing::debug!("[atomic_write] {:?} -> {:?}", target, current);
+            }
+            return Ok(current);
+        }
+        let link = fs::read_link(&current).map_err(|e| SyncError::FileReadFailed {
+            path: current.to_string_lossy().to_string(),
+            reason: e.to_string(),
+        })?;
+        // Relative links are relative to the link's directory; absolute ones replace it
+        current = match current.parent() {
+            Some(parent) => parent.join(link),
+            None => link,
+        };
+        followed = true;
+    }
+    Err(SyncError::FileWriteFailed {
+        path: target.to_string_lossy().to_string(),
+        reason: "Too many levels of symbolic links".to_string(),
+    })
+}
+
+fn check_inside_home(link: &Path, resolved: &Path, home: Option<&Path>) -> Result<()> {
+    let Some(home) = home else {
+        return Ok(());
+    };
+    // Canonicalize what exists so `..` and symlinked parents can't slip past
+    let canonical = |p: &Path| match (p.parent(), p.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|dir| dir.join(name))
+            .unwrap_or_else(|_| p.to_path_buf()),
+        _ => p.to_path_buf(),
+    };
+    let home = home.canonicalize().unwrap_or_else(|_| home.to_path_buf());
+    if canonical(resolved).starts_with(&home) {
+        Ok(())
+    } else {
+        Err(SyncError::SymlinkOutsideHome {
+            path: link.to_string_lossy().to_string(),
+            target: resolved.to_string_lossy().to_string(),
+        })
+    }
+}
+
+/// Give the temp file the mode (unless `with_mode` is false) and on Unix the
+/// owner of the file it replaces. Best effort: new files keep the defaults,
+/// and chown usually needs privileges.
+fn copy_metadata(target: &Path, tmp_path: &Path, with_mode: bool) {
+    let Ok(meta) = fs::metadata(target) else {
+        return;
+    };
+    if with_mode {
+        if let Err(e) = fs::set_permissions(tmp_path, meta.permissions()) {
+            tracing::warn!("[atomic_write] Cannot copy mode to {:?}: {}", tmp_path, e);
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Err(e) = std::os::unix::fs::chown(tmp_path, Some(meta.uid()), Some(meta.gid())) {
+            tracing::debug!("[atomic_write] Cannot copy owner to {:?}: {}", tmp_path, e);
+        }
+    }
+}
+
+/// chmod 0600. `write_private` only sets the mode on files it creates, and a
+/// stale temp file may be left over from an interrupted write.
+#[cfg(unix)]
+fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_to_owner(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Whether `path` exists and can be read by its group or by anyone (Unix mode
+/// bits). Always false on Windows, where access is governed by ACLs.
+#[cfg(unix)]
+pub fn readable_by_others(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o044 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn readable_by_others(_path: &Path) -> bool {
+    false
+}
+
+/// Serialize `value` in the style of `original` (the file's current text):
+/// same indent unit, compact vs pretty, trailing newline and line endings.
+/// When `original` already holds the same document it is returned untouched,
+/// so a no-op sync leaves the file byte-identical. Key order is kept by
+/// serde_json's `preserve_order`.
+pub fn to_json_like(value: &Value, original: &str) -> Result<String> {
+    if serde_json::from_str::<Value>(original).is_ok_and(|old| &old == value) {
+        return Ok(original.to_string());
+    }
+    let body = original.trim();
+    let serialized = if !body.is_empty() && !body.contains('\n') {
+        serde_json::to_string(value).map_err(|e| e.to_string())
+    } else {
+        let indent = detect_indent(original).unwrap_or("  ");
+        let mut buf = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        value
+            .serialize(&mut ser)
+            .map_err(|e| e.to_string())
+            .map(|()| String::from_utf8_lossy(&buf).into_owned())
+    };
+    let mut out = serialized.map_err(|reason| SyncError::JsonParseFailed {
         path: "in-memory".to_string(),
-        reason: e.to_string(),
+        reason,
+    })?;
+    if original.ends_with('\n') {
+        out.push('\n');
+    }
+    if original.contains("\r\n") {
+        out = out.replace('\n', "\r\n");
+    }
+    Ok(out)
+}
+
+/// Leading whitespace of the first indented line, i.e. one nesting level.
+pub(crate) fn detect_indent(original: &str) -> Option<&str> {
+    original.lines().find_map(|line| {
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        let indent = &line[..line.len() - trimmed.len()];
+        (!indent.is_empty() && !trimmed.is_empty()).then_some(indent)
     })
 }
 
-/// Canonical backup suffix used across all sync modules.
-pub const BACKUP_SUFFIX: &str = ".antigravity.bak";
+/// Parse JSON that may contain `//` and `/* */` comments or trailing commas
+/// (JSONC, as in VS Code style settings files).
+pub fn parse_json_lenient(content: &str) -> std::result::Result<Value, serde_json::Error> {
+    serde_json::from_str(content).or_else(|_| serde_json::from_str(&jsonc::strip(content)))
+}
 
-/// Compare two proxy URLs ignoring trailing slashes and optional /v1 suffix.
-pub fn urls_match(a: &str, b: &str) -> bool {
-    let normalize = |s: &str| {
-        let t = s.trim().trim_end_matches('/');
-        if t.ends_with("/v1") {
-            t.to_string()
+/// Write `value` to `path` in the existing file's style; skipped entirely when
+/// the content wouldn't change. A JSONC file is only edited key by key, never
+/// rewritten, so its comments survive. Every JSON config we write holds an
+/// API key, so this goes through `atomic_write_secure`.
+pub fn write_json_secure(path: &PathBuf, value: &Value) -> Result<()> {
+    let original = fs::read_to_string(path).unwrap_or_default();
+    let lenient = serde_json::from_str::<Value>(&original)
+        .is_err()
+        .then(|| parse_json_lenient(&original).ok())
+        .flatten();
+    let content = match lenient {
+        Some(old) => edit_jsonc(path, &original, &old, value)?,
+        None => to_json_like(value, &original)?,
+    };
+    if content == original {
+        tracing::debug!("[write_json] {:?} unchanged, skipping write", path);
+        return Ok(());
+    }
+    atomic_write_secure(path, &content)
+}
+
+/// How a config file is parsed when reading it back after a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Env,
+}
+
+impl ConfigFormat {
+    /// Guess from the file name: `*.toml`, `.env` / `*.env`, anything else JSON.
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".toml") {
+            Self::Toml
+        } else if name == ".env" || name.ends_with(".env") {
+            Self::Env
         } else {
-            format!("{t}/v1")
+            Self::Json
+        }
+    }
+
+    /// Every string value in `content`, or why it doesn't parse.
+    fn string_values(self, content: &str) -> std::result::Result<Vec<String>, String> {
+        fn collect_json(value: &Value, out: &mut Vec<String>) {
+            match value {
+                Value::String(s) => out.push(s.clone()),
+                Value::Array(items) => items.iter().for_each(|v| collect_json(v, out)),
+                Value::Object(map) => map.values().for_each(|v| collect_json(v, out)),
+                _ => {}
+            }
+        }
+        fn collect_toml(value: &toml::Value, out: &mut Vec<String>) {
+            match value {
+                toml::Value::String(s) => out.push(s.clone()),
+                toml::Value::Array(items) => items.iter().for_each(|v| collect_toml(v, out)),
+                toml::Value::Table(table) => table.values().for_each(|v| collect_toml(v, out)),
+                _ => {}
+            }
+        }
+
+        let mut out = Vec::new();
+        match self {
+            Self::Json => {
+                let value =
+                    parse_json_lenient(content).map_err(|e| format!("invalid JSON: {e}"))?;
+                collect_json(&value, &mut out);
+            }
+            Self::Toml => {
+                let value: toml::Value =
+                    toml::from_str(content).map_err(|e| format!("invalid TOML: {e}"))?;
+                collect_toml(&value, &mut out);
+            }
+            Self::Env => out.extend(EnvFile::parse(content).values().map(str::to_string)),
         }
+        Ok(out)
+    }
+}
+
+/// Read `path` back and check it parses and still holds every `expected` value.
+fn check_written_config(path: &Path, expected: &[&str]) -> std::result::Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("cannot read it back: {e}"))?;
+    if content.trim().is_empty() {
+        return Err("file is empty".to_string());
+    }
+    let values = ConfigFormat::from_path(path).string_values(&content)?;
+    let missing = expected
+        .iter()
+        .filter(|want| !want.is_empty() && !values.iter().any(|v| v.contains(**want)))
+        .count();
+    if missing > 0 {
+        return Err(format!("{missing} of the values just written are missing"));
+    }
+    Ok(())
+}
+
+/// Newest timestamped backup of `path` made by [`create_rotated_backup`].
+fn latest_rotated_backup(path: &Path, suffix: &str) -> Option<PathBuf> {
+    rotated_backups(path, suffix).into_iter().next()
+}
+
+/// Timestamped backups of `path` made by [`create_rotated_backup`], newest first.
+fn rotated_backups(path: &Path, suffix: &str) -> Vec<PathBuf> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
     };
-    normalize(a) == normalize(b)
+    let file_name = file_name.to_string_lossy().to_string();
+    let prefix = format!("{file_name}.");
+    let simple = format!("{file_name}{suffix}");
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(&prefix) && name.ends_with(suffix) && *name != simple)
+        .collect();
+    // The timestamp sorts lexicographically
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    names
+        .into_iter()
+        .map(|name| path.with_file_name(name))
+        .collect()
+}
+
+/// Parse `content`, the current text of the JSON config at `path`, for a
+/// merge; an empty file is `{}`. When it isn't a JSON object, the newest
+/// backup that is takes its place (the broken text is in the backup sync took
+/// just before). With no usable backup this is `ConfigCorrupted`, so a merge
+/// never starts from `{}` and drops the user's keys.
+pub fn validate_and_repair_json(path: &Path, content: &str) -> Result<Value> {
+    if content.trim().is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    let reason = match parse_json_lenient(content) {
+        Ok(value) if value.is_object() => return Ok(value),
+        Ok(_) => "not a JSON object".to_string(),
+        Err(e) => e.to_string(),
+    };
+    let candidates = rotated_backups(path, BACKUP_SUFFIX)
+        .into_iter()
+        .chain(find_backup(path));
+    for backup in candidates {
+        let Some(value) = fs::read_to_string(&backup)
+            .ok()
+            .and_then(|c| parse_json_lenient(&c).ok())
+            .filter(Value::is_object)
+        else {
+            continue;
+        };
+        tracing::warn!(
+            "[repair] {:?} is not valid JSON ({}); merging into backup {:?}",
+            path,
+            reason,
+            backup
+        );
+        return Ok(value);
+    }
+    Err(SyncError::ConfigCorrupted {
+        path: path.to_string_lossy().to_string(),
+        reason: format!("{reason}; no backup is valid JSON either, fix the file and sync again"),
+    })
+}
+
+/// Verify a config file right after a sync wrote it: it must parse as its
+/// format and contain every `expected` value (base URL, API key). Something
+/// else touching the file mid-write (antivirus on Windows) has left truncated
+/// files behind, so a failed check runs `rewrite` once and checks again;
+/// if that fails too, the backup taken before the sync is put back and
+/// `ConfigCorrupted` is returned.
+pub fn verify_written_config(
+    path: &Path,
+    expected: &[&str],
+    rewrite: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    let Err(first) = check_written_config(path, expected) else {
+        return Ok(());
+    };
+    tracing::warn!(
+        "[verify] {:?} failed read-back ({}), writing it again",
+        path,
+        first
+    );
+    let reason = match rewrite().map_err(|e| e.to_string()) {
+        Ok(()) => match check_written_config(path, expected) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        },
+        Err(e) => format!("rewrite failed: {e}"),
+    };
+
+    let restored = latest_rotated_backup(path, BACKUP_SUFFIX).and_then(|backup| {
+        let content = fs::read_to_string(&backup).ok()?;
+        match atomic_write(path, &content) {
+            Ok(()) => Some(backup),
+            Err(e) => {
+                tracing::error!("[verify] Cannot restore {:?}: {}", backup, e);
+                None
+            }
+        }
+    });
+    let reason = match &restored {
+        Some(backup) => {
+            tracing::warn!("[verify] Restored {:?} from {:?}", path, backup);
+            format!(
+                "{reason} after writing it twice; restored the backup {}",
+                backup.display()
+            )
+        }
+        None => format!("{reason} after writing it twice; no backup to restore"),
+    };
+    Err(SyncError::ConfigCorrupted {
+        path: path.to_string_lossy().to_string(),
+        reason,
+    })
+}
+
+/// Apply the top-level changes from `old` to `new` to the JSONC text in place.
+/// Refuses anything that would need a full rewrite (removed keys, non-object root).
+fn edit_jsonc(path: &Path, original: &str, old: &Value, new: &Value) -> Result<String> {
+    let refuse = || SyncError::WouldLoseComments {
+        path: path.to_string_lossy().to_string(),
+    };
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return Err(refuse());
+    };
+    if old.keys().any(|k| !new.contains_key(k)) {
+        return Err(refuse());
+    }
+    let mut text = original.to_string();
+    for (key, value) in new {
+        if old.get(key) != Some(value) {
+            text = jsonc::set_top_level(&text, key, value).ok_or_else(refuse)?;
+        }
+    }
+    // Never write something that no longer reads back as what we meant
+    match parse_json_lenient(&text) {
+        Ok(parsed) if parsed.as_object() == Some(new) => Ok(text),
+        _ => Err(refuse()),
+    }
+}
+
+/// A `.env` file edited entry by entry. Comments, blank lines and anything
+/// that isn't `KEY=value` are kept verbatim; edited entries keep their indent,
+/// `export` prefix, quoting and trailing comment.
+#[derive(Debug, Clone)]
+pub struct EnvFile {
+    lines: Vec<EnvLine>,
+    crlf: bool,
+}
+
+#[derive(Debug, Clone)]
+enum EnvLine {
+    Entry(EnvEntry),
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+struct EnvEntry {
+    indent: String,
+    export: bool,
+    key: String,
+    value: String,
+    quote: Option<char>,
+    /// Whatever followed the value (`  # comment`), kept as is.
+    suffix: String,
+    /// Original line, written back untouched unless the value changed.
+    raw: Option<String>,
+}
+
+impl EnvFile {
+    pub fn parse(content: &str) -> Self {
+        Self {
+            lines: content
+                .lines()
+                .map(|line| match EnvEntry::parse(line) {
+                    Some(entry) => EnvLine::Entry(entry),
+                    None => EnvLine::Other(line.to_string()),
+                })
+                .collect(),
+            crlf: content.contains("\r\n"),
+        }
+    }
+
+    /// Value of the last `key` entry, unquoted. Later entries win, as in shells.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .rev()
+            .find(|e| e.key == key)
+            .map(|e| e.value.as_str())
+    }
+
+    /// Update the last `key` entry, or append one.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let last = self.lines.iter_mut().rev().find_map(|line| match line {
+            EnvLine::Entry(e) if e.key == key => Some(e),
+            _ => None,
+        });
+        match last {
+            Some(entry) => {
+                entry.value = value.to_string();
+                entry.raw = None;
+            }
+            None => self.lines.push(EnvLine::Entry(EnvEntry {
+                indent: String::new(),
+                export: false,
+                key: key.to_string(),
+                value: value.to_string(),
+                quote: None,
+                suffix: String::new(),
+                raw: None,
+            })),
+        }
+    }
+
+    /// Drop every `key` entry; returns how many were removed.
+    pub fn remove(&mut self, key: &str) -> usize {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, EnvLine::Entry(e) if e.key == key));
+        before - self.lines.len()
+    }
+
+    /// File text with the original line endings and a trailing newline.
+    pub fn render(&self) -> String {
+        let eol = if self.crlf { "\r\n" } else { "\n" };
+        self.lines
+            .iter()
+            .map(|line| match line {
+                EnvLine::Entry(e) => e.render(),
+                EnvLine::Other(raw) => raw.clone(),
+            })
+            .map(|line| line + eol)
+            .collect()
+    }
+
+    /// Every entry's value, unquoted.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.entries().map(|e| e.value.as_str())
+    }
+
+    /// 1-based numbers of lines that are neither entries, comments nor blank.
+    pub fn malformed_lines(&self) -> Vec<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| match line {
+                EnvLine::Other(text) => {
+                    let text = text.trim();
+                    (!text.is_empty() && !text.starts_with('#')).then_some(i + 1)
+                }
+                EnvLine::Entry(_) => None,
+            })
+            .collect()
+    }
+
+    fn entries(&self) -> impl DoubleEndedIterator<Item = &EnvEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            EnvLine::Entry(e) => Some(e),
+            EnvLine::Other(_) => None,
+        })
+    }
+}
+
+impl EnvEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let body = line.trim_start();
+        let indent = line[..line.len() - body.len()].to_string();
+        let (export, body) = match body.strip_prefix("export") {
+            Some(rest) if rest.starts_with([' ', '\t']) => (true, rest.trim_start()),
+            _ => (false, body),
+        };
+        let (key, raw) = body.split_once('=')?;
+        let key = key.trim_end();
+        let valid_key = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !valid_key {
+            return None;
+        }
+
+        let raw = raw.trim_start();
+        let (value, quote, suffix) = match raw.chars().next() {
+            Some(q @ ('"' | '\'')) => {
+                let (value, rest) = split_quoted(&raw[1..], q)?;
+                (value, Some(q), rest.to_string())
+            }
+            _ => {
+                // Unquoted: a ` #` starts a comment
+                let end = raw
+                    .find(" #")
+                    .or_else(|| raw.find("\t#"))
+                    .unwrap_or(raw.len());
+                let value = raw[..end].trim_end();
+                (value.to_string(), None, raw[value.len()..].to_string())
+            }
+        };
+        Some(Self {
+            indent,
+            export,
+            key: key.to_string(),
+            value,
+            quote,
+            suffix,
+            raw: Some(line.to_string()),
+        })
+    }
+
+    fn render(&self) -> String {
+        if let Some(raw) = &self.raw {
+            return raw.clone();
+        }
+        let needs_quotes = self
+            .value
+            .contains(|c: char| c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '\\'));
+        let quote = match self.quote {
+            Some('\'') if !self.value.contains('\'') => Some('\''),
+            Some(_) => Some('"'),
+            None if needs_quotes => Some('"'),
+            None => None,
+        };
+        let value = match quote {
+            Some('"') => format!(
+                "\"{}\"",
+                self.value.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            Some(q) => format!("{q}{}{q}", self.value),
+            None => self.value.clone(),
+        };
+        format!(
+            "{}{}{}={}{}",
+            self.indent,
+            if self.export { "export " } else { "" },
+            self.key,
+            value,
+            self.suffix
+        )
+    }
+}
+
+/// Split `s` (just after an opening `q`) at the closing quote. Double quotes
+/// honour `\"` and `\\` escapes; single quotes are literal.
+fn split_quoted(s: &str, q: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if q == '"' => match chars.next() {
+                Some((_, next @ ('"' | '\\'))) => value.push(next),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => return None,
+            },
+            c if c == q => return Some((value, &s[i + 1..])),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Run blocking file work on the blocking pool so a slow home directory (e.g.
+/// on a network share) doesn't stall the async workers serving IPC. Lock
+/// guards stay with the awaiting caller; the closure takes any DB lock itself.
+pub async fn run_blocking<T, F>(f: F) -> std::result::Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    // The test home override is per thread, so carry it over
+    #[cfg(test)]
+    let home = crate::paths::current_override();
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(test)]
+        let _home = home.as_deref().map(crate::paths::override_home);
+        f()
+    })
+    .await
+    .map_err(|e| format!("Background file task failed: {e}"))
+}
+
+/// Canonical backup suffix used across all sync modules.
+pub const BACKUP_SUFFIX: &str = ".hajimi.bak";
+/// Suffix inherited from the project this was forked from. Backups with it are
+/// still found and restored, and renamed to `BACKUP_SUFFIX` once at startup.
+pub const LEGACY_BACKUP_SUFFIX: &str = ".antigravity.bak";
+
+/// Where sync keeps the simple (latest pre-sync) backup of `path`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{name}{BACKUP_SUFFIX}"))
+}
+
+/// The simple backup of `path` to restore from: `BACKUP_SUFFIX`, else a
+/// not-yet-migrated `LEGACY_BACKUP_SUFFIX` one.
+pub fn find_backup(path: &Path) -> Option<PathBuf> {
+    let current = backup_path(path);
+    if current.exists() {
+        return Some(current);
+    }
+    let name = path.file_name()?.to_string_lossy().to_string();
+    Some(path.with_file_name(format!("{name}{LEGACY_BACKUP_SUFFIX}"))).filter(|p| p.exists())
+}
+
+/// Rename `path`'s legacy-suffix backups (simple and timestamped) to
+/// `BACKUP_SUFFIX`, skipping any whose new name is already taken. Returns how
+/// many were renamed.
+pub fn migrate_legacy_backups(path: &Path) -> usize {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return 0;
+    };
+    let name = name.to_string_lossy().to_string();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut renamed = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let old_name = entry.file_name().to_string_lossy().to_string();
+        let Some(stem) = old_name.strip_suffix(LEGACY_BACKUP_SUFFIX) else {
+            continue;
+        };
+        // `name` itself or `name.TIMESTAMP`
+        if stem != name && !stem.starts_with(&format!("{name}.")) {
+            continue;
+        }
+        let target = dir.join(format!("{stem}{BACKUP_SUFFIX}"));
+        if target.exists() {
+            continue;
+        }
+        match fs::rename(entry.path(), &target) {
+            Ok(()) => renamed += 1,
+            Err(e) => tracing::warn!("[backup] Failed to rename {}: {}", old_name, e),
+        }
+    }
+    renamed
+}
+
+/// Canonical form of a URL for comparison: lowercase scheme and host, no
+/// default port, no duplicate or trailing slashes. Input that doesn't parse is
+/// only trimmed.
+pub fn normalize_url(url: &str) -> String {
+    let trimmed = url.trim();
+    let Ok(parsed) = reqwest::Url::parse(trimmed) else {
+        return trimmed.trim_end_matches('/').to_string();
+    };
+    // `Url` already lowercases scheme/host and drops the scheme's default port
+    let mut out = format!(
+        "{}://{}",
+        parsed.scheme(),
+        parsed.host_str().unwrap_or_default()
+    );
+    if let Some(port) = parsed.port() {
+        out.push_str(&format!(":{port}"));
+    }
+    for segment in parsed.path().split('/').filter(|s| !s.is_empty()) {
+        out.push('/');
+        out.push_str(segment);
+    }
+    if let Some(query) = parsed.query() {
+        out.push('?');
+        out.push_str(query);
+    }
+    out
+}
+
+/// Same URL after normalization.
+pub fn urls_equal(a: &str, b: &str) -> bool {
+    normalize_url(a) == normalize_url(b)
+}
+
+/// Compare two proxy URLs after normalization, treating a trailing `/v1` as
+/// optional since clients differ on whether they append it.
+pub fn urls_match(a: &str, b: &str) -> bool {
+    let root = |s: &str| {
+        let n = normalize_url(s);
+        n.strip_suffix("/v1").map(str::to_string).unwrap_or(n)
+    };
+    root(a) == root(b)
 }
 
 /// Validate a URL string (basic check: must start with http:// or https://)
@@ -441,9 +1279,315 @@ pub fn validate_url(url: &str) -> Result<()> {
     Ok(())
 }
 
+/// Proxy the app's own HTTP requests go through, set in Settings. With no
+/// `url`, reqwest's usual `HTTP(S)_PROXY` / `ALL_PROXY` / `NO_PROXY` handling
+/// applies. The password is never logged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProxyConfig {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Comma-separated hosts, domains and CIDRs that skip the proxy
+    pub no_proxy: String,
+}
+
+impl ProxyConfig {
+    pub fn is_set(&self) -> bool {
+        !self.url.trim().is_empty()
+    }
+
+    fn to_proxy(&self) -> std::result::Result<reqwest::Proxy, String> {
+        let url = self.url.trim();
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| format!("Invalid proxy URL {}: {e}", crate::logging::redact(url)))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(format!(
+                "Proxy URL must be http:// or https:// with a host: {}",
+                crate::logging::redact(url)
+            ));
+        }
+        let mut proxy = reqwest::Proxy::all(parsed)
+            .map_err(|e| format!("Invalid proxy URL: {e}"))?
+            .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy));
+        let username = self.username.trim();
+        if !username.is_empty() {
+            proxy = proxy.basic_auth(username, &self.password);
+        }
+        Ok(proxy)
+    }
+
+    /// Check the config without building a client.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.is_set() {
+            self.to_proxy()?;
+        } else if !self.username.trim().is_empty() {
+            return Err("Proxy credentials need a proxy URL".to_string());
+        }
+        Ok(())
+    }
+}
+
+static HTTP_PROXY: RwLock<Option<ProxyConfig>> = RwLock::new(None);
+
+/// Replace the proxy used by `http_client`; `None` or an empty URL falls back
+/// to the environment.
+pub fn set_http_proxy(config: Option<ProxyConfig>) {
+    let config = config.filter(ProxyConfig::is_set);
+    *HTTP_PROXY.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+pub fn http_proxy_configured() -> bool {
+    HTTP_PROXY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some()
+}
+
+/// The HTTP client every outgoing request should use, so the proxy setting
+/// applies app-wide.
+pub fn http_client(timeout: Duration) -> std::result::Result<reqwest::Client, String> {
+    let proxy = HTTP_PROXY.read().unwrap_or_else(|e| e.into_inner()).clone();
+    http_client_with(timeout, proxy.as_ref())
+}
+
+/// `http_client` for calls to the user's API gateway (model lists, connection
+/// tests, account), where the timeout override replaces `timeout`. Downloads
+/// and update checks keep their own timeouts.
+pub fn api_client(timeout: Duration) -> std::result::Result<reqwest::Client, String> {
+    let tls = HTTP_TLS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    http_client(api_timeout(timeout, tls.as_ref()))
+}
+
+fn api_timeout(timeout: Duration, tls: Option<&LoadedTls>) -> Duration {
+    tls.and_then(|t| t.config.timeout_secs)
+        .map(Duration::from_secs)
+        .unwrap_or(timeout)
+}
+
+/// First backoff of `send_with_retry`; doubled for each later attempt.
+const RETRY_BASE_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_millis(500)
+};
+
+fn retry_delay(attempt: u32) -> Duration {
+    let base = RETRY_BASE_DELAY * 2u32.pow(attempt);
+    // Up to 50% jitter so clients synced together don't retry in lockstep
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    base + base.mul_f64(f64::from(nanos % 1000) / 2000.0)
+}
+
+/// Send `request`, retrying up to `retries` more times on a 5xx response or a
+/// connection error, with jittered exponential backoff. Anything else is
+/// returned as is, as is the last attempt. Requests with a streaming body
+/// can't be cloned and are sent once.
+pub async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+    retries: u32,
+) -> std::result::Result<reqwest::Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        let Some(this) = (attempt < retries).then(|| request.try_clone()).flatten() else {
+            return request.send().await;
+        };
+        match this.send().await {
+            Ok(response) if response.status().is_server_error() => tracing::warn!(
+                "[http] {} returned {}, retrying",
+                crate::logging::redact(response.url().as_str()),
+                response.status()
+            ),
+            Err(e) if e.is_connect() => tracing::warn!(
+                "[http] Connection failed, retrying: {}",
+                crate::logging::redact(&e.to_string())
+            ),
+            other => return other,
+        }
+        tokio::time::sleep(retry_delay(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// `http_client` with an explicit proxy, e.g. one that hasn't been saved yet.
+pub fn http_client_with(
+    timeout: Duration,
+    proxy: Option<&ProxyConfig>,
+) -> std::result::Result<reqwest::Client, String> {
+    let tls = HTTP_TLS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    build_http_client(timeout, proxy, tls.as_ref())
+}
+
+fn build_http_client(
+    timeout: Duration,
+    proxy: Option<&ProxyConfig>,
+    tls: Option<&LoadedTls>,
+) -> std::result::Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().timeout(timeout);
+    if let Some(config) = proxy.filter(|c| c.is_set()) {
+        builder = builder.proxy(config.to_proxy()?);
+    }
+    if let Some(tls) = tls {
+        for cert in &tls.roots {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if tls.config.accept_invalid_certs {
+            tracing::warn!(
+                "[http] TLS certificate verification is DISABLED (accept_invalid_certs is on); \
+                 this connection can be intercepted"
+            );
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+/// Longest request timeout the override accepts.
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// TLS and timeout overrides for the app's own HTTP requests, for gateways
+/// behind an internal CA. Set in Settings next to the proxy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM file with extra root certificates, trusted on top of the built-in ones
+    pub ca_bundle_path: String,
+    /// Skip certificate verification altogether. Dangerous: anyone on the
+    /// network path can read the API key.
+    pub accept_invalid_certs: bool,
+    /// Replaces the timeout of API calls (see `api_client`) when set
+    pub timeout_secs: Option<u64>,
+}
+
+impl TlsConfig {
+    /// Read and parse the CA bundle; empty when no path is set.
+    fn load_roots(&self) -> std::result::Result<Vec<reqwest::Certificate>, String> {
+        let path = self.ca_bundle_path.trim();
+        if path.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pem = fs::read(path).map_err(|e| format!("Cannot read CA bundle {path}: {e}"))?;
+        if !String::from_utf8_lossy(&pem).contains("-----BEGIN CERTIFICATE-----") {
+            return Err(format!(
+                "{path} is not a PEM certificate bundle (expected -----BEGIN CERTIFICATE-----)"
+            ));
+        }
+        let roots = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid certificate in {path}: {e}"))?;
+        if roots.is_empty() {
+            return Err(format!("No certificates found in {path}"));
+        }
+        Ok(roots)
+    }
+
+    /// Load the bundle and check the certificates can be trusted.
+    fn load(&self) -> std::result::Result<LoadedTls, String> {
+        if let Some(secs) = self.timeout_secs {
+            if secs == 0 || secs > MAX_TIMEOUT_SECS {
+                return Err(format!(
+                    "Timeout must be between 1 and {MAX_TIMEOUT_SECS} seconds"
+                ));
+            }
+        }
+        let tls = LoadedTls {
+            roots: self.load_roots()?,
+            config: self.clone(),
+        };
+        // rustls only parses the certificates when the client is built
+        let mut builder = reqwest::Client::builder();
+        for cert in &tls.roots {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        builder.build().map_err(|e| {
+            format!(
+                "Cannot use the certificates in {}: {e}",
+                self.ca_bundle_path.trim()
+            )
+        })?;
+        Ok(tls)
+    }
+}
+
+/// A `TlsConfig` with its CA bundle already parsed, so building a client
+/// doesn't read the file each time.
+#[derive(Clone)]
+struct LoadedTls {
+    config: TlsConfig,
+    roots: Vec<reqwest::Certificate>,
+}
+
+static HTTP_TLS: RwLock<Option<LoadedTls>> = RwLock::new(None);
+
+/// Replace the TLS settings used by `http_client`. An invalid config is
+/// rejected and the previous one kept.
+pub fn set_http_tls(config: &TlsConfig) -> std::result::Result<(), String> {
+    let tls = config.load()?;
+    if config.accept_invalid_certs {
+        tracing::warn!(
+            "[http] accept_invalid_certs is ON: TLS certificates will NOT be verified for any request"
+        );
+    }
+    *HTTP_TLS.write().unwrap_or_else(|e| e.into_inner()) =
+        (*config != TlsConfig::default()).then_some(tls);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_and_repair_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("settings.json");
+        assert_eq!(validate_and_repair_json(&path, " \n").unwrap(), json!({}));
+        assert_eq!(
+            validate_and_repair_json(&path, "{\"a\": 1, // note\n}").unwrap(),
+            json!({"a": 1})
+        );
+        assert!(matches!(
+            validate_and_repair_json(&path, "[1, 2]"),
+            Err(SyncError::ConfigCorrupted { .. })
+        ));
+
+        // The newest backup is the broken file itself, so the older one wins
+        fs::write(
+            path.with_file_name("settings.json.20200101_000000.hajimi.bak"),
+            "{\"a\": 2}",
+        )
+        .unwrap();
+        fs::write(
+            path.with_file_name("settings.json.20990101_000000.hajimi.bak"),
+            "{ broken",
+        )
+        .unwrap();
+        assert_eq!(
+            validate_and_repair_json(&path, "{ broken").unwrap(),
+            json!({"a": 2})
+        );
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use std::cmp::Ordering;
+        assert_eq!(compare_versions("3.13.1", "3.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0", "2.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("extension", "0.1.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_major_version() {
+        assert_eq!(major_version("v22.12.0"), Some(22));
+        assert_eq!(major_version("18.19.1\n"), Some(18));
+        assert_eq!(major_version("detected"), None);
+    }
 
     #[test]
     fn test_extract_version_slash_format() {
@@ -489,4 +1633,534 @@ mod tests {
         assert!(validate_url("ftp://example.com").is_err());
         assert!(validate_url("not-a-url").is_err());
     }
+
+    #[test]
+    fn test_to_json_like_is_byte_identical_when_unchanged() {
+        let original =
+            "{\n    \"zeta\": 1,\n    \"env\": {\"A\": \"x\"},\n    \"alpha\": [1, 2]\n}\n";
+        let value: Value = serde_json::from_str(original).unwrap();
+        assert_eq!(to_json_like(&value, original).unwrap(), original);
+    }
+
+    #[test]
+    fn test_to_json_like_reuses_style() {
+        let original = "{\n    \"zeta\": 1,\n    \"alpha\": 2\n}\n";
+        let mut value: Value = serde_json::from_str(original).unwrap();
+        value["env"] = serde_json::json!({ "A": "x" });
+        assert_eq!(
+            to_json_like(&value, original).unwrap(),
+            "{\n    \"zeta\": 1,\n    \"alpha\": 2,\n    \"env\": {\n        \"A\": \"x\"\n    }\n}\n"
+        );
+
+        let tabs = "{\r\n\t\"a\": 1\r\n}";
+        let value = serde_json::json!({ "a": 2 });
+        assert_eq!(to_json_like(&value, tabs).unwrap(), "{\r\n\t\"a\": 2\r\n}");
+
+        assert_eq!(to_json_like(&value, "{\"a\":1}\n").unwrap(), "{\"a\":2}\n");
+        // New files get the old default
+        assert_eq!(to_json_like(&value, "").unwrap(), "{\n  \"a\": 2\n}");
+    }
+
+    #[test]
+    fn test_url_normalization_cases() {
+        let equal = [
+            ("HTTPS://Example.COM:443/v1", "https://example.com/v1"),
+            ("http://example.com:80", "http://example.com/"),
+            (
+                "https://example.com//api///v1/",
+                "https://example.com/api/v1",
+            ),
+            ("  https://example.com/v1/  ", "https://example.com/v1"),
+            ("http://localhost:3000", "http://LOCALHOST:3000/"),
+        ];
+        for (a, b) in equal {
+            assert!(urls_equal(a, b), "{a} != {b}");
+        }
+        let different = [
+            ("http://example.com", "https://example.com"),
+            ("https://example.com:8443", "https://example.com"),
+            ("https://example.com/v1", "https://example.com"),
+            ("https://example.com/API", "https://example.com/api"),
+        ];
+        for (a, b) in different {
+            assert!(!urls_equal(a, b), "{a} == {b}");
+        }
+
+        assert!(urls_match(
+            "https://Example.com:443/",
+            "https://example.com/v1"
+        ));
+        assert!(!urls_match(
+            "https://example.com/v2",
+            "https://example.com/v1"
+        ));
+        assert_eq!(normalize_url("not a url/"), "not a url");
+    }
+
+    #[test]
+    fn test_write_json_skips_identical_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("chatbox.json");
+        let original = "{\n    \"theme\": \"dark\",\n    \"openaiApiKey\": \"sk-1\"\n}\n";
+        fs::write(&path, original).unwrap();
+
+        let mut value: Value = serde_json::from_str(original).unwrap();
+        value["openaiApiKey"] = Value::String("sk-1".to_string());
+        write_json_secure(&path, &value).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+        value["openaiApiKey"] = Value::String("sk-2".to_string());
+        write_json_secure(&path, &value).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            original.replace("sk-1", "sk-2")
+        );
+    }
+
+    #[test]
+    fn test_write_json_edits_jsonc_in_place() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("settings.json");
+        let original = "{\n  // my proxy\n  \"openaiApiHost\": \"https://old.com\",\n  \"theme\": \"dark\",\n}\n";
+        fs::write(&path, original).unwrap();
+
+        let mut value = parse_json_lenient(original).unwrap();
+        value["openaiApiHost"] = Value::String("https://new.com".to_string());
+        write_json_secure(&path, &value).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            original.replace("old.com", "new.com")
+        );
+
+        // Dropping a key would need a rewrite that loses the comment
+        value.as_object_mut().unwrap().remove("theme");
+        let err = write_json_secure(&path, &value).unwrap_err();
+        assert_eq!(err.code(), "WOULD_LOSE_COMMENTS");
+    }
+
+    #[test]
+    fn test_verify_written_config_per_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let toml_path = tmp.path().join("config.toml");
+        fs::write(
+            &toml_path,
+            "[model_providers.custom]\nbase_url = \"https://p.io/v1\"\n",
+        )
+        .unwrap();
+        let never = || -> Result<()> { panic!("no rewrite needed") };
+        verify_written_config(&toml_path, &["https://p.io/v1"], never).unwrap();
+
+        let env_path = tmp.path().join(".env");
+        fs::write(&env_path, "GEMINI_API_KEY=\"sk-1\"\n").unwrap();
+        verify_written_config(&env_path, &["sk-1", ""], never).unwrap();
+
+        // A truncated write is repaired by the retry
+        let json_path = tmp.path().join("auth.json");
+        let content = "{\"OPENAI_API_KEY\": \"sk-1\"}";
+        fs::write(&json_path, &content[..10]).unwrap();
+        verify_written_config(&json_path, &["sk-1"], || atomic_write(&json_path, content)).unwrap();
+        assert_eq!(fs::read_to_string(&json_path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_verify_written_config_restores_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("settings.json");
+        fs::write(&path, "{\"theme\": \"dark\"}").unwrap();
+        create_rotated_backup(&path, BACKUP_SUFFIX).unwrap();
+
+        fs::write(&path, "{\"env\": {").unwrap();
+        let err = verify_written_config(&path, &["sk-1"], || atomic_write(&path, "{\"env\""))
+            .unwrap_err();
+        assert_eq!(err.code(), "CONFIG_CORRUPTED");
+        assert!(err.to_string().contains("settings.json"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"theme\": \"dark\"}");
+
+        // Parses fine but the key never made it
+        let err = verify_written_config(&path, &["sk-1"], || Ok(())).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_legacy_backups_found_and_migrated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("settings.json");
+        let legacy = tmp.path().join("settings.json.antigravity.bak");
+        fs::write(&path, "{}").unwrap();
+        fs::write(&legacy, "old").unwrap();
+        fs::write(
+            tmp.path()
+                .join("settings.json.20260101_000000.antigravity.bak"),
+            "older",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("other.json.antigravity.bak"), "x").unwrap();
+        assert_eq!(find_backup(&path), Some(legacy));
+
+        assert_eq!(migrate_legacy_backups(&path), 2);
+        assert_eq!(find_backup(&path), Some(backup_path(&path)));
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "old");
+        assert!(tmp
+            .path()
+            .join("settings.json.20260101_000000.hajimi.bak")
+            .exists());
+        assert!(tmp.path().join("other.json.antigravity.bak").exists());
+
+        // An existing new-suffix backup wins; the legacy one is left alone
+        fs::write(tmp.path().join("settings.json.antigravity.bak"), "stale").unwrap();
+        assert_eq!(migrate_legacy_backups(&path), 0);
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_env_file_quotes_and_export() {
+        let content = "# gemini\nexport GEMINI_API_KEY=\"old key\"\n  GOOGLE_GEMINI_BASE_URL='https://old.com' # proxy\nOTHER=1\n";
+        let mut env = EnvFile::parse(content);
+        assert_eq!(env.get("GEMINI_API_KEY"), Some("old key"));
+        assert_eq!(env.get("GOOGLE_GEMINI_BASE_URL"), Some("https://old.com"));
+
+        env.set("GEMINI_API_KEY", "sk-new");
+        env.set("GOOGLE_GEMINI_BASE_URL", "https://new.com");
+        env.set("GOOGLE_GEMINI_MODEL", "gemini-2.5-pro");
+        assert_eq!(
+            env.render(),
+            "# gemini\nexport GEMINI_API_KEY=\"sk-new\"\n  GOOGLE_GEMINI_BASE_URL='https://new.com' # proxy\nOTHER=1\nGOOGLE_GEMINI_MODEL=gemini-2.5-pro\n"
+        );
+    }
+
+    #[test]
+    fn test_env_file_duplicates_and_crlf() {
+        let content = "KEY=first\r\n# note\r\nKEY=second\r\nnot an entry\r\n";
+        let mut env = EnvFile::parse(content);
+        assert_eq!(env.get("KEY"), Some("second"));
+
+        env.set("KEY", "third");
+        assert_eq!(
+            env.render(),
+            "KEY=first\r\n# note\r\nKEY=third\r\nnot an entry\r\n"
+        );
+
+        assert_eq!(env.remove("KEY"), 2);
+        assert_eq!(env.render(), "# note\r\nnot an entry\r\n");
+        assert_eq!(EnvFile::parse("").render(), "");
+    }
+
+    #[test]
+    fn test_env_file_escapes_values_that_need_quotes() {
+        let mut env = EnvFile::parse("A=\"x \\\"y\\\"\"\n");
+        assert_eq!(env.get("A"), Some("x \"y\""));
+        env.set("B", "has space");
+        assert_eq!(env.render(), "A=\"x \\\"y\\\"\"\nB=\"has space\"\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_keeps_symlink_and_mode() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dotfiles = tmp.path().join("dotfiles");
+        fs::create_dir(&dotfiles).unwrap();
+        let real = dotfiles.join("settings.json");
+        fs::write(&real, "{}").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o600)).unwrap();
+        let link = tmp.path().join("settings.json");
+        symlink("dotfiles/settings.json", &link).unwrap();
+
+        let target = resolve_write_target(&link, Some(tmp.path())).unwrap();
+        assert_eq!(target, tmp.path().join("dotfiles/settings.json"));
+        write_with_retry(&link, "{\"a\":1}", 1, Some(tmp.path()), false).unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "{\"a\":1}");
+        let mode = fs::metadata(&real).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_secure_write_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        let env = tmp.path().join(".env");
+        fs::write(&env, "KEY=old\n").unwrap();
+        fs::set_permissions(&env, fs::Permissions::from_mode(0o644)).unwrap();
+        // Left behind by an interrupted write
+        let stale = env.with_extension("tmp");
+        fs::write(&stale, "").unwrap();
+        fs::set_permissions(&stale, fs::Permissions::from_mode(0o666)).unwrap();
+        assert!(readable_by_others(&env));
+
+        write_with_retry(&env, "KEY=sk-new\n", 1, Some(tmp.path()), true).unwrap();
+        assert_eq!(fs::read_to_string(&env).unwrap(), "KEY=sk-new\n");
+        assert_eq!(mode(&env), 0o600);
+        assert!(!readable_by_others(&env));
+
+        let fresh = tmp.path().join("auth.json");
+        write_json_secure(&fresh, &json!({"key": "sk-new"})).unwrap();
+        assert_eq!(mode(&fresh), 0o600);
+
+        // A plain write keeps whatever mode the file had
+        let plain = tmp.path().join("config.toml");
+        fs::write(&plain, "").unwrap();
+        fs::set_permissions(&plain, fs::Permissions::from_mode(0o644)).unwrap();
+        write_with_retry(&plain, "model = \"x\"\n", 1, Some(tmp.path()), false).unwrap();
+        assert_eq!(mode(&plain), 0o644);
+        assert!(!readable_by_others(&tmp.path().join("missing")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_outside_home_is_refused() {
+        use std::os::unix::fs::symlink;
+
+        let home = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        let link = home.path().join("config.toml");
+        symlink(elsewhere.path().join("config.toml"), &link).unwrap();
+
+        let err = resolve_write_target(&link, Some(home.path())).unwrap_err();
+        assert_eq!(err.code(), "SYMLINK_OUTSIDE_HOME");
+
+        // Regular files anywhere are fine; only links are checked
+        let plain = elsewhere.path().join("plain.toml");
+        assert_eq!(
+            resolve_write_target(&plain, Some(home.path())).unwrap(),
+            plain
+        );
+    }
+
+    /// Simulates a config write on a slow network home (200 ms per write) and
+    /// counts how often a 10 ms ticker task runs meanwhile on the same
+    /// single-threaded runtime. Inline the ticker is starved (0 ticks); through
+    /// `run_blocking` it keeps running (~20 ticks).
+    #[tokio::test]
+    async fn test_run_blocking_keeps_runtime_responsive() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        fn slow_write(path: PathBuf) {
+            std::thread::sleep(Duration::from_millis(200));
+            fs::write(path, "{}").unwrap();
+        }
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = {
+            let ticks = ticks.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+        tokio::task::yield_now().await;
+        let tmp = tempfile::tempdir().unwrap();
+
+        let start = ticks.load(Ordering::SeqCst);
+        slow_write(tmp.path().join("inline.json"));
+        let inline = ticks.load(Ordering::SeqCst) - start;
+
+        let start = ticks.load(Ordering::SeqCst);
+        let path = tmp.path().join("offloaded.json");
+        run_blocking(move || slow_write(path)).await.unwrap();
+        let offloaded = ticks.load(Ordering::SeqCst) - start;
+        ticker.abort();
+
+        assert_eq!(inline, 0);
+        assert!(offloaded >= 5, "ticker only ran {offloaded} times");
+        assert!(tmp.path().join("offloaded.json").exists());
+    }
+
+    fn cross_device_rename(_: &Path, _: &Path) -> std::io::Result<()> {
+        #[cfg(windows)]
+        return Err(std::io::Error::from_raw_os_error(ERROR_NOT_SAME_DEVICE));
+        #[cfg(not(windows))]
+        Err(std::io::Error::from(std::io::ErrorKind::CrossesDevices))
+    }
+
+    #[test]
+    fn test_cross_device_rename_falls_back_to_copy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("settings.json");
+        fs::write(&target, "{\"old\":true}").unwrap();
+        let tmp_path = target.with_extension("tmp");
+
+        try_atomic_write(
+            &tmp_path,
+            &target,
+            "{\"new\":true}",
+            false,
+            cross_device_rename,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "{\"new\":true}");
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_failed_rename_leaves_no_tmp_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tmp_path = tmp.path().join("settings.tmp");
+
+        // Any other rename error is reported as is
+        let denied: RenameFn = |_, _| Err(std::io::ErrorKind::PermissionDenied.into());
+        let target = tmp.path().join("settings.json");
+        let err = try_atomic_write(&tmp_path, &target, "{}", false, denied).unwrap_err();
+        assert!(matches!(err, SyncError::PermissionDenied { .. }), "{err}");
+        assert!(!tmp_path.exists());
+
+        // The copy fallback fails too when the target's directory is gone
+        let target = tmp.path().join("gone").join("settings.json");
+        try_atomic_write(&tmp_path, &target, "{}", false, cross_device_rename).unwrap_err();
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_proxy_config_validate() {
+        let config = |url: &str, username: &str| ProxyConfig {
+            url: url.to_string(),
+            username: username.to_string(),
+            ..Default::default()
+        };
+        assert!(config("", "").validate().is_ok());
+        assert!(config("http://proxy.local:3128", "me").validate().is_ok());
+        assert!(config("socks5://proxy.local:1080", "").validate().is_err());
+        assert!(config("proxy.local:3128", "").validate().is_err());
+        assert!(config("", "me").validate().is_err());
+        let err = config("http://me:secret@", "").validate().unwrap_err();
+        assert!(!err.contains("secret"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_http_client_with_proxy() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let proxy = MockServer::start().await;
+        // user:pass
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .and(header("proxy-authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+        let config = ProxyConfig {
+            url: proxy.uri(),
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            no_proxy: String::new(),
+        };
+        let client = http_client_with(Duration::from_secs(5), Some(&config)).unwrap();
+        // The host doesn't resolve; only the proxy can answer
+        let response = client
+            .get("http://upstream.invalid/ping")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+
+        // Hosts in no_proxy bypass it
+        let config = ProxyConfig {
+            no_proxy: "upstream.invalid".to_string(),
+            ..config
+        };
+        let client = http_client_with(Duration::from_secs(5), Some(&config)).unwrap();
+        assert!(client
+            .get("http://upstream.invalid/ping")
+            .send()
+            .await
+            .is_err());
+    }
+
+    // Self-signed, valid until 2126
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBiTCCAS+gAwIBAgIUXtRO3XazmArfOMNlRonyKRIP5ugwCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOSGFqaW1pIFRlc3QgQ0EwIBcNMjYxMDE2MTkyNjAzWhgPMjEy
+NjA5MjIxOTI2MDNaMBkxFzAVBgNVBAMMDkhhamltaSBUZXN0IENBMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAETielS5i5bgbpZ+OtjSVJcR1yDHZtolxvTM4XmpUo
+tke32dmWfBQoxZlpcz7ipBc5IM44eCsu475D3JMJbc10j6NTMFEwHQYDVR0OBBYE
+FGkeqzOm4Avfj4Ea006umCO40bA2MB8GA1UdIwQYMBaAFGkeqzOm4Avfj4Ea006u
+mCO40bA2MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhAPPPS2Nt
+89vA2xGo71lnQwuqWkzKfxuDxyM6uVn10cFKAiBW0boRkCHwcVKm4OLzb2CifHyM
+qUOdOs5y9YSZmf4WRA==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_tls_config_validation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ca = tmp.path().join("ca.pem");
+        fs::write(&ca, TEST_CA).unwrap();
+        let config = TlsConfig {
+            ca_bundle_path: ca.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let tls = config.load().unwrap();
+        assert_eq!(tls.roots.len(), 1);
+        assert!(build_http_client(Duration::from_secs(5), None, Some(&tls)).is_ok());
+
+        let not_pem = tmp.path().join("ca.der");
+        fs::write(&not_pem, b"\x30\x82\x01\x89").unwrap();
+        let err = TlsConfig {
+            ca_bundle_path: not_pem.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+        .load()
+        .err()
+        .unwrap();
+        assert!(err.contains("not a PEM"), "{err}");
+
+        let missing = TlsConfig {
+            ca_bundle_path: tmp.path().join("nope.pem").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        assert!(missing.load().is_err());
+
+        for secs in [0, MAX_TIMEOUT_SECS + 1] {
+            let config = TlsConfig {
+                timeout_secs: Some(secs),
+                ..Default::default()
+            };
+            assert!(config.load().is_err());
+        }
+        assert!(TlsConfig::default().load().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tls_timeout_override() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&server)
+            .await;
+        let tls = TlsConfig {
+            timeout_secs: Some(1),
+            ..Default::default()
+        }
+        .load()
+        .unwrap();
+        let api = build_http_client(
+            api_timeout(Duration::from_secs(30), Some(&tls)),
+            None,
+            Some(&tls),
+        )
+        .unwrap();
+        let err = api.get(server.uri()).send().await.unwrap_err();
+        assert!(err.is_timeout());
+
+        // A download's long timeout isn't cut short by the override
+        let download = build_http_client(Duration::from_secs(600), None, Some(&tls)).unwrap();
+        let response = download.get(server.uri()).send().await.unwrap();
+        assert!(response.status().is_success());
+    }
 }
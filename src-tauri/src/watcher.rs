@@ -0,0 +1,214 @@
+//! Watch the config files we manage and push fresh status to the UI.
+//!
+//! Parent directories are watched rather than the files themselves, so files
+//! that don't exist yet are seen when created and atomic renames aren't lost.
+//! A config directory that doesn't exist yet is covered by watching its
+//! nearest existing ancestor and re-arming once it appears. Events are
+//! debounced; each affected app's status is recomputed against the current
+//! provider and emitted as `EVENT_STATUS_CHANGED`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::database::dao::providers;
+use crate::drift;
+use crate::store::AppState;
+
+pub const EVENT_STATUS_CHANGED: &str = "status://changed";
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Stops watching when dropped.
+pub struct ConfigWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+/// Watched file → apps that write it (several apps can share one file).
+type Targets = HashMap<PathBuf, Vec<&'static str>>;
+
+/// Canonical parent + file name, so event paths (which may come back
+/// canonicalized, e.g. /private/var on macOS) compare equal to ours.
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .unwrap_or_else(|_| parent.to_path_buf())
+            .join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn targets() -> Targets {
+    let mut map = Targets::new();
+    for app in crate::status_apps() {
+        for path in drift::managed_files(app) {
+            map.entry(normalize(&path)).or_default().push(app);
+        }
+    }
+    map
+}
+
+/// Nearest existing directory above `path`.
+fn watch_dir(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|p| p.is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Apps touched by events at `paths`, and whether one of the `missing`
+/// config dirs was just created (so the watch set must be rebuilt).
+fn affected_apps(
+    targets: &Targets,
+    missing: &[PathBuf],
+    paths: &[PathBuf],
+) -> (BTreeSet<&'static str>, bool) {
+    let mut apps = BTreeSet::new();
+    let mut rewatch = false;
+    for path in paths {
+        let path = normalize(path);
+        if let Some(owners) = targets.get(&path) {
+            apps.extend(owners.iter().copied());
+        }
+        if path.is_dir() && missing.iter().any(|dir| dir.starts_with(&path)) {
+            rewatch = true;
+        }
+    }
+    (apps, rewatch)
+}
+
+pub fn start<R: Runtime>(app: &AppHandle<R>) -> Result<ConfigWatcher, String> {
+    let targets = targets();
+    let mut missing: Vec<PathBuf> = targets
+        .keys()
+        .filter_map(|p| p.parent())
+        .filter(|dir| !dir.is_dir())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.sort();
+    missing.dedup();
+    let dirs: BTreeSet<PathBuf> = targets.keys().filter_map(|p| watch_dir(p)).collect();
+
+    let handle = app.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, move |res: DebounceEventResult| {
+        let events = match res {
+            Ok(events) => events,
+            Err(e) => {
+                tracing::warn!("[watcher] Watch error: {}", e);
+                return;
+            }
+        };
+        let paths: Vec<PathBuf> = events.into_iter().map(|e| e.path).collect();
+        let (apps, rewatch) = affected_apps(&targets, &missing, &paths);
+        if !apps.is_empty() {
+            emit_status(&handle, &apps);
+        }
+        if rewatch {
+            // Restarting drops this debouncer, so not from its own callback thread.
+            let handle = handle.clone();
+            std::thread::spawn(move || restart(&handle));
+        }
+    })
+    .map_err(|e| format!("Failed to create file watcher: {e}"))?;
+
+    for dir in &dirs {
+        if let Err(e) = debouncer.watcher().watch(dir, RecursiveMode::NonRecursive) {
+            tracing::warn!("[watcher] Cannot watch {:?}: {}", dir, e);
+        }
+    }
+    tracing::info!("[watcher] Watching {} config directories", dirs.len());
+    Ok(ConfigWatcher {
+        _debouncer: debouncer,
+    })
+}
+
+/// Recompute and emit the status of `apps` against the current provider.
+fn emit_status<R: Runtime>(app: &AppHandle<R>, apps: &BTreeSet<&'static str>) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let Ok(Some(current)) = providers::get_current(&state.db) else {
+        return;
+    };
+    for name in apps {
+        let status = crate::app_status(
+            &state.db,
+            Some(state.status_cache.as_ref()),
+            name,
+            Some(&current.url),
+            Some(&current.opencode_adapter),
+        );
+        if let Some(status) = status {
+            if let Err(e) = app.emit(EVENT_STATUS_CHANGED, status) {
+                tracing::warn!("[watcher] Failed to emit status for {}: {}", name, e);
+            }
+        }
+    }
+}
+
+/// (Re)build the watcher kept in `AppState`, e.g. after config path overrides change.
+pub fn restart<R: Runtime>(app: &AppHandle<R>) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let mut slot = state.watcher.lock().unwrap_or_else(|p| p.into_inner());
+    slot.take();
+    match start(app) {
+        Ok(watcher) => *slot = Some(watcher),
+        Err(e) => tracing::warn!("[watcher] {}", e),
+    }
+}
+
+/// Tear the watcher down on exit.
+pub fn stop<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(state) = app.try_state::<AppState>() {
+        state
+            .watcher
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affected_apps_and_rewatch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let settings = normalize(&tmp.path().join("settings.json"));
+        let codex_dir = tmp.path().join(".codex");
+        let mut targets = Targets::new();
+        targets.insert(settings.clone(), vec!["claude", "claude-vscode"]);
+        targets.insert(normalize(&codex_dir.join("config.toml")), vec!["codex"]);
+        let missing = vec![normalize(&codex_dir)];
+
+        let (apps, rewatch) = affected_apps(
+            &targets,
+            &missing,
+            &[settings.clone(), tmp.path().join(".zsh_history")],
+        );
+        assert_eq!(
+            apps.into_iter().collect::<Vec<_>>(),
+            ["claude", "claude-vscode"]
+        );
+        assert!(!rewatch);
+
+        std::fs::create_dir(&codex_dir).unwrap();
+        let (apps, rewatch) = affected_apps(&targets, &missing, std::slice::from_ref(&codex_dir));
+        assert!(apps.is_empty());
+        assert!(rewatch);
+    }
+
+    #[test]
+    fn test_watch_dir_skips_missing_parents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("a").join("b").join("config.toml");
+        assert_eq!(watch_dir(&file).as_deref(), Some(tmp.path()));
+    }
+}
@@ -0,0 +1,396 @@
+//! Syncing the CLI apps inside a WSL distro from the Windows host.
+//!
+//! With the `target_wsl_distro` setting, Claude Code, Codex, Gemini CLI,
+//! opencode, OpenClaw and Droid resolve their home through
+//! `\\wsl$\<distro>\home\<user>` instead of the Windows profile (see
+//! `paths::cli_home_dir`), so sync, restore and status all use the same
+//! translated paths. PATH lookups can't see Linux binaries, so those apps are
+//! detected by probing the usual install locations in the distro instead.
+//! Desktop clients are unaffected.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::database::{dao::settings, Database};
+use crate::error::CommandError;
+use crate::store::AppState;
+use crate::utils;
+
+const TARGET_DISTRO_KEY: &str = "target_wsl_distro";
+/// Linux home of the distro's default user, looked up when the distro was
+/// picked so startup doesn't have to boot it.
+const TARGET_HOME_KEY: &str = "target_wsl_home";
+
+/// Directories, relative to the Linux home, that user-level installs put
+/// binaries in.
+const HOME_BIN_DIRS: &[&str] = &[
+    ".local/bin",
+    ".npm-global/bin",
+    ".bun/bin",
+    ".volta/bin",
+    ".opencode/bin",
+];
+const SYSTEM_BIN_DIRS: &[&str] = &["/usr/local/bin", "/usr/bin"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslTarget {
+    pub distro: String,
+    /// e.g. `/home/me`
+    pub linux_home: String,
+}
+
+impl WslTarget {
+    /// `linux_path` as seen from Windows: `\\wsl$\<distro>\...`.
+    pub fn unc_path(&self, linux_path: &str) -> PathBuf {
+        let mut path = format!(r"\\wsl$\{}", self.distro);
+        for part in linux_path.split('/').filter(|p| !p.is_empty()) {
+            path.push('\\');
+            path.push_str(part);
+        }
+        PathBuf::from(path)
+    }
+
+    pub fn home(&self) -> PathBuf {
+        self.unc_path(&self.linux_home)
+    }
+
+    /// Linux path of `path` if it lies in this distro.
+    fn linux_path(&self, path: &Path) -> Option<String> {
+        let path = path.to_string_lossy();
+        let root = format!(r"\\wsl$\{}", self.distro);
+        let rest = path.strip_prefix(&root)?;
+        if !(rest.is_empty() || rest.starts_with('\\')) {
+            return None;
+        }
+        let linux = rest.replace('\\', "/");
+        Some(if linux.is_empty() {
+            "/".to_string()
+        } else {
+            linux
+        })
+    }
+
+    /// Candidate locations of `name` in the distro, most specific first.
+    fn executable_candidates(&self, name: &str) -> Vec<String> {
+        let home = self.linux_home.trim_end_matches('/');
+        let mut dirs: Vec<String> = HOME_BIN_DIRS
+            .iter()
+            .map(|dir| format!("{home}/{dir}"))
+            .collect();
+        // nvm keeps one bin dir per installed Node version
+        let nvm = format!("{home}/.nvm/versions/node");
+        if let Ok(entries) = std::fs::read_dir(self.unc_path(&nvm)) {
+            let mut versions: Vec<String> = entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect();
+            versions.sort();
+            versions.reverse();
+            dirs.extend(versions.iter().map(|v| format!("{nvm}/{v}/bin")));
+        }
+        dirs.extend(SYSTEM_BIN_DIRS.iter().map(|d| d.to_string()));
+        dirs.into_iter()
+            .map(|dir| format!("{dir}/{name}"))
+            .collect()
+    }
+}
+
+static TARGET: RwLock<Option<WslTarget>> = RwLock::new(None);
+
+/// The distro the CLI apps are synced into, if any.
+pub fn target() -> Option<WslTarget> {
+    TARGET.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set_cached(target: Option<WslTarget>) {
+    *TARGET.write().unwrap_or_else(|e| e.into_inner()) = target;
+}
+
+/// Load the saved target (called once at startup).
+pub fn load(db: &Database) {
+    let distro = settings::get(db, TARGET_DISTRO_KEY).ok().flatten();
+    let Some(distro) = distro.filter(|d| !d.is_empty()) else {
+        return;
+    };
+    let linux_home = match settings::get(db, TARGET_HOME_KEY).ok().flatten() {
+        Some(home) if home.starts_with('/') => home,
+        _ => match query_home(&distro) {
+            Ok(home) => {
+                let _ = settings::set(db, TARGET_HOME_KEY, &home);
+                home
+            }
+            Err(e) => {
+                tracing::warn!("[wsl] Target distro {} not usable: {}", distro, e);
+                return;
+            }
+        },
+    };
+    tracing::info!("[wsl] Syncing CLI apps into WSL distro {}", distro);
+    set_cached(Some(WslTarget { distro, linux_home }));
+}
+
+/// Distro names as `wsl.exe -l` prints them: no separators, quotes or
+/// control characters.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn valid_distro_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_control() || matches!(c, '\\' | '/' | '"' | '\'' | ':'))
+}
+
+/// `wsl.exe -l` writes UTF-16LE; fall back to UTF-8 if it doesn't look like it.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn decode_wsl_output(bytes: &[u8]) -> String {
+    let looks_utf16 = bytes.len() >= 2 && bytes.len().is_multiple_of(2) && bytes.contains(&0);
+    if !looks_utf16 {
+        return String::from_utf8_lossy(bytes).to_string();
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_start_matches('\u{feff}')
+        .to_string()
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_distro_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.trim().trim_matches('\0').trim())
+        .filter(|line| valid_distro_name(line))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_command() -> std::process::Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut cmd = std::process::Command::new("wsl.exe");
+    cmd.creation_flags(utils::CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Installed distros, from `wsl.exe -l -q`.
+#[cfg(target_os = "windows")]
+fn list_distros() -> Result<Vec<String>, String> {
+    let output = wsl_command()
+        .args(["-l", "-q"])
+        .output()
+        .map_err(|e| format!("Cannot run wsl.exe (is WSL installed?): {e}"))?;
+    if !output.status.success() {
+        let message = decode_wsl_output(&output.stdout);
+        return Err(format!(
+            "wsl.exe -l failed: {}",
+            message
+                .trim()
+                .lines()
+                .next()
+                .unwrap_or("no distros installed")
+        ));
+    }
+    Ok(parse_distro_list(&decode_wsl_output(&output.stdout)))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn list_distros() -> Result<Vec<String>, String> {
+    Ok(Vec::new())
+}
+
+/// `$HOME` of the distro's default user.
+#[cfg(target_os = "windows")]
+fn query_home(distro: &str) -> Result<String, String> {
+    let output = wsl_command()
+        .args(["-d", distro, "--exec", "sh", "-c", "printf %s \"$HOME\""])
+        .output()
+        .map_err(|e| format!("Cannot run wsl.exe: {e}"))?;
+    let home = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || !home.starts_with('/') {
+        return Err(format!("Cannot read $HOME in {distro}"));
+    }
+    Ok(home)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn query_home(_distro: &str) -> Result<String, String> {
+    Err("WSL is only available on Windows".to_string())
+}
+
+/// Pick the distro to sync into, or go back to the Windows profile with `None`.
+fn set_target(db: &Database, distro: Option<&str>) -> Result<Option<WslTarget>, CommandError> {
+    let Some(distro) = distro.map(str::trim).filter(|d| !d.is_empty()) else {
+        settings::set(db, TARGET_DISTRO_KEY, "")?;
+        settings::set(db, TARGET_HOME_KEY, "")?;
+        set_cached(None);
+        tracing::info!("[wsl] Syncing CLI apps into the Windows profile again");
+        return Ok(None);
+    };
+    if !cfg!(target_os = "windows") {
+        return Err(CommandError::unsupported_operation(
+            "This system",
+            "WSL targets",
+        ));
+    }
+    if !list_distros()?.iter().any(|d| d == distro) {
+        return Err(CommandError::invalid_input(format!(
+            "WSL distro not found: {distro}"
+        )));
+    }
+    let target = WslTarget {
+        distro: distro.to_string(),
+        linux_home: query_home(distro)?,
+    };
+    if !target.home().is_dir() {
+        return Err(CommandError::new(
+            "WSL_HOME_UNREACHABLE",
+            format!("Cannot open {} from Windows", target.home().display()),
+        ));
+    }
+    settings::set(db, TARGET_DISTRO_KEY, &target.distro)?;
+    settings::set(db, TARGET_HOME_KEY, &target.linux_home)?;
+    tracing::info!(
+        "[wsl] Syncing CLI apps into {} ({})",
+        target.distro,
+        target.home().display()
+    );
+    set_cached(Some(target.clone()));
+    Ok(Some(target))
+}
+
+/// `name` on PATH, or in the target distro when one is set. The returned path
+/// is a `\\wsl$` path in the latter case.
+pub fn resolve_cli(name: &str) -> Option<PathBuf> {
+    let Some(target) = target() else {
+        return utils::resolve_executable(name);
+    };
+    target
+        .executable_candidates(name)
+        .iter()
+        .map(|linux| target.unc_path(linux))
+        .find(|path| path.is_file())
+}
+
+/// `path --version` for a path from `resolve_cli`. Binaries in the distro run
+/// through `wsl.exe`, with their own directory on PATH so nvm's `node` is found.
+pub fn cli_version(path: &PathBuf) -> Option<String> {
+    let linux = target().and_then(|t| t.linux_path(path).map(|linux| (t, linux)));
+    let Some((target, linux)) = linux else {
+        return utils::get_cli_version(path);
+    };
+    version_in_distro(&target, &linux)
+}
+
+#[cfg(target_os = "windows")]
+fn version_in_distro(target: &WslTarget, linux_path: &str) -> Option<String> {
+    let script = r#"PATH="$(dirname "$1"):$PATH" "$1" --version"#;
+    let output = wsl_command()
+        .args(["-d", &target.distro, "--exec", "sh", "-lc", script, "sh"])
+        .arg(linux_path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        tracing::debug!(
+            "[wsl] {} --version failed in {}: {}",
+            linux_path,
+            target.distro,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    Some(utils::extract_version(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn version_in_distro(_target: &WslTarget, _linux_path: &str) -> Option<String> {
+    None
+}
+
+#[tauri::command]
+pub async fn list_wsl_distros() -> Result<Vec<String>, CommandError> {
+    Ok(utils::run_blocking(list_distros).await??)
+}
+
+#[tauri::command]
+pub async fn get_wsl_target() -> Result<Option<WslTarget>, CommandError> {
+    Ok(target())
+}
+
+/// Waits for a running sync so no app is half written to each filesystem.
+#[tauri::command]
+pub async fn set_wsl_target(
+    handle: AppHandle,
+    state: State<'_, AppState>,
+    distro: Option<String>,
+) -> Result<Option<WslTarget>, CommandError> {
+    let _lock = state.sync_lock.acquire().await?;
+    let db = std::sync::Arc::clone(&state.db);
+    let target = utils::run_blocking(move || set_target(&db, distro.as_deref())).await??;
+    crate::watcher::restart(&handle);
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ubuntu() -> WslTarget {
+        WslTarget {
+            distro: "Ubuntu-22.04".to_string(),
+            linux_home: "/home/me".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_unc_paths_round_trip() {
+        let target = ubuntu();
+        let home = target.home();
+        assert_eq!(home.to_string_lossy(), r"\\wsl$\Ubuntu-22.04\home\me");
+        let bin = target.unc_path("/home/me/.local/bin/claude");
+        assert_eq!(
+            target.linux_path(&bin).as_deref(),
+            Some("/home/me/.local/bin/claude")
+        );
+        assert_eq!(
+            target
+                .linux_path(Path::new(r"\\wsl$\Ubuntu-22.04"))
+                .as_deref(),
+            Some("/")
+        );
+        // Another distro whose name starts the same isn't ours
+        assert_eq!(
+            target.linux_path(Path::new(r"\\wsl$\Ubuntu-22.04-dev\x")),
+            None
+        );
+        assert_eq!(target.linux_path(Path::new("/usr/bin/claude")), None);
+    }
+
+    #[test]
+    fn test_executable_candidates() {
+        let candidates = ubuntu().executable_candidates("claude");
+        assert_eq!(candidates[0], "/home/me/.local/bin/claude");
+        assert_eq!(candidates.last().unwrap(), "/usr/bin/claude");
+    }
+
+    #[test]
+    fn test_distro_list_parsing() {
+        let utf16: Vec<u8> = "\u{feff}Ubuntu\r\nDebian\r\n\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let output = decode_wsl_output(&utf16);
+        assert_eq!(parse_distro_list(&output), ["Ubuntu", "Debian"]);
+        assert_eq!(
+            parse_distro_list(&decode_wsl_output(b"Ubuntu\nbad/name\n")),
+            ["Ubuntu"]
+        );
+    }
+}
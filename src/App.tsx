@@ -2,23 +2,30 @@ import { useState, useEffect, useCallback, useMemo } from "react";
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
 import { getVersion } from "@tauri-apps/api/app";
+import { listen } from "@tauri-apps/api/event";
+import { confirm, save } from "@tauri-apps/plugin-dialog";
 import { toast } from "sonner";
 import { Toaster } from "sonner";
 import { Check, ExternalLink, Sun, Moon, RefreshCw } from "lucide-react";
 import { SettingsPanel } from "./components/SettingsPanel";
+import { RestoreDiff } from "./components/RestoreDiff";
 import { CliCard } from "./components/CliCard";
 import { ConfigViewer } from "./components/ConfigViewer";
+import { ProjectSyncDialog } from "./components/ProjectSyncDialog";
 import { ProviderPanel } from "./components/ProviderPanel";
 import { useCliSync, getSyncLog } from "./hooks/useCliSync";
 import type { SyncLogEntry } from "./hooks/useCliSync";
 import { useModels } from "./hooks/useModels";
 import { listProviders, saveProvider, switchProvider } from "./hooks/useProviders";
-import { CLI_LIST } from "./types";
-import type { CliInfo, CliStatusResult, ProviderRecord } from "./types";
+import { CLI_LIST, OPENCODE_SMALL_MODEL_KEY } from "./types";
+import type { AppUpdate, CliInfo, CliStatusResult, HealthSnapshot, PendingLink, ProviderRecord, RotationEvent, StartupDiagnostics } from "./types";
 import type { CliCategory } from "./types";
+import { errorMessage } from "./errors";
 
 const DEFAULT_URL = "https://vip.aipro.love";
 const DEFAULT_MODEL = "claude-sonnet-4-6";
+/** Extensions that can import a provider profile written by save_extension_profile */
+const PROFILE_EXPORT_CLIENTS = ["cline", "roo-code", "kilo-code"];
 
 function generateId(): string {
   return `${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
@@ -52,6 +59,45 @@ function App() {
     getVersion().then(setAppVersion).catch(() => {});
   }, []);
 
+  // Newer release on GitHub (cached for a day; off when the user opted out)
+  useEffect(() => {
+    invoke<AppUpdate>("check_app_update")
+      .then((update) => {
+        if (!update.update_available) return;
+        const notesUrl = update.release_notes_url;
+        toast.info(t("toast.updateAvailable", { version: update.latest, current: update.current }), {
+          duration: 10000,
+          action: notesUrl
+            ? { label: t("toast.viewRelease"), onClick: () => invoke("open_external_url", { url: notesUrl }) }
+            : undefined,
+        });
+      })
+      .catch((e) => console.warn("Update check failed:", e));
+  }, [t]);
+
+  // Database problems found at startup (corrupted file replaced, or running in memory)
+  useEffect(() => {
+    invoke<StartupDiagnostics>("get_startup_diagnostics")
+      .then((diagnostics) => {
+        const recovery = diagnostics.db_recovery;
+        if (recovery) {
+          toast.warning(t("toast.dbRecovered", { rows: recovery.salvaged_rows, path: recovery.corrupt_path }), {
+            description: recovery.damaged_tables.length
+              ? t("toast.dbDamagedTables", { tables: recovery.damaged_tables.join(", ") })
+              : undefined,
+            duration: Infinity,
+          });
+        }
+        if (diagnostics.db_error) {
+          toast.error(t("toast.dbInMemory", { path: diagnostics.db_path }), {
+            description: diagnostics.db_error,
+            duration: Infinity,
+          });
+        }
+      })
+      .catch((e) => console.warn("Startup diagnostics failed:", e));
+  }, [t]);
+
   // ── Provider state ──────────────────────────────────────────────────────────
   const [providers, setProviders] = useState<ProviderRecord[]>([]);
   const [isSwitching, setIsSwitching] = useState(false);
@@ -71,6 +117,27 @@ function App() {
   // Derived: the currently-active provider (DB truth).
   const currentProvider = providers.find((p) => p.is_current) ?? null;
 
+  // Background health of the current provider; null when polling is off or nothing is current
+  const [health, setHealth] = useState<HealthSnapshot | null>(null);
+  useEffect(() => {
+    invoke<HealthSnapshot | null>("get_provider_health_snapshot").then(setHealth).catch(() => {});
+    const unlisten = listen<HealthSnapshot | null>("health://changed", (event) => setHealth(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Scheduled rotation switched providers in the background
+  useEffect(() => {
+    const unlisten = listen<RotationEvent>("rotation://switched", (event) => {
+      reloadProviders();
+      toast.info(t("rotation.switched", { name: event.payload.to_name }));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [reloadProviders, t]);
+
   const [url, setUrl] = useState(() => localStorage.getItem("hajimi-url") || DEFAULT_URL);
   const [saveApiKey, setSaveApiKey] = useState(() => localStorage.getItem("hajimi-save-key") !== "false");
   const [apiKey, setApiKey] = useState(() =>
@@ -139,10 +206,59 @@ function App() {
   // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
+  // hajimi://add-provider links: the backend only parses and queues them, we
+  // confirm and save. Drain on mount too, for the link that launched the app.
+  useEffect(() => {
+    let draining = false;
+    let again = false;
+    const drain = async () => {
+      if (draining) {
+        again = true;
+        return;
+      }
+      draining = true;
+      try {
+        do {
+          again = false;
+          const pending = await invoke<PendingLink[]>("take_pending_deep_links");
+          for (const item of pending) {
+            if (item.kind === "error") {
+              toast.error(t("provider.deepLinkInvalid", { error: item.value }));
+              continue;
+            }
+            const link = item.value;
+            const ok = await confirm(t("provider.deepLinkConfirm", { name: link.name, url: link.url }));
+            if (!ok) continue;
+            try {
+              await invoke<ProviderRecord>("confirm_deep_link_provider", { link });
+              await reloadProviders();
+              toast.success(t("provider.deepLinkAdded", { name: link.name }));
+            } catch (e) {
+              toast.error(errorMessage(e));
+            }
+          }
+        } while (again);
+      } catch (e) {
+        toast.error(errorMessage(e));
+      } finally {
+        draining = false;
+      }
+    };
+    const unlisten = listen("deep-link-received", () => {
+      drain();
+    });
+    drain();
+    return () => {
+      unlisten.then((f) => f());
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   const [configViewer, setConfigViewer] = useState<{
     cli: CliInfo;
     status: CliStatusResult;
   } | null>(null);
+  const [projectSyncOpen, setProjectSyncOpen] = useState(false);
 
   const {
     statuses,
@@ -150,9 +266,11 @@ function App() {
     syncing,
     restoring,
     detectAll,
+    refreshIfChanged,
     syncOne,
     syncAll,
     restoreOne,
+    setPinned,
     getConfigContent,
   } = useCliSync();
 
@@ -213,7 +331,7 @@ function App() {
         await switchProvider(providerId);
         await reloadProviders();
       } catch (e) {
-        toast.error(String(e), { duration: 5000 });
+        toast.error(errorMessage(e), { duration: 5000 });
       }
     },
     [providers, defaultModel, reloadProviders]
@@ -227,6 +345,15 @@ function App() {
     return () => clearTimeout(timer);
   }, [url, detectAll]);
 
+  // Configs may have been edited in another app meanwhile; unchanged status costs one hash compare
+  useEffect(() => {
+    const onFocus = () => {
+      refreshIfChanged(url);
+    };
+    window.addEventListener("focus", onFocus);
+    return () => window.removeEventListener("focus", onFocus);
+  }, [url, refreshIfChanged]);
+
   // Fetch models when URL and API key are both available (debounced)
   useEffect(() => {
     if (!url.trim() || !apiKey.trim()) return;
@@ -238,6 +365,41 @@ function App() {
 
   const getModelForCli = (appId: string) => perCliModels[appId] || defaultModel;
 
+  const exportExtensionProfile = async (cli: CliInfo) => {
+    if (!apiKey.trim()) {
+      toast.error(t("toast.apiKeyRequired"));
+      return;
+    }
+    const destPath = await save({
+      defaultPath: `${cli.id}-hajimi-profile.json`,
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (!destPath) return;
+    try {
+      await invoke("save_extension_profile", {
+        client: cli.id,
+        destPath,
+        url,
+        apiKey,
+        model: getModelForCli(cli.id),
+      });
+      toast.success(t("toast.profileSaved", { name: cli.name, path: destPath }), { duration: 8000 });
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
+  // Self-hosted LobeChat reads its provider from the server environment
+  const copyLobechatEnv = async () => {
+    try {
+      const lines = await invoke<string>("generate_lobechat_env", { providerId: null });
+      await navigator.clipboard.writeText(lines);
+      toast.success(t("toast.lobechatEnvCopied"));
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
   const [confirmRestoreSingle, setConfirmRestoreSingle] = useState<{
     app: string;
     name: string;
@@ -273,6 +435,11 @@ function App() {
   }, [url, configViewer, confirmRestoreSingle, detectAll, toggleLang]);
 
   const hasInstalled = statuses.some((s) => s.installed);
+  const confirmRestoreFiles = useMemo(
+    () =>
+      statuses.find((s) => s.app === confirmRestoreSingle?.app)?.file_status.map((f) => f.name) ?? [],
+    [statuses, confirmRestoreSingle]
+  );
 
   const [activeTab, setActiveTab] = useState<CliCategory>(() =>
     (localStorage.getItem("hajimi-tab") as CliCategory) || "coding"
@@ -352,6 +519,10 @@ function App() {
         onModelChange={(m) =>
           setPerCliModels((prev) => ({ ...prev, [cli.id]: m }))
         }
+        smallModel={cli.id === "opencode" ? perCliModels[OPENCODE_SMALL_MODEL_KEY] ?? "" : undefined}
+        onSmallModelChange={(m) =>
+          setPerCliModels((prev) => ({ ...prev, [OPENCODE_SMALL_MODEL_KEY]: m }))
+        }
         apiModels={apiModels}
         modelsLoading={modelsLoading}
         onSync={() => {
@@ -376,7 +547,15 @@ function App() {
             toast.info(t("toast.syncSuccess", { name: cli.name }));
             return;
           }
-          syncOne(cli.id, url, apiKey, getModelForCli(cli.id), cli.name);
+          syncOne(
+            cli.id,
+            url,
+            apiKey,
+            getModelForCli(cli.id),
+            cli.name,
+            cli.id === "opencode" ? perCliModels[OPENCODE_SMALL_MODEL_KEY] : null,
+            currentProvider?.opencode_adapter
+          );
         }}
         onRestore={() => {
           if (status) {
@@ -392,11 +571,15 @@ function App() {
           invoke("open_external_url", { url: cli.downloadUrl });
         } : undefined}
         onLaunch={cli.launchName ? () => {
-          invoke("launch_app", { name: cli.launchName });
+          invoke("launch_app", { app: cli.id }).catch((e) => toast.error(errorMessage(e)));
         } : undefined}
         onCommunity={cli.communityUrl ? () => {
           invoke("open_external_url", { url: cli.communityUrl });
         } : undefined}
+        onTogglePin={() => setPinned(cli.id, !status?.pinned)}
+        onProjectSync={cli.id === "claude" ? () => setProjectSyncOpen(true) : undefined}
+        onExportProfile={PROFILE_EXPORT_CLIENTS.includes(cli.id) ? () => exportExtensionProfile(cli) : undefined}
+        onCopyServerEnv={cli.id === "lobechat" ? copyLobechatEnv : undefined}
       />
     );
   };
@@ -431,6 +614,18 @@ function App() {
               <div className="min-w-0 flex-1">
                 <h1 className="text-base font-bold leading-tight tracking-tight truncate">
                   {t("app.title")}
+                  {health && health.provider_id === currentProvider?.id && (
+                    <span
+                      className={`inline-block w-2 h-2 rounded-full ml-1.5 align-middle ${
+                        health.status === "healthy" ? "bg-success" : health.status === "slow" ? "bg-warning" : "bg-error"
+                      }`}
+                      title={
+                        health.ok
+                          ? t("app.healthOk", { ms: health.latency_ms })
+                          : t("app.healthDown", { error: health.error })
+                      }
+                    />
+                  )}
                   <span className="text-[10px] font-normal opacity-30 ml-1">{appVersion && `v${appVersion}`}</span>
                 </h1>
                 <p className="text-[11px] opacity-50 leading-tight truncate">{t("app.subtitle")}</p>
@@ -519,6 +714,8 @@ function App() {
                 saveApiKey={saveApiKey}
                 onSaveApiKeyChange={setSaveApiKey}
                 onAccountConfigReady={handleAccountConfigReady}
+                onBundleImported={reloadProviders}
+                onSyncTargetChanged={() => detectAll(url)}
               />
             </div>
           </div>
@@ -558,7 +755,7 @@ function App() {
                 }`}
                 onClick={() => {
                   if (!apiKey) { toast.error(t("toast.apiKeyRequired")); return; }
-                  syncAll(url, apiKey, defaultModel, perCliModels);
+                  syncAll(url, apiKey, defaultModel, perCliModels, currentProvider?.opencode_adapter);
                 }}
                 disabled={loading || Object.values(syncing).some(Boolean) || isSwitching || !url.trim() || !apiKey.trim()}
                 title={t("settings.syncAll")}
@@ -675,6 +872,7 @@ function App() {
         <ConfigViewer
           name={configViewer.cli.name}
           files={configViewer.status.files}
+          fileStatus={configViewer.status.file_status}
           getContent={(fileName) =>
             getConfigContent(configViewer.cli.id, fileName)
           }
@@ -683,14 +881,24 @@ function App() {
         />
       )}
 
+      {projectSyncOpen && (
+        <ProjectSyncDialog
+          url={url}
+          apiKey={apiKey}
+          model={getModelForCli("claude")}
+          onClose={() => setProjectSyncOpen(false)}
+        />
+      )}
+
       {/* Restore Single Confirmation */}
       {confirmRestoreSingle && (
         <div className="modal modal-open">
-          <div className="modal-box max-w-sm">
+          <div className="modal-box max-w-lg">
             <h3 className="font-bold text-lg">{t("confirm.restoreTitle")}</h3>
             <p className="py-4 text-sm">
               {t("confirm.restoreSingleMessage", { name: confirmRestoreSingle.name })}
             </p>
+            <RestoreDiff app={confirmRestoreSingle.app} files={confirmRestoreFiles} />
             <div className="modal-action">
               <button
                 className="btn btn-sm"
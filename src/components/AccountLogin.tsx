@@ -1,11 +1,13 @@
 import { useState, useEffect, useMemo } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTranslation } from "react-i18next";
-import { LogIn, RefreshCw, LogOut, ExternalLink, Check, AlertCircle, AlertTriangle } from "lucide-react";
+import { LogIn, UserPlus, RefreshCw, LogOut, Check, AlertCircle, AlertTriangle, Mail, Plus } from "lucide-react";
 import { toast } from "sonner";
-import { useAccount } from "../hooks/useAccount";
+import { useAccount, formatQuota } from "../hooks/useAccount";
+import { createProviderFromToken } from "../hooks/useProviders";
+import { errorMessage } from "../errors";
 import { ModelSelector } from "./ModelSelector";
-import type { ApiTokenInfo } from "../types";
+import type { ApiTokenInfo, UsageLogEntry } from "../types";
 
 interface AccountLoginProps {
   onConfigReady: (url: string, apiKey: string, tokenName: string) => void;
@@ -16,6 +18,16 @@ interface AccountLoginProps {
   modelsError: string | null;
 }
 
+const USAGE_PAGE_SIZE = 10;
+
+function hostOf(url: string): string {
+  try {
+    return new URL(url).host;
+  } catch {
+    return url;
+  }
+}
+
 /** Map Rust error codes to i18n keys */
 function mapErrorCode(code: string): string {
   const map: Record<string, string> = {
@@ -24,12 +36,23 @@ function mapErrorCode(code: string): string {
     WRONG_CREDENTIALS: "account.errWrongCredentials",
     ACCOUNT_DISABLED: "account.errAccountDisabled",
     REQUIRE_2FA: "account.errRequire2FA",
+    WRONG_TOTP: "account.errWrongTotp",
     LOGIN_FAILED: "account.errLoginFailed",
     NO_SESSION_COOKIE: "account.errNoSessionCookie",
     SESSION_EXPIRED: "account.sessionExpired",
     INVALID_RESPONSE: "account.errInvalidResponse",
     INTERNAL_ERROR: "account.errInternalError",
     NOT_LOGGED_IN: "account.sessionExpired",
+    USERNAME_TAKEN: "account.errUsernameTaken",
+    EMAIL_TAKEN: "account.errEmailTaken",
+    EMAIL_NOT_ALLOWED: "account.errEmailNotAllowed",
+    EMAIL_CODE_REQUIRED: "account.errEmailCodeRequired",
+    WRONG_EMAIL_CODE: "account.errWrongEmailCode",
+    REGISTER_DISABLED: "account.errRegisterDisabled",
+    INVALID_INPUT: "account.errInvalidInput",
+    REGISTER_FAILED: "account.errRegisterFailed",
+    ACCOUNT_NOT_FOUND: "account.sessionExpired",
+    TOKEN_NOT_FOUND: "account.errTokenNotFound",
   };
   return map[code] || "";
 }
@@ -52,8 +75,16 @@ export function AccountLogin({
     platformError,
     checkPlatform,
     login,
+    register,
+    sendEmailCode,
     fetchTokens,
+    selfQuota,
+    fetchSelfQuota,
+    fetchUsageLogs,
     checkSession,
+    accounts,
+    listAccounts,
+    switchAccount,
     logout,
     setError,
   } = useAccount();
@@ -63,9 +94,19 @@ export function AccountLogin({
   );
   const [username, setUsername] = useState("");
   const [password, setPassword] = useState("");
+  const [mode, setMode] = useState<"login" | "register">("login");
+  const [totpCode, setTotpCode] = useState("");
+  const [savingTokenId, setSavingTokenId] = useState<number | null>(null);
+  const [usageLogs, setUsageLogs] = useState<UsageLogEntry[] | null>(null);
+  const [usageLoading, setUsageLoading] = useState(false);
+  const needsTotp = error === "REQUIRE_2FA" || error === "WRONG_TOTP";
+  const [email, setEmail] = useState("");
+  const [emailCode, setEmailCode] = useState("");
+  const [sendingCode, setSendingCode] = useState(false);
   const [selectedTokenId, setSelectedTokenId] = useState<number | null>(null);
   const [sessionChecked, setSessionChecked] = useState(false);
   const [configApplied, setConfigApplied] = useState(false);
+  const [addingAccount, setAddingAccount] = useState(false);
 
   // Check platform info when URL changes (debounced)
   useEffect(() => {
@@ -79,10 +120,11 @@ export function AccountLogin({
   // On mount: try restoring session
   useEffect(() => {
     const restore = async () => {
+      await listAccounts();
       const info = await checkSession();
       setSessionChecked(true);
       if (info) {
-        await fetchTokens();
+        await refreshAccount();
       }
     };
     restore();
@@ -91,24 +133,91 @@ export function AccountLogin({
 
   const handleLogin = async () => {
     if (!username.trim() || !password.trim()) return;
+    if (needsTotp && !totpCode.trim()) return;
     setError(null);
-    const info = await login(platformUrl, username.trim(), password.trim());
+    const info = await login(
+      platformUrl,
+      username.trim(),
+      password.trim(),
+      needsTotp ? totpCode.trim() : undefined
+    );
     if (info) {
       toast.success(t("account.loginSuccess", { name: info.display_name }));
       setPassword("");
-      await fetchTokens();
+      setTotpCode("");
+      setAddingAccount(false);
+      setSelectedTokenId(null);
+      setConfigApplied(false);
+      await refreshAccount();
     }
   };
 
   const handleLogout = async () => {
-    await logout();
+    const remaining = await logout();
     setSelectedTokenId(null);
     setConfigApplied(false);
     toast.success(t("account.logoutSuccess"));
+    const next = remaining.find((a) => a.active);
+    if (next) {
+      // Fall back to the next saved account instead of the login form
+      await activateAccount(next.base_url);
+      return;
+    }
     // Re-check platform so the system name badge stays consistent
     checkPlatform(platformUrl);
   };
 
+  /** Load the now-active account that lives on `baseUrl`. */
+  const activateAccount = async (baseUrl: string) => {
+    setPlatformUrl(baseUrl);
+    checkPlatform(baseUrl);
+    const info = await checkSession();
+    if (info) await refreshAccount();
+  };
+
+  const handleSwitchAccount = async (id: number) => {
+    const target = accounts.find((a) => a.id === id);
+    if (!target || target.active) return;
+    try {
+      await switchAccount(id);
+      setSelectedTokenId(null);
+      setConfigApplied(false);
+      await activateAccount(target.base_url);
+    } catch (e) {
+      toast.error(renderError(errorMessage(e)));
+    }
+  };
+
+  const refreshAccount = async () => {
+    setUsageLogs(null);
+    await Promise.all([fetchTokens(), fetchSelfQuota()]);
+  };
+
+  const loadUsageLogs = async () => {
+    if (usageLogs || usageLoading) return;
+    setUsageLoading(true);
+    try {
+      const page = await fetchUsageLogs(1, USAGE_PAGE_SIZE);
+      setUsageLogs(page.items);
+    } catch (e) {
+      toast.error(renderError(errorMessage(e)));
+    } finally {
+      setUsageLoading(false);
+    }
+  };
+
+  const handleSaveAsProvider = async (token: ApiTokenInfo) => {
+    setSavingTokenId(token.id);
+    try {
+      const record = await createProviderFromToken(token.id, undefined, false, accountInfo?.account_id);
+      toast.success(t("account.providerCreated", { name: record.name }));
+    } catch (e) {
+      toast.error(renderError(errorMessage(e)));
+    } finally {
+      setSavingTokenId(null);
+    }
+  };
+
   const handleSelectToken = (token: ApiTokenInfo) => {
     setSelectedTokenId(token.id);
     setConfigApplied(true);
@@ -117,15 +226,39 @@ export function AccountLogin({
     toast.success(t("account.tokenSelected", { name: tokenName }));
   };
 
-  const handleRegister = () => {
-    const registerUrl = `${platformUrl.replace(/\/$/, "")}/register`;
-    invoke("open_external_url", { url: registerUrl });
+  const needsEmail = platformInfo?.email_verification ?? false;
+
+  const handleRegister = async () => {
+    if (!username.trim() || !password.trim()) return;
+    if (needsEmail && (!email.trim() || !emailCode.trim())) return;
+    setError(null);
+    const info = await register(
+      platformUrl,
+      username.trim(),
+      password,
+      email.trim(),
+      emailCode.trim() || null
+    );
+    if (info) {
+      toast.success(t("account.registerSuccess", { name: info.display_name }));
+      setPassword("");
+      setEmailCode("");
+      setMode("login");
+      await refreshAccount();
+    }
+  };
+
+  const handleSendCode = async () => {
+    if (!email.trim()) return;
+    setSendingCode(true);
+    const sent = await sendEmailCode(platformUrl, email.trim());
+    setSendingCode(false);
+    if (sent) toast.success(t("account.codeSent", { email: email.trim() }));
   };
 
-  const formatQuota = (quota: number): string => {
-    if (quota >= 1_000_000) return `${(quota / 1_000_000).toFixed(1)}M`;
-    if (quota >= 1_000) return `${(quota / 1_000).toFixed(1)}K`;
-    return String(quota);
+  const switchMode = (next: "login" | "register") => {
+    setMode(next);
+    setError(null);
   };
 
   const formatExpiry = (ts: number): string => {
@@ -176,8 +309,8 @@ export function AccountLogin({
     return key ? t(key) : code;
   };
 
-  // ── Not logged in: show login form ──
-  if (!accountInfo) {
+  // ── Not logged in (or adding another account): show login form ──
+  if (!accountInfo || addingAccount) {
     if (!sessionChecked) {
       return (
         <div className="flex items-center justify-center py-6">
@@ -213,7 +346,7 @@ export function AccountLogin({
           )}
         </div>
 
-        {/* Login form */}
+        {/* Login / register form */}
         <div className="form-control">
           <input
             type="text"
@@ -221,7 +354,7 @@ export function AccountLogin({
             value={username}
             onChange={(e) => setUsername(e.target.value)}
             placeholder={t("account.username")}
-            onKeyDown={(e) => e.key === "Enter" && handleLogin()}
+            onKeyDown={(e) => e.key === "Enter" && mode === "login" && handleLogin()}
           />
         </div>
         <div className="form-control">
@@ -231,10 +364,64 @@ export function AccountLogin({
             value={password}
             onChange={(e) => setPassword(e.target.value)}
             placeholder={t("account.password")}
-            onKeyDown={(e) => e.key === "Enter" && handleLogin()}
+            onKeyDown={(e) => e.key === "Enter" && mode === "login" && handleLogin()}
           />
         </div>
 
+        {mode === "login" && needsTotp && (
+          <div className="form-control">
+            <input
+              type="text"
+              inputMode="numeric"
+              autoComplete="one-time-code"
+              className="input input-bordered input-sm w-full"
+              value={totpCode}
+              onChange={(e) => setTotpCode(e.target.value)}
+              placeholder={t("account.totpCode")}
+              onKeyDown={(e) => e.key === "Enter" && handleLogin()}
+              autoFocus
+            />
+          </div>
+        )}
+
+        {mode === "register" && needsEmail && (
+          <>
+            <div className="form-control">
+              <div className="flex items-center gap-2">
+                <input
+                  type="email"
+                  className="input input-bordered input-sm w-full"
+                  value={email}
+                  onChange={(e) => setEmail(e.target.value)}
+                  placeholder={t("account.email")}
+                />
+                <button
+                  className="btn btn-ghost btn-sm gap-1 shrink-0"
+                  onClick={handleSendCode}
+                  disabled={sendingCode || !email.trim()}
+                >
+                  {sendingCode ? (
+                    <span className="loading loading-spinner loading-xs" />
+                  ) : (
+                    <Mail className="w-3.5 h-3.5" />
+                  )}
+                  {t("account.sendCode")}
+                </button>
+              </div>
+            </div>
+            <div className="form-control">
+              <input
+                type="text"
+                className="input input-bordered input-sm w-full"
+                value={emailCode}
+                onChange={(e) => setEmailCode(e.target.value)}
+                placeholder={t("account.emailCode")}
+                onKeyDown={(e) => e.key === "Enter" && handleRegister()}
+              />
+            </div>
+          </>
+        )}
+
         {/* Issue #3: Login error with i18n mapping */}
         {error && (
           <div className="flex items-center gap-1.5 text-error text-xs">
@@ -243,29 +430,70 @@ export function AccountLogin({
           </div>
         )}
 
-        {/* Login button */}
-        <button
-          className="btn btn-primary btn-sm w-full gap-1.5"
-          onClick={handleLogin}
-          disabled={loading || !username.trim() || !password.trim()}
-        >
-          {loading ? (
-            <span className="loading loading-spinner loading-xs" />
-          ) : (
-            <LogIn className="w-3.5 h-3.5" />
-          )}
-          {loading ? t("account.loggingIn") : t("account.login")}
-        </button>
+        {mode === "login" ? (
+          <button
+            className="btn btn-primary btn-sm w-full gap-1.5"
+            onClick={handleLogin}
+            disabled={loading || !username.trim() || !password.trim()}
+          >
+            {loading ? (
+              <span className="loading loading-spinner loading-xs" />
+            ) : (
+              <LogIn className="w-3.5 h-3.5" />
+            )}
+            {loading ? t("account.loggingIn") : t("account.login")}
+          </button>
+        ) : (
+          <button
+            className="btn btn-primary btn-sm w-full gap-1.5"
+            onClick={handleRegister}
+            disabled={
+              loading ||
+              !username.trim() ||
+              !password.trim() ||
+              (needsEmail && (!email.trim() || !emailCode.trim()))
+            }
+          >
+            {loading ? (
+              <span className="loading loading-spinner loading-xs" />
+            ) : (
+              <UserPlus className="w-3.5 h-3.5" />
+            )}
+            {loading ? t("account.registering") : t("account.register")}
+          </button>
+        )}
 
-        {/* Issue #8: Only show register link if platform allows registration */}
-        {(platformInfo?.register_enabled ?? true) && (
+        {/* Issue #8: Only offer registration if the platform allows it */}
+        {mode === "login" && (platformInfo?.register_enabled ?? true) && (
           <div className="text-center">
             <button
               className="btn btn-ghost btn-xs gap-1 opacity-60"
-              onClick={handleRegister}
+              onClick={() => switchMode("register")}
             >
               {t("account.noAccount")}
-              <ExternalLink className="w-3 h-3" />
+            </button>
+          </div>
+        )}
+        {mode === "register" && (
+          <div className="text-center">
+            <button
+              className="btn btn-ghost btn-xs gap-1 opacity-60"
+              onClick={() => switchMode("login")}
+            >
+              {t("account.haveAccount")}
+            </button>
+          </div>
+        )}
+        {addingAccount && (
+          <div className="text-center">
+            <button
+              className="btn btn-ghost btn-xs gap-1 opacity-60"
+              onClick={() => {
+                setAddingAccount(false);
+                setError(null);
+              }}
+            >
+              {t("config.cancel")}
             </button>
           </div>
         )}
@@ -282,7 +510,21 @@ export function AccountLogin({
           <div className="w-6 h-6 rounded-full bg-primary/20 flex items-center justify-center text-primary text-xs font-bold">
             {accountInfo.display_name.charAt(0).toUpperCase()}
           </div>
-          <span className="text-sm font-medium">{accountInfo.display_name}</span>
+          {accounts.length > 1 ? (
+            <select
+              className="select select-ghost select-xs text-sm font-medium max-w-[12rem]"
+              value={accountInfo.account_id}
+              onChange={(e) => handleSwitchAccount(Number(e.target.value))}
+            >
+              {accounts.map((a) => (
+                <option key={a.id} value={a.id}>
+                  {a.username} @ {hostOf(a.base_url)}
+                </option>
+              ))}
+            </select>
+          ) : (
+            <span className="text-sm font-medium">{accountInfo.display_name}</span>
+          )}
           {platformInfo && (
             <span className="badge badge-ghost badge-xs">{platformInfo.system_name}</span>
           )}
@@ -290,7 +532,20 @@ export function AccountLogin({
         <div className="flex items-center gap-1">
           <button
             className="btn btn-ghost btn-xs gap-1"
-            onClick={fetchTokens}
+            onClick={() => {
+              setAddingAccount(true);
+              setMode("login");
+              setUsername("");
+              setPassword("");
+              setError(null);
+            }}
+            title={t("account.addAccount")}
+          >
+            <UserPlus className="w-3 h-3" />
+          </button>
+          <button
+            className="btn btn-ghost btn-xs gap-1"
+            onClick={refreshAccount}
             disabled={loading}
           >
             <RefreshCw className={`w-3 h-3 ${loading ? "animate-spin" : ""}`} />
@@ -304,6 +559,50 @@ export function AccountLogin({
         </div>
       </div>
 
+      {/* Account balance */}
+      {selfQuota && (
+        <div className="grid grid-cols-3 gap-2 text-center">
+          <div className="rounded-lg bg-base-200 p-2">
+            <div className="text-xs opacity-50">{t("account.balance")}</div>
+            <div className="text-sm font-medium">{formatQuota(selfQuota.quota)}</div>
+          </div>
+          <div className="rounded-lg bg-base-200 p-2">
+            <div className="text-xs opacity-50">{t("account.used")}</div>
+            <div className="text-sm font-medium">{formatQuota(selfQuota.used_quota)}</div>
+          </div>
+          <div className="rounded-lg bg-base-200 p-2">
+            <div className="text-xs opacity-50">{t("account.requests")}</div>
+            <div className="text-sm font-medium">{selfQuota.request_count}</div>
+          </div>
+        </div>
+      )}
+
+      {/* Recent usage, loaded on first expand */}
+      {selfQuota && (
+        <details
+          className="collapse collapse-arrow bg-base-200 rounded-lg"
+          onToggle={(e) => (e.currentTarget as HTMLDetailsElement).open && loadUsageLogs()}
+        >
+          <summary className="collapse-title text-xs min-h-0 py-2">{t("account.recentUsage")}</summary>
+          <div className="collapse-content text-xs space-y-1">
+            {usageLoading && <span className="loading loading-spinner loading-xs" />}
+            {usageLogs?.length === 0 && <div className="opacity-50">{t("account.noUsage")}</div>}
+            {usageLogs?.map((log, i) => (
+              <div key={`${log.created_at}-${i}`} className="flex items-center justify-between gap-2">
+                <span className="truncate font-mono">{log.model || "—"}</span>
+                <span className="opacity-60 shrink-0">
+                  {t("account.usageTokens", { count: log.prompt_tokens + log.completion_tokens })}
+                  {" · "}
+                  {formatQuota(log.quota)}
+                  {" · "}
+                  {new Date(log.created_at * 1000).toLocaleTimeString()}
+                </span>
+              </div>
+            ))}
+          </div>
+        </details>
+      )}
+
       {/* Token list */}
       {tokens.length === 0 && !loading && (
         <div className="text-center text-xs opacity-50 py-4 space-y-1">
@@ -387,6 +686,24 @@ export function AccountLogin({
                   <span className="text-xs opacity-40">
                     {t("account.expires")}: {formatExpiry(token.expired_time)}
                   </span>
+                  {usable && (
+                    <button
+                      className="btn btn-ghost btn-xs gap-1"
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        handleSaveAsProvider(token);
+                      }}
+                      disabled={savingTokenId === token.id}
+                      title={t("account.saveAsProvider")}
+                    >
+                      {savingTokenId === token.id ? (
+                        <span className="loading loading-spinner loading-xs" />
+                      ) : (
+                        <Plus className="w-3 h-3" />
+                      )}
+                      {t("account.saveAsProvider")}
+                    </button>
+                  )}
                 </div>
               </div>
             </div>
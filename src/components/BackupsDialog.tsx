@@ -0,0 +1,123 @@
+import { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Eye, Trash2 } from "lucide-react";
+import type { BackupFile, PruneReport } from "../types";
+import { errorMessage } from "../errors";
+
+interface BackupsDialogProps {
+  onClose: () => void;
+}
+
+const formatSize = (bytes: number) =>
+  bytes < 1024 ? `${bytes} B` : `${(bytes / 1024).toFixed(1)} KB`;
+
+/** Every `.hajimi.bak` file next to the synced configs, with preview and prune. */
+export function BackupsDialog({ onClose }: BackupsDialogProps) {
+  const { t } = useTranslation();
+  const [backups, setBackups] = useState<BackupFile[] | null>(null);
+  const [preview, setPreview] = useState<{ path: string; content: string } | null>(null);
+  const [days, setDays] = useState(30);
+  const [busy, setBusy] = useState(false);
+
+  const refresh = useCallback(async () => {
+    try {
+      setBackups(await invoke<BackupFile[]>("list_all_backups"));
+    } catch (e) {
+      toast.error(errorMessage(e));
+      setBackups([]);
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const showPreview = async (path: string) => {
+    try {
+      setPreview({ path, content: await invoke<string>("preview_backup", { path }) });
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
+  const prune = async () => {
+    setBusy(true);
+    try {
+      const report = await invoke<PruneReport>("prune_backups", { olderThanDays: days });
+      toast.success(t("backups.pruned", { count: report.removed, size: formatSize(report.bytes_freed) }));
+      if (report.errors.length > 0) toast.warning(report.errors.join("\n"));
+      setPreview(null);
+      await refresh();
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const totalSize = backups?.reduce((sum, b) => sum + b.size_bytes, 0) ?? 0;
+
+  return (
+    <dialog className="modal modal-open" onClose={onClose}>
+      <div className="modal-box max-w-2xl">
+        <h3 className="font-bold text-lg mb-1">{t("backups.title")}</h3>
+        <p className="opacity-60 text-xs mb-3">
+          {backups ? t("backups.summary", { count: backups.length, size: formatSize(totalSize) }) : t("backups.loading")}
+        </p>
+
+        <div className="max-h-64 overflow-y-auto text-xs">
+          {backups?.length === 0 && <p className="opacity-50">{t("backups.empty")}</p>}
+          <ul className="space-y-1">
+            {backups?.map((b) => (
+              <li key={b.path} className="flex items-center gap-2" title={b.path}>
+                <span className="badge badge-ghost badge-xs shrink-0">{b.app}</span>
+                <span className="font-mono truncate flex-1">{b.file}</span>
+                {!b.rotated && <span className="badge badge-outline badge-xs">{t("backups.restorePoint")}</span>}
+                <span className="opacity-50 shrink-0">{new Date(b.timestamp * 1000).toLocaleString()}</span>
+                <span className="opacity-50 shrink-0 w-16 text-right">{formatSize(b.size_bytes)}</span>
+                <button className="btn btn-ghost btn-xs btn-square" onClick={() => showPreview(b.path)}>
+                  <Eye className="w-3 h-3" />
+                </button>
+              </li>
+            ))}
+          </ul>
+        </div>
+
+        {preview && (
+          <div className="mt-3">
+            <div className="font-mono text-[10px] opacity-50 truncate mb-1">{preview.path}</div>
+            <pre className="bg-base-200 rounded p-2 text-[11px] max-h-48 overflow-auto whitespace-pre-wrap break-all">
+              {preview.content}
+            </pre>
+          </div>
+        )}
+
+        <div className="modal-action items-center">
+          <span className="text-xs opacity-60 mr-auto flex items-center gap-1.5">
+            {t("backups.pruneOlderThan")}
+            <input
+              type="number"
+              min={0}
+              className="input input-bordered input-xs w-16"
+              value={days}
+              onChange={(e) => setDays(Math.max(0, Number(e.target.value) || 0))}
+            />
+            {t("backups.days")}
+          </span>
+          <button className="btn btn-sm btn-ghost gap-1" onClick={prune} disabled={busy || !backups?.some((b) => b.rotated)}>
+            {busy ? <span className="loading loading-spinner loading-xs" /> : <Trash2 className="w-3.5 h-3.5" />}
+            {t("backups.prune")}
+          </button>
+          <button className="btn btn-sm" onClick={onClose}>
+            {t("backups.close")}
+          </button>
+        </div>
+      </div>
+      <form method="dialog" className="modal-backdrop">
+        <button onClick={onClose}>close</button>
+      </form>
+    </dialog>
+  );
+}
@@ -0,0 +1,156 @@
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { save, open } from "@tauri-apps/plugin-dialog";
+import { toast } from "sonner";
+import { Download, Upload, AlertTriangle } from "lucide-react";
+import type { BundleImportResult, BundleManifest } from "../types";
+import { errorMessage, isCommandError } from "../errors";
+
+interface BundleDialogProps {
+  mode: "export" | "import";
+  onClose: () => void;
+  /** Called after providers were restored from a bundle */
+  onImported?: () => void;
+}
+
+export function BundleDialog({ mode, onClose, onImported }: BundleDialogProps) {
+  const { t } = useTranslation();
+  const [includeKeys, setIncludeKeys] = useState(false);
+  const [applyConfigs, setApplyConfigs] = useState(false);
+  const [passphrase, setPassphrase] = useState("");
+  const [busy, setBusy] = useState(false);
+
+  const handleExport = async () => {
+    const destPath = await save({
+      defaultPath: includeKeys && !passphrase ? "hajimi-bundle-WITH-KEYS.zip" : "hajimi-bundle.zip",
+      filters: [{ name: "Zip", extensions: ["zip"] }],
+    });
+    if (!destPath) return;
+    setBusy(true);
+    try {
+      const manifest = await invoke<BundleManifest>("export_bundle", {
+        destPath,
+        includeKeys,
+        passphrase: includeKeys && passphrase ? passphrase : null,
+      });
+      toast.success(t("bundle.exported", { count: manifest.apps.length }));
+      onClose();
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleImport = async () => {
+    const srcPath = await open({
+      filters: [{ name: "Zip", extensions: ["zip"] }],
+      multiple: false,
+    });
+    if (!srcPath) return;
+    setBusy(true);
+    try {
+      const result = await invoke<BundleImportResult>("import_bundle", {
+        srcPath,
+        applyConfigs,
+        passphrase: passphrase || null,
+      });
+      toast.success(
+        t("bundle.imported", {
+          providers: result.providers_imported,
+          configs: result.configs_restored.length,
+        })
+      );
+      if (result.providers_missing_keys > 0) {
+        toast.warning(t("bundle.missingKeys", { count: result.providers_missing_keys }));
+      }
+      if (result.errors.length > 0) {
+        toast.warning(t("bundle.skipped", { items: result.errors.join("\n") }));
+      }
+      onImported?.();
+      onClose();
+    } catch (e) {
+      const code = isCommandError(e) ? e.code : null;
+      toast.error(code === "PASSPHRASE_REQUIRED" ? t("bundle.passphraseRequired") : errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <dialog className="modal modal-open" onClose={onClose}>
+      <div className="modal-box max-w-md">
+        <h3 className="font-bold text-lg mb-3">
+          {mode === "export" ? t("bundle.exportTitle") : t("bundle.importTitle")}
+        </h3>
+
+        <div className="space-y-3 text-sm">
+          {mode === "export" ? (
+            <>
+              <p className="opacity-60 text-xs">{t("bundle.exportHint")}</p>
+              <label className="label cursor-pointer justify-start gap-2">
+                <input
+                  type="checkbox"
+                  className="checkbox checkbox-sm"
+                  checked={includeKeys}
+                  onChange={(e) => setIncludeKeys(e.target.checked)}
+                />
+                <span className="label-text">{t("bundle.includeKeys")}</span>
+              </label>
+              {includeKeys && (
+                <div className="alert alert-warning py-2 text-xs">
+                  <AlertTriangle className="w-4 h-4 shrink-0" />
+                  <span>{t("bundle.keysWarning")}</span>
+                </div>
+              )}
+            </>
+          ) : (
+            <label className="label cursor-pointer justify-start gap-2">
+              <input
+                type="checkbox"
+                className="checkbox checkbox-sm"
+                checked={applyConfigs}
+                onChange={(e) => setApplyConfigs(e.target.checked)}
+              />
+              <span className="label-text">{t("bundle.applyConfigs")}</span>
+            </label>
+          )}
+
+          {(mode === "import" || includeKeys) && (
+            <input
+              type="password"
+              className="input input-bordered input-sm w-full"
+              placeholder={mode === "export" ? t("bundle.passphraseOptional") : t("bundle.passphrase")}
+              value={passphrase}
+              onChange={(e) => setPassphrase(e.target.value)}
+            />
+          )}
+        </div>
+
+        <div className="modal-action">
+          <button className="btn btn-sm btn-ghost" onClick={onClose} disabled={busy}>
+            {t("confirm.cancel")}
+          </button>
+          <button
+            className="btn btn-sm btn-primary gap-1"
+            onClick={mode === "export" ? handleExport : handleImport}
+            disabled={busy}
+          >
+            {busy ? (
+              <span className="loading loading-spinner loading-xs" />
+            ) : mode === "export" ? (
+              <Download className="w-3.5 h-3.5" />
+            ) : (
+              <Upload className="w-3.5 h-3.5" />
+            )}
+            {mode === "export" ? t("bundle.export") : t("bundle.import")}
+          </button>
+        </div>
+      </div>
+      <form method="dialog" className="modal-backdrop">
+        <button onClick={onClose}>close</button>
+      </form>
+    </dialog>
+  );
+}
@@ -2,7 +2,8 @@ import { useTranslation } from "react-i18next";
 import {
   Terminal, Code, Sparkles, FileCode, Bot, MousePointer,
   MessageSquare, Cherry, Cpu, FileText, Rabbit, Ruler,
-  Beer, Brain, Zap, Waves, Check, CircleDot, Info, ExternalLink,
+  Beer, Brain, Zap, Waves, Check, CircleDot, Info, ExternalLink, PenLine,
+  Lock, LockOpen, FolderGit2, Download, Server,
   type LucideIcon,
 } from "lucide-react";
 import type { CliInfo, CliStatusResult } from "../types";
@@ -42,6 +43,9 @@ interface CliCardProps {
   isSwitching?: boolean;
   model: string;
   onModelChange: (model: string) => void;
+  /** opencode only: its `small_model`; the selector is hidden when undefined */
+  smallModel?: string;
+  onSmallModelChange?: (model: string) => void;
   apiModels: string[];
   modelsLoading: boolean;
   onSync: () => void;
@@ -50,6 +54,13 @@ interface CliCardProps {
   onOpenDownload?: () => void;
   onLaunch?: () => void;
   onCommunity?: () => void;
+  onTogglePin?: () => void;
+  /** Claude only: sync a repo's .claude/settings.local.json */
+  onProjectSync?: () => void;
+  /** Cline / Roo Code / Kilo Code: save an importable provider profile */
+  onExportProfile?: () => void;
+  /** LobeChat: copy the env lines for a self-hosted server */
+  onCopyServerEnv?: () => void;
 }
 
 export function CliCard({
@@ -61,6 +72,8 @@ export function CliCard({
   isSwitching = false,
   model,
   onModelChange,
+  smallModel,
+  onSmallModelChange,
   apiModels,
   modelsLoading,
   onSync,
@@ -69,6 +82,10 @@ export function CliCard({
   onOpenDownload,
   onLaunch,
   onCommunity,
+  onTogglePin,
+  onProjectSync,
+  onExportProfile,
+  onCopyServerEnv,
 }: CliCardProps) {
   const { t } = useTranslation();
 
@@ -77,6 +94,9 @@ export function CliCard({
   const isSynced = status?.is_synced ?? false;
   const hasBackup = status?.has_backup ?? false;
   const syncedCount = status?.synced_count;
+  const edited = status?.externally_modified ?? false;
+  const pinned = status?.pinned ?? false;
+  const providerName = status?.synced_provider_name;
 
   const busy = syncing || restoring || isSwitching;
 
@@ -122,15 +142,36 @@ export function CliCard({
           </div>
 
           {/* Status badge */}
-          <div className="shrink-0">
+          <div className="shrink-0 flex items-center gap-1">
+            {installed && !loading && pinned && (
+              <span
+                className="badge badge-neutral badge-xs gap-0.5 whitespace-nowrap"
+                title={t("cli.pinnedHint")}
+              >
+                <Lock className="w-2.5 h-2.5" />
+                {t("cli.pinned")}
+              </span>
+            )}
+            {installed && !loading && edited && (
+              <span
+                className="badge badge-info badge-xs gap-0.5 whitespace-nowrap"
+                title={t("cli.editedHint")}
+              >
+                <PenLine className="w-2.5 h-2.5" />
+                {t("cli.edited")}
+              </span>
+            )}
             {loading ? (
               <span className="loading loading-dots loading-xs opacity-40" />
             ) : !installed ? (
               <span className="badge badge-ghost badge-xs whitespace-nowrap">{t("cli.notDetected")}</span>
             ) : isSynced ? (
-              <span className="badge badge-success badge-xs gap-0.5 whitespace-nowrap">
-                <Check className="w-2.5 h-2.5" />
-                {t("cli.synced")}
+              <span
+                className="badge badge-success badge-xs gap-0.5 whitespace-nowrap max-w-[9rem]"
+                title={providerName ? t("cli.syncedTo", { name: providerName }) : undefined}
+              >
+                <Check className="w-2.5 h-2.5 shrink-0" />
+                <span className="truncate">{providerName ?? t("cli.synced")}</span>
               </span>
             ) : (
               <span className="badge badge-warning badge-xs whitespace-nowrap">{t("cli.notSynced")}</span>
@@ -142,6 +183,15 @@ export function CliCard({
         {!installed && !loading && (
           <div className="flex items-center gap-2 mt-0.5">
             <span className="text-[10px] opacity-40 flex-1">{t("cli.notDetectedHint")}</span>
+            {onCopyServerEnv && (
+              <button
+                className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
+                onClick={onCopyServerEnv}
+                title={t("cli.serverEnvHint")}
+              >
+                <Server className="w-3 h-3" />
+              </button>
+            )}
             {onOpenDownload && (
               <button
                 className="btn btn-ghost btn-xs opacity-50 hover:opacity-100 transition-opacity shrink-0"
@@ -169,6 +219,18 @@ export function CliCard({
                 />
               </div>
             )}
+            {smallModel !== undefined && onSmallModelChange && (
+              <div className="flex items-center gap-2" title={t("cli.smallModelHint")}>
+                <span className="text-[10px] opacity-50 shrink-0">{t("cli.smallModel")}</span>
+                <ModelSelector
+                  value={smallModel}
+                  onChange={onSmallModelChange}
+                  apiModels={apiModels}
+                  modelsLoading={modelsLoading}
+                  size="xs"
+                />
+              </div>
+            )}
 
             {/* Synced models count */}
             {syncedCount != null && syncedCount > 0 && (
@@ -214,10 +276,49 @@ export function CliCard({
                     <ExternalLink className="w-3 h-3" />
                   </button>
                 )}
+                {onCopyServerEnv && (
+                  <button
+                    className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
+                    onClick={onCopyServerEnv}
+                    title={t("cli.serverEnvHint")}
+                  >
+                    <Server className="w-3 h-3" />
+                  </button>
+                )}
+                {onProjectSync && (
+                  <button
+                    className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
+                    onClick={onProjectSync}
+                    disabled={busy}
+                    title={t("project.title")}
+                  >
+                    <FolderGit2 className="w-3 h-3" />
+                  </button>
+                )}
+                {onTogglePin && (
+                  <button
+                    className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
+                    onClick={onTogglePin}
+                    disabled={busy}
+                    title={pinned ? t("cli.unpin") : t("cli.pin")}
+                  >
+                    {pinned ? <LockOpen className="w-3 h-3" /> : <Lock className="w-3 h-3" />}
+                  </button>
+                )}
               </div>
             ) : (
               <div className="flex items-center gap-1 mt-0.5">
                 <span className="text-[10px] opacity-35 flex-1">{t("install.manualConfigHint")}</span>
+                {onExportProfile && (
+                  <button
+                    className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
+                    onClick={onExportProfile}
+                    title={t("cli.exportProfileHint")}
+                  >
+                    <Download className="w-3 h-3" />
+                    {t("cli.exportProfile")}
+                  </button>
+                )}
                 {onLaunch && cli.launchName && (
                   <button
                     className="btn btn-ghost btn-xs opacity-70 hover:opacity-100"
@@ -4,7 +4,9 @@ import { save, confirm } from "@tauri-apps/plugin-dialog";
 import { writeTextFile } from "@tauri-apps/plugin-fs";
 import { invoke } from "@tauri-apps/api/core";
 import { toast } from "sonner";
-import { Copy, Download, Pencil, Save, X, FolderOpen, Check } from "lucide-react";
+import { errorMessage } from "../errors";
+import type { ConfigValidation, ConfigValidationError, FileStatus } from "../types";
+import { Copy, Download, Pencil, Save, X, FolderOpen, Check, ShieldCheck } from "lucide-react";
 import hljs from "highlight.js/lib/core";
 import json from "highlight.js/lib/languages/json";
 import ini from "highlight.js/lib/languages/ini";
@@ -19,6 +21,7 @@ hljs.registerLanguage("toml", ini);
 interface ConfigViewerProps {
   name: string;
   files: string[];
+  fileStatus?: FileStatus[];
   getContent: (fileName?: string) => Promise<string>;
   onClose: () => void;
   cliId: string; // Add CLI ID for writing config
@@ -27,6 +30,7 @@ interface ConfigViewerProps {
 export function ConfigViewer({
   name,
   files,
+  fileStatus = [],
   getContent,
   onClose,
   cliId,
@@ -41,6 +45,10 @@ export function ConfigViewer({
   const [saving, setSaving] = useState(false);
   const [editing, setEditing] = useState(false);
   const [validationError, setValidationError] = useState("");
+  const [validating, setValidating] = useState(false);
+  const [validation, setValidation] = useState<ConfigValidation[] | null>(null);
+  const [pendingJump, setPendingJump] = useState<{ file: string; error: ConfigValidationError } | null>(null);
+  const [loadedFile, setLoadedFile] = useState("");
   const codeRef = useRef<HTMLElement>(null);
   const textareaRef = useRef<HTMLTextAreaElement>(null);
 
@@ -65,6 +73,7 @@ export function ConfigViewer({
     getContent(selectedFile).then((c) => {
       setContent(c);
       setEditedContent(c);
+      setLoadedFile(selectedFile);
       setLoading(false);
     });
   }, [selectedFile, getContent]);
@@ -79,6 +88,44 @@ export function ConfigViewer({
     }
   }, [content, selectedFile, editing]);
 
+  // Open the editor at a problem once its file has loaded
+  useEffect(() => {
+    if (loading || !pendingJump || pendingJump.file !== loadedFile) return;
+    const jump = pendingJump.error;
+    setPendingJump(null);
+    setEditing(true);
+    setEditedContent(content);
+    setTimeout(() => {
+      const textarea = textareaRef.current;
+      if (!textarea) return;
+      const start = offsetOf(content, jump);
+      const end = content.indexOf("\n", start);
+      textarea.focus();
+      textarea.setSelectionRange(start, end === -1 ? content.length : end);
+    }, 100);
+  }, [loading, pendingJump, loadedFile, content]);
+
+  const handleValidateAll = async () => {
+    setValidating(true);
+    try {
+      setValidation(await invoke<ConfigValidation[]>("validate_configs"));
+    } catch (err) {
+      toast.error(errorMessage(err));
+    } finally {
+      setValidating(false);
+    }
+  };
+
+  const jumpTo = (result: ConfigValidation, error: ConfigValidationError) => {
+    if (editing && editedContent !== content) {
+      if (!window.confirm(t("config.unsavedChanges"))) return;
+    }
+    setEditing(false);
+    setValidationError("");
+    setPendingJump({ file: result.file, error });
+    setSelectedFile(result.file);
+  };
+
   const validateContent = (value: string): boolean => {
     const language = getLanguage(selectedFile);
     setValidationError("");
@@ -194,22 +241,38 @@ export function ConfigViewer({
 
         {files.length > 1 && (
           <div className="tabs tabs-boxed mb-2">
-            {files.map((f) => (
-              <button
-                key={f}
-                className={`tab tab-sm ${selectedFile === f ? "tab-active" : ""}`}
-                onClick={() => {
-                  if (editing && editedContent !== content) {
-                    if (!window.confirm(t("config.unsavedChanges"))) return;
-                  }
-                  setEditing(false);
-                  setValidationError("");
-                  setSelectedFile(f);
-                }}
-              >
-                {f}
-              </button>
-            ))}
+            {files.map((f) => {
+              const st = fileStatus.find((s) => s.name === f);
+              const tip = st
+                ? [
+                    !st.exists && t("config.fileMissing"),
+                    st.detected_url,
+                    st.note,
+                  ].filter(Boolean).join(" — ")
+                : undefined;
+              return (
+                <button
+                  key={f}
+                  title={tip || undefined}
+                  className={`tab tab-sm gap-1 ${selectedFile === f ? "tab-active" : ""}`}
+                  onClick={() => {
+                    if (editing && editedContent !== content) {
+                      if (!window.confirm(t("config.unsavedChanges"))) return;
+                    }
+                    setEditing(false);
+                    setValidationError("");
+                    setSelectedFile(f);
+                  }}
+                >
+                  {st?.synced != null && (
+                    <span
+                      className={`w-1.5 h-1.5 rounded-full ${st.synced ? "bg-success" : "bg-warning"}`}
+                    />
+                  )}
+                  {f}
+                </button>
+              );
+            })}
           </div>
         )}
 
@@ -219,6 +282,15 @@ export function ConfigViewer({
           </div>
         )}
 
+        {validation && (
+          <ValidationResults
+            results={validation}
+            canJump={(r) => r.app === cliId && files.includes(r.file)}
+            onJump={jumpTo}
+            onDismiss={() => setValidation(null)}
+          />
+        )}
+
         <div className="bg-base-300 rounded-lg p-4 overflow-auto max-h-[60vh]">
           {loading ? (
             <div className="flex justify-center py-4">
@@ -302,11 +374,28 @@ export function ConfigViewer({
               </button>
               <button
                 className="btn btn-sm btn-ghost gap-1"
-                onClick={() => invoke("open_config_folder", { app: cliId })}
-                title={t("config.openFolder")}
+                onClick={handleValidateAll}
+                disabled={validating}
+                title={t("config.validateAllHint")}
+              >
+                {validating ? (
+                  <span className="loading loading-spinner loading-xs" />
+                ) : (
+                  <ShieldCheck className="w-3.5 h-3.5" />
+                )}
+                {t("config.validateAll")}
+              </button>
+              <button
+                className="btn btn-sm btn-ghost gap-1"
+                onClick={() =>
+                  invoke("reveal_config_file", { app: cliId, fileName: selectedFile || null }).catch((e) =>
+                    toast.error(errorMessage(e))
+                  )
+                }
+                title={t("config.revealFile")}
               >
                 <FolderOpen className="w-3.5 h-3.5" />
-                {t("config.openFolder")}
+                {t("config.revealFile")}
               </button>
               <button className="btn btn-sm" onClick={onClose}>
                 {t("config.close")}
@@ -321,3 +410,76 @@ export function ConfigViewer({
     </dialog>
   );
 }
+
+/** Character offset of a validation problem: its line, else the last key of its pointer. */
+function offsetOf(content: string, error: ConfigValidationError): number {
+  if (error.line) {
+    let offset = 0;
+    for (let i = 1; i < error.line; i++) {
+      const next = content.indexOf("\n", offset);
+      if (next === -1) break;
+      offset = next + 1;
+    }
+    return offset;
+  }
+  const last = error.path.split("/").pop()?.replace(/~1/g, "/").replace(/~0/g, "~");
+  if (!last || /^\d+$/.test(last)) return 0;
+  const key = last.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+  const at = content.search(new RegExp(`["']?${key}["']?\\s*[:=]`));
+  return at === -1 ? 0 : content.lastIndexOf("\n", at) + 1;
+}
+
+function ValidationResults({
+  results,
+  canJump,
+  onJump,
+  onDismiss,
+}: {
+  results: ConfigValidation[];
+  canJump: (result: ConfigValidation) => boolean;
+  onJump: (result: ConfigValidation, error: ConfigValidationError) => void;
+  onDismiss: () => void;
+}) {
+  const { t } = useTranslation();
+  const failed = results.filter((r) => !r.ok);
+  return (
+    <div className={`alert ${failed.length ? "alert-warning" : "alert-success"} text-xs py-2 mb-2 flex-col items-stretch`}>
+      <div className="flex items-center justify-between">
+        <span>
+          {failed.length
+            ? t("config.validationFailed", { count: failed.length, total: results.length })
+            : t("config.validationOk", { total: results.length })}
+        </span>
+        <button className="btn btn-xs btn-ghost" onClick={onDismiss}>
+          <X className="w-3 h-3" />
+        </button>
+      </div>
+      {failed.map((result) => (
+        <div key={result.path} className="text-left">
+          <div className="font-semibold" title={result.path}>
+            {result.app} · {result.file}
+          </div>
+          <ul className="ml-3 list-disc">
+            {result.errors.map((error, i) => {
+              const where = [error.path || null, error.line ? t("config.line", { line: error.line }) : null]
+                .filter(Boolean)
+                .join(", ");
+              const label = where ? `${where}: ${error.message}` : error.message;
+              return (
+                <li key={i}>
+                  {canJump(result) ? (
+                    <button className="link link-hover font-mono text-left" onClick={() => onJump(result, error)}>
+                      {label}
+                    </button>
+                  ) : (
+                    <span className="font-mono">{label}</span>
+                  )}
+                </li>
+              );
+            })}
+          </ul>
+        </div>
+      ))}
+    </div>
+  );
+}
@@ -0,0 +1,162 @@
+import { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+import { toast } from "sonner";
+import { FolderOpen, Check } from "lucide-react";
+import type { ProjectSyncStatus } from "../types";
+import { errorMessage } from "../errors";
+
+interface ProjectSyncDialogProps {
+  url: string;
+  apiKey: string;
+  model: string;
+  onClose: () => void;
+}
+
+/** Sync Claude Code's project-level `.claude/settings.local.json` for a repo. */
+export function ProjectSyncDialog({ url, apiKey, model, onClose }: ProjectSyncDialogProps) {
+  const { t } = useTranslation();
+  const [recent, setRecent] = useState<string[]>([]);
+  const [projectDir, setProjectDir] = useState("");
+  const [shared, setShared] = useState(false);
+  const [status, setStatus] = useState<ProjectSyncStatus | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  useEffect(() => {
+    invoke<string[]>("get_recent_project_dirs")
+      .then((dirs) => {
+        setRecent(dirs);
+        if (dirs.length > 0) setProjectDir(dirs[0]);
+      })
+      .catch(() => {});
+  }, []);
+
+  const refreshStatus = useCallback(
+    async (dir: string) => {
+      if (!dir) return setStatus(null);
+      try {
+        setStatus(await invoke<ProjectSyncStatus>("get_project_sync_status", { projectDir: dir, url }));
+      } catch {
+        setStatus(null);
+      }
+    },
+    [url]
+  );
+
+  useEffect(() => {
+    refreshStatus(projectDir);
+  }, [projectDir, refreshStatus]);
+
+  const browse = async () => {
+    const dir = await open({ directory: true, multiple: false });
+    if (typeof dir === "string") setProjectDir(dir);
+  };
+
+  const run = async (command: "sync_project_config" | "restore_project_config") => {
+    setBusy(true);
+    try {
+      if (command === "sync_project_config") {
+        await invoke(command, {
+          app: "claude",
+          projectDir,
+          url,
+          apiKey,
+          model: model || null,
+          local: !shared,
+        });
+        toast.success(t("project.synced"));
+        setRecent((prev) => [projectDir, ...prev.filter((d) => d !== projectDir)]);
+      } else {
+        await invoke(command, { projectDir });
+        toast.success(t("project.restored"));
+      }
+      await refreshStatus(projectDir);
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <dialog className="modal modal-open" onClose={onClose}>
+      <div className="modal-box max-w-lg">
+        <h3 className="font-bold text-lg mb-1">{t("project.title")}</h3>
+        <p className="opacity-60 text-xs mb-3">{t("project.hint")}</p>
+
+        <div className="space-y-3 text-sm">
+          <div className="flex gap-1.5">
+            <input
+              list="recent-project-dirs"
+              className="input input-bordered input-sm flex-1 font-mono text-xs"
+              placeholder={t("project.dirPlaceholder")}
+              value={projectDir}
+              onChange={(e) => setProjectDir(e.target.value)}
+            />
+            <datalist id="recent-project-dirs">
+              {recent.map((d) => (
+                <option key={d} value={d} />
+              ))}
+            </datalist>
+            <button className="btn btn-sm btn-ghost" onClick={browse} title={t("project.browse")}>
+              <FolderOpen className="w-4 h-4" />
+            </button>
+          </div>
+
+          <label className="label cursor-pointer justify-start gap-2">
+            <input
+              type="checkbox"
+              className="checkbox checkbox-sm"
+              checked={shared}
+              onChange={(e) => setShared(e.target.checked)}
+            />
+            <span className="label-text">{t("project.writeShared")}</span>
+          </label>
+
+          {status && (
+            <ul className="text-xs space-y-1">
+              {status.file_status.map((f) => (
+                <li key={f.name} className="flex items-center gap-1.5">
+                  {f.synced ? (
+                    <Check className="w-3 h-3 text-success" />
+                  ) : (
+                    <span className={`w-1.5 h-1.5 rounded-full ${f.synced === false ? "bg-warning" : "bg-base-300"}`} />
+                  )}
+                  <span className="font-mono">.claude/{f.name}</span>
+                  <span className="opacity-50 truncate">
+                    {!f.exists ? t("config.fileMissing") : f.note || f.detected_url}
+                  </span>
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+
+        <div className="modal-action">
+          <button className="btn btn-sm btn-ghost" onClick={onClose} disabled={busy}>
+            {t("confirm.cancel")}
+          </button>
+          <button
+            className="btn btn-sm btn-ghost"
+            onClick={() => run("restore_project_config")}
+            disabled={busy || !projectDir || !status?.file_status.some((f) => f.exists)}
+          >
+            {t("cli.restore")}
+          </button>
+          <button
+            className="btn btn-sm btn-primary"
+            onClick={() => run("sync_project_config")}
+            disabled={busy || !projectDir || !apiKey}
+          >
+            {busy && <span className="loading loading-spinner loading-xs" />}
+            {t("cli.sync")}
+          </button>
+        </div>
+      </div>
+      <form method="dialog" className="modal-backdrop">
+        <button onClick={onClose}>close</button>
+      </form>
+    </dialog>
+  );
+}
@@ -1,21 +1,111 @@
-import { useState, useCallback, useRef } from "react";
+import { useState, useCallback, useRef, useEffect } from "react";
 import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
-import { Plus, Trash2, Check, Edit2, ChevronUp, ChevronDown, X, AlertTriangle } from "lucide-react";
-import type { ProviderRecord, SwitchResult } from "../types";
+import { open } from "@tauri-apps/plugin-dialog";
+import { invoke } from "@tauri-apps/api/core";
+import { Plus, Trash2, Check, Edit2, ChevronUp, ChevronDown, X, AlertTriangle, RefreshCw, Archive, RotateCcw, Search } from "lucide-react";
+import { CLI_LIST } from "../types";
+import type { ProviderPage, ProviderRecord, SwitchResult, TokenQuota } from "../types";
+import { errorMessage, isCommandError } from "../errors";
+import { RotationSettings } from "./RotationSettings";
+import { getSelfQuota, formatQuota, refreshProviderQuota } from "../hooks/useAccount";
 import {
   saveProvider,
   deleteProvider,
+  listProviders,
+  listProvidersPage,
+  restoreArchivedProvider,
+  purgeProvider,
   switchProvider,
   reorderProviders,
+  detectExistingProvider,
+  generateEnvExports,
+  writeEnvrc,
 } from "../hooks/useProviders";
 
+/** Snippet formats offered by `generate_env_exports`. */
+const ENV_FORMATS = ["bash", "fish", "powershell", "dotenv"];
+
+/** Show the search box once the list is this long. */
+const SEARCH_THRESHOLD = 10;
+/** Search results fetched per "load more". */
+const SEARCH_PAGE_SIZE = 30;
+
+/** Apps whose existing proxy settings the backend can read back. */
+const IMPORTABLE_APPS = ["claude", "claude-vscode", "codex", "gemini", "opencode", "openclaw", "droid", "chatbox"];
+
 function maskKey(key: string): string {
   if (!key) return "—";
+  // op:// and keychain:// references name the secret; they aren't one
+  if (/^(op|keychain):\/\//.test(key)) return key;
   if (key.length <= 8) return "••••••••";
   return key.slice(0, 4) + "••••" + key.slice(-4);
 }
 
+/** Balance of the account a provider's key came from; hidden if unavailable. */
+function AccountBalance({ accountId }: { accountId: number }) {
+  const { t } = useTranslation();
+  const [quota, setQuota] = useState<number | null>(null);
+  useEffect(() => {
+    let cancelled = false;
+    getSelfQuota(accountId)
+      .then((q) => !cancelled && setQuota(q.quota))
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+    };
+  }, [accountId]);
+  if (quota === null) return null;
+  return (
+    <>
+      <span className="mx-1">·</span>
+      {t("account.balance")} {formatQuota(quota)}
+    </>
+  );
+}
+
+/** Remaining quota of a provider's linked token, cached in the DB; click to refresh. */
+function TokenQuotaBadge({ provider }: { provider: ProviderRecord }) {
+  const { t } = useTranslation();
+  const [quota, setQuota] = useState<TokenQuota | null>(provider.quota ?? null);
+  const [loading, setLoading] = useState(false);
+  useEffect(() => setQuota(provider.quota ?? null), [provider.quota]);
+
+  const refresh = async (e: React.MouseEvent) => {
+    e.stopPropagation();
+    setLoading(true);
+    try {
+      setQuota(await refreshProviderQuota(provider.id));
+    } catch (err) {
+      toast.error(errorMessage(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <>
+      <span className="mx-1">·</span>
+      {quota &&
+        `${t("provider.tokenQuota")} ${
+          quota.unlimited_quota ? t("account.unlimitedQuota") : formatQuota(quota.remain_quota)
+        }`}
+      <button
+        className="btn btn-ghost btn-xs btn-square h-3 min-h-0 w-3 ml-0.5 align-middle"
+        title={
+          quota
+            ? t("provider.quotaFetchedAt", { time: new Date(quota.fetched_at * 1000).toLocaleString() })
+            : t("provider.refreshQuota")
+        }
+        onClick={refresh}
+        disabled={loading}
+      >
+        <RefreshCw className={`w-2.5 h-2.5 ${loading ? "animate-spin" : ""}`} />
+      </button>
+    </>
+  );
+}
+
 // ── Form component (isolated so state never leaks between new/edit) ──────────
 
 interface FormState {
@@ -25,8 +115,29 @@ interface FormState {
   api_key: string;
   default_model: string;
   notes: string;
+  model_aliases: string;
+  advanced: string;
+  opencode_adapter: string;
 }
 
+const emptyForm = (id: string): FormState => ({
+  id,
+  name: "",
+  url: "",
+  api_key: "",
+  default_model: "",
+  notes: "",
+  model_aliases: "",
+  advanced: "",
+  opencode_adapter: "",
+});
+
+/** npm adapters opencode can talk to the proxy through; "" = the default (openai) */
+const OPENCODE_ADAPTERS = ["", "anthropic", "openai-compatible", "openai"];
+
+/** Apps whose resolved default model is previewed under the alias editor */
+const ALIAS_PREVIEW_APPS = ["claude", "codex", "gemini", "opencode"];
+
 interface ProviderFormProps {
   initial: FormState;
   isNew: boolean;
@@ -39,6 +150,22 @@ function ProviderForm({ initial, isNew, onSave, onCancel }: ProviderFormProps) {
   const [form, setForm] = useState<FormState>(initial);
   const [showKey, setShowKey] = useState(false);
   const [saving, setSaving] = useState(false);
+  const [aliasPreview, setAliasPreview] = useState<[string, string][]>([]);
+
+  // Preview uses the saved aliases, so it reflects what the next sync writes
+  useEffect(() => {
+    const model = initial.default_model.trim();
+    if (isNew || !model || !initial.model_aliases.trim()) return;
+    Promise.all(
+      ALIAS_PREVIEW_APPS.map((app) =>
+        invoke<string>("resolve_model_alias", { providerId: initial.id, model, app }).then(
+          (id): [string, string] => [app, id]
+        )
+      )
+    )
+      .then((pairs) => setAliasPreview(pairs.filter(([, id]) => id !== model)))
+      .catch(() => {});
+  }, [initial, isNew]);
 
   const field = (key: keyof FormState) => ({
     value: form[key] as string,
@@ -54,7 +181,7 @@ function ProviderForm({ initial, isNew, onSave, onCancel }: ProviderFormProps) {
     try {
       await onSave(form);
     } catch (e) {
-      toast.error(String(e), { duration: 5000 });
+      toast.error(errorMessage(e), { duration: 5000 });
     } finally {
       setSaving(false);
     }
@@ -113,6 +240,42 @@ function ProviderForm({ initial, isNew, onSave, onCancel }: ProviderFormProps) {
           {...field("notes")}
           onKeyDown={(e) => e.key === "Enter" && handleSave()}
         />
+        <textarea
+          className="textarea textarea-bordered textarea-xs w-full font-mono"
+          rows={2}
+          placeholder={t("provider.modelAliasesPlaceholder")}
+          title={t("provider.modelAliasesHint")}
+          value={form.model_aliases}
+          onChange={(e) => setForm((f) => ({ ...f, model_aliases: e.target.value }))}
+        />
+        {aliasPreview.length > 0 && (
+          <div className="text-[10px] opacity-50 font-mono">
+            {aliasPreview.map(([app, id]) => `${app} → ${id}`).join(" · ")}
+          </div>
+        )}
+        <textarea
+          className="textarea textarea-bordered textarea-xs w-full font-mono"
+          rows={2}
+          placeholder={t("provider.advancedPlaceholder")}
+          title={t("provider.advancedHint")}
+          value={form.advanced}
+          onChange={(e) => setForm((f) => ({ ...f, advanced: e.target.value }))}
+        />
+        <label className="flex items-center gap-2 text-xs">
+          <span className="opacity-60 shrink-0">{t("provider.opencodeAdapter")}</span>
+          <select
+            className="select select-bordered select-xs flex-1"
+            title={t("provider.opencodeAdapterHint")}
+            value={form.opencode_adapter}
+            onChange={(e) => setForm((f) => ({ ...f, opencode_adapter: e.target.value }))}
+          >
+            {OPENCODE_ADAPTERS.map((id) => (
+              <option key={id} value={id}>
+                {id ? id : t("provider.opencodeAdapterDefault")}
+              </option>
+            ))}
+          </select>
+        </label>
 
         <div className="flex gap-2 justify-end pt-1">
           <button className="btn btn-ghost btn-xs" onClick={onCancel}>
@@ -137,17 +300,19 @@ function ProviderForm({ initial, isNew, onSave, onCancel }: ProviderFormProps) {
 
 interface DeleteConfirmProps {
   name: string;
+  /** Permanent delete rather than archive */
+  purge?: boolean;
   onConfirm: () => void;
   onCancel: () => void;
 }
 
-function DeleteConfirm({ name, onConfirm, onCancel }: DeleteConfirmProps) {
+function DeleteConfirm({ name, purge, onConfirm, onCancel }: DeleteConfirmProps) {
   const { t } = useTranslation();
   return (
     <div className="flex items-center gap-2 px-2 py-1.5 rounded-lg bg-error/10 border border-error/20 mt-1 animate-in fade-in slide-in-from-top-1 duration-150">
       <AlertTriangle className="w-3.5 h-3.5 text-error shrink-0" />
       <span className="text-xs flex-1 truncate">
-        {t("provider.deleteConfirm", { name })}
+        {t(purge ? "provider.purgeConfirm" : "provider.deleteConfirm", { name })}
       </span>
       <button className="btn btn-ghost btn-xs" onClick={onCancel}>
         {t("confirm.cancel")}
@@ -159,6 +324,78 @@ function DeleteConfirm({ name, onConfirm, onCancel }: DeleteConfirmProps) {
   );
 }
 
+/** Providers deleted from the list; restore them or delete them for good. */
+function ArchivedProviders({
+  providers,
+  onProvidersChange,
+}: {
+  providers: ProviderRecord[];
+  onProvidersChange: () => Promise<unknown>;
+}) {
+  const { t } = useTranslation();
+  const [archived, setArchived] = useState<ProviderRecord[]>([]);
+  const [open, setOpen] = useState(false);
+  const [purgingId, setPurgingId] = useState<string | null>(null);
+
+  useEffect(() => {
+    listProviders(true)
+      .then((all) => setArchived(all.filter((p) => p.archived)))
+      .catch(() => {});
+  }, [providers]);
+
+  const run = async (action: () => Promise<void>, message: string) => {
+    setPurgingId(null);
+    try {
+      await action();
+      await onProvidersChange();
+      toast.success(message);
+    } catch (e) {
+      toast.error(errorMessage(e), { duration: 5000 });
+    }
+  };
+
+  if (archived.length === 0) return null;
+  return (
+    <div className="mt-2 text-xs">
+      <button className="flex items-center gap-1.5 opacity-50 hover:opacity-100" onClick={() => setOpen(!open)}>
+        <Archive className="w-3 h-3" />
+        {t("provider.archived", { count: archived.length })}
+      </button>
+      {open &&
+        archived.map((p) => (
+          <div key={p.id} className="mt-1">
+            <div className="flex items-center gap-2 px-2 py-1 rounded-lg bg-base-200/50">
+              <span className="truncate flex-1 opacity-70">{p.name}</span>
+              <span className="font-mono text-[10px] opacity-40 truncate">{p.url}</span>
+              <button
+                className="btn btn-ghost btn-xs btn-square"
+                title={t("provider.restore")}
+                onClick={() => run(() => restoreArchivedProvider(p.id), t("provider.restored", { name: p.name }))}
+              >
+                <RotateCcw className="w-3 h-3" />
+              </button>
+              <button
+                className="btn btn-ghost btn-xs btn-square text-error"
+                title={t("provider.purge")}
+                onClick={() => setPurgingId(p.id)}
+              >
+                <Trash2 className="w-3 h-3" />
+              </button>
+            </div>
+            {purgingId === p.id && (
+              <DeleteConfirm
+                name={p.name}
+                purge
+                onConfirm={() => run(() => purgeProvider(p.id), t("provider.purged"))}
+                onCancel={() => setPurgingId(null)}
+              />
+            )}
+          </div>
+        ))}
+    </div>
+  );
+}
+
 // ── Main panel ───────────────────────────────────────────────────────────────
 
 interface ProviderPanelProps {
@@ -190,14 +427,74 @@ export function ProviderPanel({
 
   // Stable ref for new-form id so it doesn't regenerate on re-render
   const newIdRef = useRef<string>("");
+  // Pre-filled values for the new form (set by "import from app")
+  const [newInitial, setNewInitial] = useState<FormState>(() => emptyForm(""));
+  const [newPerCliModels, setNewPerCliModels] = useState("{}");
+  const [importing, setImporting] = useState(false);
 
-  const openNew = useCallback(() => {
+  const openNew = useCallback((initial?: Partial<Omit<FormState, "id">>, perCliModels = "{}") => {
     newIdRef.current = crypto.randomUUID();
+    setNewInitial({ ...emptyForm(newIdRef.current), ...initial, id: newIdRef.current });
+    setNewPerCliModels(perCliModels);
     setEditingId(null);
     setDeletingId(null);
     setShowNew(true);
   }, []);
 
+  // ── Env var exports for the current provider ──────────────────────────────
+
+  const handleEnvExport = useCallback(
+    async (choice: string) => {
+      try {
+        if (choice === "envrc") {
+          const dir = await open({ directory: true, multiple: false });
+          if (!dir) return;
+          const path = await writeEnvrc(dir);
+          toast.success(t("provider.envrcWritten", { path }));
+        } else {
+          const snippet = await generateEnvExports(choice);
+          await navigator.clipboard.writeText(snippet);
+          toast.success(t("provider.envCopied"));
+        }
+      } catch (e) {
+        toast.error(errorMessage(e), { duration: 5000 });
+      }
+    },
+    [t]
+  );
+
+  // ── Import from an app's existing config ──────────────────────────────────
+
+  const handleImport = useCallback(
+    async (app: string) => {
+      const name = CLI_LIST.find((c) => c.id === app)?.name ?? app;
+      setImporting(true);
+      try {
+        const detected = await detectExistingProvider(app);
+        if (!detected) {
+          toast.info(t("provider.importNothing", { name }));
+          return;
+        }
+        openNew(
+          {
+            name,
+            url: detected.url,
+            api_key: detected.api_key,
+            default_model: "",
+            notes: t("provider.importedFrom", { name }),
+          },
+          // The detected model belongs to that app only, so it goes in per_cli_models.
+          detected.model ? JSON.stringify({ [app]: detected.model }) : "{}"
+        );
+      } catch (e) {
+        toast.error(errorMessage(e), { duration: 5000 });
+      } finally {
+        setImporting(false);
+      }
+    },
+    [openNew, t]
+  );
+
   const closeNew = useCallback(() => setShowNew(false), []);
 
   const openEdit = useCallback((id: string) => {
@@ -219,20 +516,31 @@ export function ProviderPanel({
         url: form.url.trim(),
         api_key: form.api_key.trim(),
         default_model: form.default_model.trim(),
-        per_cli_models: existing?.per_cli_models ?? "{}",
+        per_cli_models: existing?.per_cli_models ?? (isNew ? newPerCliModels : "{}"),
         is_current: existing?.is_current ?? false,
         sort_index: existing?.sort_index ?? null,
         notes: form.notes.trim() || null,
         // Unix seconds — consistent with Rust's i64 created_at column.
         created_at: existing?.created_at ?? Math.floor(Date.now() / 1000),
+        opencode_adapter: form.opencode_adapter,
+        account_id: existing?.account_id ?? null,
+        account_base_url: existing?.account_base_url ?? null,
+        token_id: existing?.token_id ?? null,
+        model_aliases: form.model_aliases.trim() || "{}",
+        advanced: form.advanced.trim() || "{}",
       };
-      await saveProvider(record);
+      const result = await saveProvider(record);
       await onProvidersChange();
       if (isNew) setShowNew(false);
       else setEditingId(null);
-      toast.success(t("provider.saved"));
+      if (result.sync && !result.sync.success) {
+        const errApps = result.sync.errors.map((e) => e.app).join(", ");
+        toast.warning(t("provider.switchedWithErrors", { apps: errApps }));
+      } else {
+        toast.success(t("provider.saved"));
+      }
     },
-    [providers, onProvidersChange, t]
+    [providers, newPerCliModels, onProvidersChange, t]
   );
 
   // ── Switch ────────────────────────────────────────────────────────────────
@@ -253,8 +561,19 @@ export function ProviderPanel({
           const errApps = result.errors.map((e) => e.app).join(", ");
           toast.warning(t("provider.switchedWithErrors", { apps: errApps }));
         }
+        if (result.skipped?.length) {
+          toast.info(t("provider.pinnedSkipped", { apps: result.skipped.map((s) => s.app).join(", ") }));
+        }
+        for (const m of result.manual ?? []) {
+          toast.info(t("toast.manualSetup", { steps: m.warning }), { duration: 10000 });
+        }
+        for (const w of result.warnings ?? []) {
+          if (w.warning) {
+            toast.warning(t("toast.syncWarning", { name: w.app, warning: w.warning }), { duration: 8000 });
+          }
+        }
       } catch (e) {
-        toast.error(String(e), { duration: 5000 });
+        toast.error(errorMessage(e), { duration: 5000 });
       } finally {
         setSwitchingId(null);
         setIsSwitching(false);
@@ -273,12 +592,42 @@ export function ProviderPanel({
         await onProvidersChange();
         toast.success(t("provider.deleted"));
       } catch (e) {
-        toast.error(String(e), { duration: 5000 });
+        toast.error(errorMessage(e), { duration: 5000 });
       }
     },
     [onProvidersChange, t]
   );
 
+  // ── Search ────────────────────────────────────────────────────────────────
+
+  // Searching pages through the backend; reordering is off until it's cleared
+  const [search, setSearch] = useState("");
+  const [results, setResults] = useState<ProviderPage | null>(null);
+
+  const fetchResults = useCallback(async (term: string, offset: number) => {
+    const trimmed = term.trim();
+    const page = trimmed.startsWith("#")
+      ? await listProvidersPage(offset, SEARCH_PAGE_SIZE, undefined, trimmed)
+      : await listProvidersPage(offset, SEARCH_PAGE_SIZE, trimmed);
+    setResults((prev) =>
+      offset > 0 && prev ? { total: page.total, items: [...prev.items, ...page.items] } : page
+    );
+  }, []);
+
+  useEffect(() => {
+    if (!search.trim()) {
+      setResults(null);
+      return;
+    }
+    const timer = setTimeout(() => {
+      fetchResults(search, 0).catch((e) => toast.error(errorMessage(e)));
+    }, 200);
+    return () => clearTimeout(timer);
+    // Re-run when the list changes so edits show up in the results
+  }, [search, providers, fetchResults]);
+
+  const shown = results ? results.items : providers;
+
   // ── Reorder ───────────────────────────────────────────────────────────────
 
   const handleReorder = useCallback(
@@ -291,7 +640,9 @@ export function ProviderPanel({
         await reorderProviders(ids);
         await onProvidersChange();
       } catch (e) {
-        toast.error(String(e), { duration: 5000 });
+        // Another window changed the list: show the current one instead
+        if (isCommandError(e) && e.code === "CONFLICT") await onProvidersChange();
+        toast.error(errorMessage(e), { duration: 5000 });
       }
     },
     [providers, onProvidersChange]
@@ -306,37 +657,83 @@ export function ProviderPanel({
         <span className="text-xs font-semibold opacity-60">
           {t("provider.title")}
         </span>
-        <button
-          className="btn btn-ghost btn-xs gap-1 opacity-60 hover:opacity-100 transition-opacity"
-          onClick={openNew}
-          disabled={showNew || isSwitching}
-        >
-          <Plus className="w-3 h-3" />
-          {t("provider.add")}
-        </button>
+        <div className="flex items-center gap-1">
+          <select
+            className="select select-ghost select-xs opacity-60 hover:opacity-100"
+            value=""
+            onChange={(e) => e.target.value && handleImport(e.target.value)}
+            disabled={showNew || isSwitching || importing}
+          >
+            <option value="">{t("provider.importFrom")}</option>
+            {IMPORTABLE_APPS.map((id) => (
+              <option key={id} value={id}>
+                {CLI_LIST.find((c) => c.id === id)?.name ?? id}
+              </option>
+            ))}
+          </select>
+          {providers.some((p) => p.is_current) && (
+            <select
+              className="select select-ghost select-xs opacity-60 hover:opacity-100"
+              value=""
+              onChange={(e) => e.target.value && handleEnvExport(e.target.value)}
+              title={t("provider.envExportHint")}
+            >
+              <option value="">{t("provider.envExport")}</option>
+              {ENV_FORMATS.map((f) => (
+                <option key={f} value={f}>
+                  {f}
+                </option>
+              ))}
+              <option value="envrc">{t("provider.envrcWrite")}</option>
+            </select>
+          )}
+          <button
+            className="btn btn-ghost btn-xs gap-1 opacity-60 hover:opacity-100 transition-opacity"
+            onClick={() => openNew()}
+            disabled={showNew || isSwitching}
+          >
+            <Plus className="w-3 h-3" />
+            {t("provider.add")}
+          </button>
+        </div>
       </div>
 
       {/* New-provider form */}
       {showNew && (
         <ProviderForm
           key={newIdRef.current}
-          initial={{
-            id: newIdRef.current,
-            name: "",
-            url: "",
-            api_key: "",
-            default_model: "",
-            notes: "",
-          }}
+          initial={newInitial}
           isNew
           onSave={(f) => handleSave(f, true)}
           onCancel={closeNew}
         />
       )}
 
+      {/* Search */}
+      {(providers.length >= SEARCH_THRESHOLD || search) && (
+        <label className="input input-bordered input-xs flex items-center gap-1.5">
+          <Search className="w-3 h-3 opacity-40" />
+          <input
+            type="text"
+            className="grow"
+            value={search}
+            onChange={(e) => setSearch(e.target.value)}
+            placeholder={t("provider.searchPlaceholder")}
+          />
+          {search && (
+            <button className="opacity-40 hover:opacity-100" onClick={() => setSearch("")}>
+              <X className="w-3 h-3" />
+            </button>
+          )}
+        </label>
+      )}
+
       {/* Provider list */}
       <div className="space-y-1.5">
-        {providers.map((p, idx) => {
+        {results && results.total === 0 && (
+          <div className="text-xs opacity-40 text-center py-2">{t("provider.searchEmpty")}</div>
+        )}
+        {shown.map((p, idx) => {
           const isThisSwitching = switchingId === p.id;
           return (
             <div key={p.id}>
@@ -377,6 +774,11 @@ export function ProviderPanel({
                         {p.default_model}
                       </>
                     )}
+                    {p.token_id != null ? (
+                      <TokenQuotaBadge provider={p} />
+                    ) : (
+                      p.account_id != null && <AccountBalance accountId={p.account_id} />
+                    )}
                   </div>
                 </div>
 
@@ -385,7 +787,7 @@ export function ProviderPanel({
                   <button
                     className="btn btn-ghost btn-xs btn-square h-3.5 min-h-0 opacity-25 hover:opacity-70 disabled:opacity-10"
                     onClick={() => handleReorder(idx, -1)}
-                    disabled={idx === 0 || !!isSwitching}
+                    disabled={idx === 0 || !!isSwitching || !!results}
                     tabIndex={-1}
                   >
                     <ChevronUp className="w-2.5 h-2.5" />
@@ -393,7 +795,7 @@ export function ProviderPanel({
                   <button
                     className="btn btn-ghost btn-xs btn-square h-3.5 min-h-0 opacity-25 hover:opacity-70 disabled:opacity-10"
                     onClick={() => handleReorder(idx, 1)}
-                    disabled={idx === providers.length - 1 || !!isSwitching}
+                    disabled={idx === providers.length - 1 || !!isSwitching || !!results}
                     tabIndex={-1}
                   >
                     <ChevronDown className="w-2.5 h-2.5" />
@@ -460,6 +862,9 @@ export function ProviderPanel({
                     api_key: p.api_key,
                     default_model: p.default_model,
                     notes: p.notes ?? "",
+                    model_aliases: p.model_aliases && p.model_aliases !== "{}" ? p.model_aliases : "",
+                    advanced: p.advanced && p.advanced !== "{}" ? p.advanced : "",
+                    opencode_adapter: p.opencode_adapter ?? "",
                   }}
                   isNew={false}
                   onSave={(f) => handleSave(f, false)}
@@ -469,6 +874,14 @@ export function ProviderPanel({
             </div>
           );
         })}
+        {results && results.items.length < results.total && (
+          <button
+            className="btn btn-ghost btn-xs w-full opacity-60"
+            onClick={() => fetchResults(search, results.items.length).catch((e) => toast.error(errorMessage(e)))}
+          >
+            {t("provider.loadMore", { shown: results.items.length, total: results.total })}
+          </button>
+        )}
       </div>
 
       {/* Empty state */}
@@ -477,6 +890,9 @@ export function ProviderPanel({
           <p className="text-xs">{t("provider.empty")}</p>
         </div>
       )}
+
+      <ArchivedProviders providers={providers} onProvidersChange={onProvidersChange} />
+      <RotationSettings providers={providers} />
     </div>
   );
 }
@@ -0,0 +1,99 @@
+import { useEffect, useState, type ChangeEvent } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "react-i18next";
+import { RefreshCw } from "lucide-react";
+import { toast } from "sonner";
+import type { ProxyConfig, ProxyTestResult } from "../types";
+import { errorMessage } from "../errors";
+
+const EMPTY: ProxyConfig = { url: "", username: "", password: "", noProxy: "" };
+
+export function ProxySettings() {
+  const { t } = useTranslation();
+  const [config, setConfig] = useState<ProxyConfig>(EMPTY);
+  const [testing, setTesting] = useState(false);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    invoke<ProxyConfig>("get_proxy_settings")
+      .then(setConfig)
+      .catch(() => {});
+  }, []);
+
+  const update = (key: keyof ProxyConfig) => (e: ChangeEvent<HTMLInputElement>) =>
+    setConfig({ ...config, [key]: e.target.value });
+
+  const handleTest = async () => {
+    setTesting(true);
+    try {
+      const result = await invoke<ProxyTestResult>("test_proxy_settings", { config });
+      toast.success(t("proxy.testSuccess", { url: result.probeUrl, ms: result.latencyMs }));
+    } catch (e) {
+      toast.error(t("proxy.testFailed") + ": " + errorMessage(e));
+    } finally {
+      setTesting(false);
+    }
+  };
+
+  const handleSave = async () => {
+    setSaving(true);
+    try {
+      await invoke("set_proxy_settings", { config });
+      toast.success(t("proxy.saved"));
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <details className="px-0.5">
+      <summary className="text-xs opacity-60 cursor-pointer select-none" title={t("proxy.hint")}>
+        {t("proxy.title")}
+      </summary>
+      <div className="space-y-1.5 mt-1.5">
+        <input
+          type="text"
+          className="input input-bordered input-xs w-full"
+          value={config.url}
+          onChange={update("url")}
+          placeholder={t("proxy.urlPlaceholder")}
+        />
+        <div className="flex gap-1.5">
+          <input
+            type="text"
+            className="input input-bordered input-xs flex-1 min-w-0"
+            value={config.username}
+            onChange={update("username")}
+            placeholder={t("proxy.username")}
+            autoComplete="off"
+          />
+          <input
+            type="password"
+            className="input input-bordered input-xs flex-1 min-w-0"
+            value={config.password}
+            onChange={update("password")}
+            placeholder={t("proxy.password")}
+            autoComplete="off"
+          />
+        </div>
+        <input
+          type="text"
+          className="input input-bordered input-xs w-full"
+          value={config.noProxy}
+          onChange={update("noProxy")}
+          placeholder={t("proxy.noProxy")}
+        />
+        <div className="flex gap-2">
+          <button className="btn btn-ghost btn-xs flex-1" onClick={handleTest} disabled={testing}>
+            {testing ? <RefreshCw className="w-3 h-3 animate-spin" /> : t("proxy.test")}
+          </button>
+          <button className="btn btn-primary btn-xs flex-1" onClick={handleSave} disabled={saving}>
+            {t("proxy.save")}
+          </button>
+        </div>
+      </div>
+    </details>
+  );
+}
@@ -0,0 +1,74 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import type { ConfigDiff } from "../types";
+
+interface RestoreDiffProps {
+  app: string;
+  files: string[];
+}
+
+/** What Restore would change in each of an app's config files. */
+export function RestoreDiff({ app, files }: RestoreDiffProps) {
+  const { t } = useTranslation();
+  const [diffs, setDiffs] = useState<Record<string, ConfigDiff | null>>({});
+
+  useEffect(() => {
+    let cancelled = false;
+    for (const file of files) {
+      invoke<ConfigDiff>("diff_config_with_backup", { app, fileName: file })
+        .then((d) => !cancelled && setDiffs((prev) => ({ ...prev, [file]: d })))
+        // Files without a known path (e.g. overridden away) just aren't shown
+        .catch(() => !cancelled && setDiffs((prev) => ({ ...prev, [file]: null })));
+    }
+    return () => {
+      cancelled = true;
+    };
+  }, [app, files]);
+
+  const shown = files.filter((f) => diffs[f]?.backup_exists);
+  if (shown.length === 0) return null;
+
+  return (
+    <div className="space-y-2 max-h-72 overflow-y-auto">
+      {shown.map((file) => {
+        const d = diffs[file]!;
+        const unchanged = d.additions.length === 0 && d.removals.length === 0 && !d.truncated;
+        return (
+          <div key={file} className="text-xs">
+            <div className="flex items-center gap-2 font-mono mb-1">
+              <span className="truncate flex-1">{file}</span>
+              {unchanged ? (
+                <span className="opacity-50">{t("restoreDiff.unchanged")}</span>
+              ) : (
+                <>
+                  <span className="text-success">+{d.additions.length}</span>
+                  <span className="text-error">-{d.removals.length}</span>
+                </>
+              )}
+            </div>
+            {d.note && <p className="opacity-60 mb-1">{d.note}</p>}
+            {d.unified_diff && (
+              <pre className="bg-base-200 rounded p-2 text-[10px] overflow-x-auto">
+                {d.unified_diff.split("\n").map((line, i) => (
+                  <div
+                    key={i}
+                    className={
+                      line.startsWith("+") && !line.startsWith("+++")
+                        ? "text-success"
+                        : line.startsWith("-") && !line.startsWith("---")
+                          ? "text-error"
+                          : "opacity-60"
+                    }
+                  >
+                    {line || " "}
+                  </div>
+                ))}
+              </pre>
+            )}
+          </div>
+        );
+      })}
+    </div>
+  );
+}
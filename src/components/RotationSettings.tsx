@@ -0,0 +1,100 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Repeat } from "lucide-react";
+import type { ProviderRecord, RotationConfig, RotationStatus } from "../types";
+import { errorMessage } from "../errors";
+
+/** Scheduled rotation between some of the saved providers. */
+export function RotationSettings({ providers }: { providers: ProviderRecord[] }) {
+  const { t } = useTranslation();
+  const [status, setStatus] = useState<RotationStatus | null>(null);
+  const [open, setOpen] = useState(false);
+
+  useEffect(() => {
+    invoke<RotationStatus>("get_rotation_status").then(setStatus).catch(() => {});
+    // The current provider changes on every switch, which moves the next one
+  }, [providers]);
+
+  if (!status || providers.length < 2) return null;
+  const config = status.config;
+
+  const save = async (next: RotationConfig) => {
+    try {
+      setStatus(await invoke<RotationStatus>("set_rotation_config", { config: next }));
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
+  const toggleProvider = (id: string) => {
+    const ids = config.provider_ids.includes(id)
+      ? config.provider_ids.filter((p) => p !== id)
+      : [...config.provider_ids, id];
+    // Rotation needs two providers; dropping below that turns it off
+    save({ ...config, provider_ids: ids, enabled: config.enabled && ids.length >= 2 });
+  };
+
+  const nextName = providers.find((p) => p.id === status.next_provider_id)?.name;
+
+  return (
+    <div className="mt-3 pt-3 border-t border-base-300 text-xs">
+      <div className="flex items-center gap-2">
+        <button className="flex items-center gap-1.5 opacity-70 hover:opacity-100" onClick={() => setOpen(!open)}>
+          <Repeat className="w-3 h-3" />
+          {t("rotation.title")}
+        </button>
+        {config.enabled && status.next_due_at != null && (
+          <span className="opacity-50 truncate">
+            {t("rotation.next", {
+              name: nextName ?? "—",
+              time: new Date(status.next_due_at * 1000).toLocaleString(),
+            })}
+          </span>
+        )}
+        <input
+          type="checkbox"
+          className="toggle toggle-xs toggle-primary ml-auto"
+          checked={config.enabled}
+          disabled={config.provider_ids.length < 2}
+          onChange={(e) => save({ ...config, enabled: e.target.checked })}
+        />
+      </div>
+
+      {open && (
+        <div className="mt-2 space-y-2">
+          <p className="opacity-50">{t("rotation.hint")}</p>
+          <div className="flex flex-wrap gap-x-3 gap-y-1">
+            {providers.map((p) => (
+              <label key={p.id} className="flex items-center gap-1 cursor-pointer">
+                <input
+                  type="checkbox"
+                  className="checkbox checkbox-xs"
+                  checked={config.provider_ids.includes(p.id)}
+                  onChange={() => toggleProvider(p.id)}
+                />
+                {p.name}
+              </label>
+            ))}
+          </div>
+          <label className="flex items-center gap-1.5">
+            {t("rotation.every")}
+            <input
+              type="number"
+              min={1}
+              max={720}
+              className="input input-bordered input-xs w-16"
+              defaultValue={config.interval_hours}
+              onBlur={(e) => {
+                const hours = Math.round(Number(e.target.value));
+                if (hours !== config.interval_hours && hours >= 1) save({ ...config, interval_hours: hours });
+              }}
+            />
+            {t("rotation.hours")}
+          </label>
+        </div>
+      )}
+    </div>
+  );
+}
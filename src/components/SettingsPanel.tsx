@@ -3,11 +3,17 @@ import { invoke } from "@tauri-apps/api/core";
 import { useTranslation } from "react-i18next";
 import { save, open } from "@tauri-apps/plugin-dialog";
 import { writeTextFile, readTextFile } from "@tauri-apps/plugin-fs";
-import { Eye, EyeOff, Check, X, RefreshCw, Download, Upload, KeyRound, UserCircle, ShoppingCart } from "lucide-react";
+import { Eye, EyeOff, Check, X, RefreshCw, Download, Upload, KeyRound, UserCircle, ShoppingCart, Archive } from "lucide-react";
 import { toast } from "sonner";
 import { ModelSelector } from "./ModelSelector";
 import { AccountLogin } from "./AccountLogin";
+import { BundleDialog } from "./BundleDialog";
+import { BackupsDialog } from "./BackupsDialog";
+import { ProxySettings } from "./ProxySettings";
+import { TlsSettings } from "./TlsSettings";
+import { WslSettings } from "./WslSettings";
 import type { AuthMode } from "../types";
+import { errorMessage } from "../errors";
 
 interface SettingsPanelProps {
   url: string;
@@ -25,6 +31,10 @@ interface SettingsPanelProps {
   onSaveApiKeyChange: (save: boolean) => void;
   /** Called when user selects a token in account mode — includes token name for Provider naming */
   onAccountConfigReady: (url: string, apiKey: string, tokenName: string) => void;
+  /** Called after a backup bundle restored providers */
+  onBundleImported?: () => void;
+  /** Called when the CLI apps' config location moved (WSL target) */
+  onSyncTargetChanged?: () => void;
 }
 
 export function SettingsPanel({
@@ -42,6 +52,8 @@ export function SettingsPanel({
   saveApiKey,
   onSaveApiKeyChange,
   onAccountConfigReady,
+  onBundleImported,
+  onSyncTargetChanged,
 }: SettingsPanelProps) {
   const { t } = useTranslation();
   const [authMode, setAuthMode] = useState<AuthMode>(
@@ -50,6 +62,50 @@ export function SettingsPanel({
   const [showKey, setShowKey] = useState(false);
   const [testing, setTesting] = useState(false);
   const [testResult, setTestResult] = useState<"success" | "error" | null>(null);
+  const [bundleMode, setBundleMode] = useState<"export" | "import" | null>(null);
+  const [showBackups, setShowBackups] = useState(false);
+  const [codexProfileMode, setCodexProfileMode] = useState(false);
+  const [geminiForceAuth, setGeminiForceAuth] = useState(false);
+  const [updateCheck, setUpdateCheck] = useState(true);
+
+  useEffect(() => {
+    invoke<boolean>("get_codex_profile_mode")
+      .then(setCodexProfileMode)
+      .catch(() => {});
+    invoke<boolean>("get_gemini_force_auth")
+      .then(setGeminiForceAuth)
+      .catch(() => {});
+    invoke<boolean>("get_update_check_enabled")
+      .then(setUpdateCheck)
+      .catch(() => {});
+  }, []);
+
+  const handleCodexProfileModeChange = async (enabled: boolean) => {
+    try {
+      await invoke("set_codex_profile_mode", { enabled });
+      setCodexProfileMode(enabled);
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
+  const handleGeminiForceAuthChange = async (enabled: boolean) => {
+    try {
+      await invoke("set_gemini_force_auth", { enabled });
+      setGeminiForceAuth(enabled);
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
+
+  const handleUpdateCheckChange = async (enabled: boolean) => {
+    try {
+      await invoke("set_update_check_enabled", { enabled });
+      setUpdateCheck(enabled);
+    } catch (e) {
+      toast.error(errorMessage(e));
+    }
+  };
 
   const handleAuthModeChange = (mode: AuthMode) => {
     setAuthMode(mode);
@@ -304,6 +360,55 @@ export function SettingsPanel({
             />
           </div>
 
+          {/* Codex profile mode */}
+          <label
+            className="flex items-center gap-1.5 cursor-pointer select-none px-0.5"
+            title={t("settings.codexProfileModeHint")}
+          >
+            <input
+              type="checkbox"
+              className="toggle toggle-xs toggle-primary"
+              checked={codexProfileMode}
+              onChange={(e) => handleCodexProfileModeChange(e.target.checked)}
+            />
+            <span className="text-xs opacity-60">{t("settings.codexProfileMode")}</span>
+          </label>
+
+          {/* Gemini auth type */}
+          <label
+            className="flex items-center gap-1.5 cursor-pointer select-none px-0.5"
+            title={t("settings.geminiForceAuthHint")}
+          >
+            <input
+              type="checkbox"
+              className="toggle toggle-xs toggle-primary"
+              checked={geminiForceAuth}
+              onChange={(e) => handleGeminiForceAuthChange(e.target.checked)}
+            />
+            <span className="text-xs opacity-60">{t("settings.geminiForceAuth")}</span>
+          </label>
+
+          {/* Update check */}
+          <label
+            className="flex items-center gap-1.5 cursor-pointer select-none px-0.5"
+            title={t("settings.updateCheckHint")}
+          >
+            <input
+              type="checkbox"
+              className="toggle toggle-xs toggle-primary"
+              checked={updateCheck}
+              onChange={(e) => handleUpdateCheckChange(e.target.checked)}
+            />
+            <span className="text-xs opacity-60">{t("settings.updateCheck")}</span>
+          </label>
+
+          {/* WSL */}
+          <WslSettings onChange={onSyncTargetChanged} />
+
+          {/* Proxy */}
+          <ProxySettings />
+          <TlsSettings />
+
           {/* Import / Export */}
           <div className="flex gap-2">
             <button
@@ -321,6 +426,37 @@ export function SettingsPanel({
               {t("settings.import")}
             </button>
           </div>
+          <div className="flex gap-2">
+            <button
+              className="btn btn-ghost btn-xs flex-1 gap-1 opacity-60"
+              onClick={() => setBundleMode("export")}
+            >
+              <Download className="w-3 h-3" />
+              {t("settings.bundleExport")}
+            </button>
+            <button
+              className="btn btn-ghost btn-xs flex-1 gap-1 opacity-60"
+              onClick={() => setBundleMode("import")}
+            >
+              <Upload className="w-3 h-3" />
+              {t("settings.bundleImport")}
+            </button>
+            <button
+              className="btn btn-ghost btn-xs flex-1 gap-1 opacity-60"
+              onClick={() => setShowBackups(true)}
+            >
+              <Archive className="w-3 h-3" />
+              {t("settings.backups")}
+            </button>
+          </div>
+          {showBackups && <BackupsDialog onClose={() => setShowBackups(false)} />}
+          {bundleMode && (
+            <BundleDialog
+              mode={bundleMode}
+              onClose={() => setBundleMode(null)}
+              onImported={onBundleImported}
+            />
+          )}
 
           {/* Purchase CTA */}
           <button
@@ -0,0 +1,93 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useTranslation } from "react-i18next";
+import { FolderOpen, ShieldAlert } from "lucide-react";
+import { toast } from "sonner";
+import type { TlsConfig } from "../types";
+import { errorMessage } from "../errors";
+
+const EMPTY: TlsConfig = { caBundlePath: "", acceptInvalidCerts: false, timeoutSecs: null };
+
+export function TlsSettings() {
+  const { t } = useTranslation();
+  const [config, setConfig] = useState<TlsConfig>(EMPTY);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    invoke<TlsConfig>("get_tls_settings")
+      .then(setConfig)
+      .catch(() => {});
+  }, []);
+
+  const pickBundle = async () => {
+    const path = await open({
+      filters: [{ name: "PEM", extensions: ["pem", "crt", "cer"] }],
+      multiple: false,
+    });
+    if (path) setConfig({ ...config, caBundlePath: path as string });
+  };
+
+  const handleSave = async () => {
+    setSaving(true);
+    try {
+      await invoke("set_tls_settings", { config });
+      toast.success(t("tls.saved"));
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <details className="px-0.5">
+      <summary className="text-xs opacity-60 cursor-pointer select-none" title={t("tls.hint")}>
+        {t("tls.title")}
+      </summary>
+      <div className="space-y-1.5 mt-1.5">
+        <div className="flex gap-1.5">
+          <input
+            type="text"
+            className="input input-bordered input-xs flex-1 min-w-0"
+            value={config.caBundlePath}
+            onChange={(e) => setConfig({ ...config, caBundlePath: e.target.value })}
+            placeholder={t("tls.caBundle")}
+          />
+          <button className="btn btn-ghost btn-xs" onClick={pickBundle} title={t("tls.browse")}>
+            <FolderOpen className="w-3 h-3" />
+          </button>
+        </div>
+        <input
+          type="number"
+          min={1}
+          max={600}
+          className="input input-bordered input-xs w-full"
+          value={config.timeoutSecs ?? ""}
+          onChange={(e) =>
+            setConfig({ ...config, timeoutSecs: e.target.value ? Number(e.target.value) : null })
+          }
+          placeholder={t("tls.timeout")}
+        />
+        <label className="flex items-center gap-2 cursor-pointer">
+          <input
+            type="checkbox"
+            className="checkbox checkbox-xs checkbox-error"
+            checked={config.acceptInvalidCerts}
+            onChange={(e) => setConfig({ ...config, acceptInvalidCerts: e.target.checked })}
+          />
+          <span className="text-xs text-error flex items-center gap-1">
+            <ShieldAlert className="w-3 h-3" />
+            {t("tls.acceptInvalid")}
+          </span>
+        </label>
+        {config.acceptInvalidCerts && (
+          <p className="text-xs text-error opacity-80">{t("tls.acceptInvalidWarning")}</p>
+        )}
+        <button className="btn btn-primary btn-xs w-full" onClick={handleSave} disabled={saving}>
+          {t("proxy.save")}
+        </button>
+      </div>
+    </details>
+  );
+}
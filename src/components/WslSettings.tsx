@@ -0,0 +1,58 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import type { WslTarget } from "../types";
+import { errorMessage } from "../errors";
+
+/** Sync the CLI apps into a WSL distro instead of the Windows profile. Hidden without WSL. */
+export function WslSettings({ onChange }: { onChange?: () => void }) {
+  const { t } = useTranslation();
+  const [distros, setDistros] = useState<string[]>([]);
+  const [target, setTarget] = useState<WslTarget | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    invoke<string[]>("list_wsl_distros")
+      .then(setDistros)
+      .catch(() => {});
+    invoke<WslTarget | null>("get_wsl_target")
+      .then(setTarget)
+      .catch(() => {});
+  }, []);
+
+  const handleChange = async (distro: string) => {
+    setSaving(true);
+    try {
+      const next = await invoke<WslTarget | null>("set_wsl_target", { distro: distro || null });
+      setTarget(next);
+      toast.success(next ? t("wsl.targetSet", { distro: next.distro }) : t("wsl.targetCleared"));
+      onChange?.();
+    } catch (e) {
+      toast.error(errorMessage(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  if (distros.length === 0 && !target) return null;
+  return (
+    <label className="flex items-center gap-1.5 px-0.5" title={t("wsl.hint")}>
+      <span className="text-xs opacity-60 shrink-0">{t("wsl.title")}</span>
+      <select
+        className="select select-bordered select-xs flex-1 min-w-0"
+        value={target?.distro ?? ""}
+        onChange={(e) => handleChange(e.target.value)}
+        disabled={saving}
+      >
+        <option value="">{t("wsl.windows")}</option>
+        {target && !distros.includes(target.distro) && <option value={target.distro}>{target.distro}</option>}
+        {distros.map((d) => (
+          <option key={d} value={d}>
+            {d}
+          </option>
+        ))}
+      </select>
+    </label>
+  );
+}
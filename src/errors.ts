@@ -0,0 +1,17 @@
+import type { CommandError } from "./types";
+
+export function isCommandError(e: unknown): e is CommandError {
+  return (
+    typeof e === "object" &&
+    e !== null &&
+    typeof (e as CommandError).code === "string" &&
+    typeof (e as CommandError).message === "string"
+  );
+}
+
+/** Human-readable text for anything thrown by `invoke`. */
+export function errorMessage(e: unknown): string {
+  if (isCommandError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}
@@ -1,6 +1,14 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useState, useCallback } from "react";
-import type { PlatformInfo, AccountInfo, ApiTokenInfo } from "../types";
+import type {
+  PlatformInfo,
+  AccountInfo,
+  AccountSummary,
+  ApiTokenInfo,
+  UserQuota,
+  UsageLogPage,
+  TokenQuota,
+} from "../types";
 
 const SESSION_KEYS = {
   mode: "hajimi-auth-mode",
@@ -10,10 +18,29 @@ const SESSION_KEYS = {
   username: "hajimi-account-username",
 } as const;
 
+/** Balance of a saved account (default: the active one). */
+export async function getSelfQuota(accountId?: number): Promise<UserQuota> {
+  return invoke("account_get_self_quota", { accountId: accountId ?? null });
+}
+
+/** Fetch and cache the quota of a provider's linked token; null if it has none. */
+export async function refreshProviderQuota(providerId: string): Promise<TokenQuota | null> {
+  return invoke("refresh_provider_quota", { providerId });
+}
+
+/** new-api quota units, abbreviated. */
+export function formatQuota(quota: number): string {
+  if (quota >= 1_000_000) return `${(quota / 1_000_000).toFixed(1)}M`;
+  if (quota >= 1_000) return `${(quota / 1_000).toFixed(1)}K`;
+  return String(quota);
+}
+
 export function useAccount() {
   const [platformInfo, setPlatformInfo] = useState<PlatformInfo | null>(null);
   const [accountInfo, setAccountInfo] = useState<AccountInfo | null>(null);
   const [tokens, setTokens] = useState<ApiTokenInfo[]>([]);
+  const [selfQuota, setSelfQuota] = useState<UserQuota | null>(null);
+  const [accounts, setAccounts] = useState<AccountSummary[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [platformError, setPlatformError] = useState<string | null>(null);
@@ -32,31 +59,85 @@ export function useAccount() {
     }
   }, []);
 
-  const login = useCallback(async (baseUrl: string, username: string, password: string) => {
-    setLoading(true);
+  const listAccounts = useCallback(async () => {
+    try {
+      const list = await invoke<AccountSummary[]>("account_list");
+      setAccounts(list);
+      return list;
+    } catch {
+      return [];
+    }
+  }, []);
+
+  const login = useCallback(
+    async (baseUrl: string, username: string, password: string, totpCode?: string) => {
+      setLoading(true);
+      setError(null);
+      try {
+        const info = await invoke<AccountInfo>("account_login", {
+          baseUrl,
+          username,
+          password,
+          totpCode: totpCode || null,
+        });
+        setAccountInfo(info);
+        rememberSession(baseUrl, info);
+        listAccounts();
+        return info;
+      } catch (e) {
+        const msg = e instanceof Error ? e.message : String(e);
+        setError(msg);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    [listAccounts]
+  );
+
+  const register = useCallback(
+    async (
+      baseUrl: string,
+      username: string,
+      password: string,
+      email: string,
+      verificationCode: string | null
+    ) => {
+      setLoading(true);
+      setError(null);
+      try {
+        // The backend logs in right after a successful registration
+        const info = await invoke<AccountInfo>("account_register", {
+          baseUrl,
+          username,
+          password,
+          email,
+          verificationCode,
+        });
+        setAccountInfo(info);
+        rememberSession(baseUrl, info);
+        listAccounts();
+        return info;
+      } catch (e) {
+        const msg = e instanceof Error ? e.message : String(e);
+        setError(msg);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    [listAccounts]
+  );
+
+  const sendEmailCode = useCallback(async (baseUrl: string, email: string) => {
     setError(null);
     try {
-      const info = await invoke<AccountInfo>("account_login", {
-        baseUrl,
-        username,
-        password,
-      });
-      setAccountInfo(info);
-      // Persist non-sensitive session metadata to localStorage.
-      // SECURITY: Session cookie is NOT stored in localStorage — it stays
-      // only in the Rust backend's in-memory state. Users must re-login
-      // after app restart. This prevents credential theft via XSS or
-      // local file access.
-      localStorage.setItem(SESSION_KEYS.url, baseUrl);
-      localStorage.setItem(SESSION_KEYS.userId, String(info.user_id));
-      localStorage.setItem(SESSION_KEYS.username, info.username);
-      return info;
+      await invoke("account_send_email_code", { baseUrl, email });
+      return true;
     } catch (e) {
       const msg = e instanceof Error ? e.message : String(e);
       setError(msg);
-      return null;
-    } finally {
-      setLoading(false);
+      return false;
     }
   }, []);
 
@@ -80,10 +161,34 @@ export function useAccount() {
     }
   }, []);
 
+  const fetchSelfQuota = useCallback(async () => {
+    try {
+      const quota = await invoke<UserQuota>("account_get_self_quota");
+      setSelfQuota(quota);
+      return quota;
+    } catch {
+      // Balance is informational; token errors already surface session expiry
+      setSelfQuota(null);
+      return null;
+    }
+  }, []);
+
+  const fetchUsageLogs = useCallback(async (page: number, pageSize: number) => {
+    return invoke<UsageLogPage>("account_get_usage_logs", { page, pageSize });
+  }, []);
+
+  /** Make `id` the active account; callers re-check the session and refetch. */
+  const switchAccount = useCallback(async (id: number) => {
+    await invoke("account_switch_active", { id });
+    setTokens([]);
+    setSelfQuota(null);
+    setError(null);
+    await listAccounts();
+  }, [listAccounts]);
+
   const checkSession = useCallback(async () => {
-    // Session cookie is kept only in Rust's in-memory state (not persisted
-    // to localStorage for security). On app restart the cookie is gone,
-    // so this will fail and the user re-logs in.
+    // The backend restores the saved session (encrypted in its SQLite DB)
+    // at startup; this validates it against the server.
     try {
       const info = await invoke<AccountInfo>("account_check_session");
       setAccountInfo(info);
@@ -101,28 +206,49 @@ export function useAccount() {
     }
     setAccountInfo(null);
     setTokens([]);
+    setSelfQuota(null);
     setPlatformInfo(null);
     setError(null);
     setPlatformError(null);
     clearSession();
-  }, []);
+    // Another saved account may have become active
+    return listAccounts();
+  }, [listAccounts]);
 
   return {
     platformInfo,
     accountInfo,
     tokens,
+    selfQuota,
+    accounts,
     loading,
     error,
     platformError,
     checkPlatform,
     login,
+    register,
+    sendEmailCode,
     fetchTokens,
+    fetchSelfQuota,
+    fetchUsageLogs,
     checkSession,
+    listAccounts,
+    switchAccount,
     logout,
     setError,
   };
 }
 
+// Persist non-sensitive session metadata to localStorage.
+// SECURITY: Session cookie is NOT stored in localStorage — the Rust
+// backend keeps it, encrypted, in its own database. This prevents
+// credential theft via XSS.
+function rememberSession(baseUrl: string, info: AccountInfo) {
+  localStorage.setItem(SESSION_KEYS.url, baseUrl);
+  localStorage.setItem(SESSION_KEYS.userId, String(info.user_id));
+  localStorage.setItem(SESSION_KEYS.username, info.username);
+}
+
 function clearSession() {
   localStorage.removeItem(SESSION_KEYS.session);
   localStorage.removeItem(SESSION_KEYS.userId);
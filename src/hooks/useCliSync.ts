@@ -1,8 +1,18 @@
 import { invoke } from "@tauri-apps/api/core";
-import { useState, useCallback, useRef } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useState, useCallback, useEffect, useRef } from "react";
 import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
-import type { CliStatusResult, SyncAllResult } from "../types";
+import type {
+  CliStatusResult,
+  CliStatusSnapshot,
+  CliUpdate,
+  InstallProgress,
+  StatusChange,
+  SyncAllResult,
+  SyncResult,
+} from "../types";
+import { errorMessage } from "../errors";
 
 export interface SyncLogEntry {
   id: number;
@@ -43,24 +53,70 @@ export function useCliSync() {
   const [statuses, setStatuses] = useState<CliStatusResult[]>([]);
   const statusesRef = useRef<CliStatusResult[]>(statuses);
   statusesRef.current = statuses;
+  const stateTokenRef = useRef<string | null>(null);
   const [loading, setLoading] = useState(false);
   const [syncing, setSyncing] = useState<Record<string, boolean>>({});
   const [restoring, setRestoring] = useState<Record<string, boolean>>({});
   const [installing, setInstalling] = useState<Record<string, boolean>>({});
+  const [installProgress, setInstallProgress] = useState<Record<string, InstallProgress>>({});
+
+  // The backend watches config files and pushes the new status of any app whose files changed
+  useEffect(() => {
+    const unlisten = listen<CliStatusResult>("status://changed", (event) => {
+      const next = event.payload;
+      setStatuses((prev) => prev.map((s) => (s.app === next.app ? next : s)));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Installs report each phase (and download percentage) while they run
+  useEffect(() => {
+    const unlisten = listen<InstallProgress>("install://progress", (event) => {
+      const next = event.payload;
+      setInstallProgress((prev) => ({ ...prev, [next.tool]: next }));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // With no URL entered yet the backend still reports installs and configured URLs
+  const loadStatuses = useCallback(async (url: string) => {
+    const snapshot = await invoke<CliStatusSnapshot>("get_all_cli_status", {
+      url: url.trim() || null,
+    });
+    stateTokenRef.current = snapshot.state_token;
+    setStatuses(snapshot.results);
+  }, []);
+
+  // Cheap re-check: the backend only sends results when they differ from the last ones we saw
+  const refreshIfChanged = useCallback(async (url: string) => {
+    try {
+      const change = await invoke<StatusChange>("get_status_if_changed", {
+        url: url.trim() || null,
+        token: stateTokenRef.current,
+      });
+      if (change.status === "changed") {
+        stateTokenRef.current = change.state_token;
+        setStatuses(change.results);
+      }
+    } catch (e) {
+      console.error("Failed to refresh CLI status:", e);
+    }
+  }, []);
 
   const detectAll = useCallback(async (url: string) => {
     setLoading(true);
     try {
-      const result = await invoke<CliStatusResult[]>("get_all_cli_status", {
-        url,
-      });
-      setStatuses(result);
+      await loadStatuses(url);
     } catch (e) {
       console.error("Failed to detect CLIs:", e);
     } finally {
       setLoading(false);
     }
-  }, []);
+  }, [loadStatuses]);
 
   const syncOne = useCallback(
     async (
@@ -68,31 +124,49 @@ export function useCliSync() {
       url: string,
       apiKey: string,
       model: string | null,
-      name: string
+      name: string,
+      smallModel?: string | null,
+      opencodeAdapter?: string | null
     ) => {
       setSyncing((prev) => ({ ...prev, [app]: true }));
       try {
-        await invoke("sync_cli", { app, url, apiKey, model });
-        toast.success(t("toast.syncSuccess", { name }));
-        appendLog({ action: "sync", app: name, success: true });
-        const allStatus = await invoke<CliStatusResult[]>(
-          "get_all_cli_status",
-          { url }
-        );
-        setStatuses(allStatus);
+        const result = await invoke<SyncResult>("sync_cli", {
+          app,
+          url,
+          apiKey,
+          model,
+          smallModel: smallModel || null,
+          opencodeAdapter: opencodeAdapter || null,
+        });
+        const warning = result.warning;
+        if (result.manual) {
+          toast.info(t("toast.manualSetup", { steps: warning }), { duration: 10000 });
+        } else if (warning) {
+          toast.warning(t("toast.syncWarning", { name, warning }), { duration: 8000 });
+        } else {
+          toast.success(t("toast.syncSuccess", { name }));
+        }
+        appendLog({ action: "sync", app: name, success: true, detail: warning ?? undefined });
+        await loadStatuses(url);
       } catch (e: unknown) {
-        const error = e instanceof Error ? e.message : String(e);
+        const error = errorMessage(e);
         toast.error(t("toast.syncFailed", { name, error }), { duration: 5000 });
         appendLog({ action: "sync", app: name, success: false, detail: error });
       } finally {
         setSyncing((prev) => ({ ...prev, [app]: false }));
       }
     },
-    [t]
+    [t, loadStatuses]
   );
 
   const syncAll = useCallback(
-    async (url: string, apiKey: string, model: string | null, perCliModels?: Record<string, string>) => {
+    async (
+      url: string,
+      apiKey: string,
+      model: string | null,
+      perCliModels?: Record<string, string>,
+      opencodeAdapter?: string | null
+    ) => {
       setSyncing((prev) => {
         const next = { ...prev };
         statusesRef.current
@@ -106,33 +180,41 @@ export function useCliSync() {
           apiKey,
           model,
           perCliModels: perCliModels || null,
+          opencodeAdapter: opencodeAdapter || null,
         });
-        const successCount = result.results.filter((r) => r.success).length;
-        const totalCount = result.results.length;
+        const manual = result.results.filter((r) => r.manual);
+        for (const m of manual) {
+          toast.info(t("toast.manualSetup", { steps: m.warning }), { duration: 10000 });
+        }
+        const synced = result.results.filter((r) => !r.skipped && !r.manual);
+        const successCount = synced.filter((r) => r.success).length;
+        const totalCount = synced.length;
+        const skippedCount = result.results.length - totalCount - manual.length;
+        if (skippedCount > 0) {
+          toast.info(t("toast.pinnedSkipped", { count: skippedCount }));
+        }
         if (successCount === totalCount && totalCount > 0) {
           toast.success(
             t("toast.syncAllSuccess", { success: successCount, total: totalCount })
           );
           appendLog({ action: "sync_all", app: `${successCount}/${totalCount}`, success: true });
         } else if (totalCount === 0) {
-          toast.error(t("toast.noInstalledCli"), { duration: 5000 });
+          if (skippedCount === 0 && manual.length === 0) {
+            toast.error(t("toast.noInstalledCli"), { duration: 5000 });
+          }
         } else {
           toast.error(t("toast.syncAllFailed"), { duration: 5000 });
           appendLog({ action: "sync_all", app: `${successCount}/${totalCount}`, success: false });
         }
-        const allStatus = await invoke<CliStatusResult[]>(
-          "get_all_cli_status",
-          { url }
-        );
-        setStatuses(allStatus);
+        await loadStatuses(url);
       } catch (e: unknown) {
-        const error = e instanceof Error ? e.message : String(e);
+        const error = errorMessage(e);
         toast.error(t("toast.syncFailed", { name: "Sync All", error }), { duration: 5000 });
       } finally {
         setSyncing({});
       }
     },
-    [t]
+    [t, loadStatuses]
   );
 
   const restoreOne = useCallback(
@@ -142,20 +224,16 @@ export function useCliSync() {
         await invoke("restore_cli", { app });
         toast.success(t("toast.restoreSuccess", { name }));
         appendLog({ action: "restore", app: name, success: true });
-        const allStatus = await invoke<CliStatusResult[]>(
-          "get_all_cli_status",
-          { url }
-        );
-        setStatuses(allStatus);
+        await loadStatuses(url);
       } catch (e: unknown) {
-        const error = e instanceof Error ? e.message : String(e);
+        const error = errorMessage(e);
         toast.error(t("toast.restoreFailed", { name, error }), { duration: 5000 });
         appendLog({ action: "restore", app: name, success: false, detail: error });
       } finally {
         setRestoring((prev) => ({ ...prev, [app]: false }));
       }
     },
-    [t]
+    [t, loadStatuses]
   );
 
   const installOne = useCallback(
@@ -170,12 +248,7 @@ export function useCliSync() {
         }
       };
       try {
-        const result = await invoke<{
-          tool: string;
-          status: string;
-          progress: number;
-          message: string;
-        }>("install_cli_tool", { tool: app });
+        const result = await invoke<InstallProgress>("install_cli_tool", { tool: app });
         if (result.status === "failed") {
           handleFail(result.message);
           appendLog({ action: "install", app: name, success: false, detail: result.message });
@@ -184,22 +257,82 @@ export function useCliSync() {
           appendLog({ action: "install", app: name, success: true });
           // Wait for PATH to refresh before re-detecting
           await new Promise((r) => setTimeout(r, 2000));
-          const allStatus = await invoke<CliStatusResult[]>(
-            "get_all_cli_status",
-            { url }
-          );
-          setStatuses(allStatus);
+          await loadStatuses(url);
         }
       } catch (e: unknown) {
-        const error = e instanceof Error ? e.message : String(e);
+        const error = errorMessage(e);
         handleFail(error);
       } finally {
         setInstalling((prev) => ({ ...prev, [app]: false }));
       }
     },
-    [t]
+    [t, loadStatuses]
+  );
+
+  // Update or uninstall share the install job slot, so `installing` covers them too
+  const changeOne = useCallback(
+    async (action: "update" | "uninstall", app: string, url: string, name: string) => {
+      setInstalling((prev) => ({ ...prev, [app]: true }));
+      const failedKey = action === "update" ? "install.updateFailed" : "install.uninstallFailed";
+      try {
+        const result = await invoke<InstallProgress>(`${action}_cli_tool`, { tool: app });
+        if (result.status === "failed") {
+          toast.error(t(failedKey, { error: result.message }));
+          appendLog({ action: "install", app: name, success: false, detail: result.message });
+          return;
+        }
+        toast.success(
+          t(action === "update" ? "install.updateSuccess" : "install.uninstallSuccess", { name })
+        );
+        appendLog({ action: "install", app: name, success: true, detail: action });
+        await loadStatuses(url);
+      } catch (e: unknown) {
+        toast.error(t(failedKey, { error: errorMessage(e) }));
+      } finally {
+        setInstalling((prev) => ({ ...prev, [app]: false }));
+      }
+    },
+    [t, loadStatuses]
   );
 
+  const updateOne = useCallback(
+    (app: string, url: string, name: string) => changeOne("update", app, url, name),
+    [changeOne]
+  );
+
+  const uninstallOne = useCallback(
+    (app: string, url: string, name: string) => changeOne("uninstall", app, url, name),
+    [changeOne]
+  );
+
+  const checkUpdates = useCallback(async (): Promise<CliUpdate[]> => {
+    try {
+      return await invoke<CliUpdate[]>("check_cli_updates");
+    } catch (e: unknown) {
+      console.error("Failed to check for CLI updates:", e);
+      return [];
+    }
+  }, []);
+
+  // The running install_cli_tool call then resolves with status "failed"
+  const cancelInstall = useCallback(async (app: string): Promise<boolean> => {
+    try {
+      return await invoke<boolean>("cancel_install", { tool: app });
+    } catch (e: unknown) {
+      toast.error(errorMessage(e));
+      return false;
+    }
+  }, []);
+
+  const setPinned = useCallback(async (app: string, pinned: boolean) => {
+    try {
+      await invoke(pinned ? "pin_app" : "unpin_app", { app });
+      setStatuses((prev) => prev.map((s) => (s.app === app ? { ...s, pinned } : s)));
+    } catch (e: unknown) {
+      toast.error(errorMessage(e));
+    }
+  }, []);
+
   const getConfigContent = useCallback(
     async (app: string, fileName?: string): Promise<string> => {
       try {
@@ -208,7 +341,7 @@ export function useCliSync() {
           fileName: fileName || null,
         });
       } catch (e: unknown) {
-        const error = e instanceof Error ? e.message : String(e);
+        const error = errorMessage(e);
         return `Error: ${error}`;
       }
     },
@@ -221,11 +354,18 @@ export function useCliSync() {
     syncing,
     restoring,
     installing,
+    installProgress,
     detectAll,
+    refreshIfChanged,
     syncOne,
     syncAll,
     restoreOne,
     installOne,
+    updateOne,
+    uninstallOne,
+    checkUpdates,
+    cancelInstall,
+    setPinned,
     getConfigContent,
   };
 }
@@ -1,13 +1,28 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useState, useCallback, useRef } from "react";
+import type { ModelInfo, ModelList } from "../types";
+import { errorMessage } from "../errors";
+
+/** Uncached listing with each model classified, for per-app pre-filtering. */
+export async function fetchModelsDetailed(url: string, apiKey: string, flavor?: string): Promise<ModelInfo[]> {
+  return invoke("fetch_models_detailed", { url, apiKey, flavor });
+}
 
 export function useModels() {
   const [models, setModels] = useState<string[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const lastFetched = useRef<{ url: string; key: string } | null>(null);
+  // Age of the served list in seconds (0 when freshly fetched)
+  const [ageSecs, setAgeSecs] = useState<number | null>(null);
+  // The proxy listed more pages than the backend follows
+  const [truncated, setTruncated] = useState(false);
+  const lastFetched = useRef<{
+    url: string;
+    key: string;
+    flavor?: string;
+  } | null>(null);
 
-  const fetchModels = useCallback(async (url: string, apiKey: string) => {
+  const fetchModels = useCallback(async (url: string, apiKey: string, flavor?: string, force = false) => {
     if (!url.trim() || !apiKey.trim()) {
       setModels([]);
       setError(null);
@@ -17,9 +32,11 @@ export function useModels() {
 
     // Skip if same url+key already fetched
     if (
+      !force &&
       lastFetched.current &&
       lastFetched.current.url === url &&
-      lastFetched.current.key === apiKey
+      lastFetched.current.key === apiKey &&
+      lastFetched.current.flavor === flavor
     ) {
       return;
     }
@@ -27,16 +44,22 @@ export function useModels() {
     setLoading(true);
     setError(null);
     try {
-      const result = await invoke<string[]>("fetch_models", {
+      const result = await invoke<ModelList>("fetch_models", {
         url,
         apiKey,
+        flavor,
+        force,
       });
-      setModels(result);
-      lastFetched.current = { url, key: apiKey };
+      setModels(result.models);
+      setAgeSecs(result.age_secs);
+      setTruncated(result.truncated);
+      lastFetched.current = { url, key: apiKey, flavor };
     } catch (e: unknown) {
-      const msg = e instanceof Error ? e.message : String(e);
+      const msg = errorMessage(e);
       setError(msg);
       setModels([]);
+      setAgeSecs(null);
+      setTruncated(false);
       lastFetched.current = null;
     } finally {
       setLoading(false);
@@ -45,9 +68,11 @@ export function useModels() {
 
   const clearModels = useCallback(() => {
     setModels([]);
+    setAgeSecs(null);
+    setTruncated(false);
     setError(null);
     lastFetched.current = null;
   }, []);
 
-  return { models, loading, error, fetchModels, clearModels };
+  return { models, loading, error, ageSecs, truncated, fetchModels, clearModels };
 }
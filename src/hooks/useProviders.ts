@@ -1,22 +1,42 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ProviderRecord, SwitchResult } from "../types";
+import type { DetectedProvider, ProviderPage, ProviderRecord, SaveProviderResult, SwitchResult } from "../types";
 
-export async function listProviders(): Promise<ProviderRecord[]> {
-  return invoke("list_providers");
+export async function listProviders(includeArchived = false): Promise<ProviderRecord[]> {
+  return invoke("list_providers", { includeArchived });
+}
+
+/** `query` matches name, URL or notes; `tag` a `#tag` in the notes. */
+export async function listProvidersPage(
+  offset: number,
+  limit: number,
+  query?: string,
+  tag?: string
+): Promise<ProviderPage> {
+  return invoke("list_providers_page", { offset, limit, query: query ?? null, tag: tag ?? null });
 }
 
 export async function getCurrentProvider(): Promise<ProviderRecord | null> {
   return invoke("get_current_provider");
 }
 
-export async function saveProvider(provider: ProviderRecord): Promise<void> {
+export async function saveProvider(provider: ProviderRecord): Promise<SaveProviderResult> {
   return invoke("save_provider", { provider });
 }
 
+/** Archive: the provider leaves the list but can be restored. */
 export async function deleteProvider(id: string): Promise<void> {
   return invoke("delete_provider", { id });
 }
 
+export async function restoreArchivedProvider(id: string): Promise<void> {
+  return invoke("restore_archived_provider", { id });
+}
+
+/** Delete for good, key included. */
+export async function purgeProvider(id: string): Promise<void> {
+  return invoke("purge_provider", { id });
+}
+
 export async function switchProvider(id: string): Promise<SwitchResult> {
   return invoke("switch_provider", { id });
 }
@@ -24,3 +44,36 @@ export async function switchProvider(id: string): Promise<SwitchResult> {
 export async function reorderProviders(ids: string[]): Promise<void> {
   return invoke("reorder_providers", { ids });
 }
+
+export async function detectExistingProvider(app: string): Promise<DetectedProvider | null> {
+  return invoke("detect_existing_provider", { app });
+}
+
+/** Shell snippet (bash, fish, powershell, dotenv, direnv) exporting the provider's URL and key. */
+export async function generateEnvExports(format: string, providerId?: string): Promise<string> {
+  return invoke("generate_env_exports", { format, providerId: providerId ?? null });
+}
+
+/** Write `.envrc` into a folder the user picked; returns the written path. */
+export async function writeEnvrc(projectDir: string): Promise<string> {
+  return invoke("write_envrc", { projectDir, format: "direnv", userChosen: true });
+}
+
+export async function importProviderFromApp(app: string, name: string): Promise<ProviderRecord> {
+  return invoke("import_provider_from_app", { app, name });
+}
+
+/** Save an account token as a provider; `switchTo` also switches to it. */
+export async function createProviderFromToken(
+  tokenId: number,
+  name?: string,
+  switchTo = false,
+  accountId?: number
+): Promise<ProviderRecord> {
+  return invoke("create_provider_from_token", {
+    tokenId,
+    name: name ?? null,
+    switch: switchTo,
+    accountId: accountId ?? null,
+  });
+}
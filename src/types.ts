@@ -1,3 +1,21 @@
+/** Sync state of one config file; `synced` is null for files without proxy settings. */
+export interface FileStatus {
+  name: string;
+  exists: boolean;
+  synced: boolean | null;
+  detected_url: string | null;
+  note: string | null;
+}
+
+/** Claude Code's project-level settings in `<project>/.claude`. */
+export interface ProjectSyncStatus {
+  project_dir: string;
+  is_synced: boolean;
+  has_backup: boolean;
+  current_base_url: string | null;
+  file_status: FileStatus[];
+}
+
 export interface CliStatusResult {
   app: string;
   installed: boolean;
@@ -7,29 +25,228 @@ export interface CliStatusResult {
   current_base_url: string | null;
   files: string[];
   synced_count: number | null;
+  /** A file we wrote was edited by hand since the last sync. */
+  externally_modified: boolean;
+  /** Left out of sync-all and provider switches. */
+  pinned: boolean;
+  /** Per-file detail behind is_synced / current_base_url. */
+  file_status: FileStatus[];
+  /** Saved provider the config points at, when it can be told apart. */
+  synced_provider_id: string | null;
+  synced_provider_name: string | null;
+}
+
+export interface CliStatusSnapshot {
+  results: CliStatusResult[];
+  /** Changes whenever the results do; pass it back to get_status_if_changed. */
+  state_token: string;
+}
+
+export type StatusChange =
+  | { status: "unchanged" }
+  | ({ status: "changed" } & CliStatusSnapshot);
+
+/** Structured error returned by backend commands. */
+export interface CommandError {
+  code: string;
+  message: string;
+  details: Record<string, unknown> | null;
+  recoverable: boolean;
 }
 
 export interface SyncResult {
   app: string;
   success: boolean;
   error: string | null;
+  warning?: string | null;
+  /** Not synced because the app is pinned. */
+  skipped?: boolean;
+  /** Nothing written: the app is set up by hand, and `warning` says how. */
+  manual?: boolean;
 }
 
 export interface SyncAllResult {
   results: SyncResult[];
 }
 
+export interface ModelList {
+  models: string[];
+  cached: boolean;
+  age_secs: number;
+  /** The proxy had more pages than were fetched */
+  truncated: boolean;
+  /** Models listed before `filter` was applied */
+  total: number;
+}
+
+export type ModelKind = "chat" | "reasoning" | "image" | "embedding" | "other";
+
+export interface ModelInfo {
+  id: string;
+  kind: ModelKind;
+  owned_by: string | null;
+  context_length: number | null;
+  declared_type: string | null;
+}
+
+export type ProbeErrorKind =
+  | "timeout"
+  | "rate_limited"
+  | "auth"
+  | "connect"
+  | "http"
+  | "no_model";
+
+export interface ConnectionReport {
+  models_ok: boolean;
+  chat_ok: boolean;
+  latency_ms: number | null;
+  model_used: string | null;
+  error: { kind: ProbeErrorKind; message: string } | null;
+}
+
+export type WireProtocol =
+  | "anthropic_messages"
+  | "openai_responses"
+  | "openai_chat"
+  | "gemini_generate_content";
+
+export interface ProtocolCheck {
+  protocol: WireProtocol;
+  endpoint: string;
+  model: string;
+  ok: boolean;
+  status: number | null;
+  latency_ms: number | null;
+  body: string;
+  error: { kind: ProbeErrorKind; message: string } | null;
+}
+
+export interface DiagnosisFinding {
+  severity: "error" | "warning" | "info";
+  code: string;
+  message: string;
+  fixHint: string;
+}
+
+export interface Diagnosis {
+  app: string;
+  provider: string | null;
+  /** In check order; passing checks are "info". */
+  findings: DiagnosisFinding[];
+}
+
 export interface SwitchResult {
   success: boolean;
   errors: SyncResult[];
+  /** Apps that synced with a notice, e.g. manual edits were overwritten. */
+  warnings: SyncResult[];
+  /** Installed apps left alone because they are pinned. */
+  skipped?: SyncResult[];
+  /** Installed apps that have to be pointed at the provider by hand. */
+  manual?: SyncResult[];
+}
+
+/** What a sync does when a managed file was edited by hand since the last sync. */
+export type DriftPolicy = "note" | "abort";
+
+export interface SaveProviderResult {
+  saved: boolean;
+  /** Set when auto-sync-on-save re-synced the current provider. */
+  sync: SwitchResult | null;
+}
+
+/** Proxy settings read back from an app's existing config. */
+export interface DetectedProvider {
+  app: string;
+  url: string;
+  api_key: string;
+  model: string | null;
 }
 
+/** Provider carried by a hajimi://add-provider link, pending confirmation. */
+export interface ProviderLink {
+  name: string;
+  url: string;
+  api_key: string;
+  model: string | null;
+}
+
+/** A received hajimi:// link waiting to be confirmed, or why it was rejected. */
+export type PendingLink =
+  | { kind: "provider"; value: ProviderLink }
+  | { kind: "error"; value: string };
+
 export interface AppConfig {
   url: string;
   apiKey: string;
   defaultModel: string;
 }
 
+/** Last background health check of the current provider (`health://changed`). */
+export interface HealthSnapshot {
+  provider_id: string;
+  status: "healthy" | "slow" | "down";
+  ok: boolean;
+  latency_ms: number | null;
+  error: string | null;
+  checked_at: number;
+  consecutive_failures: number;
+}
+
+/** Scheduled provider rotation (`set_rotation_config`) */
+export interface RotationConfig {
+  enabled: boolean;
+  provider_ids: string[];
+  interval_hours: number;
+}
+
+/** Payload of `rotation://switched` */
+export interface RotationEvent {
+  from_id: string | null;
+  to_id: string;
+  to_name: string;
+  switched_at: number;
+  failed_apps: string[];
+}
+
+export interface RotationStatus {
+  config: RotationConfig;
+  last_switch_at: number | null;
+  next_due_at: number | null; // null while rotation is off
+  next_provider_id: string | null;
+  last_rotation: RotationEvent | null;
+}
+
+/** `diff_config_with_backup`: from the current file to what Restore would write */
+export interface ConfigDiff {
+  backup_exists: boolean;
+  backup_path: string | null;
+  additions: string[];
+  removals: string[];
+  unified_diff: string;
+  truncated: boolean;
+  note: string | null;
+}
+
+/** Written into every backup bundle (`export_bundle`). */
+export interface BundleManifest {
+  format: string;
+  version: number;
+  created_at: string;
+  app_version: string;
+  contains_keys: boolean;
+  encrypted: boolean;
+  apps: { app: string; version: string | null; files: string[] }[];
+}
+
+export interface BundleImportResult {
+  providers_imported: number;
+  providers_missing_keys: number;
+  configs_restored: string[];
+  errors: string[];
+}
+
 export interface ProviderRecord {
   id: string;
   name: string;
@@ -41,6 +258,116 @@ export interface ProviderRecord {
   sort_index: number | null;
   notes: string | null;
   created_at: number;
+  opencode_adapter?: string; // "anthropic" | "openai-compatible" | "openai"; "" = default
+  account_id?: number | null; // saved account the key came from
+  model_aliases?: string; // JSON string: { model: { app or protocol: id } }
+  account_base_url?: string | null; // platform of the linked token
+  token_id?: number | null; // linked new-api token, for quota
+  quota?: TokenQuota | null; // cached by refresh_provider_quota; ignored on save
+  archived?: boolean; // deleted from the list, restorable
+  advanced?: string; // JSON string: { headers?, small_fast_model?, api_timeout_ms? }; Claude only
+}
+
+/** WSL distro the CLI apps are synced into instead of the Windows profile. */
+export interface WslTarget {
+  distro: string;
+  /** e.g. /home/me */
+  linuxHome: string;
+}
+
+/** A window of the provider list from `list_providers_page`. */
+export interface ProviderPage {
+  /** Rows matching the search, across all pages. */
+  total: number;
+  items: ProviderRecord[];
+}
+
+/** Quota of a provider's linked token (`refresh_provider_quota`) */
+export interface TokenQuota {
+  used_quota: number;
+  remain_quota: number;
+  unlimited_quota: boolean;
+  fetched_at: number; // Unix seconds
+}
+
+/** Payload of the backend's `install://progress` event */
+export interface AppUpdate {
+  current: string;
+  /** null when the update check is turned off. */
+  latest: string | null;
+  update_available: boolean;
+  release_notes_url: string | null;
+}
+
+/** One problem found by validate_configs. */
+export interface ConfigValidationError {
+  /** JSON pointer (TOML keys written the same way); "" for the whole file. */
+  path: string;
+  line: number | null;
+  message: string;
+}
+
+export interface ConfigValidation {
+  app: string;
+  file: string;
+  path: string;
+  ok: boolean;
+  errors: ConfigValidationError[];
+}
+
+/** A corrupted provider database that was moved aside and replaced at startup. */
+export interface DbRecovery {
+  reason: string;
+  corrupt_path: string;
+  salvaged_rows: number;
+  damaged_tables: string[];
+}
+
+export interface StartupDiagnostics {
+  db_path: string;
+  db_recovery: DbRecovery | null;
+  /** Set when no database could be opened; changes are lost on exit. */
+  db_error: string | null;
+}
+
+/** Proxy for the app's own requests. An empty url falls back to HTTP(S)_PROXY. */
+export interface ProxyConfig {
+  url: string;
+  username: string;
+  password: string;
+  /** Comma-separated hosts that skip the proxy. */
+  noProxy: string;
+}
+
+/** Extra trust and timeout for the app's own requests, e.g. a gateway behind an internal CA. */
+export interface TlsConfig {
+  /** PEM bundle trusted in addition to the built-in roots. */
+  caBundlePath: string;
+  /** Skips certificate verification entirely. */
+  acceptInvalidCerts: boolean;
+  /** Replaces every request's own timeout when set. */
+  timeoutSecs: number | null;
+}
+
+export interface ProxyTestResult {
+  probeUrl: string;
+  status: number;
+  latencyMs: number;
+}
+
+export interface InstallProgress {
+  tool: string;
+  status: "checking" | "downloading" | "installing" | "completed" | "failed" | "skipped";
+  progress: number; // 0-100; meaningful while downloading
+  message: string;
+}
+
+/** A CLI tool with a newer npm release than the installed one */
+export interface CliUpdate {
+  tool: string;
+  package: string;
+  installed: string;
+  latest: string;
 }
 
 export type InstallType = "npm" | "vscode" | "desktop" | "manual" | "manual-config";
@@ -72,15 +399,24 @@ export interface PlatformInfo {
   system_name: string;
   version: string;
   register_enabled: boolean;
+  email_verification: boolean;
 }
 
 export interface AccountInfo {
+  account_id: number; // local id of the saved account
   user_id: number;
   username: string;
   display_name: string;
   session_cookie: string | null;
 }
 
+export interface AccountSummary {
+  id: number;
+  base_url: string;
+  username: string;
+  active: boolean;
+}
+
 export interface ApiTokenInfo {
   id: number;
   name: string;
@@ -94,8 +430,48 @@ export interface ApiTokenInfo {
   model_limits: string[];
 }
 
+export interface UserQuota {
+  quota: number; // remaining balance, new-api quota units
+  used_quota: number;
+  request_count: number;
+  group: string;
+}
+
+export interface UsageLogEntry {
+  model: string;
+  token_name: string;
+  prompt_tokens: number;
+  completion_tokens: number;
+  quota: number;
+  created_at: number; // unix timestamp
+}
+
+export interface UsageLogPage {
+  items: UsageLogEntry[];
+  total: number;
+}
+
 export type AuthMode = "manual" | "account";
 
+/** A `.hajimi.bak` file next to a synced config (`list_all_backups`) */
+export interface BackupFile {
+  app: string;
+  file: string;
+  path: string;
+  timestamp: number; // Unix seconds
+  size_bytes: number;
+  rotated: boolean; // false for the restore point
+}
+
+export interface PruneReport {
+  removed: number;
+  bytes_freed: number;
+  errors: string[];
+}
+
+/** perCliModels key for opencode's `small_model` (the `opencode` key is its `model`) */
+export const OPENCODE_SMALL_MODEL_KEY = "opencode:small";
+
 export const CLI_LIST: CliInfo[] = [
   { id: "claude", name: "Claude Code", icon: "terminal", color: "border-purple-400", installType: "npm", category: "coding", descKey: "toolDesc.claude", postSyncHintKey: "toolHint.claude" },
   { id: "claude-vscode", name: "Claude Code (VS Code)", icon: "file-code", color: "border-purple-300", installType: "vscode", category: "coding", descKey: "toolDesc.claudeVscode", communityUrl: "https://marketplace.visualstudio.com/items?itemName=anthropic.claude-code" },
@@ -110,8 +486,10 @@ export const CLI_LIST: CliInfo[] = [
   { id: "chatbox", name: "Chatbox", icon: "message-square", color: "border-sky-400", installType: "desktop", category: "chat", downloadUrl: "https://chatboxai.app", descKey: "toolDesc.chatbox", launchName: "Chatbox" },
   { id: "cherry-studio", name: "Cherry Studio", icon: "cherry", color: "border-pink-400", installType: "desktop", category: "chat", downloadUrl: "https://cherry-ai.com", descKey: "toolDesc.cherryStudio", launchName: "Cherry Studio", deepLinkTemplate: "cherrystudio://providers/api-keys?v=1&data={config}" },
   { id: "jan", name: "Jan", icon: "cpu", color: "border-indigo-400", installType: "desktop", category: "chat", downloadUrl: "https://jan.ai/download", descKey: "toolDesc.jan", launchName: "Jan" },
-  { id: "lobechat", name: "LobeChat", icon: "brain", color: "border-violet-400", installType: "manual-config", category: "chat", downloadUrl: "https://lobehub.com/zh", descKey: "toolDesc.lobechat", postSyncHintKey: "toolHint.lobechat", launchName: "LobeChat" },
-  { id: "boltai", name: "BoltAI", icon: "zap", color: "border-slate-400", installType: "manual-config", category: "chat", downloadUrl: "https://boltai.com", descKey: "toolDesc.boltai", postSyncHintKey: "toolHint.boltai", launchName: "BoltAI" },
+  { id: "lobechat", name: "LobeChat", icon: "brain", color: "border-violet-400", installType: "desktop", category: "chat", downloadUrl: "https://lobehub.com/zh", descKey: "toolDesc.lobechat", postSyncHintKey: "toolHint.lobechat", launchName: "LobeChat" },
+  { id: "boltai", name: "BoltAI", icon: "zap", color: "border-slate-400", installType: "desktop", category: "chat", downloadUrl: "https://boltai.com", descKey: "toolDesc.boltai", postSyncHintKey: "toolHint.boltai", launchName: "BoltAI" },
+  { id: "warp", name: "Warp", icon: "terminal", color: "border-sky-400", installType: "manual-config", category: "coding", downloadUrl: "https://www.warp.dev/download", descKey: "toolDesc.warp", postSyncHintKey: "toolHint.warp", launchName: "Warp" },
+  { id: "amazon-q", name: "Amazon Q", icon: "bot", color: "border-amber-400", installType: "manual-config", category: "coding", downloadUrl: "https://aws.amazon.com/q/developer/", descKey: "toolDesc.amazonQ", postSyncHintKey: "toolHint.amazonQ", launchName: "Amazon Q" },
   { id: "openclaw", name: "OpenClaw", icon: "waves", color: "border-rose-400", installType: "npm", category: "agent", downloadUrl: "https://docs.openclaw.ai", descKey: "toolDesc.openclaw", postSyncHintKey: "toolHint.openclaw" },
   { id: "sillytavern", name: "SillyTavern", icon: "beer", color: "border-yellow-400", installType: "manual", category: "rp", downloadUrl: "https://docs.sillytavern.app/installation/", descKey: "toolDesc.sillytavern", postSyncHintKey: "toolHint.sillytavern" },
 ];